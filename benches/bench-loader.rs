@@ -13,6 +13,7 @@ struct Context {
     file: gio::File,
     pool: Arc<Pool>,
     main_loop: glib::MainLoop,
+    read_buffer_size: usize,
 }
 
 impl Context {
@@ -29,6 +30,7 @@ impl Context {
             file: gio::File::for_path(path),
             main_loop,
             pool,
+            read_buffer_size: u16::MAX as usize,
         };
 
         context
@@ -41,6 +43,7 @@ impl Context {
             self.main_loop.context(),
         ));
         loader.apply_transformations(false);
+        loader.read_buffer_size(self.read_buffer_size);
         loader
     }
 }
@@ -80,11 +83,47 @@ fn loader(c: &mut Criterion) {
     }
 }
 
+fn read_buffer_size(c: &mut Criterion) {
+    // Only large files show a difference
+    for image_path in utils::test_images()
+        .into_iter()
+        .filter(|x| x.starts_with("cache"))
+    {
+        let mut group = c.benchmark_group(format!(
+            "read-buffer-size-{}",
+            utils::bench_name(&image_path)
+        ));
+
+        for size in [4 * 1024, 64 * 1024, 1024 * 1024] {
+            group.bench_function(format!("{} KiB", size / 1024), |b| {
+                b.iter_custom(|iters| {
+                    let mut context = Context::new(&image_path);
+                    context.read_buffer_size = size;
+
+                    for _ in 0..iters {
+                        do_glycin_load(black_box(&context))
+                    }
+
+                    let start = std::time::Instant::now();
+                    for _ in 0..iters {
+                        do_glycin_load(black_box(&context))
+                    }
+                    let elapsed = start.elapsed();
+
+                    drop(context);
+
+                    elapsed
+                });
+            });
+        }
+    }
+}
+
 criterion_main!(benches);
 criterion_group!(
     name = benches;
     config = Criterion::default().sample_size(100).measurement_time(Duration::from_secs(2)).with_plots();
-    targets = loader
+    targets = loader, read_buffer_size
 );
 
 fn do_glycin_load(context: &Context) {
@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+use zvariant::Type;
+
+#[repr(i32)]
+#[derive(Deserialize, Serialize, Type, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "gobject", derive(glib::Enum))]
+#[cfg_attr(feature = "gobject", enum_type(name = "GlyDisposalMethod"))]
+#[zvariant(signature = "s")]
+/// How the area of an animation frame is treated before rendering the next
+/// frame
+///
+/// The area is the sub-rectangle of the canvas that the frame covers.
+pub enum DisposalMethod {
+    #[default]
+    /// Leave the canvas as it is
+    None,
+    /// Clear the area of the frame to fully transparent
+    Background,
+    /// Restore the area of the frame to its content before rendering the
+    /// frame
+    Previous,
+}
+
+#[repr(i32)]
+#[derive(Deserialize, Serialize, Type, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "gobject", derive(glib::Enum))]
+#[cfg_attr(feature = "gobject", enum_type(name = "GlyBlendOp"))]
+#[zvariant(signature = "s")]
+/// How an animation frame is combined with the canvas
+pub enum BlendOp {
+    #[default]
+    /// Replace the area of the frame, including alpha
+    Source,
+    /// Composite the frame over the canvas using its alpha
+    Over,
+}
@@ -0,0 +1,94 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Read-only binary data
+///
+/// Used for large data that are part of an [`Operation`](crate::Operation),
+/// like the table of [`Operation::ApplyLut3D`](crate::Operation::ApplyLut3D).
+/// The data can't be changed after creation. Clones share the data.
+///
+/// The data are serialized as a byte string, not as a sequence of numbers.
+/// Operations are passed to editors via shared memory, such that the data
+/// are not part of the D-Bus message.
+#[derive(Clone, PartialEq, Eq)]
+pub struct BinaryData {
+    data: Arc<[u8]>,
+}
+
+impl BinaryData {
+    pub fn from_data(data: impl Into<Arc<[u8]>>) -> Self {
+        Self { data: data.into() }
+    }
+
+    /// Stores `values` as native endian bytes
+    pub fn from_f32(values: &[f32]) -> Self {
+        Self::from_data(
+            values
+                .iter()
+                .flat_map(|x| x.to_ne_bytes())
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl std::fmt::Debug for BinaryData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BinaryData")
+            .field("len", &self.as_slice().len())
+            .finish()
+    }
+}
+
+impl Serialize for BinaryData {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self.as_slice())
+    }
+}
+
+impl<'de> Deserialize<'de> for BinaryData {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl serde::de::Visitor<'_> for Visitor {
+            type Value = BinaryData;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a byte string")
+            }
+
+            fn visit_bytes<E: serde::de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+                Ok(BinaryData::from_data(v))
+            }
+
+            fn visit_byte_buf<E: serde::de::Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+                Ok(BinaryData::from_data(v))
+            }
+        }
+
+        deserializer.deserialize_byte_buf(Visitor)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn message_pack() {
+        let data = BinaryData::from_f32(&[0.5, 1.]);
+
+        let mut buf = Vec::new();
+        data.serialize(&mut rmp_serde::Serializer::new(&mut buf).with_human_readable())
+            .unwrap();
+        // Stored as a byte string with a two byte header
+        assert_eq!(buf.len(), 8 + 2);
+
+        let deserialized: BinaryData = rmp_serde::decode::from_slice(&buf).unwrap();
+        assert_eq!(deserialized, data);
+    }
+}
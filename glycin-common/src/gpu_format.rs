@@ -0,0 +1,254 @@
+use crate::MemoryFormat;
+
+/// Recommendation for uploading a [`MemoryFormat`] to the GPU
+///
+/// Format names are given as the names of the Vulkan and OpenGL enum values.
+/// If [`conversion`](Self::conversion) is set, the data have to be converted
+/// to that memory format before uploading, and all other fields refer to the
+/// converted data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct GpuFormatHint {
+    /// Memory format to convert to before uploading
+    ///
+    /// Three channel formats are converted since Vulkan implementations rarely
+    /// support sampling from them.
+    pub conversion: Option<MemoryFormat>,
+    /// Vulkan format, like `VK_FORMAT_R8G8B8A8_UNORM`
+    pub vulkan_format: &'static str,
+    /// OpenGL sized internal format, like `GL_RGBA8`
+    pub gl_internal_format: &'static str,
+    /// OpenGL pixel data format, like `GL_RGBA`
+    pub gl_format: &'static str,
+    /// OpenGL pixel data type, like `GL_UNSIGNED_BYTE`
+    pub gl_type: &'static str,
+    /// Texture channels to sample the RGBA values from
+    ///
+    /// Uses the notation of texture swizzles, like `rrr1` for grayscale
+    /// images. Channels that are stored in a different order than the format
+    /// supports are reordered via the swizzle instead of a conversion.
+    pub swizzle: &'static str,
+    /// Whether the color channels are premultiplied with the alpha channel
+    pub premultiplied: bool,
+}
+
+impl GpuFormatHint {
+    /// Whether the data have to be converted before uploading
+    pub fn needs_conversion(&self) -> bool {
+        self.conversion.is_some()
+    }
+}
+
+const RGBA8: GpuFormatHint = GpuFormatHint {
+    conversion: None,
+    vulkan_format: "VK_FORMAT_R8G8B8A8_UNORM",
+    gl_internal_format: "GL_RGBA8",
+    gl_format: "GL_RGBA",
+    gl_type: "GL_UNSIGNED_BYTE",
+    swizzle: "rgba",
+    premultiplied: false,
+};
+
+const RGBA16: GpuFormatHint = GpuFormatHint {
+    conversion: None,
+    vulkan_format: "VK_FORMAT_R16G16B16A16_UNORM",
+    gl_internal_format: "GL_RGBA16",
+    gl_format: "GL_RGBA",
+    gl_type: "GL_UNSIGNED_SHORT",
+    swizzle: "rgba",
+    premultiplied: false,
+};
+
+const RGBA16F: GpuFormatHint = GpuFormatHint {
+    conversion: None,
+    vulkan_format: "VK_FORMAT_R16G16B16A16_SFLOAT",
+    gl_internal_format: "GL_RGBA16F",
+    gl_format: "GL_RGBA",
+    gl_type: "GL_HALF_FLOAT",
+    swizzle: "rgba",
+    premultiplied: false,
+};
+
+const RGBA32F: GpuFormatHint = GpuFormatHint {
+    conversion: None,
+    vulkan_format: "VK_FORMAT_R32G32B32A32_SFLOAT",
+    gl_internal_format: "GL_RGBA32F",
+    gl_format: "GL_RGBA",
+    gl_type: "GL_FLOAT",
+    swizzle: "rgba",
+    premultiplied: false,
+};
+
+const RG8: GpuFormatHint = GpuFormatHint {
+    conversion: None,
+    vulkan_format: "VK_FORMAT_R8G8_UNORM",
+    gl_internal_format: "GL_RG8",
+    gl_format: "GL_RG",
+    gl_type: "GL_UNSIGNED_BYTE",
+    swizzle: "rrrg",
+    premultiplied: false,
+};
+
+const R8: GpuFormatHint = GpuFormatHint {
+    conversion: None,
+    vulkan_format: "VK_FORMAT_R8_UNORM",
+    gl_internal_format: "GL_R8",
+    gl_format: "GL_RED",
+    gl_type: "GL_UNSIGNED_BYTE",
+    swizzle: "rrr1",
+    premultiplied: false,
+};
+
+const RG16: GpuFormatHint = GpuFormatHint {
+    conversion: None,
+    vulkan_format: "VK_FORMAT_R16G16_UNORM",
+    gl_internal_format: "GL_RG16",
+    gl_format: "GL_RG",
+    gl_type: "GL_UNSIGNED_SHORT",
+    swizzle: "rrrg",
+    premultiplied: false,
+};
+
+const R16: GpuFormatHint = GpuFormatHint {
+    conversion: None,
+    vulkan_format: "VK_FORMAT_R16_UNORM",
+    gl_internal_format: "GL_R16",
+    gl_format: "GL_RED",
+    gl_type: "GL_UNSIGNED_SHORT",
+    swizzle: "rrr1",
+    premultiplied: false,
+};
+
+impl MemoryFormat {
+    /// Recommended format for uploading data in this memory format to the GPU
+    pub const fn gpu_format_hint(self) -> GpuFormatHint {
+        let hint = match self {
+            MemoryFormat::B8g8r8a8Premultiplied | MemoryFormat::B8g8r8a8 => GpuFormatHint {
+                vulkan_format: "VK_FORMAT_B8G8R8A8_UNORM",
+                gl_format: "GL_BGRA",
+                ..RGBA8
+            },
+            MemoryFormat::A8r8g8b8Premultiplied | MemoryFormat::A8r8g8b8 => GpuFormatHint {
+                swizzle: "gbar",
+                ..RGBA8
+            },
+            MemoryFormat::R8g8b8a8Premultiplied | MemoryFormat::R8g8b8a8 => RGBA8,
+            MemoryFormat::A8b8g8r8 => GpuFormatHint {
+                swizzle: "abgr",
+                ..RGBA8
+            },
+            MemoryFormat::R8g8b8 | MemoryFormat::B8g8r8 => GpuFormatHint {
+                conversion: Some(MemoryFormat::R8g8b8a8),
+                ..RGBA8
+            },
+            MemoryFormat::R16g16b16 => GpuFormatHint {
+                conversion: Some(MemoryFormat::R16g16b16a16),
+                ..RGBA16
+            },
+            MemoryFormat::R16g16b16a16Premultiplied | MemoryFormat::R16g16b16a16 => RGBA16,
+            MemoryFormat::R16g16b16Float => GpuFormatHint {
+                conversion: Some(MemoryFormat::R16g16b16a16Float),
+                ..RGBA16F
+            },
+            MemoryFormat::R16g16b16a16Float => RGBA16F,
+            MemoryFormat::R32g32b32Float => GpuFormatHint {
+                conversion: Some(MemoryFormat::R32g32b32a32Float),
+                ..RGBA32F
+            },
+            MemoryFormat::R32g32b32a32FloatPremultiplied | MemoryFormat::R32g32b32a32Float => {
+                RGBA32F
+            }
+            MemoryFormat::G8a8Premultiplied | MemoryFormat::G8a8 => RG8,
+            MemoryFormat::G8 => R8,
+            MemoryFormat::G16a16Premultiplied | MemoryFormat::G16a16 => RG16,
+            MemoryFormat::G16 => R16,
+            MemoryFormat::C8m8y8k8 => GpuFormatHint {
+                conversion: Some(MemoryFormat::R8g8b8a8),
+                ..RGBA8
+            },
+            MemoryFormat::C16m16y16k16 => GpuFormatHint {
+                conversion: Some(MemoryFormat::R16g16b16a16),
+                ..RGBA16
+            },
+        };
+
+        GpuFormatHint {
+            premultiplied: self.is_premultiplied(),
+            ..hint
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryFormatInfo;
+
+    #[test]
+    fn consistent_with_memory_format() {
+        for memory_format in MemoryFormat::ALL {
+            let hint = memory_format.gpu_format_hint();
+            let uploaded = hint.conversion.unwrap_or(*memory_format);
+
+            assert!(
+                !uploaded.gpu_format_hint().needs_conversion(),
+                "{memory_format:?}"
+            );
+            assert_eq!(
+                hint.premultiplied,
+                memory_format.is_premultiplied(),
+                "{memory_format:?}"
+            );
+            assert_eq!(hint.swizzle.len(), 4, "{memory_format:?}");
+
+            let n_channels = match hint.gl_format {
+                "GL_RED" => 1,
+                "GL_RG" => 2,
+                "GL_RGBA" | "GL_BGRA" => 4,
+                other => panic!("Unexpected format {other}"),
+            };
+            assert_eq!(n_channels, uploaded.n_channels(), "{memory_format:?}");
+
+            let channel_size = match hint.gl_type {
+                "GL_UNSIGNED_BYTE" => 1,
+                "GL_UNSIGNED_SHORT" | "GL_HALF_FLOAT" => 2,
+                "GL_FLOAT" => 4,
+                other => panic!("Unexpected type {other}"),
+            };
+            assert_eq!(
+                channel_size,
+                uploaded.channel_type().size(),
+                "{memory_format:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn channel_order() {
+        assert_eq!(
+            MemoryFormat::B8g8r8a8.gpu_format_hint().vulkan_format,
+            "VK_FORMAT_B8G8R8A8_UNORM"
+        );
+        assert_eq!(MemoryFormat::A8r8g8b8.gpu_format_hint().swizzle, "gbar");
+        assert_eq!(MemoryFormat::A8b8g8r8.gpu_format_hint().swizzle, "abgr");
+        assert_eq!(MemoryFormat::G8a8.gpu_format_hint().swizzle, "rrrg");
+        assert_eq!(MemoryFormat::G16.gpu_format_hint().swizzle, "rrr1");
+    }
+
+    #[test]
+    fn three_channels_converted() {
+        assert_eq!(
+            MemoryFormat::B8g8r8.gpu_format_hint().conversion,
+            Some(MemoryFormat::R8g8b8a8)
+        );
+        assert_eq!(
+            MemoryFormat::R32g32b32Float.gpu_format_hint().vulkan_format,
+            "VK_FORMAT_R32G32B32A32_SFLOAT"
+        );
+        assert!(
+            !MemoryFormat::R8g8b8a8Premultiplied
+                .gpu_format_hint()
+                .needs_conversion()
+        );
+    }
+}
@@ -1,11 +1,17 @@
+mod animation;
+mod binary_data;
 mod color_profile_preference;
 mod error;
+mod gpu_format;
 mod memory_format;
 mod memory_format_selection;
 mod operations;
 
+pub use animation::*;
+pub use binary_data::BinaryData;
 pub use color_profile_preference::*;
 pub use error::Error;
+pub use gpu_format::*;
 pub use memory_format::*;
 pub use memory_format_selection::*;
 pub use operations::*;
@@ -40,6 +40,10 @@ gufo_common::maybe_convertible_enum!(
         G16a16Premultiplied = 20,
         G16a16 = 21,
         G16 = 22,
+        /// CMYK without alpha, where `0` is no ink and the maximum value
+        /// full ink
+        C8m8y8k8 = 23,
+        C16m16y16k16 = 24,
     }
 );
 
@@ -69,6 +73,8 @@ impl MemoryFormatInfo for MemoryFormat {
             MemoryFormat::G16a16Premultiplied => MemoryFormatBytes::B4,
             MemoryFormat::G16a16 => MemoryFormatBytes::B4,
             MemoryFormat::G16 => MemoryFormatBytes::B2,
+            MemoryFormat::C8m8y8k8 => MemoryFormatBytes::B4,
+            MemoryFormat::C16m16y16k16 => MemoryFormatBytes::B8,
         }
     }
 
@@ -85,7 +91,9 @@ impl MemoryFormatInfo for MemoryFormat {
             | MemoryFormat::R16g16b16a16
             | MemoryFormat::R16g16b16a16Float
             | MemoryFormat::R32g32b32a32FloatPremultiplied
-            | MemoryFormat::R32g32b32a32Float => 4,
+            | MemoryFormat::R32g32b32a32Float
+            | MemoryFormat::C8m8y8k8
+            | MemoryFormat::C16m16y16k16 => 4,
             MemoryFormat::R8g8b8
             | MemoryFormat::B8g8r8
             | MemoryFormat::R16g16b16
@@ -125,6 +133,8 @@ impl MemoryFormat {
         Self::G16a16Premultiplied,
         Self::G16a16,
         Self::G16,
+        Self::C8m8y8k8,
+        Self::C16m16y16k16,
     ];
 
     pub const fn channel_type(self) -> ChannelType {
@@ -140,14 +150,16 @@ impl MemoryFormat {
             | MemoryFormat::B8g8r8
             | MemoryFormat::G8a8Premultiplied
             | MemoryFormat::G8a8
-            | MemoryFormat::G8 => ChannelType::U8,
+            | MemoryFormat::G8
+            | MemoryFormat::C8m8y8k8 => ChannelType::U8,
 
             MemoryFormat::R16g16b16
             | MemoryFormat::R16g16b16a16Premultiplied
             | MemoryFormat::R16g16b16a16
             | MemoryFormat::G16a16Premultiplied
             | MemoryFormat::G16a16
-            | MemoryFormat::G16 => ChannelType::U16,
+            | MemoryFormat::G16
+            | MemoryFormat::C16m16y16k16 => ChannelType::U16,
 
             MemoryFormat::R16g16b16Float | MemoryFormat::R16g16b16a16Float => ChannelType::F16,
 
@@ -157,6 +169,17 @@ impl MemoryFormat {
         }
     }
 
+    /// Range of channel values that this format can store
+    ///
+    /// Integer formats are limited to values from `0.0` to `1.0`. Float
+    /// formats can carry HDR values and don't limit the range.
+    pub const fn value_range(self) -> (f32, f32) {
+        match self.channel_type() {
+            ChannelType::U8 | ChannelType::U16 => (0., 1.),
+            ChannelType::F16 | ChannelType::F32 => (f32::NEG_INFINITY, f32::INFINITY),
+        }
+    }
+
     pub const fn has_alpha(self) -> bool {
         match self {
             MemoryFormat::B8g8r8a8Premultiplied
@@ -182,7 +205,25 @@ impl MemoryFormat {
             | MemoryFormat::R16g16b16Float
             | MemoryFormat::R32g32b32Float
             | MemoryFormat::G8
-            | MemoryFormat::G16 => false,
+            | MemoryFormat::G16
+            | MemoryFormat::C8m8y8k8
+            | MemoryFormat::C16m16y16k16 => false,
+        }
+    }
+
+    /// Whether the channels are CMYK instead of RGB or grayscale
+    pub const fn is_cmyk(self) -> bool {
+        matches!(self, MemoryFormat::C8m8y8k8 | MemoryFormat::C16m16y16k16)
+    }
+
+    /// RGB format with the same channel type for CMYK formats
+    ///
+    /// Returns the format itself for all other formats.
+    pub const fn without_cmyk(self) -> Self {
+        match self {
+            MemoryFormat::C8m8y8k8 => MemoryFormat::R8g8b8,
+            MemoryFormat::C16m16y16k16 => MemoryFormat::R16g16b16,
+            format => format,
         }
     }
 
@@ -211,13 +252,17 @@ impl MemoryFormat {
             | MemoryFormat::G8a8
             | MemoryFormat::G8
             | MemoryFormat::G16a16
-            | MemoryFormat::G16 => false,
+            | MemoryFormat::G16
+            | MemoryFormat::C8m8y8k8
+            | MemoryFormat::C16m16y16k16 => false,
         }
     }
 
     /// Defines from which channels to get the RGBA values
     ///
-    /// The return value is in the order `[R, G, B, A]`.
+    /// The return value is in the order `[R, G, B, A]`. For CMYK formats,
+    /// the RGB values are not taken from a single channel. Their definition
+    /// only lists the first three channels for completeness.
     pub const fn source_definition(self) -> [Source; 4] {
         match self {
             MemoryFormat::B8g8r8a8Premultiplied | MemoryFormat::B8g8r8a8 => {
@@ -253,6 +298,10 @@ impl MemoryFormat {
             MemoryFormat::G8 | MemoryFormat::G16 => {
                 [Source::C0, Source::C0, Source::C0, Source::Opaque]
             }
+
+            MemoryFormat::C8m8y8k8 | MemoryFormat::C16m16y16k16 => {
+                [Source::C0, Source::C1, Source::C2, Source::Opaque]
+            }
         }
     }
 
@@ -282,15 +331,232 @@ impl MemoryFormat {
             | MemoryFormat::G16a16Premultiplied
             | MemoryFormat::G16a16 => &[Target::RgbAvg, Target::A],
             MemoryFormat::G8 | MemoryFormat::G16 => &[Target::RgbAvg],
+            MemoryFormat::C8m8y8k8 | MemoryFormat::C16m16y16k16 => {
+                &[Target::C, Target::M, Target::Y, Target::K]
+            }
         }
     }
 
+    /// Converts a pixel from `src_format` to `target_format`
+    ///
+    /// Values are clamped to the [`value_range`](Self::value_range) of the
+    /// target format and NaN values are replaced by `0.0`.
     #[inline]
     pub fn transform(src_format: Self, src: &[u8], target_format: Self, target: &mut [u8]) {
-        let channels_f32 = Self::to_f32(src_format, src);
+        Self::transform_with_range(
+            src_format,
+            src,
+            target_format,
+            target,
+            target_format.value_range(),
+        );
+    }
+
+    /// Swaps the byte order of all 16-bit channels in `buf`
+    ///
+    /// Converts big endian samples to little endian and vice versa, for
+    /// example for loaders that get big endian data from the decoder. Does
+    /// nothing for formats with 8-bit or 32-bit channels. Calling it twice
+    /// restores the original data.
+    ///
+    /// Since this only works on pairs of bytes, `buf` can contain complete
+    /// frames including the padding of the rows.
+    pub fn swap_endianness_in_place(format: Self, buf: &mut [u8]) {
+        match format.channel_type() {
+            ChannelType::U16 | ChannelType::F16 => {
+                for sample in buf.chunks_exact_mut(2) {
+                    sample.swap(0, 1);
+                }
+            }
+            ChannelType::U8 | ChannelType::F32 => {}
+        }
+    }
+
+    /// Converts `n_pixels` consecutive pixels from `src` to `target`
+    ///
+    /// Gives the same results as calling [`MemoryFormat::transform`] for
+    /// every pixel. The channel layouts and types are only looked up once,
+    /// which makes this much faster for converting whole rows.
+    ///
+    /// Panics if `src` or `target` are too short for `n_pixels`.
+    pub fn transform_row(
+        src_format: Self,
+        src: &[u8],
+        target_format: Self,
+        target: &mut [u8],
+        n_pixels: usize,
+    ) {
+        match src_format.channel_type() {
+            ChannelType::U8 => {
+                Self::transform_row_from::<u8>(src_format, src, target_format, target, n_pixels)
+            }
+            ChannelType::U16 => {
+                Self::transform_row_from::<u16>(src_format, src, target_format, target, n_pixels)
+            }
+            ChannelType::F16 => Self::transform_row_from::<half::f16>(
+                src_format,
+                src,
+                target_format,
+                target,
+                n_pixels,
+            ),
+            ChannelType::F32 => {
+                Self::transform_row_from::<f32>(src_format, src, target_format, target, n_pixels)
+            }
+        }
+    }
+
+    fn transform_row_from<S: ChannelValue>(
+        src_format: Self,
+        src: &[u8],
+        target_format: Self,
+        target: &mut [u8],
+        n_pixels: usize,
+    ) {
+        match target_format.channel_type() {
+            ChannelType::U8 => Self::transform_row_internal::<S, u8>(
+                src_format,
+                src,
+                target_format,
+                target,
+                n_pixels,
+            ),
+            ChannelType::U16 => Self::transform_row_internal::<S, u16>(
+                src_format,
+                src,
+                target_format,
+                target,
+                n_pixels,
+            ),
+            ChannelType::F16 => Self::transform_row_internal::<S, half::f16>(
+                src_format,
+                src,
+                target_format,
+                target,
+                n_pixels,
+            ),
+            ChannelType::F32 => Self::transform_row_internal::<S, f32>(
+                src_format,
+                src,
+                target_format,
+                target,
+                n_pixels,
+            ),
+        }
+    }
+
+    fn transform_row_internal<S: ChannelValue, T: ChannelValue>(
+        src_format: Self,
+        src: &[u8],
+        target_format: Self,
+        target: &mut [u8],
+        n_pixels: usize,
+    ) {
+        let src_n_bytes = src_format.n_bytes().usize();
+        let target_n_bytes = target_format.n_bytes().usize();
+
+        let src = &src[..n_pixels * src_n_bytes];
+        let target = &mut target[..n_pixels * target_n_bytes];
+
+        let source_definition = src_format.source_definition();
+        let target_definition = target_format.target_definition();
+        let src_cmyk = src_format.is_cmyk();
+        let unpremultiply = src_format.is_premultiplied();
+        let premultiply = target_format.is_premultiplied();
+        let (min, max) = target_format.value_range();
+        let src_channel_size = size_of::<S>();
+
+        for (src, target) in src
+            .chunks_exact(src_n_bytes)
+            .zip(target.chunks_exact_mut(target_n_bytes))
+        {
+            let mut channels_f32 = if src_cmyk {
+                let [r, g, b] = cmyk_to_rgb(
+                    [0, 1, 2, 3]
+                        .map(|n| S::from_ne_slice(&src[n * src_channel_size..]).to_f32_normed()),
+                );
+                [r, g, b, 1.]
+            } else {
+                source_definition.map(|source| {
+                    let n = match source {
+                        Source::C0 => 0,
+                        Source::C1 => 1,
+                        Source::C2 => 2,
+                        Source::C3 => 3,
+                        Source::Opaque => return 1.,
+                    };
+                    S::from_ne_slice(&src[n * src_channel_size..]).to_f32_normed()
+                })
+            };
+
+            if unpremultiply && channels_f32[3] > 0. {
+                channels_f32[0] /= channels_f32[3];
+                channels_f32[1] /= channels_f32[3];
+                channels_f32[2] /= channels_f32[3];
+            }
+
+            let channels_f32 =
+                channels_f32.map(|x| if x.is_nan() { 0. } else { x.clamp(min, max) });
+
+            Self::write_channels::<T>(channels_f32, target_definition, premultiply, target);
+        }
+    }
+
+    /// Converts a pixel and clamps the values to `range`
+    ///
+    /// This allows to clamp HDR values when converting to a float format. NaN
+    /// values are replaced by `0.0`.
+    #[inline]
+    pub fn transform_with_range(
+        src_format: Self,
+        src: &[u8],
+        target_format: Self,
+        target: &mut [u8],
+        (min, max): (f32, f32),
+    ) {
+        let channels_f32 =
+            Self::to_f32(src_format, src).map(|x| if x.is_nan() { 0. } else { x.clamp(min, max) });
+        Self::from_f32(channels_f32, target_format, target);
+    }
+
+    /// Converts a pixel from an extended format to `target_format`
+    ///
+    /// Like [`MemoryFormat::transform`], but also accepts YCbCr sources. See
+    /// [`MemoryFormat::to_f32_extended`] for the conversion to RGB.
+    #[inline]
+    pub fn transform_extended(
+        src_format: ExtendedMemoryFormat,
+        src: &[u8],
+        target_format: Self,
+        target: &mut [u8],
+    ) {
+        let (min, max) = target_format.value_range();
+        let channels_f32 = Self::to_f32_extended(src_format, src)
+            .map(|x| if x.is_nan() { 0. } else { x.clamp(min, max) });
         Self::from_f32(channels_f32, target_format, target);
     }
 
+    /// Converts a pixel from an extended format to RGBA values
+    ///
+    /// YCbCr is converted via full range BT.601 coefficients, as used by JPEG.
+    /// For [`ExtendedMemoryFormat::Y8Cb8Cr8K8`], the converted values are the
+    /// inverted CMY channels, which are combined with the K channel.
+    #[inline]
+    pub fn to_f32_extended(src_format: ExtendedMemoryFormat, src: &[u8]) -> [f32; 4] {
+        match src_format {
+            ExtendedMemoryFormat::Basic(basic) => Self::to_f32(basic, src),
+            ExtendedMemoryFormat::Y8Cb8Cr8 => {
+                let [r, g, b] = ycbcr_to_rgb(src[0], src[1], src[2]);
+                [r, g, b, 1.]
+            }
+            ExtendedMemoryFormat::Y8Cb8Cr8K8 => {
+                let [r, g, b] = ycbcr_to_rgb(src[0], src[1], src[2]);
+                let k = u8::to_f32_normed(src[3]);
+                [r * (1. - k), g * (1. - k), b * (1. - k), 1.]
+            }
+        }
+    }
+
     #[inline]
     pub fn to_f32(src_format: Self, mut src: &[u8]) -> [f32; 4] {
         match src_format.channel_type() {
@@ -316,6 +582,11 @@ impl MemoryFormat {
 
     #[inline]
     fn to_f32_internal<T: ChannelValue>(source_channels: &[T], source_format: Self) -> [f32; 4] {
+        if source_format.is_cmyk() {
+            let [r, g, b] = cmyk_to_rgb([0, 1, 2, 3].map(|n| source_channels[n].to_f32_normed()));
+            return [r, g, b, 1.];
+        }
+
         let mut channels_f32 = [0.0_f32; 4];
 
         let source_definition = source_format.source_definition();
@@ -339,6 +610,24 @@ impl MemoryFormat {
         channels_f32
     }
 
+    /// Converts a pixel and changes its colors on the way
+    ///
+    /// `f` receives the straight, not premultiplied, RGB values of the pixel
+    /// as returned by [`MemoryFormat::to_f32`]. The returned values are
+    /// written to `target`. Alpha is kept and the values are not clamped.
+    #[inline]
+    pub fn transform_rgb(
+        src_format: Self,
+        src: &[u8],
+        target_format: Self,
+        target: &mut [u8],
+        f: impl FnOnce([f32; 3]) -> [f32; 3],
+    ) {
+        let [r, g, b, a] = Self::to_f32(src_format, src);
+        let [r, g, b] = f([r, g, b]);
+        Self::from_f32([r, g, b, a], target_format, target);
+    }
+
     #[inline]
     pub(crate) fn from_f32(channels_f32: [f32; 4], target_format: Self, target: &mut [u8]) {
         match target_format.channel_type() {
@@ -357,15 +646,31 @@ impl MemoryFormat {
         target_format: Self,
         target: &mut [u8],
     ) {
-        let target_channel_size = target_format.channel_type().size() as usize;
+        Self::write_channels::<T>(
+            channels_f32,
+            target_format.target_definition(),
+            target_format.is_premultiplied(),
+            target,
+        );
+    }
 
-        let premultiply = if target_format.is_premultiplied() {
-            channels_f32[3]
+    #[inline]
+    fn write_channels<T: ChannelValue>(
+        channels_f32: [f32; 4],
+        target_definition: &[Target],
+        premultiplied: bool,
+        target: &mut [u8],
+    ) {
+        let target_channel_size = size_of::<T>();
+
+        let premultiply = if premultiplied { channels_f32[3] } else { 1. };
+        let cmyk = if target_definition.contains(&Target::K) {
+            rgb_to_cmyk([channels_f32[0], channels_f32[1], channels_f32[2]])
         } else {
-            1.
+            [0.; 4]
         };
 
-        for (n, def) in target_format.target_definition().iter().enumerate() {
+        for (n, def) in target_definition.iter().enumerate() {
             let new_channel = match def {
                 Target::R => T::from_f32_normed(channels_f32[0] * premultiply),
                 Target::G => T::from_f32_normed(channels_f32[1] * premultiply),
@@ -374,6 +679,10 @@ impl MemoryFormat {
                 Target::RgbAvg => {
                     T::from_f32_normed((channels_f32[0] + channels_f32[1] + channels_f32[2]) / 3.)
                 }
+                Target::C => T::from_f32_normed(cmyk[0]),
+                Target::M => T::from_f32_normed(cmyk[1]),
+                Target::Y => T::from_f32_normed(cmyk[2]),
+                Target::K => T::from_f32_normed(cmyk[3]),
             };
 
             let bytes = new_channel.as_bytes_wrapper();
@@ -409,6 +718,8 @@ impl MemoryFormat {
             "G16a16Premultiplied" => Self::G16a16Premultiplied,
             "G16a16" => Self::G16a16,
             "G16" => Self::G16,
+            "C8m8y8k8" => Self::C8m8y8k8,
+            "C16m16y16k16" => Self::C16m16y16k16,
             _ => return None,
         })
     }
@@ -440,6 +751,66 @@ impl MemoryFormat {
             Self::G16 => "G16",
         }
     }
+
+    /// Order of the channels in memory
+    ///
+    /// Returns strings like `"BGRA"` or `"GA"`, where `G` stands for the gray
+    /// channel in grayscale formats. CMYK formats return `"CMYK"`.
+    pub const fn channel_layout(self) -> &'static str {
+        match self {
+            Self::B8g8r8a8Premultiplied | Self::B8g8r8a8 => "BGRA",
+            Self::A8r8g8b8Premultiplied | Self::A8r8g8b8 => "ARGB",
+            Self::R8g8b8a8Premultiplied
+            | Self::R8g8b8a8
+            | Self::R16g16b16a16Premultiplied
+            | Self::R16g16b16a16
+            | Self::R16g16b16a16Float
+            | Self::R32g32b32a32FloatPremultiplied
+            | Self::R32g32b32a32Float => "RGBA",
+            Self::A8b8g8r8 => "ABGR",
+            Self::R8g8b8 | Self::R16g16b16 | Self::R16g16b16Float | Self::R32g32b32Float => "RGB",
+            Self::B8g8r8 => "BGR",
+            Self::G8a8Premultiplied | Self::G8a8 | Self::G16a16Premultiplied | Self::G16a16 => "GA",
+            Self::G8 | Self::G16 => "G",
+            Self::C8m8y8k8 | Self::C16m16y16k16 => "CMYK",
+        }
+    }
+
+    /// Human-readable description of the format
+    ///
+    /// Contains the bits per channel, whether the channels are floats, the
+    /// [channel layout](Self::channel_layout), and whether the alpha channel
+    /// is premultiplied. For example, `"8-bit BGRA premultiplied"` or
+    /// `"16-bit float RGB"`.
+    pub const fn description(self) -> &'static str {
+        match self {
+            Self::B8g8r8a8Premultiplied => "8-bit BGRA premultiplied",
+            Self::A8r8g8b8Premultiplied => "8-bit ARGB premultiplied",
+            Self::R8g8b8a8Premultiplied => "8-bit RGBA premultiplied",
+            Self::B8g8r8a8 => "8-bit BGRA",
+            Self::A8r8g8b8 => "8-bit ARGB",
+            Self::R8g8b8a8 => "8-bit RGBA",
+            Self::A8b8g8r8 => "8-bit ABGR",
+            Self::R8g8b8 => "8-bit RGB",
+            Self::B8g8r8 => "8-bit BGR",
+            Self::R16g16b16 => "16-bit RGB",
+            Self::R16g16b16a16Premultiplied => "16-bit RGBA premultiplied",
+            Self::R16g16b16a16 => "16-bit RGBA",
+            Self::R16g16b16Float => "16-bit float RGB",
+            Self::R16g16b16a16Float => "16-bit float RGBA",
+            Self::R32g32b32Float => "32-bit float RGB",
+            Self::R32g32b32a32FloatPremultiplied => "32-bit float RGBA premultiplied",
+            Self::R32g32b32a32Float => "32-bit float RGBA",
+            Self::G8a8Premultiplied => "8-bit grayscale GA premultiplied",
+            Self::G8a8 => "8-bit grayscale GA",
+            Self::G8 => "8-bit grayscale G",
+            Self::G16a16Premultiplied => "16-bit grayscale GA premultiplied",
+            Self::G16a16 => "16-bit grayscale GA",
+            Self::G16 => "16-bit grayscale G",
+            Self::C8m8y8k8 => "8-bit CMYK",
+            Self::C16m16y16k16 => "16-bit CMYK",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -467,10 +838,54 @@ impl MemoryFormatInfo for ExtendedMemoryFormat {
     }
 }
 
+/// Full range BT.601 conversion to normalized RGB
+#[inline]
+fn ycbcr_to_rgb(y: u8, cb: u8, cr: u8) -> [f32; 3] {
+    let y = u8::to_f32_normed(y);
+    let cb = u8::to_f32_normed(cb) - 128. / 255.;
+    let cr = u8::to_f32_normed(cr) - 128. / 255.;
+
+    [
+        y + 1.402 * cr,
+        y - 0.344_136 * cb - 0.714_136 * cr,
+        y + 1.772 * cb,
+    ]
+}
+
+/// Naive conversion without color management
+#[inline]
+fn cmyk_to_rgb([c, m, y, k]: [f32; 4]) -> [f32; 3] {
+    [
+        (1. - c) * (1. - k),
+        (1. - m) * (1. - k),
+        (1. - y) * (1. - k),
+    ]
+}
+
+/// Naive conversion without color management, using as much black as possible
+#[inline]
+fn rgb_to_cmyk([r, g, b]: [f32; 3]) -> [f32; 4] {
+    let k = 1. - r.max(g).max(b);
+
+    if k >= 1. {
+        return [0., 0., 0., 1.];
+    }
+
+    [
+        (1. - r - k) / (1. - k),
+        (1. - g - k) / (1. - k),
+        (1. - b - k) / (1. - k),
+        k,
+    ]
+}
+
 trait ChannelValue: Default + Copy {
     fn from_f32_normed(value: f32) -> Self;
     fn to_f32_normed(self) -> f32;
     fn as_bytes_wrapper(&self) -> &[u8];
+    /// Reads the value from the start of `bytes`, which doesn't have to be
+    /// aligned
+    fn from_ne_slice(bytes: &[u8]) -> Self;
 }
 
 impl ChannelValue for u8 {
@@ -485,6 +900,10 @@ impl ChannelValue for u8 {
     fn as_bytes_wrapper(&self) -> &[u8] {
         self.as_bytes()
     }
+
+    fn from_ne_slice(bytes: &[u8]) -> Self {
+        bytes[0]
+    }
 }
 
 impl ChannelValue for u16 {
@@ -499,6 +918,10 @@ impl ChannelValue for u16 {
     fn as_bytes_wrapper(&self) -> &[u8] {
         self.as_bytes()
     }
+
+    fn from_ne_slice(bytes: &[u8]) -> Self {
+        Self::from_ne_bytes([bytes[0], bytes[1]])
+    }
 }
 
 impl ChannelValue for half::f16 {
@@ -513,6 +936,10 @@ impl ChannelValue for half::f16 {
     fn as_bytes_wrapper(&self) -> &[u8] {
         self.as_bytes()
     }
+
+    fn from_ne_slice(bytes: &[u8]) -> Self {
+        Self::from_ne_bytes([bytes[0], bytes[1]])
+    }
 }
 
 impl ChannelValue for f32 {
@@ -527,6 +954,10 @@ impl ChannelValue for f32 {
     fn as_bytes_wrapper(&self) -> &[u8] {
         self.as_bytes()
     }
+
+    fn from_ne_slice(bytes: &[u8]) -> Self {
+        Self::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -536,6 +967,10 @@ pub enum Target {
     B,
     A,
     RgbAvg,
+    C,
+    M,
+    Y,
+    K,
 }
 
 /// Defines a channel from which to take the value for a color/opacity
@@ -610,6 +1045,38 @@ impl MemoryFormatBytes {
 mod tests {
     use super::*;
 
+    #[test]
+    fn description() {
+        assert_eq!(
+            MemoryFormat::B8g8r8a8Premultiplied.description(),
+            "8-bit BGRA premultiplied"
+        );
+        assert_eq!(MemoryFormat::R8g8b8.description(), "8-bit RGB");
+        assert_eq!(MemoryFormat::R16g16b16a16.description(), "16-bit RGBA");
+        assert_eq!(
+            MemoryFormat::R16g16b16Float.description(),
+            "16-bit float RGB"
+        );
+        assert_eq!(MemoryFormat::G8a8.description(), "8-bit grayscale GA");
+
+        for memory_format in MemoryFormat::ALL {
+            let description = memory_format.description();
+            let layout = memory_format.channel_layout();
+
+            assert_eq!(layout.len(), memory_format.n_channels() as usize);
+            assert_eq!(layout.contains('A'), memory_format.has_alpha());
+            assert!(description.contains(layout), "{description}");
+            assert_eq!(
+                description.ends_with(" premultiplied"),
+                memory_format.is_premultiplied()
+            );
+            assert!(
+                description
+                    .starts_with(&format!("{}-bit", memory_format.channel_type().size() * 8))
+            );
+        }
+    }
+
     #[test]
     fn simple() {
         let target = &mut [0; 4];
@@ -651,4 +1118,328 @@ mod tests {
 
         assert_eq!(*target, [255, 255, 0, 0, 127, 127]);
     }
+
+    #[test]
+    fn half_float() {
+        fn f16_values(bytes: &[u8]) -> Vec<f32> {
+            bytes
+                .chunks_exact(2)
+                .map(|x| half::f16::from_ne_bytes([x[0], x[1]]).to_f32())
+                .collect()
+        }
+
+        // Integer values are normalized
+        let target = &mut [0; 8];
+        MemoryFormat::transform(
+            MemoryFormat::R8g8b8,
+            &[255, 0, 51],
+            MemoryFormat::R16g16b16a16Float,
+            target,
+        );
+        let values = f16_values(target);
+        assert_eq!(&values[..2], [1., 0.]);
+        assert!((values[2] - 0.2).abs() < 1e-3);
+        assert_eq!(values[3], 1.);
+
+        // HDR values are kept
+        let src = [0.25_f32, 4., 1000., 0.5]
+            .iter()
+            .flat_map(|x| x.to_ne_bytes())
+            .collect::<Vec<_>>();
+        MemoryFormat::transform(
+            MemoryFormat::R32g32b32a32Float,
+            &src,
+            MemoryFormat::R16g16b16a16Float,
+            target,
+        );
+        assert_eq!(f16_values(target), [0.25, 4., 1000., 0.5]);
+
+        // Round trip
+        let roundtrip = &mut [0; 16];
+        MemoryFormat::transform(
+            MemoryFormat::R16g16b16a16Float,
+            target,
+            MemoryFormat::R32g32b32a32Float,
+            roundtrip,
+        );
+        assert_eq!(roundtrip.as_slice(), src.as_slice());
+    }
+
+    #[test]
+    fn ycbcr() {
+        let target = &mut [0; 3];
+
+        for (ycbcr, rgb) in [
+            ([0, 128, 128], [0, 0, 0]),
+            ([255, 128, 128], [255, 255, 255]),
+            ([128, 128, 128], [128, 128, 128]),
+            ([76, 85, 255], [255, 0, 0]),
+            ([150, 44, 21], [0, 255, 0]),
+            ([29, 255, 107], [0, 0, 255]),
+        ] {
+            MemoryFormat::transform_extended(
+                ExtendedMemoryFormat::Y8Cb8Cr8,
+                &ycbcr,
+                MemoryFormat::R8g8b8,
+                target,
+            );
+
+            for (value, expected) in target.iter().zip(rgb) {
+                assert!(value.abs_diff(expected) <= 1, "{ycbcr:?}: {target:?}");
+            }
+        }
+
+        // Basic formats are passed through
+        MemoryFormat::transform_extended(
+            ExtendedMemoryFormat::Basic(MemoryFormat::B8g8r8),
+            &[1, 2, 3],
+            MemoryFormat::R8g8b8,
+            target,
+        );
+        assert_eq!(*target, [3, 2, 1]);
+    }
+
+    #[test]
+    fn ycck() {
+        let target = &mut [0; 4];
+
+        for (ycck, rgba) in [
+            ([255, 128, 128, 0], [255, 255, 255, 255]),
+            ([255, 128, 128, 255], [0, 0, 0, 255]),
+            ([255, 128, 128, 128], [127, 127, 127, 255]),
+            ([0, 128, 128, 0], [0, 0, 0, 255]),
+            ([76, 85, 255, 0], [255, 0, 0, 255]),
+            ([76, 85, 255, 51], [204, 0, 0, 255]),
+            ([29, 255, 107, 102], [0, 0, 153, 255]),
+        ] {
+            MemoryFormat::transform_extended(
+                ExtendedMemoryFormat::Y8Cb8Cr8K8,
+                &ycck,
+                MemoryFormat::R8g8b8a8,
+                target,
+            );
+
+            for (value, expected) in target.iter().zip(rgba) {
+                assert!(value.abs_diff(expected) <= 1, "{ycck:?}: {target:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn cmyk() {
+        assert_eq!(MemoryFormat::C8m8y8k8.n_bytes(), MemoryFormatBytes::B4);
+        assert_eq!(MemoryFormat::C16m16y16k16.n_bytes(), MemoryFormatBytes::B8);
+        assert!(!MemoryFormat::C8m8y8k8.has_alpha());
+
+        let target = &mut [0; 3];
+        for (cmyk, rgb) in [
+            ([255, 0, 0, 0], [0, 255, 255]),
+            ([0, 0, 0, 0], [255, 255, 255]),
+            ([0, 0, 0, 255], [0, 0, 0]),
+            ([0, 255, 255, 0], [255, 0, 0]),
+        ] {
+            MemoryFormat::transform(MemoryFormat::C8m8y8k8, &cmyk, MemoryFormat::R8g8b8, target);
+            assert_eq!(*target, rgb, "{cmyk:?}");
+        }
+
+        // Round trip
+        let target = &mut [0; 4];
+        MemoryFormat::transform(
+            MemoryFormat::R8g8b8,
+            &[0, 255, 255],
+            MemoryFormat::C8m8y8k8,
+            target,
+        );
+        assert_eq!(*target, [255, 0, 0, 0]);
+    }
+
+    #[test]
+    fn swap_endianness_in_place() {
+        let original: Vec<u8> = (0..=255).collect();
+
+        for format in MemoryFormat::ALL.iter().copied() {
+            let mut buf = original.clone();
+            MemoryFormat::swap_endianness_in_place(format, &mut buf);
+
+            if format.channel_type().size() == 2 {
+                assert_eq!(buf[..4], [1, 0, 3, 2], "{format:?}");
+            } else {
+                assert_eq!(buf, original, "{format:?}");
+            }
+
+            MemoryFormat::swap_endianness_in_place(format, &mut buf);
+            assert_eq!(buf, original, "{format:?}");
+        }
+
+        let mut buf = 0x1234_u16.to_be_bytes();
+        MemoryFormat::swap_endianness_in_place(MemoryFormat::G16, &mut buf);
+        assert_eq!(u16::from_le_bytes(buf), 0x1234);
+    }
+
+    #[test]
+    fn transform_row() {
+        let n_pixels = 4096;
+
+        // Deterministic pseudo-random data, including NaN and infinite floats
+        let mut state = 0x2545_f491_u32;
+        let src = (0..n_pixels * 16)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect::<Vec<_>>();
+
+        for src_format in MemoryFormat::ALL.iter().copied() {
+            for target_format in MemoryFormat::ALL.iter().copied() {
+                let src_n_bytes = src_format.n_bytes().usize();
+                let target_n_bytes = target_format.n_bytes().usize();
+
+                let mut per_pixel = vec![0; n_pixels * target_n_bytes];
+                for (src, target) in src
+                    .chunks_exact(src_n_bytes)
+                    .zip(per_pixel.chunks_exact_mut(target_n_bytes))
+                {
+                    MemoryFormat::transform(src_format, src, target_format, target);
+                }
+
+                let mut row = vec![0; n_pixels * target_n_bytes];
+                MemoryFormat::transform_row(src_format, &src, target_format, &mut row, n_pixels);
+
+                assert!(
+                    per_pixel == row,
+                    "{src_format:?} to {target_format:?} differs"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn unpremultiply_transparent() {
+        let target = &mut [0; 4];
+
+        // Zero alpha keeps the color values instead of dividing by zero
+        MemoryFormat::transform(
+            MemoryFormat::R8g8b8a8Premultiplied,
+            &[10, 20, 30, 0],
+            MemoryFormat::R8g8b8a8,
+            target,
+        );
+        assert_eq!(*target, [10, 20, 30, 0]);
+
+        let target = &mut [0; 16];
+        MemoryFormat::transform(
+            MemoryFormat::R8g8b8a8Premultiplied,
+            &[0, 0, 0, 0],
+            MemoryFormat::R32g32b32a32Float,
+            target,
+        );
+        assert!(target.iter().all(|x| *x == 0));
+
+        // Non-zero alpha is divided as before
+        let target = &mut [0; 4];
+        MemoryFormat::transform(
+            MemoryFormat::R8g8b8a8Premultiplied,
+            &[64, 32, 0, 128],
+            MemoryFormat::R8g8b8a8,
+            target,
+        );
+        assert_eq!(*target, [128, 64, 0, 128]);
+    }
+
+    #[test]
+    fn half_float_round_trip() {
+        let f16_target = &mut [0; 8];
+        let target = &mut [0; 3];
+
+        for value in 0..=255 {
+            let src = [value, 255 - value, value / 2];
+
+            MemoryFormat::transform(
+                MemoryFormat::R8g8b8,
+                &src,
+                MemoryFormat::R16g16b16a16Float,
+                f16_target,
+            );
+            MemoryFormat::transform(
+                MemoryFormat::R16g16b16a16Float,
+                f16_target,
+                MemoryFormat::R8g8b8,
+                target,
+            );
+
+            assert_eq!(*target, src);
+        }
+
+        // Infinity and values beyond the f16 range are kept as infinity
+        let src = [f32::INFINITY, -1e6, f32::NAN, 1.]
+            .iter()
+            .flat_map(|x| x.to_ne_bytes())
+            .collect::<Vec<_>>();
+        MemoryFormat::transform(
+            MemoryFormat::R32g32b32a32Float,
+            &src,
+            MemoryFormat::R16g16b16a16Float,
+            f16_target,
+        );
+        let values = f16_target
+            .chunks_exact(2)
+            .map(|x| half::f16::from_ne_bytes([x[0], x[1]]).to_f32())
+            .collect::<Vec<_>>();
+        assert_eq!(values, [f32::INFINITY, f32::NEG_INFINITY, 0., 1.]);
+    }
+
+    #[test]
+    fn out_of_range_float() {
+        let src = [f32::NAN, -1., 2.]
+            .iter()
+            .flat_map(|x| x.to_ne_bytes())
+            .collect::<Vec<_>>();
+        fn f32_values(bytes: &[u8]) -> Vec<f32> {
+            bytes
+                .chunks_exact(4)
+                .map(|x| f32::from_ne_bytes([x[0], x[1], x[2], x[3]]))
+                .collect()
+        }
+
+        // Clamped for integer formats
+        let target = &mut [0; 3];
+        MemoryFormat::transform(
+            MemoryFormat::R32g32b32Float,
+            &src,
+            MemoryFormat::R8g8b8,
+            target,
+        );
+        assert_eq!(*target, [0, 0, 255]);
+
+        let target = &mut [0; 6];
+        MemoryFormat::transform(
+            MemoryFormat::R32g32b32Float,
+            &src,
+            MemoryFormat::R16g16b16,
+            target,
+        );
+        assert_eq!(*target, [0, 0, 0, 0, 255, 255]);
+
+        // Passed through for float formats
+        let target = &mut [0; 12];
+        MemoryFormat::transform(
+            MemoryFormat::R32g32b32Float,
+            &src,
+            MemoryFormat::R32g32b32Float,
+            target,
+        );
+        assert_eq!(f32_values(target), [0., -1., 2.]);
+
+        // Explicit range
+        MemoryFormat::transform_with_range(
+            MemoryFormat::R32g32b32Float,
+            &src,
+            MemoryFormat::R32g32b32Float,
+            target,
+            (0., 1.),
+        );
+        assert_eq!(f32_values(target), [0., 0., 1.]);
+    }
 }
@@ -3,6 +3,8 @@ use std::borrow::Borrow;
 use crate::{MemoryFormat, MemoryFormatInfo};
 
 /// Selection of memory formats the API user accepts
+///
+/// CMYK formats can't be selected. They are always converted to RGB.
 #[cfg(feature = "gobject")]
 #[glib::flags(name = "GlyMemoryFormatSelection")]
 pub enum MemoryFormatSelection {
@@ -142,6 +144,26 @@ impl MemoryFormatSelection {
 
         vec
     }
+
+    /// Selection without premultiplied formats
+    ///
+    /// ```
+    /// # use glycin_common::MemoryFormatSelection;
+    ///
+    /// assert_eq!(
+    ///     (MemoryFormatSelection::R8g8b8a8Premultiplied | MemoryFormatSelection::R8g8b8a8)
+    ///         .without_premultiplied(),
+    ///     MemoryFormatSelection::R8g8b8a8
+    /// );
+    /// ```
+    pub fn without_premultiplied(self) -> Self {
+        Self::from_memory_formats(
+            self.memory_formats()
+                .into_iter()
+                .filter(|x| !x.is_premultiplied()),
+        )
+    }
+
     pub fn from_memory_format(memory_format: MemoryFormat) -> Self {
         for (selection, format) in Self::X {
             if format == memory_format {
@@ -164,7 +186,8 @@ impl MemoryFormatSelection {
 
     /// Select the best contained format to represent `src`
     ///
-    /// The function returns `None` if no formats are selected.
+    /// The function returns `None` if no formats are selected. CMYK formats
+    /// are treated like the RGB format they are converted to.
     ///
     /// ```
     /// # use glycin_common::{MemoryFormatSelection, MemoryFormat};
@@ -206,6 +229,9 @@ impl MemoryFormatSelection {
             return Some(src);
         }
 
+        // CMYK is converted to RGB and should be represented as such
+        let src = src.without_cmyk();
+
         let mut formats_categorized = formats
             .into_iter()
             .map(|x| {
@@ -5,6 +5,8 @@ use gufo_common::orientation::{Orientation, Rotation};
 use serde::de::{IntoDeserializer, value};
 use serde::{Deserialize, Deserializer, Serialize};
 
+use crate::BinaryData;
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[non_exhaustive]
 pub enum Operation {
@@ -13,6 +15,34 @@ pub enum Operation {
     MirrorVertically,
     /// Counter-clockwise rotation
     Rotate(gufo_common::orientation::Rotation),
+    /// Losslessly reduce the file size
+    ///
+    /// For PNG, the image data are re-encoded with the highest compression
+    /// effort and adaptive filtering. For JPEG, the entropy coded data are
+    /// re-encoded with Huffman tables optimized for the image, without
+    /// decoding the pixels. Progressive and arithmetic coded JPEGs are kept
+    /// as they are. Other formats don't support this operation.
+    ///
+    /// The pixel data are never changed by this operation. Depending on the
+    /// image size, this operation can be slow.
+    Optimize,
+    /// Color grade the image via a 3D lookup table
+    ///
+    /// The `lut` contains `size³` RGB entries as consecutive native endian
+    /// `f32` triples, see [`BinaryData::from_f32`]. The entries are in the
+    /// order of `.cube` files: The red index changes fastest, then
+    /// green, then blue. Entry `(r, g, b)` is therefore stored at index
+    /// `3 * (r + g * size + b * size²)`. The values are normalized to
+    /// `0.0..=1.0`, the grid points are evenly spaced over that range.
+    /// Common sizes are 17, 33, and 65.
+    ///
+    /// Pixel values are clamped to `0.0..=1.0` and trilinearly interpolated
+    /// between the grid points. The alpha channel is not changed. Since the
+    /// image data are always changed, this operation is never sparse.
+    ApplyLut3D {
+        lut: BinaryData,
+        size: u32,
+    },
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, PartialOrd, Ord)]
@@ -22,6 +52,8 @@ pub enum OperationId {
     MirrorHorizontally,
     MirrorVertically,
     Rotate,
+    Optimize,
+    ApplyLut3D,
 }
 
 #[derive(Debug, PartialEq, Deserialize, Serialize, Clone)]
@@ -157,6 +189,7 @@ impl Operations {
                 Operation::Rotate(rotation) => {
                     orientation = orientation.add_rotation(*rotation);
                 }
+                Operation::Optimize => {}
                 _ => return None,
             }
         }
@@ -230,6 +263,8 @@ impl Operation {
             Self::MirrorHorizontally => OperationId::MirrorHorizontally,
             Self::MirrorVertically => OperationId::MirrorVertically,
             Self::Rotate(_) => OperationId::Rotate,
+            Self::Optimize => OperationId::Optimize,
+            Self::ApplyLut3D { .. } => OperationId::ApplyLut3D,
         }
     }
 }
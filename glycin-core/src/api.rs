@@ -1,11 +1,35 @@
 mod common;
+mod compare;
 mod creator;
+mod depth;
+mod diagnostics;
 mod editor;
+mod fit;
+mod format_support;
+mod index;
 mod limits;
+mod load_options;
 mod loader;
+mod process_context;
+mod scanlines;
+mod stats;
+#[cfg(feature = "external")]
+mod warm_loader;
 
 pub use common::*;
+pub use compare::*;
 pub use creator::*;
+pub use depth::*;
+pub use diagnostics::*;
 pub use editor::*;
+pub use fit::*;
+pub use format_support::*;
+pub use index::*;
 pub use limits::*;
+pub use load_options::*;
 pub use loader::*;
+pub use process_context::*;
+pub use scanlines::*;
+pub use stats::*;
+#[cfg(feature = "external")]
+pub use warm_loader::*;
@@ -1,6 +1,9 @@
+use std::ffi::OsString;
 #[cfg(feature = "builtin")]
 use std::marker::PhantomData;
+use std::os::fd::RawFd;
 use std::path::{Path, PathBuf};
+use std::process::Command;
 use std::sync::Arc;
 
 #[cfg(feature = "gobject")]
@@ -15,14 +18,19 @@ use crate::dbus::{EditorProxy, LoaderProxy};
 use crate::pool::{PooledProcess, UsageTracker};
 use crate::source::SourceTransmission;
 use crate::util::RunEnvironment;
-use crate::{Error, ErrorKind, MimeType, Pool, config};
+use crate::{Error, ErrorKind, MimeType, Pool, config, util};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[non_exhaustive]
 /// Sandboxing mechanism for image loading and editing
 pub enum SandboxMechanism {
     Bwrap,
     FlatpakSpawn,
     NotSandboxed,
+    /// Started via a [`SandboxWrapper`]
+    ///
+    /// See [`Loader::sandbox_wrapper`](crate::Loader::sandbox_wrapper).
+    Custom,
 }
 
 impl SandboxMechanism {
@@ -36,15 +44,138 @@ impl SandboxMechanism {
         }
     }
 
+    /// Selector that results in this mechanism
+    ///
+    /// A [`SandboxWrapper`] can't be selected this way. Since glycin doesn't
+    /// set up a sandbox around the wrapper, [`SandboxMechanism::Custom`]
+    /// returns [`SandboxSelector::NotSandboxed`].
     pub fn into_selector(self) -> SandboxSelector {
         match self {
             Self::Bwrap => SandboxSelector::Bwrap,
             Self::FlatpakSpawn => SandboxSelector::FlatpakSpawn,
-            Self::NotSandboxed => SandboxSelector::NotSandboxed,
+            Self::NotSandboxed | Self::Custom => SandboxSelector::NotSandboxed,
         }
     }
 }
 
+type WrapperFn = dyn Fn(&SandboxWrapperArgs) -> Command + Send + Sync;
+
+/// Custom command that starts loaders in a sandbox
+///
+/// Allows using sandboxing tools like firejail or custom namespace setups
+/// instead of the mechanisms built into glycin. The wrapper is called for
+/// every loader process that is spawned and has to return a [`Command`] that
+/// runs the loader binary [`SandboxWrapperArgs::exec`] inside the sandbox.
+///
+/// The returned command has to honor the following contract:
+///
+/// - glycin appends arguments to the command. They have to be passed to the
+///   loader binary unchanged and as its last arguments.
+/// - The file descriptor [`SandboxWrapperArgs::dbus_fd`] has to be available
+///   in the loader under the same number. glycin removes `FD_CLOEXEC` from
+///   it, but the wrapper must not close it.
+/// - stdin, stdout, and stderr are connected to glycin and have to be passed
+///   through to the loader. With [`PoolConfig::token_authentication`](crate::PoolConfig::token_authentication),
+///   the authentication token is sent via stdin.
+/// - The environment of the command is used as is. The wrapper should clear
+///   it and only set the variables from [`SandboxWrapperArgs::environment`].
+/// - Besides the loader binary and its libraries, the paths from
+///   [`SandboxWrapperArgs::ro_binds`] have to be readable and
+///   [`SandboxWrapperArgs::tmpdir`] has to be writable.
+/// - The started process is treated as the loader process. It must not exit
+///   before the loader does and killing it has to end the loader.
+///
+/// Wrappers are only equal to their clones. Processes started via different
+/// wrappers are therefore not shared in the [`Pool`].
+#[derive(Clone)]
+pub struct SandboxWrapper {
+    wrapper: Arc<WrapperFn>,
+}
+
+impl SandboxWrapper {
+    pub fn new(wrapper: impl Fn(&SandboxWrapperArgs) -> Command + Send + Sync + 'static) -> Self {
+        Self {
+            wrapper: Arc::new(wrapper),
+        }
+    }
+
+    pub(crate) fn command(&self, args: &SandboxWrapperArgs) -> Command {
+        (self.wrapper)(args)
+    }
+
+    fn id(&self) -> usize {
+        Arc::as_ptr(&self.wrapper).cast::<()>() as usize
+    }
+}
+
+impl std::fmt::Debug for SandboxWrapper {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SandboxWrapper")
+            .field("id", &self.id())
+            .finish()
+    }
+}
+
+impl PartialEq for SandboxWrapper {
+    fn eq(&self, other: &Self) -> bool {
+        self.id() == other.id()
+    }
+}
+
+impl Eq for SandboxWrapper {}
+
+impl PartialOrd for SandboxWrapper {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SandboxWrapper {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.id().cmp(&other.id())
+    }
+}
+
+/// Information passed to a [`SandboxWrapper`]
+#[derive(Debug)]
+pub struct SandboxWrapperArgs {
+    pub(crate) exec: PathBuf,
+    pub(crate) dbus_fd: RawFd,
+    pub(crate) ro_binds: Vec<PathBuf>,
+    pub(crate) tmpdir: Option<PathBuf>,
+    pub(crate) environment: Vec<(OsString, OsString)>,
+}
+
+impl SandboxWrapperArgs {
+    /// Loader binary that has to be started
+    pub fn exec(&self) -> &Path {
+        &self.exec
+    }
+
+    /// File descriptor of the D-Bus connection to the loader
+    pub fn dbus_fd(&self) -> RawFd {
+        self.dbus_fd
+    }
+
+    /// Paths that the loader needs read access to
+    ///
+    /// Contains the directory of the image for formats like SVG and sidecar
+    /// files.
+    pub fn ro_binds(&self) -> &[PathBuf] {
+        &self.ro_binds
+    }
+
+    /// Directory for temporary files, see [`Loader::sandbox_tmpdir`](crate::Loader::sandbox_tmpdir)
+    pub fn tmpdir(&self) -> Option<&Path> {
+        self.tmpdir.as_deref()
+    }
+
+    /// Environment variables the loader should be started with
+    pub fn environment(&self) -> &[(OsString, OsString)] {
+        &self.environment
+    }
+}
+
 #[derive(Debug, Copy, Clone, Default)]
 #[cfg_attr(feature = "gobject", derive(gio::glib::Enum))]
 #[cfg_attr(feature = "gobject", enum_type(name = "GlySandboxSelector"))]
@@ -80,6 +211,72 @@ impl SandboxSelector {
     }
 }
 
+/// Scheduling priority of loader processes
+///
+/// Lowers the CPU and IO priority of loader processes, for example to
+/// generate thumbnails in the background without competing with foreground
+/// work. Priorities can only be lowered, since raising them requires
+/// privileges.
+///
+/// The priority is set in the forked process before the sandbox or loader
+/// binary is executed and is inherited by the loader. The nice value is set
+/// via `setpriority(2)` and the idle IO class via `ioprio_set(2)`. The IO
+/// class only has an effect with IO schedulers that support it, like BFQ.
+/// For [`SandboxMechanism::FlatpakSpawn`], the loader is started by the
+/// Flatpak portal and the priority can't be applied. Builtin loaders are not
+/// affected.
+///
+/// Processes with different priorities are not shared in the [`Pool`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ProcessPriority {
+    nice: u8,
+    io_idle: bool,
+}
+
+impl ProcessPriority {
+    /// Largest nice value and therefore lowest CPU priority
+    pub const MAX_NICE: i32 = 19;
+
+    /// Priority with the given nice value
+    ///
+    /// Returns [`ErrorKind::InvalidProcessPriority`] if `nice` is not between
+    /// `0` (normal priority) and [`ProcessPriority::MAX_NICE`].
+    pub fn new(nice: i32) -> Result<Self, Error> {
+        if !(0..=Self::MAX_NICE).contains(&nice) {
+            return Err(ErrorKind::InvalidProcessPriority(nice).err());
+        }
+
+        Ok(Self {
+            nice: nice as u8,
+            io_idle: false,
+        })
+    }
+
+    /// Lowest CPU priority and idle IO class
+    pub fn background() -> Self {
+        Self {
+            nice: Self::MAX_NICE as u8,
+            io_idle: true,
+        }
+    }
+
+    /// Only perform IO when no other process needs the disk
+    pub fn io_idle(mut self, io_idle: bool) -> Self {
+        self.io_idle = io_idle;
+        self
+    }
+
+    /// Nice value of the process
+    pub fn nice(&self) -> i32 {
+        self.nice.into()
+    }
+
+    /// Whether the idle IO class is used
+    pub fn is_io_idle(&self) -> bool {
+        self.io_idle
+    }
+}
+
 #[derive(Debug, Clone)]
 #[non_exhaustive]
 pub enum ColorState {
@@ -112,6 +309,8 @@ impl GInputStreamSend {
 pub(crate) enum Source {
     File(gio::File),
     Stream(GInputStreamSend),
+    /// In-memory buffer owned by the source
+    Vec(Vec<u8>),
     TransferredStream,
 }
 
@@ -123,22 +322,25 @@ impl Source {
         }
     }
 
-    pub async fn to_stream(&self) -> Result<gio::InputStream, Error> {
+    pub async fn to_stream(self) -> Result<gio::InputStream, Error> {
         match self {
             Self::File(file) => file
                 .read_future(glib::Priority::DEFAULT)
                 .await
                 .map(|x| x.upcast())
                 .map_err(|e| ErrorKind::ImageSource(e).err()),
-            Self::Stream(stream) => Ok(stream.0.clone()),
+            Self::Stream(stream) => Ok(stream.0),
+            Self::Vec(data) => {
+                Ok(gio::MemoryInputStream::from_bytes(&glib::Bytes::from_owned(data)).upcast())
+            }
             Self::TransferredStream => Err(ErrorKind::TransferredStream.into()),
         }
     }
 
     /// Get a [`Source`] for sending to [`GFileWorker`]
     ///
-    /// This will remove the stored stream from `self` to avoid it getting used
-    /// anywhere else than the [`GFileWorker`] it has been sent to.
+    /// This will remove the stored stream or buffer from `self` to avoid it
+    /// getting used anywhere else than the [`GFileWorker`] it has been sent to.
     pub fn send(&mut self) -> Self {
         let new = self
             .file()
@@ -160,6 +362,7 @@ impl Source {
             Self::Stream(stream) => {
                 format!("Stream({})", stream.display())
             }
+            Self::Vec(data) => format!("Vec({} bytes)", data.len()),
             Self::TransferredStream => String::from("TransferredStream"),
         }
     }
@@ -169,9 +372,13 @@ impl Source {
 pub(crate) struct ProcessorContext<T: GetConfig, S> {
     pub mime_type: MimeType,
     pub sandbox_mechanism: SandboxMechanism,
+    pub sandbox_wrapper: Option<SandboxWrapper>,
     pub config_entry: T,
     pub g_file_worker: S,
     pub base_dir: Option<PathBuf>,
+    pub sidecars: Vec<PathBuf>,
+    pub tmpdir: Option<PathBuf>,
+    pub process_priority: ProcessPriority,
 }
 
 pub trait GetConfig {
@@ -222,18 +429,26 @@ impl<T: GetConfig + Clone> ProcessorContext<T, SourceTransmission> {
     pub(crate) async fn new(
         source: Source,
         use_expose_base_dir: bool,
+        sidecars: &[gio::File],
+        tmpdir: Option<PathBuf>,
         sandbox_selector: &SandboxSelector,
+        mime_type: Option<MimeType>,
+        read_buffer_size: usize,
     ) -> Result<ProcessorContext<T, SourceTransmission>, Error> {
         let file = source.file();
 
-        let source_transmission = SourceTransmission::init(source).await?;
+        let source_transmission = SourceTransmission::init(source, read_buffer_size).await?;
         let config = config::Config::cached().await;
 
-        let mime_type = T::guess_mime_type(
-            &config,
-            source_transmission.file().and_then(|x| x.path()).as_deref(),
-            source_transmission.first_bytes(),
-        );
+        let mime_type = mime_type
+            .or_else(|| crate::sniffer::sniff(source_transmission.first_bytes()))
+            .or_else(|| {
+                T::guess_mime_type(
+                    &config,
+                    source_transmission.file().and_then(|x| x.path()).as_deref(),
+                    source_transmission.first_bytes(),
+                )
+            });
 
         let mime_type = if let Some(mime_type) = mime_type {
             mime_type
@@ -247,24 +462,93 @@ impl<T: GetConfig + Clone> ProcessorContext<T, SourceTransmission> {
 
         let config_entry = T::config_entry(&config, &mime_type)?.clone();
 
-        let base_dir = if use_expose_base_dir && config_entry.expose_base_dir() {
-            file.and_then(|x| x.parent()).and_then(|x| x.path())
-        } else {
-            None
+        let base_dir = match file {
+            Some(file) if use_expose_base_dir && config_entry.expose_base_dir() => {
+                base_dir(&file).await
+            }
+            _ => None,
         };
 
+        let mut sidecar_paths = Vec::new();
+        for sidecar in sidecars {
+            if let Some(path) = sidecar_path(sidecar).await {
+                sidecar_paths.push(path);
+            }
+        }
+
         let sandbox_mechanism = sandbox_selector.determine_sandbox_mechanism().await;
 
         Ok(ProcessorContext {
             config_entry,
             base_dir,
+            sidecars: sidecar_paths,
+            tmpdir,
+            process_priority: ProcessPriority::default(),
             mime_type,
             sandbox_mechanism,
+            sandbox_wrapper: None,
             g_file_worker: source_transmission,
         })
     }
 }
 
+/// Directory of `file` that can be exposed to the loader
+///
+/// Symlinks and relative components are resolved, such that the directory is
+/// mounted at its actual location in the sandbox. Files without local path,
+/// like from remote GVfs backends, have no base directory.
+async fn base_dir(file: &gio::File) -> Option<PathBuf> {
+    let Some(path) = file.parent().and_then(|x| x.path()) else {
+        tracing::info!(
+            "Not exposing base directory of '{}' since it has no local path",
+            file.uri()
+        );
+        return None;
+    };
+
+    match util::spawn_blocking(move || std::fs::canonicalize(&path).map_err(|err| (path, err)))
+        .await
+    {
+        Ok(Ok(base_dir)) => Some(base_dir),
+        Ok(Err((path, err))) => {
+            tracing::info!("Not exposing base directory {path:?}: {err}");
+            None
+        }
+        Err(err) => {
+            tracing::info!("Not exposing base directory: {err}");
+            None
+        }
+    }
+}
+
+/// Path of a sidecar file that can be exposed to the loader
+///
+/// Like for [`base_dir`], symlinks are resolved and files without local path
+/// are skipped.
+async fn sidecar_path(file: &gio::File) -> Option<PathBuf> {
+    let Some(path) = file.path() else {
+        tracing::info!(
+            "Not exposing sidecar '{}' since it has no local path",
+            file.uri()
+        );
+        return None;
+    };
+
+    match util::spawn_blocking(move || std::fs::canonicalize(&path).map_err(|err| (path, err)))
+        .await
+    {
+        Ok(Ok(path)) => Some(path),
+        Ok(Err((path, err))) => {
+            tracing::info!("Not exposing sidecar {path:?}: {err}");
+            None
+        }
+        Err(err) => {
+            tracing::info!("Not exposing sidecar: {err}");
+            None
+        }
+    }
+}
+
 impl<T: GetConfig + Clone> ProcessorContext<T, ()> {
     pub async fn new_sourceless(
         mime_type: MimeType,
@@ -277,13 +561,25 @@ impl<T: GetConfig + Clone> ProcessorContext<T, ()> {
         Ok(Self {
             mime_type,
             base_dir: None,
+            sidecars: Vec::new(),
+            tmpdir: None,
+            process_priority: ProcessPriority::default(),
             config_entry,
             sandbox_mechanism,
+            sandbox_wrapper: None,
             g_file_worker: (),
         })
     }
 }
 
+impl<T: GetConfig, S> ProcessorContext<T, S> {
+    /// Start the process via `sandbox_wrapper` instead of the selected mechanism
+    pub fn set_sandbox_wrapper(&mut self, sandbox_wrapper: SandboxWrapper) {
+        self.sandbox_mechanism = SandboxMechanism::Custom;
+        self.sandbox_wrapper = Some(sandbox_wrapper);
+    }
+}
+
 impl<S> ProcessorContext<ImageLoaderConfig, S> {
     pub async fn loader(
         self,
@@ -301,6 +597,7 @@ impl<S> ProcessorContext<ImageLoaderConfig, S> {
                 builtin,
                 source_transmission: self.g_file_worker,
                 mime_type: self.mime_type,
+                sidecars: self.sidecars,
                 _phantom_data: Default::default(),
             })),
         }
@@ -317,7 +614,11 @@ impl<S> ProcessorContext<ImageLoaderConfig, S> {
             .get_loader(
                 self.config_entry,
                 self.sandbox_mechanism,
+                self.sandbox_wrapper,
                 self.base_dir,
+                self.sidecars,
+                self.tmpdir,
+                self.process_priority,
                 cancellable,
             )
             .await?;
@@ -349,6 +650,7 @@ impl<S> ProcessorContext<ImageEditorConfig, S> {
                 builtin,
                 source_transmission: self.g_file_worker,
                 mime_type: self.mime_type,
+                sidecars: self.sidecars,
                 _phantom_data: Default::default(),
             })),
         }
@@ -365,7 +667,11 @@ impl<S> ProcessorContext<ImageEditorConfig, S> {
             .get_editor(
                 self.config_entry,
                 self.sandbox_mechanism,
+                self.sandbox_wrapper,
                 self.base_dir,
+                self.sidecars,
+                self.tmpdir,
+                self.process_priority,
                 cancellable,
             )
             .await?;
@@ -410,6 +716,7 @@ pub(crate) struct BuiltinProcessor<T, S> {
     pub builtin: config::BuiltinProcessor,
     pub mime_type: MimeType,
     pub source_transmission: S,
+    pub sidecars: Vec<PathBuf>,
     _phantom_data: PhantomData<T>,
 }
 
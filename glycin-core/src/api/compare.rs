@@ -0,0 +1,73 @@
+use futures_util::future;
+use gio::prelude::*;
+use glycin_common::MemoryFormatInfo;
+use glycin_utils::safe_math::*;
+
+use crate::{Error, ErrorKind, Frame, Loader};
+
+/// Whether two files decode to identical pixels
+///
+/// Both images are loaded like with [`Loader::new`], including the Exif
+/// orientation and the conversion of ICC profiles. The images are identical
+/// if their first frames have the same dimensions, memory format, and pixel
+/// values. File bytes and metadata are not compared. For animations, only the
+/// first frame is compared.
+///
+/// Returns `false` without decoding any frame if the image dimensions differ.
+/// The comparison stops at the first differing row. Images that are only
+/// identical after a memory format conversion, like an opaque RGBA and an RGB
+/// image, are considered different.
+///
+/// Cancelling `cancellable` aborts loading and the comparison.
+pub async fn images_equal(
+    a: gio::File,
+    b: gio::File,
+    cancellable: impl IsA<gio::Cancellable>,
+) -> Result<bool, Error> {
+    let cancellable = cancellable.upcast();
+
+    let load = |file| {
+        let mut loader = Loader::new(file);
+        loader.cancellable(cancellable.clone());
+        loader.load()
+    };
+
+    let (mut image_a, mut image_b) = future::try_join(load(a), load(b)).await?;
+
+    let (details_a, details_b) = (image_a.details(), image_b.details());
+    if (details_a.oriented_width(), details_a.oriented_height())
+        != (details_b.oriented_width(), details_b.oriented_height())
+    {
+        return Ok(false);
+    }
+
+    let (frame_a, frame_b) = future::try_join(image_a.next_frame(), image_b.next_frame()).await?;
+
+    frames_equal(&frame_a, &frame_b, &cancellable)
+}
+
+fn frames_equal(a: &Frame, b: &Frame, cancellable: &gio::Cancellable) -> Result<bool, Error> {
+    if (a.width(), a.height(), a.memory_format()) != (b.width(), b.height(), b.memory_format()) {
+        return Ok(false);
+    }
+
+    let row_length = a
+        .width()
+        .try_usize()?
+        .smul(a.memory_format().n_bytes().usize())?;
+
+    let rows_a = a.buf_slice().chunks(a.stride().try_usize()?);
+    let rows_b = b.buf_slice().chunks(b.stride().try_usize()?);
+
+    for (row_a, row_b) in rows_a.zip(rows_b).take(a.height().try_usize()?) {
+        if cancellable.is_cancelled() {
+            return Err(ErrorKind::Canceled(None).err());
+        }
+
+        if row_a.get(..row_length) != row_b.get(..row_length) {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
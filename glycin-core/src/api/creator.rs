@@ -1,6 +1,7 @@
 use std::collections::BTreeMap;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Duration;
 
 use glib::object::IsA;
 use glib::prelude::*;
@@ -13,10 +14,10 @@ use gufo_common::physical_dimension::PixelDensity;
 #[cfg(feature = "builtin")]
 use crate::config;
 use crate::config::{Config, ImageEditorConfig};
-use crate::error::ResultExt;
+use crate::error::{ResultExt, Stage};
 use crate::pool::Pool;
-use crate::util::CancellableFuture;
-use crate::{Error, ErrorKind, MimeType, Processor, ProcessorContext, SandboxSelector};
+use crate::util::{self, CancellableFuture};
+use crate::{Error, ErrorKind, Frame, MimeType, Processor, ProcessorContext, SandboxSelector};
 
 #[derive(Debug)]
 pub struct Creator {
@@ -62,6 +63,83 @@ impl Creator {
         })
     }
 
+    /// Create an encoder for raw pixel data
+    ///
+    /// This is a shorthand for [`Creator::new`] and [`Creator::add_frame`]
+    /// that allows to encode images the application generated itself. The
+    /// pixel data are validated against the dimensions and the memory format
+    /// before being sent to the sandboxed encoder. Options like
+    /// [`set_encoding_quality`](Self::set_encoding_quality) can be set before
+    /// calling [`create`](Self::create).
+    ///
+    /// The supported target formats are listed by
+    /// [`Creator::supported_mime_types`].
+    pub async fn from_pixels(
+        mime_type: MimeType,
+        width: u32,
+        height: u32,
+        memory_format: MemoryFormat,
+        texture: Vec<u8>,
+    ) -> Result<Creator, Error> {
+        let mut creator = Self::new(mime_type).await?;
+        creator.add_frame(width, height, memory_format, texture)?;
+
+        Ok(creator)
+    }
+
+    /// Create an encoder for an animation
+    ///
+    /// Each frame is shown for the given duration. The `loop_count` is the
+    /// number of times the animation is played, `0` plays it infinitely.
+    /// All frames must have the same dimensions. Options like
+    /// [`set_encoding_compression`](Self::set_encoding_compression) can be
+    /// set before calling [`create`](Self::create).
+    ///
+    /// Animations are supported for GIF, WebP, and PNG, where the latter
+    /// creates an APNG. Every frame covers the complete canvas and the
+    /// disposal of frames is always "none". Other formats return
+    /// [`FeatureNotSupported`] for more than one frame.
+    pub async fn from_animation(
+        mime_type: MimeType,
+        frames: Vec<(Frame, Duration)>,
+        loop_count: u32,
+    ) -> Result<Creator, Error> {
+        let mut creator = Self::new(mime_type).await?;
+        if frames.len() > 1 {
+            creator.set_loop_count(loop_count)?;
+        }
+
+        for (frame, delay) in frames {
+            if frame.planes().is_some() {
+                return Err(Error::other("Planar frames can't be encoded"));
+            }
+
+            let new_frame = creator.add_frame_with_stride(
+                frame.width(),
+                frame.height(),
+                frame.stride(),
+                frame.memory_format(),
+                frame.buf_slice().to_vec(),
+            )?;
+            new_frame.set_delay(Some(delay));
+        }
+
+        Ok(creator)
+    }
+
+    /// Returns a list of mime types for which encoders are configured
+    ///
+    /// These are the formats with an editor configured as `Creator`.
+    pub async fn supported_mime_types() -> Vec<MimeType> {
+        Config::cached()
+            .await
+            .editors()
+            .iter()
+            .filter(|(_, config)| config.is_creator())
+            .map(|(mime_type, _)| mime_type.clone())
+            .collect()
+    }
+
     pub fn add_frame(
         &mut self,
         width: u32,
@@ -89,6 +167,12 @@ impl Creator {
         memory_format: MemoryFormat,
         mut texture: Vec<u8>,
     ) -> Result<&mut NewFrame, Error> {
+        if width == 0 || height == 0 {
+            return Err(
+                ErrorKind::WidgthOrHeightZero(format!("Image size: {width} x {height}")).into(),
+            );
+        }
+
         let pixel_size = memory_format.n_bytes().u32();
 
         let smallest_stride = pixel_size
@@ -112,23 +196,13 @@ impl Creator {
         }
 
         if smallest_stride != stride {
-            let old_stride = stride as usize;
-            let new_stride = smallest_stride as usize;
-
-            let height_ = height as usize;
-            let mut source = vec![0; new_stride];
-
-            for row in 0..height_ {
-                let old_row_begin = row * old_stride;
-                let old_row_end = old_row_begin + new_stride;
-                source.copy_from_slice(&texture[old_row_begin..old_row_end]);
-
-                let new_row_begin = row * new_stride;
-                let new_row_end = new_row_begin + new_stride;
-                texture[new_row_begin..new_row_end].copy_from_slice(&source);
-            }
-
-            texture.resize(new_stride * height_, 0);
+            texture = util::change_stride(
+                &texture,
+                height as usize,
+                smallest_stride as usize,
+                stride as usize,
+                smallest_stride as usize,
+            )?;
         };
 
         let new_frame = NewFrame::new(self.config.clone(), width, height, memory_format, texture);
@@ -144,13 +218,32 @@ impl Creator {
         Box::pin(async move {
             let cancellable = self.cancellable.clone();
 
-            self.create_internal().make_cancellable(cancellable).await
+            async move { self.create_internal().await.stage(Stage::Editor) }
+                .make_cancellable(cancellable)
+                .await
         })
     }
 
     async fn create_internal(self) -> Result<EncodedImage, Error> {
         let mut new_image = self.new_image;
 
+        if self.new_frames.len() > 1 {
+            if !self.config.creator_animation {
+                return Err(FeatureNotSupported.into());
+            }
+
+            let first = &self.new_frames[0];
+            for frame in &self.new_frames[1..] {
+                if (frame.width, frame.height) != (first.width, first.height) {
+                    return Err(ErrorKind::AnimationDimensionsMismatch(format!(
+                        "{} x {} and {} x {}",
+                        first.width, first.height, frame.width, frame.height
+                    ))
+                    .into());
+                }
+            }
+        }
+
         for frame in self.new_frames {
             let mut frame = frame.frame()?;
 
@@ -181,7 +274,8 @@ impl Creator {
 
         let editor = editor_context
             .editor(self.pool.clone(), &self.cancellable)
-            .await?;
+            .await
+            .stage(Stage::Spawn)?;
 
         match editor {
             #[cfg(feature = "external")]
@@ -264,6 +358,28 @@ impl Creator {
         Ok(())
     }
 
+    /// Set how often an animation is played
+    ///
+    /// A `loop_count` of `0` plays the animation infinitely, which is the
+    /// default.
+    pub fn set_loop_count(&mut self, loop_count: u32) -> Result<(), FeatureNotSupported> {
+        if !self.config.creator_animation {
+            return Err(FeatureNotSupported);
+        }
+
+        self.encoding_options.loop_count = Some(loop_count);
+        Ok(())
+    }
+
+    /// Set bits per channel of the output
+    ///
+    /// Currently, only PNG supports this for 8 and 16 bits. Frames with a
+    /// higher depth are reduced with ordered dithering. For other formats
+    /// and depths, the depth of the frame data is kept.
+    pub fn set_encoding_bit_depth(&mut self, bit_depth: u8) {
+        self.encoding_options.bit_depth = Some(bit_depth);
+    }
+
     pub fn set_metadata_key_value(
         &mut self,
         key_value: BTreeMap<String, String>,
@@ -328,7 +444,7 @@ pub struct NewFrame {
     //stride: Option<u32>,
     memory_format: MemoryFormat,
     texture: Vec<u8>,
-    //delay: Option<Duration>,
+    delay: Option<Duration>,
     details: glycin_utils::FrameDetails<FungibleMemory>,
     icc_profile: Option<Vec<u8>>,
 }
@@ -348,7 +464,7 @@ impl NewFrame {
             memory_format,
             texture,
             //stride: None,
-            //delay: None,
+            delay: None,
             details: Default::default(),
             icc_profile: Default::default(),
         }
@@ -379,6 +495,20 @@ impl NewFrame {
         Ok(())
     }
 
+    /// Duration for which the frame is shown in an animation
+    pub fn set_delay(&mut self, delay: Option<Duration>) {
+        self.delay = delay;
+    }
+
+    pub(crate) fn set_color_cicp(&mut self, cicp: crate::Cicp) {
+        self.details.color_cicp = Some([
+            cicp.color_primaries.into(),
+            cicp.transfer_characteristics.into(),
+            cicp.matrix_coefficients.into(),
+            cicp.video_full_range_flag.into(),
+        ]);
+    }
+
     fn frame(self) -> Result<glycin_utils::Frame<FungibleMemory>, Error> {
         let texture = FungibleMemory::try_from_vec(self.texture)?;
         let mut frame = glycin_utils::Frame::<FungibleMemory>::new(
@@ -389,6 +519,7 @@ impl NewFrame {
         )?;
 
         frame.details = self.details;
+        frame.delay = self.delay.into();
 
         if let Some(icc_profile) = self.icc_profile {
             let icc_profile = FungibleMemory::try_from_vec(icc_profile)?;
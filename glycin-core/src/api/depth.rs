@@ -0,0 +1,153 @@
+use std::future::Future;
+use std::pin::Pin;
+
+use gio::glib;
+
+use crate::{Error, Frame, FrameRequest, Image, ImageItemRole, Loader, MemoryFormatSelection};
+
+/// Depth map of an image
+///
+/// See [`Loader::depth_map`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct DepthMap {
+    /// Grayscale frame with the depth values
+    ///
+    /// The resolution is often lower than the one of the image.
+    pub frame: Frame,
+    pub encoding: DepthEncoding,
+    pub source: DepthMapSource,
+}
+
+/// Meaning of the values of a [`DepthMap`]
+///
+/// The normalized value `dn` goes from `0.0` for the smallest to `1.0` for
+/// the largest value of the memory format. The distance from the camera is
+/// `d`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub enum DepthEncoding {
+    /// `dn = (d - near) / (far - near)`
+    RangeLinear { near: f64, far: f64 },
+    /// `dn = (far * (d - near)) / (d * (far - near))`
+    ///
+    /// This gives more precision to close distances.
+    RangeInverse { near: f64, far: f64 },
+    /// The encoding is not reported
+    ///
+    /// Depth maps in HEIF files from phones often store disparity, where
+    /// larger values are closer to the camera.
+    Unknown,
+}
+
+/// Where a [`DepthMap`] was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DepthMapSource {
+    /// Image item with the ID, see [`FrameRequest::image_item`]
+    ImageItem(u32),
+    /// Auxiliary image with the index, see [`FrameRequest::auxiliary_image`]
+    AuxiliaryImage(u32),
+    /// Google depth map stored in the `GDepth` XMP namespace
+    Xmp,
+}
+
+impl Loader {
+    /// Load the depth map of portrait mode photos
+    ///
+    /// The following sources are consulted in this order:
+    ///
+    /// 1. [Image items](crate::ImageDetails::image_items) with the
+    ///    [`ImageItemRole::Depth`] role.
+    /// 2. Auxiliary images with a depth type, like
+    ///    `urn:mpeg:hevc:2015:auxid:2` or
+    ///    `urn:mpeg:mpegB:cicp:systems:auxiliary:depth` in HEIF files. All
+    ///    auxiliary images are decoded until a depth map is found.
+    /// 3. The Google depth map, an image stored as `GDepth:Data` in the XMP
+    ///    metadata, with the encoding given by `GDepth:Format`,
+    ///    `GDepth:Near`, and `GDepth:Far`.
+    ///
+    /// Returns `None` if the image has no depth data. Depth maps from image
+    /// items and auxiliary images are returned in the memory formats
+    /// accepted by the loader, the ones from XMP always as
+    /// [`MemoryFormat::G8`] or [`MemoryFormat::G16`](crate::MemoryFormat::G16).
+    /// The XMP depth map is decoded with the limits and sandbox of this
+    /// loader.
+    ///
+    /// [`MemoryFormat::G8`]: crate::MemoryFormat::G8
+    pub fn depth_map(
+        self,
+    ) -> Pin<Box<dyn Future<Output = Result<Option<DepthMap>, Error>> + Send>> {
+        Box::pin(async move { self.load().await?.depth_map().await })
+    }
+}
+
+impl Image {
+    fn depth_map<'a>(
+        &'a mut self,
+    ) -> Pin<Box<dyn Future<Output = Result<Option<DepthMap>, Error>> + 'a + Send>> {
+        Box::pin(async move {
+            let details = self.details();
+
+            if let Some(item) = details
+                .image_items()
+                .into_iter()
+                .find(|x| x.role == ImageItemRole::Depth)
+            {
+                let frame = self
+                    .specific_frame(FrameRequest::new().image_item(item.id))
+                    .await?;
+
+                return Ok(Some(DepthMap {
+                    frame,
+                    encoding: DepthEncoding::Unknown,
+                    source: DepthMapSource::ImageItem(item.id),
+                }));
+            }
+
+            for index in 0..details.n_auxiliary_images().unwrap_or_default() {
+                let frame = self
+                    .specific_frame(FrameRequest::new().auxiliary_image(index))
+                    .await?;
+
+                if frame
+                    .details()
+                    .auxiliary_type()
+                    .is_some_and(is_depth_auxiliary_type)
+                {
+                    return Ok(Some(DepthMap {
+                        frame,
+                        encoding: DepthEncoding::Unknown,
+                        source: DepthMapSource::AuxiliaryImage(index),
+                    }));
+                }
+            }
+
+            let Some(gdepth) = details.xmp().and_then(crate::xmp::Xmp::gdepth) else {
+                return Ok(None);
+            };
+
+            let encoding = match (gdepth.format, gdepth.near, gdepth.far) {
+                ("RangeLinear", Some(near), Some(far)) => DepthEncoding::RangeLinear { near, far },
+                ("RangeInverse", Some(near), Some(far)) => {
+                    DepthEncoding::RangeInverse { near, far }
+                }
+                _ => DepthEncoding::Unknown,
+            };
+
+            let mut loader = self.loader.embedded(glib::base64_decode(gdepth.data));
+            loader.accepted_memory_formats(MemoryFormatSelection::G8 | MemoryFormatSelection::G16);
+            let frame = loader.load().await?.next_frame().await?;
+
+            Ok(Some(DepthMap {
+                frame,
+                encoding,
+                source: DepthMapSource::Xmp,
+            }))
+        })
+    }
+}
+
+fn is_depth_auxiliary_type(auxiliary_type: &str) -> bool {
+    auxiliary_type == "urn:mpeg:hevc:2015:auxid:2" || auxiliary_type.ends_with(":depth")
+}
@@ -0,0 +1,284 @@
+use std::pin::Pin;
+
+use glycin_common::MemoryFormatInfo;
+
+use crate::{Error, Frame, IccProfileClass, Image, Loader};
+
+/// Severity of a [`Diagnostic`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Unusual but valid, like padded rows
+    Info,
+    /// Part of the image was ignored or recovered, like broken metadata
+    Warning,
+}
+
+/// Non-fatal issue found while loading an image
+///
+/// See [`Loader::load_with_diagnostics`]. Diagnostics have machine-readable
+/// codes of the form `<area>.<issue>`. The following codes exist:
+///
+/// | Code                   | Severity  | Issue |
+/// |------------------------|-----------|-------|
+/// | `image.warning`        | Warning   | Warning reported by the loader, see [`ImageDetails::warnings`](crate::ImageDetails::warnings) |
+/// | `frame.warning`        | Warning   | Warning for the frame, like a recovered truncation or an ignored oversized ICC profile, see [`FrameDetails::warnings`](crate::FrameDetails::warnings) |
+/// | `frame.stride-padding` | Info      | Rows are padded beyond the width of the frame |
+/// | `icc.invalid`          | Warning   | The ICC profile can't be parsed |
+/// | `icc.conflict`         | Warning   | The image and the frame have different ICC profiles, see [`FrameDetails::conflicting_icc_profiles`](crate::FrameDetails::conflicting_icc_profiles) |
+/// | `cicp.invalid`         | Warning   | The CICP values are invalid |
+/// | `exif.invalid`         | Warning   | The Exif data don't have a TIFF header |
+/// | `xmp.invalid`          | Warning   | The XMP data are not well-formed XML |
+///
+/// New codes might be added in the future. Tools should treat unknown codes
+/// according to their severity.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    severity: Severity,
+    code: &'static str,
+    message: String,
+}
+
+impl Diagnostic {
+    fn new(severity: Severity, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    /// Machine-readable code like `"icc.invalid"`
+    pub fn code(&self) -> &str {
+        self.code
+    }
+
+    /// Human-readable description
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.code, self.message)
+    }
+}
+
+impl Loader {
+    /// Load the image and report all non-fatal issues
+    ///
+    /// Loads the image and decodes its first frame. Besides the warnings
+    /// reported by the loader, the metadata and color information are
+    /// checked. An empty list of [`Diagnostic`]s means that the image was
+    /// decoded cleanly. Fatal issues are returned as error instead.
+    ///
+    /// This is meant for tools that validate image libraries. For displaying
+    /// images, [`Loader::load`] avoids the additional checks.
+    pub fn load_with_diagnostics(
+        self,
+    ) -> Pin<Box<dyn Future<Output = Result<(Image, Frame, Vec<Diagnostic>), Error>> + Send>> {
+        Box::pin(async move {
+            let mut image = self.load().await?;
+            let frame = image.next_frame().await?;
+            let diagnostics = diagnostics(&frame);
+
+            Ok((image, frame, diagnostics))
+        })
+    }
+}
+
+fn diagnostics(frame: &Frame) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let image_details = &frame.image_details;
+    let frame_details = frame.details();
+
+    for warning in image_details.warnings() {
+        diagnostics.push(Diagnostic::new(Severity::Warning, "image.warning", warning));
+    }
+
+    for warning in frame_details.warnings() {
+        diagnostics.push(Diagnostic::new(Severity::Warning, "frame.warning", warning));
+    }
+
+    let row_len = u64::from(frame.width) * frame.memory_format.n_bytes().u64();
+    if u64::from(frame.stride) > row_len {
+        diagnostics.push(Diagnostic::new(
+            Severity::Info,
+            "frame.stride-padding",
+            format!(
+                "Stride of {} bytes for rows of {row_len} bytes",
+                frame.stride
+            ),
+        ));
+    }
+
+    if let Some(icc_profile) = frame_details.color_icc_profile()
+        && let Err(err) = IccProfileClass::from_profile(icc_profile)
+    {
+        diagnostics.push(Diagnostic::new(
+            Severity::Warning,
+            "icc.invalid",
+            format!("Invalid ICC profile: {err}"),
+        ));
+    }
+
+    if let Some((image_icc_profile, frame_icc_profile)) = frame_details.conflicting_icc_profiles() {
+        diagnostics.push(Diagnostic::new(
+            Severity::Warning,
+            "icc.conflict",
+            format!(
+                "Using frame ICC profile ({} bytes) instead of image ICC profile ({} bytes)",
+                frame_icc_profile.len(),
+                image_icc_profile.len()
+            ),
+        ));
+    }
+
+    if frame.details.color_cicp.is_some() && frame_details.color_cicp().is_none() {
+        diagnostics.push(Diagnostic::new(
+            Severity::Warning,
+            "cicp.invalid",
+            format!("Invalid CICP values: {:?}", frame.details.color_cicp),
+        ));
+    }
+
+    if let Some(exif) = image_details.metadata_exif()
+        && !crate::exif::is_valid(exif)
+    {
+        diagnostics.push(Diagnostic::new(
+            Severity::Warning,
+            "exif.invalid",
+            "Exif data without TIFF header",
+        ));
+    }
+
+    if let Some(xmp) = image_details.metadata_xmp()
+        && image_details.xmp().is_none()
+    {
+        let message = match std::str::from_utf8(xmp) {
+            Err(err) => format!("XMP data are not valid UTF-8: {err}"),
+            Ok(_) => String::from("XMP data are not well-formed XML"),
+        };
+        diagnostics.push(Diagnostic::new(Severity::Warning, "xmp.invalid", message));
+    }
+
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use glycin_utils::FungibleMemory;
+
+    use super::*;
+    use crate::{ImageDetails, MemoryFormat};
+
+    fn frame(
+        stride: u32,
+        image_details: glycin_utils::ImageDetails<FungibleMemory>,
+        details: glycin_utils::FrameDetails<FungibleMemory>,
+    ) -> Frame {
+        let mut frame = Frame::for_test(
+            2,
+            2,
+            stride,
+            MemoryFormat::R8g8b8,
+            vec![0; stride as usize * 2],
+        );
+        frame.details = Arc::new(details);
+        frame.image_details = ImageDetails::new(Arc::new(image_details), (2, 2));
+        frame
+    }
+
+    fn codes(frame: &Frame) -> Vec<&'static str> {
+        diagnostics(frame).into_iter().map(|x| x.code).collect()
+    }
+
+    #[test]
+    fn clean() {
+        let frame = frame(6, glycin_utils::ImageDetails::new(2, 2), Default::default());
+
+        assert!(diagnostics(&frame).is_empty());
+    }
+
+    #[test]
+    fn representative() {
+        let mut image_details = glycin_utils::ImageDetails::new(2, 2);
+        image_details.warnings = Some(vec![String::from("Unknown chunk")]);
+        image_details.metadata_exif = Some(FungibleMemory::LocalMemory(b"no tiff".to_vec()));
+        image_details.metadata_xmp = Some(FungibleMemory::LocalMemory(vec![0xff, 0xfe]));
+
+        let mut details = glycin_utils::FrameDetails::default();
+        details.warnings = Some(vec![String::from("Truncated animation")]);
+        details.color_icc_profile = Some(FungibleMemory::LocalMemory(b"broken".to_vec()));
+
+        let frame = frame(8, image_details, details);
+
+        assert_eq!(
+            codes(&frame),
+            [
+                "image.warning",
+                "frame.warning",
+                "frame.stride-padding",
+                "icc.invalid",
+                "exif.invalid",
+                "xmp.invalid",
+            ]
+        );
+
+        let diagnostics = diagnostics(&frame);
+        assert_eq!(diagnostics[0].message(), "Unknown chunk");
+        assert_eq!(diagnostics[2].severity(), Severity::Info);
+        assert_eq!(diagnostics[3].severity(), Severity::Warning);
+    }
+
+    #[test]
+    fn xmp() {
+        let frame_with_xmp = |xmp: &[u8]| {
+            let mut image_details = glycin_utils::ImageDetails::new(2, 2);
+            image_details.metadata_xmp = Some(FungibleMemory::LocalMemory(xmp.to_vec()));
+            frame(6, image_details, Default::default())
+        };
+
+        let valid = frame_with_xmp(br#"<x:xmpmeta xmlns:x="adobe:ns:meta/"/>"#);
+        assert!(diagnostics(&valid).is_empty());
+
+        let unclosed = frame_with_xmp(br#"<x:xmpmeta xmlns:x="adobe:ns:meta/">"#);
+        let diagnostics = diagnostics(&unclosed);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code(), "xmp.invalid");
+        assert_eq!(diagnostics[0].message(), "XMP data are not well-formed XML");
+    }
+
+    #[test]
+    fn conflicting_icc_profiles() {
+        let image_details = || {
+            let mut image_details = glycin_utils::ImageDetails::new(2, 2);
+            image_details.color_icc_profile = Some(FungibleMemory::LocalMemory(b"image".to_vec()));
+            image_details
+        };
+
+        let mut details = glycin_utils::FrameDetails::default();
+        details.color_icc_profile = Some(FungibleMemory::LocalMemory(b"frame".to_vec()));
+
+        let conflicting = frame(6, image_details(), details);
+        assert_eq!(
+            conflicting.details().conflicting_icc_profiles(),
+            Some((&b"image"[..], &b"frame"[..]))
+        );
+        assert_eq!(codes(&conflicting), ["icc.invalid", "icc.conflict"]);
+
+        // Identical profiles don't conflict
+        let mut details = glycin_utils::FrameDetails::default();
+        details.color_icc_profile = Some(FungibleMemory::LocalMemory(b"image".to_vec()));
+
+        let identical = frame(6, image_details(), details);
+        assert_eq!(identical.details().conflicting_icc_profiles(), None);
+        assert_eq!(codes(&identical), ["icc.invalid"]);
+    }
+}
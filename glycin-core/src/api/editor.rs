@@ -8,9 +8,11 @@ use gio::glib;
 use gio::prelude::{IsA, *};
 #[cfg(feature = "builtin")]
 use glycin_utils::EditorImplementation;
+use glycin_utils::editing::EditingFrame;
 use glycin_utils::safe_math::SafeConversion;
 use glycin_utils::{
     ByteChanges, ByteData, CompleteEditorOutput, FungibleMemory, Operations, SparseEditorOutput,
+    SparseOperations,
 };
 #[cfg(feature = "external")]
 use zbus::zvariant::OwnedObjectPath;
@@ -18,25 +20,39 @@ use zbus::zvariant::OwnedObjectPath;
 use crate::api::*;
 #[cfg(feature = "external")]
 use crate::dbus::EditorProxy;
-use crate::error::ResultExt;
+use crate::error::{ResultExt, Stage};
 use crate::main_context::{MainContextSelector, ProvidesMainContext};
 #[cfg(feature = "external")]
 use crate::pool::PooledProcess;
 use crate::util::{self, CancellableFuture, ShortcutErrorFuture};
-use crate::{Error, ErrorKind, MimeType, Pool, config};
+use crate::{ColorState, Creator, Error, ErrorKind, Frame, MimeType, Pool, config};
 
 /// Image edit builder
+///
+/// Animated PNG and WebP images are edited frame by frame. Frame delays and
+/// the loop count are kept. Animated PNGs with 16 bit per channel and other
+/// animated formats, like GIF, can't be edited and return an error instead of
+/// being flattened to a single frame or reduced to 8 bit.
 #[derive(Debug)]
 pub struct Editor {
-    source: Source,
+    input: EditorInput,
     pool: Arc<Pool>,
     cancellable: gio::Cancellable,
     pub(crate) sandbox_selector: SandboxSelector,
+    sandbox_wrapper: Option<SandboxWrapper>,
     pub(crate) main_context_selector: MainContextSelector,
+    encoding_options: glycin_utils::EncodingOptions,
 }
 
 static_assertions::assert_impl_all!(Editor: Send, Sync);
 
+#[derive(Debug)]
+enum EditorInput {
+    Source(Source),
+    /// Already decoded frame and the format to encode it in
+    Frame(Frame, MimeType),
+}
+
 impl Editor {
     /// Create an editor with a [`gio::File`] as source
     pub fn new(file: gio::File) -> Self {
@@ -64,13 +80,43 @@ impl Editor {
         Self::new_bytes(bytes)
     }
 
+    /// Create an editor that operates on an already decoded [`Frame`]
+    ///
+    /// The operations are applied directly to the pixel data of the frame
+    /// and the result is encoded as `mime_type`. This avoids decoding the
+    /// image a second time if it has already been loaded, for example to
+    /// display it.
+    ///
+    /// Since there is no original file, the result is always a complete
+    /// re-encode. [`EditableImage::apply_sparse`] always returns
+    /// [`SparseEdit::Complete`] and [`Edit::is_lossless`] is always `false`.
+    /// Metadata of the original file, like Exif or XMP, are not kept, and
+    /// [`Editor::embed_thumbnail`] has no effect. Prefer [`Editor::new`] with
+    /// the original file if the edit should be lossless where possible.
+    ///
+    /// Fails with [`ErrorKind::PlanarFrame`] for
+    /// [planar](crate::Loader::planar) frames.
+    pub fn from_frame(frame: Frame, mime_type: MimeType) -> Result<Self, Error> {
+        if frame.planes().is_some() {
+            return Err(ErrorKind::PlanarFrame.err());
+        }
+
+        Ok(Self::new_input(EditorInput::Frame(frame, mime_type)))
+    }
+
     pub(crate) fn new_source(source: Source) -> Self {
+        Self::new_input(EditorInput::Source(source))
+    }
+
+    fn new_input(input: EditorInput) -> Self {
         Self {
-            source,
+            input,
             pool: Pool::global(),
             cancellable: gio::Cancellable::new(),
             sandbox_selector: SandboxSelector::default(),
+            sandbox_wrapper: None,
             main_context_selector: MainContextSelector::Auto,
+            encoding_options: glycin_utils::EncodingOptions::default(),
         }
     }
 
@@ -84,20 +130,49 @@ impl Editor {
             let main_context = self.main_context();
             let cancellable = self.cancellable.clone();
 
-            let f = || async move { self.edit_internal().await }.make_cancellable(cancellable);
+            let f = || {
+                async move { self.edit_internal().await.stage(Stage::Editor) }
+                    .make_cancellable(cancellable)
+            };
 
             main_context.spawn_from_within(f).await?
         })
     }
 
     async fn edit_internal(mut self) -> Result<EditableImage, Error> {
-        let source: Source = self.source.send();
+        let source: Source = match &mut self.input {
+            EditorInput::Source(source) => source.send(),
+            EditorInput::Frame(frame, mime_type) => {
+                let image_editor = ImageEditor::Frame(frame.clone(), mime_type.clone());
+                let mime_type = mime_type.clone();
 
-        let editor_context = ProcessorContext::new(source, false, &self.sandbox_selector).await?;
+                return Ok(EditableImage {
+                    editor: self,
+                    image_editor,
+                    _mime_type: mime_type,
+                });
+            }
+        };
+
+        let mut editor_context = ProcessorContext::new(
+            source,
+            false,
+            &[],
+            None,
+            &self.sandbox_selector,
+            None,
+            crate::source::BUF_SIZE,
+        )
+        .await?;
+
+        if let Some(sandbox_wrapper) = &self.sandbox_wrapper {
+            editor_context.set_sandbox_wrapper(sandbox_wrapper.clone());
+        }
 
         let editor = editor_context
             .editor(self.pool.clone(), &self.cancellable)
-            .await?;
+            .await
+            .stage(Stage::Spawn)?;
 
         match editor {
             #[cfg(feature = "external")]
@@ -187,11 +262,49 @@ impl Editor {
         self
     }
 
+    /// Start editors via a custom sandbox
+    ///
+    /// Same as [`Loader::sandbox_wrapper`](crate::Loader::sandbox_wrapper).
+    /// The option has no effect for builtin editors and for editors created
+    /// via [`Editor::from_frame`].
+    pub fn sandbox_wrapper(&mut self, sandbox_wrapper: SandboxWrapper) -> &mut Self {
+        self.sandbox_wrapper = Some(sandbox_wrapper);
+        self
+    }
+
     /// Set [`Cancellable`](gio::Cancellable) to cancel any editing operations.
     pub fn cancellable(&mut self, cancellable: impl IsA<gio::Cancellable>) -> &mut Self {
         self.cancellable = cancellable.upcast();
         self
     }
+
+    /// Embed a thumbnail into images written by
+    /// [`EditableImage::apply_complete`]
+    ///
+    /// The thumbnail is scaled to fit into `max_edge` times `max_edge`
+    /// pixels. Photo managers and file browsers can use it as a fast preview.
+    ///
+    /// Currently, only JPEG supports embedded thumbnails. The thumbnail is
+    /// stored as a JPEG with quality 75 in the Exif data. Since the Exif data
+    /// have to fit into a single JPEG segment, the thumbnail is omitted if it
+    /// would exceed 64 KiB. Use [`Edit::has_embedded_thumbnail`] to check
+    /// whether a thumbnail was embedded.
+    pub fn embed_thumbnail(&mut self, max_edge: u32) -> &mut Self {
+        self.encoding_options.embed_thumbnail = Some(max_edge);
+        self
+    }
+
+    /// Set bits per channel of images that are re-encoded
+    ///
+    /// Currently, only PNG supports this for 8 and 16 bits. For example, a
+    /// 16-bit PNG can be saved with 8 bits for the web. When reducing the
+    /// depth, ordered dithering is applied and the edit is not lossless. For
+    /// other formats and depths, the depth of the image is kept. Use
+    /// [`Edit::bit_depth`] to check the depth of the output.
+    pub fn bit_depth(&mut self, bit_depth: u8) -> &mut Self {
+        self.encoding_options.bit_depth = Some(bit_depth);
+        self
+    }
 }
 
 #[derive(Debug)]
@@ -225,7 +338,11 @@ impl EditableImage {
         operations: &Operations,
     ) -> Pin<Box<dyn Future<Output = Result<SparseEdit, Error>> + Send>> {
         let operations = operations.to_owned();
-        Box::pin(self.apply_sparse_internal(operations))
+        Box::pin(async move {
+            self.apply_sparse_internal(operations)
+                .await
+                .stage(Stage::Editor)
+        })
     }
 
     async fn apply_sparse_internal(self, operations: Operations) -> Result<SparseEdit, Error> {
@@ -235,7 +352,7 @@ impl EditableImage {
                 let process = editor.process.use_();
 
                 let mut editor_output = process
-                    .editor_apply_sparse(&operations, &self)
+                    .editor_apply_sparse(&operations, self.editor.encoding_options.clone(), &self)
                     .await
                     .err_context(&process)?;
 
@@ -246,17 +363,22 @@ impl EditableImage {
             #[cfg(feature = "builtin")]
             ImageEditor::Builtin(editor) => {
                 let editor_function: Box<dyn FnOnce() -> _ + Send>;
+                let encoding_options = self.editor.encoding_options.clone();
 
                 match editor {
                     #[cfg(feature = "builtin-image-rs")]
                     ImageEditorBuiltin::ImageRs(editor) => {
                         let editor = editor.clone();
-                        editor_function = Box::new(move || editor.apply_sparse(operations));
+                        editor_function = Box::new(move || {
+                            editor.apply_sparse_with_options(operations, encoding_options)
+                        });
                     }
                     #[cfg(feature = "builtin-test")]
                     ImageEditorBuiltin::Test(editor) => {
                         let editor = editor.clone();
-                        editor_function = Box::new(move || editor.apply_sparse(operations));
+                        editor_function = Box::new(move || {
+                            editor.apply_sparse_with_options(operations, encoding_options)
+                        });
                     }
                 }
 
@@ -268,6 +390,72 @@ impl EditableImage {
 
                 SparseEdit::try_from(editor_output)
             }
+            ImageEditor::Frame(..) => {
+                let edit = self.apply_complete_internal(operations).await?;
+                Ok(SparseEdit::Complete(edit.inner.data))
+            }
+        }
+    }
+
+    /// Split operations into a sparse and a complete part
+    ///
+    /// The editor reports which operations it can apply as byte changes, like
+    /// a rotation of a JPEG with Exif data. See [`SparseSplit`] for how the
+    /// operations are divided. Unlike [`apply_sparse`](Self::apply_sparse),
+    /// this doesn't apply any operation.
+    pub fn split_sparse(
+        &self,
+        operations: &Operations,
+    ) -> Pin<Box<dyn Future<Output = Result<SparseSplit, Error>> + Send + '_>> {
+        let operations = operations.to_owned();
+
+        Box::pin(async move {
+            let sparse_operations = self
+                .sparse_operations_internal(operations.clone())
+                .await
+                .stage(Stage::Editor)?;
+            Ok(SparseSplit::new(&operations, &sparse_operations))
+        })
+    }
+
+    async fn sparse_operations_internal(
+        &self,
+        operations: Operations,
+    ) -> Result<SparseOperations, Error> {
+        match &self.image_editor {
+            #[cfg(feature = "external")]
+            ImageEditor::External(editor) => {
+                let process = editor.process.use_();
+
+                process
+                    .editor_sparse_operations(&operations, self)
+                    .await
+                    .err_context(&process)
+            }
+            #[cfg(feature = "builtin")]
+            ImageEditor::Builtin(editor) => {
+                let editor_function: Box<dyn FnOnce() -> _ + Send>;
+
+                match editor {
+                    #[cfg(feature = "builtin-image-rs")]
+                    ImageEditorBuiltin::ImageRs(editor) => {
+                        let editor = editor.clone();
+                        editor_function = Box::new(move || editor.sparse_operations(operations));
+                    }
+                    #[cfg(feature = "builtin-test")]
+                    ImageEditorBuiltin::Test(editor) => {
+                        let editor = editor.clone();
+                        editor_function = Box::new(move || editor.sparse_operations(operations));
+                    }
+                }
+
+                gio::spawn_blocking(|| {
+                    editor_function().map_err(|e| Error::from(e.into_editor_error()))
+                })
+                .await
+                .map_err(|e| ErrorKind::panic(e))?
+            }
+            ImageEditor::Frame(..) => Ok(SparseOperations::default()),
         }
     }
 
@@ -278,7 +466,11 @@ impl EditableImage {
     ) -> Pin<Box<dyn Future<Output = Result<Edit, Error>> + Send + '_>> {
         let operations = operations.to_owned();
 
-        Box::pin(self.apply_complete_internal(operations))
+        Box::pin(async move {
+            self.apply_complete_internal(operations)
+                .await
+                .stage(Stage::Editor)
+        })
     }
 
     async fn apply_complete_internal(&self, operations: Operations) -> Result<Edit, Error> {
@@ -288,7 +480,7 @@ impl EditableImage {
                 let process = editor.process.use_();
 
                 let mut editor_output = process
-                    .editor_apply_complete(&operations, self)
+                    .editor_apply_complete(&operations, self.editor.encoding_options.clone(), self)
                     .await
                     .err_context(&process)?
                     .into_fungible();
@@ -302,17 +494,22 @@ impl EditableImage {
             #[cfg(feature = "builtin")]
             ImageEditor::Builtin(editor) => {
                 let apply_function: Box<dyn FnOnce() -> _ + Send + 'static>;
+                let encoding_options = self.editor.encoding_options.clone();
 
                 match editor {
                     #[cfg(feature = "builtin-image-rs")]
                     ImageEditorBuiltin::ImageRs(editor) => {
                         let editor = editor.clone();
-                        apply_function = Box::new(move || editor.apply_complete(operations));
+                        apply_function = Box::new(move || {
+                            editor.apply_complete_with_options(operations, encoding_options)
+                        });
                     }
                     #[cfg(feature = "builtin-test")]
                     ImageEditorBuiltin::Test(editor) => {
                         let editor = editor.clone();
-                        apply_function = Box::new(move || editor.apply_complete(operations));
+                        apply_function = Box::new(move || {
+                            editor.apply_complete_with_options(operations, encoding_options)
+                        });
                     }
                 }
 
@@ -326,6 +523,43 @@ impl EditableImage {
                     inner: editor_output,
                 })
             }
+            ImageEditor::Frame(frame, mime_type) => {
+                let editing_frame = EditingFrame {
+                    width: frame.width(),
+                    height: frame.height(),
+                    stride: frame.stride(),
+                    memory_format: frame.memory_format().into(),
+                    texture: FungibleMemory::from_vec(frame.buf_slice().to_vec()),
+                };
+
+                let edited_frame = util::spawn_blocking(move || {
+                    glycin_utils::editing::apply_operations(editing_frame, &operations)
+                })
+                .await??;
+
+                let mut creator = Creator::new(mime_type.clone()).await?;
+                creator
+                    .sandbox_selector(self.editor.sandbox_selector)
+                    .cancellable(self.editor.cancellable.clone());
+
+                // Editing operations don't change the memory format
+                let new_frame = creator.add_frame_with_stride(
+                    edited_frame.width,
+                    edited_frame.height,
+                    edited_frame.stride,
+                    frame.memory_format(),
+                    edited_frame.texture.to_vec(),
+                )?;
+                if let ColorState::Cicp(cicp) = frame.color_state() {
+                    new_frame.set_color_cicp(*cicp);
+                }
+
+                let encoded_image = creator.create().await?;
+
+                Ok(Edit {
+                    inner: CompleteEditorOutput::new(encoded_image.inner.data),
+                })
+            }
         }
     }
 
@@ -352,6 +586,8 @@ enum ImageEditor {
     External(ImageEditorExternal),
     #[cfg(feature = "builtin")]
     Builtin(ImageEditorBuiltin),
+    /// Editing of an already decoded frame
+    Frame(Frame, MimeType),
 }
 
 #[cfg(feature = "external")]
@@ -392,6 +628,40 @@ pub enum SparseEdit {
     Complete(FungibleMemory),
 }
 
+/// Operations split by whether they can be applied sparsely
+///
+/// Returned by [`EditableImage::split_sparse`]. The `sparse` operations are
+/// the leading operations that the editor can apply as byte changes.
+/// Starting with the first operation that requires re-encoding the image,
+/// all operations are part of `complete`, such that the order of operations
+/// is kept.
+///
+/// Applying `sparse` via [`EditableImage::apply_sparse`] and afterwards
+/// `complete` to the result gives the same image as applying all operations.
+/// If `complete` is empty, the complete edit is sparse. If `sparse` is
+/// empty, the image has to be re-encoded.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct SparseSplit {
+    pub sparse: Operations,
+    pub complete: Operations,
+}
+
+impl SparseSplit {
+    fn new(operations: &Operations, sparse_operations: &SparseOperations) -> Self {
+        let n_sparse = (0..operations.operations().len())
+            .take_while(|i| u32::try_from(*i).is_ok_and(|i| sparse_operations.sparse.contains(&i)))
+            .count();
+
+        let (sparse, complete) = operations.operations().split_at(n_sparse);
+
+        Self {
+            sparse: Operations::new(sparse.to_vec()),
+            complete: Operations::new(complete.to_vec()),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Edit {
     inner: CompleteEditorOutput<FungibleMemory>,
@@ -405,6 +675,20 @@ impl Edit {
     pub fn is_lossless(&self) -> bool {
         self.inner.info.lossless
     }
+
+    /// Whether a thumbnail was embedded
+    ///
+    /// See [`Editor::embed_thumbnail`].
+    pub fn has_embedded_thumbnail(&self) -> bool {
+        self.inner.info.thumbnail_embedded
+    }
+
+    /// Bits per channel of the output, if reported by the editor
+    ///
+    /// See [`Editor::bit_depth`].
+    pub fn bit_depth(&self) -> Option<u8> {
+        self.inner.info.bit_depth
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -476,3 +760,47 @@ impl TryFrom<SparseEditorOutput<FungibleMemory>> for SparseEdit {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use glycin_utils::Operation;
+    use gufo_common::orientation::Rotation;
+
+    use super::*;
+
+    #[test]
+    fn sparse_split_mixed() {
+        let clip = Operation::Clip((0, 0, 10, 10));
+        let rotate = Operation::Rotate(Rotation::_90);
+        let operations = Operations::new(vec![
+            rotate.clone(),
+            Operation::MirrorHorizontally,
+            clip.clone(),
+            rotate.clone(),
+        ]);
+
+        // The last rotation has to be applied after the clip
+        let split = SparseSplit::new(&operations, &SparseOperations::new(vec![0, 1, 3]));
+        assert_eq!(
+            split.sparse,
+            Operations::new(vec![rotate.clone(), Operation::MirrorHorizontally])
+        );
+        assert_eq!(split.complete, Operations::new(vec![clip, rotate]));
+    }
+
+    #[test]
+    fn sparse_split_all_or_nothing() {
+        let operations = Operations::new(vec![
+            Operation::Rotate(Rotation::_180),
+            Operation::MirrorVertically,
+        ]);
+
+        let split = SparseSplit::new(&operations, &SparseOperations::new(vec![0, 1]));
+        assert_eq!(split.sparse, operations);
+        assert!(split.complete.operations().is_empty());
+
+        let split = SparseSplit::new(&operations, &SparseOperations::default());
+        assert!(split.sparse.operations().is_empty());
+        assert_eq!(split.complete, operations);
+    }
+}
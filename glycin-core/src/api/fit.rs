@@ -0,0 +1,176 @@
+use gufo_common::orientation::Rotation;
+
+/// How an image is fitted into target dimensions
+///
+/// Used with [`Loader::fit`](crate::Loader::fit). For an image of
+/// `width` × `height` and a target of `target_width` × `target_height`, the
+/// modes compute the following frame request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FitMode {
+    /// Fill the target and cut off what is outside
+    ///
+    /// The image is scaled by `max(target_width / width, target_height /
+    /// height)`, keeping the aspect ratio. At least one of the scaled
+    /// dimensions exceeds the target. The excess is removed by a clip that is
+    /// centered in the scaled image, resulting in exactly the target
+    /// dimensions.
+    Cover,
+    /// Show the complete image within the target
+    ///
+    /// The image is scaled by `min(target_width / width, target_height /
+    /// height)`, keeping the aspect ratio. One of the resulting dimensions
+    /// equals the target, the other one can be smaller. No clip is applied.
+    Contain,
+    /// Stretch the image to the target
+    ///
+    /// The image is scaled to exactly the target dimensions, ignoring the
+    /// aspect ratio. No clip is applied.
+    Fill,
+}
+
+/// Scale and clip of a frame request
+pub(crate) type FitRequest = ((u32, u32), Option<(u32, u32, u32, u32)>);
+
+impl FitMode {
+    /// Scale and clip for fitting an image of `dimensions` into `target`
+    ///
+    /// The clip refers to the scaled dimensions. All dimensions are at least
+    /// one pixel.
+    pub(crate) fn frame_request(self, dimensions: (u32, u32), target: (u32, u32)) -> FitRequest {
+        let (width, height) = (dimensions.0.max(1), dimensions.1.max(1));
+        let (target_width, target_height) = (target.0.max(1), target.1.max(1));
+
+        let factor_x = target_width as f64 / width as f64;
+        let factor_y = target_height as f64 / height as f64;
+
+        let scaled = |factor: f64| {
+            (
+                scaled_dimension(width, factor),
+                scaled_dimension(height, factor),
+            )
+        };
+
+        match self {
+            Self::Fill => ((target_width, target_height), None),
+            Self::Contain => {
+                let (scaled_width, scaled_height) = scaled(factor_x.min(factor_y));
+                (
+                    (
+                        scaled_width.min(target_width),
+                        scaled_height.min(target_height),
+                    ),
+                    None,
+                )
+            }
+            Self::Cover => {
+                let (scaled_width, scaled_height) = scaled(factor_x.max(factor_y));
+                let scaled_width = scaled_width.max(target_width);
+                let scaled_height = scaled_height.max(target_height);
+
+                let x = (scaled_width - target_width) / 2;
+                let y = (scaled_height - target_height) / 2;
+
+                (
+                    (scaled_width, scaled_height),
+                    Some((x, y, target_width, target_height)),
+                )
+            }
+        }
+    }
+
+    /// Like [`frame_request`](Self::frame_request) for a `target` that
+    /// refers to the image after `rotation` is applied
+    ///
+    /// Loaders scale and clip the image before it is rotated. For rotations
+    /// by 90° or 270°, the target is therefore swapped.
+    pub(crate) fn oriented_frame_request(
+        self,
+        raw_dimensions: (u32, u32),
+        target: (u32, u32),
+        rotation: Rotation,
+    ) -> FitRequest {
+        let target = match rotation {
+            Rotation::_90 | Rotation::_270 => (target.1, target.0),
+            _ => target,
+        };
+
+        self.frame_request(raw_dimensions, target)
+    }
+}
+
+fn scaled_dimension(dimension: u32, factor: f64) -> u32 {
+    (dimension as f64 * factor)
+        .round()
+        .clamp(1., u32::MAX as f64) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cover() {
+        assert_eq!(
+            FitMode::Cover.frame_request((400, 200), (80, 24)),
+            ((80, 40), Some((0, 8, 80, 24)))
+        );
+        assert_eq!(
+            FitMode::Cover.frame_request((200, 400), (80, 24)),
+            ((80, 160), Some((0, 68, 80, 24)))
+        );
+        assert_eq!(
+            FitMode::Cover.frame_request((100, 100), (30, 60)),
+            ((60, 60), Some((15, 0, 30, 60)))
+        );
+    }
+
+    #[test]
+    fn contain() {
+        assert_eq!(
+            FitMode::Contain.frame_request((400, 200), (80, 24)),
+            ((48, 24), None)
+        );
+        assert_eq!(
+            FitMode::Contain.frame_request((400, 200), (80, 80)),
+            ((80, 40), None)
+        );
+        assert_eq!(
+            FitMode::Contain.frame_request((10_000, 1), (80, 24)),
+            ((80, 1), None)
+        );
+    }
+
+    #[test]
+    fn rotated() {
+        // Image of 400 × 200 that is displayed as 200 × 400
+        assert_eq!(
+            FitMode::Contain.oriented_frame_request((400, 200), (24, 80), Rotation::_90),
+            ((48, 24), None)
+        );
+        assert_eq!(
+            FitMode::Cover.oriented_frame_request((400, 200), (24, 80), Rotation::_270),
+            ((80, 40), Some((0, 8, 80, 24)))
+        );
+        assert_eq!(
+            FitMode::Fill.oriented_frame_request((400, 200), (24, 80), Rotation::_90),
+            ((80, 24), None)
+        );
+        assert_eq!(
+            FitMode::Fill.oriented_frame_request((400, 200), (24, 80), Rotation::_180),
+            ((24, 80), None)
+        );
+    }
+
+    #[test]
+    fn fill() {
+        assert_eq!(
+            FitMode::Fill.frame_request((400, 200), (80, 24)),
+            ((80, 24), None)
+        );
+        assert_eq!(
+            FitMode::Fill.frame_request((0, 0), (0, 24)),
+            ((1, 24), None)
+        );
+    }
+}
@@ -0,0 +1,88 @@
+use std::collections::BTreeSet;
+
+use glycin_common::OperationId;
+
+use crate::MimeType;
+use crate::config::Config;
+
+/// Support for an image format by the installed loaders and editors
+///
+/// Returned by [`format_support`].
+#[derive(Debug, Clone)]
+pub struct FormatSupport {
+    mime_type: MimeType,
+    loader: bool,
+    editor: bool,
+    creator: bool,
+    animation: bool,
+    metadata: bool,
+    operations: BTreeSet<OperationId>,
+}
+
+impl FormatSupport {
+    pub fn mime_type(&self) -> &MimeType {
+        &self.mime_type
+    }
+
+    /// A loader is installed for this format
+    pub fn has_loader(&self) -> bool {
+        self.loader
+    }
+
+    /// An editor is installed for this format
+    pub fn has_editor(&self) -> bool {
+        self.editor
+    }
+
+    /// New images can be created in this format
+    pub fn has_creator(&self) -> bool {
+        self.creator
+    }
+
+    /// The loader advertises support for animations
+    pub fn animation(&self) -> bool {
+        self.animation
+    }
+
+    /// The loader advertises support for reading metadata like Exif or XMP
+    pub fn metadata(&self) -> bool {
+        self.metadata
+    }
+
+    /// Editing operations supported by the editor
+    pub fn operations(&self) -> &BTreeSet<OperationId> {
+        &self.operations
+    }
+}
+
+/// Lists all formats with an installed loader or editor
+///
+/// The capabilities are taken from the static loader and editor configs. No
+/// loader process is started to obtain this information.
+pub async fn format_support() -> Vec<FormatSupport> {
+    let config = Config::cached().await;
+
+    let mime_types = config
+        .image_loader
+        .keys()
+        .chain(config.image_editor.keys())
+        .collect::<BTreeSet<_>>();
+
+    mime_types
+        .into_iter()
+        .map(|mime_type| {
+            let loader = config.image_loader.get(mime_type);
+            let editor = config.image_editor.get(mime_type);
+
+            FormatSupport {
+                mime_type: mime_type.clone(),
+                loader: loader.is_some(),
+                editor: editor.is_some(),
+                creator: editor.is_some_and(|x| x.creator),
+                animation: loader.is_some_and(|x| x.animation),
+                metadata: loader.is_some_and(|x| x.metadata),
+                operations: editor.map(|x| x.operations.clone()).unwrap_or_default(),
+            }
+        })
+        .collect()
+}
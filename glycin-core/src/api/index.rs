@@ -0,0 +1,348 @@
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use futures_util::{Stream, StreamExt};
+use gio::glib;
+use gio::prelude::*;
+
+use super::loader::{CancelOnDrop, ForwardCancellation};
+use crate::{Error, ImageDetails, Limits, Loader, SandboxSelector, util};
+
+/// Options for [`Loader::index_library`]
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct IndexOptions {
+    concurrency: usize,
+    perceptual_hash: bool,
+    limits: Limits,
+    sandbox_selector: SandboxSelector,
+    cancellable: Option<gio::Cancellable>,
+}
+
+impl Default for IndexOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 4,
+            perceptual_hash: false,
+            limits: Limits::default(),
+            sandbox_selector: SandboxSelector::default(),
+            cancellable: None,
+        }
+    }
+}
+
+impl IndexOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maximum number of images that are loaded at the same time
+    ///
+    /// Defaults to `4`. A value of `0` is treated like `1`.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Compute the perceptual hash of each image
+    ///
+    /// This requires decoding the first frame of each image, which is
+    /// considerably slower than only reading the metadata. See
+    /// [`Frame::perceptual_hash`](crate::Frame::perceptual_hash). Defaults to
+    /// `false`.
+    pub fn perceptual_hash(mut self, perceptual_hash: bool) -> Self {
+        self.perceptual_hash = perceptual_hash;
+        self
+    }
+
+    /// Limits for loading each image
+    ///
+    /// See [`Loader::limits`].
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Method by which the sandbox mechanism is selected for each image
+    ///
+    /// See [`Loader::sandbox_selector`]. Defaults to [`SandboxSelector::Auto`].
+    pub fn sandbox_selector(mut self, sandbox_selector: SandboxSelector) -> Self {
+        self.sandbox_selector = sandbox_selector;
+        self
+    }
+
+    /// Cancellable to stop indexing
+    ///
+    /// Once cancelled, no further files are loaded. Images that are being
+    /// loaded at that moment are returned with [`ErrorKind::Canceled`](crate::ErrorKind::Canceled).
+    pub fn cancellable(mut self, cancellable: impl IsA<gio::Cancellable>) -> Self {
+        self.cancellable = Some(cancellable.upcast());
+        self
+    }
+}
+
+/// Result for one file returned by [`Loader::index_library`]
+#[derive(Debug)]
+pub struct IndexEntry {
+    file: gio::File,
+    result: Result<IndexedImage, Error>,
+    n_done: usize,
+    n_total: usize,
+}
+
+impl IndexEntry {
+    /// The image file
+    ///
+    /// For directories that can't be read, this is the directory.
+    pub fn file(&self) -> &gio::File {
+        &self.file
+    }
+
+    pub fn result(&self) -> Result<&IndexedImage, &Error> {
+        self.result.as_ref()
+    }
+
+    pub fn into_result(self) -> Result<IndexedImage, Error> {
+        self.result
+    }
+
+    /// Number of files that have been processed, including this one
+    ///
+    /// Skipped files are counted as well. Therefore, the value can increase
+    /// by more than one between entries.
+    pub fn n_done(&self) -> usize {
+        self.n_done
+    }
+
+    /// Number of files that have been found so far
+    ///
+    /// Since files are loaded while `root` is still being walked, the value
+    /// grows until the walk has finished. It's never smaller than
+    /// [`IndexEntry::n_done`].
+    pub fn n_total(&self) -> usize {
+        self.n_total
+    }
+
+    /// Fraction of processed files between `0.` and `1.`
+    ///
+    /// Relative to the files found so far, see [`IndexEntry::n_total`].
+    pub fn progress(&self) -> f64 {
+        if self.n_total == 0 {
+            1.
+        } else {
+            self.n_done as f64 / self.n_total as f64
+        }
+    }
+}
+
+/// Metadata of an image in the library
+#[derive(Debug, Clone)]
+pub struct IndexedImage {
+    details: ImageDetails,
+    capture_time: Option<glib::DateTime>,
+    perceptual_hash: Option<u64>,
+}
+
+impl IndexedImage {
+    pub fn details(&self) -> &ImageDetails {
+        &self.details
+    }
+
+    pub fn width(&self) -> u32 {
+        self.details.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.details.height()
+    }
+
+    /// See [`ImageDetails::capture_time`]
+    pub fn capture_time(&self) -> Option<&glib::DateTime> {
+        self.capture_time.as_ref()
+    }
+
+    /// Only available if enabled via [`IndexOptions::perceptual_hash`]
+    pub fn perceptual_hash(&self) -> Option<u64> {
+        self.perceptual_hash
+    }
+}
+
+impl Loader {
+    /// Index all images in a directory and its subdirectories
+    ///
+    /// `root` is walked recursively in a separate thread. Symbolic links are
+    /// not followed. Files that are not images according to their content
+    /// type are skipped. While the walk is still in progress, the image
+    /// details of the found files are loaded with the concurrency set in
+    /// `options`. No frames are decoded unless
+    /// [`IndexOptions::perceptual_hash`] is enabled.
+    ///
+    /// The stream returns one [`IndexEntry`] per image in the order in which
+    /// the loads finish, not in the order of the directory tree. Files for
+    /// which no loader is available are skipped. Other errors, including
+    /// directories that can't be read, are returned as entries and don't stop
+    /// indexing. Each entry reports the progress via
+    /// [`IndexEntry::n_done`] and [`IndexEntry::n_total`]. The stream ends
+    /// once all files have been processed.
+    ///
+    /// Dropping the stream cancels all loads in progress like cancelling the
+    /// [`IndexOptions::cancellable`].
+    pub fn index_library(
+        root: gio::File,
+        options: IndexOptions,
+    ) -> Pin<Box<dyn Stream<Item = IndexEntry> + Send>> {
+        let IndexOptions {
+            concurrency,
+            perceptual_hash,
+            limits,
+            sandbox_selector,
+            cancellable: options_cancellable,
+        } = options;
+
+        let cancellable = gio::Cancellable::new();
+        let cancel_on_drop = CancelOnDrop(cancellable.clone());
+        let forward_cancellation = options_cancellable.map(|options_cancellable| {
+            ForwardCancellation::new(&options_cancellable, &cancellable)
+        });
+
+        let n_found = Arc::new(AtomicUsize::new(0));
+        let mut n_done = 0;
+
+        let stream = walk(root, cancellable.clone(), n_found.clone())
+            .map(move |entry| {
+                let cancellable = cancellable.clone();
+                let limits = limits.clone();
+
+                async move {
+                    let file = match entry {
+                        Ok(file) => file,
+                        Err((dir, err)) => return Some((dir, Err(err))),
+                    };
+
+                    // Skip queued files after cancellation
+                    if cancellable.is_cancelled() {
+                        return None;
+                    }
+
+                    let mut loader = Loader::new(file.clone());
+                    loader
+                        .cancellable(cancellable)
+                        .limits(limits)
+                        .sandbox_selector(sandbox_selector);
+
+                    match index_image(loader, perceptual_hash).await {
+                        Err(err) if err.unsupported_format().is_some() => None,
+                        result => Some((file, result)),
+                    }
+                }
+            })
+            .buffer_unordered(concurrency)
+            .filter_map(move |result| {
+                // Keep guards alive as long as the stream exists
+                let _ = (&cancel_on_drop, &forward_cancellation);
+                n_done += 1;
+
+                std::future::ready(result.map(|(file, result)| IndexEntry {
+                    file,
+                    result,
+                    n_done,
+                    n_total: n_found.load(Ordering::Relaxed),
+                }))
+            });
+
+        Box::pin(stream)
+    }
+}
+
+async fn index_image(loader: Loader, perceptual_hash: bool) -> Result<IndexedImage, Error> {
+    let mut image = loader.load().await?;
+    let details = image.details();
+
+    let perceptual_hash = if perceptual_hash {
+        Some(image.perceptual_hash().await?)
+    } else {
+        None
+    };
+
+    Ok(IndexedImage {
+        capture_time: details.capture_time(),
+        details,
+        perceptual_hash,
+    })
+}
+
+/// Image files below `root` and errors for directories that can't be read
+///
+/// Entries are returned while the walk is still in progress. `n_found` counts
+/// the returned entries.
+fn walk(
+    root: gio::File,
+    cancellable: gio::Cancellable,
+    n_found: Arc<AtomicUsize>,
+) -> impl Stream<Item = Result<gio::File, (gio::File, Error)>> + Send + 'static {
+    util::blocking_stream(move |emit| {
+        let emit = |entry| {
+            n_found.fetch_add(1, Ordering::Relaxed);
+            emit(entry)
+        };
+
+        let attributes = [
+            gio::FILE_ATTRIBUTE_STANDARD_NAME.as_str(),
+            gio::FILE_ATTRIBUTE_STANDARD_TYPE.as_str(),
+            gio::FILE_ATTRIBUTE_STANDARD_FAST_CONTENT_TYPE.as_str(),
+        ]
+        .join(",");
+
+        let mut dirs = vec![root];
+
+        while let Some(dir) = dirs.pop() {
+            if cancellable.is_cancelled() {
+                return;
+            }
+
+            let enumerator = match dir.enumerate_children(
+                &attributes,
+                gio::FileQueryInfoFlags::NOFOLLOW_SYMLINKS,
+                Some(&cancellable),
+            ) {
+                Ok(enumerator) => enumerator,
+                Err(err) => {
+                    if !emit(Err((dir, err.into()))) {
+                        return;
+                    }
+                    continue;
+                }
+            };
+
+            for info in enumerator {
+                let info = match info {
+                    Ok(info) => info,
+                    Err(err) => {
+                        if !emit(Err((dir.clone(), err.into()))) {
+                            return;
+                        }
+                        break;
+                    }
+                };
+
+                let child = dir.child(info.name());
+
+                match info.file_type() {
+                    gio::FileType::Directory => dirs.push(child),
+                    gio::FileType::Regular => {
+                        let is_image = info
+                            .attribute_string(gio::FILE_ATTRIBUTE_STANDARD_FAST_CONTENT_TYPE)
+                            .and_then(|x| gio::content_type_get_mime_type(&x))
+                            .is_some_and(|x| x.starts_with("image/"));
+
+                        if is_image && !emit(Ok(child)) {
+                            return;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    })
+}
@@ -1,8 +1,31 @@
 use std::time::Duration;
 
-#[derive(Debug, Default)]
+/// Default for [`Limits::max_icc_profile_size`]
+///
+/// Legitimate ICC profiles are rarely larger than a few hundred KiB, even
+/// with large lookup tables.
+pub const DEFAULT_MAX_ICC_PROFILE_SIZE: usize = 4 * 1024 * 1024;
+
+/// Default for [`Limits::max_placeholder_scan_size`]
+pub const DEFAULT_MAX_PLACEHOLDER_SCAN_SIZE: u64 = 256 * 1024 * 1024;
+
+#[derive(Debug, Clone)]
 pub struct Limits {
     pub(crate) inner: glycin_utils::Limits,
+    pub(crate) strict_frame_size: bool,
+    pub(crate) max_icc_profile_size: usize,
+    pub(crate) max_placeholder_scan_size: u64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            inner: Default::default(),
+            strict_frame_size: false,
+            max_icc_profile_size: DEFAULT_MAX_ICC_PROFILE_SIZE,
+            max_placeholder_scan_size: DEFAULT_MAX_PLACEHOLDER_SCAN_SIZE,
+        }
+    }
 }
 
 impl Limits {
@@ -21,4 +44,42 @@ impl Limits {
         self.inner.max_dimensions = dimensions;
         self
     }
+
+    /// Reject frames with more data than announced
+    ///
+    /// Frames with a texture that is smaller than the announced dimensions
+    /// and stride are always rejected. By default, larger textures are
+    /// accepted and the extra data are ignored. With this option, textures
+    /// that exceed the announced size by more than one memory page are
+    /// rejected with [`ErrorKind::FrameSizeMismatch`](crate::ErrorKind::FrameSizeMismatch).
+    /// This helps to catch bugs in loaders.
+    pub fn strict_frame_size(mut self, strict_frame_size: bool) -> Self {
+        self.strict_frame_size = strict_frame_size;
+        self
+    }
+
+    /// Maximum size of ICC profiles in bytes
+    ///
+    /// Larger ICC profiles of images and frames are dropped before they are
+    /// copied or parsed. The frame is then treated as sRGB and a warning with
+    /// [`ErrorKind::IccProfileTooLarge`](crate::ErrorKind::IccProfileTooLarge)
+    /// is added to [`ImageDetails::warnings`](crate::ImageDetails::warnings)
+    /// or [`FrameDetails::warnings`](crate::FrameDetails::warnings).
+    /// This protects against files that embed gigantic profiles to exhaust
+    /// memory. Defaults to [`DEFAULT_MAX_ICC_PROFILE_SIZE`].
+    pub fn max_icc_profile_size(mut self, max_icc_profile_size: usize) -> Self {
+        self.max_icc_profile_size = max_icc_profile_size;
+        self
+    }
+
+    /// Maximum number of bytes that are scanned for embedded previews
+    ///
+    /// Only the beginning of the file up to this size is read by
+    /// [`Loader::load_or_placeholder`](crate::Loader::load_or_placeholder).
+    /// Previews that are stored further back are not found. Defaults to
+    /// [`DEFAULT_MAX_PLACEHOLDER_SCAN_SIZE`].
+    pub fn max_placeholder_scan_size(mut self, max_placeholder_scan_size: u64) -> Self {
+        self.max_placeholder_scan_size = max_placeholder_scan_size;
+        self
+    }
 }
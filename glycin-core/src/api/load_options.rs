@@ -0,0 +1,313 @@
+use std::time::Duration;
+
+use crate::{FitMode, Limits, Loader, MemoryFormat, MemoryFormatSelection, ProcessPriority};
+
+/// Reusable set of decoding options
+///
+/// Collects options that are otherwise set via the individual [`Loader`]
+/// methods, such that presets like "thumbnail" or "full quality" can be
+/// defined once and applied to many loaders via [`Loader::options`]. Each
+/// method corresponds to the [`Loader`] method of the same name.
+///
+/// Only options that have been set are applied, all other options of the
+/// loader are kept. Since applying the options calls the individual
+/// setters, later calls override earlier ones: Setters called after
+/// [`Loader::options`] take precedence over the options, and options take
+/// precedence over setters called before.
+#[derive(Debug, Clone, Default)]
+#[must_use]
+pub struct LoadOptions {
+    apply_transformations: Option<bool>,
+    memory_format_selection: Option<MemoryFormatSelection>,
+    accepted_formats: Option<Vec<MemoryFormat>>,
+    limits: Option<Limits>,
+    max_compression_ratio: Option<f64>,
+    read_buffer_size: Option<usize>,
+    read_ahead: Option<usize>,
+    raw: Option<bool>,
+    planar: Option<bool>,
+    straight_alpha: Option<bool>,
+    scale_factor: Option<f64>,
+    allow_partial: Option<bool>,
+    raw_animation_frames: Option<bool>,
+    assume_adobe_cmyk: Option<Option<bool>>,
+    timeout: Option<Duration>,
+    fit: Option<(u32, u32, FitMode)>,
+    frame_stride: Option<u32>,
+    process_priority: Option<ProcessPriority>,
+    use_sidecars: Option<bool>,
+}
+
+impl LoadOptions {
+    /// Options that don't change any setting of the loader
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// See [`Loader::apply_transformations`]
+    pub fn apply_transformations(mut self, apply_transformations: bool) -> Self {
+        self.apply_transformations = Some(apply_transformations);
+        self
+    }
+
+    /// See [`Loader::accepted_memory_formats`]
+    pub fn accepted_memory_formats(
+        mut self,
+        memory_format_selection: MemoryFormatSelection,
+    ) -> Self {
+        self.memory_format_selection = Some(memory_format_selection);
+        self.accepted_formats = None;
+        self
+    }
+
+    /// See [`Loader::accepted_formats`]
+    pub fn accepted_formats(mut self, memory_formats: Vec<MemoryFormat>) -> Self {
+        self.accepted_formats = Some(memory_formats);
+        self.memory_format_selection = None;
+        self
+    }
+
+    /// See [`Loader::memory_format`]
+    pub fn memory_format(self, memory_format: MemoryFormat) -> Self {
+        self.accepted_memory_formats(MemoryFormatSelection::from_memory_format(memory_format))
+    }
+
+    /// See [`Loader::limits`]
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = Some(limits);
+        self
+    }
+
+    /// See [`Loader::max_compression_ratio`]
+    pub fn max_compression_ratio(mut self, max_compression_ratio: f64) -> Self {
+        self.max_compression_ratio = Some(max_compression_ratio);
+        self
+    }
+
+    /// See [`Loader::read_buffer_size`]
+    pub fn read_buffer_size(mut self, read_buffer_size: usize) -> Self {
+        self.read_buffer_size = Some(read_buffer_size);
+        self
+    }
+
+    /// See [`Loader::read_ahead`]
+    pub fn read_ahead(mut self, n_chunks: usize) -> Self {
+        self.read_ahead = Some(n_chunks);
+        self
+    }
+
+    /// See [`Loader::raw`]
+    pub fn raw(mut self, raw: bool) -> Self {
+        self.raw = Some(raw);
+        self
+    }
+
+    /// See [`Loader::planar`]
+    pub fn planar(mut self, planar: bool) -> Self {
+        self.planar = Some(planar);
+        self
+    }
+
+    /// See [`Loader::straight_alpha`]
+    pub fn straight_alpha(mut self, straight_alpha: bool) -> Self {
+        self.straight_alpha = Some(straight_alpha);
+        self
+    }
+
+    /// See [`Loader::scale_factor`]
+    pub fn scale_factor(mut self, scale_factor: f64) -> Self {
+        self.scale_factor = Some(scale_factor);
+        self
+    }
+
+    /// See [`Loader::allow_partial`]
+    pub fn allow_partial(mut self, allow_partial: bool) -> Self {
+        self.allow_partial = Some(allow_partial);
+        self
+    }
+
+    /// See [`Loader::raw_animation_frames`]
+    pub fn raw_animation_frames(mut self, raw_animation_frames: bool) -> Self {
+        self.raw_animation_frames = Some(raw_animation_frames);
+        self
+    }
+
+    /// See [`Loader::assume_adobe_cmyk`]
+    pub fn assume_adobe_cmyk(mut self, assume_adobe_cmyk: Option<bool>) -> Self {
+        self.assume_adobe_cmyk = Some(assume_adobe_cmyk);
+        self
+    }
+
+    /// See [`Loader::timeout`]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// See [`Loader::fit`]
+    pub fn fit(mut self, width: u32, height: u32, fit_mode: FitMode) -> Self {
+        self.fit = Some((width, height, fit_mode));
+        self
+    }
+
+    /// See [`Loader::frame_stride`]
+    pub fn frame_stride(mut self, n: u32) -> Self {
+        self.frame_stride = Some(n);
+        self
+    }
+
+    /// See [`Loader::process_priority`]
+    pub fn process_priority(mut self, process_priority: ProcessPriority) -> Self {
+        self.process_priority = Some(process_priority);
+        self
+    }
+
+    /// See [`Loader::use_sidecars`]
+    pub fn use_sidecars(mut self, use_sidecars: bool) -> Self {
+        self.use_sidecars = Some(use_sidecars);
+        self
+    }
+}
+
+impl Loader {
+    /// Apply a set of options
+    ///
+    /// Only the options set in `options` are changed. See [`LoadOptions`]
+    /// for the precedence over the individual setters.
+    pub fn options(&mut self, options: LoadOptions) -> &mut Self {
+        if let Some(apply_transformations) = options.apply_transformations {
+            self.apply_transformations(apply_transformations);
+        }
+        if let Some(memory_format_selection) = options.memory_format_selection {
+            self.accepted_memory_formats(memory_format_selection);
+        }
+        if let Some(memory_formats) = options.accepted_formats {
+            self.accepted_formats(memory_formats);
+        }
+        if let Some(limits) = options.limits {
+            self.limits(limits);
+        }
+        if let Some(max_compression_ratio) = options.max_compression_ratio {
+            self.max_compression_ratio(max_compression_ratio);
+        }
+        if let Some(read_buffer_size) = options.read_buffer_size {
+            self.read_buffer_size(read_buffer_size);
+        }
+        if let Some(n_chunks) = options.read_ahead {
+            self.read_ahead(n_chunks);
+        }
+        if let Some(raw) = options.raw {
+            self.raw(raw);
+        }
+        if let Some(planar) = options.planar {
+            self.planar(planar);
+        }
+        if let Some(straight_alpha) = options.straight_alpha {
+            self.straight_alpha(straight_alpha);
+        }
+        if let Some(scale_factor) = options.scale_factor {
+            self.scale_factor(scale_factor);
+        }
+        if let Some(allow_partial) = options.allow_partial {
+            self.allow_partial(allow_partial);
+        }
+        if let Some(raw_animation_frames) = options.raw_animation_frames {
+            self.raw_animation_frames(raw_animation_frames);
+        }
+        if let Some(assume_adobe_cmyk) = options.assume_adobe_cmyk {
+            self.assume_adobe_cmyk(assume_adobe_cmyk);
+        }
+        if let Some(timeout) = options.timeout {
+            self.timeout(timeout);
+        }
+        if let Some((width, height, fit_mode)) = options.fit {
+            self.fit(width, height, fit_mode);
+        }
+        if let Some(n) = options.frame_stride {
+            self.frame_stride(n);
+        }
+        if let Some(process_priority) = options.process_priority {
+            self.process_priority(process_priority);
+        }
+        if let Some(use_sidecars) = options.use_sidecars {
+            self.use_sidecars(use_sidecars);
+        }
+
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn precedence() {
+        let options = LoadOptions::new()
+            .apply_transformations(false)
+            .memory_format(crate::MemoryFormat::R8g8b8);
+
+        let mut loader = Loader::new_vec(Vec::new());
+        loader.raw(true);
+        loader.accepted_memory_formats(MemoryFormatSelection::G8);
+        loader.options(options.clone());
+
+        // Options override earlier setters but keep unset options
+        assert!(!loader.apply_transformations);
+        assert_eq!(
+            loader.memory_format_selection,
+            MemoryFormatSelection::R8g8b8
+        );
+        assert!(loader.raw);
+
+        // Later setters override options
+        loader.apply_transformations(true);
+        assert!(loader.apply_transformations);
+
+        // Presets can be reused
+        let mut loader = Loader::new_vec(Vec::new());
+        loader.options(options);
+        assert!(!loader.apply_transformations);
+    }
+
+    #[test]
+    fn all_options() {
+        let options = LoadOptions::new()
+            .accepted_formats(vec![MemoryFormat::B8g8r8a8, MemoryFormat::R8g8b8a8])
+            .assume_adobe_cmyk(Some(true))
+            .timeout(Duration::from_secs(3))
+            .use_sidecars(true);
+
+        let mut loader = Loader::new_vec(Vec::new());
+        loader.options(options);
+
+        assert_eq!(
+            loader.accepted_formats,
+            [MemoryFormat::B8g8r8a8, MemoryFormat::R8g8b8a8]
+        );
+        assert_eq!(
+            loader.memory_format_selection,
+            MemoryFormatSelection::B8g8r8a8 | MemoryFormatSelection::R8g8b8a8
+        );
+        assert_eq!(loader.assume_adobe_cmyk, Some(true));
+        assert_eq!(loader.timeout, Some(Duration::from_secs(3)));
+        assert!(loader.use_sidecars);
+
+        // The last memory format setter wins
+        let mut loader = Loader::new_vec(Vec::new());
+        loader.options(
+            LoadOptions::new()
+                .accepted_formats(vec![MemoryFormat::B8g8r8a8])
+                .memory_format(MemoryFormat::G8),
+        );
+        assert!(loader.accepted_formats.is_empty());
+        assert_eq!(loader.memory_format_selection, MemoryFormatSelection::G8);
+
+        // Unset options are kept
+        let mut loader = Loader::new_vec(Vec::new());
+        loader.assume_adobe_cmyk(Some(false));
+        loader.options(LoadOptions::new());
+        assert_eq!(loader.assume_adobe_cmyk, Some(false));
+        assert_eq!(loader.timeout, None);
+    }
+}
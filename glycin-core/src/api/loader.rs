@@ -1,12 +1,20 @@
+use std::os::fd::{AsFd, BorrowedFd, OwnedFd};
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
-use std::sync::{Arc, Mutex, OnceLock};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
 
 #[cfg(feature = "builtin")]
 use futures_util::FutureExt;
+use futures_util::{Stream, StreamExt};
 use gio::glib;
 use gio::prelude::*;
 pub use glycin_common::MemoryFormat;
-use glycin_common::{ColorProfilePreference, MemoryFormatInfo, MemoryFormatSelection};
+use glycin_common::{
+    BlendOp, ColorProfilePreference, DisposalMethod, MemoryFormatInfo, MemoryFormatSelection,
+};
 #[cfg(feature = "builtin")]
 use glycin_utils::LoaderImplementation;
 use glycin_utils::safe_math::*;
@@ -14,7 +22,7 @@ use glycin_utils::{ByteData, FungibleMemory};
 use gufo_common::cicp::Cicp;
 use gufo_common::orientation::{Orientation, Rotation};
 use gufo_common::physical_dimension;
-use util::{CancellableFuture, ShortcutErrorFuture, TimeoutFuture};
+use util::{CancellableFuture, Deadline, ShortcutErrorFuture, TimeoutFuture};
 #[cfg(feature = "external")]
 use zbus::zvariant::OwnedObjectPath;
 
@@ -22,13 +30,16 @@ use crate::api::*;
 pub use crate::config::MimeType;
 #[cfg(feature = "external")]
 use crate::dbus::*;
-use crate::error::ResultExt;
+use crate::error::{ResultExt, Stage};
 use crate::main_context::{MainContextSelector, ProvidesMainContext};
 #[cfg(feature = "external")]
 use crate::pool::{PooledProcess, UsageTracker};
 use crate::source::SourceTransmission;
 use crate::util::spawn_blocking;
-use crate::{Error, ErrorKind, MAX_TEXTURE_SIZE, Pool, config, icc, orientation, util};
+use crate::{
+    CameraSettings, Error, ErrorKind, MAX_TEXTURE_SIZE, Plane, Pool, config, icc, orientation,
+    source, util,
+};
 
 /// Image request builder
 #[derive(Debug)]
@@ -39,9 +50,39 @@ pub struct Loader {
     use_expose_base_dir: bool,
     pub(crate) apply_transformations: bool,
     pub(crate) sandbox_selector: SandboxSelector,
+    sandbox_wrapper: Option<SandboxWrapper>,
     pub(crate) memory_format_selection: MemoryFormatSelection,
+    /// Formats in order of preference, see [`Loader::accepted_formats`]
+    pub(crate) accepted_formats: Vec<MemoryFormat>,
     pub(crate) limits: Limits,
     pub(crate) main_context_selector: MainContextSelector,
+    scale_factor: Option<f64>,
+    allow_partial: bool,
+    raw_animation_frames: bool,
+    pub(crate) assume_adobe_cmyk: Option<bool>,
+    pub(crate) timeout: Option<Duration>,
+    deadline: Option<Deadline>,
+    fit: Option<(u32, u32, FitMode)>,
+    frame_stride: u32,
+    sidecars: Vec<gio::File>,
+    pub(crate) use_sidecars: bool,
+    max_compression_ratio: Option<f64>,
+    /// Size of the source if known without querying it
+    input_size: Option<u64>,
+    pub(crate) raw: bool,
+    planar: bool,
+    straight_alpha: bool,
+    sandbox_tmpdir: Option<PathBuf>,
+    process_priority: ProcessPriority,
+    /// Use this MIME type instead of detecting it
+    mime_type: Option<MimeType>,
+    mime_type_fallbacks: Vec<MimeType>,
+    read_buffer_size: usize,
+    read_ahead: usize,
+    /// Only allow loading with this process, see [`WarmLoader`]
+    pub(crate) warm_process: Option<config::ConfigEntryHash>,
+    pub(crate) process_context: ProcessContext,
+    process_ready: ProcessReady,
 }
 
 static_assertions::assert_impl_all!(Loader: Send, Sync);
@@ -52,6 +93,25 @@ impl Loader {
         Self::new_source(Source::File(file))
     }
 
+    /// Create a loader with a [`gio::File`] and additional sidecar files
+    ///
+    /// Sidecars are files that belong to the image, like XMP files with
+    /// metadata from photo editors. They are made readable in the sandbox and
+    /// their paths are passed to the loader. Sidecars without local path are
+    /// skipped.
+    ///
+    /// The first sidecar with an `.xmp` extension, in any case, is merged
+    /// into the XMP metadata, independent of the loader. Its properties take
+    /// precedence over the ones embedded in the image. The key
+    /// `glycin:xmp-sidecar` of [`ImageDetails::metadata_key_value`] then
+    /// contains the sidecar's file name. Sidecars that are not valid XML are
+    /// ignored with a warning.
+    pub fn new_multi(primary: gio::File, sidecars: Vec<gio::File>) -> Self {
+        let mut loader = Self::new(primary);
+        loader.sidecars = sidecars;
+        loader
+    }
+
     /// Create a loader with a [`gio::InputStream`] as source
     ///
     /// # Safety
@@ -62,18 +122,35 @@ impl Loader {
     }
 
     /// Create a loader with [`glib::Bytes`] as source
+    ///
+    /// Like for [`Loader::new_vec`], there is no base directory that
+    /// could be exposed to the sandbox.
     pub fn new_bytes(bytes: glib::Bytes) -> Self {
         let stream = gio::MemoryInputStream::from_bytes(&bytes);
-        unsafe { Self::new_stream(stream) }
+        let mut loader = unsafe { Self::new_stream(stream) };
+        loader.input_size = Some(bytes.len() as u64);
+        loader
     }
 
     /// Create a loader with [`Vec<u8>`] as source
+    ///
+    /// The data are passed to the loader directly from the buffer without
+    /// going through a [`gio::InputStream`]. The buffer is kept alive until
+    /// the loader has read all data.
+    ///
+    /// Since there is no file, there is also no base directory. Formats like
+    /// SVG can therefore not resolve relative references to external files,
+    /// even if [`Loader::use_expose_base_dir`] is set.
     pub fn new_vec(buf: Vec<u8>) -> Self {
-        let bytes = glib::Bytes::from_owned(buf);
-        Self::new_bytes(bytes)
+        let input_size = buf.len() as u64;
+        let mut loader = Self::new_source(Source::Vec(buf));
+        loader.input_size = Some(input_size);
+        loader
     }
 
     pub(crate) fn new_source(source: Source) -> Self {
+        let (process_context, process_ready) = ProcessContext::new();
+
         Self {
             source,
             pool: Pool::global(),
@@ -81,9 +158,35 @@ impl Loader {
             apply_transformations: true,
             use_expose_base_dir: false,
             sandbox_selector: SandboxSelector::default(),
+            sandbox_wrapper: None,
             memory_format_selection: MemoryFormatSelection::all(),
+            accepted_formats: Vec::new(),
             limits: Limits::default(),
             main_context_selector: MainContextSelector::Auto,
+            scale_factor: None,
+            allow_partial: false,
+            raw_animation_frames: false,
+            assume_adobe_cmyk: None,
+            timeout: None,
+            deadline: None,
+            fit: None,
+            frame_stride: 1,
+            sidecars: Vec::new(),
+            use_sidecars: false,
+            max_compression_ratio: None,
+            input_size: None,
+            raw: false,
+            planar: false,
+            straight_alpha: false,
+            sandbox_tmpdir: None,
+            process_priority: ProcessPriority::default(),
+            mime_type: None,
+            mime_type_fallbacks: Vec::new(),
+            read_buffer_size: source::BUF_SIZE,
+            read_ahead: 0,
+            warm_process: None,
+            process_context,
+            process_ready,
         }
     }
 
@@ -95,6 +198,18 @@ impl Loader {
         self
     }
 
+    /// Start loaders via a custom sandbox
+    ///
+    /// The wrapper is used instead of the mechanism chosen via
+    /// [`Loader::sandbox_selector`] and [`Image::active_sandbox_mechanism`]
+    /// returns [`SandboxMechanism::Custom`]. See [`SandboxWrapper`] for the
+    /// contract the wrapper has to honor. The option has no effect for builtin
+    /// loaders.
+    pub fn sandbox_wrapper(&mut self, sandbox_wrapper: SandboxWrapper) -> &mut Self {
+        self.sandbox_wrapper = Some(sandbox_wrapper);
+        self
+    }
+
     /// Set [`Cancellable`](gio::Cancellable) to cancel any loader operations
     pub fn cancellable(&mut self, cancellable: impl IsA<gio::Cancellable>) -> &mut Self {
         self.cancellable = cancellable.upcast();
@@ -121,6 +236,53 @@ impl Loader {
         memory_format_selection: MemoryFormatSelection,
     ) -> &mut Self {
         self.memory_format_selection = memory_format_selection;
+        self.accepted_formats.clear();
+        self
+    }
+
+    /// Sets the accepted memory formats in order of preference
+    ///
+    /// If the memory format of the loader is one of `memory_formats`, frames
+    /// keep this format. Otherwise, frames are converted into the first
+    /// format of the list. This avoids conversions when several formats can
+    /// be used, for example, preferring [`MemoryFormat::B8g8r8a8`] while also
+    /// accepting [`MemoryFormat::R8g8b8a8`]. [`Frame::memory_format`]
+    /// returns the chosen format.
+    ///
+    /// CMYK formats in the list are ignored, and an empty list accepts all
+    /// formats. This replaces the formats set via
+    /// [`Loader::accepted_memory_formats`] or [`Loader::memory_format`] and
+    /// vice versa.
+    pub fn accepted_formats(&mut self, memory_formats: Vec<MemoryFormat>) -> &mut Self {
+        let memory_formats: Vec<_> = memory_formats
+            .into_iter()
+            .filter(|x| !x.is_cmyk())
+            .collect();
+
+        self.memory_format_selection = if memory_formats.is_empty() {
+            MemoryFormatSelection::all()
+        } else {
+            MemoryFormatSelection::from_memory_formats(&memory_formats)
+        };
+        self.accepted_formats = memory_formats;
+        self
+    }
+
+    /// Sets the memory format in which all frames are returned
+    ///
+    /// This is the same as [`Loader::accepted_memory_formats`] with only a
+    /// single format selected. The conversion happens after the ICC profile
+    /// has been applied. Converting into a format with a lower bit depth or
+    /// without alpha channel loses information.
+    ///
+    /// For HDR pipelines, [`MemoryFormat::R16g16b16a16Float`] can be used to
+    /// directly obtain half-float textures. Integer data are normalized to
+    /// the range from `0.0` to `1.0`. Float data keep their values, including
+    /// values outside of this range. Values that are too large for half-floats
+    /// become infinite.
+    pub fn memory_format(&mut self, memory_format: MemoryFormat) -> &mut Self {
+        self.memory_format_selection = MemoryFormatSelection::from_memory_format(memory_format);
+        self.accepted_formats.clear();
         self
     }
 
@@ -138,6 +300,22 @@ impl Loader {
         self
     }
 
+    /// Sets if XMP sidecars next to the image are used automatically
+    ///
+    /// For an image `photo.cr2`, the sidecars `photo.cr2.xmp`, as written by
+    /// darktable and digiKam, and `photo.xmp`, as written by Adobe software,
+    /// are checked in that order. The uppercase `.XMP` extension is checked as
+    /// well. Existing sidecars are added after the sidecars passed to
+    /// [`Loader::new_multi`], see there for how they are used.
+    ///
+    /// Only files with a local path are checked. By default, this option is
+    /// disabled since it gives loaders access to additional files, and since
+    /// other files can change what metadata are reported for an image.
+    pub fn use_sidecars(&mut self, use_sidecars: bool) -> &mut Self {
+        self.use_sidecars = use_sidecars;
+        self
+    }
+
     pub fn pool(&mut self, pool: Arc<Pool>) -> &mut Self {
         self.pool = pool;
         self
@@ -148,6 +326,321 @@ impl Loader {
         self
     }
 
+    /// Reject images that are suspected decompression bombs
+    ///
+    /// Decompression bombs are tiny files that announce huge images, in order
+    /// to exhaust memory or CPU time. The compression ratio is the size the
+    /// image would have as 8-bit RGBA, divided by the size of the source in
+    /// bytes. If it exceeds `max_compression_ratio`, loading fails with
+    /// [`ErrorKind::SuspectedDecompressionBomb`] before any frame is decoded.
+    ///
+    /// The check is disabled by default. A ratio of `1000.0` leaves room for
+    /// large areas of uniform color, as they are common in screenshots, and
+    /// still catches typical bombs. For sources with unknown size, like
+    /// streams, the bytes read until the image details are known are used,
+    /// which can make the ratio larger than it actually is.
+    pub fn max_compression_ratio(&mut self, max_compression_ratio: f64) -> &mut Self {
+        self.max_compression_ratio = Some(max_compression_ratio);
+        self
+    }
+
+    /// Sets the size of the chunks in which the source is read
+    ///
+    /// Larger chunks can improve the throughput for large files, smaller
+    /// chunks reduce the memory used while the data are passed to the loader.
+    /// The first chunk is also used to detect the MIME type.
+    ///
+    /// The size is clamped to the range of 4 KiB to 16 MiB. The default is
+    /// 64 KiB.
+    pub fn read_buffer_size(&mut self, read_buffer_size: usize) -> &mut Self {
+        self.read_buffer_size = read_buffer_size.clamp(source::MIN_BUF_SIZE, source::MAX_BUF_SIZE);
+        self
+    }
+
+    /// Read ahead of the loader by up to `n_chunks` chunks
+    ///
+    /// By default, the next chunk of the source is only read once the loader
+    /// has received the previous one. For sources with high latency, like
+    /// files on SMB or WebDAV shares, the loader then waits for every read.
+    /// With a read-ahead, the source is read in the background and the chunks
+    /// are queued until the loader consumes them.
+    ///
+    /// The queue holds up to `n_chunks` chunks of the size set via
+    /// [`Loader::read_buffer_size`], such that the additional memory use
+    /// is at most their product. The read-ahead is disabled by default and
+    /// limited to 256 chunks and 64 MiB. It only affects external loaders,
+    /// builtin loaders always queue the data.
+    pub fn read_ahead(&mut self, n_chunks: usize) -> &mut Self {
+        self.read_ahead = n_chunks.min(source::MAX_READ_AHEAD);
+        self
+    }
+
+    /// Set whether to return frames exactly as the loader provided them
+    ///
+    /// When enabled, the following steps, that are normally applied to each
+    /// frame, are skipped:
+    ///
+    /// - Correcting the orientation as specified by Exif or other metadata,
+    ///   independent of [`Loader::apply_transformations`].
+    /// - Applying an embedded ICC profile to convert the frame to sRGB.
+    /// - Converting the memory format to one of the
+    ///   [`Loader::accepted_memory_formats`].
+    /// - Changing the stride of the frame.
+    ///
+    /// The color state of the frames reports attached CICP information. An
+    /// attached ICC profile is only available via
+    /// [`FrameDetails::color_icc_profile`].
+    ///
+    /// CMYK data are still converted to RGB, without applying the ICC
+    /// profile, since frames never have CMYK memory formats.
+    ///
+    /// This option is disabled by default.
+    pub fn raw(&mut self, raw: bool) -> &mut Self {
+        self.raw = raw;
+        self
+    }
+
+    /// Return frames with one plane per channel
+    ///
+    /// By default, the channels of each pixel are stored next to each other,
+    /// like `RGBRGBRGB…`. With this option, frames are repacked after all
+    /// other processing, like the conversion to an accepted memory format,
+    /// such that each channel is stored in its own plane, like
+    /// `RRR…GGG…BBB…`. This is the layout expected by many video encoders
+    /// and machine learning frameworks.
+    ///
+    /// The planes are stored directly after each other, in the order of the
+    /// channels in the [`Frame::memory_format`], and rows have no padding.
+    /// Their positions are reported by [`Frame::planes`], and
+    /// [`Frame::stride`] is the stride of the planes. The memory format still
+    /// describes the type and order of the channels.
+    ///
+    /// Planar frames can't be used with [`Frame::texture`] or other
+    /// functions that process the pixel data of the frame, since those
+    /// expect interleaved data. The caller has to handle the planes.
+    ///
+    /// This option is disabled by default and has no effect for
+    /// [`Loader::raw`].
+    pub fn planar(&mut self, planar: bool) -> &mut Self {
+        self.planar = planar;
+        self
+    }
+
+    /// Return frames with straight alpha
+    ///
+    /// Image editors usually work with straight (not premultiplied) alpha,
+    /// since repeatedly premultiplying and unpremultiplying loses precision,
+    /// especially for low alpha values. With this option, premultiplied
+    /// formats are removed from the [accepted memory
+    /// formats](Self::accepted_memory_formats). If the loader returns
+    /// premultiplied data, they are converted to the best straight alpha
+    /// format. Pixels that are fully transparent keep a color of zero.
+    ///
+    /// If only premultiplied formats are accepted, for example via
+    /// [`Loader::memory_format`], their straight alpha counterparts are used
+    /// instead.
+    ///
+    /// By default, premultiplied formats are returned if the loader chose
+    /// them and they are accepted.
+    pub fn straight_alpha(&mut self, straight_alpha: bool) -> &mut Self {
+        self.straight_alpha = straight_alpha;
+        self
+    }
+
+    /// Directory for temporary files of the loader
+    ///
+    /// Some loaders write large intermediate files. By default, they are
+    /// stored in a `tmpfs` inside the sandbox, which uses memory. With this
+    /// option, the directory is mounted writable into the sandbox and set as
+    /// `TMPDIR` for the loader.
+    ///
+    /// This grants the loader write access to this directory and nothing
+    /// else. It should not contain any other data. Loading fails with
+    /// [`ErrorKind::InvalidTmpdir`] if the directory doesn't exist or isn't
+    /// writable. The option has no effect for builtin loaders.
+    pub fn sandbox_tmpdir(&mut self, path: impl Into<PathBuf>) -> &mut Self {
+        self.sandbox_tmpdir = Some(path.into());
+        self
+    }
+
+    /// Scheduling priority of the loader process
+    ///
+    /// A file manager can use [`ProcessPriority::background`] when generating
+    /// thumbnails, such that the loaders don't compete with foreground work.
+    /// See [`ProcessPriority`] for platform specifics. The option has no
+    /// effect for builtin loaders.
+    pub fn process_priority(&mut self, process_priority: ProcessPriority) -> &mut Self {
+        self.process_priority = process_priority;
+        self
+    }
+
+    /// Memory formats in which frames are returned
+    fn frame_memory_formats(&self) -> MemoryFormatSelection {
+        if !self.straight_alpha {
+            return self.memory_format_selection;
+        }
+
+        let selection = self.memory_format_selection.without_premultiplied();
+        if selection.is_empty() {
+            MemoryFormatSelection::all().without_premultiplied()
+        } else {
+            selection
+        }
+    }
+
+    /// Formats in order of preference in which frames are returned
+    fn frame_accepted_formats(&self) -> Vec<MemoryFormat> {
+        if self.straight_alpha {
+            self.accepted_formats
+                .iter()
+                .copied()
+                .filter(|x| !x.is_premultiplied())
+                .collect()
+        } else {
+            self.accepted_formats.clone()
+        }
+    }
+
+    /// Set MIME types to try if loading the image fails
+    ///
+    /// If loading the image with the detected MIME type fails, the image is
+    /// loaded as each of the `mime_types` in the given order until loading
+    /// succeeds. This is useful for files that are valid in multiple formats.
+    /// [`Image::mime_type`] returns the MIME type that succeeded.
+    ///
+    /// If all attempts fail, the error of the first attempt is returned. Only
+    /// errors while loading the image details lead to another attempt, errors
+    /// in later frame requests don't. Retrying is only possible for loaders
+    /// created via [`Loader::new`] since streams can't be read a second time.
+    pub fn as_mime_type_fallbacks(&mut self, mime_types: Vec<MimeType>) -> &mut Self {
+        self.mime_type_fallbacks = mime_types;
+        self
+    }
+
+    /// Set the display scale factor
+    ///
+    /// For formats like ICO that contain the same image in multiple sizes,
+    /// the size matching the scale factor is selected. The smallest
+    /// embedded size is considered to be the size for a scale factor of
+    /// `1.0`. If no size matches exactly, the next larger size is used, or the
+    /// largest size if there is no larger one. Without a scale factor, the
+    /// largest size is used.
+    ///
+    /// The image dimensions and a scaling requested via
+    /// [`FrameRequest::scale`] refer to the selected size.
+    ///
+    /// Scale factors that are not finite or not positive are ignored and
+    /// reset a previously set scale factor.
+    pub fn scale_factor(&mut self, scale_factor: f64) -> &mut Self {
+        self.scale_factor = (scale_factor.is_finite() && scale_factor > 0.).then_some(scale_factor);
+        self
+    }
+
+    /// Load the complete frames of truncated animations
+    ///
+    /// By default, loading a frame of a truncated animation, like a GIF
+    /// that is still downloading, fails once the missing data is reached.
+    /// With this option, the animation ends before the first incomplete
+    /// frame instead. [`Image::next_frame`] then behaves as if the animation
+    /// only consisted of the complete frames. The last complete frame reports
+    /// the truncation in [`FrameDetails::warnings`].
+    ///
+    /// Currently, only GIF, WebP, and APNG animations decoded by the image-rs
+    /// loader support this. Defaults to `false`.
+    pub fn allow_partial(&mut self, allow_partial: bool) -> &mut Self {
+        self.allow_partial = allow_partial;
+        self
+    }
+
+    /// Return animation frames as stored in the file
+    ///
+    /// By default, loaders composite animation frames onto the canvas, such
+    /// that every frame covers the whole image. With this option, frames only
+    /// cover their own area of the canvas and report how they have to be
+    /// composited via [`Frame::offset`], [`Frame::blend_op`], and
+    /// [`Frame::disposal_method`]. This is useful for custom animation
+    /// renderers.
+    ///
+    /// Currently, only GIF and APNG animations decoded by the image-rs loader
+    /// support this. Other loaders return composited frames that don't carry
+    /// these values. Defaults to `false`.
+    pub fn raw_animation_frames(&mut self, raw_animation_frames: bool) -> &mut Self {
+        self.raw_animation_frames = raw_animation_frames;
+        self
+    }
+
+    /// Interpretation of CMYK values in JPEGs
+    ///
+    /// Adobe tools store CMYK JPEGs with inverted values, where the maximum
+    /// value means no ink. Such files are detected via the Adobe APP14
+    /// marker, which is the default behavior with `None`. `Some(true)` treats
+    /// the values as inverted and `Some(false)` as regular, independent of
+    /// the marker. This allows correcting files that lack the marker.
+    ///
+    /// The CMYK data are returned as [`MemoryFormat::C8m8y8k8`] by the
+    /// loader and then converted to RGB. Currently, only the image-rs loader
+    /// supports this.
+    pub fn assume_adobe_cmyk(&mut self, assume_adobe_cmyk: Option<bool>) -> &mut Self {
+        self.assume_adobe_cmyk = assume_adobe_cmyk;
+        self
+    }
+
+    /// Kill the loader if it doesn't finish in time
+    ///
+    /// The timeout starts with [`Loader::load`] and covers the complete
+    /// operation, including the frames requested from the returned
+    /// [`Image`]. It is not reset for each request. Once the timeout is
+    /// reached, the loader process is killed with `SIGKILL`, like on
+    /// cancellation, and [`ErrorKind::Timeout`] with the elapsed time is
+    /// returned. All further requests to the image fail.
+    ///
+    /// This protects against loaders that hang. Unlike
+    /// [`Limits::timeout`], which applies to each request separately, the
+    /// process doesn't keep running after the timeout. Builtin loaders don't
+    /// run in a separate process and are not affected by this option.
+    pub fn timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Fit frames into the given dimensions
+    ///
+    /// Frames loaded via [`Image::next_frame`] and [`Image::try_next_frame`]
+    /// are requested with the scale and clip that fit the image into `width`
+    /// × `height` as described for the [`FitMode`] variants. The
+    /// computation is based on the image dimensions from
+    /// [`Image::details`]. For [`FitMode::Cover`], this always implies a
+    /// clip that is centered in the scaled image.
+    ///
+    /// Frame requests passed to [`Image::specific_frame`] are not changed.
+    /// Like other frame request instructions, loaders can ignore the scale
+    /// and clip.
+    pub fn fit(&mut self, width: u32, height: u32, fit_mode: FitMode) -> &mut Self {
+        self.fit = Some((width, height, fit_mode));
+        self
+    }
+
+    /// Only return every `n`th frame of animations
+    ///
+    /// [`Image::next_frame`] returns the first frame and then skips the
+    /// following `n - 1` frames. The [`Frame::delay`] of a returned frame is
+    /// the sum of its own delay and the delays of the skipped frames, such
+    /// that the animation keeps its duration. The
+    /// [`Frame::frame_index`] and [`Frame::presentation_time`] still count
+    /// the skipped frames. Skipping continues when the animation loops.
+    ///
+    /// Animation frames often only contain the changes to the previous
+    /// frame. Therefore, loaders still have to decode the skipped frames, and
+    /// they are passed to glycin as well. This reduces the number of frames
+    /// an application has to handle, but not the decoding time.
+    ///
+    /// Still images and [`Image::specific_frame`] are not affected. A value
+    /// of `0` is treated like `1`, which is the default.
+    pub fn frame_stride(&mut self, n: u32) -> &mut Self {
+        self.frame_stride = n.max(1);
+        self
+    }
+
     pub fn main_context_selector(&mut self, selector: MainContextSelector) -> &mut Self {
         self.main_context_selector = selector;
         self
@@ -155,37 +648,608 @@ impl Loader {
 
     /// Load basic image information and enable further operations
     pub fn load(mut self) -> Pin<Box<dyn Future<Output = Result<Image, Error>> + Send>> {
+        self.deadline = self.timeout.map(Deadline::new);
+
         Box::pin(async {
-            tracing::debug!(image = self.source.display(), "Loading image");
+            let fallbacks = std::mem::take(&mut self.mime_type_fallbacks);
+            let file = self.source.file();
+            let fallback_loader = self.with_source(Source::TransferredStream);
+            // Fallbacks load the same file and keep its options
+            let fit = self.fit;
+            let sidecars = self.sidecars.clone();
+
+            let err = match self.load_once().await {
+                Ok(image) => return Ok(image),
+                Err(err) => err,
+            };
 
-            let source = self.source.send();
-            let main_context = self.main_context();
-            let cancellable = self.cancellable.clone();
-            let timeout = self.limits.inner.timeout;
+            let Some(file) = file.filter(|_| !err.is_cancelled()) else {
+                return Err(err);
+            };
 
-            let f = move || {
-                async move { self.load_internal(source).await }
-                    .make_cancellable(cancellable)
-                    .enforce_timeout(timeout)
+            for mime_type in fallbacks {
+                tracing::debug!("Loading failed, trying as {mime_type}");
+
+                let mut loader = fallback_loader.with_source(Source::File(file.clone()));
+                loader.mime_type = Some(mime_type);
+                loader.fit = fit;
+                loader.sidecars = sidecars.clone();
+
+                match loader.load_once().await {
+                    Ok(image) => return Ok(image),
+                    Err(err) if err.is_cancelled() => return Err(err),
+                    Err(err) => tracing::debug!("Fallback failed: {err}"),
+                }
+            }
+
+            Err(err)
+        })
+    }
+
+    /// Load basic image information without an async runtime
+    ///
+    /// Blocks the current thread until [`Loader::load`] finishes. This is
+    /// intended for scripts, command line tools, and tests. Applications with
+    /// an event loop should use [`Loader::load`] instead.
+    ///
+    /// Unless another selector has been set via
+    /// [`Loader::main_context_selector`], the sandbox communication runs on
+    /// the main context managed by glycin, see [`MainContextSelector::Managed`].
+    /// Therefore, this doesn't deadlock if the calling thread owns a
+    /// [`MainContext`](glib::MainContext). A
+    /// [`MainContextSelector::Specific`] context has to be run by another
+    /// thread. Further operations on the returned [`Image`] are async and can
+    /// be driven by any executor.
+    ///
+    /// Not available with the `tokio` feature since the communication
+    /// requires a running tokio runtime in that case.
+    #[cfg(not(feature = "tokio"))]
+    pub fn load_blocking(mut self) -> Result<Image, Error> {
+        if matches!(self.main_context_selector, MainContextSelector::Auto) {
+            self.main_context_selector(MainContextSelector::Managed);
+        }
+        glib::MainContext::new().block_on(self.load())
+    }
+
+    /// Load a small grayscale preview of the first frame
+    ///
+    /// The frame is fitted into `size` × `size` like with [`FitMode::Contain`],
+    /// keeping the aspect ratio. The longer side is always `size`, such that
+    /// smaller images are enlarged. Each pixel is the average over the
+    /// corresponding area of the image. The result is always a single channel
+    /// [`MemoryFormat::G8`] frame with the Rec. 709 luma
+    /// `0.2126 R + 0.7152 G + 0.0722 B`. Transparent areas are treated as black.
+    ///
+    /// This is meant for pipelines like image classification, which don't
+    /// need color or full resolution.
+    pub fn luma_preview(
+        mut self,
+        size: u32,
+    ) -> Pin<Box<dyn Future<Output = Result<Frame, Error>> + Send>> {
+        self.fit(size, size, FitMode::Contain);
+        self.planar = false;
+
+        Box::pin(async move {
+            let frame = self.load().await?.next_frame().await?;
+
+            let ((width, height), _) =
+                FitMode::Contain.frame_request((frame.width, frame.height), (size, size));
+            let values = crate::luma::downscale(
+                frame.texture_ref()?,
+                width.try_usize()?,
+                height.try_usize()?,
+                crate::luma::REC_709,
+            )?;
+            let buffer = values
+                .into_iter()
+                .map(|x| (x * 255.).round() as u8)
+                .collect::<Vec<_>>();
+
+            // Details like the ICC profile or bit depth don't apply anymore
+            let mut details = glycin_utils::FrameDetails::default();
+            details.n_frame = frame.details.n_frame;
+            details.info_bit_depth = Some(8);
+            details.info_grayscale = Some(true);
+
+            Ok(Frame {
+                buffer: glib::Bytes::from_owned(buffer),
+                width,
+                height,
+                stride: width,
+                memory_format: MemoryFormat::G8,
+                details: Arc::new(details),
+                color_state: ColorState::Srgb,
+                planes: None,
+                memfd: None,
+                ..frame
+            })
+        })
+    }
+
+    /// Load the first frame as tightly packed RGBA
+    ///
+    /// Returns the pixel data together with the width and height. The data
+    /// are always in [`MemoryFormat::R8g8b8a8`], that is, straight (not
+    /// premultiplied) alpha with one byte per channel in the order red, green,
+    /// blue, alpha. Rows follow each other without padding, such that the
+    /// data are exactly `width * height * 4` bytes long. Images without an
+    /// alpha channel are fully opaque.
+    ///
+    /// The source format, stride, and premultiplication are handled
+    /// internally. Other options of the loader, like
+    /// [`Loader::apply_transformations`] or [`Loader::fit`], are respected.
+    /// [`Loader::raw`] and [`Loader::planar`] are disabled since they don't
+    /// allow converting the frame.
+    pub fn load_rgba8(
+        mut self,
+    ) -> Pin<Box<dyn Future<Output = Result<(Vec<u8>, u32, u32), Error>> + Send>> {
+        self.memory_format(MemoryFormat::R8g8b8a8);
+        self.straight_alpha = true;
+        self.raw = false;
+        self.planar = false;
+
+        Box::pin(async move {
+            let frame = self.load().await?.next_frame().await?;
+
+            let row_len = frame.width.try_usize()?.smul(4)?;
+            let buffer = util::change_stride(
+                frame.buf_slice(),
+                frame.height.try_usize()?,
+                row_len,
+                frame.stride.try_usize()?,
+                row_len,
+            )?;
+
+            Ok((buffer, frame.width, frame.height))
+        })
+    }
+
+    async fn load_once(self) -> Result<Image, Error> {
+        tracing::debug!(image = self.source.display(), "Loading image");
+
+        let source = self.source.send();
+        let main_context = self.main_context();
+        let cancellable = self.cancellable.clone();
+        let timeout = self.limits.inner.timeout;
+
+        let f = move || {
+            async move { self.load_internal(source).await }
+                .make_cancellable(cancellable)
+                .enforce_timeout(timeout)
+        };
+
+        main_context.spawn_from_within(f).await?
+    }
+
+    /// Load image or fall back to an embedded preview
+    ///
+    /// If no loader supports the image format, the file is scanned for an
+    /// embedded JPEG preview, as they are found in many RAW formats or PDFs.
+    /// If such a preview is found, it is loaded instead. The returned `bool` is
+    /// `true` if the image is such a placeholder.
+    ///
+    /// This is best effort. The preview might have a lower resolution or show
+    /// something different than the actual image. Only the beginning of the
+    /// file up to [`Limits::max_placeholder_scan_size`] is scanned. The
+    /// fallback is only available for loaders created via [`Loader::new`]
+    /// since streams can't be read a second time.
+    pub fn load_or_placeholder(
+        self,
+    ) -> Pin<Box<dyn Future<Output = Result<(Image, bool), Error>> + Send>> {
+        Box::pin(async {
+            let file = self.source.file();
+            let mut placeholder_loader = self.with_source(Source::TransferredStream);
+
+            let err = match self.load().await {
+                Ok(image) => return Ok((image, false)),
+                Err(err) if err.unsupported_format().is_some() => err,
+                Err(err) => return Err(err),
+            };
+
+            let Some(file) = file else {
+                return Err(err);
+            };
+
+            tracing::debug!("Unsupported format, searching for embedded preview");
+
+            let cancellable = placeholder_loader.cancellable.clone();
+            let max_size = placeholder_loader.limits.max_placeholder_scan_size;
+            let preview = util::spawn_blocking(move || {
+                use std::io::Read;
+
+                let stream = file
+                    .read(Some(&cancellable))
+                    .map_err(|e| ErrorKind::ImageSource(e).err())?;
+
+                let mut data = Vec::new();
+                stream.into_read().take(max_size).read_to_end(&mut data)?;
+
+                Ok::<_, Error>(crate::placeholder::embedded_jpeg(&data).map(|x| x.to_vec()))
+            })
+            .await??;
+
+            let Some(preview) = preview else {
+                return Err(err);
             };
 
-            main_context.spawn_from_within(f).await?
+            let stream = gio::MemoryInputStream::from_bytes(&glib::Bytes::from_owned(preview));
+            placeholder_loader.source =
+                unsafe { Source::Stream(GInputStreamSend::new(stream.upcast())) };
+
+            let image = placeholder_loader.load().await?;
+
+            Ok((image, true))
         })
     }
 
+    /// Load image details of many files concurrently
+    ///
+    /// Only the image details are loaded, no frames are decoded. At most
+    /// `concurrency` files are loaded at the same time and the loader is shut
+    /// down as soon as the details of a file are available. The results are
+    /// returned in the order in which they finish.
+    ///
+    /// Errors only affect the respective file and don't stop loading the
+    /// remaining files. Dropping the stream cancels all loads in progress.
+    pub fn info_many(
+        files: Vec<gio::File>,
+        concurrency: usize,
+    ) -> Pin<Box<dyn Stream<Item = (gio::File, Result<ImageDetails, Error>)> + Send>> {
+        let cancellable = gio::Cancellable::new();
+        let cancel_on_drop = CancelOnDrop(cancellable.clone());
+
+        let stream = futures_util::stream::iter(files)
+            .map(move |file| {
+                let mut loader = Loader::new(file.clone());
+                loader.cancellable(cancellable.clone());
+
+                async move {
+                    let details = loader.load().await.map(|image| image.details());
+                    (file, details)
+                }
+            })
+            .buffer_unordered(concurrency.max(1))
+            .map(move |result| {
+                // Keep guard alive as long as the stream exists
+                let _ = &cancel_on_drop;
+                result
+            });
+
+        Box::pin(stream)
+    }
+
+    /// Load thumbnails for the images in a directory concurrently
+    ///
+    /// The files in `dir` are enumerated and files that are not images
+    /// according to their content type or that no loader supports are
+    /// skipped. The first frame of each image is loaded with a [fit](Self::fit)
+    /// into `size` × `size` via [`FitMode::Contain`]. If the format is not
+    /// supported, an embedded preview is used like for
+    /// [`Loader::load_or_placeholder`].
+    ///
+    /// Loaders can ignore the scale of the frame request. Such frames are
+    /// downscaled afterwards, such that no side exceeds `size`. They are
+    /// returned as [`MemoryFormat::R8g8b8a8Premultiplied`].
+    ///
+    /// Loading starts while the directory is still being enumerated. At most
+    /// `concurrency` images are loaded at the same time. The results are
+    /// returned in the order in which they finish, not in the order of the
+    /// directory. Errors only affect the respective file. If enumerating the
+    /// directory fails, this error is returned for `dir`. Dropping the stream
+    /// cancels all loads in progress.
+    pub fn thumbnail_directory(
+        dir: gio::File,
+        size: u32,
+        concurrency: usize,
+    ) -> Pin<Box<dyn Stream<Item = (gio::File, Result<Frame, Error>)> + Send>> {
+        let cancellable = gio::Cancellable::new();
+        let cancel_on_drop = CancelOnDrop(cancellable.clone());
+
+        let stream = Self::image_files(dir.clone(), cancellable.clone())
+            .map(move |entry| {
+                let cancellable = cancellable.clone();
+                let dir = dir.clone();
+
+                async move {
+                    let file = match entry {
+                        Ok(file) => file,
+                        Err(err) => return Some((dir, Err(err))),
+                    };
+
+                    let mut loader = Loader::new(file.clone());
+                    loader
+                        .cancellable(cancellable)
+                        .fit(size, size, FitMode::Contain);
+
+                    let frame = match loader.load_or_placeholder().await {
+                        Ok((mut image, _)) => match image.next_frame().await {
+                            Ok(frame) => spawn_blocking(move || frame.fit_into(size))
+                                .await
+                                .and_then(|x| x),
+                            Err(err) => Err(err),
+                        },
+                        Err(err) if err.unsupported_format().is_some() => return None,
+                        Err(err) => Err(err),
+                    };
+
+                    Some((file, frame))
+                }
+            })
+            .buffer_unordered(concurrency.max(1))
+            .filter_map(std::future::ready)
+            .map(move |result| {
+                // Keep guard alive as long as the stream exists
+                let _ = &cancel_on_drop;
+                result
+            });
+
+        Box::pin(stream)
+    }
+
+    /// Load many images concurrently with a shared cancellable
+    ///
+    /// At most `concurrency` loaders are running at the same time. The
+    /// results are returned in the order in which they finish, together with
+    /// the index of the loader in `loaders`.
+    ///
+    /// Cancelling `cancellable` stops the whole batch:
+    ///
+    /// - The cancellables of the loaders that are in flight are cancelled as
+    ///   well and the loads return [`ErrorKind::Canceled`].
+    /// - All loader processes of the batch are killed via `SIGKILL` without
+    ///   waiting for a graceful shutdown. Images that have already been
+    ///   returned can therefore no longer load frames.
+    /// - Loaders that have not been started yet are dropped without spawning
+    ///   a process and don't return a result. The stream ends once the loads
+    ///   in flight have returned.
+    ///
+    /// Since the processes are killed, they are not shared with other loaders.
+    /// All loaders of the batch use a separate [`Pool`] instead of the one set
+    /// via [`Loader::pool`]. Dropping the stream cancels all loads in progress
+    /// like cancelling `cancellable`.
+    pub fn load_many(
+        loaders: Vec<Loader>,
+        concurrency: usize,
+        cancellable: impl IsA<gio::Cancellable>,
+    ) -> Pin<Box<dyn Stream<Item = (usize, Result<Image, Error>)> + Send>> {
+        let batch_cancellable = gio::Cancellable::new();
+        let cancel_on_drop = CancelOnDrop(batch_cancellable.clone());
+        let forward_cancellation =
+            ForwardCancellation::new(cancellable.upcast_ref(), &batch_cancellable);
+
+        #[cfg(feature = "external")]
+        let pool = Pool::new(crate::PoolConfig::new().retention_time(Duration::ZERO));
+        #[cfg(feature = "external")]
+        batch_cancellable.connect_cancelled(glib::clone!(
+            #[weak]
+            pool,
+            move |_| {
+                // The loads themselves are cancelled via their cancellables
+                if !util::try_spawn_detached(pool.kill_all()) {
+                    tracing::debug!("No runtime to kill the loader processes");
+                }
+            }
+        ));
+
+        let stream = futures_util::stream::iter(loaders.into_iter().enumerate())
+            .map(move |(index, mut loader)| {
+                let batch_cancellable = batch_cancellable.clone();
+                #[cfg(feature = "external")]
+                loader.pool(pool.clone());
+
+                async move {
+                    // Drop queued loaders without spawning a process
+                    if batch_cancellable.is_cancelled() {
+                        return None;
+                    }
+
+                    let loader_cancellable = loader.cancellable.clone();
+                    let tie = batch_cancellable
+                        .connect_cancelled(move |_| loader_cancellable.cancel())?;
+
+                    let result = loader.load().await;
+                    batch_cancellable.disconnect_cancelled(tie);
+
+                    Some((index, result))
+                }
+            })
+            .buffer_unordered(concurrency.max(1))
+            .filter_map(move |result| {
+                // Keep guards alive as long as the stream exists
+                let _ = (&cancel_on_drop, &forward_cancellation);
+                futures_util::future::ready(result)
+            });
+
+        Box::pin(stream)
+    }
+
+    /// Regular files in `dir` with an image content type
+    ///
+    /// Files are returned while the directory is still being enumerated. The
+    /// stream ends after the first error.
+    fn image_files(
+        dir: gio::File,
+        cancellable: gio::Cancellable,
+    ) -> impl Stream<Item = Result<gio::File, Error>> + Send + 'static {
+        util::blocking_stream(move |emit| {
+            let attributes = [
+                gio::FILE_ATTRIBUTE_STANDARD_NAME.as_str(),
+                gio::FILE_ATTRIBUTE_STANDARD_TYPE.as_str(),
+                gio::FILE_ATTRIBUTE_STANDARD_FAST_CONTENT_TYPE.as_str(),
+            ]
+            .join(",");
+
+            let enumerator = match dir.enumerate_children(
+                &attributes,
+                gio::FileQueryInfoFlags::NONE,
+                Some(&cancellable),
+            ) {
+                Ok(enumerator) => enumerator,
+                Err(err) => {
+                    emit(Err(err.into()));
+                    return;
+                }
+            };
+
+            for info in enumerator {
+                let info = match info {
+                    Ok(info) => info,
+                    Err(err) => {
+                        emit(Err(err.into()));
+                        return;
+                    }
+                };
+
+                let is_image = info
+                    .attribute_string(gio::FILE_ATTRIBUTE_STANDARD_FAST_CONTENT_TYPE)
+                    .and_then(|x| gio::content_type_get_mime_type(&x))
+                    .is_some_and(|x| x.starts_with("image/"));
+
+                if info.file_type() == gio::FileType::Regular
+                    && is_image
+                    && !emit(Ok(dir.child(info.name())))
+                {
+                    return;
+                }
+            }
+        })
+    }
+
+    /// New loader for an image that is embedded in the current one
+    ///
+    /// Keeps the limits, sandbox, and process pool, but drops the options
+    /// that only refer to the outer image, like [`Loader::fit`].
+    pub(crate) fn embedded(&self, data: Vec<u8>) -> Self {
+        let (process_context, process_ready) = ProcessContext::new();
+
+        Self {
+            input_size: None,
+            frame_stride: 1,
+            use_sidecars: false,
+            accepted_formats: Vec::new(),
+            planar: false,
+            warm_process: None,
+            process_context,
+            process_ready,
+            ..self.with_source(Source::Vec(data))
+        }
+    }
+
+    /// New loader with the same settings but a different source
+    ///
+    /// Sidecars and [`Loader::fit`] belong to the previous image and are
+    /// reset.
+    fn with_source(&self, source: Source) -> Self {
+        Self {
+            source,
+            pool: self.pool.clone(),
+            cancellable: self.cancellable.clone(),
+            use_expose_base_dir: self.use_expose_base_dir,
+            apply_transformations: self.apply_transformations,
+            sandbox_selector: self.sandbox_selector,
+            sandbox_wrapper: self.sandbox_wrapper.clone(),
+            memory_format_selection: self.memory_format_selection,
+            accepted_formats: self.accepted_formats.clone(),
+            limits: self.limits.clone(),
+            main_context_selector: self.main_context_selector.clone(),
+            scale_factor: self.scale_factor,
+            allow_partial: self.allow_partial,
+            raw_animation_frames: self.raw_animation_frames,
+            assume_adobe_cmyk: self.assume_adobe_cmyk,
+            timeout: self.timeout,
+            deadline: self.deadline,
+            fit: None,
+            frame_stride: self.frame_stride,
+            sidecars: Vec::new(),
+            use_sidecars: self.use_sidecars,
+            max_compression_ratio: self.max_compression_ratio,
+            input_size: self.input_size,
+            raw: self.raw,
+            planar: self.planar,
+            straight_alpha: self.straight_alpha,
+            sandbox_tmpdir: self.sandbox_tmpdir.clone(),
+            process_priority: self.process_priority,
+            mime_type: None,
+            mime_type_fallbacks: Vec::new(),
+            read_buffer_size: self.read_buffer_size,
+            read_ahead: self.read_ahead,
+            warm_process: self.warm_process.clone(),
+            process_context: self.process_context.clone(),
+            process_ready: self.process_ready.clone(),
+        }
+    }
+
     async fn load_internal(self, source: Source) -> Result<Image, Error> {
-        let loader_context =
-            ProcessorContext::new(source, self.use_expose_base_dir, &self.sandbox_selector).await?;
+        let mut sidecars = self.sidecars.clone();
+        if self.use_sidecars
+            && let Some(file) = source.file()
+        {
+            for sidecar in detect_sidecars(&file).await {
+                if !sidecars.iter().any(|x| x.equal(&sidecar)) {
+                    sidecars.push(sidecar);
+                }
+            }
+        }
 
+        let mut loader_context = ProcessorContext::new(
+            source,
+            self.use_expose_base_dir,
+            &sidecars,
+            self.sandbox_tmpdir.clone(),
+            &self.sandbox_selector,
+            self.mime_type.clone(),
+            self.read_buffer_size,
+        )
+        .await?;
+
+        loader_context.g_file_worker.set_read_ahead(self.read_ahead);
+        let xmp_sidecar = loader_context
+            .sidecars
+            .iter()
+            .find(|x| x.extension().is_some_and(|x| x.eq_ignore_ascii_case("xmp")))
+            .cloned();
+        loader_context.process_priority = self.process_priority;
+        if let Some(sandbox_wrapper) = &self.sandbox_wrapper {
+            loader_context.set_sandbox_wrapper(sandbox_wrapper.clone());
+        }
+
+        if let Some(warm_process) = &self.warm_process {
+            let process = config::ConfigEntry::Loader(loader_context.config_entry.clone())
+                .hash_value(
+                    loader_context.base_dir.clone(),
+                    loader_context.sidecars.clone(),
+                    loader_context.tmpdir.clone(),
+                    loader_context.sandbox_mechanism,
+                    loader_context.sandbox_wrapper.clone(),
+                    loader_context.process_priority,
+                );
+
+            if &process != warm_process {
+                return Err(ErrorKind::IncompatibleWarmLoader(loader_context.mime_type).err());
+            }
+        }
+
+        let spawn_start = Instant::now();
         let loader = loader_context
             .loader(self.pool.clone(), &self.cancellable)
-            .await?;
+            .await
+            .stage(Stage::Spawn)?;
+        self.process_ready.notify();
+
+        let mut stats = DecodeStats::default();
+        stats.spawn_time = spawn_start.elapsed();
 
         match loader {
             #[cfg(feature = "external")]
-            Processor::Binary(binary_loader) => self.load_internal_external(binary_loader).await,
+            Processor::Binary(binary_loader) => self
+                .load_internal_external(binary_loader, xmp_sidecar, stats)
+                .await
+                .stage(Stage::Init),
             #[cfg(feature = "builtin")]
-            Processor::Builtin(builtin) => self.load_internal_builtin(builtin).await,
+            Processor::Builtin(builtin) => self
+                .load_internal_builtin(builtin, xmp_sidecar, stats)
+                .await
+                .stage(Stage::Init),
         }
     }
 
@@ -193,14 +1257,26 @@ impl Loader {
     async fn load_internal_external(
         self,
         binary_loader: ExternalProcessor<LoaderProxy<'static>, SourceTransmission>,
+        xmp_sidecar: Option<PathBuf>,
+        mut stats: DecodeStats,
     ) -> Result<Image, Error> {
         tracing::debug!("Using external loader");
 
         let process = binary_loader.use_process();
+        let bytes_read = binary_loader.source_transmission.bytes_read();
+        let source_byte_size = self
+            .input_size
+            .or(binary_loader.source_transmission.file_size());
+        let init_start = Instant::now();
         let (remote_reader, file_read_future) =
             binary_loader.source_transmission.spawn_external()?;
 
-        let remote_image_future = process.init(&binary_loader.mime_type, remote_reader);
+        let remote_image_future = process.init(
+            &binary_loader.mime_type,
+            remote_reader,
+            self.initialization_details(),
+            self.deadline,
+        );
 
         // Drive reading the image source in parallel and shortcut if it errors
         let mut remote_image = remote_image_future
@@ -208,14 +1284,31 @@ impl Loader {
             .await
             .err_context(&process)?;
 
+        stats.init_time = init_start.elapsed();
+        stats.input_bytes = bytes_read.load(Ordering::Relaxed);
+
         remote_image.final_seal().await?;
 
         let mut details = remote_image.details.into_fungible();
+        validate_details(&details)?;
+        limit_icc_profile(
+            &mut details.color_icc_profile,
+            &mut details.warnings,
+            &self.limits,
+        );
+        details.source_byte_size = source_byte_size;
+        self.check_compression_ratio(&details, stats.input_bytes)?;
+        if let Some(xmp_sidecar) = xmp_sidecar {
+            apply_xmp_sidecar(&mut details, xmp_sidecar).await?;
+        }
 
-        if self.apply_transformations {
+        let raw_dimensions = (details.width, details.height);
+
+        if self.apply_transformations && !self.raw {
             match Image::transformation_orientation_internal(&details).rotate() {
                 Rotation::_90 | Rotation::_270 => {
                     std::mem::swap(&mut details.width, &mut details.height);
+                    details.intrinsic_aspect_ratio = details.intrinsic_aspect_ratio.map(|x| 1. / x);
                 }
                 _ => {}
             }
@@ -241,10 +1334,17 @@ impl Loader {
         });
 
         Ok(Image {
-            image_loader,
+            image_loader: Arc::new(image_loader),
             details: Arc::new(details),
+            raw_dimensions,
+            image_details: OnceLock::new(),
             loader: self,
             mime_type,
+            prefetched_frame: None,
+            pending_frame: Mutex::new(None),
+            animation_position: Default::default(),
+            stats: Arc::new(Mutex::new(stats)),
+            input_bytes: bytes_read,
         })
     }
 
@@ -252,6 +1352,8 @@ impl Loader {
     async fn load_internal_builtin<P: DBusProxy>(
         self,
         builtin: BuiltinProcessor<P, SourceTransmission>,
+        xmp_sidecar: Option<PathBuf>,
+        mut stats: DecodeStats,
     ) -> Result<Image, Error> {
         tracing::debug!("Using builtin loader '{}'", builtin.builtin.common().name());
 
@@ -262,9 +1364,13 @@ impl Loader {
             config::BuiltinProcessor::ImageRs(_) => {
                 init_function = Box::new(|stream, mime_type, details| {
                     glycin_image_rs::ImgLoader::load(stream, mime_type, details).map(
-                        |(decoder, details)| {
+                        |(mut decoder, details)| {
+                            let frame_cancellation = builtin_frame_cancellation(&mut decoder);
                             (
-                                ImageBuiltinLoader::ImageRs(Arc::new(Mutex::new(decoder))),
+                                (
+                                    ImageBuiltinLoader::ImageRs(Arc::new(Mutex::new(decoder))),
+                                    frame_cancellation,
+                                ),
                                 details,
                             )
                         },
@@ -275,9 +1381,13 @@ impl Loader {
             config::BuiltinProcessor::Test(_) => {
                 init_function = Box::new(|stream, mime_type, details| {
                     glycin_test::ImgDecoder::load(stream, mime_type, details).map(
-                        |(decoder, details)| {
+                        |(mut decoder, details)| {
+                            let frame_cancellation = builtin_frame_cancellation(&mut decoder);
                             (
-                                ImageBuiltinLoader::Test(Arc::new(Mutex::new(decoder))),
+                                (
+                                    ImageBuiltinLoader::Test(Arc::new(Mutex::new(decoder))),
+                                    frame_cancellation,
+                                ),
                                 details,
                             )
                         },
@@ -288,31 +1398,95 @@ impl Loader {
 
         let mime_type = builtin.mime_type.clone();
 
+        let bytes_read = builtin.source_transmission.bytes_read();
+        let source_byte_size = self.input_size.or(builtin.source_transmission.file_size());
+        let init_start = Instant::now();
         let (source_reader, file_read_future) = builtin.source_transmission.spawn_builtin();
 
+        // TODO: That should be something different?
+        let mut details = self.initialization_details();
+        details.accepts_cmyk = true;
+        details.sidecars = (!builtin.sidecars.is_empty()).then_some(builtin.sidecars);
+
         let remote_image_future = gio::spawn_blocking(move || {
-            init_function(
-                source_reader,
-                builtin.mime_type.to_string(),
-                // TODO: That should be something different?
-                glycin_utils::InitializationDetails::default(),
-            )
-            .map_err(|e| Error::from(e.into_loader_error()))
+            init_function(source_reader, builtin.mime_type.to_string(), details)
+                .map_err(|e| Error::from(e.into_loader_error()))
         })
         .map(|x| x.map_err(|e| ErrorKind::panic(e).err()));
 
-        let (image_loader, image_details) = remote_image_future
+        let ((image_loader, frame_cancellation), mut image_details) = remote_image_future
             .join_abort_on_error(file_read_future)
             .await??;
 
+        stats.init_time = init_start.elapsed();
+        stats.input_bytes = bytes_read.load(Ordering::Relaxed);
+
+        validate_details(&image_details)?;
+        limit_icc_profile(
+            &mut image_details.color_icc_profile,
+            &mut image_details.warnings,
+            &self.limits,
+        );
+        image_details.source_byte_size = source_byte_size;
+        self.check_compression_ratio(&image_details, stats.input_bytes)?;
+        if let Some(xmp_sidecar) = xmp_sidecar {
+            apply_xmp_sidecar(&mut image_details, xmp_sidecar).await?;
+        }
+
         Ok(Image {
-            image_loader: ImageLoader::Builtin(image_loader),
+            image_loader: Arc::new(ImageLoader::Builtin(image_loader, frame_cancellation)),
+            raw_dimensions: (image_details.width, image_details.height),
             details: Arc::new(image_details),
+            image_details: OnceLock::new(),
             loader: self,
             mime_type,
+            prefetched_frame: None,
+            pending_frame: Mutex::new(None),
+            animation_position: Default::default(),
+            stats: Arc::new(Mutex::new(stats)),
+            input_bytes: bytes_read,
         })
     }
 
+    /// Options that are passed on to the loader
+    fn initialization_details(&self) -> glycin_utils::InitializationDetails {
+        let mut details = glycin_utils::InitializationDetails::default();
+        details.scale_factor = self.scale_factor;
+        details.allow_partial = self.allow_partial;
+        details.raw_animation_frames = self.raw_animation_frames;
+        details.assume_adobe_cmyk = self.assume_adobe_cmyk;
+        details
+    }
+
+    /// Reject images whose decoded size is suspiciously large for the input
+    ///
+    /// The input size is the `source_byte_size` of the `details`, or the
+    /// number of bytes read if that is larger or unknown.
+    fn check_compression_ratio<B: ByteData>(
+        &self,
+        details: &glycin_utils::ImageDetails<B>,
+        bytes_read: u64,
+    ) -> Result<(), Error> {
+        let Some(max_ratio) = self.max_compression_ratio else {
+            return Ok(());
+        };
+
+        let input_size = details
+            .source_byte_size
+            .unwrap_or_default()
+            .max(bytes_read)
+            .max(1);
+
+        let decoded_size = details.width as f64 * details.height as f64 * 4.;
+        let ratio = decoded_size / input_size as f64;
+
+        if ratio > max_ratio {
+            return Err(ErrorKind::SuspectedDecompressionBomb { ratio, max_ratio }.err());
+        }
+
+        Ok(())
+    }
+
     /// Returns a list of mime types for which loaders are configured
     pub async fn supported_mime_types() -> Vec<MimeType> {
         config::Config::cached()
@@ -355,22 +1529,76 @@ impl Loader {
     ];
 }
 
+/// Cancels the cancellable when dropped
+pub(super) struct CancelOnDrop(pub(super) gio::Cancellable);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
+
+/// Cancels `target` when `source` is cancelled, until dropped
+pub(super) struct ForwardCancellation {
+    source: gio::Cancellable,
+    handler_id: Option<gio::CancelledHandlerId>,
+}
+
+impl ForwardCancellation {
+    pub(super) fn new(source: &gio::Cancellable, target: &gio::Cancellable) -> Self {
+        let handler_id = source.connect_cancelled(glib::clone!(
+            #[weak]
+            target,
+            move |_| target.cancel()
+        ));
+
+        Self {
+            source: source.clone(),
+            handler_id,
+        }
+    }
+}
+
+impl Drop for ForwardCancellation {
+    fn drop(&mut self) {
+        if let Some(handler_id) = self.handler_id.take() {
+            self.source.disconnect_cancelled(handler_id);
+        }
+    }
+}
+
 /// Image handle containing metadata and allowing frame requests
 #[derive(Debug)]
 pub struct Image {
     pub(crate) loader: Loader,
-    image_loader: ImageLoader,
+    image_loader: Arc<ImageLoader>,
     details: Arc<glycin_utils::ImageDetails<FungibleMemory>>,
+    /// Dimensions before the orientation is applied
+    raw_dimensions: (u32, u32),
+    /// Shared by all [`Image::details`] calls such that parsed metadata are
+    /// cached
+    image_details: OnceLock<ImageDetails>,
     mime_type: MimeType,
+    /// First frame if it had to be loaded early, for example for the ICC
+    /// profile
+    prefetched_frame: Option<Frame>,
+    /// Frame request started by [`Image::try_next_frame`]
+    pending_frame: Mutex<Option<FrameFuture>>,
+    stats: Arc<Mutex<DecodeStats>>,
+    /// Bytes read from the source, updated while the source is still read
+    input_bytes: Arc<AtomicU64>,
+    animation_position: Arc<Mutex<AnimationPosition>>,
 }
 
+type FrameFuture = Pin<Box<dyn Future<Output = Result<Frame, Error>> + Send>>;
+
 static_assertions::assert_impl_all!(Image: Send, Sync);
 
 impl Drop for Image {
     fn drop(&mut self) {
         #[cfg(feature = "external")]
         #[allow(irrefutable_let_patterns)]
-        if let ImageLoader::Binary(image_loader) = &self.image_loader {
+        if let ImageLoader::Binary(image_loader) = &*self.image_loader {
             let process = image_loader.process.clone();
             let path = self.frame_request_path();
             let loader_alive = std::mem::take(&mut *image_loader.usage_tracker.lock().unwrap());
@@ -391,10 +1619,94 @@ impl Image {
     /// Loads texture and information of the next frame. For single still
     /// images, this can only be called once. For animated images, this
     /// function will loop to the first frame, when the last frame is reached.
+    ///
+    /// If a frame request was already started via [`Image::try_next_frame`],
+    /// this waits for that request instead of starting a new one.
     pub fn next_frame<'a>(
         &'a mut self,
     ) -> Pin<Box<dyn Future<Output = Result<Frame, Error>> + 'a + Send>> {
-        self.specific_frame(FrameRequest::default())
+        if self.loader.frame_stride > 1 {
+            return Box::pin(self.next_frame_strided());
+        }
+
+        self.next_single_frame()
+    }
+
+    /// Returns the next frame and skips the frames set via
+    /// [`Loader::frame_stride`]
+    async fn next_frame_strided(&mut self) -> Result<Frame, Error> {
+        let mut frame = self.next_single_frame().await?;
+
+        // Still images have no frames to skip
+        let Some(mut delay) = frame.delay else {
+            return Ok(frame);
+        };
+
+        for _ in 1..self.loader.frame_stride {
+            match self.next_single_frame().await {
+                Ok(skipped) => delay += skipped.delay.unwrap_or_default(),
+                Err(err) if err.has_no_more_frames() => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        frame.delay = Some(delay);
+
+        Ok(frame)
+    }
+
+    fn next_single_frame<'a>(
+        &'a mut self,
+    ) -> Pin<Box<dyn Future<Output = Result<Frame, Error>> + 'a + Send>> {
+        if let Some(frame) = self.prefetched_frame.take() {
+            return Box::pin(async move { Ok(frame) });
+        }
+
+        if let Some(pending_frame) = self.take_pending_frame() {
+            return pending_frame;
+        }
+
+        self.specific_frame(self.default_frame_request())
+    }
+
+    /// Tries to obtain the next frame without waiting
+    ///
+    /// Returns [`Poll::Pending`] if the loader has not produced the next frame
+    /// yet. The first call starts the frame request, and every following
+    /// call drives the same request further until [`Poll::Ready`] is
+    /// returned. Afterwards, the next call starts a request for the frame
+    /// after that. This makes it possible to check for a frame once per
+    /// iteration of an existing loop, instead of awaiting
+    /// [`Image::next_frame`].
+    ///
+    /// No waker is registered. Hence, this function has to be called
+    /// repeatedly until the frame is ready. It must be called from an async
+    /// context, where the runtime that glycin uses for the D-Bus
+    /// communication is available.
+    ///
+    /// Calling [`Image::next_frame`] while a request is pending continues
+    /// that request. Calling [`Image::specific_frame`] discards it.
+    pub fn try_next_frame(&mut self) -> Poll<Result<Frame, Error>> {
+        if let Some(frame) = self.prefetched_frame.take() {
+            return Poll::Ready(Ok(frame));
+        }
+
+        let mut pending_frame = self
+            .take_pending_frame()
+            .unwrap_or_else(|| self.frame_future(self.default_frame_request(), true));
+
+        let result = pending_frame
+            .as_mut()
+            .poll(&mut Context::from_waker(Waker::noop()));
+
+        if result.is_pending() {
+            *self
+                .pending_frame
+                .get_mut()
+                .unwrap_or_else(PoisonError::into_inner) = Some(pending_frame);
+        }
+
+        result
     }
 
     /// Loads a specific frame
@@ -405,81 +1717,442 @@ impl Image {
         &'a mut self,
         frame_request: FrameRequest,
     ) -> Pin<Box<dyn Future<Output = Result<Frame, Error>> + 'a + Send>> {
-        Box::pin(async move {
-            let cancellable = self.loader.cancellable.clone();
+        // Loaders that don't know about auxiliary images would return an
+        // animation frame instead
+        if let Some(index) = frame_request.request.auxiliary_image
+            && index >= self.details.n_auxiliary_images.unwrap_or_default()
+        {
+            return Box::pin(async move { Err(ErrorKind::NoAuxiliaryImage(index).err()) });
+        }
 
-            self.specific_frame_internal(frame_request)
-                .make_cancellable(cancellable)
-                .enforce_timeout(self.loader.limits.inner.timeout)
-                .await
-        })
+        // Same for loaders that don't know about image items
+        if let Some(id) = frame_request.request.image_item
+            && !self
+                .details
+                .image_items
+                .iter()
+                .flatten()
+                .any(|(item_id, _)| *item_id == id)
+        {
+            return Box::pin(async move { Err(ErrorKind::NoImageItem(id).err()) });
+        }
+
+        // Auxiliary images, image items, and scans don't affect the animation
+        if frame_request.request.auxiliary_image.is_none()
+            && frame_request.request.image_item.is_none()
+            && frame_request.request.progressive_scan.is_none()
+        {
+            self.prefetched_frame = None;
+            self.take_pending_frame();
+        }
+
+        self.frame_future(frame_request, true)
     }
 
-    async fn specific_frame_internal(&self, frame_request: FrameRequest) -> Result<Frame, Error> {
-        let frame_request = frame_request.request;
+    /// Loads next frame without post-processing
+    ///
+    /// Returns the frame exactly as the loader provided it. The orientation
+    /// is not corrected, ICC profiles are not applied, and the memory format
+    /// and stride are not changed, independent of the loader settings. Only
+    /// CMYK data are converted to RGB. The frame details are the ones
+    /// reported by the loader. This is the same as enabling [`Loader::raw`]
+    /// for a single frame.
+    ///
+    /// This is meant for debugging loaders.
+    #[cfg(feature = "unstable")]
+    pub fn next_frame_raw<'a>(
+        &'a mut self,
+    ) -> Pin<Box<dyn Future<Output = Result<Frame, Error>> + 'a + Send>> {
+        self.prefetched_frame = None;
+        self.take_pending_frame();
 
-        match &self.image_loader {
-            #[cfg(feature = "external")]
-            ImageLoader::Binary(image_loader) => {
-                let process = image_loader.process.use_();
+        self.frame_future(FrameRequest::default(), false)
+    }
 
-                let frame = process
-                    .request_frame(frame_request, self)
-                    .await
-                    .err_context(&process)?;
+    /// Decode the first frame as a stream of rows
+    ///
+    /// Each item contains up to `max_rows` rows, starting with the top row of
+    /// the image. Only the rows that are currently processed have to be kept
+    /// in memory. This allows processing huge images, like scans or
+    /// panoramas, with constant memory usage. Not all loaders support this.
+    /// For unsupported images, the stream returns an error as first item.
+    ///
+    /// Since rows are returned in the order they are decoded, operations
+    /// that need random access to the pixels are not applied. The
+    /// orientation is not corrected, ICC profiles are not applied, and
+    /// [`Loader::fit`] and [`Loader::planar`] are ignored. The memory format
+    /// is converted according to [`Loader::accepted_memory_formats`].
+    ///
+    /// Rows can't be mixed with frames from the same image. Frames requested
+    /// after the stream has been used fail or return arbitrary rows.
+    pub fn scanlines(
+        &mut self,
+        max_rows: u32,
+    ) -> Pin<Box<dyn Stream<Item = Result<Scanlines, Error>> + Send + '_>> {
+        self.prefetched_frame = None;
+        self.take_pending_frame();
+
+        let frame_context = self.frame_context();
+        let max_rows = max_rows.max(1);
+        let cancellable = self.loader.cancellable.clone();
+        let timeout = self.loader.limits.inner.timeout;
+
+        let stream = futures_util::stream::unfold(Some((frame_context, 0)), move |state| {
+            let cancellable = cancellable.clone();
+            async move {
+                let (frame_context, first_row) = state?;
+
+                let result = frame_context
+                    .next_rows(first_row, max_rows)
+                    .make_cancellable(cancellable)
+                    .enforce_timeout(timeout)
+                    .await;
 
-                Frame::from_loader(frame, self).await
+                match result {
+                    Ok(Some(scanlines)) => {
+                        let next_row = first_row + scanlines.n_rows;
+                        Some((Ok(scanlines), Some((frame_context, next_row))))
+                    }
+                    Ok(None) => None,
+                    Err(err) => Some((Err(err), None)),
+                }
             }
-            #[cfg(feature = "builtin")]
-            ImageLoader::Builtin(builtin) => {
-                use glycin_utils::LocalMemory;
+        });
 
-                let editor_function: Box<dyn FnOnce() -> _ + Send>;
+        Box::pin(stream)
+    }
 
-                match builtin {
-                    #[cfg(feature = "builtin-image-rs")]
-                    ImageBuiltinLoader::ImageRs(loader) => {
-                        let loader: Arc<Mutex<glycin_image_rs::ImgLoader>> = loader.to_owned();
-                        editor_function = Box::new(move || {
-                            loader
-                                .lock()
-                                .unwrap()
-                                .specific_frame::<LocalMemory>(frame_request)
-                        });
-                    }
-                    #[cfg(feature = "builtin-test")]
-                    ImageBuiltinLoader::Test(editor) => {
-                        let editor = editor.to_owned();
-                        editor_function = Box::new(move || {
-                            editor
-                                .lock()
-                                .unwrap()
-                                .specific_frame::<LocalMemory>(frame_request)
-                        });
+    /// Decode the first frame scan by scan
+    ///
+    /// Progressive images, like progressive JPEGs, store a coarse version of
+    /// the whole image first and refine it with every following scan. Each
+    /// item of the stream is a frame of the full size, decoded from all scans
+    /// up to that point. The last item is the complete image. This allows to
+    /// show a preview of large images early, similar to how browsers display
+    /// them while loading.
+    ///
+    /// Progressive JPEGs typically contain 10 scans for color images and 6
+    /// for grayscale images, when written with libjpeg's default
+    /// progression. Baseline JPEGs and formats without progressive decoding
+    /// support yield a single frame. The number of scans is reported by
+    /// [`FrameDetails::n_progressive_scans`].
+    ///
+    /// The frames are post-processed as by [`Image::next_frame`]. The stream
+    /// does not advance animations.
+    pub fn progressive_frames(
+        &mut self,
+    ) -> Pin<Box<dyn Stream<Item = Result<Frame, Error>> + Send + '_>> {
+        self.prefetched_frame = None;
+        self.take_pending_frame();
+
+        let frame_context = self.frame_context();
+        let frame_request = self.default_frame_request();
+        let cancellable = self.loader.cancellable.clone();
+        let timeout = self.loader.limits.inner.timeout;
+
+        let stream = futures_util::stream::unfold(Some(1), move |state| {
+            let frame_context = frame_context.clone();
+            let frame_request = frame_request.clone();
+            let cancellable = cancellable.clone();
+            async move {
+                let scan = state?;
+
+                let result = frame_context
+                    .interruptible_frame(frame_request.progressive_scan(scan), true)
+                    .make_cancellable(cancellable)
+                    .enforce_timeout(timeout)
+                    .await;
+
+                match result {
+                    Ok(frame) => {
+                        let next_scan = frame
+                            .details()
+                            .n_progressive_scans()
+                            .filter(|n_scans| scan < *n_scans)
+                            .map(|_| scan + 1);
+                        Some((Ok(frame), next_scan))
                     }
+                    Err(err) => Some((Err(err), None)),
                 }
+            }
+        });
 
-                let frame = gio::spawn_blocking(|| {
-                    editor_function().map_err(|e| Error::from(e.into_loader_error()))
-                })
+        Box::pin(stream)
+    }
+
+    fn frame_context(&self) -> FrameContext {
+        FrameContext {
+            image_loader: self.image_loader.clone(),
+            details: self.details.clone(),
+            raw_dimensions: self.raw_dimensions,
+            limits: self.loader.limits.clone(),
+            apply_transformations: self.loader.apply_transformations,
+            raw: self.loader.raw,
+            memory_format_selection: self.loader.frame_memory_formats(),
+            accepted_formats: self.loader.frame_accepted_formats(),
+            planar: self.loader.planar,
+            deadline: self.loader.deadline,
+            stats: self.stats.clone(),
+            animation_position: self.animation_position.clone(),
+        }
+    }
+
+    /// Frame request for the next frame, including the [`Loader::fit`]
+    fn default_frame_request(&self) -> FrameRequest {
+        match self.loader.fit {
+            Some((width, height, fit_mode)) => self.fit_frame_request(width, height, fit_mode),
+            None => FrameRequest::default(),
+        }
+    }
+
+    /// Frame request that fits the next frame into `width` × `height`
+    fn fit_frame_request(&self, width: u32, height: u32, fit_mode: FitMode) -> FrameRequest {
+        let mut frame_request = FrameRequest::default();
+
+        // The fit refers to the oriented image, the frame request to the raw one
+        let rotation = if self.loader.apply_transformations && !self.loader.raw {
+            self.transformation_orientation().rotate()
+        } else {
+            Rotation::_0
+        };
+        let (scale, clip) =
+            fit_mode.oriented_frame_request(self.raw_dimensions, (width, height), rotation);
+        frame_request.request.scale = Some(scale);
+        frame_request.request.clip = clip;
+
+        frame_request
+    }
+
+    fn take_pending_frame(&mut self) -> Option<FrameFuture> {
+        self.pending_frame
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+    }
+
+    /// Frame request that does not borrow the image
+    fn frame_future(&self, frame_request: FrameRequest, post_process: bool) -> FrameFuture {
+        let frame_context = self.frame_context();
+        let cancellable = self.loader.cancellable.clone();
+        let timeout = self.loader.limits.inner.timeout;
+
+        Box::pin(async move {
+            frame_context
+                .interruptible_frame(frame_request, post_process)
+                .make_cancellable(cancellable)
+                .enforce_timeout(timeout)
                 .await
-                .map_err(|e| ErrorKind::panic(e))??;
+        })
+    }
+
+    /// Embedded ICC profile
+    ///
+    /// Returns the raw ICC profile as it is stored in the image file. Like
+    /// for applying profiles, the profile of the first frame takes
+    /// precedence over the profile in the image information, see
+    /// [`FrameDetails::color_icc_profile`]. The first frame is loaded to
+    /// obtain its profile. The next call to [`Image::next_frame`] returns
+    /// that frame without loading it again.
+    ///
+    /// The returned profile is independent from whether and how color
+    /// transformations are applied to the frames.
+    pub fn icc_profile<'a>(
+        &'a mut self,
+    ) -> Pin<Box<dyn Future<Output = Result<Option<Vec<u8>>, Error>> + 'a + Send>> {
+        Box::pin(async move {
+            let frame = self.next_frame().await?;
+
+            let icc_profile = frame
+                .details
+                .color_icc_profile
+                .as_deref()
+                .or(self.details.color_icc_profile.as_deref())
+                .map(<[u8]>::to_vec);
+            self.prefetched_frame = Some(frame);
+
+            Ok(icc_profile)
+        })
+    }
+
+    /// Perceptual hash of the next frame
+    ///
+    /// See [`Frame::perceptual_hash`]. The frame is requested with a small
+    /// scale and reduced precision, which loaders can use to decode it
+    /// faster. Like [`Image::specific_frame`], this advances animations.
+    pub fn perceptual_hash<'a>(
+        &'a mut self,
+    ) -> Pin<Box<dyn Future<Output = Result<u64, Error>> + 'a + Send>> {
+        Box::pin(async move {
+            let frame_request = self
+                .fit_frame_request(
+                    crate::perceptual_hash::FRAME_SIZE,
+                    crate::perceptual_hash::FRAME_SIZE,
+                    FitMode::Contain,
+                )
+                .reduced_precision(true);
+
+            self.specific_frame(frame_request).await?.perceptual_hash()
+        })
+    }
+
+    /// BlurHash of the next frame
+    ///
+    /// See [`Frame::blurhash`]. The frame is requested with a small scale and
+    /// reduced precision, which loaders can use to decode it faster. Like
+    /// [`Image::specific_frame`], this advances animations.
+    pub fn blurhash<'a>(
+        &'a mut self,
+        components_x: u32,
+        components_y: u32,
+    ) -> Pin<Box<dyn Future<Output = Result<String, Error>> + 'a + Send>> {
+        Box::pin(async move {
+            let frame_request = self
+                .fit_frame_request(
+                    crate::blurhash::MAX_GRID_SIZE,
+                    crate::blurhash::MAX_GRID_SIZE,
+                    FitMode::Contain,
+                )
+                .reduced_precision(true);
+
+            self.specific_frame(frame_request)
+                .await?
+                .blurhash(components_x, components_y)
+        })
+    }
+
+    /// Loads the next frame repeated as a pattern
+    ///
+    /// The frame is decoded once and repeated to fill a texture of
+    /// `width` × `height` pixels, starting at the top left corner. If the
+    /// size is not a multiple of the frame size, the tiles at the right and
+    /// bottom edges are cut off. This is useful for repeating backgrounds,
+    /// where a single texture is preferred over repeating it on the GPU.
+    ///
+    /// Fails with [`ErrorKind::TextureTooLarge`] if the resulting texture
+    /// would exceed the maximum texture size.
+    pub fn load_tiled<'a>(
+        &'a mut self,
+        width: u32,
+        height: u32,
+    ) -> Pin<Box<dyn Future<Output = Result<Frame, Error>> + 'a + Send>> {
+        Box::pin(async move {
+            let frame = self.next_frame().await?;
+            util::spawn_blocking(move || frame.tiled(width, height)).await?
+        })
+    }
+
+    /// Writes all remaining frames as individual image files
+    ///
+    /// The frames are encoded in the image format given by `mime_type`, for
+    /// example `image/png`, with the default encoding options of the format.
+    /// The files are named `frame_0000.png`, `frame_0001.png`, etc. within
+    /// `dir`. The number is padded to at least four digits, and more if
+    /// required for the number of frames. Existing files are overwritten.
+    ///
+    /// For still images, a single file is written. For animations, the export
+    /// stops after the last frame instead of looping. Frames that were
+    /// already obtained via [`Image::next_frame`] are not exported.
+    ///
+    /// Returns the number of written files.
+    pub fn export_frames<'a>(
+        &'a mut self,
+        dir: impl AsRef<Path>,
+        mime_type: MimeType,
+    ) -> Pin<Box<dyn Future<Output = Result<u32, Error>> + 'a + Send>> {
+        let dir = dir.as_ref().to_path_buf();
+
+        Box::pin(async move {
+            let extension = mime_type
+                .extension()
+                .or_else(|| mime_type.as_str().split('/').nth(1))
+                .unwrap_or_default()
+                .to_string();
+
+            let n_frames = self.details.n_animation_frames;
+            let padding = n_frames
+                .map_or(0, |n| n.saturating_sub(1).to_string().len())
+                .max(4);
+
+            let mut n_written: u32 = 0;
+
+            while n_frames.is_none_or(|n| n_written < n) {
+                let frame = match self.prefetched_frame.take() {
+                    Some(frame) => frame,
+                    None => match self
+                        .frame_future(FrameRequest::new().loop_animation(false), true)
+                        .await
+                    {
+                        Ok(frame) => frame,
+                        Err(err) if n_written > 0 && err.has_no_more_frames() => break,
+                        Err(err) => return Err(err),
+                    },
+                };
+
+                if frame.planes().is_some() {
+                    return Err(ErrorKind::PlanarFrame.err());
+                }
+
+                let mut creator = Creator::new(mime_type.clone()).await?;
+                creator.add_frame_with_stride(
+                    frame.width(),
+                    frame.height(),
+                    frame.stride(),
+                    frame.memory_format(),
+                    frame.buf_slice().to_vec(),
+                )?;
+                let data = creator.create().await?.data_full();
+
+                let path = dir.join(format!("frame_{n_written:0padding$}.{extension}"));
+                util::spawn_blocking(move || std::fs::write(path, data)).await??;
 
-                Frame::from_loader(frame, self).await
+                n_written += 1;
+
+                // Still images only have a single frame
+                if frame.delay().is_none() {
+                    break;
+                }
             }
+
+            Ok(n_written)
+        })
+    }
+
+    /// Timings and data sizes of loading the image and its frames
+    ///
+    /// See [`DecodeStats`] for details.
+    pub fn stats(&self) -> DecodeStats {
+        DecodeStats {
+            input_bytes: self.input_bytes.load(Ordering::Relaxed),
+            ..*self.stats.lock().unwrap_or_else(PoisonError::into_inner)
         }
     }
 
     /// Returns already obtained info
     pub fn details(&self) -> ImageDetails {
-        ImageDetails::new(self.details.clone())
+        self.image_details
+            .get_or_init(|| ImageDetails::new(self.details.clone(), self.raw_dimensions))
+            .clone()
+    }
+
+    /// Time at which the image was captured
+    ///
+    /// See [`ImageDetails::capture_time`].
+    pub fn capture_time(&self) -> Option<glib::DateTime> {
+        self.details().capture_time()
+    }
+
+    /// Shooting parameters of a photo
+    ///
+    /// See [`ImageDetails::camera_settings`].
+    pub fn camera_settings(&self) -> Option<CameraSettings> {
+        self.details().camera_settings()
     }
 
     /// Returns already obtained info
     #[cfg(feature = "external")]
     pub(crate) fn frame_request_path(&self) -> OwnedObjectPath {
         #[allow(irrefutable_let_patterns)]
-        if let ImageLoader::Binary(image_loader) = &self.image_loader {
+        if let ImageLoader::Binary(image_loader) = &*self.image_loader {
             image_loader.frame_request.clone()
         } else {
             todo!()
@@ -487,6 +2160,10 @@ impl Image {
     }
 
     /// Returns detected MIME type of the file
+    ///
+    /// If the image was loaded via one of the
+    /// [`Loader::as_mime_type_fallbacks`], this is the fallback MIME type
+    /// that succeeded.
     pub fn mime_type(&self) -> MimeType {
         self.mime_type.clone()
     }
@@ -505,11 +2182,11 @@ impl Image {
 
     /// Active sandbox mechanism
     pub fn active_sandbox_mechanism(&self) -> SandboxMechanism {
-        match &self.image_loader {
+        match &*self.image_loader {
             #[cfg(feature = "external")]
             ImageLoader::Binary(image_loader) => image_loader.active_sandbox_mechanism,
             #[cfg(feature = "builtin")]
-            ImageLoader::Builtin(_) => SandboxMechanism::NotSandboxed,
+            ImageLoader::Builtin(..) => SandboxMechanism::NotSandboxed,
         }
     }
 
@@ -522,7 +2199,7 @@ impl Image {
         Self::transformation_orientation_internal(&self.details)
     }
 
-    fn transformation_orientation_internal(
+    pub(crate) fn transformation_orientation_internal(
         details: &glycin_utils::ImageDetails<FungibleMemory>,
     ) -> Orientation {
         if let Some(orientation) = details.transformation_orientation {
@@ -551,7 +2228,406 @@ enum ImageLoader {
     #[cfg(feature = "external")]
     Binary(ImageExternalLoader),
     #[cfg(feature = "builtin")]
-    Builtin(ImageBuiltinLoader),
+    Builtin(ImageBuiltinLoader, Option<glycin_utils::FrameCancellation>),
+}
+
+/// Everything needed to load frames without borrowing the [`Image`]
+#[derive(Debug, Clone)]
+struct FrameContext {
+    image_loader: Arc<ImageLoader>,
+    details: Arc<glycin_utils::ImageDetails<FungibleMemory>>,
+    raw_dimensions: (u32, u32),
+    limits: Limits,
+    apply_transformations: bool,
+    raw: bool,
+    memory_format_selection: MemoryFormatSelection,
+    accepted_formats: Vec<MemoryFormat>,
+    planar: bool,
+    deadline: Option<Deadline>,
+    stats: Arc<Mutex<DecodeStats>>,
+    animation_position: Arc<Mutex<AnimationPosition>>,
+}
+
+/// Animation frames returned so far
+#[derive(Debug, Default)]
+struct AnimationPosition {
+    next_index: u64,
+    elapsed: Duration,
+}
+
+impl FrameContext {
+    /// Loads the frame unless [`FrameRequest::cancellable`] is cancelled
+    async fn interruptible_frame(
+        self,
+        frame_request: FrameRequest,
+        post_process: bool,
+    ) -> Result<Frame, Error> {
+        let Some(frame_cancellable) = frame_request.cancellable.clone() else {
+            return self.specific_frame(frame_request, post_process).await;
+        };
+
+        if frame_cancellable.is_cancelled() {
+            return Err(ErrorKind::Canceled(None).err());
+        }
+
+        let image_loader = self.image_loader.clone();
+        let frame = Box::pin(self.specific_frame(frame_request, post_process));
+
+        match futures_util::future::select(frame_cancellable.future(), frame).await {
+            futures_util::future::Either::Left(_) => {
+                Self::interrupt_frame(&image_loader).await;
+                Err(ErrorKind::Canceled(None).err())
+            }
+            futures_util::future::Either::Right((frame, _)) => frame,
+        }
+    }
+
+    /// Stops decoding the current frame
+    ///
+    /// Falls back to killing the loader process if the loader does not
+    /// support interrupting frames.
+    async fn interrupt_frame(image_loader: &ImageLoader) {
+        match image_loader {
+            #[cfg(feature = "external")]
+            ImageLoader::Binary(image_loader) => {
+                let process = image_loader.process.use_();
+
+                match process
+                    .cancel_frame(image_loader.frame_request.clone())
+                    .await
+                {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        tracing::debug!("Loader can't interrupt frames, killing process");
+                        process.kill();
+                    }
+                    Err(err) => {
+                        tracing::debug!("Failed to interrupt frame, killing process: {err}");
+                        process.kill();
+                    }
+                }
+            }
+            #[cfg(feature = "builtin")]
+            ImageLoader::Builtin(_, frame_cancellation) => {
+                // Loaders without support finish the frame in the background
+                if let Some(frame_cancellation) = frame_cancellation {
+                    frame_cancellation.cancel();
+                }
+            }
+        }
+    }
+
+    fn add_decode_time(&self, decode_start: Instant) {
+        self.stats
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .decode_time += decode_start.elapsed();
+    }
+
+    /// Sets index and presentation time of an animation frame
+    fn advance_animation(&self, frame: &mut Frame) {
+        let mut position = self
+            .animation_position
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        frame.frame_index = position.next_index;
+        frame.presentation_time = position.elapsed;
+
+        position.next_index += 1;
+        position.elapsed += frame.delay.unwrap_or_default();
+    }
+
+    fn add_output_bytes(&self, frame: &Frame) {
+        self.stats
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .output_bytes += frame.buffer.len() as u64;
+    }
+
+    async fn specific_frame(
+        self,
+        frame_request: FrameRequest,
+        post_process: bool,
+    ) -> Result<Frame, Error> {
+        let frame_request = frame_request.request;
+        // Auxiliary images and image items don't affect the animation
+        let advances_animation = frame_request.auxiliary_image.is_none()
+            && frame_request.image_item.is_none()
+            && frame_request.progressive_scan.is_none();
+
+        match &*self.image_loader {
+            #[cfg(feature = "external")]
+            ImageLoader::Binary(image_loader) => {
+                let process = image_loader.process.use_();
+
+                let decode_start = Instant::now();
+                let frame = process
+                    .request_frame(
+                        frame_request,
+                        image_loader.frame_request.clone(),
+                        self.deadline,
+                    )
+                    .await
+                    .err_context(&process)
+                    .stage(Stage::Frame)?;
+                self.add_decode_time(decode_start);
+
+                let mut frame = Frame::from_loader(frame, &self, post_process)
+                    .await
+                    .stage(Stage::Frame)?;
+                self.add_output_bytes(&frame);
+                if advances_animation {
+                    self.advance_animation(&mut frame);
+                }
+
+                Ok(frame)
+            }
+            #[cfg(feature = "builtin")]
+            ImageLoader::Builtin(builtin, frame_cancellation) => {
+                use glycin_utils::LocalMemory;
+
+                reset_frame_cancellation(frame_cancellation);
+
+                let editor_function: Box<dyn FnOnce() -> _ + Send>;
+
+                match builtin {
+                    #[cfg(feature = "builtin-image-rs")]
+                    ImageBuiltinLoader::ImageRs(loader) => {
+                        let loader: Arc<Mutex<glycin_image_rs::ImgLoader>> = loader.to_owned();
+                        editor_function = Box::new(move || {
+                            let mut loader = loader.lock().unwrap();
+                            loader.specific_frame::<LocalMemory>(frame_request)
+                        });
+                    }
+                    #[cfg(feature = "builtin-test")]
+                    ImageBuiltinLoader::Test(editor) => {
+                        let editor = editor.to_owned();
+                        editor_function = Box::new(move || {
+                            let mut editor = editor.lock().unwrap();
+                            editor.specific_frame::<LocalMemory>(frame_request)
+                        });
+                    }
+                }
+
+                let decode_start = Instant::now();
+                let frame = gio::spawn_blocking(|| {
+                    editor_function().map_err(|e| Error::from(e.into_loader_error()))
+                })
+                .await
+                .map_err(|e| ErrorKind::panic(e).err())
+                .and_then(|x| x)
+                .stage(Stage::Frame)?;
+                self.add_decode_time(decode_start);
+
+                let mut frame = Frame::from_loader(frame, &self, post_process)
+                    .await
+                    .stage(Stage::Frame)?;
+                self.add_output_bytes(&frame);
+                if advances_animation {
+                    self.advance_animation(&mut frame);
+                }
+
+                Ok(frame)
+            }
+        }
+    }
+
+    /// Format into which data in `memory_format` have to be converted
+    ///
+    /// With [`Loader::accepted_formats`], the format is kept if it's accepted
+    /// and the most preferred format is used otherwise.
+    fn target_memory_format(&self, memory_format: MemoryFormat) -> Option<MemoryFormat> {
+        if let Some(preferred) = self.accepted_formats.first() {
+            let accepted = self.accepted_formats.contains(&memory_format);
+            return Some(if accepted { memory_format } else { *preferred });
+        }
+
+        // CMYK is converted even if no formats are selected
+        self.memory_format_selection
+            .best_format_for(memory_format)
+            .or_else(|| {
+                memory_format
+                    .is_cmyk()
+                    .then_some(memory_format.without_cmyk())
+            })
+    }
+
+    /// Rows following `first_row`, `None` once all rows have been returned
+    async fn next_rows(&self, first_row: u32, max_rows: u32) -> Result<Option<Scanlines>, Error> {
+        let rows_request = glycin_utils::RowsRequest::new(max_rows);
+
+        let result = match &*self.image_loader {
+            #[cfg(feature = "external")]
+            ImageLoader::Binary(image_loader) => {
+                let process = image_loader.process.use_();
+
+                let decode_start = Instant::now();
+                let rows = process
+                    .request_rows(
+                        rows_request,
+                        image_loader.frame_request.clone(),
+                        self.deadline,
+                    )
+                    .await
+                    .err_context(&process);
+                self.add_decode_time(decode_start);
+
+                match rows {
+                    Ok(rows) => self.scanlines(rows, first_row, max_rows).await,
+                    Err(err) => Err(err),
+                }
+            }
+            #[cfg(feature = "builtin")]
+            ImageLoader::Builtin(builtin, _) => {
+                use glycin_utils::LocalMemory;
+
+                let rows_function: Box<dyn FnOnce() -> _ + Send>;
+
+                match builtin {
+                    #[cfg(feature = "builtin-image-rs")]
+                    ImageBuiltinLoader::ImageRs(loader) => {
+                        let loader: Arc<Mutex<glycin_image_rs::ImgLoader>> = loader.to_owned();
+                        rows_function = Box::new(move || {
+                            let mut loader = loader.lock().unwrap();
+                            loader.next_rows::<LocalMemory>(rows_request)
+                        });
+                    }
+                    #[cfg(feature = "builtin-test")]
+                    ImageBuiltinLoader::Test(editor) => {
+                        let editor = editor.to_owned();
+                        rows_function = Box::new(move || {
+                            let mut editor = editor.lock().unwrap();
+                            editor.next_rows::<LocalMemory>(rows_request)
+                        });
+                    }
+                }
+
+                let decode_start = Instant::now();
+                let rows = gio::spawn_blocking(|| {
+                    rows_function().map_err(|e| Error::from(e.into_loader_error()))
+                })
+                .await
+                .map_err(|e| ErrorKind::panic(e).err())
+                .and_then(|x| x);
+                self.add_decode_time(decode_start);
+
+                match rows {
+                    Ok(rows) => self.scanlines(rows, first_row, max_rows).await,
+                    Err(err) => Err(err),
+                }
+            }
+        };
+
+        match result {
+            Err(err) if err.has_no_more_frames() => Ok(None),
+            result => result.map(Some).stage(Stage::Frame),
+        }
+    }
+
+    async fn scanlines<B: ByteData>(
+        &self,
+        mut rows: glycin_utils::Frame<B>,
+        first_row: u32,
+        max_rows: u32,
+    ) -> Result<Scanlines, Error> {
+        rows.initial_seal().await?;
+
+        validate_frame(&rows, &self.limits)?;
+
+        let n_rows = rows.height;
+        if rows.width != self.raw_dimensions.0
+            || n_rows > max_rows
+            || n_rows > self.raw_dimensions.1.saturating_sub(first_row)
+        {
+            return Err(ErrorKind::UnexpectedRows {
+                first_row,
+                rows: format!("{:?}", rows.desc()),
+            }
+            .err());
+        }
+
+        let mut rows = rows.into_fungible();
+
+        if let Some(target_format) = self.target_memory_format(rows.memory_format)
+            && rows.memory_format != target_format
+        {
+            rows = util::spawn_blocking(move || {
+                glycin_utils::editing::change_memory_format(&mut rows, target_format)?;
+                Ok::<_, Error>(rows)
+            })
+            .await??;
+        }
+
+        rows.final_seal().await?;
+
+        Ok(Scanlines {
+            buffer: rows.texture.into_gbytes()?,
+            first_row,
+            width: rows.width,
+            n_rows,
+            stride: rows.stride,
+            memory_format: rows.memory_format,
+        })
+    }
+}
+
+/// Existing XMP sidecars of `file`, see [`Loader::use_sidecars`]
+async fn detect_sidecars(file: &gio::File) -> Vec<gio::File> {
+    let (Some(path), Some(file_name)) = (file.path(), file.basename()) else {
+        return Vec::new();
+    };
+
+    // For each sidecar name, the extensions that are tried in that order
+    let mut candidates = Vec::new();
+
+    candidates.push(["xmp", "XMP"].map(|extension| {
+        let mut name = file_name.clone().into_os_string();
+        name.push(".");
+        name.push(extension);
+        path.with_file_name(name)
+    }));
+
+    if path.extension().is_some() {
+        candidates.push(["xmp", "XMP"].map(|extension| path.with_extension(extension)));
+    }
+
+    util::spawn_blocking(move || {
+        candidates
+            .into_iter()
+            .filter_map(|x| {
+                x.into_iter()
+                    // Don't use an XMP file as its own sidecar
+                    .find(|x| *x != path && x.is_file())
+            })
+            .map(gio::File::for_path)
+            .collect()
+    })
+    .await
+    .unwrap_or_default()
+}
+
+/// Hands a [`glycin_utils::FrameCancellation`] to loaders that support it
+#[cfg(feature = "builtin")]
+fn builtin_frame_cancellation(
+    loader: &mut impl LoaderImplementation,
+) -> Option<glycin_utils::FrameCancellation> {
+    let frame_cancellation = glycin_utils::FrameCancellation::default();
+    loader
+        .frame_cancellation(frame_cancellation.clone())
+        .then_some(frame_cancellation)
+}
+
+/// Resets the cancellation when a frame is requested
+///
+/// This happens before waiting for the loader lock, such that a cancellation
+/// sent in the meantime is not lost. A previous, interrupted frame that still
+/// holds the lock might then run to completion.
+#[cfg(feature = "builtin")]
+fn reset_frame_cancellation(frame_cancellation: &Option<glycin_utils::FrameCancellation>) {
+    if let Some(frame_cancellation) = frame_cancellation {
+        frame_cancellation.reset();
+    }
 }
 
 #[cfg(feature = "external")]
@@ -582,16 +2658,23 @@ impl std::fmt::Debug for ImageBuiltinLoader {
 #[derive(Debug, Clone)]
 pub struct ImageDetails {
     inner: Arc<glycin_utils::ImageDetails<FungibleMemory>>,
+    raw_dimensions: (u32, u32),
     metadata: Arc<OnceLock<gufo::Metadata>>,
+    xmp: Arc<OnceLock<Option<crate::xmp::Xmp>>>,
 }
 
 static_assertions::assert_impl_all!(ImageDetails: Send, Sync);
 
 impl ImageDetails {
-    fn new(inner: Arc<glycin_utils::ImageDetails<FungibleMemory>>) -> Self {
+    pub(crate) fn new(
+        inner: Arc<glycin_utils::ImageDetails<FungibleMemory>>,
+        raw_dimensions: (u32, u32),
+    ) -> Self {
         Self {
             inner,
+            raw_dimensions,
             metadata: Default::default(),
+            xmp: Default::default(),
         }
     }
 
@@ -603,25 +2686,249 @@ impl ImageDetails {
         self.inner.height
     }
 
+    /// Width of the image as stored in the file
+    ///
+    /// The raw dimensions match the unoriented sensor data. They are the
+    /// dimensions of frames if [`Loader::apply_transformations`] is disabled.
+    pub fn raw_width(&self) -> u32 {
+        self.raw_dimensions.0
+    }
+
+    /// Height of the image as stored in the file
+    ///
+    /// See [`raw_width`](Self::raw_width).
+    pub fn raw_height(&self) -> u32 {
+        self.raw_dimensions.1
+    }
+
+    /// Width of the image after the orientation is applied
+    ///
+    /// If the [transformation orientation](Image::transformation_orientation)
+    /// rotates by 90° or 270°, this is the [raw height](Self::raw_height),
+    /// otherwise the [raw width](Self::raw_width). The oriented dimensions
+    /// are the dimensions of frames if [`Loader::apply_transformations`] is
+    /// enabled. This allows to lay out the image before the first frame is
+    /// loaded.
+    pub fn oriented_width(&self) -> u32 {
+        self.oriented_dimensions().0
+    }
+
+    /// Height of the image after the orientation is applied
+    ///
+    /// See [`oriented_width`](Self::oriented_width).
+    pub fn oriented_height(&self) -> u32 {
+        self.oriented_dimensions().1
+    }
+
+    fn oriented_dimensions(&self) -> (u32, u32) {
+        let (width, height) = self.raw_dimensions;
+        match Image::transformation_orientation_internal(&self.inner).rotate() {
+            Rotation::_90 | Rotation::_270 => (height, width),
+            _ => (width, height),
+        }
+    }
+
+    /// Intrinsic width divided by height
+    ///
+    /// For raster images, this is derived from [`width`](Self::width) and
+    /// [`height`](Self::height). For vector images, the loader provides it,
+    /// for example from the `viewBox` of SVGs without a fixed size. This
+    /// allows to choose a render size before loading a frame.
+    ///
+    /// Returns `None` if the aspect ratio can't be determined.
+    pub fn intrinsic_aspect_ratio(&self) -> Option<f64> {
+        self.inner
+            .intrinsic_aspect_ratio
+            .or_else(|| {
+                (self.inner.height > 0).then(|| self.inner.width as f64 / self.inner.height as f64)
+            })
+            .filter(|x| x.is_finite() && *x > 0.)
+    }
+
+    /// Number of animation frames
+    ///
+    /// Only set if the loader can determine it without decoding the frames.
+    pub fn n_animation_frames(&self) -> Option<u32> {
+        self.inner.n_animation_frames
+    }
+
+    /// Number of auxiliary images
+    ///
+    /// Auxiliary images can be loaded via [`FrameRequest::auxiliary_image`].
+    pub fn n_auxiliary_images(&self) -> Option<u32> {
+        self.inner.n_auxiliary_images
+    }
+
+    /// Items stored in the image container
+    ///
+    /// Formats like HEIF can store several images as items, of which one is
+    /// marked as primary. Other items can be thumbnails, depth maps, or
+    /// alpha planes. The items can be loaded via
+    /// [`FrameRequest::image_item`]. Without that option, the primary item
+    /// is always decoded.
+    ///
+    /// This is independent of animation frames and of
+    /// [auxiliary images](Self::n_auxiliary_images). Auxiliary images are
+    /// also listed here if the loader provides item IDs for them. The list is
+    /// empty if the loader doesn't provide items.
+    pub fn image_items(&self) -> Vec<ImageItem> {
+        self.inner
+            .image_items
+            .iter()
+            .flatten()
+            .map(|(id, role)| ImageItem {
+                id: *id,
+                role: ImageItemRole::new(role),
+            })
+            .collect()
+    }
+
+    /// Whether the image contains pixels that are not fully opaque
+    ///
+    /// This differs from having an alpha channel: Images with an alpha
+    /// channel can still be completely opaque. `Some(false)` guarantees that
+    /// all pixels are opaque, such that rendering paths for opaque content
+    /// can be used. Loaders only report this if it's cheap to know, for
+    /// example if the format has no alpha channel. Otherwise, `None` is
+    /// returned since answering it would require scanning all pixels.
+    pub fn has_transparency(&self) -> Option<bool> {
+        self.inner.has_transparency
+    }
+
+    /// Caveats reported by the loader, like ignored metadata
+    ///
+    /// Warnings are informational and don't mean that loading failed. A viewer
+    /// can use them to show a subtle indicator that the image might not be
+    /// displayed as intended.
+    pub fn warnings(&self) -> &[String] {
+        self.inner.warnings.as_deref().unwrap_or_default()
+    }
+
     /// A textual representation of the image format
+    ///
+    /// This is the name of the format as users know it, like `"AVIF"`. Unlike
+    /// the MIME type, it can contain details about the variant of the format.
+    /// The container that holds the image data is reported separately by
+    /// [`info_container_format`](Self::info_container_format).
     pub fn info_format_name(&self) -> Option<&str> {
         self.inner.info_format_name.as_deref()
     }
 
+    /// Generic container format the image data are stored in
+    ///
+    /// For example, `"ISOBMFF"` for AVIF and HEIC or `"RIFF"` for WebP.
+    /// Returns `None` for formats that don't use a generic container or if
+    /// the loader doesn't report it.
+    pub fn info_container_format(&self) -> Option<&str> {
+        self.inner.info_container_format.as_deref()
+    }
+
+    /// Size of the image source in bytes
+    ///
+    /// Known for files on file systems that report the size and for
+    /// [`Loader::new_bytes`] and [`Loader::new_vec`]. Returns `None` for
+    /// streams.
+    pub fn source_byte_size(&self) -> Option<u64> {
+        self.inner.source_byte_size
+    }
+
     pub fn info_dimensions_text(&self) -> Option<&str> {
         self.inner.info_dimensions_text.as_deref()
     }
 
-    pub fn metadata_exif(&self) -> Option<&[u8]> {
-        self.inner.metadata_exif.as_deref()
+    pub fn metadata_exif(&self) -> Option<&[u8]> {
+        self.inner.metadata_exif.as_deref()
+    }
+
+    pub fn transformation_orientation(&self) -> Option<Orientation> {
+        self.inner.transformation_orientation
+    }
+
+    pub fn metadata_xmp(&self) -> Option<&[u8]> {
+        self.inner.metadata_xmp.as_deref()
+    }
+
+    /// Parsed XMP packet, only parsed on first use
+    pub(crate) fn xmp(&self) -> Option<&crate::xmp::Xmp> {
+        self.xmp
+            .get_or_init(|| crate::xmp::Xmp::parse(self.metadata_xmp()?))
+            .as_ref()
+    }
+
+    /// Point of interest in the image
+    ///
+    /// Read from the Google `GFocus:FocalPointX` and `GFocus:FocalPointY` XMP
+    /// properties. The `(x, y)` coordinates are normalized to the range from
+    /// `0.0` to `1.0`, where `(0.0, 0.0)` is the top-left corner of the image
+    /// as stored, before any transformations are applied.
+    pub fn focal_point(&self) -> Option<(f64, f64)> {
+        self.xmp()?.focal_point()
+    }
+
+    /// Location of the video embedded in a motion photo
+    ///
+    /// Motion photos, also called live photos, store a short video after the
+    /// image data. Returns the `(offset, length)` of the video in bytes,
+    /// relative to the start of the file. The image data are not affected,
+    /// glycin doesn't decode or play the video. Applications have to read the
+    /// byte range from the file themselves.
+    ///
+    /// The video is detected via the XMP properties `GCamera:MotionPhoto`
+    /// with the `Item:Length` of the `Container:Item` that has the
+    /// `Item:Semantic` `MotionPhoto`, or via the older `GCamera:MicroVideo`
+    /// with `GCamera:MicroVideoOffset`. Returns `None` if these properties
+    /// are absent or the [`source_byte_size`](Self::source_byte_size) is
+    /// unknown.
+    pub fn motion_photo(&self) -> Option<(u64, u64)> {
+        let length = self.xmp()?.motion_photo_length()?;
+        let offset = self.source_byte_size()?.checked_sub(length)?;
+
+        Some((offset, length))
+    }
+
+    /// Crop suggested by the image's author
+    ///
+    /// Read from the crop of the Adobe Camera Raw settings (`crs:CropLeft` etc.)
+    /// in the XMP data. Rotated crops are ignored. Returns normalized
+    /// `(x, y, width, height)` in the same coordinate system as
+    /// [`ImageDetails::focal_point`].
+    pub fn suggested_crop(&self) -> Option<(f64, f64, f64, f64)> {
+        self.xmp()?.suggested_crop()
+    }
+
+    /// Time at which the image was captured
+    ///
+    /// Read from the Exif `DateTimeOriginal` tag, with the offset from UTC
+    /// taken from `OffsetTimeOriginal`. Falls back to the XMP
+    /// `exif:DateTimeOriginal`, `photoshop:DateCreated`, or `xmp:CreateDate`
+    /// properties. Times without offset are interpreted in the local time
+    /// zone. The file's modification time is never used.
+    pub fn capture_time(&self) -> Option<glib::DateTime> {
+        let exif = crate::exif::date_time_original(self.metadata());
+        if let Some(date_time) = exif.as_deref().and_then(util::date_time_from_iso8601) {
+            return Some(date_time);
+        }
+
+        util::date_time_from_iso8601(self.xmp()?.capture_time()?)
     }
 
-    pub fn transformation_orientation(&self) -> Option<Orientation> {
-        self.inner.transformation_orientation
+    /// Shooting parameters of a photo
+    ///
+    /// Read from the Exif tags `PhotographicSensitivity` (ISO),
+    /// `FNumber`, `ExposureTime`, `FocalLength`, and `LensModel`. Fields are
+    /// `None` if the tag is missing or invalid. Returns `None` if none of the
+    /// tags are set.
+    pub fn camera_settings(&self) -> Option<CameraSettings> {
+        crate::exif::camera_settings(self.metadata())
     }
 
-    pub fn metadata_xmp(&self) -> Option<&[u8]> {
-        self.inner.metadata_xmp.as_deref()
+    /// ICC profile that applies to the whole image
+    ///
+    /// See [`Image::icc_profile`] for also considering the first frame. This
+    /// profile is informational: Only the profiles of the frames, see
+    /// [`FrameDetails::color_icc_profile`], are applied.
+    pub fn color_icc_profile(&self) -> Option<&[u8]> {
+        self.inner.color_icc_profile.as_deref()
     }
 
     pub fn metadata_key_value(&self) -> Option<&std::collections::BTreeMap<String, String>> {
@@ -659,6 +2966,48 @@ impl ImageDetails {
     }
 }
 
+/// Item in an image container
+///
+/// See [`ImageDetails::image_items`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ImageItem {
+    /// ID to use with [`FrameRequest::image_item`]
+    pub id: u32,
+    pub role: ImageItemRole,
+}
+
+/// Role of an [`ImageItem`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ImageItemRole {
+    /// The image that is decoded by default
+    Primary,
+    /// Further image that is not the primary one
+    Image,
+    Thumbnail,
+    Depth,
+    Alpha,
+    /// Other auxiliary image
+    Auxiliary,
+    /// Role not known to this version of glycin
+    Other(String),
+}
+
+impl ImageItemRole {
+    fn new(role: &str) -> Self {
+        match role {
+            "primary" => Self::Primary,
+            "image" => Self::Image,
+            "thumbnail" => Self::Thumbnail,
+            "depth" => Self::Depth,
+            "alpha" => Self::Alpha,
+            "auxiliary" => Self::Auxiliary,
+            other => Self::Other(other.to_string()),
+        }
+    }
+}
+
 /// A frame of an image often being the complete image
 #[derive(Debug, Clone)]
 pub struct Frame {
@@ -672,10 +3021,48 @@ pub struct Frame {
     pub(crate) details: Arc<glycin_utils::FrameDetails<FungibleMemory>>,
     pub(crate) image_details: ImageDetails,
     pub(crate) color_state: ColorState,
+    pub(crate) frame_index: u64,
+    pub(crate) presentation_time: Duration,
+    pub(crate) planes: Option<Vec<Plane>>,
+    pub(crate) applied_transformations: AppliedTransforms,
+    /// Sealed memfd that contains `buffer`, see [`Frame::as_memfd`]
+    ///
+    /// This is the memfd the loader sent, not a duplicate. Clones of the
+    /// frame share it.
+    pub(crate) memfd: Option<Arc<OwnedFd>>,
 }
 
 static_assertions::assert_impl_all!(Frame: Send, Sync);
 
+/// Transformations glycin applied to a frame
+///
+/// See [`Frame::applied_transformations`]. All flags are `false` for frames
+/// loaded with [`Loader::raw`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct AppliedTransforms {
+    /// The frame was rotated or flipped according to the Exif orientation
+    ///
+    /// Never set if [`Loader::apply_transformations`] is disabled or the
+    /// loader already applied the orientation.
+    pub orientation: bool,
+    /// The colors were converted from the embedded ICC profile to sRGB
+    ///
+    /// The frame's [`ColorState`] is [`ColorState::Srgb`] in this case.
+    pub icc_to_srgb: bool,
+    /// The memory format differs from the one the loader delivered
+    ///
+    /// This happens for formats not accepted via
+    /// [`Loader::accepted_memory_formats`] and for formats that the ICC
+    /// profile can't be applied to.
+    pub memory_format_changed: bool,
+    /// HDR content was tone mapped to SDR
+    ///
+    /// glycin currently doesn't tone map frames, such that this is always
+    /// `false`. HDR frames keep their [`ColorState`].
+    pub tone_mapped: bool,
+}
+
 impl Frame {
     pub fn buf_bytes(&self) -> glib::Bytes {
         self.buffer.clone()
@@ -685,6 +3072,171 @@ impl Frame {
         self.buffer.as_ref()
     }
 
+    /// Sealed memfd that contains the frame's data
+    ///
+    /// The memfd has the same content as [`Frame::buf_bytes`]. This allows
+    /// passing the data to other processes or mapping it directly without
+    /// copying. The memfd is sealed against writing, growing, and shrinking,
+    /// and no further seals can be added. Therefore, its content and size
+    /// can't change while it's in use.
+    ///
+    /// Returns `None` if the data are not backed by a memfd. This is the case
+    /// for frames from builtin loaders and for frames that glycin had to
+    /// transform, for example, to apply the orientation or change the memory
+    /// format.
+    ///
+    /// The memfd is only duplicated when needed, via
+    /// [`BorrowedFd::try_clone_to_owned`], for example, to keep it beyond the
+    /// frame's lifetime.
+    pub fn as_memfd(&self) -> Option<BorrowedFd<'_>> {
+        self.memfd.as_deref().map(AsFd::as_fd)
+    }
+
+    /// Perceptual hash of the frame's content
+    ///
+    /// Uses the difference hash (dHash) algorithm: The frame is reduced to a
+    /// grayscale image of 9×8 cells by averaging the pixels of each cell. For
+    /// each row, a bit is set if a cell is brighter than its right neighbor,
+    /// resulting in 64 bits. Transparent pixels are treated as black.
+    ///
+    /// Unlike a hash of the file, this tolerates re-compression, scaling, and
+    /// changed metadata. The number of differing bits,
+    /// `(a ^ b).count_ones()`, is a measure for the similarity of two images.
+    /// Images with a distance of up to `10` can be considered similar, while
+    /// unrelated images usually differ in around `32` bits.
+    ///
+    /// Fails with [`ErrorKind::PlanarFrame`] for [planar](Loader::planar)
+    /// frames.
+    pub fn perceptual_hash(&self) -> Result<u64, Error> {
+        crate::perceptual_hash::dhash(self.texture_ref()?)
+    }
+
+    /// [BlurHash](https://blurha.sh) placeholder of the frame
+    ///
+    /// The hash is a short string that apps can decode into a blurred
+    /// placeholder while the image itself is still loading. The number of
+    /// components along each axis defines how much detail is kept and has to
+    /// be between `1` and `9`. Otherwise,
+    /// [`ErrorKind::InvalidBlurHashComponents`] is returned. A common choice
+    /// is `4` × `3`.
+    ///
+    /// For speed, the hash is computed from a version of the frame that is
+    /// downscaled to at most 32 × 32 pixels. Transparent pixels are treated as
+    /// black. Fails with [`ErrorKind::PlanarFrame`] for
+    /// [planar](Loader::planar) frames.
+    pub fn blurhash(&self, components_x: u32, components_y: u32) -> Result<String, Error> {
+        crate::blurhash::encode(self.texture_ref()?, components_x, components_y)
+    }
+
+    /// Positions of the channel planes for [`Loader::planar`]
+    ///
+    /// Returns `None` if the channels are interleaved.
+    pub fn planes(&self) -> Option<&[Plane]> {
+        self.planes.as_deref()
+    }
+
+    fn into_planar(self) -> Result<Frame, Error> {
+        let (buffer, planes) = crate::planar::to_planar(self.texture_ref()?)?;
+        let stride = planes.first().map_or(self.stride, |x| x.stride);
+
+        Ok(Self {
+            buffer: glib::Bytes::from_owned(buffer),
+            stride,
+            planes: Some(planes),
+            memfd: None,
+            ..self
+        })
+    }
+
+    /// Downscales the frame to fit into `size` × `size` if it is larger
+    ///
+    /// Each pixel is the average over the corresponding area. Downscaled
+    /// frames are in [`MemoryFormat::R8g8b8a8Premultiplied`].
+    fn fit_into(self, size: u32) -> Result<Frame, Error> {
+        if self.planes.is_some() {
+            return Err(ErrorKind::PlanarFrame.err());
+        }
+
+        if self.width <= size && self.height <= size {
+            return Ok(self);
+        }
+
+        let ((width, height), _) =
+            FitMode::Contain.frame_request((self.width, self.height), (size, size));
+        let cells = crate::luma::average_cells(
+            self.texture_ref()?,
+            width.try_usize()?,
+            height.try_usize()?,
+            |[r, g, b, a]| [r * a, g * a, b * a, a],
+        )?;
+        let buffer = cells
+            .into_iter()
+            .flatten()
+            .map(|x| (x.clamp(0., 1.) * 255.).round() as u8)
+            .collect::<Vec<_>>();
+
+        // Details like the pixel density don't apply anymore
+        let mut details = glycin_utils::FrameDetails::default();
+        details.n_frame = self.details.n_frame;
+
+        Ok(Frame {
+            buffer: glib::Bytes::from_owned(buffer),
+            width,
+            height,
+            stride: width.smul(4)?,
+            memory_format: MemoryFormat::R8g8b8a8Premultiplied,
+            details: Arc::new(details),
+            planes: None,
+            memfd: None,
+            ..self
+        })
+    }
+
+    /// Frame with default details for unit tests
+    #[cfg(test)]
+    pub(crate) fn for_test(
+        width: u32,
+        height: u32,
+        stride: u32,
+        memory_format: MemoryFormat,
+        buffer: Vec<u8>,
+    ) -> Self {
+        Self {
+            buffer: glib::Bytes::from_owned(buffer),
+            width,
+            height,
+            stride,
+            memory_format,
+            delay: None,
+            details: Default::default(),
+            image_details: ImageDetails::new(
+                Arc::new(glycin_utils::ImageDetails::new(width, height)),
+                (width, height),
+            ),
+            color_state: ColorState::Srgb,
+            frame_index: 0,
+            presentation_time: Duration::ZERO,
+            planes: None,
+            applied_transformations: AppliedTransforms::default(),
+            memfd: None,
+        }
+    }
+
+    /// Pixel data with interleaved channels
+    pub(crate) fn texture_ref(&self) -> Result<crate::luma::TextureRef<'_>, Error> {
+        if self.planes.is_some() {
+            return Err(ErrorKind::PlanarFrame.err());
+        }
+
+        Ok(crate::luma::TextureRef {
+            width: self.width,
+            height: self.height,
+            stride: self.stride,
+            memory_format: self.memory_format,
+            buf: self.buf_slice(),
+        })
+    }
+
     /// Width in pixels
     pub fn width(&self) -> u32 {
         self.width
@@ -704,10 +3256,27 @@ impl Frame {
         self.memory_format
     }
 
+    /// Recommended GPU texture format for uploading the frame
+    ///
+    /// Intended for renderers that don't use GDK. The hint only describes the
+    /// format and doesn't depend on any graphics API. See
+    /// [`MemoryFormat::gpu_format_hint`].
+    pub fn recommended_gpu_format(&self) -> crate::GpuFormatHint {
+        self.memory_format.gpu_format_hint()
+    }
+
     pub fn color_state(&self) -> &ColorState {
         &self.color_state
     }
 
+    /// Transformations glycin applied after the loader delivered the frame
+    ///
+    /// Useful to decide which steps are left when passing the frame into
+    /// another color pipeline.
+    pub fn applied_transformations(&self) -> AppliedTransforms {
+        self.applied_transformations
+    }
+
     /// Duration to show frame for animations.
     ///
     /// If the value is not set, the image is not animated.
@@ -715,38 +3284,294 @@ impl Frame {
         self.delay
     }
 
+    /// How the frame's area is treated before rendering the next frame
+    ///
+    /// Together with [`Frame::blend_op`] and [`Frame::offset`], this allows
+    /// to composite animations with a custom renderer. Only set by loaders
+    /// that provide this information for animations.
+    ///
+    /// Loaders composite the frames by default and return frames that cover
+    /// the whole canvas without these values. See
+    /// [`Loader::raw_animation_frames`] to get the frames as stored in the
+    /// file.
+    pub fn disposal_method(&self) -> Option<DisposalMethod> {
+        self.details.animation_disposal_method
+    }
+
+    /// How the frame is combined with the canvas
+    ///
+    /// See [`Frame::disposal_method`].
+    pub fn blend_op(&self) -> Option<BlendOp> {
+        self.details.animation_blend_op
+    }
+
+    /// Position of the frame within the canvas
+    ///
+    /// The position `(x, y)` of the frame's top left corner in pixels,
+    /// relative to the top left corner of the canvas. The canvas has the
+    /// dimensions of the image. The frame's width and height are the size of
+    /// the covered area. See [`Frame::disposal_method`].
+    pub fn offset(&self) -> Option<(u32, u32)> {
+        self.details.animation_offset
+    }
+
+    /// Position of the frame within the frames returned for the image
+    ///
+    /// Frames returned by [`Image::next_frame`] and [`Image::specific_frame`]
+    /// are counted from `0`. The count continues when an animation loops
+    /// back to its first frame, and is only reset by loading the image
+    /// again. Requesting a specific frame, like the first frame of the loop
+    /// after the last one, does not reset the count. Auxiliary images and
+    /// image items are not part of the animation and always have index `0`.
+    pub fn frame_index(&self) -> u64 {
+        self.frame_index
+    }
+
+    /// Time at which the frame is due, relative to the first frame
+    ///
+    /// This is the sum of the [`delay`](Self::delay) of all frames returned
+    /// before. Like the [`frame_index`](Self::frame_index), it keeps
+    /// increasing when the animation loops and is only reset by loading the
+    /// image again. This allows syncing an animation to an external clock,
+    /// like audio.
+    pub fn presentation_time(&self) -> Duration {
+        self.presentation_time
+    }
+
+    /// Structure of the frame's ICC profile
+    ///
+    /// Indicates whether a fast matrix path can be used when applying the
+    /// profile in a custom color pipeline. The class describes the profile
+    /// delivered by the loader, even if glycin already converted the frame
+    /// to sRGB. Returns `None` if the frame has no ICC profile or if the
+    /// profile can't be parsed.
+    pub fn icc_profile_class(&self) -> Option<crate::IccProfileClass> {
+        let icc_profile = self.details.color_icc_profile.as_deref()?;
+
+        match crate::IccProfileClass::from_profile(icc_profile) {
+            Ok(class) => Some(class),
+            Err(err) => {
+                tracing::debug!("Failed to parse ICC profile: {err}");
+                None
+            }
+        }
+    }
+
     pub fn details(&self) -> FrameDetails {
         FrameDetails::new(self.details.clone(), self.image_details.clone())
     }
 
+    /// Returns a copy of the frame with an aligned stride
+    ///
+    /// Each row is padded with zeros such that the stride is a multiple of
+    /// `alignment` bytes. This is useful for GPU APIs like Vulkan, that often
+    /// require strides to be aligned to 256 bytes for texture uploads. The
+    /// `alignment` has to be a power of two. Planar frames are not supported.
+    pub fn realign_stride(&self, alignment: usize) -> Result<Frame, Error> {
+        if !alignment.is_power_of_two() {
+            return Err(ErrorKind::InvalidAlignment(alignment).err());
+        }
+
+        if self.planes.is_some() {
+            return Err(ErrorKind::PlanarFrame.err());
+        }
+
+        let row_len = (self.width as usize).smul(self.memory_format.n_bytes().usize())?;
+        let stride = row_len
+            .checked_next_multiple_of(alignment)
+            .ok_or(glycin_utils::DimensionTooLargerError)?;
+
+        if (stride as u64).smul(self.height as u64)? > MAX_TEXTURE_SIZE {
+            return Err(ErrorKind::TextureTooLarge.err());
+        }
+
+        let buffer = util::change_stride(
+            self.buf_slice(),
+            self.height as usize,
+            row_len,
+            self.stride as usize,
+            stride,
+        )?;
+
+        Ok(Self {
+            buffer: glib::Bytes::from_owned(buffer),
+            stride: stride.try_u32()?,
+            memfd: None,
+            ..self.clone()
+        })
+    }
+
+    /// Repeats the frame to fill `width` × `height` pixels
+    ///
+    /// The frame is placed at the top left corner and repeated to the right
+    /// and to the bottom. Tiles at the right and bottom edges are cut off if
+    /// the size is not a multiple of the frame size.
+    pub(crate) fn tiled(&self, width: u32, height: u32) -> Result<Frame, Error> {
+        if self.planes.is_some() {
+            return Err(ErrorKind::PlanarFrame.err());
+        }
+
+        if width == 0 || height == 0 {
+            return Err(
+                ErrorKind::WidgthOrHeightZero(format!("Tiled size is {width} x {height}")).err(),
+            );
+        }
+
+        let pixel_size = self.memory_format.n_bytes().usize();
+        let tile_row_len = (self.width as usize).smul(pixel_size)?;
+        let row_len = (width as usize).smul(pixel_size)?;
+
+        if (row_len as u64).smul(height as u64)? > MAX_TEXTURE_SIZE {
+            return Err(ErrorKind::TextureTooLarge.err());
+        }
+
+        let mut buffer = Vec::with_capacity(row_len.smul(height as usize)?);
+        for y in 0..height as usize {
+            let start = (y % self.height as usize).smul(self.stride as usize)?;
+            let tile_row = self
+                .buf_slice()
+                .get(start..start.sadd(tile_row_len)?)
+                .ok_or_else(|| {
+                    ErrorKind::TextureWrongSize {
+                        texture_size: self.buffer.len(),
+                        frame: format!("{self:?}"),
+                    }
+                    .err()
+                })?;
+
+            buffer.extend(tile_row.iter().cycle().take(row_len));
+        }
+
+        Ok(Self {
+            buffer: glib::Bytes::from_owned(buffer),
+            width,
+            height,
+            stride: row_len.try_u32()?,
+            memfd: None,
+            ..self.clone()
+        })
+    }
+
+    /// Memory texture of the frame
+    ///
+    /// Falls back to sRGB if GDK doesn't support the frame's color state.
+    /// Fails with [`ErrorKind::PlanarFrame`] for [planar](Loader::planar)
+    /// frames, since GDK textures need interleaved channels.
     #[cfg(feature = "gdk4")]
-    pub fn texture(&self) -> gdk::Texture {
+    pub fn texture(&self) -> Result<gdk::Texture, Error> {
         let color_state = crate::util::gdk_color_state(&self.color_state).unwrap_or_else(|_| {
             tracing::warn!("Unsupported color state: {:?}", self.color_state);
             gdk::ColorState::srgb()
         });
 
-        gdk::MemoryTextureBuilder::new()
+        self.build_texture(&color_state)
+    }
+
+    /// Memory texture with the frame's color state
+    ///
+    /// The [`ColorState`] of the frame is mapped to the corresponding
+    /// [`gdk::ColorState`], such that GTK can display HDR and wide-gamut
+    /// images correctly. ICC profiles are already applied by glycin and
+    /// result in sRGB frames.
+    ///
+    /// Unlike [`Frame::texture`], this doesn't fall back to sRGB if GDK
+    /// doesn't support the color state, but returns an error. Color states
+    /// require GTK 4.16 or newer. Since glycin's `gdk4` feature depends on
+    /// GTK 4.16, older GTK versions are not supported. Applications that
+    /// want to display such frames anyway can use [`Frame::texture`].
+    #[cfg(feature = "gdk4")]
+    pub fn to_memory_texture_with_color_state(&self) -> Result<gdk::MemoryTexture, Error> {
+        let color_state = crate::util::gdk_color_state(&self.color_state)?;
+
+        self.build_texture(&color_state)?
+            .downcast()
+            .map_err(|_| ErrorKind::unreachable().err())
+    }
+
+    #[cfg(feature = "gdk4")]
+    fn build_texture(&self, color_state: &gdk::ColorState) -> Result<gdk::Texture, Error> {
+        if self.planes.is_some() {
+            return Err(ErrorKind::PlanarFrame.err());
+        }
+
+        Ok(gdk::MemoryTextureBuilder::new()
             .set_bytes(Some(&self.buffer))
             // Use unwraps here since the compatibility was checked before
             .set_width(self.width().try_i32().unwrap())
             .set_height(self.height().try_i32().unwrap())
             .set_stride(self.stride().try_usize().unwrap())
             .set_format(crate::util::gdk_memory_format(self.memory_format()))
-            .set_color_state(&color_state)
-            .build()
+            .set_color_state(color_state)
+            .build())
     }
 
-    pub(crate) async fn from_loader<B: ByteData>(
+    async fn from_loader<B: ByteData>(
         mut frame: glycin_utils::Frame<B>,
-        image: &Image,
+        frame_context: &FrameContext,
+        post_process: bool,
     ) -> Result<Self, Error> {
         frame.initial_seal().await?;
 
-        validate_frame(&frame, &image.loader.limits)?;
+        validate_frame(&frame, &frame_context.limits)?;
+
+        limit_icc_profile(
+            &mut frame.details.color_icc_profile,
+            &mut frame.details.warnings,
+            &frame_context.limits,
+        );
+
+        let image_details =
+            ImageDetails::new(frame_context.details.clone(), frame_context.raw_dimensions);
+
+        if !post_process || frame_context.raw {
+            let mut frame = frame.into_fungible();
+
+            // Frames can't have CMYK formats, see `util::gdk_memory_format`
+            if frame.memory_format.is_cmyk() {
+                let target_format = frame.memory_format.without_cmyk();
+                frame = util::spawn_blocking(move || {
+                    glycin_utils::editing::change_memory_format(&mut frame, target_format)?;
+                    Ok::<_, Error>(frame)
+                })
+                .await??;
+            }
+
+            frame.final_seal().await?;
+
+            let color_state = frame
+                .details
+                .color_cicp
+                .and_then(|x| Cicp::from_bytes(&x).ok())
+                .map_or(ColorState::Srgb, ColorState::Cicp);
+
+            let (buffer, memfd) = frame.texture.into_gbytes_and_memfd()?;
+
+            return Ok(Self {
+                buffer,
+                width: frame.width,
+                height: frame.height,
+                stride: frame.stride,
+                memory_format: frame.memory_format,
+                delay: frame.delay.into(),
+                details: Arc::new(frame.details.into_other()?),
+                image_details,
+                color_state,
+                frame_index: 0,
+                presentation_time: Duration::ZERO,
+                planes: None,
+                applied_transformations: AppliedTransforms::default(),
+                memfd: memfd.map(Arc::new),
+            });
+        }
+
+        let mut applied_transformations = AppliedTransforms::default();
+        let original_memory_format = frame.memory_format;
 
-        let frame = if image.loader.apply_transformations {
-            orientation::apply_exif_orientation(frame.into_fungible(), image)
+        let frame = if frame_context.apply_transformations {
+            let (frame, orientation_applied) =
+                orientation::apply_exif_orientation(frame.into_fungible(), &frame_context.details);
+            applied_transformations.orientation = orientation_applied;
+            frame
         } else {
             frame.into_fungible()
         };
@@ -771,7 +3596,9 @@ impl Frame {
             frame
         } else if let Some(icc_profile) = icc_profile {
             let (frame, icc_result) =
-                spawn_blocking(move || icc::apply_transformation(&icc_profile, frame)).await?;
+                spawn_blocking(move || icc::apply_transformation(&icc_profile, frame))
+                    .await
+                    .stage(Stage::Icc)?;
 
             match icc_result {
                 Err(err) => {
@@ -779,6 +3606,7 @@ impl Frame {
                 }
                 Ok(new_color_state) => {
                     color_state = new_color_state;
+                    applied_transformations.icc_to_srgb = true;
                 }
             }
 
@@ -789,10 +3617,7 @@ impl Frame {
 
         let mut frame = frame.into_fungible();
 
-        if let Some(target_format) = image
-            .loader
-            .memory_format_selection
-            .best_format_for(frame.memory_format)
+        if let Some(target_format) = frame_context.target_memory_format(frame.memory_format)
             && frame.memory_format != target_format
         {
             frame = util::spawn_blocking(move || {
@@ -804,17 +3629,33 @@ impl Frame {
 
         frame.final_seal().await?;
 
-        Ok(Self {
-            buffer: frame.texture.into_gbytes()?,
+        applied_transformations.memory_format_changed =
+            frame.memory_format != original_memory_format;
+
+        let (buffer, memfd) = frame.texture.into_gbytes_and_memfd()?;
+
+        let frame = Self {
+            buffer,
             width: frame.width,
             height: frame.height,
             stride: frame.stride,
             memory_format: frame.memory_format,
             delay: frame.delay.into(),
             details: Arc::new(frame.details.into_other()?),
-            image_details: image.details(),
+            image_details,
             color_state,
-        })
+            frame_index: 0,
+            presentation_time: Duration::ZERO,
+            planes: None,
+            applied_transformations,
+            memfd: memfd.map(Arc::new),
+        };
+
+        if frame_context.planar {
+            spawn_blocking(move || frame.into_planar()).await?
+        } else {
+            Ok(frame)
+        }
     }
 }
 
@@ -823,6 +3664,7 @@ impl Frame {
 /// Request information to get a specific frame
 pub struct FrameRequest {
     pub(crate) request: glycin_utils::FrameRequest,
+    cancellable: Option<gio::Cancellable>,
 }
 
 impl Default for FrameRequest {
@@ -831,6 +3673,84 @@ impl Default for FrameRequest {
     }
 }
 
+/// Reject images with degenerate dimensions before requesting any frame
+fn validate_details<B: ByteData>(details: &glycin_utils::ImageDetails<B>) -> Result<(), Error> {
+    if details.width < 1 || details.height < 1 {
+        return Err(ErrorKind::InvalidDimensions {
+            width: details.width,
+            height: details.height,
+        }
+        .err());
+    }
+
+    Ok(())
+}
+
+/// Drop oversized ICC profiles before they are copied or parsed
+fn limit_icc_profile<B: ByteData>(
+    icc_profile: &mut Option<B>,
+    warnings: &mut Option<Vec<String>>,
+    limits: &Limits,
+) {
+    let max_size = limits.max_icc_profile_size;
+    if let Some(size) = icc_profile.as_ref().map(|x| x.len())
+        && size > max_size
+    {
+        let err = ErrorKind::IccProfileTooLarge { size, max_size }.err();
+        tracing::warn!("Ignoring ICC profile: {err}");
+        *icc_profile = None;
+        warnings.get_or_insert_default().push(err.to_string());
+    }
+}
+
+/// Merges an XMP sidecar into the XMP metadata, see [`Loader::new_multi`]
+///
+/// Sidecars that can't be read or aren't valid XMP are ignored with a
+/// warning.
+async fn apply_xmp_sidecar<B: ByteData>(
+    details: &mut glycin_utils::ImageDetails<B>,
+    sidecar: PathBuf,
+) -> Result<(), Error> {
+    let file_name = sidecar
+        .file_name()
+        .map(|x| x.to_string_lossy().into_owned());
+    let data = match spawn_blocking(move || std::fs::read(sidecar)).await? {
+        Ok(data) => data,
+        Err(err) => {
+            tracing::warn!("Ignoring XMP sidecar: {err}");
+            details
+                .warnings
+                .get_or_insert_default()
+                .push(format!("Failed to read XMP sidecar: {err}"));
+            return Ok(());
+        }
+    };
+
+    if crate::xmp::Xmp::parse(&data).is_none() {
+        tracing::warn!("Ignoring XMP sidecar: Invalid XML");
+        details
+            .warnings
+            .get_or_insert_default()
+            .push(String::from("Ignoring XMP sidecar with invalid XML"));
+        return Ok(());
+    }
+
+    let merged = details
+        .metadata_xmp
+        .as_ref()
+        .and_then(|embedded| crate::xmp::merge(embedded, &data));
+    details.metadata_xmp = Some(B::try_from_vec(merged.unwrap_or(data))?);
+
+    if let Some(file_name) = file_name {
+        details
+            .metadata_key_value
+            .get_or_insert_default()
+            .insert(String::from("glycin:xmp-sidecar"), file_name);
+    }
+
+    Ok(())
+}
+
 fn validate_frame<B: ByteData>(
     frame: &glycin_utils::Frame<B>,
     limits: &Limits,
@@ -845,6 +3765,15 @@ fn validate_frame<B: ByteData>(
         .err());
     }
 
+    if limits.strict_frame_size && img_buf.len() > frame.n_bytes()?.sadd(util::page_size())? {
+        return Err(ErrorKind::FrameSizeMismatch {
+            texture_size: img_buf.len(),
+            expected: frame.n_bytes()?,
+            frame: format!("{:?}", frame.desc()),
+        }
+        .err());
+    }
+
     if frame.stride < frame.width.smul(frame.memory_format.n_bytes().u32())? {
         return Err(ErrorKind::StrideTooSmall(format!("{:?}", frame.desc())).err());
     }
@@ -878,7 +3807,10 @@ impl FrameRequest {
         let mut request = glycin_utils::FrameRequest::default();
         request.loop_animation = true;
 
-        Self { request }
+        Self {
+            request,
+            cancellable: None,
+        }
     }
 
     pub fn scale(mut self, width: u32, height: u32) -> Self {
@@ -899,6 +3831,75 @@ impl FrameRequest {
         self.request.loop_animation = loop_animation;
         self
     }
+
+    /// Allow decoding with a lower bit depth for speed
+    ///
+    /// This trades quality for speed, for example for previews while
+    /// scrubbing through images. Images with more than 8 bits per channel
+    /// can then be decoded with 8 bits if that is faster. Loaders ignore the
+    /// hint if it doesn't make decoding faster. Whether the precision was
+    /// reduced is reported by [`FrameDetails::reduced_precision`]. Request
+    /// the final frame without this option to get the full precision.
+    ///
+    /// None of the loaders shipped with glycin can decode faster with fewer
+    /// bits. They ignore the hint and always return the full precision.
+    pub fn reduced_precision(mut self, reduced_precision: bool) -> Self {
+        self.request.reduced_precision = reduced_precision;
+        self
+    }
+
+    /// Request an auxiliary image instead of an animation frame
+    ///
+    /// Images can contain auxiliary images, like depth maps or alpha planes,
+    /// in addition to their animation frames. Both are indexed
+    /// independently. Animation frames are returned in order by
+    /// [`Image::next_frame`]. Auxiliary images are addressed by an index from
+    /// `0` to [`ImageDetails::n_auxiliary_images`] minus one.
+    ///
+    /// Requesting an auxiliary image does not advance the animation. Without
+    /// this option, frame requests only return animation frames, as before.
+    pub fn auxiliary_image(mut self, index: u32) -> Self {
+        self.request.auxiliary_image = Some(index);
+        self
+    }
+
+    /// Only decode the scans up to `scan` of a progressive image
+    ///
+    /// Scans are counted from `1`. See [`Image::progressive_frames`].
+    /// Requesting scans does not advance the animation.
+    pub fn progressive_scan(mut self, scan: u32) -> Self {
+        self.request.progressive_scan = Some(scan);
+        self
+    }
+
+    /// Request an item of the image container instead of an animation frame
+    ///
+    /// The `id` has to be one of the [`ImageDetails::image_items`]. By
+    /// default, the primary item is decoded. For animated images, the
+    /// animation frames belong to the primary item, while the other items are
+    /// single images. Like for auxiliary images, requesting an item does not
+    /// advance the animation.
+    pub fn image_item(mut self, id: u32) -> Self {
+        self.request.image_item = Some(id);
+        self
+    }
+
+    /// Set [`Cancellable`](gio::Cancellable) to abandon only this frame request
+    ///
+    /// Unlike the cancellable set via [`Loader::cancellable`], cancelling it
+    /// does not end the loader process. The in-flight frame request returns
+    /// a cancelled error and the next frame request reuses the running
+    /// loader. This is useful when a request is abandoned but another one
+    /// follows immediately, like when scrubbing through an animation.
+    ///
+    /// This requires the loader to support interrupting the decoding of a
+    /// frame. For loaders that don't, the loader process is killed instead,
+    /// as with the loader's cancellable, and the image can't be used
+    /// anymore.
+    pub fn cancellable(mut self, cancellable: impl IsA<gio::Cancellable>) -> Self {
+        self.cancellable = Some(cancellable.upcast());
+        self
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -924,10 +3925,29 @@ impl FrameDetails {
             .and_then(|x| crate::Cicp::from_bytes(&x).ok())
     }
 
+    /// ICC profile of the frame
+    ///
+    /// This profile is the one that is applied to the frame. The profile of
+    /// the whole image in [`ImageDetails::color_icc_profile`] is never
+    /// applied, even if the frame has no profile.
     pub fn color_icc_profile(&self) -> Option<&[u8]> {
         self.inner.color_icc_profile.as_deref()
     }
 
+    /// Profiles of the image and of the frame if they disagree
+    ///
+    /// Returns the profile from [`ImageDetails::color_icc_profile`] and the
+    /// profile from [`FrameDetails::color_icc_profile`], in that order, if
+    /// both are present and not identical. Only the frame's profile is
+    /// applied. Such files are also reported by
+    /// [`Loader::load_with_diagnostics`] with the code `icc.conflict`.
+    pub fn conflicting_icc_profiles(&self) -> Option<(&[u8], &[u8])> {
+        let image_icc_profile = self.image_details.color_icc_profile()?;
+        let frame_icc_profile = self.color_icc_profile()?;
+
+        (image_icc_profile != frame_icc_profile).then_some((image_icc_profile, frame_icc_profile))
+    }
+
     pub fn color_profile_preference(&self) -> ColorProfilePreference {
         self.inner
             .color_profile_preference
@@ -943,6 +3963,16 @@ impl FrameDetails {
         self.inner.info_bit_depth
     }
 
+    /// Whether the frame was decoded with fewer bits than
+    /// [`info_bit_depth`](Self::info_bit_depth)
+    ///
+    /// Only happens for frames requested with
+    /// [`FrameRequest::reduced_precision`]. The bit depth of the returned data
+    /// is the one of the frame's memory format.
+    pub fn reduced_precision(&self) -> bool {
+        self.inner.reduced_precision.unwrap_or_default()
+    }
+
     pub fn info_grayscale(&self) -> Option<bool> {
         self.inner.info_grayscale
     }
@@ -951,6 +3981,29 @@ impl FrameDetails {
         self.inner.n_frame
     }
 
+    /// Number of scans of a progressive image
+    ///
+    /// Only set by loaders that support [`FrameRequest::progressive_scan`].
+    pub fn n_progressive_scans(&self) -> Option<u32> {
+        self.inner.n_progressive_scans
+    }
+
+    /// Type of the auxiliary image
+    ///
+    /// Only set for frames requested via [`FrameRequest::auxiliary_image`].
+    /// For HEIF based formats, this is an URN like
+    /// `urn:mpeg:hevc:2015:auxid:1` for alpha planes.
+    pub fn auxiliary_type(&self) -> Option<&str> {
+        self.inner.auxiliary_type.as_deref()
+    }
+
+    /// Caveats reported by the loader for this frame
+    ///
+    /// See [`ImageDetails::warnings`].
+    pub fn warnings(&self) -> &[String] {
+        self.inner.warnings.as_deref().unwrap_or_default()
+    }
+
     pub fn pixel_density(&self) -> Option<physical_dimension::PixelDensity> {
         self.inner
             .pixel_density
@@ -966,6 +4019,168 @@ impl FrameDetails {
 #[cfg(test)]
 mod test {
     use super::*;
+
+    #[test]
+    fn scale_factor() {
+        let mut loader = Loader::new_vec(Vec::new());
+
+        loader.scale_factor(2.);
+        assert_eq!(loader.scale_factor, Some(2.));
+
+        for invalid in [0., -1., f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            loader.scale_factor(2.);
+            loader.scale_factor(invalid);
+            assert_eq!(loader.scale_factor, None);
+        }
+
+        loader.scale_factor(2.);
+        loader.options(crate::LoadOptions::new().scale_factor(f64::NAN));
+        assert_eq!(loader.scale_factor, None);
+    }
+
+    #[test]
+    fn with_source_input_size() {
+        let loader = Loader::new_vec(vec![0; 10]);
+
+        let fallback_loader = loader.with_source(Source::TransferredStream);
+        assert_eq!(fallback_loader.input_size, Some(10));
+
+        // Embedded images have their own size
+        assert_eq!(loader.embedded(vec![0; 5]).input_size, None);
+    }
+
+    #[test]
+    fn with_source_resets_image_options() {
+        let mut loader = Loader::new_multi(
+            gio::File::for_path("image.cr2"),
+            vec![gio::File::for_path("image.xmp")],
+        );
+        loader.fit(10, 10, FitMode::Contain).frame_stride(2);
+
+        let new_loader = loader.with_source(Source::TransferredStream);
+        assert!(new_loader.fit.is_none());
+        assert!(new_loader.sidecars.is_empty());
+        // Other options are kept
+        assert_eq!(new_loader.frame_stride, 2);
+    }
+
+    #[test]
+    fn realign_stride() {
+        let width = 5;
+        let height = 3;
+        let buffer = (0..width * height * 3).map(|x| x as u8).collect::<Vec<_>>();
+
+        let frame = Frame::for_test(width, height, width * 3, MemoryFormat::R8g8b8, buffer);
+
+        let aligned = frame.realign_stride(256).unwrap();
+
+        assert_eq!(aligned.stride(), 256);
+        assert_eq!(aligned.buf_slice().len(), 256 * 3);
+        for row in 0..height as usize {
+            let aligned_row = &aligned.buf_slice()[row * 256..(row + 1) * 256];
+            assert_eq!(
+                &aligned_row[..15],
+                &frame.buf_slice()[row * 15..(row + 1) * 15]
+            );
+            assert!(aligned_row[15..].iter().all(|x| *x == 0));
+        }
+
+        assert!(frame.realign_stride(24).is_err());
+    }
+
+    #[cfg(feature = "gdk4")]
+    #[test]
+    fn memory_texture_with_color_state() {
+        use gdk::prelude::*;
+
+        let frame = Frame::for_test(2, 2, 2 * 3, MemoryFormat::R8g8b8, vec![255; 2 * 2 * 3]);
+
+        let texture = frame.to_memory_texture_with_color_state().unwrap();
+
+        assert_eq!((texture.width(), texture.height()), (2, 2));
+        assert_eq!(texture.format(), gdk::MemoryFormat::R8g8b8);
+        assert!(texture.color_state().equal(&gdk::ColorState::srgb()));
+    }
+
+    #[test]
+    fn tiled() {
+        // 2x2 tile with padding at the end of each row
+        let frame = Frame::for_test(2, 2, 3, MemoryFormat::G8, vec![1, 2, 0, 3, 4, 0]);
+
+        let tiled = frame.tiled(5, 5).unwrap();
+
+        assert_eq!(tiled.width(), 5);
+        assert_eq!(tiled.height(), 5);
+        assert_eq!(tiled.stride(), 5);
+        #[rustfmt::skip]
+        assert_eq!(
+            tiled.buf_slice(),
+            &[
+                1, 2, 1, 2, 1,
+                3, 4, 3, 4, 3,
+                1, 2, 1, 2, 1,
+                3, 4, 3, 4, 3,
+                1, 2, 1, 2, 1,
+            ]
+        );
+
+        assert!(frame.tiled(0, 5).is_err());
+        assert!(frame.tiled(u32::MAX, u32::MAX).is_err());
+    }
+
+    #[test]
+    fn oriented_dimensions() {
+        let mut inner = glycin_utils::ImageDetails::new(3, 2);
+        inner.transformation_orientation = Some(Orientation::Rotation90);
+        let details = ImageDetails::new(Arc::new(inner), (3, 2));
+
+        assert_eq!((details.raw_width(), details.raw_height()), (3, 2));
+        assert_eq!(
+            (details.oriented_width(), details.oriented_height()),
+            (2, 3)
+        );
+
+        let mut inner = glycin_utils::ImageDetails::new(3, 2);
+        inner.transformation_orientation = Some(Orientation::Rotation180);
+        let details = ImageDetails::new(Arc::new(inner), (3, 2));
+
+        assert_eq!(
+            (details.oriented_width(), details.oriented_height()),
+            (3, 2)
+        );
+    }
+
+    #[test]
+    fn motion_photo() {
+        let details = |source_byte_size| {
+            let mut inner = glycin_utils::ImageDetails::new(3, 2);
+            inner.metadata_xmp = Some(FungibleMemory::from_vec(
+                br#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+                    <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+                        <rdf:Description rdf:about=""
+                            xmlns:GCamera="http://ns.google.com/photos/1.0/camera/"
+                            GCamera:MicroVideo="1"
+                            GCamera:MicroVideoOffset="100"/>
+                    </rdf:RDF>
+                </x:xmpmeta>"#
+                    .to_vec(),
+            ));
+            inner.source_byte_size = source_byte_size;
+            ImageDetails::new(Arc::new(inner), (3, 2))
+        };
+
+        assert_eq!(details(Some(1000)).motion_photo(), Some((900, 100)));
+        assert_eq!(details(Some(50)).motion_photo(), None);
+        assert_eq!(details(None).motion_photo(), None);
+
+        // The packet is parsed once and shared between clones
+        let details = details(Some(1000));
+        let clone = details.clone();
+        assert!(clone.xmp.get().is_none());
+        assert_eq!(details.motion_photo(), Some((900, 100)));
+        assert!(clone.xmp.get().is_some_and(|x| x.is_some()));
+    }
+
     #[allow(dead_code)]
     fn ensure_futures_are_send() {
         gio::glib::spawn_future(async {
@@ -0,0 +1,104 @@
+use std::sync::{Arc, Mutex, PoisonError};
+
+use futures_channel::oneshot;
+use futures_util::FutureExt;
+use futures_util::future::Shared;
+
+use crate::Loader;
+
+/// Observes the process of a [`Loader`]
+///
+/// Obtained via [`Loader::process_context`] before loading the image. This
+/// allows to overlap work, like setting up the UI, with spinning up the
+/// loader.
+#[derive(Clone)]
+pub struct ProcessContext {
+    ready: Shared<oneshot::Receiver<()>>,
+}
+
+impl std::fmt::Debug for ProcessContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProcessContext")
+            .field("ready", &self.is_ready())
+            .finish()
+    }
+}
+
+impl ProcessContext {
+    pub(crate) fn new() -> (Self, ProcessReady) {
+        let (sender, receiver) = oneshot::channel();
+
+        (
+            Self {
+                ready: receiver.shared(),
+            },
+            ProcessReady(Arc::new(Mutex::new(Some(sender)))),
+        )
+    }
+
+    /// Resolves once the loader is ready to receive the image data
+    ///
+    /// For external loaders, this is the case when the loader process has
+    /// been spawned and the D-Bus connection to it has been established, or
+    /// when a running process from the pool is reused. Builtin loaders are
+    /// ready once they have been selected. In all cases, this happens before
+    /// the image data are sent to the loader.
+    ///
+    /// Readiness doesn't imply that the image is valid or can be decoded by
+    /// the loader. Returns `false` if loading failed or was aborted before
+    /// the loader was ready.
+    pub async fn ready(&self) -> bool {
+        self.ready.clone().await.is_ok()
+    }
+
+    /// Returns `true` if [`ProcessContext::ready`] would resolve with `true`
+    /// immediately
+    pub fn is_ready(&self) -> bool {
+        matches!(self.ready.clone().now_or_never(), Some(Ok(())))
+    }
+}
+
+/// Sending side of [`ProcessContext::ready`]
+///
+/// If all copies are dropped without notifying, readiness resolves as
+/// failed.
+#[derive(Debug, Clone)]
+pub(crate) struct ProcessReady(Arc<Mutex<Option<oneshot::Sender<()>>>>);
+
+impl ProcessReady {
+    pub(crate) fn notify(&self) {
+        if let Some(sender) = self.0.lock().unwrap_or_else(PoisonError::into_inner).take() {
+            let _ = sender.send(());
+        }
+    }
+}
+
+impl Loader {
+    /// Observe the process that loads the image
+    ///
+    /// See [`ProcessContext`].
+    pub fn process_context(&self) -> ProcessContext {
+        self.process_context.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gio::glib;
+
+    use super::*;
+
+    #[test]
+    fn ready() {
+        let (context, process_ready) = ProcessContext::new();
+        assert!(!context.is_ready());
+
+        process_ready.clone().notify();
+        assert!(context.is_ready());
+        assert!(glib::MainContext::new().block_on(context.ready()));
+
+        let (context, process_ready) = ProcessContext::new();
+        drop(process_ready);
+        assert!(!glib::MainContext::new().block_on(context.ready()));
+    }
+}
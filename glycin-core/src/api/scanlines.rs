@@ -0,0 +1,69 @@
+use gio::glib;
+use glycin_common::MemoryFormatInfo;
+
+use crate::MemoryFormat;
+
+/// Consecutive rows of an image
+///
+/// Returned by [`Image::scanlines`](crate::Image::scanlines).
+#[derive(Debug, Clone)]
+pub struct Scanlines {
+    pub(crate) buffer: glib::Bytes,
+    pub(crate) first_row: u32,
+    pub(crate) width: u32,
+    pub(crate) n_rows: u32,
+    /// Line stride in bytes
+    pub(crate) stride: u32,
+    pub(crate) memory_format: MemoryFormat,
+}
+
+impl Scanlines {
+    /// Index of the first row within the image
+    pub fn first_row(&self) -> u32 {
+        self.first_row
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Number of rows
+    pub fn n_rows(&self) -> u32 {
+        self.n_rows
+    }
+
+    /// Line stride in bytes
+    pub fn stride(&self) -> u32 {
+        self.stride
+    }
+
+    pub fn memory_format(&self) -> MemoryFormat {
+        self.memory_format
+    }
+
+    pub fn buf_bytes(&self) -> glib::Bytes {
+        self.buffer.clone()
+    }
+
+    pub fn buf_slice(&self) -> &[u8] {
+        self.buffer.as_ref()
+    }
+
+    /// Pixel data of row `n`, relative to [`first_row`](Self::first_row)
+    ///
+    /// Does not include the padding at the end of the row.
+    pub fn row(&self, n: u32) -> Option<&[u8]> {
+        if n >= self.n_rows {
+            return None;
+        }
+
+        let row_len = self.width as usize * self.memory_format.n_bytes().usize();
+        let start = n as usize * self.stride as usize;
+        self.buf_slice().get(start..start + row_len)
+    }
+
+    /// Iterator over the pixel data of all rows
+    pub fn rows(&self) -> impl Iterator<Item = &[u8]> {
+        (0..self.n_rows).filter_map(|n| self.row(n))
+    }
+}
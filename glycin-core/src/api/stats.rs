@@ -0,0 +1,31 @@
+use std::time::Duration;
+
+/// Statistics about loading an image
+///
+/// All timings are wall-clock durations, measured in the calling process.
+/// Hence, they include the overhead of the sandbox and the communication with
+/// the loader process. The values accumulate over the lifetime of the
+/// [`Image`](crate::Image).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DecodeStats {
+    /// Time for spawning the loader process
+    ///
+    /// This is close to zero if a running process from the pool was reused or
+    /// a builtin loader was used.
+    pub spawn_time: Duration,
+    /// Time until the loader returned the image details
+    pub init_time: Duration,
+    /// Time the loader spent on all frame requests
+    ///
+    /// Post-processing, like applying ICC profiles, is not included.
+    pub decode_time: Duration,
+    /// Bytes read from the image source
+    ///
+    /// Loaders can continue reading the source after the image details have
+    /// been returned. The value includes all bytes read until
+    /// [`Image::stats`](crate::Image::stats) is called.
+    pub input_bytes: u64,
+    /// Size of the textures of all loaded frames
+    pub output_bytes: u64,
+}
@@ -0,0 +1,180 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::api::{
+    ImageDetails, ProcessPriority, ProcessorContext, SandboxMechanism, SandboxSelector,
+    SandboxWrapper,
+};
+use crate::config::{self, ImageLoaderConfig, MimeType};
+use crate::dbus::LoaderProxy;
+use crate::pool::{PooledProcess, UsageTracker};
+use crate::util::AsyncMutex;
+use crate::{Error, ErrorKind, Image, Loader, Pool, PoolConfig};
+
+/// Loader that keeps its loader process running
+///
+/// Loader processes in a [`Pool`] are ended after they have not been used for
+/// a while. A `WarmLoader` spawns the loader process for one image format
+/// and keeps it running until [`WarmLoader::close`] is called or the
+/// `WarmLoader` is dropped. This avoids the spawn time when loading many
+/// images of the same format one after another.
+///
+/// Only images that are handled by the same loader process can be loaded.
+/// For other images, [`ErrorKind::IncompatibleWarmLoader`] is returned. If
+/// the process terminates, for example after a crash, the next load spawns a
+/// new process.
+///
+/// Only available for external loaders.
+#[derive(Debug)]
+pub struct WarmLoader {
+    mime_type: MimeType,
+    config_entry: ImageLoaderConfig,
+    sandbox_mechanism: SandboxMechanism,
+    sandbox_wrapper: Option<SandboxWrapper>,
+    process_hash: config::ConfigEntryHash,
+    pool: Arc<Pool>,
+    process: AsyncMutex<(Arc<PooledProcess<LoaderProxy<'static>>>, Arc<UsageTracker>)>,
+    cancellable: gio::Cancellable,
+}
+
+impl WarmLoader {
+    /// Spawn the loader process for `mime_type`
+    pub async fn new(mime_type: MimeType) -> Result<Self, Error> {
+        Self::new_internal(mime_type, None).await
+    }
+
+    /// Spawn the loader process for `mime_type` via a custom sandbox
+    ///
+    /// See [`Loader::sandbox_wrapper`]. Loaders returned by
+    /// [`WarmLoader::loader`] use the same wrapper.
+    pub async fn new_with_sandbox_wrapper(
+        mime_type: MimeType,
+        sandbox_wrapper: SandboxWrapper,
+    ) -> Result<Self, Error> {
+        Self::new_internal(mime_type, Some(sandbox_wrapper)).await
+    }
+
+    async fn new_internal(
+        mime_type: MimeType,
+        sandbox_wrapper: Option<SandboxWrapper>,
+    ) -> Result<Self, Error> {
+        let mut context = ProcessorContext::<ImageLoaderConfig, ()>::new_sourceless(
+            mime_type.clone(),
+            &SandboxSelector::default(),
+        )
+        .await?;
+
+        if let Some(sandbox_wrapper) = sandbox_wrapper {
+            context.set_sandbox_wrapper(sandbox_wrapper);
+        }
+
+        if !matches!(context.config_entry.processor, config::Processor::Binary(_)) {
+            return Err(ErrorKind::ExpectedBinaryProcessor.err());
+        }
+
+        let process_hash = config::ConfigEntry::Loader(context.config_entry.clone()).hash_value(
+            None,
+            Vec::new(),
+            None,
+            context.sandbox_mechanism,
+            context.sandbox_wrapper.clone(),
+            ProcessPriority::default(),
+        );
+
+        // Processes are ended as soon as nobody uses them anymore
+        let pool = Pool::new(PoolConfig::new().retention_time(Duration::ZERO));
+        let cancellable = gio::Cancellable::new();
+
+        let process = pool
+            .clone()
+            .get_loader(
+                context.config_entry.clone(),
+                context.sandbox_mechanism,
+                context.sandbox_wrapper.clone(),
+                None,
+                Vec::new(),
+                None,
+                ProcessPriority::default(),
+                &cancellable,
+            )
+            .await?;
+
+        Ok(Self {
+            mime_type,
+            config_entry: context.config_entry,
+            sandbox_mechanism: context.sandbox_mechanism,
+            sandbox_wrapper: context.sandbox_wrapper,
+            process_hash,
+            pool,
+            process: AsyncMutex::new(process),
+            cancellable,
+        })
+    }
+
+    /// MIME type the loader process was spawned for
+    pub fn mime_type(&self) -> MimeType {
+        self.mime_type.clone()
+    }
+
+    /// Loader that uses the running process
+    ///
+    /// Changing options that affect the process, like
+    /// [`Loader::sandbox_selector`], [`Loader::sandbox_tmpdir`], or
+    /// [`Loader::process_priority`], makes loading fail with
+    /// [`ErrorKind::IncompatibleWarmLoader`].
+    pub fn loader(&self, file: gio::File) -> Loader {
+        let mut loader = Loader::new(file);
+        loader.pool(self.pool.clone());
+        if let Some(sandbox_wrapper) = &self.sandbox_wrapper {
+            loader.sandbox_wrapper(sandbox_wrapper.clone());
+        }
+        loader.warm_process = Some(self.process_hash.clone());
+        loader
+    }
+
+    /// Load the image with the running process
+    ///
+    /// See [`Loader::load`].
+    pub async fn load(&self, file: gio::File) -> Result<Image, Error> {
+        self.respawn_if_disconnected().await?;
+        self.loader(file).load().await
+    }
+
+    /// Load the image details with the running process
+    pub async fn info(&self, file: gio::File) -> Result<ImageDetails, Error> {
+        Ok(self.load(file).await?.details())
+    }
+
+    /// End the loader process
+    ///
+    /// The process is asked to shut down and killed if it doesn't. Images
+    /// loaded before keep the process running until they are dropped. Same
+    /// as dropping the `WarmLoader`.
+    pub fn close(self) {
+        drop(self);
+    }
+
+    async fn respawn_if_disconnected(&self) -> Result<(), Error> {
+        let mut process = self.process.lock().await;
+
+        if process.0.is_disconnected() {
+            tracing::debug!("Warm loader process disconnected. Spawning new one.");
+            *process = self
+                .pool
+                .clone()
+                .get_loader(
+                    self.config_entry.clone(),
+                    self.sandbox_mechanism,
+                    self.sandbox_wrapper.clone(),
+                    None,
+                    Vec::new(),
+                    None,
+                    ProcessPriority::default(),
+                    &self.cancellable,
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
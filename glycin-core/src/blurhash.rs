@@ -0,0 +1,151 @@
+//! BlurHash placeholders of textures
+
+use std::f64::consts::PI;
+
+use crate::luma::{self, TextureRef};
+use crate::{Error, ErrorKind};
+
+/// Size of the grid the texture is reduced to before computing the factors
+pub(crate) const MAX_GRID_SIZE: u32 = 32;
+
+const BASE83: &[u8; 83] =
+    b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz#$%*+,-.:;=?@[]^_{|}~";
+
+/// BlurHash of a texture
+///
+/// The texture is first reduced to at most 32×32 cells by averaging in
+/// linear sRGB. Transparent pixels are treated as black. The encoding
+/// follows the reference implementation at <https://blurha.sh>.
+pub(crate) fn encode(
+    texture: TextureRef,
+    components_x: u32,
+    components_y: u32,
+) -> Result<String, Error> {
+    if !(1..=9).contains(&components_x) || !(1..=9).contains(&components_y) {
+        return Err(ErrorKind::InvalidBlurHashComponents(components_x, components_y).err());
+    }
+
+    let width = texture.width.clamp(1, MAX_GRID_SIZE) as usize;
+    let height = texture.height.clamp(1, MAX_GRID_SIZE) as usize;
+
+    let cells = luma::average_cells(texture, width, height, |[r, g, b, a]| {
+        [
+            srgb_to_linear(r) * a,
+            srgb_to_linear(g) * a,
+            srgb_to_linear(b) * a,
+        ]
+    })?;
+
+    let mut factors = Vec::with_capacity((components_x * components_y) as usize);
+    for j in 0..components_y {
+        for i in 0..components_x {
+            let normalisation = if i == 0 && j == 0 { 1. } else { 2. };
+
+            let mut factor = [0.; 3];
+            for (n, cell) in cells.iter().enumerate() {
+                let (x, y) = (n % width, n / width);
+                let basis = normalisation
+                    * (PI * i as f64 * x as f64 / width as f64).cos()
+                    * (PI * j as f64 * y as f64 / height as f64).cos();
+
+                for (factor, value) in factor.iter_mut().zip(cell) {
+                    *factor += basis * *value as f64;
+                }
+            }
+
+            factors.push(factor.map(|x| x / cells.len() as f64));
+        }
+    }
+
+    let (dc, ac) = factors.split_first().ok_or(ErrorKind::unreachable())?;
+
+    let mut hash = String::with_capacity(4 + 2 * factors.len());
+    encode_base83((components_x - 1) + (components_y - 1) * 9, 1, &mut hash);
+
+    let maximum_value = if ac.is_empty() {
+        encode_base83(0, 1, &mut hash);
+        1.
+    } else {
+        let actual_maximum = ac.iter().flatten().fold(0., |max: f64, x| max.max(x.abs()));
+        let quantised_maximum = (actual_maximum * 166. - 0.5).floor().clamp(0., 82.) as u32;
+        encode_base83(quantised_maximum, 1, &mut hash);
+        (quantised_maximum + 1) as f64 / 166.
+    };
+
+    let [r, g, b] = dc.map(linear_to_srgb);
+    encode_base83((r << 16) + (g << 8) + b, 4, &mut hash);
+
+    for factor in ac {
+        let [r, g, b] = factor.map(|x| {
+            let x = x / maximum_value;
+            (x.signum() * x.abs().sqrt() * 9. + 9.5)
+                .floor()
+                .clamp(0., 18.) as u32
+        });
+        encode_base83(r * 19 * 19 + g * 19 + b, 2, &mut hash);
+    }
+
+    Ok(hash)
+}
+
+fn encode_base83(value: u32, length: u32, hash: &mut String) {
+    for i in (0..length).rev() {
+        let digit = (value / 83_u32.pow(i)) % 83;
+        hash.push(BASE83[digit as usize] as char);
+    }
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f64) -> u32 {
+    let value = value.clamp(0., 1.);
+    if value <= 0.0031308 {
+        (value * 12.92 * 255. + 0.5) as u32
+    } else {
+        ((1.055 * value.powf(1. / 2.4) - 0.055) * 255. + 0.5) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glycin_common::MemoryFormat;
+
+    use super::*;
+
+    fn blurhash(color: [u8; 3], components_x: u32, components_y: u32) -> Result<String, Error> {
+        let buf = color.repeat(4 * 4);
+
+        encode(
+            TextureRef {
+                width: 4,
+                height: 4,
+                stride: 4 * 3,
+                memory_format: MemoryFormat::R8g8b8,
+                buf: &buf,
+            },
+            components_x,
+            components_y,
+        )
+    }
+
+    #[test]
+    fn solid_color() {
+        assert_eq!(
+            blurhash([255, 0, 0], 4, 3).unwrap(),
+            "L~TI:j|cfQ|c|c$5fQ$5fQfQfQfQ"
+        );
+        assert_eq!(blurhash([255, 0, 0], 1, 1).unwrap(), "00TI:j");
+    }
+
+    #[test]
+    fn invalid_components() {
+        assert!(blurhash([0, 0, 0], 0, 3).is_err());
+        assert!(blurhash([0, 0, 0], 4, 10).is_err());
+    }
+}
@@ -7,6 +7,9 @@ use std::os::unix::ffi::OsStrExt;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::Arc;
+#[cfg(feature = "external")]
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 use futures_util::StreamExt;
 use gio::glib;
@@ -14,7 +17,7 @@ use glycin_common::{MemoryFormat, OperationId};
 
 use crate::config::indentifier::Identifier;
 use crate::util::{self, AsyncMutex, new_async_mutex, read};
-use crate::{Error, ErrorKind, SandboxMechanism};
+use crate::{Error, ErrorKind, ProcessPriority, SandboxMechanism, SandboxWrapper};
 
 #[derive(Clone, Debug)]
 /// Mime type
@@ -117,6 +120,58 @@ impl std::fmt::Display for MimeType {
 
 const CONFIG_FILE_EXT: &str = "conf";
 
+#[cfg(feature = "external")]
+static CONFIG_SEARCH_DIRS: RwLock<Vec<(u64, PathBuf)>> = RwLock::new(Vec::new());
+/// Changes whenever the config has to be reloaded
+static CONFIG_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Handle to a directory registered via [`register_config_search_dir`]
+///
+/// Dropping the handle keeps the directory registered.
+#[cfg(feature = "external")]
+#[derive(Debug)]
+pub struct ConfigSearchDirRegistration {
+    id: u64,
+}
+
+#[cfg(feature = "external")]
+impl ConfigSearchDirRegistration {
+    /// Removes the directory from the config search dirs
+    ///
+    /// Loaders only configured in this directory are not available for new
+    /// loads anymore.
+    pub fn unregister(self) {
+        CONFIG_SEARCH_DIRS
+            .write()
+            .unwrap_or_else(|err| err.into_inner())
+            .retain(|(id, _)| *id != self.id);
+        CONFIG_GENERATION.fetch_add(1, Ordering::AcqRel);
+    }
+}
+
+/// Register an additional directory with config files
+///
+/// All `*.conf` files directly inside `dir` are loaded before the config
+/// files from the XDG data dirs or `$GLYCIN_DATA_DIR`. Therefore, their
+/// loaders and editors take precedence over installed ones, but not over
+/// built-in ones. Directories registered earlier take precedence over later
+/// ones.
+///
+/// The config is reloaded for the next load or edit. Together with
+/// [`register_sniffer`](crate::register_sniffer), this allows supporting
+/// custom formats without installing config files.
+#[cfg(feature = "external")]
+pub fn register_config_search_dir(dir: impl Into<PathBuf>) -> ConfigSearchDirRegistration {
+    let id = CONFIG_GENERATION.fetch_add(1, Ordering::AcqRel) + 1;
+
+    CONFIG_SEARCH_DIRS
+        .write()
+        .unwrap_or_else(|err| err.into_inner())
+        .push((id, dir.into()));
+
+    ConfigSearchDirRegistration { id }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct Config {
     pub(crate) image_loader: BTreeMap<MimeType, ImageLoaderConfig>,
@@ -191,6 +246,10 @@ pub struct ImageLoaderConfig {
     pub identifiers: Vec<Identifier>,
     pub expose_base_dir: bool,
     pub fontconfig: bool,
+    /// Loader can load animations
+    pub animation: bool,
+    /// Loader can provide metadata like Exif or XMP
+    pub metadata: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -247,7 +306,11 @@ pub struct ConfigEntryHash {
     processor: Processor,
     expose_base_dir: bool,
     base_dir: Option<PathBuf>,
+    sidecars: Vec<PathBuf>,
+    tmpdir: Option<PathBuf>,
     sandbox_mechanism: SandboxMechanism,
+    sandbox_wrapper: Option<SandboxWrapper>,
+    process_priority: ProcessPriority,
 }
 
 impl ConfigEntryHash {
@@ -264,6 +327,7 @@ pub struct ImageEditorConfig {
     pub(crate) fontconfig: bool,
     pub(crate) operations: BTreeSet<OperationId>,
     pub(crate) creator: bool,
+    pub(crate) creator_animation: bool,
     pub(crate) creator_color_icc_profile: bool,
     pub(crate) creator_encoding_quality: bool,
     pub(crate) creator_encoding_compression: bool,
@@ -292,14 +356,22 @@ impl ConfigEntry {
     pub fn hash_value(
         &self,
         base_dir: Option<PathBuf>,
+        sidecars: Vec<PathBuf>,
+        tmpdir: Option<PathBuf>,
         sandbox_mechanism: SandboxMechanism,
+        sandbox_wrapper: Option<SandboxWrapper>,
+        process_priority: ProcessPriority,
     ) -> ConfigEntryHash {
         ConfigEntryHash {
             fontconfig: self.fontconfig(),
             processor: self.processor().clone(),
             expose_base_dir: self.expose_base_dir(),
             base_dir,
+            sidecars,
+            tmpdir,
             sandbox_mechanism,
+            sandbox_wrapper,
+            process_priority,
         }
     }
 
@@ -341,14 +413,17 @@ impl ConfigEntry {
 
 impl Config {
     pub async fn cached() -> Arc<Self> {
-        static CONFIG: AsyncMutex<Option<Arc<Config>>> = new_async_mutex(None);
+        static CONFIG: AsyncMutex<Option<(u64, Arc<Config>)>> = new_async_mutex(None);
         let mut config = CONFIG.lock().await;
+        let generation = CONFIG_GENERATION.load(Ordering::Acquire);
 
-        if let Some(config) = config.clone() {
+        if let Some((config_generation, config)) = config.clone()
+            && config_generation == generation
+        {
             config
         } else {
             let loaded_config = Arc::new(Self::load().await);
-            *config = Some(loaded_config.clone());
+            *config = Some((generation, loaded_config.clone()));
             loaded_config
         }
     }
@@ -387,12 +462,8 @@ impl Config {
         .await;
 
         #[cfg(feature = "external")]
-        for mut data_dir in Self::data_dirs() {
-            data_dir.push("glycin-loaders");
-            data_dir.push(format!("{}+", crate::COMPAT_VERSION));
-            data_dir.push("conf.d");
-
-            if let Ok(mut config_files) = util::read_dir(data_dir).await {
+        for config_dir in Self::config_dirs() {
+            if let Ok(mut config_files) = util::read_dir(config_dir).await {
                 while let Some(result) = config_files.next().await {
                     if let Ok(path) = result
                         && path.extension() == Some(OsStr::new(CONFIG_FILE_EXT))
@@ -477,11 +548,15 @@ impl Config {
             let expose_base_dir =
                 Self::handle_and_default(keyfile.boolean(&group, "ExposeBaseDir"))?;
             let fontconfig = Self::handle_and_default(keyfile.boolean(&group, "Fontconfig"))?;
+            let animation = Self::handle_and_default(keyfile.boolean(&group, "Animation"))?;
+            let metadata = Self::handle_and_default(keyfile.boolean(&group, "Metadata"))?;
 
             let cfg = ImageLoaderConfig {
                 processor,
                 expose_base_dir,
                 fontconfig,
+                animation,
+                metadata,
                 identifiers,
             };
 
@@ -537,6 +612,9 @@ impl Config {
 
             let creator = Self::handle_and_default(keyfile.boolean(&group, "Creator"))?;
 
+            let creator_animation =
+                Self::handle_and_default(keyfile.boolean(&group, "CreatorAnimation"))?;
+
             let creator_color_icc_profile =
                 Self::handle_and_default(keyfile.boolean(&group, "CreatorColorIccProfile"))?;
 
@@ -573,6 +651,7 @@ impl Config {
                 fontconfig,
                 operations,
                 creator,
+                creator_animation,
                 creator_color_icc_profile,
                 creator_encoding_compression,
                 creator_encoding_quality,
@@ -587,6 +666,26 @@ impl Config {
         Ok(())
     }
 
+    /// Directories containing config files, registered ones first
+    #[cfg(feature = "external")]
+    fn config_dirs() -> Vec<PathBuf> {
+        let mut config_dirs = CONFIG_SEARCH_DIRS
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+            .iter()
+            .map(|(_, dir)| dir.clone())
+            .collect::<Vec<_>>();
+
+        for mut data_dir in Self::data_dirs() {
+            data_dir.push("glycin-loaders");
+            data_dir.push(format!("{}+", crate::COMPAT_VERSION));
+            data_dir.push("conf.d");
+            config_dirs.push(data_dir);
+        }
+
+        config_dirs
+    }
+
     fn data_dirs() -> Vec<PathBuf> {
         // Force only specific data dir via env variable
         if let Some(data_dir) = std::env::var_os("GLYCIN_DATA_DIR") {
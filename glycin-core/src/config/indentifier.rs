@@ -168,4 +168,20 @@ mod tests {
         let identifier = Identifier::parse(r"0:ab,3:de").unwrap();
         assert!(identifier.matches(None, b"abCde"));
     }
+
+    #[test]
+    fn test_jxl() {
+        // Identifiers from glycin-jxl.conf
+        let codestream = Identifier::parse(r"0:\xFF\x0A").unwrap();
+        let container = Identifier::parse(r"0:\x00\x00\x00\x0CJXL\x20\x0D\x0A\x87\x0A").unwrap();
+
+        assert!(codestream.matches(None, b"\xFF\x0A\xFA\x7F\x01\x90"));
+        assert!(container.matches(
+            None,
+            b"\x00\x00\x00\x0CJXL\x20\x0D\x0A\x87\x0A\x00\x00\x00\x14ftypjxl\x20"
+        ));
+
+        assert!(!container.matches(None, b"\x00\x00\x00\x0CJXL\x20"));
+        assert!(!codestream.matches(None, b"\xFF\xD8\xFF\xE0"));
+    }
 }
@@ -2,11 +2,12 @@
 
 //! Internal DBus API
 
-use std::io::Read;
+use std::io::{Read, Write};
 use std::os::fd::OwnedFd;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use futures_channel::oneshot;
 use futures_util::FutureExt;
@@ -15,34 +16,89 @@ use gio::prelude::*;
 use glycin_common::Operations;
 use glycin_utils::{
     CompleteEditorOutput, EditRequest, EncodedImage, EncodingOptions, FrameRequest, InitRequest,
-    InitializationDetails, NewImage, RemoteEditableImage, RemoteError, RemoteImage, SharedMemory,
-    SparseEditorOutput,
+    InitializationDetails, NewImage, RemoteEditableImage, RemoteError, RemoteImage, RowsRequest,
+    SharedMemory, SparseEditorOutput, SparseOperations,
 };
 use nix::sys::signal;
 use zbus::zvariant::{self, OwnedObjectPath};
 
 use crate::sandbox::Sandbox;
-use crate::util::{self, Task, spawn};
+use crate::util::{self, Deadline, Task, spawn};
 use crate::{
-    DBusProxy, EditableImage, Error, ErrorKind, Image, MimeType, SandboxMechanism, config,
+    DBusProxy, EditableImage, Error, ErrorKind, MimeType, ProcessPriority, SandboxMechanism,
+    SandboxWrapper, config,
 };
 
+/// Length of the token used for [`PoolConfig::token_authentication`](crate::PoolConfig::token_authentication)
+///
+/// The token is hex encoded and therefore never starts with the null byte
+/// that starts the D-Bus authentication.
+const AUTH_TOKEN_LEN: usize = 32;
+
+/// Time the loader has to send the authentication token
+const AUTH_TOKEN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Time the process has to shut down gracefully before it is killed
+///
+/// The shutdown only drops the remaining image states, which doesn't take
+/// long for an idle process. Kept short since the process continues to use
+/// resources until it's killed.
+const SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_millis(100);
+
 #[derive(Debug)]
 pub struct RemoteProcess<P: DBusProxy> {
     dbus_connection: zbus::Connection,
-    _dbus_connection_task: Task<()>,
+    dbus_connection_task: Option<Task<()>>,
     proxy: P,
     pub stderr_content: Arc<Mutex<String>>,
     pub stdout_content: Arc<Mutex<String>>,
     pub process_disconnected: Arc<AtomicBool>,
     cancellable: gio::Cancellable,
     base_dir: Option<PathBuf>,
+    sidecars: Vec<PathBuf>,
 }
 
 impl<P: DBusProxy> Drop for RemoteProcess<P> {
+    /// Asks the process to shut down and kills it afterwards
+    ///
+    /// The shutdown gives the process the chance to clean up temporary files.
+    /// This is best-effort. The process is killed after
+    /// [`SHUTDOWN_GRACE_PERIOD`] even if the shutdown didn't finish.
     fn drop(&mut self) {
         tracing::debug!("Winding down process");
-        self.cancellable.cancel();
+
+        if self.process_disconnected.load(Ordering::Relaxed) {
+            self.cancellable.cancel();
+            return;
+        }
+
+        let proxy = self.proxy.inner().clone();
+        let cancellable = self.cancellable.clone();
+        // Keep the connection running until the process is killed
+        let dbus_connection_task = self.dbus_connection_task.take();
+
+        let shutdown = async move {
+            futures_util::select! {
+                result = proxy.call_method("Shutdown", &()).fuse() => {
+                    if let Err(err) = result {
+                        tracing::debug!("Graceful shutdown failed: {err}");
+                    }
+                }
+                _ = util::timeout_future(SHUTDOWN_GRACE_PERIOD).fuse() => {
+                    tracing::debug!("Graceful shutdown timed out");
+                }
+            }
+
+            cancellable.cancel();
+            drop(dbus_connection_task);
+        };
+
+        // Without a runtime, e.g. when dropped outside of one with tokio,
+        // the process is killed right away
+        if !util::try_spawn_detached(shutdown) {
+            tracing::debug!("No runtime for graceful shutdown, killing process");
+            self.cancellable.cancel();
+        }
     }
 }
 
@@ -52,6 +108,7 @@ static_assertions::assert_impl_all!(RemoteProcess<EditorProxy>: Send, Sync);
 pub trait ZbusProxy<'a>: Sized + Sync + Send + From<zbus::Proxy<'a>> {
     const TYPE: &'static str;
     fn builder(conn: &zbus::Connection) -> zbus::proxy::Builder<'a, Self>;
+    fn inner(&self) -> &zbus::Proxy<'a>;
 }
 
 impl<'a> ZbusProxy<'a> for LoaderProxy<'a> {
@@ -59,6 +116,10 @@ impl<'a> ZbusProxy<'a> for LoaderProxy<'a> {
     fn builder(conn: &zbus::Connection) -> zbus::proxy::Builder<'a, Self> {
         Self::builder(conn)
     }
+
+    fn inner(&self) -> &zbus::Proxy<'a> {
+        Self::inner(self)
+    }
 }
 
 impl<'a> ZbusProxy<'a> for EditorProxy<'a> {
@@ -66,26 +127,52 @@ impl<'a> ZbusProxy<'a> for EditorProxy<'a> {
     fn builder(conn: &zbus::Connection) -> zbus::proxy::Builder<'a, Self> {
         Self::builder(conn)
     }
+
+    fn inner(&self) -> &zbus::Proxy<'a> {
+        Self::inner(self)
+    }
 }
 
 impl<P: DBusProxy> RemoteProcess<P> {
     pub async fn new(
         config_entry: config::ConfigEntry,
         sandbox_mechanism: SandboxMechanism,
+        sandbox_wrapper: Option<SandboxWrapper>,
         base_dir: Option<PathBuf>,
+        sidecars: Vec<PathBuf>,
+        tmpdir: Option<PathBuf>,
+        process_priority: ProcessPriority,
+        token_authentication: bool,
         cancellable: &gio::Cancellable,
     ) -> Result<Self, Error> {
         // UnixStream which facilitates the D-Bus connection. The stream is passed as
         // stdin to loader binaries.
         let (unix_stream, loader_stdin) = std::os::unix::net::UnixStream::pair()?;
-        unix_stream.set_nonblocking(true)?;
         loader_stdin.set_nonblocking(true)?;
 
         let mut sandbox = Sandbox::new(sandbox_mechanism, config_entry.clone(), loader_stdin)?;
+        if let Some(sandbox_wrapper) = sandbox_wrapper {
+            sandbox.set_wrapper(sandbox_wrapper);
+        }
         // Mount dir that contains the file as read only for formats like SVG
         if let Some(base_dir) = &base_dir {
             sandbox.add_ro_bind(base_dir.clone());
         }
+        for sidecar in &sidecars {
+            sandbox.add_ro_bind(sidecar.clone());
+        }
+        if let Some(tmpdir) = tmpdir {
+            let tmpdir = util::spawn_blocking(move || check_tmpdir(tmpdir)).await??;
+            sandbox.set_tmpdir(tmpdir);
+        }
+        sandbox.set_process_priority(process_priority);
+
+        let auth_token = if token_authentication {
+            sandbox.enable_token_authentication();
+            Some(generate_auth_token()?)
+        } else {
+            None
+        };
 
         let spawned_sandbox = sandbox.spawn().await?;
 
@@ -110,6 +197,27 @@ impl<P: DBusProxy> RemoteProcess<P> {
                     tracing::debug!("Spawning loader/editor:\n    {command_dbg}");
                     let mut child = match command.spawn() {
                         Ok(mut child) => {
+                            // Only the loader can read the token from its stdin
+                            if let Some(auth_token) = auth_token
+                                && let Err(err) = write_auth_token(&mut child, &auth_token)
+                            {
+                                tracing::debug!("Failed to pass authentication token: {err}");
+                                let _result = child.kill();
+                                let _result = child.wait();
+                                process_disconnected.store(true, Ordering::Relaxed);
+
+                                let err = ErrorKind::StdIoError {
+                                    err: Arc::new(err),
+                                    info: command_dbg.clone(),
+                                };
+                                if let Err(err) = sender_child.send(Err(err)) {
+                                    tracing::info!(
+                                    "Failed to inform coordinating thread about process state: {err:?}"
+                                );
+                                }
+                                return;
+                            }
+
                             let id = child.id();
                             let info = Ok((child.stderr.take(), child.stdout.take(), id));
                             if let Err(err) = sender_child.send(info) {
@@ -173,17 +281,27 @@ impl<P: DBusProxy> RemoteProcess<P> {
             "stdout",
         );
 
-        #[cfg(feature = "tokio")]
-        let unix_stream = tokio::net::UnixStream::from_std(unix_stream)?;
-
         let guid = zbus::Guid::generate();
-        let dbus_result = zbus::connection::Builder::unix_stream(unix_stream)
-            .p2p()
-            .server(guid)?
-            .auth_mechanism(zbus::AuthMechanism::Anonymous)
-            .internal_executor(false)
-            .build()
-            .shared();
+        let dbus_result = async move {
+            if let Some(auth_token) = auth_token {
+                verify_auth_token(&unix_stream, auth_token).await?;
+            }
+
+            unix_stream.set_nonblocking(true)?;
+
+            #[cfg(feature = "tokio")]
+            let unix_stream = tokio::net::UnixStream::from_std(unix_stream)?;
+
+            zbus::connection::Builder::unix_stream(unix_stream)
+                .p2p()
+                .server(guid)?
+                .auth_mechanism(zbus::AuthMechanism::Anonymous)
+                .internal_executor(false)
+                .build()
+                .await
+                .map_err(Error::from)
+        }
+        .shared();
 
         let subprocess_id = nix::unistd::Pid::from_raw(child_process.2.try_into().unwrap());
 
@@ -229,13 +347,14 @@ impl<P: DBusProxy> RemoteProcess<P> {
 
         Ok(Self {
             dbus_connection,
-            _dbus_connection_task: dbus_connection_task,
+            dbus_connection_task: Some(dbus_connection_task),
             proxy: decoding_instruction,
             stderr_content,
             stdout_content,
             process_disconnected,
             cancellable: cancellable.clone(),
             base_dir,
+            sidecars,
         })
     }
 
@@ -243,13 +362,16 @@ impl<P: DBusProxy> RemoteProcess<P> {
         &self,
         mime_type: &MimeType,
         external_reader: OwnedFd,
+        mut details: InitializationDetails,
     ) -> Result<InitRequest, Error> {
         let fd = zvariant::OwnedFd::from(external_reader);
 
         let mime_type = mime_type.to_string();
 
-        let mut details = InitializationDetails::default();
         details.base_dir = self.base_dir.clone();
+        details.sidecars = (!self.sidecars.is_empty()).then(|| self.sidecars.clone());
+        // CMYK frames are converted in `Frame::from_loader`
+        details.accepts_cmyk = true;
 
         Ok(InitRequest {
             fd,
@@ -257,6 +379,31 @@ impl<P: DBusProxy> RemoteProcess<P> {
             details,
         })
     }
+
+    /// Kills the process immediately
+    pub fn kill(&self) {
+        self.cancellable.cancel();
+    }
+
+    /// Kills the process if `future` doesn't finish before the deadline
+    async fn with_deadline<T>(
+        &self,
+        deadline: Option<Deadline>,
+        future: impl Future<Output = Result<T, Error>>,
+    ) -> Result<T, Error> {
+        let Some(deadline) = deadline else {
+            return future.await;
+        };
+
+        futures_util::select! {
+            result = future.fuse() => result,
+            _ = util::timeout_future(deadline.remaining()).fuse() => {
+                tracing::debug!("Killing process due to timeout.");
+                self.kill();
+                Err(ErrorKind::Timeout(deadline.elapsed()).err())
+            }
+        }
+    }
 }
 
 impl RemoteProcess<LoaderProxy<'static>> {
@@ -264,10 +411,16 @@ impl RemoteProcess<LoaderProxy<'static>> {
         &self,
         mime_type: &MimeType,
         external_reader: OwnedFd,
+        details: InitializationDetails,
+        deadline: Option<Deadline>,
     ) -> Result<RemoteImage<SharedMemory>, Error> {
-        let init_request = self.init_request(mime_type, external_reader)?;
+        let init_request = self.init_request(mime_type, external_reader, details)?;
 
-        let image_info = self.proxy.init(init_request).await?;
+        let image_info = self
+            .with_deadline(deadline, async {
+                self.proxy.init(init_request).await.map_err(Into::into)
+            })
+            .await?;
 
         Ok(image_info)
     }
@@ -285,17 +438,53 @@ impl RemoteProcess<LoaderProxy<'static>> {
     pub async fn request_frame(
         &self,
         frame_request: FrameRequest,
-        image: &Image,
+        frame_request_path: OwnedObjectPath,
+        deadline: Option<Deadline>,
+    ) -> Result<glycin_utils::Frame<SharedMemory>, Error> {
+        let loader_proxy = LoaderStateProxy::builder(&self.dbus_connection)
+            .destination("org.gnome.glycin")?
+            .path(frame_request_path)?
+            .build()
+            .await?;
+
+        self.with_deadline(deadline, async {
+            loader_proxy.frame(frame_request).await.map_err(frame_error)
+        })
+        .await
+    }
+
+    pub async fn request_rows(
+        &self,
+        rows_request: RowsRequest,
+        frame_request_path: OwnedObjectPath,
+        deadline: Option<Deadline>,
     ) -> Result<glycin_utils::Frame<SharedMemory>, Error> {
-        let frame_request_path = image.frame_request_path();
+        let loader_proxy = LoaderStateProxy::builder(&self.dbus_connection)
+            .destination("org.gnome.glycin")?
+            .path(frame_request_path)?
+            .build()
+            .await?;
+
+        self.with_deadline(deadline, async {
+            loader_proxy
+                .next_rows(rows_request)
+                .await
+                .map_err(frame_error)
+        })
+        .await
+    }
 
+    /// Interrupts the frame request without ending the process
+    ///
+    /// Returns `false` if the loader does not support interrupting frames.
+    pub async fn cancel_frame(&self, frame_request_path: OwnedObjectPath) -> Result<bool, Error> {
         let loader_proxy = LoaderStateProxy::builder(&self.dbus_connection)
             .destination("org.gnome.glycin")?
             .path(frame_request_path)?
             .build()
             .await?;
 
-        loader_proxy.frame(frame_request).await.map_err(Into::into)
+        loader_proxy.cancel_frame().await.map_err(Into::into)
     }
 }
 
@@ -317,7 +506,8 @@ impl RemoteProcess<EditorProxy<'static>> {
         external_reader: OwnedFd,
         mime_type: &MimeType,
     ) -> Result<RemoteEditableImage, Error> {
-        let init_request = self.init_request(mime_type, external_reader)?;
+        let init_request =
+            self.init_request(mime_type, external_reader, InitializationDetails::default())?;
 
         self.proxy.edit(init_request).await.map_err(Into::into)
     }
@@ -325,6 +515,7 @@ impl RemoteProcess<EditorProxy<'static>> {
     pub async fn editor_apply_sparse(
         &self,
         operations: &Operations,
+        encoding_options: EncodingOptions,
         editable_image: &EditableImage,
     ) -> Result<SparseEditorOutput<SharedMemory>, Error> {
         let editor_proxy = EditableImageProxy::builder(&self.dbus_connection)
@@ -333,7 +524,8 @@ impl RemoteProcess<EditorProxy<'static>> {
             .build()
             .await?;
 
-        let edit_request = EditRequest::for_operations(operations)?;
+        let mut edit_request = EditRequest::for_operations(operations)?;
+        edit_request.set_encoding_options(encoding_options);
 
         editor_proxy
             .apply_sparse(edit_request)
@@ -341,9 +533,37 @@ impl RemoteProcess<EditorProxy<'static>> {
             .map_err(Into::into)
     }
 
+    /// Operations the editor can apply sparsely
+    ///
+    /// Editors that don't know the method report no sparse operations.
+    pub async fn editor_sparse_operations(
+        &self,
+        operations: &Operations,
+        editable_image: &EditableImage,
+    ) -> Result<SparseOperations, Error> {
+        let editor_proxy = EditableImageProxy::builder(&self.dbus_connection)
+            .destination("org.gnome.glycin")?
+            .path(editable_image.edit_request_path())?
+            .build()
+            .await?;
+
+        let edit_request = EditRequest::for_operations(operations)?;
+
+        match editor_proxy.sparse_operations(edit_request).await {
+            Err(RemoteError::ZBus(zbus::Error::MethodError(name, _, _)))
+                if name.as_str() == "org.freedesktop.DBus.Error.UnknownMethod" =>
+            {
+                tracing::debug!("Editor doesn't support reporting sparse operations");
+                Ok(SparseOperations::default())
+            }
+            result => result.map_err(Into::into),
+        }
+    }
+
     pub async fn editor_apply_complete(
         &self,
         operations: &Operations,
+        encoding_options: EncodingOptions,
         editable_image: &EditableImage,
     ) -> Result<CompleteEditorOutput<SharedMemory>, Error> {
         let editor_proxy = EditableImageProxy::builder(&self.dbus_connection)
@@ -352,7 +572,8 @@ impl RemoteProcess<EditorProxy<'static>> {
             .build()
             .await?;
 
-        let edit_request = EditRequest::for_operations(operations)?;
+        let mut edit_request = EditRequest::for_operations(operations)?;
+        edit_request.set_encoding_options(encoding_options);
 
         editor_proxy
             .apply_complete(edit_request)
@@ -384,6 +605,8 @@ pub trait Loader {
         &self,
         init_request: InitRequest,
     ) -> Result<RemoteImage<SharedMemory>, RemoteError>;
+
+    async fn shutdown(&self) -> Result<(), RemoteError>;
 }
 
 #[zbus::proxy(name = "org.gnome.glycin.Image")]
@@ -392,6 +615,11 @@ pub trait LoaderState {
         &self,
         frame_request: FrameRequest,
     ) -> Result<glycin_utils::Frame<SharedMemory>, RemoteError>;
+    async fn next_rows(
+        &self,
+        rows_request: RowsRequest,
+    ) -> Result<glycin_utils::Frame<SharedMemory>, RemoteError>;
+    async fn cancel_frame(&self) -> Result<bool, RemoteError>;
     async fn done(&self) -> Result<(), RemoteError>;
 }
 
@@ -408,6 +636,8 @@ pub trait Editor {
     ) -> Result<EncodedImage<SharedMemory>, RemoteError>;
 
     async fn edit(&self, init_request: InitRequest) -> Result<RemoteEditableImage, RemoteError>;
+
+    async fn shutdown(&self) -> Result<(), RemoteError>;
 }
 
 #[zbus::proxy(interface = "org.gnome.glycin.EditableImage")]
@@ -417,6 +647,11 @@ pub trait EditableImage {
         edit_request: EditRequest,
     ) -> Result<SparseEditorOutput<SharedMemory>, RemoteError>;
 
+    async fn sparse_operations(
+        &self,
+        edit_request: EditRequest,
+    ) -> Result<SparseOperations, RemoteError>;
+
     async fn apply_complete(
         &self,
         edit_request: EditRequest,
@@ -425,6 +660,116 @@ pub trait EditableImage {
     async fn done(&self) -> Result<(), RemoteError>;
 }
 
+/// Error of a request that returns a frame
+fn frame_error(err: RemoteError) -> Error {
+    match err.detect_unknown_memory_format() {
+        RemoteError::UnknownMemoryFormat { value } => ErrorKind::UnknownMemoryFormat(value).err(),
+        err => err.into(),
+    }
+}
+
+/// Checks that the temporary directory exists and is writable
+///
+/// Returns the canonicalized path, such that it can be mounted in the
+/// sandbox.
+fn check_tmpdir(path: PathBuf) -> Result<PathBuf, Error> {
+    let invalid = |err| {
+        ErrorKind::InvalidTmpdir {
+            path: path.clone(),
+            err: Arc::new(err),
+        }
+        .err()
+    };
+
+    let canonical_path = std::fs::canonicalize(&path).map_err(invalid)?;
+
+    if !canonical_path.is_dir() {
+        return Err(invalid(std::io::ErrorKind::NotADirectory.into()));
+    }
+
+    nix::unistd::access(
+        &canonical_path,
+        nix::unistd::AccessFlags::W_OK | nix::unistd::AccessFlags::X_OK,
+    )
+    .map_err(|err| invalid(err.into()))?;
+
+    Ok(canonical_path)
+}
+
+fn generate_auth_token() -> Result<[u8; AUTH_TOKEN_LEN], Error> {
+    let mut random = [0; AUTH_TOKEN_LEN / 2];
+    std::fs::File::open("/dev/urandom")?.read_exact(&mut random)?;
+
+    let mut token = [0; AUTH_TOKEN_LEN];
+    for (hex, byte) in token.chunks_exact_mut(2).zip(random) {
+        hex.copy_from_slice(format!("{byte:02x}").as_bytes());
+    }
+
+    Ok(token)
+}
+
+/// Passes the token via stdin and closes it afterwards
+fn write_auth_token(
+    child: &mut std::process::Child,
+    auth_token: &[u8; AUTH_TOKEN_LEN],
+) -> std::io::Result<()> {
+    let mut stdin = child
+        .stdin
+        .take()
+        .ok_or_else(|| std::io::Error::other("No stdin for process"))?;
+    stdin.write_all(auth_token)
+}
+
+/// Checks that the first bytes the loader sends are the expected token
+async fn verify_auth_token(
+    unix_stream: &std::os::unix::net::UnixStream,
+    auth_token: [u8; AUTH_TOKEN_LEN],
+) -> Result<(), Error> {
+    let mut unix_stream = unix_stream.try_clone()?;
+
+    let received = util::spawn_blocking(move || read_auth_token(&mut unix_stream)).await??;
+
+    check_auth_token(&received, &auth_token)
+}
+
+/// Reads the token from the start of the stream
+///
+/// Fails right away if the loader doesn't support the token and starts the
+/// D-Bus authentication instead, which begins with a null byte.
+fn read_auth_token(
+    unix_stream: &mut std::os::unix::net::UnixStream,
+) -> Result<[u8; AUTH_TOKEN_LEN], Error> {
+    unix_stream.set_read_timeout(Some(AUTH_TOKEN_TIMEOUT))?;
+
+    let mut received = [0; AUTH_TOKEN_LEN];
+    unix_stream.read_exact(&mut received[..1])?;
+    if received[0] == 0 {
+        return Err(ErrorKind::AuthenticationFailed.err());
+    }
+    unix_stream.read_exact(&mut received[1..])?;
+
+    unix_stream.set_read_timeout(None)?;
+
+    Ok(received)
+}
+
+fn check_auth_token(
+    received: &[u8; AUTH_TOKEN_LEN],
+    auth_token: &[u8; AUTH_TOKEN_LEN],
+) -> Result<(), Error> {
+    // Compare in constant time
+    let diff = received
+        .iter()
+        .zip(auth_token.iter())
+        .fold(0, |acc, (a, b)| acc | (a ^ b));
+
+    if diff == 0 {
+        Ok(())
+    } else {
+        Err(ErrorKind::AuthenticationFailed.err())
+    }
+}
+
 #[cfg(not(feature = "tokio"))]
 fn spawn_stdio_reader(
     stdio: &mut Option<impl Read + Send + std::os::fd::AsFd + async_io::IoSafe + 'static>,
@@ -507,3 +852,46 @@ fn spawn_stdio_reader(
             });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::net::UnixStream;
+
+    use super::*;
+
+    fn authenticate(sent: &[u8], auth_token: &[u8; AUTH_TOKEN_LEN]) -> Result<(), Error> {
+        let (mut unix_stream, mut loader) = UnixStream::pair().unwrap();
+        loader.write_all(sent).unwrap();
+        drop(loader);
+
+        let received = read_auth_token(&mut unix_stream)?;
+        check_auth_token(&received, auth_token)
+    }
+
+    #[test]
+    fn auth_token() {
+        let auth_token = generate_auth_token().unwrap();
+        assert!(auth_token.iter().all(u8::is_ascii_hexdigit));
+        assert_ne!(auth_token, generate_auth_token().unwrap());
+
+        let authentication_failed = ErrorKind::AuthenticationFailed.err().to_string();
+
+        // Correct token
+        authenticate(&auth_token, &auth_token).unwrap();
+
+        // Wrong token
+        let wrong_token = generate_auth_token().unwrap();
+        let err = authenticate(&wrong_token, &auth_token).unwrap_err();
+        assert_eq!(err.to_string(), authentication_failed);
+
+        // Missing token, fails without waiting for the timeout
+        let start = std::time::Instant::now();
+        let err = authenticate(b"\0AUTH ANONYMOUS\r\n", &auth_token).unwrap_err();
+        assert_eq!(err.to_string(), authentication_failed);
+        assert!(start.elapsed() < AUTH_TOKEN_TIMEOUT);
+
+        // Incomplete token
+        let err = authenticate(&auth_token[..10], &auth_token).unwrap_err();
+        assert_ne!(err.to_string(), authentication_failed);
+    }
+}
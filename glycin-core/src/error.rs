@@ -1,5 +1,6 @@
 use std::any::Any;
 use std::fmt::Display;
+use std::path::PathBuf;
 use std::process::ExitStatus;
 use std::sync::Arc;
 use std::time::Duration;
@@ -38,9 +39,39 @@ impl std::fmt::Display for ErrorContext {
     }
 }
 
+/// Stage of the pipeline in which an error occurred
+///
+/// Returned by [`Error::stage`]. The stage is tagged when an error passes the
+/// boundary of a stage. If an error crosses several boundaries, the innermost
+/// stage is kept. This is best-effort: Errors that originate deep inside a
+/// loader are only attributed to the stage of the request that returned
+/// them, and errors that don't pass any tagged boundary, like failing to
+/// read the file before a loader is spawned, are reported as
+/// [`Stage::Unknown`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub enum Stage {
+    /// The error was not attributed to a stage
+    #[default]
+    Unknown,
+    /// Spawning or reusing the loader or editor process
+    Spawn,
+    /// Initializing the image and reading its details
+    Init,
+    /// Decoding and post-processing a frame
+    Frame,
+    /// Applying the ICC profile to a frame
+    Icc,
+    /// Editing or encoding an image
+    Editor,
+}
+
 pub trait ResultExt<T> {
     #[cfg(feature = "external")]
     fn err_context<S: DBusProxy>(self, process: &RemoteProcess<S>) -> Result<T, Error>;
+
+    /// Tags the error with `stage` unless it already has a stage
+    fn stage(self, stage: Stage) -> Result<T, Error>;
 }
 
 impl<T, E: Into<Error>> ResultExt<T> for Result<T, E> {
@@ -60,6 +91,18 @@ impl<T, E: Into<Error>> ResultExt<T> for Result<T, E> {
             }
         }
     }
+
+    fn stage(self, stage: Stage) -> Result<T, Error> {
+        self.map_err(|err| {
+            let mut err = err.into();
+
+            if err.stage == Stage::Unknown {
+                err.stage = stage;
+            }
+
+            err
+        })
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -67,6 +110,7 @@ impl<T, E: Into<Error>> ResultExt<T> for Result<T, E> {
 pub struct Error {
     kind: Box<ErrorKind>,
     context: Option<ErrorContext>,
+    stage: Stage,
 }
 
 impl Display for Error {
@@ -82,6 +126,7 @@ impl Error {
         Self {
             kind: Box::new(kind),
             context: None,
+            stage: Stage::Unknown,
         }
     }
 
@@ -89,6 +134,7 @@ impl Error {
         Self {
             kind: Box::new(ErrorKind::Other(msg.to_string())),
             context: None,
+            stage: Stage::Unknown,
         }
     }
 
@@ -149,6 +195,13 @@ impl Error {
     pub fn is_timeout(&self) -> bool {
         matches!(*self.kind, ErrorKind::Timeout(_))
     }
+
+    /// Stage of the pipeline in which the error occurred
+    ///
+    /// See [`Stage`] for the limitations.
+    pub fn stage(&self) -> Stage {
+        self.stage
+    }
 }
 
 #[derive(Debug, Clone, thiserror::Error)]
@@ -160,6 +213,14 @@ pub enum ErrorKind {
     GLibError(#[from] glib::Error),
     #[error("Failed to load file/stream: {0}")]
     ImageSource(glib::Error),
+    /// The file was truncated or modified while reading it
+    ///
+    /// This is detected by comparing size and modification time before and
+    /// after reading. Without a snapshot of the file, consistent data can't
+    /// be fully guaranteed, but this signals most changes, for example by sync
+    /// tools rewriting files.
+    #[error("File changed while it was being read")]
+    FileChangedDuringRead,
     #[cfg(feature = "external")]
     #[error("Libc error: {0}")]
     NixError(#[from] nix::errno::Errno),
@@ -197,17 +258,61 @@ pub enum ErrorKind {
     },
     #[error("Texture is only {texture_size} but was announced differently: {frame}")]
     TextureWrongSize { texture_size: usize, frame: String },
+    #[error("Texture is {texture_size} bytes but only {expected} bytes were announced: {frame}")]
+    FrameSizeMismatch {
+        texture_size: usize,
+        expected: usize,
+        frame: String,
+    },
     #[error("Texture size exceeds hardcoded limit of {MAX_TEXTURE_SIZE} bytes")]
     TextureTooLarge,
     #[error("Stride is smaller than possible: {0}")]
     StrideTooSmall(String),
+    #[error("Animation frames must have the same dimensions: {0}")]
+    AnimationDimensionsMismatch(String),
+    #[error("Loader returned rows that don't follow row {first_row}: {rows}")]
+    UnexpectedRows { first_row: u32, rows: String },
     #[error("Width or height is zero: {0}")]
     WidgthOrHeightZero(String),
+    #[error(
+        "Image has invalid dimensions {width}x{height}. The file might be truncated or corrupt."
+    )]
+    InvalidDimensions { width: u32, height: u32 },
+    #[error(
+        "Image is a suspected decompression bomb. Compression ratio {ratio:.0} exceeds limit of {max_ratio}."
+    )]
+    SuspectedDecompressionBomb { ratio: f64, max_ratio: f64 },
+    #[error("Image has no auxiliary image with index {0}")]
+    NoAuxiliaryImage(u32),
+    #[error("Image has no item with ID {0}")]
+    NoImageItem(u32),
+    #[error("Loader sent memory format {0} that is unknown to this version")]
+    UnknownMemoryFormat(i32),
+    #[error("Temporary directory {path:?} can't be used: {err}")]
+    InvalidTmpdir {
+        path: PathBuf,
+        err: Arc<std::io::Error>,
+    },
+    #[error("Image format {0} is not handled by the process of the warm loader")]
+    IncompatibleWarmLoader(config::MimeType),
+    #[error("Alignment must be a power of two but is {0}")]
+    InvalidAlignment(usize),
+    #[error("Operation requires interleaved channels but the frame is planar")]
+    PlanarFrame,
+    #[error("BlurHash components must be between 1 and 9 but are {0}x{1}")]
+    InvalidBlurHashComponents(u32, u32),
+    #[error("Nice value must be between 0 and 19 but is {0}")]
+    InvalidProcessPriority(i32),
+    #[cfg(feature = "external")]
+    #[error("Loader did not authenticate with the expected token")]
+    AuthenticationFailed,
     #[cfg(feature = "external")]
     #[error("Seccomp: {0}")]
     Seccomp(Arc<libseccomp::error::SeccompError>),
     #[error("ICC profile: {0}")]
     IccProfile(#[from] moxcms::CmsError),
+    #[error("ICC profile of {size} bytes exceeds limit of {max_size} bytes")]
+    IccProfileTooLarge { size: usize, max_size: usize },
     #[error("Memory transformation: {0}")]
     MemoryTransformation(#[from] bytemuck::PodCastError),
     #[error("Operation was explicitly canceled.\nOriginal error: {0:?}")]
@@ -0,0 +1,275 @@
+//! Exif values derived from gufo's metadata
+
+/// Shooting parameters of a photo
+///
+/// See [`ImageDetails::camera_settings`](crate::ImageDetails::camera_settings).
+#[derive(Debug, Clone, Default, PartialEq)]
+#[non_exhaustive]
+pub struct CameraSettings {
+    /// ISO speed
+    pub iso: Option<u32>,
+    /// Aperture as f-number, like `2.8` for f/2.8
+    pub f_number: Option<f64>,
+    /// Shutter speed as exposure time in seconds
+    pub exposure_time: Option<f64>,
+    /// Focal length of the lens in millimeters
+    pub focal_length: Option<f64>,
+    /// Name of the lens as reported by the camera, like `RF 50mm F1.8 STM`
+    pub lens_model: Option<String>,
+}
+
+/// Whether the Exif data can be parsed
+pub(crate) fn is_valid(exif: &[u8]) -> bool {
+    gufo_exif::Exif::for_vec(exif.to_vec()).is_ok()
+}
+
+/// Shooting parameters from the Exif data
+///
+/// Returns `None` if none of the parameters are set.
+pub(crate) fn camera_settings(metadata: &gufo::Metadata) -> Option<CameraSettings> {
+    let camera_settings = CameraSettings {
+        iso: metadata
+            .iso_speed_rating()
+            .map(u32::from)
+            .filter(|x| *x > 0),
+        f_number: metadata.f_number().map(f64::from).filter(|x| x.is_finite()),
+        exposure_time: metadata
+            .exposure_time()
+            .filter(|(_, denominator)| *denominator != 0)
+            .map(|(numerator, denominator)| f64::from(numerator) / f64::from(denominator)),
+        focal_length: metadata
+            .focal_length()
+            .map(f64::from)
+            .filter(|x| x.is_finite()),
+        lens_model: metadata.lens_model().filter(|x| !x.is_empty()),
+    };
+
+    (camera_settings != CameraSettings::default()).then_some(camera_settings)
+}
+
+/// Capture time from the `DateTimeOriginal` tag as ISO 8601 string
+///
+/// The offset from UTC is appended if the `OffsetTimeOriginal` tag is set.
+pub(crate) fn date_time_original(metadata: &gufo::Metadata) -> Option<String> {
+    match metadata.date_time_original()? {
+        gufo_common::datetime::DateTime::Naive(date_time) => {
+            Some(date_time.format("%Y-%m-%dT%H:%M:%S").to_string())
+        }
+        gufo_common::datetime::DateTime::FixedOffset(date_time) => {
+            Some(date_time.format("%Y-%m-%dT%H:%M:%S%:z").to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Pointer from IFD0 to the Exif IFD
+    const EXIF_IFD_POINTER: u16 = 0x8769;
+    const DATE_TIME_ORIGINAL: u16 = 0x9003;
+    const OFFSET_TIME_ORIGINAL: u16 = 0x9011;
+    const EXPOSURE_TIME: u16 = 0x829a;
+    const F_NUMBER: u16 = 0x829d;
+    const PHOTOGRAPHIC_SENSITIVITY: u16 = 0x8827;
+    const FOCAL_LENGTH: u16 = 0x920a;
+    const LENS_MODEL: u16 = 0xa434;
+
+    const TYPE_ASCII: u16 = 2;
+    const TYPE_SHORT: u16 = 3;
+    const TYPE_LONG: u16 = 4;
+    const TYPE_RATIONAL: u16 = 5;
+
+    fn metadata(exif: Vec<u8>) -> gufo::Metadata {
+        let mut metadata = gufo::Metadata::new();
+        let _ = metadata.add_raw_exif(exif);
+        metadata
+    }
+
+    /// Little endian Exif data with `DateTimeOriginal` and optional
+    /// `OffsetTimeOriginal`
+    fn exif(offset: Option<&str>) -> Vec<u8> {
+        let n_entries: u16 = if offset.is_some() { 2 } else { 1 };
+        let exif_ifd = 8 + 2 + 12 + 4;
+        let values = exif_ifd + 2 + n_entries as u32 * 12 + 4;
+
+        let mut data = b"II*\0".to_vec();
+        data.extend_from_slice(&8_u32.to_le_bytes());
+
+        // IFD0 with pointer to Exif IFD
+        data.extend_from_slice(&1_u16.to_le_bytes());
+        data.extend_from_slice(&EXIF_IFD_POINTER.to_le_bytes());
+        data.extend_from_slice(&TYPE_LONG.to_le_bytes());
+        data.extend_from_slice(&1_u32.to_le_bytes());
+        data.extend_from_slice(&exif_ifd.to_le_bytes());
+        data.extend_from_slice(&[0; 4]);
+
+        // Exif IFD
+        data.extend_from_slice(&n_entries.to_le_bytes());
+        data.extend_from_slice(&DATE_TIME_ORIGINAL.to_le_bytes());
+        data.extend_from_slice(&TYPE_ASCII.to_le_bytes());
+        data.extend_from_slice(&20_u32.to_le_bytes());
+        data.extend_from_slice(&values.to_le_bytes());
+        if offset.is_some() {
+            data.extend_from_slice(&OFFSET_TIME_ORIGINAL.to_le_bytes());
+            data.extend_from_slice(&TYPE_ASCII.to_le_bytes());
+            data.extend_from_slice(&7_u32.to_le_bytes());
+            data.extend_from_slice(&(values + 20).to_le_bytes());
+        }
+        data.extend_from_slice(&[0; 4]);
+
+        data.extend_from_slice(b"2024:05:03 14:22:10\0");
+        if let Some(offset) = offset {
+            data.extend_from_slice(offset.as_bytes());
+            data.push(0);
+        }
+
+        data
+    }
+
+    #[test]
+    fn with_offset() {
+        assert_eq!(
+            date_time_original(&metadata(exif(Some("+02:00")))).as_deref(),
+            Some("2024-05-03T14:22:10+02:00")
+        );
+
+        let mut data = b"Exif\0\0".to_vec();
+        data.extend(exif(Some("-05:30")));
+        assert_eq!(
+            date_time_original(&metadata(data)).as_deref(),
+            Some("2024-05-03T14:22:10-05:30")
+        );
+    }
+
+    #[test]
+    fn without_offset() {
+        assert_eq!(
+            date_time_original(&metadata(exif(None))).as_deref(),
+            Some("2024-05-03T14:22:10")
+        );
+        assert_eq!(
+            date_time_original(&metadata(exif(Some("local")))).as_deref(),
+            Some("2024-05-03T14:22:10")
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(date_time_original(&metadata(Vec::new())), None);
+        assert_eq!(
+            date_time_original(&metadata(exif(None)[..30].to_vec())),
+            None
+        );
+
+        assert!(!is_valid(b""));
+        assert!(is_valid(&exif(None)));
+    }
+
+    /// Little endian Exif data with the given entries in the Exif IFD
+    ///
+    /// Entries are `(tag, type, count, value)`. Values longer than four bytes
+    /// are stored after the IFD.
+    fn exif_entries(entries: &[(u16, u16, u32, Vec<u8>)]) -> Vec<u8> {
+        let exif_ifd = 8 + 2 + 12 + 4;
+        let mut value_pos = exif_ifd + 2 + entries.len() as u32 * 12 + 4;
+
+        let mut data = b"II*\0".to_vec();
+        data.extend_from_slice(&8_u32.to_le_bytes());
+
+        // IFD0 with pointer to Exif IFD
+        data.extend_from_slice(&1_u16.to_le_bytes());
+        data.extend_from_slice(&EXIF_IFD_POINTER.to_le_bytes());
+        data.extend_from_slice(&TYPE_LONG.to_le_bytes());
+        data.extend_from_slice(&1_u32.to_le_bytes());
+        data.extend_from_slice(&exif_ifd.to_le_bytes());
+        data.extend_from_slice(&[0; 4]);
+
+        // Exif IFD
+        let mut values = Vec::new();
+        data.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        for (tag, field_type, count, value) in entries {
+            data.extend_from_slice(&tag.to_le_bytes());
+            data.extend_from_slice(&field_type.to_le_bytes());
+            data.extend_from_slice(&count.to_le_bytes());
+            if value.len() <= 4 {
+                let mut inline = value.clone();
+                inline.resize(4, 0);
+                data.extend_from_slice(&inline);
+            } else {
+                data.extend_from_slice(&value_pos.to_le_bytes());
+                value_pos += value.len() as u32;
+                values.extend_from_slice(value);
+            }
+        }
+        data.extend_from_slice(&[0; 4]);
+        data.extend(values);
+
+        data
+    }
+
+    fn rational(numerator: u32, denominator: u32) -> (u16, u32, Vec<u8>) {
+        let mut value = numerator.to_le_bytes().to_vec();
+        value.extend_from_slice(&denominator.to_le_bytes());
+        (TYPE_RATIONAL, 1, value)
+    }
+
+    fn entry(
+        tag: u16,
+        (field_type, count, value): (u16, u32, Vec<u8>),
+    ) -> (u16, u16, u32, Vec<u8>) {
+        (tag, field_type, count, value)
+    }
+
+    #[test]
+    fn camera_settings_with_lens() {
+        let lens = b"RF 50mm F1.8 STM\0".to_vec();
+        let data = exif_entries(&[
+            entry(EXPOSURE_TIME, rational(1, 250)),
+            entry(F_NUMBER, rational(28, 10)),
+            entry(
+                PHOTOGRAPHIC_SENSITIVITY,
+                (TYPE_SHORT, 1, 400_u16.to_le_bytes().to_vec()),
+            ),
+            entry(FOCAL_LENGTH, rational(50, 1)),
+            entry(LENS_MODEL, (TYPE_ASCII, lens.len() as u32, lens)),
+        ]);
+
+        assert_eq!(
+            camera_settings(&metadata(data)),
+            Some(CameraSettings {
+                iso: Some(400),
+                // gufo reads rationals as `f32`
+                f_number: Some(f64::from(2.8_f32)),
+                exposure_time: Some(0.004),
+                focal_length: Some(50.),
+                lens_model: Some(String::from("RF 50mm F1.8 STM")),
+            })
+        );
+    }
+
+    #[test]
+    fn camera_settings_without_lens() {
+        let data = exif_entries(&[
+            entry(F_NUMBER, rational(18, 10)),
+            entry(
+                PHOTOGRAPHIC_SENSITIVITY,
+                (TYPE_LONG, 1, 12800_u32.to_le_bytes().to_vec()),
+            ),
+            // Invalid denominator
+            entry(FOCAL_LENGTH, rational(50, 0)),
+        ]);
+
+        assert_eq!(
+            camera_settings(&metadata(data)),
+            Some(CameraSettings {
+                iso: Some(12800),
+                f_number: Some(f64::from(1.8_f32)),
+                ..Default::default()
+            })
+        );
+
+        assert_eq!(camera_settings(&metadata(exif(None))), None);
+        assert_eq!(camera_settings(&metadata(Vec::new())), None);
+    }
+}
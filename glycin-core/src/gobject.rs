@@ -12,7 +12,7 @@ use std::sync::OnceLock;
 
 pub use creator::GlyCreator;
 pub use encoded_image::GlyEncodedImage;
-pub use frame::{GlyCicp, GlyFrame};
+pub use frame::{GlyCicp, GlyColorMode, GlyFrame};
 pub use frame_details::GlyFrameDetails;
 pub use frame_request::GlyFrameRequest;
 pub use image::GlyImage;
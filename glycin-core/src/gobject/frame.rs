@@ -1,3 +1,4 @@
+use std::marker::PhantomData;
 use std::sync::OnceLock;
 
 use gio::glib;
@@ -34,6 +35,13 @@ pub mod imp {
     #[properties(wrapper_type = super::GlyFrame)]
     pub struct GlyFrame {
         pub(super) frame: OnceLock<Frame>,
+
+        /// Color state of the texture
+        ///
+        /// For [`GlyColorMode::Cicp`], the code points are available via
+        /// [`color_cicp`](super::GlyFrame::color_cicp).
+        #[property(get=Self::color_state, builder(GlyColorMode::Srgb))]
+        color_state: PhantomData<GlyColorMode>,
     }
 
     #[glib::object_subclass]
@@ -44,6 +52,12 @@ pub mod imp {
 
     #[glib::derived_properties]
     impl ObjectImpl for GlyFrame {}
+
+    impl GlyFrame {
+        fn color_state(&self) -> GlyColorMode {
+            self.obj().color_mode()
+        }
+    }
 }
 
 glib::wrapper! {
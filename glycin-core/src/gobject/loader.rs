@@ -5,7 +5,7 @@ use gio::glib;
 use glib::g_critical;
 use glib::prelude::*;
 use glib::subclass::prelude::*;
-use glycin_common::MemoryFormatSelection;
+use glycin_common::{MemoryFormat, MemoryFormatSelection};
 
 use super::{GlyImage, init};
 use crate::main_context::ProvidesMainContext;
@@ -33,6 +33,8 @@ pub mod imp {
         sandbox_selector: PhantomData<SandboxSelector>,
         #[property(set=Self::set_accepted_memory_formats)]
         accepted_memory_formats: PhantomData<MemoryFormatSelection>,
+        #[property(set=Self::set_memory_format, builder(MemoryFormat::R8g8b8a8Premultiplied))]
+        memory_format: PhantomData<MemoryFormat>,
         #[property(set=Self::set_apply_transformations)]
         apply_transformations: PhantomData<bool>,
 
@@ -116,6 +118,12 @@ pub mod imp {
             });
         }
 
+        fn set_memory_format(&self, memory_format: MemoryFormat) {
+            self.inspect(|x| {
+                x.memory_format(memory_format);
+            });
+        }
+
         fn set_apply_transformations(&self, apply_transformations: bool) {
             self.inspect(|x| {
                 x.apply_transformations(apply_transformations);
@@ -2,8 +2,60 @@ use std::sync::Arc;
 
 use glycin_common::{ChannelType, MemoryFormat, MemoryFormatInfo};
 use glycin_utils::{FungibleMemory, MemoryFormatSelection};
+use gufo_common::math::Checked;
 
-use crate::{ColorState, Error};
+use crate::{ColorState, Error, ErrorKind};
+
+/// Structure of the device to PCS transform of an ICC profile
+///
+/// The structure determines how expensive and how accurate a transformation
+/// via the profile is. Matrix and gray profiles are evaluated with a few
+/// curves and a 3×3 matrix, while lookup tables require interpolation in a
+/// multidimensional grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum IccProfileClass {
+    /// RGB profile with colorants and tone reproduction curves (`rXYZ`,
+    /// `rTRC`, etc.)
+    MatrixShaper,
+    /// Gray profile with a single tone reproduction curve (`kTRC`)
+    Gray,
+    /// Profile with `A2B0`, `A2B1`, or `A2B2` lookup tables
+    ///
+    /// Lookup tables take precedence over colorants and curves if a profile
+    /// contains both.
+    Lut,
+    /// Profile without any of the above, like device link or named color
+    /// profiles
+    Other,
+}
+
+impl IccProfileClass {
+    pub(crate) fn from_profile(icc_profile: &[u8]) -> Result<Self, Error> {
+        let profile = moxcms::ColorProfile::new_from_slice(icc_profile)?;
+
+        let has_lut = profile.lut_a_to_b_perceptual.is_some()
+            || profile.lut_a_to_b_colorimetric.is_some()
+            || profile.lut_a_to_b_saturation.is_some();
+
+        let class = if has_lut {
+            Self::Lut
+        } else if profile.color_space == moxcms::DataColorSpace::Rgb
+            && profile.red_trc.is_some()
+            && profile.green_trc.is_some()
+            && profile.blue_trc.is_some()
+        {
+            Self::MatrixShaper
+        } else if profile.color_space == moxcms::DataColorSpace::Gray && profile.gray_trc.is_some()
+        {
+            Self::Gray
+        } else {
+            Self::Other
+        };
+
+        Ok(class)
+    }
+}
 
 pub fn apply_transformation(
     icc_profile: &[u8],
@@ -87,6 +139,15 @@ fn transform(
     let multiple = std::thread::available_parallelism().map_or(2, |x| x.get());
     tracing::trace!("Applying ICC profiles while using {multiple} threads");
 
+    if frame.memory_format.is_cmyk() {
+        let src_profile = moxcms::ColorProfile::new_from_slice(icc_profile)?;
+        // Other profiles can't describe CMYK data, such that the data are
+        // converted naively before applying them
+        if src_profile.color_space == moxcms::DataColorSpace::Cmyk {
+            return transform_cmyk(&src_profile, frame, multiple);
+        }
+    }
+
     let supported_formats = MemoryFormatSelection::R8g8b8
         | MemoryFormatSelection::R16g16b16
         | MemoryFormatSelection::R32g32b32Float
@@ -130,6 +191,110 @@ fn transform(
     Ok(ColorState::Srgb)
 }
 
+/// Converts CMYK data to RGB via a CMYK profile
+///
+/// Unlike the naive conversion in
+/// [`change_memory_format`](glycin_utils::editing::change_memory_format),
+/// this takes the inks and paper described by the profile into account.
+fn transform_cmyk(
+    src_profile: &moxcms::ColorProfile,
+    frame: &mut glycin_utils::Frame<FungibleMemory>,
+    multiple: usize,
+) -> std::result::Result<ColorState, Error> {
+    tracing::debug!("Converting CMYK to sRGB via ICC profile");
+
+    let src_format = frame.memory_format;
+    let target_format = src_format.without_cmyk();
+    let target_profile = moxcms::ColorProfile::new_srgb();
+    let options = moxcms::TransformOptions::default();
+
+    let src_stride = frame.stride as usize;
+    let src_row_length =
+        (Checked::new(frame.width as usize) * src_format.n_bytes().usize()).check()?;
+    let new_stride = (Checked::new(frame.width) * target_format.n_bytes().u32()).check()?;
+    let new_total_size: usize =
+        (Checked::new(frame.height as usize) * new_stride as usize).check()?;
+
+    let mut new_data = vec![0; new_total_size];
+    let rows = Rows {
+        src: &frame.texture,
+        src_stride,
+        src_row_length,
+        target: &mut new_data,
+        target_stride: new_stride as usize,
+        chunk_rows: (frame.height as usize).div_ceil(multiple).max(1),
+    };
+
+    // moxcms uses the RGBA layout for all data with four channels
+    match src_format.channel_type() {
+        ChannelType::U8 => rows.transform(&*src_profile.create_transform_8bit(
+            moxcms::Layout::Rgba,
+            &target_profile,
+            moxcms::Layout::Rgb,
+            options,
+        )?)?,
+        ChannelType::U16 => rows.transform(&*src_profile.create_transform_16bit(
+            moxcms::Layout::Rgba,
+            &target_profile,
+            moxcms::Layout::Rgb,
+            options,
+        )?)?,
+        ChannelType::F16 | ChannelType::F32 => unreachable!(),
+    }
+
+    frame.stride = new_stride;
+    frame.memory_format = target_format;
+    frame.texture = FungibleMemory::from_vec(new_data);
+
+    Ok(ColorState::Srgb)
+}
+
+/// Rows of a transformation that doesn't work in place
+struct Rows<'a> {
+    src: &'a [u8],
+    src_stride: usize,
+    src_row_length: usize,
+    target: &'a mut [u8],
+    target_stride: usize,
+    /// Number of rows handled by each thread
+    chunk_rows: usize,
+}
+
+impl Rows<'_> {
+    fn transform<T: bytemuck::Pod>(
+        self,
+        executor: &(dyn moxcms::TransformExecutor<T> + Send + Sync),
+    ) -> std::result::Result<(), Error> {
+        std::thread::scope(|s| {
+            let threads = self
+                .src
+                .chunks(self.chunk_rows * self.src_stride)
+                .zip(self.target.chunks_mut(self.chunk_rows * self.target_stride))
+                .map(|(src, target)| {
+                    s.spawn(move || {
+                        for (src_row, target_row) in src
+                            .chunks(self.src_stride)
+                            .zip(target.chunks_mut(self.target_stride))
+                        {
+                            executor.transform(
+                                bytemuck::try_cast_slice(&src_row[..self.src_row_length])?,
+                                bytemuck::try_cast_slice_mut(target_row)?,
+                            )?;
+                        }
+                        Ok::<(), Error>(())
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            for thread in threads {
+                thread.join().map_err(|err| ErrorKind::panic(err).err())??;
+            }
+
+            Ok(())
+        })
+    }
+}
+
 const fn pixel_layout(format: MemoryFormat) -> moxcms::Layout {
     match format {
         MemoryFormat::R8g8b8 | MemoryFormat::R16g16b16 | MemoryFormat::R32g32b32Float => {
@@ -143,3 +308,82 @@ const fn pixel_layout(format: MemoryFormat) -> moxcms::Layout {
         _ => unreachable!(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal RGB profile with an identity `A2B0` lut16 tag
+    fn lut_profile() -> Vec<u8> {
+        let ramp = [0, u16::MAX];
+
+        let mut lut = b"mft2\0\0\0\0".to_vec();
+        // Input channels, output channels, grid points, padding
+        lut.extend_from_slice(&[3, 3, 2, 0]);
+        for i in 0..9 {
+            let value: i32 = if i % 4 == 0 { 0x10000 } else { 0 };
+            lut.extend_from_slice(&value.to_be_bytes());
+        }
+        // Number of input and output table entries
+        lut.extend_from_slice(&2_u16.to_be_bytes());
+        lut.extend_from_slice(&2_u16.to_be_bytes());
+        let input_tables = [ramp; 3].into_iter().flatten();
+        let clut = ramp
+            .into_iter()
+            .flat_map(|r| ramp.into_iter().flat_map(move |g| ramp.map(|b| [r, g, b])))
+            .flatten();
+        let output_tables = [ramp; 3].into_iter().flatten();
+        for value in input_tables.chain(clut).chain(output_tables) {
+            lut.extend_from_slice(&value.to_be_bytes());
+        }
+
+        let tag_offset = 128 + 4 + 12;
+        let size = tag_offset + lut.len();
+
+        let mut profile = Vec::new();
+        profile.extend_from_slice(&(size as u32).to_be_bytes());
+        profile.extend_from_slice(&[0; 4]);
+        profile.extend_from_slice(&0x04300000_u32.to_be_bytes());
+        profile.extend_from_slice(b"mntrRGB XYZ ");
+        profile.extend_from_slice(&[0; 12]);
+        profile.extend_from_slice(b"acsp");
+        profile.resize(68, 0);
+        // D50 illuminant
+        for value in [0xF6D6_i32, 0x10000, 0xD32D] {
+            profile.extend_from_slice(&value.to_be_bytes());
+        }
+        profile.resize(128, 0);
+
+        profile.extend_from_slice(&1_u32.to_be_bytes());
+        profile.extend_from_slice(b"A2B0");
+        profile.extend_from_slice(&(tag_offset as u32).to_be_bytes());
+        profile.extend_from_slice(&(lut.len() as u32).to_be_bytes());
+        profile.extend_from_slice(&lut);
+
+        profile
+    }
+
+    #[test]
+    fn profile_class() {
+        let srgb = moxcms::ColorProfile::new_srgb().encode().unwrap();
+        assert_eq!(
+            IccProfileClass::from_profile(&srgb).unwrap(),
+            IccProfileClass::MatrixShaper
+        );
+
+        let gray = moxcms::ColorProfile::new_gray_with_gamma(2.2)
+            .encode()
+            .unwrap();
+        assert_eq!(
+            IccProfileClass::from_profile(&gray).unwrap(),
+            IccProfileClass::Gray
+        );
+
+        assert_eq!(
+            IccProfileClass::from_profile(&lut_profile()).unwrap(),
+            IccProfileClass::Lut
+        );
+
+        assert!(IccProfileClass::from_profile(b"not a profile").is_err());
+    }
+}
@@ -14,7 +14,7 @@
 //! let image = Loader::new(file).load().await?;
 //!
 //! let height = image.details().height();
-//! let texture = image.next_frame().await?.texture();
+//! let texture = image.next_frame().await?.texture()?;
 //! # Ok::<(), ErrorCtx>(()) };
 //! ```
 //!
@@ -87,25 +87,33 @@ compile_error!(
 );
 
 mod api;
+mod blurhash;
 pub mod config;
 #[cfg(feature = "external")]
 mod dbus;
 #[cfg(not(feature = "external"))]
 mod dbus_shim;
 mod error;
+mod exif;
 #[cfg(feature = "external")]
 mod fontconfig;
 mod icc;
+mod luma;
 mod main_context;
 mod orientation;
+mod perceptual_hash;
+mod placeholder;
+mod planar;
 #[cfg(feature = "external")]
 mod pool;
 #[cfg(not(feature = "external"))]
 mod pool_shim;
 #[cfg(feature = "external")]
 mod sandbox;
+mod sniffer;
 mod source;
 mod util;
+mod xmp;
 
 #[cfg(feature = "gobject")]
 pub mod gobject;
@@ -118,14 +126,19 @@ pub const COMPAT_VERSION: u8 = 2;
 pub use api::*;
 #[cfg(not(feature = "external"))]
 use dbus_shim as dbus;
-pub use error::{Error, ErrorContext, ErrorKind};
+pub use error::{Error, ErrorContext, ErrorKind, Stage};
+pub use exif::CameraSettings;
 pub use glycin_common::{
-    ColorProfilePreference, MemoryFormat, MemoryFormatSelection, Operation, OperationId, Operations,
+    BinaryData, BlendOp, ColorProfilePreference, DisposalMethod, GpuFormatHint, MemoryFormat,
+    MemoryFormatSelection, Operation, OperationId, Operations,
 };
 pub use gufo_common::cicp::Cicp;
+pub use icc::IccProfileClass;
 pub use main_context::MainContextSelector;
+pub use planar::Plane;
 pub use pool::{Pool, PoolConfig};
 #[cfg(not(feature = "external"))]
 use pool_shim as pool;
+pub use sniffer::{SnifferRegistration, register_sniffer};
 #[cfg(feature = "gdk4")]
 pub use util::gdk_memory_format;
@@ -0,0 +1,133 @@
+//! Downscaling of textures
+
+use glycin_common::{MemoryFormat, MemoryFormatInfo};
+use glycin_utils::safe_math::*;
+
+use crate::{Error, ErrorKind};
+
+/// Texture that is borrowed from a frame
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TextureRef<'a> {
+    pub width: u32,
+    pub height: u32,
+    pub stride: u32,
+    pub memory_format: MemoryFormat,
+    pub buf: &'a [u8],
+}
+
+/// Rec. 601 weights of red, green, and blue
+pub(crate) const REC_601: [f32; 3] = [0.299, 0.587, 0.114];
+/// Rec. 709 weights of red, green, and blue
+pub(crate) const REC_709: [f32; 3] = [0.2126, 0.7152, 0.0722];
+
+/// Reduces the texture to `target_width` × `target_height` luma values
+///
+/// The luma is computed with the `weights` for red, green, and blue. Each
+/// value is the average of the pixels in the corresponding area. The values
+/// are row by row and range from `0.0` to `1.0`.
+pub(crate) fn downscale(
+    texture: TextureRef,
+    target_width: usize,
+    target_height: usize,
+    weights: [f32; 3],
+) -> Result<Vec<f32>, Error> {
+    let values = average_cells(texture, target_width, target_height, |pixel| {
+        [luma(pixel, weights)]
+    })?;
+
+    Ok(values.into_iter().map(|[x]| x.clamp(0., 1.)).collect())
+}
+
+/// Reduces the texture to `target_width` × `target_height` cells
+///
+/// Each pixel is mapped to `N` values via `f`, which are then averaged over
+/// the pixels in the corresponding area. The cells are row by row.
+pub(crate) fn average_cells<const N: usize>(
+    texture: TextureRef,
+    target_width: usize,
+    target_height: usize,
+    f: impl Fn([f32; 4]) -> [f32; N],
+) -> Result<Vec<[f32; N]>, Error> {
+    let width = texture.width.try_usize()?;
+    let height = texture.height.try_usize()?;
+    let stride = texture.stride.try_usize()?;
+    let pixel_size = texture.memory_format.n_bytes().usize();
+
+    let mut values = Vec::with_capacity(target_width.smul(target_height)?);
+
+    for cell_y in 0..target_height {
+        let (y0, y1) = cell_range(cell_y, target_height, height);
+
+        for cell_x in 0..target_width {
+            let (x0, x1) = cell_range(cell_x, target_width, width);
+
+            let mut sum = [0.; N];
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    let pos = y.smul(stride)?.sadd(x.smul(pixel_size)?)?;
+                    let pixel = texture.buf.get(pos..pos.sadd(pixel_size)?).ok_or_else(|| {
+                        ErrorKind::TextureWrongSize {
+                            texture_size: texture.buf.len(),
+                            frame: format!("{width}x{height}, stride {stride}"),
+                        }
+                        .err()
+                    })?;
+                    let mapped = f(MemoryFormat::to_f32(texture.memory_format, pixel));
+                    for (sum, value) in sum.iter_mut().zip(mapped) {
+                        *sum += value;
+                    }
+                }
+            }
+
+            let n_pixels = ((x1 - x0) * (y1 - y0)) as f32;
+            values.push(sum.map(|x| x / n_pixels));
+        }
+    }
+
+    Ok(values)
+}
+
+/// Pixel range covered by a cell
+///
+/// Every cell covers at least one pixel, such that images smaller than the
+/// grid repeat pixels.
+fn cell_range(cell: usize, n_cells: usize, n_pixels: usize) -> (usize, usize) {
+    let start = (cell * n_pixels / n_cells).min(n_pixels.saturating_sub(1));
+    let end = ((cell + 1) * n_pixels / n_cells).max(start + 1);
+
+    (start, end)
+}
+
+/// Luma composited onto black
+fn luma([r, g, b, a]: [f32; 4], [wr, wg, wb]: [f32; 3]) -> f32 {
+    (wr * r + wg * g + wb * b) * a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn average_and_weights() {
+        let texture = TextureRef {
+            width: 4,
+            height: 2,
+            stride: 4 * 3,
+            memory_format: MemoryFormat::R8g8b8,
+            buf: &[
+                255, 0, 0, 255, 0, 0, 0, 255, 0, 0, 255, 0, //
+                255, 0, 0, 255, 0, 0, 0, 0, 255, 0, 0, 255,
+            ],
+        };
+
+        let values = downscale(texture, 2, 1, REC_709).unwrap();
+        assert!((values[0] - 0.2126).abs() < 1e-6);
+        assert!((values[1] - (0.7152 + 0.0722) / 2.).abs() < 1e-6);
+
+        let values = downscale(texture, 2, 1, REC_601).unwrap();
+        assert!((values[0] - 0.299).abs() < 1e-6);
+
+        // Upscaling repeats pixels
+        assert_eq!(downscale(texture, 8, 4, REC_709).unwrap().len(), 32);
+    }
+}
@@ -1,15 +1,20 @@
-use glycin_utils::{Frame, FungibleMemory};
+use glycin_utils::{Frame, FungibleMemory, ImageDetails};
+use gufo_common::orientation::Orientation;
 
 use crate::Image;
 
+/// Returns the frame and whether its orientation was changed
 pub fn apply_exif_orientation(
     frame: Frame<FungibleMemory>,
-    image: &Image,
-) -> Frame<FungibleMemory> {
-    if image.details().transformation_ignore_exif() {
-        frame
+    details: &ImageDetails<FungibleMemory>,
+) -> (Frame<FungibleMemory>, bool) {
+    if details.transformation_ignore_exif {
+        (frame, false)
     } else {
-        let orientation = image.transformation_orientation();
-        glycin_utils::editing::change_orientation(frame, orientation)
+        let orientation = Image::transformation_orientation_internal(details);
+        (
+            glycin_utils::editing::change_orientation(frame, orientation),
+            orientation != Orientation::Id,
+        )
     }
 }
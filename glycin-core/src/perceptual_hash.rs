@@ -0,0 +1,102 @@
+//! Difference hash (dHash) of textures
+
+use crate::Error;
+use crate::luma::{self, TextureRef};
+
+const WIDTH: usize = 9;
+const HEIGHT: usize = 8;
+
+/// Size of the frame that is requested for hashing
+///
+/// Several pixels per cell avoid that single pixels dominate a cell.
+pub(crate) const FRAME_SIZE: u32 = 64;
+
+/// Difference hash of a texture
+///
+/// The texture is reduced to 9×8 grayscale cells by averaging the Rec. 601
+/// luma. Each bit is
+/// set if a cell is brighter than its right neighbor. Bits are ordered row
+/// by row, starting with the most significant bit.
+pub(crate) fn dhash(texture: TextureRef) -> Result<u64, Error> {
+    // Changing the weights would change existing hashes
+    let cells = luma::downscale(texture, WIDTH, HEIGHT, luma::REC_601)?;
+
+    let mut hash = 0;
+    for row in cells.chunks_exact(WIDTH) {
+        for pair in row.windows(2) {
+            hash = (hash << 1) | u64::from(pair[0] > pair[1]);
+        }
+    }
+
+    Ok(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use glycin_common::MemoryFormat;
+
+    use super::*;
+
+    fn dhash(
+        width: u32,
+        height: u32,
+        stride: u32,
+        memory_format: MemoryFormat,
+        buf: &[u8],
+    ) -> Result<u64, Error> {
+        super::dhash(TextureRef {
+            width,
+            height,
+            stride,
+            memory_format,
+            buf,
+        })
+    }
+
+    /// Horizontal gradient, brightening towards the right
+    fn gradient(width: u32, height: u32) -> Vec<u8> {
+        (0..height)
+            .flat_map(|_| (0..width).map(move |x| (x * 255 / (width - 1)) as u8))
+            .collect()
+    }
+
+    #[test]
+    fn gradient_direction() {
+        let texture = gradient(90, 40);
+        assert_eq!(dhash(90, 40, 90, MemoryFormat::G8, &texture).unwrap(), 0);
+
+        let mirrored = texture
+            .chunks_exact(90)
+            .flat_map(|row| row.iter().rev().copied())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            dhash(90, 40, 90, MemoryFormat::G8, &mirrored).unwrap(),
+            u64::MAX
+        );
+    }
+
+    #[test]
+    fn scale_and_format_independent() {
+        let texture = gradient(90, 40);
+        let small = gradient(9, 8);
+        assert_eq!(
+            dhash(90, 40, 90, MemoryFormat::G8, &texture).unwrap(),
+            dhash(9, 8, 9, MemoryFormat::G8, &small).unwrap()
+        );
+
+        let rgba = texture
+            .iter()
+            .flat_map(|x| [*x, *x, *x, 255])
+            .collect::<Vec<_>>();
+        assert_eq!(
+            dhash(90, 40, 90 * 4, MemoryFormat::R8g8b8a8, &rgba).unwrap(),
+            dhash(90, 40, 90, MemoryFormat::G8, &texture).unwrap()
+        );
+    }
+
+    #[test]
+    fn tiny_and_truncated() {
+        assert_eq!(dhash(1, 1, 1, MemoryFormat::G8, &[100]).unwrap(), 0);
+        assert!(dhash(90, 40, 90, MemoryFormat::G8, &[0; 100]).is_err());
+    }
+}
@@ -0,0 +1,131 @@
+//! Best effort extraction of embedded previews from unsupported files
+
+const JPEG_SOI: [u8; 3] = [0xFF, 0xD8, 0xFF];
+
+/// Returns the largest JPEG embedded in `data`
+///
+/// Many formats like RAW images or PDFs contain complete JPEG files as
+/// previews. JPEGs starting at the very beginning of `data` are ignored since
+/// those would have been detected as JPEG in the first place.
+pub(crate) fn embedded_jpeg(data: &[u8]) -> Option<&[u8]> {
+    let mut largest: Option<&[u8]> = None;
+    let mut pos = 1;
+
+    while let Some(offset) = data
+        .get(pos..)
+        .and_then(|x| x.windows(JPEG_SOI.len()).position(|x| x == JPEG_SOI))
+    {
+        let start = pos + offset;
+
+        if let Some(len) = jpeg_len(&data[start..]) {
+            let jpeg = &data[start..start + len];
+            if largest.is_none_or(|x| x.len() < jpeg.len()) {
+                largest = Some(jpeg);
+            }
+            // Skip embedded JPEGs, like Exif thumbnails, inside this JPEG
+            pos = start + len;
+        } else {
+            pos = start + 1;
+        }
+    }
+
+    largest
+}
+
+/// Length of the JPEG at the beginning of `data`
+///
+/// Walks the JPEG segments until the end of image marker is found. Returns
+/// `None` if the data are not a complete JPEG.
+fn jpeg_len(data: &[u8]) -> Option<usize> {
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+
+    let mut pos = 2;
+
+    loop {
+        if *data.get(pos)? != 0xFF {
+            return None;
+        }
+
+        // Markers can be preceded by fill bytes
+        while *data.get(pos)? == 0xFF {
+            pos += 1;
+        }
+
+        let marker = *data.get(pos)?;
+        pos += 1;
+
+        match marker {
+            // End of image
+            0xD9 => return Some(pos),
+            // Markers without length
+            0x01 | 0xD0..=0xD7 => continue,
+            _ => {}
+        }
+
+        let len = u16::from_be_bytes([*data.get(pos)?, *data.get(pos + 1)?]) as usize;
+        if len < 2 {
+            return None;
+        }
+        pos = pos.checked_add(len)?;
+
+        // Start of scan is followed by entropy coded data
+        if marker == 0xDA {
+            loop {
+                pos += data.get(pos..)?.iter().position(|x| *x == 0xFF)?;
+                match *data.get(pos + 1)? {
+                    // Stuffed zero byte and restart markers are part of the data
+                    0x00 | 0xD0..=0xD7 => pos += 2,
+                    0xFF => pos += 1,
+                    _ => break,
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JPEG: &[u8] = &[
+        0xFF, 0xD8, // SOI
+        0xFF, 0xE0, 0x00, 0x04, 0x4A, 0x46, // APP0
+        0xFF, 0xDA, 0x00, 0x02, // SOS
+        0x12, 0xFF, 0x00, 0x34, 0xFF, 0xD0, 0x56, // Entropy coded data
+        0xFF, 0xD9, // EOI
+    ];
+
+    #[test]
+    fn finds_embedded_jpeg() {
+        let mut data = b"RAW-HEADER".to_vec();
+        data.extend_from_slice(JPEG);
+        data.extend_from_slice(b"trailing data");
+
+        assert_eq!(embedded_jpeg(&data), Some(JPEG));
+    }
+
+    #[test]
+    fn prefers_largest_jpeg() {
+        let mut large = JPEG[..12].to_vec();
+        large.extend_from_slice(&[0x11; 64]);
+        large.extend_from_slice(&JPEG[12..]);
+
+        let mut data = b"head".to_vec();
+        data.extend_from_slice(JPEG);
+        data.extend_from_slice(b"between");
+        data.extend_from_slice(&large);
+
+        assert_eq!(embedded_jpeg(&data), Some(large.as_slice()));
+    }
+
+    #[test]
+    fn ignores_truncated_jpeg() {
+        let mut data = b"head".to_vec();
+        data.extend_from_slice(&JPEG[..JPEG.len() - 2]);
+
+        assert_eq!(embedded_jpeg(&data), None);
+        assert_eq!(embedded_jpeg(JPEG), None);
+    }
+}
@@ -0,0 +1,126 @@
+//! Conversion of interleaved textures into planar layout
+
+use glycin_common::MemoryFormatInfo;
+use glycin_utils::safe_math::*;
+
+use crate::luma::TextureRef;
+use crate::{Error, ErrorKind};
+
+/// Plane with the values of a single channel
+///
+/// See [`Loader::planar`](crate::Loader::planar).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Plane {
+    /// Position of the first value of the plane in the buffer in bytes
+    pub offset: usize,
+    /// Distance between the starts of two rows in bytes
+    pub stride: u32,
+}
+
+/// Repacks the texture into one plane per channel
+///
+/// The planes follow each other without gaps, in the order of the channels
+/// in the memory format. Rows within a plane have no padding.
+pub(crate) fn to_planar(texture: TextureRef) -> Result<(Vec<u8>, Vec<Plane>), Error> {
+    let width = texture.width.try_usize()?;
+    let height = texture.height.try_usize()?;
+    let stride = texture.stride.try_usize()?;
+    let pixel_size = texture.memory_format.n_bytes().usize();
+    let n_channels = usize::from(texture.memory_format.n_channels());
+    let channel_size = usize::from(texture.memory_format.channel_type().size());
+
+    let row_len = width.smul(pixel_size)?;
+    let plane_stride = width.smul(channel_size)?;
+    let plane_size = plane_stride.smul(height)?;
+
+    let mut buf = Vec::with_capacity(plane_size.smul(n_channels)?);
+    let mut planes = Vec::with_capacity(n_channels);
+
+    for channel in 0..n_channels {
+        planes.push(Plane {
+            offset: buf.len(),
+            stride: plane_stride.try_u32()?,
+        });
+
+        let channel_start = channel.smul(channel_size)?;
+        let channel_range = channel_start..channel_start.sadd(channel_size)?;
+
+        for y in 0..height {
+            let row_start = y.smul(stride)?;
+            let row = texture
+                .buf
+                .get(row_start..row_start.sadd(row_len)?)
+                .ok_or_else(|| {
+                    ErrorKind::TextureWrongSize {
+                        texture_size: texture.buf.len(),
+                        frame: format!("{width}x{height}, stride {stride}"),
+                    }
+                    .err()
+                })?;
+
+            for pixel in row.chunks_exact(pixel_size) {
+                buf.extend_from_slice(&pixel[channel_range.clone()]);
+            }
+        }
+    }
+
+    Ok((buf, planes))
+}
+
+#[cfg(test)]
+mod tests {
+    use glycin_common::MemoryFormat;
+
+    use super::*;
+
+    #[test]
+    fn rgb() {
+        // 2x2 with padding at the end of each row
+        let buf = [1, 2, 3, 4, 5, 6, 0, 7, 8, 9, 10, 11, 12, 0];
+        let (planar, planes) = to_planar(TextureRef {
+            width: 2,
+            height: 2,
+            stride: 7,
+            memory_format: MemoryFormat::R8g8b8,
+            buf: &buf,
+        })
+        .unwrap();
+
+        assert_eq!(planar, [1, 4, 7, 10, 2, 5, 8, 11, 3, 6, 9, 12]);
+        assert_eq!(
+            planes,
+            [
+                Plane {
+                    offset: 0,
+                    stride: 2
+                },
+                Plane {
+                    offset: 4,
+                    stride: 2
+                },
+                Plane {
+                    offset: 8,
+                    stride: 2
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn multi_byte_channels() {
+        let buf = [1, 2, 3, 4, 5, 6, 7, 8];
+        let (planar, planes) = to_planar(TextureRef {
+            width: 2,
+            height: 1,
+            stride: 8,
+            memory_format: MemoryFormat::G16a16,
+            buf: &buf,
+        })
+        .unwrap();
+
+        assert_eq!(planar, [1, 2, 5, 6, 3, 4, 7, 8]);
+        assert_eq!(planes[1].offset, 4);
+        assert_eq!(planes[1].stride, 4);
+    }
+}
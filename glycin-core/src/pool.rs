@@ -13,7 +13,7 @@ use gio::prelude::*;
 use crate::DBusProxy;
 use crate::config::{ConfigEntry, ConfigEntryHash};
 use crate::util::{AsyncMutex, TimerHandle, spawn_timeout};
-use crate::{Error, ErrorKind, SandboxMechanism, config, dbus};
+use crate::{Error, ErrorKind, ProcessPriority, SandboxMechanism, SandboxWrapper, config, dbus};
 
 #[derive(Debug)]
 pub struct PooledProcess<P: DBusProxy> {
@@ -56,6 +56,10 @@ impl<P: DBusProxy> PooledProcess<P> {
         self.process.clone()
     }
 
+    pub fn is_disconnected(&self) -> bool {
+        self.process.process_disconnected.load(Ordering::Relaxed)
+    }
+
     pub fn n_users(&self) -> usize {
         self.useage_tracker.lock().unwrap().strong_count()
     }
@@ -76,6 +80,7 @@ pub struct Pool {
 pub struct PoolConfig {
     loader_retention_time: Duration,
     max_parallel_operations: usize,
+    token_authentication: bool,
 }
 
 impl Default for PoolConfig {
@@ -83,6 +88,7 @@ impl Default for PoolConfig {
         Self {
             loader_retention_time: Duration::from_secs(30),
             max_parallel_operations: usize::MAX,
+            token_authentication: false,
         }
     }
 }
@@ -105,6 +111,26 @@ impl PoolConfig {
         self.loader_retention_time = retention_time;
         self
     }
+
+    /// Require loaders and editors to authenticate with a token
+    ///
+    /// The D-Bus connection to loaders uses anonymous authentication over a
+    /// socket pair. If another process manages to get hold of the socket, for
+    /// example a compromised process that shares the same sandbox, it could
+    /// impersonate the loader and deliver arbitrary data.
+    ///
+    /// With this option enabled, a random token is generated for every spawned
+    /// process and passed to it via its stdin. The process has to send the
+    /// token before the D-Bus connection is established. Otherwise, the
+    /// connection is rejected.
+    ///
+    /// Loaders built against older versions of glycin do not support this
+    /// and fail with [`ErrorKind::AuthenticationFailed`](crate::ErrorKind::AuthenticationFailed).
+    /// Therefore, this option is disabled by default.
+    pub fn token_authentication(mut self, token_authentication: bool) -> Self {
+        self.token_authentication = token_authentication;
+        self
+    }
 }
 
 impl Pool {
@@ -123,7 +149,11 @@ impl Pool {
         self: Arc<Self>,
         loader_config: config::ImageLoaderConfig,
         sandbox_mechanism: SandboxMechanism,
+        sandbox_wrapper: Option<SandboxWrapper>,
         base_dir: Option<PathBuf>,
+        sidecars: Vec<PathBuf>,
+        tmpdir: Option<PathBuf>,
+        process_priority: ProcessPriority,
         cancellable: &gio::Cancellable,
     ) -> Result<
         (
@@ -140,7 +170,11 @@ impl Pool {
                 pooled_loaders,
                 ConfigEntry::Loader(loader_config.clone()),
                 sandbox_mechanism,
+                sandbox_wrapper,
                 base_dir,
+                sidecars,
+                tmpdir,
+                process_priority,
                 cancellable,
             )
             .await?;
@@ -153,7 +187,11 @@ impl Pool {
         self: Arc<Self>,
         editor_config: config::ImageEditorConfig,
         sandbox_mechanism: SandboxMechanism,
+        sandbox_wrapper: Option<SandboxWrapper>,
         base_dir: Option<PathBuf>,
+        sidecars: Vec<PathBuf>,
+        tmpdir: Option<PathBuf>,
+        process_priority: ProcessPriority,
         cancellable: &gio::Cancellable,
     ) -> Result<
         (
@@ -170,7 +208,11 @@ impl Pool {
                 pooled_editors,
                 ConfigEntry::Editor(editor_config.clone()),
                 sandbox_mechanism,
+                sandbox_wrapper,
                 base_dir,
+                sidecars,
+                tmpdir,
+                process_priority,
                 cancellable,
             )
             .await?;
@@ -184,10 +226,21 @@ impl Pool {
         pooled_processes: &AsyncMutex<BTreeMap<ConfigEntryHash, Vec<Arc<PooledProcess<P>>>>>,
         config: config::ConfigEntry,
         sandbox_mechanism: SandboxMechanism,
+        sandbox_wrapper: Option<SandboxWrapper>,
         base_dir: Option<PathBuf>,
+        sidecars: Vec<PathBuf>,
+        tmpdir: Option<PathBuf>,
+        process_priority: ProcessPriority,
         cancellable: &gio::Cancellable,
     ) -> Result<(Arc<PooledProcess<P>>, Arc<UsageTracker>), Error> {
-        let config_hash = config.hash_value(base_dir.clone(), sandbox_mechanism);
+        let config_hash = config.hash_value(
+            base_dir.clone(),
+            sidecars.clone(),
+            tmpdir.clone(),
+            sandbox_mechanism,
+            sandbox_wrapper.clone(),
+            process_priority,
+        );
         let mut pooled_processes = pooled_processes.lock().await;
         let pooled_processes = pooled_processes.entry(config_hash).or_default();
 
@@ -224,7 +277,12 @@ impl Pool {
             dbus::RemoteProcess::new(
                 config.clone(),
                 sandbox_mechanism,
+                sandbox_wrapper,
                 base_dir,
+                sidecars,
+                tmpdir,
+                process_priority,
+                self.config.token_authentication,
                 &process_cancellable,
             )
             .await?,
@@ -248,6 +306,19 @@ impl Pool {
         Ok((pp, usage_tracker))
     }
 
+    /// Kills all processes of the pool without a graceful shutdown
+    pub(crate) async fn kill_all(self: Arc<Self>) {
+        tracing::debug!("Killing all processes in pool");
+
+        for process in self.loaders.lock().await.values().flatten() {
+            process.process.kill();
+        }
+
+        for process in self.editors.lock().await.values().flatten() {
+            process.process.kill();
+        }
+    }
+
     pub(crate) async fn clean_loaders(self: Arc<Self>) {
         tracing::debug!("Cleaning up loaders");
         let mut loader_map = self.loaders.lock().await;
@@ -1,6 +1,6 @@
 // Copyright (c) 2024 GNOME Foundation Inc.
 
-use std::ffi::{c_int, c_void};
+use std::ffi::{OsString, c_int, c_void};
 use std::fs::{DirEntry, File, canonicalize};
 use std::io::{self, BufRead, BufReader};
 use std::os::fd::{AsRawFd, BorrowedFd, OwnedFd};
@@ -21,7 +21,9 @@ use nix::unistd;
 
 use crate::config::{ConfigEntry, ImageLoaderConfig, Processor};
 use crate::util::{self, AsyncMutex, new_async_mutex, spawn_blocking};
-use crate::{Error, ErrorKind, SandboxMechanism};
+use crate::{
+    Error, ErrorKind, ProcessPriority, SandboxMechanism, SandboxWrapper, SandboxWrapperArgs,
+};
 
 type SystemSetupStore = Arc<Result<SystemSetup, Arc<io::Error>>>;
 
@@ -144,10 +146,14 @@ const INHERITED_ENVIRONMENT_VARIABLES: &[&str] = &["RUST_BACKTRACE", "RUST_LOG",
 
 pub struct Sandbox {
     sandbox_mechanism: SandboxMechanism,
+    sandbox_wrapper: Option<SandboxWrapper>,
     config_entry: ConfigEntry,
     exec: PathBuf,
     dbus_socket: UnixStream,
     ro_bind_extra: Vec<PathBuf>,
+    tmpdir: Option<PathBuf>,
+    process_priority: ProcessPriority,
+    token_authentication: bool,
 }
 
 static_assertions::assert_impl_all!(Sandbox: Send, Sync);
@@ -169,6 +175,7 @@ impl Sandbox {
     ) -> Result<Self, Error> {
         Ok(Self {
             sandbox_mechanism,
+            sandbox_wrapper: None,
             exec: config_entry
                 .exec()
                 .map(|x| x.to_path_buf())
@@ -176,6 +183,9 @@ impl Sandbox {
             config_entry,
             dbus_socket,
             ro_bind_extra: Vec::new(),
+            tmpdir: None,
+            process_priority: ProcessPriority::default(),
+            token_authentication: false,
         })
     }
 
@@ -187,13 +197,38 @@ impl Sandbox {
         self.ro_bind_extra.push(path);
     }
 
+    /// Start the process via the wrapper instead of the sandbox mechanism
+    pub fn set_wrapper(&mut self, sandbox_wrapper: SandboxWrapper) {
+        self.sandbox_wrapper = Some(sandbox_wrapper);
+    }
+
+    /// Mount the directory writable and use it as `TMPDIR`
+    pub fn set_tmpdir(&mut self, path: PathBuf) {
+        self.tmpdir = Some(path);
+    }
+
+    /// Lower the scheduling priority of the process
+    pub fn set_process_priority(&mut self, process_priority: ProcessPriority) {
+        self.process_priority = process_priority;
+    }
+
+    /// Let the loader read a token from stdin that it has to send first
+    pub fn enable_token_authentication(&mut self) {
+        self.token_authentication = true;
+    }
+
     pub async fn spawn(self) -> Result<SpawnedSandbox, Error> {
         let dbus_fd = self.dbus_socket.as_raw_fd();
 
         let mut shared_fds = Vec::new();
 
-        let (mut command, seccomp_fd) = match self.sandbox_mechanism {
-            SandboxMechanism::Bwrap => {
+        let (mut command, seccomp_fd) = match (&self.sandbox_wrapper, self.sandbox_mechanism) {
+            (Some(sandbox_wrapper), _) => {
+                let command = self.custom_command(sandbox_wrapper);
+
+                (command, None)
+            }
+            (None, SandboxMechanism::Bwrap) => {
                 let seccomp_memfd = Self::seccomp_export_bpf(&self.seccomp_filter()?)?;
                 let command = self.bwrap_command(&seccomp_memfd).await?;
 
@@ -201,21 +236,30 @@ impl Sandbox {
 
                 (command, Some(seccomp_memfd))
             }
-            SandboxMechanism::FlatpakSpawn => {
+            (None, SandboxMechanism::FlatpakSpawn) => {
                 let command = self.flatpak_spawn_command();
 
                 (command, None)
             }
-            SandboxMechanism::NotSandboxed => {
+            (None, SandboxMechanism::NotSandboxed) => {
                 let command = self.no_sandbox_command();
 
                 (command, None)
             }
+            (None, SandboxMechanism::Custom) => {
+                return Err(Error::other(
+                    "Custom sandbox mechanism without sandbox wrapper",
+                ));
+            }
         };
 
         command.arg("--dbus-fd");
         command.arg(dbus_fd.to_string());
 
+        if self.token_authentication {
+            command.arg("--dbus-token-stdin");
+        }
+
         command.stdin(Stdio::piped());
         command.stderr(Stdio::piped());
         command.stdout(Stdio::piped());
@@ -251,6 +295,20 @@ impl Sandbox {
             });
         }
 
+        let process_priority = self.process_priority;
+        if process_priority != ProcessPriority::default() {
+            if self.sandbox_mechanism == SandboxMechanism::FlatpakSpawn {
+                tracing::warn!("Process priority can't be applied with flatpak-spawn");
+            } else {
+                unsafe {
+                    command.pre_exec(move || {
+                        Self::set_process_priority_current(process_priority);
+                        Ok(())
+                    });
+                }
+            }
+        }
+
         Ok(SpawnedSandbox {
             command,
             _seccomp_fd: seccomp_fd,
@@ -401,6 +459,17 @@ impl Sandbox {
             mount(&mut command, "--ro-bind", dir);
         }
 
+        // Writable directory for temporary files
+        if let Some(tmpdir) = &self.tmpdir {
+            command.arg("--bind");
+            command.arg(tmpdir);
+            command.arg(tmpdir);
+
+            command.arg("--setenv");
+            command.arg("TMPDIR");
+            command.arg(tmpdir);
+        }
+
         // Make loader binary available if not in /usr. This is useful for testing and
         // adding loaders in user (/home) configurations.
         if !self.exec().starts_with("/usr") {
@@ -497,6 +566,12 @@ impl Sandbox {
             }
         }
 
+        // Writable directory for temporary files
+        if let Some(tmpdir) = &self.tmpdir {
+            command.arg(format!("--sandbox-expose-path-rw={}", tmpdir.display()));
+            command.arg(format!("--env=TMPDIR={}", tmpdir.display()));
+        }
+
         // Forward dbus connection
         command.arg(format!("--forward-fd={dbus_fd}"));
 
@@ -529,6 +604,10 @@ impl Sandbox {
             }
         }
 
+        if let Some(tmpdir) = &self.tmpdir {
+            command.env("TMPDIR", tmpdir);
+        }
+
         // Set sandbox memory limit
         unsafe {
             command.pre_exec(|| {
@@ -539,6 +618,37 @@ impl Sandbox {
         command
     }
 
+    fn custom_command(&self, sandbox_wrapper: &SandboxWrapper) -> Command {
+        let mut environment = INHERITED_ENVIRONMENT_VARIABLES
+            .iter()
+            .filter_map(|key| Some((OsString::from(key), std::env::var_os(key)?)))
+            .collect::<Vec<_>>();
+
+        if let Some(tmpdir) = &self.tmpdir {
+            environment.push((OsString::from("TMPDIR"), tmpdir.clone().into_os_string()));
+        }
+
+        let args = SandboxWrapperArgs {
+            exec: self.exec.clone(),
+            dbus_fd: self.dbus_socket.as_raw_fd(),
+            ro_binds: self.ro_bind_extra.clone(),
+            tmpdir: self.tmpdir.clone(),
+            environment,
+        };
+
+        tracing::debug!("Starting loader via custom sandbox: {args:?}");
+
+        let mut command = sandbox_wrapper.command(&args);
+
+        unsafe {
+            command.pre_exec(|| {
+                nix::sys::prctl::set_pdeathsig(nix::sys::signal::SIGKILL).map_err(Into::into)
+            });
+        }
+
+        command
+    }
+
     /// Memory limit in bytes that should be applied to sandboxes
     fn memory_limit() -> resource::rlim_t {
         // Lookup free memory
@@ -614,6 +724,41 @@ impl Sandbox {
         }
     }
 
+    /// Set scheduling priority for the current process
+    ///
+    /// Failures are only reported on stderr since the process can still work
+    /// with normal priority.
+    fn set_process_priority_current(process_priority: ProcessPriority) {
+        const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+        const IOPRIO_CLASS_IDLE: libc::c_int = 3;
+        const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+
+        if process_priority.nice() != 0
+            && unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, process_priority.nice()) } != 0
+        {
+            let msg = b"Error setpriority(PRIO_PROCESS)\n";
+            unsafe {
+                let _ = libc::write(libc::STDERR_FILENO, msg.as_ptr() as *const _, msg.len());
+            }
+        }
+
+        if process_priority.is_io_idle()
+            && unsafe {
+                libc::syscall(
+                    libc::SYS_ioprio_set,
+                    IOPRIO_WHO_PROCESS,
+                    0,
+                    IOPRIO_CLASS_IDLE << IOPRIO_CLASS_SHIFT,
+                )
+            } != 0
+        {
+            let msg = b"Error ioprio_set(IOPRIO_CLASS_IDLE)\n";
+            unsafe {
+                let _ = libc::write(libc::STDERR_FILENO, msg.as_ptr() as *const _, msg.len());
+            }
+        }
+    }
+
     fn seccomp_filter(&self) -> Result<ScmpFilterContext, SeccompError> {
         let mut filter = ScmpFilterContext::new(ScmpAction::Allow)?;
 
@@ -670,6 +815,8 @@ impl Sandbox {
             processor: Processor::Binary(PathBuf::from("/usr/bin/true")),
             expose_base_dir: false,
             fontconfig: false,
+            animation: false,
+            metadata: false,
             identifiers: Vec::new(),
         });
 
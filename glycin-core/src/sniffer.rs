@@ -0,0 +1,92 @@
+//! Detection of image formats via custom functions
+
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::MimeType;
+
+type Sniffer = Box<dyn Fn(&[u8]) -> Option<MimeType> + Send + Sync>;
+
+static SNIFFERS: RwLock<Vec<(u64, Sniffer)>> = RwLock::new(Vec::new());
+static NEXT_SNIFFER_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Handle to a sniffer registered via [`register_sniffer`]
+///
+/// Dropping the handle keeps the sniffer registered.
+#[derive(Debug)]
+pub struct SnifferRegistration {
+    id: u64,
+}
+
+impl SnifferRegistration {
+    /// Removes the sniffer from the registry
+    pub fn unregister(self) {
+        SNIFFERS
+            .write()
+            .unwrap_or_else(|err| err.into_inner())
+            .retain(|(id, _)| *id != self.id);
+    }
+}
+
+/// Register a custom format detection
+///
+/// The `sniffer` gets the first bytes of the image and returns the MIME type
+/// if it recognizes the format. Registered sniffers are consulted in the
+/// order of registration before the identifiers from the loader configs,
+/// the built-in signatures, and shared-mime-info. The first sniffer
+/// returning a MIME type wins.
+///
+/// The registry is global and sniffers can be registered from any thread.
+/// Sniffers can be called from multiple threads at the same time.
+///
+/// A loader for the returned MIME type still has to be configured. Config
+/// files are searched in `glycin-loaders/<compat-version>+/conf.d` inside
+/// the XDG data dirs or inside `$GLYCIN_DATA_DIR` if that is set. Additional
+/// directories can be added via
+/// [`register_config_search_dir`](crate::config::register_config_search_dir).
+///
+/// The sniffer stays registered until [`SnifferRegistration::unregister`] is
+/// called.
+pub fn register_sniffer(
+    sniffer: impl Fn(&[u8]) -> Option<MimeType> + Send + Sync + 'static,
+) -> SnifferRegistration {
+    let id = NEXT_SNIFFER_ID.fetch_add(1, Ordering::Relaxed);
+
+    SNIFFERS
+        .write()
+        .unwrap_or_else(|err| err.into_inner())
+        .push((id, Box::new(sniffer)));
+
+    SnifferRegistration { id }
+}
+
+/// Returns the MIME type detected by the first matching custom sniffer
+pub(crate) fn sniff(head: &[u8]) -> Option<MimeType> {
+    SNIFFERS
+        .read()
+        .unwrap_or_else(|err| err.into_inner())
+        .iter()
+        .find_map(|(_, sniffer)| sniffer(head))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn custom_sniffer() {
+        let registration = register_sniffer(|head| {
+            head.starts_with(b"in-house")
+                .then_some(MimeType::new_static("image/x-in-house"))
+        });
+
+        assert_eq!(
+            sniff(b"in-house-image-data"),
+            Some(MimeType::new_static("image/x-in-house"))
+        );
+        assert_eq!(sniff(b"other"), None);
+
+        registration.unregister();
+        assert_eq!(sniff(b"in-house-image-data"), None);
+    }
+}
@@ -1,89 +1,260 @@
 #[cfg(feature = "external")]
 use std::os::fd::OwnedFd;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 
-#[cfg(feature = "builtin")]
+#[cfg(any(feature = "builtin", feature = "external"))]
 use futures_util::SinkExt;
+#[cfg(feature = "external")]
+use futures_util::StreamExt;
 use gio::prelude::*;
 
 use crate::{Error, ErrorKind, Source};
 
-const BUF_SIZE: usize = u16::MAX as usize;
+/// Default size of chunks read from the source
+pub(crate) const BUF_SIZE: usize = u16::MAX as usize;
+/// Smallest allowed read buffer size
+///
+/// The first chunk is used for detecting the MIME type and has to contain
+/// enough data for the signatures.
+pub(crate) const MIN_BUF_SIZE: usize = 4 * 1024;
+/// Largest allowed read buffer size
+pub(crate) const MAX_BUF_SIZE: usize = 16 * 1024 * 1024;
+/// Largest allowed number of chunks that are read ahead
+pub(crate) const MAX_READ_AHEAD: usize = 256;
+/// Largest amount of data in bytes that is read ahead
+pub(crate) const MAX_READ_AHEAD_SIZE: usize = 64 * 1024 * 1024;
 
 #[derive(Debug)]
 pub struct SourceTransmission {
     file: Option<gio::File>,
-    input_stream: gio::InputStream,
+    input: Arc<SourceInput>,
     first_bytes: Vec<u8>,
+    buf_size: usize,
+    /// Number of chunks that are read before the loader consumes them
+    read_ahead: usize,
+    bytes_read: Arc<AtomicU64>,
+    /// State of the file when it was opened
+    file_state: Option<FileState>,
 }
 
-impl SourceTransmission {
-    pub async fn init(source: Source) -> Result<SourceTransmission, Error> {
-        tracing::trace!("Opening source");
+/// Data that the chunks are read from
+#[derive(Debug)]
+enum SourceInput {
+    Stream(gio::InputStream),
+    /// Buffer owned by the transmission
+    ///
+    /// The buffer is kept until the transmission is dropped, which is after
+    /// the loader has received all data.
+    Vec {
+        data: Vec<u8>,
+        pos: AtomicUsize,
+    },
+}
+
+impl SourceInput {
+    async fn read(&self, buf_size: usize) -> Result<Vec<u8>, Error> {
+        match self {
+            Self::Stream(input_stream) => {
+                let buf = vec![0; buf_size];
+
+                let (mut buf, n) = input_stream
+                    .read_future(buf, glib::Priority::DEFAULT)
+                    .await
+                    .map_err(|(_, err)| ErrorKind::ImageSource(err).err())?;
 
-        let input_stream = source.to_stream().await?;
-        let buf = vec![0; BUF_SIZE];
+                buf.truncate(n);
+                Ok(buf)
+            }
+            Self::Vec { data, pos } => {
+                let start = pos.load(Ordering::Relaxed);
+                let end = start.saturating_add(buf_size).min(data.len());
+                pos.store(end, Ordering::Relaxed);
+
+                Ok(data
+                    .get(start..end)
+                    .ok_or_else(|| ErrorKind::unreachable().err())?
+                    .to_vec())
+            }
+        }
+    }
 
-        tracing::trace!("Read first {BUF_SIZE} bytes");
+    /// Reads the next chunk and adds its length to `bytes_read`
+    ///
+    /// Returns `None` once the end of the source has been reached.
+    async fn read_chunk(
+        &self,
+        buf_size: usize,
+        bytes_read: &AtomicU64,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let buf = self.read(buf_size).await?;
+
+        if buf.is_empty() {
+            return Ok(None);
+        }
+
+        bytes_read.fetch_add(buf.len() as u64, Ordering::Relaxed);
 
-        let (buf, n) = input_stream
-            .read_future(buf, glib::Priority::DEFAULT)
+        Ok(Some(buf))
+    }
+}
+
+/// Size and modification time of a file that is read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileState {
+    size: u64,
+    modified: (u64, u32),
+    /// Only regular files report the number of bytes that can be read
+    regular: bool,
+}
+
+impl FileState {
+    /// Queries the state of the opened file
+    ///
+    /// Returns `None` if the stream is not reading a file or if the file
+    /// system does not report the information.
+    async fn query(input: &SourceInput) -> Option<Self> {
+        let SourceInput::Stream(input_stream) = input else {
+            return None;
+        };
+        let file_input_stream = input_stream.downcast_ref::<gio::FileInputStream>()?;
+
+        let attributes = [
+            gio::FILE_ATTRIBUTE_STANDARD_SIZE.as_str(),
+            gio::FILE_ATTRIBUTE_STANDARD_TYPE.as_str(),
+            gio::FILE_ATTRIBUTE_TIME_MODIFIED.as_str(),
+            gio::FILE_ATTRIBUTE_TIME_MODIFIED_USEC.as_str(),
+        ]
+        .join(",");
+
+        let info = file_input_stream
+            .query_info_future(&attributes, glib::Priority::DEFAULT)
             .await
-            .map_err(|(_, err)| ErrorKind::ImageSource(err).err())?;
+            .ok()?;
+
+        if !info.has_attribute(gio::FILE_ATTRIBUTE_STANDARD_SIZE) {
+            return None;
+        }
 
-        let first_bytes = buf
-            .get(..n)
-            .ok_or_else(|| ErrorKind::unreachable().err())?
-            .to_vec();
+        Some(Self {
+            size: u64::try_from(info.size()).ok()?,
+            modified: (
+                info.attribute_uint64(gio::FILE_ATTRIBUTE_TIME_MODIFIED),
+                info.attribute_uint32(gio::FILE_ATTRIBUTE_TIME_MODIFIED_USEC),
+            ),
+            regular: info.file_type() == gio::FileType::Regular,
+        })
+    }
+}
+
+impl SourceTransmission {
+    pub async fn init(source: Source, buf_size: usize) -> Result<SourceTransmission, Error> {
+        tracing::trace!("Opening source");
+
+        let buf_size = buf_size.clamp(MIN_BUF_SIZE, MAX_BUF_SIZE);
+        let file = source.file();
+        let input = match source {
+            Source::Vec(data) => SourceInput::Vec {
+                data,
+                pos: AtomicUsize::new(0),
+            },
+            source => SourceInput::Stream(source.to_stream().await?),
+        };
+
+        tracing::trace!("Read first {buf_size} bytes");
+
+        let first_bytes = input.read(buf_size).await?;
+        let file_state = FileState::query(&input).await;
 
         Ok(Self {
-            file: source.file(),
-            input_stream,
+            file,
+            input: Arc::new(input),
+            bytes_read: Arc::new(AtomicU64::new(first_bytes.len() as u64)),
             first_bytes,
+            buf_size,
+            read_ahead: 0,
+            file_state,
         })
     }
 
-    #[cfg(feature = "external")]
-    async fn spawn_with_stream(self, stream: gio_unix::OutputStream) -> Result<(), Error> {
-        let res = stream
-            .write_all_future(self.first_bytes, glib::Priority::DEFAULT)
-            .await;
-
-        match res {
-            Err((_, err)) => return Err(err.into()),
-            Ok((_, _, Some(err))) => return Err(err.into()),
-            Ok(_) => {}
+    /// Read up to `n_chunks` chunks before the loader consumes them
+    ///
+    /// Reading from the source then continues in a separate task while the
+    /// data are passed to the loader. A value of `0` disables the read-ahead.
+    /// The number of chunks is limited to `MAX_READ_AHEAD` and such that
+    /// they don't exceed `MAX_READ_AHEAD_SIZE`.
+    pub fn set_read_ahead(&mut self, n_chunks: usize) {
+        let max_chunks = (MAX_READ_AHEAD_SIZE / self.buf_size).clamp(1, MAX_READ_AHEAD);
+        self.read_ahead = n_chunks.min(max_chunks);
+    }
+
+    /// Reads the next chunk from the source
+    ///
+    /// Returns `None` once the end of the source has been reached.
+    async fn read_chunk(&self) -> Result<Option<Vec<u8>>, Error> {
+        self.input.read_chunk(self.buf_size, &self.bytes_read).await
+    }
+
+    /// Checks that the file did not change while reading it
+    ///
+    /// Files are read in chunks. If another program truncates or rewrites the
+    /// file in the meantime, the loader would get inconsistent data.
+    async fn check_file_unchanged(&self) -> Result<(), Error> {
+        let Some(file_state) = self.file_state else {
+            return Ok(());
+        };
+
+        let bytes_read = self.bytes_read.load(Ordering::Relaxed);
+        let current_state = FileState::query(&self.input).await;
+
+        // FIFOs and files in procfs report a size of zero or none at all
+        let size_known = file_state.regular && file_state.size > 0;
+
+        if (size_known && bytes_read != file_state.size)
+            || current_state.is_some_and(|x| x != file_state)
+        {
+            tracing::debug!(
+                "File changed during read: {file_state:?} -> {current_state:?} with {bytes_read} bytes read"
+            );
+            return Err(ErrorKind::FileChangedDuringRead.err());
         }
 
-        loop {
-            use crate::ErrorKind;
+        Ok(())
+    }
 
-            let buf = vec![0; BUF_SIZE];
+    #[cfg(feature = "external")]
+    async fn spawn_with_stream(mut self, stream: gio_unix::OutputStream) -> Result<(), Error> {
+        write_all(&stream, std::mem::take(&mut self.first_bytes)).await?;
 
-            let (buf, n) = self
-                .input_stream
-                .read_future(buf, glib::Priority::DEFAULT)
-                .await
-                .map_err(|(_, err)| ErrorKind::ImageSource(err).err())?;
-            if n == 0 {
-                return Ok(());
+        if self.read_ahead == 0 {
+            while let Some(chunk) = self.read_chunk().await? {
+                write_all(&stream, chunk).await?;
             }
+        } else {
+            let (mut sender, mut receiver) = futures_channel::mpsc::channel(self.read_ahead);
+
+            // Keep reading from the source in a separate task while writing to
+            // the loader blocks. The task ends once the receiver is dropped.
+            let input = self.input.clone();
+            let buf_size = self.buf_size;
+            let bytes_read = self.bytes_read.clone();
+            let _reader = glib::MainContext::ref_thread_default().spawn_local(async move {
+                loop {
+                    let result = input.read_chunk(buf_size, &bytes_read).await;
+                    let end = !matches!(result, Ok(Some(_)));
+
+                    if sender.send(result).await.is_err() || end {
+                        break;
+                    }
+                }
+            });
 
-            // TODO: Avoiding to_vec()
-            let res = stream
-                .write_all_future(
-                    buf.get(..n)
-                        .ok_or_else(|| ErrorKind::unreachable().err())?
-                        .to_vec(),
-                    glib::Priority::DEFAULT,
-                )
-                .await;
-
-            match res {
-                Err((_, err)) => return Err(err.into()),
-                Ok((_, _, Some(err))) => return Err(err.into()),
-                Ok(_) => {}
+            while let Some(chunk) = receiver.next().await.transpose()?.flatten() {
+                write_all(&stream, chunk).await?;
             }
         }
+
+        self.check_file_unchanged().await
     }
 
     #[cfg(feature = "external")]
@@ -104,27 +275,12 @@ impl SourceTransmission {
     ) -> Result<(), Error> {
         channel.send(self.first_bytes.to_vec()).await.unwrap();
 
-        if self.first_bytes.len() < BUF_SIZE {
-            // TODO: Potentially unsound, but gives 10 micro seconds
-            return Ok(());
+        // Streams can return less than requested before their end
+        while let Some(chunk) = self.read_chunk().await? {
+            channel.send(chunk).await.unwrap();
         }
 
-        loop {
-            use crate::ErrorKind;
-
-            let buf = vec![0; BUF_SIZE];
-
-            let (buf, n) = self
-                .input_stream
-                .read_future(buf, glib::Priority::DEFAULT)
-                .await
-                .map_err(|(_, err)| ErrorKind::ImageSource(err).err())?;
-            if n == 0 {
-                return Ok(());
-            }
-
-            channel.send(buf[..n].to_vec()).await.unwrap();
-        }
+        self.check_file_unchanged().await
     }
 
     #[cfg(feature = "builtin")]
@@ -143,6 +299,28 @@ impl SourceTransmission {
     pub fn first_bytes(&self) -> &[u8] {
         &self.first_bytes
     }
+
+    /// Size of the file when it was opened
+    ///
+    /// Returns `None` for streams and file systems that don't report the
+    /// size.
+    pub fn file_size(&self) -> Option<u64> {
+        self.file_state.map(|x| x.size)
+    }
+
+    /// Counter for the bytes read from the source so far
+    pub fn bytes_read(&self) -> Arc<AtomicU64> {
+        self.bytes_read.clone()
+    }
+}
+
+#[cfg(feature = "external")]
+async fn write_all(stream: &gio_unix::OutputStream, data: Vec<u8>) -> Result<(), Error> {
+    match stream.write_all_future(data, glib::Priority::DEFAULT).await {
+        Err((_, err)) => Err(err.into()),
+        Ok((_, _, Some(err))) => Err(err.into()),
+        Ok(_) => Ok(()),
+    }
 }
 
 #[cfg(feature = "builtin")]
@@ -194,3 +372,272 @@ fn write_data(target: &mut [u8], src: &[u8]) -> (usize, Vec<u8>) {
         (src.len(), Vec::new())
     }
 }
+
+#[cfg(all(test, any(feature = "builtin", feature = "external")))]
+mod test {
+    use std::io::Read;
+    #[cfg(feature = "external")]
+    use std::time::Duration;
+
+    use super::*;
+    use crate::GInputStreamSend;
+
+    mod slow_stream {
+        use std::io::Cursor;
+        use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+        use std::sync::{Arc, Mutex};
+        use std::time::Duration;
+
+        use gio::subclass::prelude::*;
+
+        mod imp {
+            use super::*;
+
+            #[derive(Default)]
+            pub struct SlowStream {
+                pub data: Mutex<Cursor<Vec<u8>>>,
+                pub finished: Arc<AtomicBool>,
+                /// Largest number of bytes returned per read, `0` for no limit
+                pub max_read: AtomicUsize,
+            }
+
+            #[glib::object_subclass]
+            impl ObjectSubclass for SlowStream {
+                const NAME: &'static str = "GlycinTestSlowStream";
+                type Type = super::SlowStream;
+                type ParentType = gio::InputStream;
+            }
+
+            impl ObjectImpl for SlowStream {}
+
+            impl InputStreamImpl for SlowStream {
+                fn read(
+                    &self,
+                    buffer: &mut [u8],
+                    _cancellable: Option<&gio::Cancellable>,
+                ) -> Result<usize, glib::Error> {
+                    // Simulate network latency
+                    std::thread::sleep(Duration::from_millis(2));
+                    let max_read = match self.max_read.load(Ordering::Relaxed) {
+                        0 => buffer.len(),
+                        max_read => max_read.min(buffer.len()),
+                    };
+                    let n = std::io::Read::read(
+                        &mut *self.data.lock().unwrap(),
+                        &mut buffer[..max_read],
+                    )
+                    .unwrap();
+                    if n == 0 {
+                        self.finished.store(true, Ordering::Relaxed);
+                    }
+                    Ok(n)
+                }
+            }
+        }
+
+        glib::wrapper! {
+            pub struct SlowStream(ObjectSubclass<imp::SlowStream>) @extends gio::InputStream;
+        }
+
+        impl SlowStream {
+            pub fn new(data: Vec<u8>) -> Self {
+                let stream: Self = glib::Object::new();
+                *stream.imp().data.lock().unwrap() = Cursor::new(data);
+                stream
+            }
+
+            /// Set once the end of the data has been read
+            #[cfg(feature = "external")]
+            pub fn finished(&self) -> Arc<AtomicBool> {
+                self.imp().finished.clone()
+            }
+
+            /// Return at most `max_read` bytes per read
+            #[cfg(feature = "builtin")]
+            pub fn set_max_read(&self, max_read: usize) {
+                self.imp().max_read.store(max_read, Ordering::Relaxed);
+            }
+        }
+    }
+
+    #[cfg(feature = "builtin")]
+    #[test]
+    fn builtin_partial_reads() {
+        let data = (0..MIN_BUF_SIZE * 3 + 7)
+            .map(|x| (x % 251) as u8)
+            .collect::<Vec<_>>();
+
+        let stream = slow_stream::SlowStream::new(data.clone());
+        stream.set_max_read(1000);
+
+        let consumer = glib::MainContext::new().block_on(async {
+            let source = Source::Stream(unsafe { GInputStreamSend::new(stream.upcast()) });
+            let transmission = SourceTransmission::init(source, MIN_BUF_SIZE)
+                .await
+                .unwrap();
+            assert_eq!(transmission.first_bytes().len(), 1000);
+
+            let (mut reader, future) = transmission.spawn_builtin();
+            let consumer = std::thread::spawn(move || {
+                let mut received = Vec::new();
+                reader.read_to_end(&mut received).unwrap();
+                received
+            });
+
+            future.await.unwrap();
+            consumer
+        });
+
+        assert_eq!(consumer.join().unwrap(), data);
+    }
+
+    #[cfg(feature = "external")]
+    #[test]
+    fn read_ahead_slow_stream() {
+        let data = (0..MIN_BUF_SIZE * 20)
+            .map(|x| (x % 251) as u8)
+            .collect::<Vec<_>>();
+
+        for read_ahead in [0, 4, MAX_READ_AHEAD] {
+            let stream = slow_stream::SlowStream::new(data.clone());
+
+            let consumer = glib::MainContext::new().block_on(async {
+                let source = Source::Stream(unsafe { GInputStreamSend::new(stream.upcast()) });
+                let mut transmission = SourceTransmission::init(source, MIN_BUF_SIZE)
+                    .await
+                    .unwrap();
+                transmission.set_read_ahead(read_ahead);
+
+                let (reader, future) = transmission.spawn_external().unwrap();
+
+                // Consumer that is slow as well
+                let consumer = std::thread::spawn(move || {
+                    let mut reader = std::fs::File::from(reader);
+                    let mut received = Vec::new();
+                    let mut buf = vec![0; MIN_BUF_SIZE];
+                    loop {
+                        let n = reader.read(&mut buf).unwrap();
+                        if n == 0 {
+                            break received;
+                        }
+                        std::thread::sleep(Duration::from_millis(1));
+                        received.extend_from_slice(&buf[..n]);
+                    }
+                });
+
+                future.await.unwrap();
+                consumer
+            });
+
+            assert_eq!(consumer.join().unwrap(), data, "read-ahead {read_ahead}");
+        }
+    }
+
+    #[cfg(feature = "external")]
+    #[test]
+    fn read_ahead_overlaps() {
+        // More than fits into the socket buffer
+        let buf_size = 64 * 1024;
+        let data = vec![1; buf_size * 64];
+
+        let stream = slow_stream::SlowStream::new(data.clone());
+        let finished = stream.finished();
+
+        let consumer = glib::MainContext::new().block_on(async {
+            let source = Source::Stream(unsafe { GInputStreamSend::new(stream.upcast()) });
+            let mut transmission = SourceTransmission::init(source, buf_size).await.unwrap();
+            transmission.set_read_ahead(64);
+
+            let (reader, future) = transmission.spawn_external().unwrap();
+
+            // The consumer only starts once the source has been read completely
+            let consumer = std::thread::spawn(move || {
+                for _ in 0..500 {
+                    if finished.load(std::sync::atomic::Ordering::Relaxed) {
+                        break;
+                    }
+                    std::thread::sleep(Duration::from_millis(10));
+                }
+                let finished_first = finished.load(std::sync::atomic::Ordering::Relaxed);
+
+                let mut received = Vec::new();
+                std::fs::File::from(reader)
+                    .read_to_end(&mut received)
+                    .unwrap();
+
+                (finished_first, received)
+            });
+
+            future.await.unwrap();
+            consumer
+        });
+
+        let (finished_first, received) = consumer.join().unwrap();
+        assert!(finished_first, "Source was not read ahead of the consumer");
+        assert_eq!(received, data);
+    }
+
+    #[cfg(feature = "external")]
+    #[test]
+    fn vec_source() {
+        let data = (0..MIN_BUF_SIZE * 3 + 7)
+            .map(|x| (x % 251) as u8)
+            .collect::<Vec<_>>();
+
+        let received = glib::MainContext::new().block_on(async {
+            let transmission = SourceTransmission::init(Source::Vec(data.clone()), MIN_BUF_SIZE)
+                .await
+                .unwrap();
+            assert_eq!(transmission.first_bytes(), &data[..MIN_BUF_SIZE]);
+
+            let (reader, future) = transmission.spawn_external().unwrap();
+            let consumer = std::thread::spawn(move || {
+                let mut received = Vec::new();
+                std::fs::File::from(reader)
+                    .read_to_end(&mut received)
+                    .unwrap();
+                received
+            });
+
+            future.await.unwrap();
+            consumer
+        });
+
+        assert_eq!(received.join().unwrap(), data);
+    }
+
+    #[cfg(feature = "external")]
+    #[test]
+    fn file_truncated_during_read() {
+        let path = std::env::temp_dir().join(format!("glycin-truncated-{}", std::process::id()));
+        std::fs::write(&path, vec![0; MIN_BUF_SIZE * 3]).unwrap();
+
+        glib::MainContext::new().block_on(async {
+            let source = Source::File(gio::File::for_path(&path));
+            let transmission = SourceTransmission::init(source, MIN_BUF_SIZE)
+                .await
+                .unwrap();
+            let (_reader, future) = transmission.spawn_external().unwrap();
+            future.await.unwrap();
+
+            let source = Source::File(gio::File::for_path(&path));
+            let transmission = SourceTransmission::init(source, MIN_BUF_SIZE)
+                .await
+                .unwrap();
+
+            // Truncate the file after the first chunk has been read
+            std::fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_len(MIN_BUF_SIZE as u64 * 2)
+                .unwrap();
+
+            let (_reader, future) = transmission.spawn_external().unwrap();
+            let err = future.await.unwrap_err();
+            assert!(matches!(err.kind(), ErrorKind::FileChangedDuringRead));
+        });
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,12 +1,13 @@
 use std::future::Future;
 use std::path::{Path, PathBuf};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use futures_util::{Stream, StreamExt};
 use gio::glib;
 use gio::prelude::CancellableExtManual;
 #[cfg(feature = "gdk4")]
 use glycin_utils::MemoryFormat;
+use glycin_utils::safe_math::SafeMath;
 
 #[cfg(feature = "gdk4")]
 use crate::ColorState;
@@ -65,6 +66,47 @@ pub trait TimeoutFuture<T>: Future<Output = Result<T, crate::Error>> + Sized {
 
 impl<T, F: Future<Output = Result<T, crate::Error>>> TimeoutFuture<T> for F {}
 
+/// Time limit for a complete operation
+///
+/// Unlike [`TimeoutFuture::enforce_timeout`], the limit is not reset for
+/// subsequent steps of the operation.
+#[derive(Debug, Clone, Copy)]
+pub struct Deadline {
+    start: Instant,
+    timeout: Duration,
+}
+
+impl Deadline {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            start: Instant::now(),
+            timeout,
+        }
+    }
+
+    /// Time since the operation started
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    /// Time left until the deadline is reached
+    pub fn remaining(&self) -> Duration {
+        self.timeout.saturating_sub(self.elapsed())
+    }
+}
+
+/// Corresponding GDK memory format
+///
+/// All formats except CMYK have an exact GDK equivalent with the same
+/// channels, channel order, and bit depth. The match has no wildcard arm,
+/// such that new formats have to be mapped explicitly.
+///
+/// CMYK formats, which GDK doesn't support, are mapped to the format of
+/// [`MemoryFormat::without_cmyk`]. Frames never use CMYK formats since they are
+/// converted to RGB in `Frame::from_loader`, also for raw frames. Other CMYK
+/// data have to be converted via
+/// [`change_memory_format`](glycin_utils::editing::change_memory_format)
+/// before using them with the returned format.
 #[cfg(feature = "gdk4")]
 pub const fn gdk_memory_format(format: MemoryFormat) -> gdk::MemoryFormat {
     match format {
@@ -93,6 +135,9 @@ pub const fn gdk_memory_format(format: MemoryFormat) -> gdk::MemoryFormat {
         MemoryFormat::G16a16Premultiplied => gdk::MemoryFormat::G16a16Premultiplied,
         MemoryFormat::G16a16 => gdk::MemoryFormat::G16a16,
         MemoryFormat::G16 => gdk::MemoryFormat::G16,
+        MemoryFormat::C8m8y8k8 | MemoryFormat::C16m16y16k16 => {
+            gdk_memory_format(format.without_cmyk())
+        }
     }
 }
 
@@ -203,6 +248,66 @@ async fn flatpak_devel() -> Option<bool> {
     Some(flatpak_builder && name.ends_with("Devel"))
 }
 
+/// Size of a memory page in bytes
+pub(crate) fn page_size() -> usize {
+    // SAFETY: Only reads a constant system configuration value
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    usize::try_from(page_size).unwrap_or(4096)
+}
+
+/// Parses an ISO 8601 date and time
+///
+/// Date-only values are interpreted as midnight. Values without offset from
+/// UTC are interpreted in the local time zone.
+pub(crate) fn date_time_from_iso8601(s: &str) -> Option<glib::DateTime> {
+    let s = if s.contains('T') {
+        s.to_string()
+    } else {
+        format!("{s}T00:00:00")
+    };
+
+    glib::DateTime::from_iso8601(&s, Some(&glib::TimeZone::local())).ok()
+}
+
+/// Copies `height` rows of `row_len` bytes into a buffer with a new stride
+///
+/// The padding at the end of each row in the new buffer is filled with zeros.
+/// The last row in `src` doesn't need to have the complete stride length.
+pub(crate) fn change_stride(
+    src: &[u8],
+    height: usize,
+    row_len: usize,
+    src_stride: usize,
+    target_stride: usize,
+) -> Result<Vec<u8>, crate::Error> {
+    if target_stride < row_len {
+        return Err(ErrorKind::StrideTooSmall(format!(
+            "Stride is {target_stride} but must be at least {row_len}"
+        ))
+        .err());
+    }
+
+    if target_stride == 0 {
+        return Ok(Vec::new());
+    }
+
+    let mut target = vec![0; target_stride.smul(height)?];
+
+    for (row, target_row) in target.chunks_exact_mut(target_stride).enumerate() {
+        let row_begin = row.smul(src_stride)?;
+        let src_row = src
+            .get(row_begin..row_begin.sadd(row_len)?)
+            .ok_or_else(|| ErrorKind::TextureWrongSize {
+                texture_size: src.len(),
+                frame: format!("Stride size: {src_stride} Row size: {row_len} Height: {height}"),
+            })?;
+
+        target_row[..row_len].copy_from_slice(src_row);
+    }
+
+    Ok(target)
+}
+
 pub async fn spawn_blocking<F: FnOnce() -> T + Send + 'static, T: Send + 'static>(
     f: F,
 ) -> Result<T, crate::Error> {
@@ -211,6 +316,37 @@ pub async fn spawn_blocking<F: FnOnce() -> T + Send + 'static, T: Send + 'static
         .map_err(|e| ErrorKind::panic(e).err())
 }
 
+/// Number of values [`blocking_stream`] emits ahead of the consumer
+const BLOCKING_STREAM_BUFFER: usize = 64;
+
+/// Runs `f` in a separate thread and streams the values it emits
+///
+/// Values passed to the callback of `f` are returned by the stream right
+/// away. Once [`BLOCKING_STREAM_BUFFER`] values are waiting, the callback
+/// blocks until the stream is polled again. The callback returns `false`
+/// once the stream has been dropped, such that `f` can stop early. The
+/// stream ends when `f` returns.
+pub fn blocking_stream<T: Send + 'static>(
+    f: impl FnOnce(&dyn Fn(T) -> bool) + Send + 'static,
+) -> impl futures_util::Stream<Item = T> + Send + 'static {
+    let (sender, receiver) = futures_channel::mpsc::channel(BLOCKING_STREAM_BUFFER);
+
+    std::thread::spawn(move || {
+        let main_context = glib::MainContext::new();
+        let sender = std::cell::RefCell::new(sender);
+
+        f(&|value| {
+            let mut sender = sender.borrow_mut();
+            main_context
+                .block_on(futures_util::future::poll_fn(|cx| sender.poll_ready(cx)))
+                .and_then(|()| sender.start_send(value))
+                .is_ok()
+        })
+    });
+
+    receiver
+}
+
 #[cfg(feature = "async-io")]
 pub use async_io_utils::*;
 #[cfg(feature = "tokio")]
@@ -239,6 +375,17 @@ mod async_io_utils {
         async_global_executor::spawn(f).detach()
     }
 
+    /// Same as [`spawn_detached`], always succeeds for async-io
+    #[cfg(feature = "external")]
+    pub fn try_spawn_detached<F>(f: F) -> bool
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        spawn_detached(f);
+        true
+    }
+
     pub type AsyncMutex<T> = async_lock::Mutex<T>;
 
     pub const fn new_async_mutex<T>(t: T) -> AsyncMutex<T> {
@@ -303,6 +450,26 @@ mod tokio_utils {
         tokio::task::spawn(f);
     }
 
+    /// Spawns `f` if the current thread has a tokio runtime
+    ///
+    /// Returns `false` without spawning `f` if there is no runtime. Unlike
+    /// [`spawn_detached`], this doesn't panic and can be used in `Drop`
+    /// implementations or signal handlers.
+    #[cfg(feature = "external")]
+    pub fn try_spawn_detached<F>(f: F) -> bool
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn(f);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
     pub type AsyncMutex<T> = tokio::sync::Mutex<T>;
 
     pub const fn new_async_mutex<T>(t: T) -> AsyncMutex<T> {
@@ -348,3 +515,103 @@ mod tokio_utils {
         tokio::time::sleep(duration).await;
     }
 }
+
+#[cfg(all(test, feature = "external"))]
+mod spawn_tests {
+    use super::*;
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn try_spawn_detached_without_runtime() {
+        assert!(!try_spawn_detached(async {}));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn try_spawn_detached_with_runtime() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let _guard = runtime.enter();
+        assert!(try_spawn_detached(async {}));
+    }
+
+    #[cfg(feature = "async-io")]
+    #[test]
+    fn try_spawn_detached_async_io() {
+        let (send, recv) = std::sync::mpsc::channel();
+        assert!(try_spawn_detached(async move { send.send(()).unwrap() }));
+        recv.recv_timeout(Duration::from_secs(10)).unwrap();
+    }
+}
+
+#[cfg(all(test, feature = "gdk4"))]
+mod tests {
+    use glycin_common::MemoryFormatInfo;
+
+    use super::*;
+
+    #[test]
+    fn gdk_memory_format_all() {
+        let mut gdk_formats = Vec::new();
+
+        for format in MemoryFormat::ALL.iter().copied() {
+            let gdk_format = gdk_memory_format(format);
+
+            if format.is_cmyk() {
+                let rgb_format = format.without_cmyk();
+                assert_eq!(gdk_format, gdk_memory_format(rgb_format));
+                assert_eq!(rgb_format.n_channels(), 3);
+                assert_eq!(rgb_format.channel_type(), format.channel_type());
+
+                // Frames are converted like this before the mapping is used
+                let texture =
+                    glycin_utils::FungibleMemory::from_vec(vec![0; format.n_bytes().usize()]);
+                let mut frame = glycin_utils::Frame::new(1, 1, format, texture).unwrap();
+                glycin_utils::editing::change_memory_format(&mut frame, rgb_format).unwrap();
+                assert_eq!(gdk_memory_format(frame.memory_format), gdk_format);
+                // No ink is white
+                assert!(frame.texture.iter().all(|x| *x == u8::MAX));
+                continue;
+            }
+
+            // Same variant name implies same channels, order, and bit depth
+            assert_eq!(format!("{format:?}"), format!("{gdk_format:?}"));
+            assert!(!gdk_formats.contains(&gdk_format), "{format:?}");
+            gdk_formats.push(gdk_format);
+        }
+    }
+
+    #[test]
+    fn blocking_stream_bounded() {
+        let emitted = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let (done_sender, done_receiver) = std::sync::mpsc::channel();
+
+        let mut stream = Box::pin(blocking_stream({
+            let emitted = emitted.clone();
+            move |emit| {
+                for i in 0..10_000 {
+                    if !emit(i) {
+                        break;
+                    }
+                    emitted.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                }
+                done_sender.send(()).unwrap();
+            }
+        }));
+
+        glib::MainContext::new().block_on(async {
+            assert_eq!(stream.next().await, Some(0));
+        });
+
+        // The thread waits for the stream instead of emitting all values
+        std::thread::sleep(Duration::from_millis(100));
+        let n_emitted = emitted.load(std::sync::atomic::Ordering::Relaxed);
+        assert!(n_emitted <= BLOCKING_STREAM_BUFFER + 2, "{n_emitted}");
+
+        // Dropping the stream stops the thread
+        drop(stream);
+        done_receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(emitted.load(std::sync::atomic::Ordering::Relaxed) < 10_000);
+    }
+}
@@ -0,0 +1,440 @@
+//! Extraction of simple XMP properties that are not covered by gufo
+
+use std::collections::HashMap;
+
+use xml::name::OwnedName;
+use xml::reader::{ParserConfig, XmlEvent};
+use xml::writer::EmitterConfig;
+
+const NS_CONTAINER: &str = "http://ns.google.com/photos/1.0/container/";
+const NS_CONTAINER_ITEM: &str = "http://ns.google.com/photos/1.0/container/item/";
+const NS_CRS: &str = "http://ns.adobe.com/camera-raw-settings/1.0/";
+const NS_EXIF: &str = "http://ns.adobe.com/exif/1.0/";
+const NS_GCAMERA: &str = "http://ns.google.com/photos/1.0/camera/";
+const NS_GDEPTH: &str = "http://ns.google.com/photos/1.0/depthmap/";
+const NS_GFOCUS: &str = "http://ns.google.com/photos/1.0/focus/";
+const NS_PHOTOSHOP: &str = "http://ns.adobe.com/photoshop/1.0/";
+const NS_RDF: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+const NS_XMP: &str = "http://ns.adobe.com/xap/1.0/";
+
+/// Property name as namespace URI and local name
+type Name = (String, String);
+
+/// Simple properties of an XMP packet
+///
+/// Properties are identified by their namespace URI, independent of the
+/// prefix used in the packet. They can be written as attribute,
+/// `ns:Name="value"`, or as element, `<ns:Name>value</ns:Name>`. If a
+/// property occurs multiple times, the first occurrence is used.
+#[derive(Debug, Default)]
+pub(crate) struct Xmp {
+    properties: HashMap<Name, String>,
+    /// Attributes of the `Container:Item` elements of a motion photo
+    container_items: Vec<HashMap<Name, String>>,
+}
+
+impl Xmp {
+    /// Parses the packet
+    ///
+    /// Returns `None` if the packet is not well-formed XML.
+    pub(crate) fn parse(data: &[u8]) -> Option<Self> {
+        let reader = ParserConfig::new()
+            .trim_whitespace(true)
+            .ignore_comments(true)
+            .create_reader(data);
+
+        let mut xmp = Self::default();
+        // Element that might contain a property value as text
+        let mut element = None;
+        let mut text = String::new();
+
+        for event in reader {
+            match event.ok()? {
+                XmlEvent::StartElement {
+                    name, attributes, ..
+                } => {
+                    let attributes = attributes
+                        .into_iter()
+                        .filter_map(|x| Some(((x.name.namespace?, x.name.local_name), x.value)))
+                        .collect::<HashMap<_, _>>();
+
+                    if name.namespace.as_deref() == Some(NS_CONTAINER) && name.local_name == "Item"
+                    {
+                        xmp.container_items.push(attributes.clone());
+                    }
+
+                    for (key, value) in attributes {
+                        xmp.properties.entry(key).or_insert(value);
+                    }
+
+                    element = name.namespace.map(|x| (x, name.local_name));
+                    text.clear();
+                }
+                XmlEvent::Characters(x) | XmlEvent::CData(x) => text.push_str(&x),
+                XmlEvent::EndElement { .. } => {
+                    // Only elements without child elements contain values
+                    if let Some(name) = element.take()
+                        && !text.is_empty()
+                    {
+                        xmp.properties
+                            .entry(name)
+                            .or_insert_with(|| text.trim().to_string());
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Some(xmp)
+    }
+
+    /// Focal point from the Google `GFocus` namespace
+    ///
+    /// Returns normalized `(x, y)` coordinates.
+    pub(crate) fn focal_point(&self) -> Option<(f64, f64)> {
+        let x = self.number(NS_GFOCUS, "FocalPointX")?;
+        let y = self.number(NS_GFOCUS, "FocalPointY")?;
+
+        (normalized(x) && normalized(y)).then_some((x, y))
+    }
+
+    /// Crop from Adobe Camera Raw settings
+    ///
+    /// Returns normalized `(x, y, width, height)`. Rotated crops are ignored.
+    pub(crate) fn suggested_crop(&self) -> Option<(f64, f64, f64, f64)> {
+        if !self
+            .property(NS_CRS, "HasCrop")?
+            .eq_ignore_ascii_case("true")
+        {
+            return None;
+        }
+
+        if self.number(NS_CRS, "CropAngle").is_some_and(|x| x != 0.) {
+            return None;
+        }
+
+        let left = self.number(NS_CRS, "CropLeft")?;
+        let top = self.number(NS_CRS, "CropTop")?;
+        let right = self.number(NS_CRS, "CropRight")?;
+        let bottom = self.number(NS_CRS, "CropBottom")?;
+
+        if !(normalized(left) && normalized(top) && normalized(right) && normalized(bottom)) {
+            return None;
+        }
+
+        (left < right && top < bottom).then_some((left, top, right - left, bottom - top))
+    }
+
+    /// Capture time as ISO 8601 string
+    ///
+    /// Tries `exif:DateTimeOriginal`, `photoshop:DateCreated`, and
+    /// `xmp:CreateDate` in that order.
+    pub(crate) fn capture_time(&self) -> Option<&str> {
+        [
+            (NS_EXIF, "DateTimeOriginal"),
+            (NS_PHOTOSHOP, "DateCreated"),
+            (NS_XMP, "CreateDate"),
+        ]
+        .into_iter()
+        .filter_map(|(namespace, name)| self.property(namespace, name))
+        .find(|x| !x.is_empty())
+    }
+
+    /// Google depth map from the `GDepth` namespace
+    pub(crate) fn gdepth(&self) -> Option<GDepth<'_>> {
+        Some(GDepth {
+            format: self.property(NS_GDEPTH, "Format").unwrap_or_default(),
+            near: self.number(NS_GDEPTH, "Near"),
+            far: self.number(NS_GDEPTH, "Far"),
+            data: self.property(NS_GDEPTH, "Data").filter(|x| !x.is_empty())?,
+        })
+    }
+
+    /// Length of the video appended to a motion photo
+    ///
+    /// The video is stored at the end of the file. Supports the `GCamera`
+    /// namespace, with `GCamera:MotionPhoto` and the length of the
+    /// `Container:Item` with `Item:Semantic="MotionPhoto"`, as well as the
+    /// older `GCamera:MicroVideo` with `GCamera:MicroVideoOffset`.
+    pub(crate) fn motion_photo_length(&self) -> Option<u64> {
+        if self.property(NS_GCAMERA, "MotionPhoto") == Some("1") {
+            let item = self.container_items.iter().find(|x| {
+                x.get(&name(NS_CONTAINER_ITEM, "Semantic"))
+                    .is_some_and(|x| x == "MotionPhoto")
+            })?;
+
+            item.get(&name(NS_CONTAINER_ITEM, "Length"))?.parse().ok()
+        } else if self.property(NS_GCAMERA, "MicroVideo") == Some("1") {
+            self.property(NS_GCAMERA, "MicroVideoOffset")?.parse().ok()
+        } else {
+            None
+        }
+        .filter(|x| *x > 0)
+    }
+
+    fn property(&self, namespace: &str, local_name: &str) -> Option<&str> {
+        self.properties
+            .get(&name(namespace, local_name))
+            .map(String::as_str)
+    }
+
+    fn number(&self, namespace: &str, local_name: &str) -> Option<f64> {
+        self.property(namespace, local_name)?.parse().ok()
+    }
+}
+
+/// Merges the descriptions of the `preferred` packet into `packet`
+///
+/// The descriptions are inserted before the existing ones, such that their
+/// properties take precedence. The rest of `packet`, like the `xpacket`
+/// wrapper, is kept. Returns `None` if either packet is not well-formed XML
+/// or has no `rdf:RDF` element.
+pub(crate) fn merge(packet: &[u8], preferred: &[u8]) -> Option<Vec<u8>> {
+    let mut descriptions = Vec::new();
+    // Depth below the `rdf:RDF` element
+    let mut depth = None;
+
+    for event in ParserConfig::new()
+        .ignore_comments(true)
+        .create_reader(preferred)
+    {
+        let event = event.ok()?;
+        match (&event, &mut depth) {
+            (XmlEvent::StartElement { name, .. }, None) if is_rdf(name) => depth = Some(0),
+            (XmlEvent::EndElement { .. }, Some(0)) => depth = None,
+            (_, Some(depth)) => {
+                match &event {
+                    XmlEvent::StartElement { .. } => *depth += 1,
+                    XmlEvent::EndElement { .. } => *depth -= 1,
+                    _ => {}
+                }
+                descriptions.push(event);
+            }
+            _ => {}
+        }
+    }
+
+    if descriptions.is_empty() {
+        return None;
+    }
+
+    let mut merged = Vec::new();
+    let mut writer = EmitterConfig::new()
+        .write_document_declaration(false)
+        .create_writer(&mut merged);
+    let mut inserted = false;
+
+    for event in ParserConfig::new().create_reader(packet) {
+        let event = event.ok()?;
+        if matches!(
+            event,
+            XmlEvent::StartDocument { .. } | XmlEvent::EndDocument
+        ) {
+            continue;
+        }
+
+        if let Some(event) = event.as_writer_event() {
+            writer.write(event).ok()?;
+        }
+
+        if !inserted && matches!(&event, XmlEvent::StartElement { name, .. } if is_rdf(name)) {
+            for description in &descriptions {
+                if let Some(event) = description.as_writer_event() {
+                    writer.write(event).ok()?;
+                }
+            }
+            inserted = true;
+        }
+    }
+
+    inserted.then_some(merged)
+}
+
+fn is_rdf(name: &OwnedName) -> bool {
+    name.namespace.as_deref() == Some(NS_RDF) && name.local_name == "RDF"
+}
+
+/// Google depth map from the `GDepth` namespace
+pub(crate) struct GDepth<'a> {
+    /// `RangeLinear` or `RangeInverse`
+    pub format: &'a str,
+    pub near: Option<f64>,
+    pub far: Option<f64>,
+    /// Base64 encoded image
+    pub data: &'a str,
+}
+
+fn name(namespace: &str, local_name: &str) -> Name {
+    (namespace.to_string(), local_name.to_string())
+}
+
+fn normalized(x: f64) -> bool {
+    (0. ..=1.).contains(&x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XMP: &str = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+        <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+            <rdf:Description
+                xmlns:GFocus="http://ns.google.com/photos/1.0/focus/"
+                xmlns:crs="http://ns.adobe.com/camera-raw-settings/1.0/"
+                xmlns:xmp="http://ns.adobe.com/xap/1.0/"
+                GFocus:FocalPointX="0.25"
+                GFocus:FocalPointY = '0.75'
+                crs:HasCrop="True"
+                crs:CropTop="0.1"
+                crs:CropLeft="0.2"
+                xmp:CreateDate="2023-01-02T03:04:05">
+                <crs:CropBottom>0.9</crs:CropBottom>
+                <crs:CropRight>0.6</crs:CropRight>
+            </rdf:Description>
+        </rdf:RDF>
+    </x:xmpmeta>"#;
+
+    fn parse(xmp: &str) -> Xmp {
+        Xmp::parse(xmp.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn attributes_and_elements() {
+        let xmp = parse(XMP);
+        assert_eq!(xmp.focal_point(), Some((0.25, 0.75)));
+
+        let (x, y, width, height) = xmp.suggested_crop().unwrap();
+        assert_eq!((x, y), (0.2, 0.1));
+        assert!((width - 0.4).abs() < 1e-9);
+        assert!((height - 0.8).abs() < 1e-9);
+
+        assert_eq!(xmp.capture_time(), Some("2023-01-02T03:04:05"));
+    }
+
+    #[test]
+    fn capture_time_order() {
+        let xmp = parse(&XMP.replace(
+            "<crs:CropRight>",
+            r#"<exif:DateTimeOriginal xmlns:exif="http://ns.adobe.com/exif/1.0/">2022-01-01T10:00:00+01:00</exif:DateTimeOriginal><crs:CropRight>"#,
+        ));
+        assert_eq!(xmp.capture_time(), Some("2022-01-01T10:00:00+01:00"));
+    }
+
+    #[test]
+    fn namespace_prefixes() {
+        // Different prefix for the same namespace
+        let xmp = parse(
+            r#"<rdf:Description
+            xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"
+            xmlns:focus="http://ns.google.com/photos/1.0/focus/"
+            focus:FocalPointX="0.5"
+            focus:FocalPointY="0.5"/>"#,
+        );
+        assert_eq!(xmp.focal_point(), Some((0.5, 0.5)));
+
+        // Common prefix for a different namespace
+        let xmp = parse(
+            r#"<rdf:Description
+            xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"
+            xmlns:GFocus="http://example.org/focus/"
+            GFocus:FocalPointX="0.5"
+            GFocus:FocalPointY="0.5"/>"#,
+        );
+        assert_eq!(xmp.focal_point(), None);
+    }
+
+    #[test]
+    fn gdepth_properties() {
+        let gdepth_xmp = parse(
+            r#"<rdf:Description
+            xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"
+            xmlns:GDepth="http://ns.google.com/photos/1.0/depthmap/"
+            GDepth:Format="RangeInverse"
+            GDepth:Near="0.5"
+            GDepth:Far="12.25"
+            GDepth:Mime="image/png"
+            GDepth:Data="iVBORw0KGgo="/>"#,
+        );
+
+        let gdepth = gdepth_xmp.gdepth().unwrap();
+        assert_eq!(gdepth.format, "RangeInverse");
+        assert_eq!((gdepth.near, gdepth.far), (Some(0.5), Some(12.25)));
+        assert_eq!(gdepth.data, "iVBORw0KGgo=");
+
+        assert!(parse(XMP).gdepth().is_none());
+    }
+
+    #[test]
+    fn motion_photo() {
+        let motion_photo = parse(
+            r#"<rdf:Description
+            xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"
+            xmlns:GCamera="http://ns.google.com/photos/1.0/camera/"
+            xmlns:Container="http://ns.google.com/photos/1.0/container/"
+            xmlns:Item="http://ns.google.com/photos/1.0/container/item/"
+            GCamera:MotionPhoto="1"
+            GCamera:MotionPhotoVersion="1">
+            <Container:Directory>
+                <rdf:Seq>
+                    <rdf:li rdf:parseType="Resource">
+                        <Container:Item Item:Mime="image/jpeg" Item:Semantic="Primary" Item:Length="0" Item:Padding="0"/>
+                    </rdf:li>
+                    <rdf:li rdf:parseType="Resource">
+                        <Container:Item Item:Mime="video/mp4" Item:Semantic="MotionPhoto" Item:Length="4242"/>
+                    </rdf:li>
+                </rdf:Seq>
+            </Container:Directory>
+        </rdf:Description>"#,
+        );
+        assert_eq!(motion_photo.motion_photo_length(), Some(4242));
+
+        let micro_video = |enabled: &str| {
+            parse(&format!(
+                r#"<rdf:Description
+                xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"
+                xmlns:GCamera="http://ns.google.com/photos/1.0/camera/"
+                GCamera:MicroVideo="{enabled}"
+                GCamera:MicroVideoOffset="1234"/>"#
+            ))
+        };
+        assert_eq!(micro_video("1").motion_photo_length(), Some(1234));
+        assert_eq!(micro_video("0").motion_photo_length(), None);
+        assert_eq!(parse(XMP).motion_photo_length(), None);
+    }
+
+    #[test]
+    fn merge_packets() {
+        let sidecar = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+            <r:RDF xmlns:r="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+                <r:Description xmlns:xmp="http://ns.adobe.com/xap/1.0/">
+                    <xmp:CreateDate>2024-05-06T07:08:09</xmp:CreateDate>
+                </r:Description>
+            </r:RDF>
+        </x:xmpmeta>"#;
+        let packet = format!(r#"<?xpacket begin="" id="W5M0MpCehiHzreSzNTczkc9d"?>{XMP}"#);
+
+        let merged = merge(packet.as_bytes(), sidecar.as_bytes()).unwrap();
+        assert!(merged.starts_with(b"<?xpacket"));
+
+        let xmp = Xmp::parse(&merged).unwrap();
+        // Sidecar takes precedence
+        assert_eq!(xmp.capture_time(), Some("2024-05-06T07:08:09"));
+        // Embedded properties are kept
+        assert_eq!(xmp.focal_point(), Some((0.25, 0.75)));
+
+        assert!(merge(packet.as_bytes(), b"<x:xmpmeta").is_none());
+        assert!(merge(b"<rdf:Description/>", sidecar.as_bytes()).is_none());
+    }
+
+    #[test]
+    fn missing_or_invalid() {
+        assert!(Xmp::parse(b"<rdf:Description").is_none());
+        assert_eq!(Xmp::default().focal_point(), None);
+        assert_eq!(parse(&XMP.replace("True", "False")).suggested_crop(), None);
+        assert_eq!(
+            parse(&XMP.replace(r#"crs:CropLeft="0.2""#, r#"crs:CropLeft="0.8""#)).suggested_crop(),
+            None
+        );
+        assert_eq!(parse(&XMP.replace("0.25", "1.5")).focal_point(), None);
+    }
+}
@@ -27,6 +27,6 @@ where
     let mut image = loader.load().await.expect("request failed");
     let frame = image.next_frame().await.expect("next frame failed");
 
-    frame.texture().save_to_png("output.png")?;
+    frame.texture()?.save_to_png("output.png")?;
     Ok(())
 }
@@ -1,15 +1,15 @@
 mod editing;
 
 use std::io::{Cursor, Read};
-use std::sync::Mutex;
 use std::sync::mpsc::{Receiver, Sender, channel};
+use std::sync::{Arc, Mutex};
 
 use glycin_utils::safe_math::*;
 use glycin_utils::*;
 use gufo_common::cicp::Cicp;
 use libheif_rs::{
-    ColorProfile, ColorProfileNCLX, ColorProfileRaw, ColorSpace, HeifContext, HeifError,
-    HeifErrorCode, ImageHandle, LibHeif, RgbChroma, StreamReader,
+    AuxiliaryImagesFilter, ColorProfile, ColorProfileNCLX, ColorProfileRaw, ColorSpace,
+    HeifContext, HeifError, HeifErrorCode, ImageHandle, LibHeif, RgbChroma, StreamReader,
 };
 
 use crate::editing::ImgEditor;
@@ -24,10 +24,31 @@ pub struct ImgDecoder {
     pub decoder: Option<HeifContext<'static>>,
     pub thread: Mutex<Option<(std::thread::JoinHandle<()>, FrameReceiver)>>,
     pub mime_type: String,
+    /// Context to decode auxiliary images or further items
+    ///
+    /// Only kept if there are auxiliary images or further items.
+    pub items: Option<HeifContext<'static>>,
 }
 
 unsafe impl Sync for ImgDecoder {}
 
+/// File data that are shared between contexts without copying
+#[derive(Clone)]
+pub struct SharedData(Arc<Vec<u8>>);
+
+impl AsRef<[u8]> for SharedData {
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_slice()
+    }
+}
+
+impl SharedData {
+    fn context(&self) -> Result<HeifContext<'static>, ProcessError> {
+        let stream_reader = StreamReader::new(Cursor::new(self.clone()), self.0.len().try_u64()?);
+        HeifContext::read_from_reader(Box::new(stream_reader)).expected_error()
+    }
+}
+
 fn rgb_chroma(handle: &ImageHandle) -> RgbChroma {
     if handle.luma_bits_per_pixel() > 8 {
         if handle.has_alpha_channel() {
@@ -102,7 +123,7 @@ fn scale_image_to_16bit(image: &mut libheif_rs::Image) {
     }
 }
 
-fn animated_worker(data: Vec<u8>, mime_type: String, send: FrameSender) {
+fn animated_worker(data: SharedData, mime_type: String, send: FrameSender) {
     std::thread::park();
 
     // Is the sequence being currently repeated?
@@ -111,12 +132,10 @@ fn animated_worker(data: Vec<u8>, mime_type: String, send: FrameSender) {
     // Repeat the image sequence
     loop {
         let mut current_frame_num: u64 = 0;
-        let stream_reader = StreamReader::new(Cursor::new(&data), data.len() as u64);
-        let context = match HeifContext::read_from_reader(Box::new(stream_reader)) {
+        let context = match data.context() {
             Ok(c) => c,
             Err(e) => {
-                send.send(Err(ProcessError::expected(&e.to_string())))
-                    .unwrap();
+                send.send(Err(e)).unwrap();
                 return;
             }
         };
@@ -232,14 +251,13 @@ impl LoaderImplementation for ImgDecoder {
         _details: InitializationDetails,
     ) -> Result<(Self, ImageDetails<B>), ProcessError> {
         let mut data = Vec::new();
-        let total_size = stream.read_to_end(&mut data).internal_error()?;
+        stream.read_to_end(&mut data).internal_error()?;
+        let data = SharedData(Arc::new(data));
 
-        // Read image info and sequence
-        let (has_sequence, image_info) = {
-            let stream_reader = StreamReader::new(Cursor::new(&data), total_size.try_u64()?);
-            let context =
-                HeifContext::read_from_reader(Box::new(stream_reader)).expected_error()?;
+        let context = data.context()?;
 
+        // Read image info and sequence
+        let (has_sequence, mut image_info) = {
             let handle = context.primary_image_handle().expected_error()?;
 
             let format_name = match mime_type.as_str() {
@@ -248,30 +266,53 @@ impl LoaderImplementation for ImgDecoder {
                 _ => "HEIF (Unknown)",
             };
 
+            let mut warnings = Vec::new();
             let mut image_info = ImageDetails::new(handle.width(), handle.height());
-            image_info.metadata_exif = exif(&handle)
+            image_info.metadata_exif = exif(&handle, &mut warnings)
                 .map(B::try_from_vec)
                 .transpose()
                 .expected_error()?;
             image_info.info_format_name = Some(format_name.to_string());
+            image_info.info_container_format = Some(String::from("ISOBMFF"));
 
             // TODO: Later use libheif 1.16 to get info if there is a transformation
             image_info.transformation_ignore_exif = true;
+            image_info.has_transparency = (!handle.has_alpha_channel()).then_some(false);
+
+            image_info.n_auxiliary_images = Some(
+                handle
+                    .auxiliary_images(AuxiliaryImagesFilter::empty())
+                    .len()
+                    .try_u32()?,
+            );
+            image_info.image_items = Some(image_items(&context));
+            image_info.warnings = (!warnings.is_empty()).then_some(warnings);
 
             (context.has_sequence(), image_info)
         };
 
-        let mut decoder = Self::default();
+        let mut decoder = Self {
+            mime_type: mime_type.clone(),
+            ..Default::default()
+        };
+
+        let has_items = image_info.n_auxiliary_images.is_some_and(|x| x > 0)
+            || image_info.image_items.as_ref().is_some_and(|x| x.len() > 1);
+
         if has_sequence {
+            decoder.items = has_items.then_some(context);
+
             let (send, recv) = channel();
             let thread = std::thread::spawn(move || animated_worker(data, mime_type, send));
             *decoder.thread.lock().unwrap() = Some((thread, recv));
         } else {
-            let stream_reader = StreamReader::new(Cursor::new(data), total_size.try_u64()?);
-            let context =
-                HeifContext::read_from_reader(Box::new(stream_reader)).expected_error()?;
+            image_info.n_animation_frames = Some(1);
+
+            // The primary image context is consumed by decoding it
+            if has_items {
+                decoder.items = Some(data.context()?);
+            }
             decoder.decoder = Some(context);
-            decoder.mime_type = mime_type;
         }
 
         Ok((decoder, image_info))
@@ -281,6 +322,16 @@ impl LoaderImplementation for ImgDecoder {
         &mut self,
         frame_request: FrameRequest,
     ) -> Result<Frame<B>, ProcessError> {
+        if let Some(index) = frame_request.auxiliary_image {
+            let context = self.items()?;
+            return decode_auxiliary(context, index);
+        }
+
+        if let Some(id) = frame_request.image_item {
+            let context = self.items()?;
+            return decode_item(context, id, &self.mime_type);
+        }
+
         if let Some(decoder) = self.decoder.take() {
             // Static image
             decode(decoder, &self.mime_type)
@@ -304,13 +355,31 @@ impl LoaderImplementation for ImgDecoder {
     }
 }
 
+impl ImgDecoder {
+    fn items(&self) -> Result<&HeifContext<'static>, ProcessError> {
+        self.items
+            .as_ref()
+            .ok_or_else(|| ProcessError::expected(&"No auxiliary images or further items"))
+    }
+}
+
+/// Decodes the primary image
+///
+/// The primary image is always the default, even if the file contains more
+/// top-level images.
 fn decode<B: ByteData>(context: HeifContext, mime_type: &str) -> Result<Frame<B>, ProcessError> {
     let handle = context.primary_image_handle().expected_error()?;
+    decode_handle(&handle, mime_type)
+}
 
-    let rgb_chroma = rgb_chroma(&handle);
+fn decode_handle<B: ByteData>(
+    handle: &ImageHandle,
+    mime_type: &str,
+) -> Result<Frame<B>, ProcessError> {
+    let rgb_chroma = rgb_chroma(handle);
 
     let libheif = LibHeif::new();
-    let image_result = libheif.decode(&handle, ColorSpace::Rgb(rgb_chroma), None);
+    let image_result = libheif.decode(handle, ColorSpace::Rgb(rgb_chroma), None);
 
     let mut image = match image_result {
         Err(err) if matches!(err.sub_code, libheif_rs::HeifErrorSubCode::UnsupportedCodec) => {
@@ -328,7 +397,7 @@ fn decode<B: ByteData>(context: HeifContext, mime_type: &str) -> Result<Frame<B>
         None
     };
 
-    let memory_format = memory_format(&handle, rgb_chroma);
+    let memory_format = memory_format(handle, rgb_chroma);
 
     // Scale HDR pixels to 16bit (they are usually 10bit or 12bit)
     if is_rgb_chroma_hdr(rgb_chroma) {
@@ -359,7 +428,158 @@ fn decode<B: ByteData>(context: HeifContext, mime_type: &str) -> Result<Frame<B>
     Ok(frame)
 }
 
-fn exif(handle: &libheif_rs::ImageHandle) -> Option<Vec<u8>> {
+/// Decodes auxiliary images like depth maps or alpha planes
+///
+/// These are decoded independently from the primary image or the sequence.
+fn decode_auxiliary<B: ByteData>(
+    context: &HeifContext,
+    index: u32,
+) -> Result<Frame<B>, ProcessError> {
+    let handle = context.primary_image_handle().expected_error()?;
+
+    let auxiliary_handle = handle
+        .auxiliary_images(AuxiliaryImagesFilter::empty())
+        .into_iter()
+        .nth(index.try_usize()?)
+        .ok_or_else(|| ProcessError::expected(&format!("No auxiliary image {index}")))?;
+
+    decode_monochrome(&auxiliary_handle)
+}
+
+fn decode_monochrome<B: ByteData>(
+    auxiliary_handle: &ImageHandle,
+) -> Result<Frame<B>, ProcessError> {
+    let image = LibHeif::new()
+        .decode(auxiliary_handle, ColorSpace::Monochrome, None)
+        .expected_error()?;
+
+    let plane = image.planes().y.expected_error()?;
+
+    let (memory_format, texture) = if plane.storage_bits_per_pixel > 8 {
+        // Scale pixels to 16bit like for HDR images
+        let mut data = plane.data.to_vec();
+        for pixel in data.chunks_exact_mut(2) {
+            let value = u16::from_ne_bytes([pixel[0], pixel[1]]) << (16 - plane.bits_per_pixel);
+            pixel.copy_from_slice(&value.to_ne_bytes());
+        }
+        (MemoryFormat::G16, B::try_from_vec(data).expected_error()?)
+    } else {
+        (
+            MemoryFormat::G8,
+            B::try_from_slice(plane.data).expected_error()?,
+        )
+    };
+
+    let mut frame = Frame::new(plane.width, plane.height, memory_format, texture)?;
+    frame.stride = plane.stride.try_u32()?;
+    frame.details.auxiliary_type = auxiliary_handle.auxiliary_type().ok();
+    if plane.bits_per_pixel > 8 {
+        frame.details.info_bit_depth = Some(plane.bits_per_pixel);
+    }
+
+    Ok(frame)
+}
+
+/// Lists all top-level images with their thumbnails and auxiliary images
+///
+/// Returns the item IDs with the roles as defined for
+/// [`ImageDetails::image_items`].
+fn image_items(context: &HeifContext) -> Vec<(u32, String)> {
+    let primary_id = context.primary_image_handle().ok().map(|x| x.item_id());
+
+    let mut top_level_ids = vec![0; context.number_of_top_level_images()];
+    let n_ids = context.top_level_image_ids(&mut top_level_ids);
+    top_level_ids.truncate(n_ids);
+
+    let mut items = Vec::new();
+
+    for id in top_level_ids {
+        let Ok(handle) = context.image_handle(id) else {
+            continue;
+        };
+
+        let role = if Some(id) == primary_id {
+            "primary"
+        } else {
+            "image"
+        };
+        items.push((id, String::from(role)));
+
+        items.extend(
+            thumbnail_ids(&handle)
+                .into_iter()
+                .map(|id| (id, String::from("thumbnail"))),
+        );
+
+        for auxiliary_handle in handle.auxiliary_images(AuxiliaryImagesFilter::empty()) {
+            items.push((
+                auxiliary_handle.item_id(),
+                String::from(auxiliary_role(&auxiliary_handle)),
+            ));
+        }
+    }
+
+    items
+}
+
+fn thumbnail_ids(handle: &ImageHandle) -> Vec<u32> {
+    let mut ids = vec![0; handle.number_of_thumbnails()];
+    let n_ids = handle.thumbnail_ids(&mut ids);
+    ids.truncate(n_ids);
+    ids
+}
+
+fn auxiliary_role(handle: &ImageHandle) -> &'static str {
+    let auxiliary_type = handle.auxiliary_type().unwrap_or_default();
+
+    if auxiliary_type.contains("alpha") || auxiliary_type == "urn:mpeg:hevc:2015:auxid:1" {
+        "alpha"
+    } else if auxiliary_type.contains("depth") || auxiliary_type == "urn:mpeg:hevc:2015:auxid:2" {
+        "depth"
+    } else {
+        "auxiliary"
+    }
+}
+
+/// Decodes the item with the given ID
+///
+/// Thumbnails and further top-level images are decoded like the primary
+/// image, auxiliary images like depth maps as grayscale.
+fn decode_item<B: ByteData>(
+    context: &HeifContext,
+    id: u32,
+    mime_type: &str,
+) -> Result<Frame<B>, ProcessError> {
+    // Top-level images can be looked up directly
+    if let Ok(handle) = context.image_handle(id) {
+        return decode_handle(&handle, mime_type);
+    }
+
+    let mut top_level_ids = vec![0; context.number_of_top_level_images()];
+    let n_ids = context.top_level_image_ids(&mut top_level_ids);
+    top_level_ids.truncate(n_ids);
+
+    for top_level_id in top_level_ids {
+        let handle = context.image_handle(top_level_id).expected_error()?;
+
+        if thumbnail_ids(&handle).contains(&id) {
+            let thumbnail = handle.thumbnail(id).expected_error()?;
+            return decode_handle(&thumbnail, mime_type);
+        }
+
+        if let Some(auxiliary_handle) = handle
+            .auxiliary_images(AuxiliaryImagesFilter::empty())
+            .into_iter()
+            .find(|x| x.item_id() == id)
+        {
+            return decode_monochrome(&auxiliary_handle);
+        }
+    }
+
+    Err(ProcessError::expected(&format!("No image item {id}")))
+}
+
+fn exif(handle: &libheif_rs::ImageHandle, warnings: &mut Vec<String>) -> Option<Vec<u8>> {
     let mut meta_ids = vec![0];
     handle.metadata_block_ids(&mut meta_ids, b"Exif");
 
@@ -374,10 +594,10 @@ fn exif(handle: &libheif_rs::ImageHandle) -> Option<Vec<u8>> {
                         exif_bytes.drain(0..skip + 4);
                         return Some(exif_bytes);
                     } else {
-                        eprintln!("EXIF data has far too few bytes");
+                        warnings.push(String::from("Exif data ignored: Far too few bytes"));
                     }
                 } else {
-                    eprintln!("EXIF data has far too few bytes");
+                    warnings.push(String::from("Exif data ignored: Far too few bytes"));
                 }
             }
             Err(_) => return None,
@@ -5,7 +5,13 @@ use glycin_utils::*;
 
 use crate::{FrameSender, ImageRsDecoder, ImageRsFormat, Reader};
 
-pub fn worker(format: ImageRsFormat<Reader>, data: Reader, mime_type: String, send: FrameSender) {
+pub fn worker(
+    format: ImageRsFormat<Reader>,
+    data: Reader,
+    mime_type: String,
+    allow_partial: bool,
+    send: FrameSender,
+) {
     let mut format = Some(format);
 
     std::thread::park();
@@ -61,9 +67,35 @@ pub fn worker(format: ImageRsFormat<Reader>, data: Reader, mime_type: String, se
         };
 
         if is_animated {
-            for frame in first_frames.into_iter().chain(frames).enumerate() {
-                let decoded_frame = animated_get_frame(frame, None, is_animated);
+            let mut frames = first_frames
+                .into_iter()
+                .chain(frames)
+                .enumerate()
+                .peekable();
+            while let Some(frame) = frames.next() {
+                let mut decoded_frame = animated_get_frame(frame, None, is_animated);
+
+                // End the animation before the first incomplete frame
+                let truncated = allow_partial && matches!(frames.peek(), Some((_, Err(_))));
+                if truncated && let Ok(decoded_frame) = &mut decoded_frame {
+                    log::debug!(
+                        "animated: Animation truncated after frame {}",
+                        decoded_frame.details.n_frame.unwrap_or_default()
+                    );
+                    decoded_frame
+                        .details
+                        .warnings
+                        .get_or_insert_default()
+                        .push(String::from(
+                            "Animation is truncated. Incomplete frames are skipped.",
+                        ));
+                }
+
                 send.send(decoded_frame.map(|x| (x, looped))).unwrap();
+
+                if truncated {
+                    break;
+                }
             }
         } else {
             // Only use FrameDetails for still images because they might not make too much
@@ -93,15 +125,7 @@ pub fn animated_get_frame<B: ByteData>(
 
     let (delay_num, delay_den) = frame.delay().numer_denom_ms();
 
-    let delay = if !is_animated {
-        None
-    } else if delay_num == 0 || delay_den == 0 {
-        // Other decoders default to this value as well
-        Some(std::time::Duration::from_millis(100))
-    } else {
-        let micros = f64::round(delay_num as f64 * 1000. / delay_den as f64) as u64;
-        Some(std::time::Duration::from_micros(micros))
-    };
+    let delay = is_animated.then(|| frame_delay(delay_num, delay_den));
 
     let buffer = frame.into_buffer();
 
@@ -128,3 +152,16 @@ pub fn animated_get_frame<B: ByteData>(
 
     Ok(out_frame)
 }
+
+/// Delay of `numer / denom` milliseconds
+///
+/// Delays of zero are replaced by 100 ms.
+pub fn frame_delay(numer: u32, denom: u32) -> std::time::Duration {
+    if numer == 0 || denom == 0 {
+        // Other decoders default to this value as well
+        std::time::Duration::from_millis(100)
+    } else {
+        let micros = f64::round(numer as f64 * 1000. / denom as f64) as u64;
+        std::time::Duration::from_micros(micros)
+    }
+}
@@ -0,0 +1,238 @@
+//! Decoding of CMYK JPEGs
+//!
+//! The JPEG format doesn't define how CMYK values are stored. Adobe tools
+//! store them inverted, such that the maximum value means no ink, and mark
+//! such files with an APP14 segment starting with `Adobe`. Files without the
+//! marker are assumed to store regular values, where `0` means no ink. The
+//! detection can be overridden via
+//! [`InitializationDetails::assume_adobe_cmyk`].
+
+use std::io::Cursor;
+
+use glycin_utils::*;
+use zune_jpeg::zune_core::colorspace::ColorSpace;
+use zune_jpeg::zune_core::options::DecoderOptions;
+
+const APP14: u8 = 0xEE;
+const SOS: u8 = 0xDA;
+
+/// Whether the JPEG stores CMYK or YCCK data
+pub fn is_cmyk(data: &[u8]) -> bool {
+    let mut decoder = zune_jpeg::JpegDecoder::new(Cursor::new(data));
+    if decoder.decode_headers().is_err() {
+        return false;
+    }
+
+    matches!(
+        decoder.input_colorspace(),
+        Some(ColorSpace::CMYK | ColorSpace::YCCK)
+    )
+}
+
+/// Whether the CMYK values have to be inverted
+pub fn is_inverted(data: &[u8], assume_adobe_cmyk: Option<bool>) -> bool {
+    assume_adobe_cmyk.unwrap_or_else(|| has_adobe_marker(data))
+}
+
+/// Decodes the JPEG as CMYK with its ICC profile
+///
+/// If the client doesn't accept CMYK frames, the data are converted to RGB
+/// without color management instead.
+pub fn frame<B: ByteData>(
+    data: &[u8],
+    inverted: bool,
+    accepts_cmyk: bool,
+) -> Result<Frame<B>, ProcessError> {
+    let decoder_options = DecoderOptions::new_fast()
+        .jpeg_set_out_colorspace(ColorSpace::CMYK)
+        .set_max_height(u32::MAX as usize)
+        .set_max_width(u32::MAX as usize);
+    let mut decoder = zune_jpeg::JpegDecoder::new_with_options(Cursor::new(data), decoder_options);
+
+    let mut pixels = decoder.decode().expected_error()?;
+    let info = decoder.info().expected_error()?;
+
+    if inverted {
+        for value in &mut pixels {
+            *value = u8::MAX - *value;
+        }
+    }
+
+    if !accepts_cmyk {
+        let mut rgb = vec![0; pixels.len() / 4 * 3];
+        for (cmyk, rgb) in pixels.chunks_exact(4).zip(rgb.chunks_exact_mut(3)) {
+            MemoryFormat::transform(MemoryFormat::C8m8y8k8, cmyk, MemoryFormat::R8g8b8, rgb);
+        }
+
+        let texture = B::try_from_vec(rgb).expected_error()?;
+        return Ok(Frame::new(
+            info.width as u32,
+            info.height as u32,
+            MemoryFormat::R8g8b8,
+            texture,
+        )?);
+    }
+
+    let icc_profile = decoder.icc_profile();
+    let texture = B::try_from_vec(pixels).expected_error()?;
+
+    let mut frame = Frame::new(
+        info.width as u32,
+        info.height as u32,
+        MemoryFormat::C8m8y8k8,
+        texture,
+    )?;
+
+    frame.details.color_icc_profile = icc_profile
+        .map(|x| B::try_from_vec(x))
+        .transpose()
+        .expected_error()?;
+
+    Ok(frame)
+}
+
+/// Whether the JPEG contains an Adobe APP14 segment before the image data
+fn has_adobe_marker(data: &[u8]) -> bool {
+    segments(data).any(|(marker, payload)| marker == APP14 && payload.starts_with(b"Adobe"))
+}
+
+/// Iterates over the marker segments before the first scan
+fn segments(data: &[u8]) -> impl Iterator<Item = (u8, &[u8])> {
+    // Skip SOI marker
+    let mut pos = if data.starts_with(&[0xFF, 0xD8]) {
+        2
+    } else {
+        data.len()
+    };
+
+    std::iter::from_fn(move || {
+        let Some(&[0xFF, marker, len_hi, len_lo]) = data.get(pos..pos + 4) else {
+            return None;
+        };
+
+        if marker == SOS {
+            return None;
+        }
+
+        // The length includes the two length bytes
+        let len = usize::from(u16::from_be_bytes([len_hi, len_lo]));
+        let payload = data.get(pos + 4..pos + 2 + len)?;
+        pos += 2 + len;
+
+        Some((marker, payload))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADOBE_SEGMENT: &[u8] = &[
+        0xFF, APP14, 0, 14, b'A', b'd', b'o', b'b', b'e', 0, 100, 0, 0, 0, 0, 0,
+    ];
+
+    /// Uniform 16x16 CMYK JPEG without Adobe marker
+    fn cmyk_jpeg() -> Vec<u8> {
+        let pixels = [20, 80, 160, 240].repeat(16 * 16);
+
+        let mut data = Vec::new();
+        jpeg_encoder::Encoder::new(&mut data, 100)
+            .encode(&pixels, 16, 16, jpeg_encoder::ColorType::Cmyk)
+            .unwrap();
+
+        // Normalize in case the encoder adds an Adobe marker
+        let mut jpeg = data[..2].to_vec();
+        let mut scan = 2;
+        for (marker, payload) in segments(&data) {
+            scan += payload.len() + 4;
+            if marker != APP14 {
+                jpeg.extend_from_slice(&data[scan - payload.len() - 4..scan]);
+            }
+        }
+        jpeg.extend_from_slice(&data[scan..]);
+
+        jpeg
+    }
+
+    fn with_adobe_marker(data: &[u8]) -> Vec<u8> {
+        [&data[..2], ADOBE_SEGMENT, &data[2..]].concat()
+    }
+
+    fn first_pixel(data: &[u8], inverted: bool) -> [u8; 4] {
+        let frame = frame::<LocalMemory>(data, inverted, true).unwrap();
+        assert_eq!(frame.memory_format, MemoryFormat::C8m8y8k8);
+        assert_eq!((frame.width, frame.height), (16, 16));
+        frame.texture[..4].try_into().unwrap()
+    }
+
+    #[test]
+    fn detect_adobe_marker() {
+        let regular = cmyk_jpeg();
+        let inverted = with_adobe_marker(&regular);
+
+        assert!(is_cmyk(&regular));
+        assert!(is_cmyk(&inverted));
+
+        assert!(!is_inverted(&regular, None));
+        assert!(is_inverted(&inverted, None));
+
+        // Overrides ignore the marker
+        assert!(is_inverted(&regular, Some(true)));
+        assert!(!is_inverted(&inverted, Some(false)));
+    }
+
+    #[test]
+    fn inverted_and_regular() {
+        let regular = cmyk_jpeg();
+        let inverted = with_adobe_marker(&regular);
+
+        let regular_pixel = first_pixel(&regular, is_inverted(&regular, None));
+        let inverted_pixel = first_pixel(&inverted, is_inverted(&inverted, None));
+
+        for (regular, inverted) in regular_pixel.into_iter().zip(inverted_pixel) {
+            assert_eq!(regular, u8::MAX - inverted);
+        }
+
+        assert_eq!(
+            first_pixel(&regular, is_inverted(&regular, Some(true))),
+            inverted_pixel
+        );
+    }
+
+    #[test]
+    fn icc_profile() {
+        let pixels = [20, 80, 160, 240].repeat(16 * 16);
+        let icc_profile = b"not a real profile".to_vec();
+
+        let mut data = Vec::new();
+        let mut encoder = jpeg_encoder::Encoder::new(&mut data, 100);
+        encoder.add_icc_profile(&icc_profile).unwrap();
+        encoder
+            .encode(&pixels, 16, 16, jpeg_encoder::ColorType::Cmyk)
+            .unwrap();
+
+        let frame = frame::<LocalMemory>(&data, false, true).unwrap();
+        assert_eq!(
+            frame.details.color_icc_profile.as_deref(),
+            Some(icc_profile.as_slice())
+        );
+    }
+
+    #[test]
+    fn convert_without_cmyk_support() {
+        let regular = cmyk_jpeg();
+
+        let frame = frame::<LocalMemory>(&regular, false, false).unwrap();
+        assert_eq!(frame.memory_format, MemoryFormat::R8g8b8);
+        assert!(frame.details.color_icc_profile.is_none());
+
+        let mut expected = [0; 3];
+        MemoryFormat::transform(
+            MemoryFormat::C8m8y8k8,
+            &first_pixel(&regular, false),
+            MemoryFormat::R8g8b8,
+            &mut expected,
+        );
+        assert_eq!(frame.texture[..3], expected);
+    }
+}
@@ -1,4 +1,7 @@
+mod animation;
+mod exif_thumbnail;
 mod jpeg;
+mod jpeg_huffman;
 mod png;
 mod tiff;
 
@@ -10,16 +13,32 @@ use image::{ExtendedColorType, ImageFormat};
 pub enum ImgEditor {
     Png(png::EditorPng),
     Jpeg(jpeg::EditJpeg),
+    Animation(animation::EditorAnimation),
 }
 
 impl EditorImplementation for ImgEditor {
     fn edit<S: Read>(
-        stream: S,
+        mut stream: S,
         mime_type: String,
         _details: InitializationDetails,
     ) -> Result<Self, ProcessError> {
         Ok(match mime_type.as_str() {
-            "image/png" => Self::Png(png::load(stream)?),
+            "image/png" | "image/apng" => {
+                let mut data = Vec::new();
+                stream.read_to_end(&mut data).internal_error()?;
+
+                if animation::is_apng(&data) {
+                    Self::Animation(animation::load_apng(data)?)
+                } else {
+                    Self::Png(png::load(Cursor::new(data))?)
+                }
+            }
+            "image/webp" => {
+                let mut data = Vec::new();
+                stream.read_to_end(&mut data).internal_error()?;
+
+                Self::Animation(animation::load_webp(data)?)
+            }
             "image/jpeg" => Self::Jpeg(jpeg::load(stream)?),
             mime_type => return Err(ProcessError::UnsupportedImageFormat(mime_type.to_string())),
         })
@@ -29,11 +48,26 @@ impl EditorImplementation for ImgEditor {
         &self,
         operations: Operations,
     ) -> Result<SparseEditorOutput<B>, glycin_utils::ProcessError> {
+        self.apply_sparse_with_options(operations, EncodingOptions::default())
+    }
+
+    fn apply_sparse_with_options<B: ByteData>(
+        &self,
+        operations: Operations,
+        encoding_options: EncodingOptions,
+    ) -> Result<SparseEditorOutput<B>, ProcessError> {
         match self {
             Self::Jpeg(jpeg) => Ok(jpeg::apply_sparse(jpeg, operations)?),
-            _ => Ok(SparseEditorOutput::from(Self::apply_complete(
-                self, operations,
-            )?)),
+            _ => Ok(SparseEditorOutput::from(
+                self.apply_complete_with_options(operations, encoding_options)?,
+            )),
+        }
+    }
+
+    fn sparse_operations(&self, operations: Operations) -> Result<SparseOperations, ProcessError> {
+        match self {
+            Self::Jpeg(jpeg) => jpeg::sparse_operations(jpeg, operations),
+            _ => Ok(SparseOperations::default()),
         }
     }
 
@@ -42,11 +76,29 @@ impl EditorImplementation for ImgEditor {
         operations: Operations,
     ) -> Result<CompleteEditorOutput<B>, ProcessError> {
         match self {
-            Self::Png(png) => png::apply(png, operations),
+            Self::Png(png) => png::apply(png, operations, None),
             Self::Jpeg(jpeg) => jpeg::apply_complete(jpeg, operations),
+            Self::Animation(animation) => animation::apply(animation, operations),
         }
     }
 
+    fn apply_complete_with_options<B: ByteData>(
+        &self,
+        operations: Operations,
+        encoding_options: EncodingOptions,
+    ) -> Result<CompleteEditorOutput<B>, ProcessError> {
+        let mut output = match self {
+            Self::Png(png) => png::apply(png, operations, encoding_options.bit_depth)?,
+            _ => Self::apply_complete(self, operations)?,
+        };
+
+        if let (Self::Jpeg(_), Some(max_edge)) = (self, encoding_options.embed_thumbnail) {
+            exif_thumbnail::embed(&mut output, max_edge)?;
+        }
+
+        Ok(output)
+    }
+
     fn create<B: ByteData>(
         mime_type: String,
         mut new_image: NewImage<B>,
@@ -55,11 +107,22 @@ impl EditorImplementation for ImgEditor {
         if new_image.frames.is_empty() {
             return Err(ProcessError::expected(&"No frames passed."));
         }
+
+        if new_image.frames.len() > 1 {
+            let loop_count = encoding_options.loop_count.unwrap_or_default();
+            let data = animation::create(&mime_type, new_image, loop_count)?;
+            return Ok(EncodedImage::new(B::try_from_vec(data).expected_error()?));
+        }
+
         let frame = new_image.frames.remove(0);
 
         let image_format = image_format(&mime_type)?;
 
-        let frame = frame.into_fungible();
+        let mut frame = frame.into_fungible();
+
+        if let (ImageFormat::Png, Some(bit_depth)) = (image_format, encoding_options.bit_depth) {
+            frame = png::change_bit_depth(frame, bit_depth)?;
+        }
 
         let memory_format = image_memory_format(frame.memory_format)?;
 
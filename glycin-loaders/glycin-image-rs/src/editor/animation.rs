@@ -0,0 +1,529 @@
+//! Editing of animated PNG and WebP images and creation of animations
+//!
+//! All frames are decoded to full canvases, edited, and re-encoded. Frame
+//! delays and the loop count are carried over. Since every frame covers the
+//! complete canvas, the original disposal and blending are already applied to
+//! the pixel data. Metadata like Exif are not carried over.
+//!
+//! Still WebP images are handled as animation with a single frame and are
+//! re-encoded as still, lossless WebP.
+//!
+//! New animations can be created as APNG, WebP, and GIF. Their frames also
+//! cover the complete canvas and use the disposal "none".
+
+use std::io::Cursor;
+
+use glycin_utils::safe_math::*;
+use glycin_utils::*;
+use image::{AnimationDecoder, ExtendedColorType, ImageEncoder};
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\x0D\x0A\x1A\x0A";
+
+#[derive(Debug, Clone, Copy)]
+enum Format {
+    Png,
+    WebP,
+    StillWebP,
+    Gif,
+}
+
+pub struct EditorAnimation {
+    format: Format,
+    width: u32,
+    height: u32,
+    /// Number of times the animation is played, `0` is infinite
+    loop_count: u32,
+    frames: Vec<AnimationFrame>,
+}
+
+#[derive(Clone)]
+struct AnimationFrame {
+    delay_ms: u32,
+    /// Tightly packed RGBA data
+    texture: Vec<u8>,
+}
+
+/// Whether the PNG contains an animation control chunk
+pub fn is_apng(data: &[u8]) -> bool {
+    png_chunk(data, b"acTL").is_some()
+}
+
+pub fn load_apng(data: Vec<u8>) -> Result<EditorAnimation, ProcessError> {
+    let loop_count = png_chunk(&data, b"acTL")
+        .and_then(|x| x.get(4..8))
+        .map(|x| u32::from_be_bytes([x[0], x[1], x[2], x[3]]))
+        .unwrap_or_default();
+
+    let decoder = image::codecs::png::PngDecoder::new(Cursor::new(&data)).expected_error()?;
+    // Frames are only available as RGBA with 8 bit per channel
+    let color_type = image::ImageDecoder::color_type(&decoder);
+    if color_type.bytes_per_pixel() > color_type.channel_count() {
+        return Err(ProcessError::UnsupportedImageFormat(String::from(
+            "Editing APNG images with 16 bit per channel is not supported",
+        )));
+    }
+    let (width, height) = image::ImageDecoder::dimensions(&decoder);
+    let frames = decode_frames(decoder.apng().expected_error()?)?;
+
+    Ok(EditorAnimation {
+        format: Format::Png,
+        width,
+        height,
+        loop_count,
+        frames,
+    })
+}
+
+/// Loads an animated or still WebP
+pub fn load_webp(data: Vec<u8>) -> Result<EditorAnimation, ProcessError> {
+    let Some(anim) = riff_chunks(&data).find(|(id, _)| id == b"ANIM") else {
+        return load_still_webp(&data);
+    };
+
+    let loop_count = anim
+        .1
+        .get(4..6)
+        .map(|x| u32::from(u16::from_le_bytes([x[0], x[1]])))
+        .unwrap_or_default();
+
+    let mut decoder = image::codecs::webp::WebPDecoder::new(Cursor::new(&data)).expected_error()?;
+    // Use transparent background instead of suggested background color
+    let _result = decoder.set_background_color(image::Rgba::from([0, 0, 0, 0]));
+    let (width, height) = image::ImageDecoder::dimensions(&decoder);
+    let frames = decode_frames(decoder)?;
+
+    Ok(EditorAnimation {
+        format: Format::WebP,
+        width,
+        height,
+        loop_count,
+        frames,
+    })
+}
+
+fn load_still_webp(data: &[u8]) -> Result<EditorAnimation, ProcessError> {
+    let decoder = image::codecs::webp::WebPDecoder::new(Cursor::new(data)).expected_error()?;
+    let image = image::DynamicImage::from_decoder(decoder)
+        .expected_error()?
+        .into_rgba8();
+
+    Ok(EditorAnimation {
+        format: Format::StillWebP,
+        width: image.width(),
+        height: image.height(),
+        loop_count: 0,
+        frames: vec![AnimationFrame {
+            delay_ms: 0,
+            texture: image.into_raw(),
+        }],
+    })
+}
+
+pub fn apply<B: ByteData>(
+    animation: &EditorAnimation,
+    operations: Operations,
+) -> Result<CompleteEditorOutput<B>, ProcessError> {
+    let mut edited = EditorAnimation {
+        format: animation.format,
+        width: animation.width,
+        height: animation.height,
+        loop_count: animation.loop_count,
+        frames: Vec::with_capacity(animation.frames.len()),
+    };
+
+    for (n_frame, frame) in animation.frames.iter().enumerate() {
+        let editing_frame = editing::EditingFrame {
+            width: animation.width,
+            height: animation.height,
+            stride: animation.width.smul(4)?,
+            memory_format: MemoryFormat::R8g8b8a8.into(),
+            texture: FungibleMemory::LocalMemory(frame.texture.clone()),
+        };
+
+        let editing_frame =
+            editing::apply_operations(editing_frame, &operations).expected_error()?;
+
+        if n_frame == 0 {
+            edited.width = editing_frame.width;
+            edited.height = editing_frame.height;
+        } else if (editing_frame.width, editing_frame.height) != (edited.width, edited.height) {
+            return Err(ProcessError::expected(
+                &"Frames have different dimensions after editing",
+            ));
+        }
+
+        edited.frames.push(AnimationFrame {
+            delay_ms: frame.delay_ms,
+            texture: tightly_packed(&editing_frame)?,
+        });
+    }
+
+    Ok(CompleteEditorOutput::new(
+        B::try_from_vec(encode(&edited)?).expected_error()?,
+    ))
+}
+
+/// Encodes the frames of `new_image` as animation
+pub fn create<B: ByteData>(
+    mime_type: &str,
+    new_image: NewImage<B>,
+    loop_count: u32,
+) -> Result<Vec<u8>, ProcessError> {
+    let format = match mime_type {
+        "image/png" | "image/apng" => Format::Png,
+        "image/webp" => Format::WebP,
+        "image/gif" => Format::Gif,
+        mime_type => return Err(ProcessError::UnsupportedImageFormat(mime_type.to_string())),
+    };
+
+    let mut animation = EditorAnimation {
+        format,
+        width: 0,
+        height: 0,
+        loop_count,
+        frames: Vec::with_capacity(new_image.frames.len()),
+    };
+
+    for (n_frame, frame) in new_image.frames.into_iter().enumerate() {
+        let mut frame = frame.into_fungible();
+        editing::change_memory_format(&mut frame, MemoryFormat::R8g8b8a8).expected_error()?;
+
+        if n_frame == 0 {
+            animation.width = frame.width;
+            animation.height = frame.height;
+        } else if (frame.width, frame.height) != (animation.width, animation.height) {
+            return Err(ProcessError::expected(
+                &"Frames of an animation must have the same dimensions",
+            ));
+        }
+
+        let delay_ms = frame
+            .delay
+            .map(|x| x.as_millis().try_into().unwrap_or(u32::MAX))
+            .unwrap_or_default();
+
+        let editing_frame = editing::EditingFrame {
+            width: frame.width,
+            height: frame.height,
+            stride: frame.stride,
+            memory_format: frame.memory_format.into(),
+            texture: frame.texture,
+        };
+
+        animation.frames.push(AnimationFrame {
+            delay_ms,
+            texture: tightly_packed(&editing_frame)?,
+        });
+    }
+
+    encode(&animation)
+}
+
+fn encode(animation: &EditorAnimation) -> Result<Vec<u8>, ProcessError> {
+    match animation.format {
+        Format::Png => encode_apng(animation),
+        Format::WebP => encode_webp(animation),
+        Format::StillWebP => encode_still_webp(animation),
+        Format::Gif => encode_gif(animation),
+    }
+}
+
+fn decode_frames<'a>(
+    decoder: impl AnimationDecoder<'a>,
+) -> Result<Vec<AnimationFrame>, ProcessError> {
+    decoder
+        .into_frames()
+        .map(|frame| {
+            let frame = frame.expected_error()?;
+            let (num, den) = frame.delay().numer_denom_ms();
+            let delay_ms = if den == 0 {
+                0
+            } else {
+                f64::round(num as f64 / den as f64) as u32
+            };
+
+            Ok(AnimationFrame {
+                delay_ms,
+                texture: frame.into_buffer().into_raw(),
+            })
+        })
+        .collect()
+}
+
+fn tightly_packed(frame: &editing::EditingFrame<FungibleMemory>) -> Result<Vec<u8>, ProcessError> {
+    let row_len = frame.width.smul(4)?.try_usize()?;
+    let stride = frame.stride.try_usize()?;
+
+    let mut texture = Vec::with_capacity(row_len.smul(frame.height.try_usize()?)?);
+    for row in 0..frame.height.try_usize()? {
+        let start = row.smul(stride)?;
+        texture.extend_from_slice(
+            frame
+                .texture
+                .get(start..start.sadd(row_len)?)
+                .expected_error()?,
+        );
+    }
+
+    Ok(texture)
+}
+
+fn encode_apng(animation: &EditorAnimation) -> Result<Vec<u8>, ProcessError> {
+    let mut out = Vec::new();
+
+    let mut encoder = png::Encoder::new(&mut out, animation.width, animation.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .set_animated(animation.frames.len().try_u32()?, animation.loop_count)
+        .expected_error()?;
+
+    let mut writer = encoder.write_header().expected_error()?;
+
+    for frame in &animation.frames {
+        writer
+            .set_frame_delay(u16::try_from(frame.delay_ms).unwrap_or(u16::MAX), 1000)
+            .expected_error()?;
+        writer
+            .set_dispose_op(png::DisposeOp::None)
+            .expected_error()?;
+        writer.set_blend_op(png::BlendOp::Source).expected_error()?;
+        writer.write_image_data(&frame.texture).expected_error()?;
+    }
+
+    writer.finish().expected_error()?;
+
+    Ok(out)
+}
+
+fn encode_webp(animation: &EditorAnimation) -> Result<Vec<u8>, ProcessError> {
+    let mut chunks = Vec::new();
+
+    // Animation and alpha flags, followed by the canvas size
+    let mut vp8x = vec![0x12, 0, 0, 0];
+    vp8x.extend_from_slice(&u24(animation.width.checked_sub(1).expected_error()?)?);
+    vp8x.extend_from_slice(&u24(animation.height.checked_sub(1).expected_error()?)?);
+    push_riff_chunk(&mut chunks, b"VP8X", &vp8x)?;
+
+    // Transparent background color, followed by the loop count
+    let mut anim = vec![0, 0, 0, 0];
+    anim.extend_from_slice(
+        &u16::try_from(animation.loop_count)
+            .unwrap_or(u16::MAX)
+            .to_le_bytes(),
+    );
+    push_riff_chunk(&mut chunks, b"ANIM", &anim)?;
+
+    for frame in &animation.frames {
+        let mut still = Vec::new();
+        image::codecs::webp::WebPEncoder::new_lossless(&mut still)
+            .write_image(
+                &frame.texture,
+                animation.width,
+                animation.height,
+                ExtendedColorType::Rgba8,
+            )
+            .expected_error()?;
+
+        // Frame position, size, and duration
+        let mut anmf = vec![0; 6];
+        anmf.extend_from_slice(&u24(animation.width.checked_sub(1).expected_error()?)?);
+        anmf.extend_from_slice(&u24(animation.height.checked_sub(1).expected_error()?)?);
+        anmf.extend_from_slice(&u24(frame.delay_ms.min(0xFF_FFFF))?);
+        // Don't blend with the previous frame and don't dispose
+        anmf.push(0b10);
+
+        for (id, data) in riff_chunks(&still) {
+            if matches!(&id, b"ALPH" | b"VP8 " | b"VP8L") {
+                push_riff_chunk(&mut anmf, &id, data)?;
+            }
+        }
+
+        push_riff_chunk(&mut chunks, b"ANMF", &anmf)?;
+    }
+
+    let mut out = b"RIFF".to_vec();
+    out.extend_from_slice(&chunks.len().sadd(4)?.try_u32()?.to_le_bytes());
+    out.extend_from_slice(b"WEBP");
+    out.extend_from_slice(&chunks);
+
+    Ok(out)
+}
+
+fn encode_still_webp(animation: &EditorAnimation) -> Result<Vec<u8>, ProcessError> {
+    let [frame] = animation.frames.as_slice() else {
+        return Err(ProcessError::expected(
+            &"Still image must have exactly one frame",
+        ));
+    };
+
+    let mut out = Vec::new();
+    image::codecs::webp::WebPEncoder::new_lossless(&mut out)
+        .write_image(
+            &frame.texture,
+            animation.width,
+            animation.height,
+            ExtendedColorType::Rgba8,
+        )
+        .expected_error()?;
+
+    Ok(out)
+}
+
+fn encode_gif(animation: &EditorAnimation) -> Result<Vec<u8>, ProcessError> {
+    let mut out = Vec::new();
+
+    {
+        let mut encoder = image::codecs::gif::GifEncoder::new(&mut out);
+
+        // GIF counts the repetitions after the first play and plays only once
+        // without the loop extension
+        let repeat = match animation.loop_count {
+            0 => Some(image::codecs::gif::Repeat::Infinite),
+            1 => None,
+            n => Some(image::codecs::gif::Repeat::Finite(
+                u16::try_from(n - 1).unwrap_or(u16::MAX),
+            )),
+        };
+        if let Some(repeat) = repeat {
+            encoder.set_repeat(repeat).expected_error()?;
+        }
+
+        for frame in &animation.frames {
+            let buffer = image::RgbaImage::from_raw(
+                animation.width,
+                animation.height,
+                frame.texture.clone(),
+            )
+            .expected_error()?;
+            let delay = image::Delay::from_numer_denom_ms(frame.delay_ms, 1);
+
+            encoder
+                .encode_frame(image::Frame::from_parts(buffer, 0, 0, delay))
+                .expected_error()?;
+        }
+    }
+
+    Ok(out)
+}
+
+/// Data of the first PNG chunk with the given type
+fn png_chunk<'a>(data: &'a [u8], chunk_type: &[u8; 4]) -> Option<&'a [u8]> {
+    let mut pos = PNG_SIGNATURE.len();
+
+    if !data.starts_with(PNG_SIGNATURE) {
+        return None;
+    }
+
+    loop {
+        let len = u32::from_be_bytes(data.get(pos..pos + 4)?.try_into().ok()?) as usize;
+        let start = pos + 8;
+        let end = start.checked_add(len)?;
+
+        match data.get(pos + 4..start)? {
+            x if x == chunk_type => return data.get(start..end),
+            b"IDAT" | b"IEND" => return None,
+            _ => {}
+        }
+
+        // Skip data and CRC
+        pos = end.checked_add(4)?;
+    }
+}
+
+/// Iterates over the chunks of a RIFF based WebP
+fn riff_chunks(data: &[u8]) -> impl Iterator<Item = ([u8; 4], &[u8])> {
+    let mut pos = 12;
+
+    std::iter::from_fn(move || {
+        let id: [u8; 4] = data.get(pos..pos + 4)?.try_into().ok()?;
+        let len = u32::from_le_bytes(data.get(pos + 4..pos + 8)?.try_into().ok()?) as usize;
+        let start = pos + 8;
+        let chunk = data.get(start..start.checked_add(len)?)?;
+
+        // Chunks are padded to an even size
+        pos = start + len + len % 2;
+
+        Some((id, chunk))
+    })
+}
+
+fn push_riff_chunk(out: &mut Vec<u8>, id: &[u8; 4], data: &[u8]) -> Result<(), ProcessError> {
+    out.extend_from_slice(id);
+    out.extend_from_slice(&data.len().try_u32()?.to_le_bytes());
+    out.extend_from_slice(data);
+    if data.len() % 2 == 1 {
+        out.push(0);
+    }
+
+    Ok(())
+}
+
+fn u24(x: u32) -> Result<[u8; 3], ProcessError> {
+    if x > 0xFF_FFFF {
+        return Err(ProcessError::expected(&"Value does not fit into 24 bit"));
+    }
+
+    let [a, b, c, _] = x.to_le_bytes();
+    Ok([a, b, c])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn riff_chunks_padding() {
+        let mut data = b"RIFF\0\0\0\0WEBP".to_vec();
+        push_riff_chunk(&mut data, b"ODD ", &[1, 2, 3]).unwrap();
+        push_riff_chunk(&mut data, b"ANIM", &[0, 0, 0, 0, 5, 0]).unwrap();
+
+        let chunks = riff_chunks(&data).collect::<Vec<_>>();
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0], (*b"ODD ", &[1, 2, 3][..]));
+        assert_eq!(chunks[1], (*b"ANIM", &[0, 0, 0, 0, 5, 0][..]));
+    }
+
+    #[test]
+    fn apng_16_bit() {
+        let mut data = Vec::new();
+        let mut encoder = png::Encoder::new(&mut data, 1, 1);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Sixteen);
+        encoder.set_animated(1, 0).unwrap();
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&[0xFF; 8]).unwrap();
+        writer.finish().unwrap();
+
+        assert!(is_apng(&data));
+        assert!(matches!(
+            load_apng(data),
+            Err(ProcessError::UnsupportedImageFormat(_))
+        ));
+    }
+
+    #[test]
+    fn still_webp() {
+        let mut data = Vec::new();
+        image::codecs::webp::WebPEncoder::new_lossless(&mut data)
+            .write_image(&[1, 2, 3, 4, 5, 6, 7, 8], 2, 1, ExtendedColorType::Rgba8)
+            .unwrap();
+
+        let still = load_webp(data).unwrap();
+        assert!(matches!(still.format, Format::StillWebP));
+        assert_eq!(still.frames.len(), 1);
+
+        let out = encode(&still).unwrap();
+        assert!(riff_chunks(&out).all(|(id, _)| id != *b"ANIM"));
+        assert_eq!(
+            load_webp(out).unwrap().frames[0].texture,
+            still.frames[0].texture
+        );
+    }
+
+    #[test]
+    fn u24_overflow() {
+        assert_eq!(u24(0x01_02_03).unwrap(), [3, 2, 1]);
+        assert!(u24(0x01_00_00_00).is_err());
+    }
+}
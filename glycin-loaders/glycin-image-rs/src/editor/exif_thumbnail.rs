@@ -0,0 +1,261 @@
+//! Embedding of thumbnails into the Exif data of JPEG files
+
+use glycin_utils::*;
+
+/// Quality used to encode thumbnails
+const THUMBNAIL_QUALITY: u8 = 75;
+
+const EXIF_HEADER: &[u8] = b"Exif\0\0";
+
+/// Little endian TIFF header followed by an empty IFD0
+const EMPTY_TIFF: &[u8] = &[b'I', b'I', 42, 0, 8, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+/// Embeds a thumbnail into the JPEG in `output`
+///
+/// The output stays unchanged if the Exif data with the thumbnail would not
+/// fit into a single JPEG segment.
+pub fn embed<B: ByteData>(
+    output: &mut CompleteEditorOutput<B>,
+    max_edge: u32,
+) -> Result<(), ProcessError> {
+    let thumbnail = encode_thumbnail(&output.data, max_edge)?;
+
+    if let Some(data) = insert(&output.data, &thumbnail) {
+        output.data = B::try_from_vec(data).expected_error()?;
+        output.info.thumbnail_embedded = true;
+    }
+
+    Ok(())
+}
+
+fn encode_thumbnail(jpeg: &[u8], max_edge: u32) -> Result<Vec<u8>, ProcessError> {
+    let image =
+        image::load_from_memory_with_format(jpeg, image::ImageFormat::Jpeg).expected_error()?;
+    // JPEG dimensions are limited to 16 bit
+    let max_edge = max_edge.min(u32::from(u16::MAX));
+    let thumbnail = image.thumbnail(max_edge, max_edge).into_rgb8();
+
+    let mut out_buf = Vec::new();
+    jpeg_encoder::Encoder::new(&mut out_buf, THUMBNAIL_QUALITY)
+        .encode(
+            &thumbnail,
+            u16::try_from(thumbnail.width()).expected_error()?,
+            u16::try_from(thumbnail.height()).expected_error()?,
+            jpeg_encoder::ColorType::Rgb,
+        )
+        .expected_error()?;
+
+    Ok(out_buf)
+}
+
+/// Returns `jpeg` with `thumbnail` added to the Exif data
+///
+/// Existing Exif data are kept and a new Exif segment is created if there is
+/// none.
+fn insert(jpeg: &[u8], thumbnail: &[u8]) -> Option<Vec<u8>> {
+    if !jpeg.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+
+    // New Exif segments are placed after the JFIF segment
+    let mut insert_pos = 2;
+    let mut exif_segment = None;
+    let mut pos = 2;
+
+    loop {
+        if *jpeg.get(pos)? != 0xFF {
+            return None;
+        }
+
+        let marker = *jpeg.get(pos + 1)?;
+
+        // Metadata segments are located before the image data
+        if matches!(marker, 0xDA | 0xD9) {
+            break;
+        }
+
+        let len = u16::from_be_bytes([*jpeg.get(pos + 2)?, *jpeg.get(pos + 3)?]) as usize;
+        let end = pos + 2 + len;
+        let data = jpeg.get(pos + 4..end)?;
+
+        if marker == 0xE0 && insert_pos == pos {
+            insert_pos = end;
+        }
+
+        if marker == 0xE1 && data.starts_with(EXIF_HEADER) && exif_segment.is_none() {
+            exif_segment = Some((pos, end));
+        }
+
+        pos = end;
+    }
+
+    let mut tiff = match exif_segment {
+        Some((start, end)) => jpeg[start + 4 + EXIF_HEADER.len()..end].to_vec(),
+        None => EMPTY_TIFF.to_vec(),
+    };
+
+    append_thumbnail_ifd(&mut tiff, thumbnail)?;
+
+    let segment_len = u16::try_from(2 + EXIF_HEADER.len() + tiff.len()).ok()?;
+
+    let (start, end) = exif_segment.unwrap_or((insert_pos, insert_pos));
+
+    let mut out = Vec::with_capacity(jpeg.len() + tiff.len());
+    out.extend_from_slice(&jpeg[..start]);
+    out.extend_from_slice(&[0xFF, 0xE1]);
+    out.extend_from_slice(&segment_len.to_be_bytes());
+    out.extend_from_slice(EXIF_HEADER);
+    out.extend_from_slice(&tiff);
+    out.extend_from_slice(&jpeg[end..]);
+
+    Some(out)
+}
+
+/// Links a new IFD1 with the thumbnail to IFD0
+///
+/// A previously existing IFD1 is no longer referenced afterwards.
+fn append_thumbnail_ifd(tiff: &mut Vec<u8>, thumbnail: &[u8]) -> Option<()> {
+    let big_endian = match tiff.get(..2)? {
+        b"II" => false,
+        b"MM" => true,
+        _ => return None,
+    };
+
+    let u16_bytes = |x: u16| {
+        if big_endian {
+            x.to_be_bytes()
+        } else {
+            x.to_le_bytes()
+        }
+    };
+    let u32_bytes = |x: u32| {
+        if big_endian {
+            x.to_be_bytes()
+        } else {
+            x.to_le_bytes()
+        }
+    };
+    let read_u16 = |x: &[u8]| -> Option<u16> {
+        let x = x.try_into().ok()?;
+        Some(if big_endian {
+            u16::from_be_bytes(x)
+        } else {
+            u16::from_le_bytes(x)
+        })
+    };
+    let read_u32 = |x: &[u8]| -> Option<u32> {
+        let x = x.try_into().ok()?;
+        Some(if big_endian {
+            u32::from_be_bytes(x)
+        } else {
+            u32::from_le_bytes(x)
+        })
+    };
+
+    let ifd0 = read_u32(tiff.get(4..8)?)? as usize;
+    let n_entries = read_u16(tiff.get(ifd0..ifd0 + 2)?)? as usize;
+    let next_ifd_pos = ifd0 + 2 + n_entries * 12;
+    tiff.get(next_ifd_pos..next_ifd_pos + 4)?;
+
+    // IFDs have to start at word boundaries
+    if tiff.len() % 2 == 1 {
+        tiff.push(0);
+    }
+
+    let ifd1 = u32::try_from(tiff.len()).ok()?;
+    let thumbnail_offset = ifd1 + 2 + 3 * 12 + 4;
+    let thumbnail_len = u32::try_from(thumbnail.len()).ok()?;
+
+    tiff[next_ifd_pos..next_ifd_pos + 4].copy_from_slice(&u32_bytes(ifd1));
+
+    tiff.extend_from_slice(&u16_bytes(3));
+
+    // Compression: JPEG
+    tiff.extend_from_slice(&u16_bytes(0x0103));
+    tiff.extend_from_slice(&u16_bytes(3));
+    tiff.extend_from_slice(&u32_bytes(1));
+    tiff.extend_from_slice(&u16_bytes(6));
+    tiff.extend_from_slice(&[0, 0]);
+
+    // JPEGInterchangeFormat and JPEGInterchangeFormatLength
+    for (tag, value) in [(0x0201, thumbnail_offset), (0x0202, thumbnail_len)] {
+        tiff.extend_from_slice(&u16_bytes(tag));
+        tiff.extend_from_slice(&u16_bytes(4));
+        tiff.extend_from_slice(&u32_bytes(1));
+        tiff.extend_from_slice(&u32_bytes(value));
+    }
+
+    // No further IFD
+    tiff.extend_from_slice(&[0; 4]);
+    tiff.extend_from_slice(thumbnail);
+
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JPEG: &[u8] = &[
+        0xFF, 0xD8, // SOI
+        0xFF, 0xE0, 0x00, 0x04, 0x4A, 0x46, // APP0
+        0xFF, 0xDA, 0x00, 0x02, // SOS
+        0x12, 0x34, // Entropy coded data
+        0xFF, 0xD9, // EOI
+    ];
+
+    const THUMBNAIL: &[u8] = b"thumbnail";
+
+    /// Returns the thumbnail referenced from IFD1
+    fn thumbnail(jpeg: &[u8]) -> &[u8] {
+        let exif = jpeg
+            .windows(EXIF_HEADER.len())
+            .position(|x| x == EXIF_HEADER)
+            .unwrap();
+        let tiff = &jpeg[exif + EXIF_HEADER.len()..];
+        assert_eq!(&tiff[..2], b"II");
+
+        let u16_at = |pos: usize| u16::from_le_bytes([tiff[pos], tiff[pos + 1]]) as usize;
+        let u32_at = |pos: usize| u32::from_le_bytes(tiff[pos..pos + 4].try_into().unwrap());
+
+        let ifd0 = u32_at(4) as usize;
+        let ifd1 = u32_at(ifd0 + 2 + u16_at(ifd0) * 12) as usize;
+        assert_eq!(u16_at(ifd1), 3);
+        assert_eq!(u16_at(ifd1 + 2 + 12), 0x0201);
+
+        let offset = u32_at(ifd1 + 2 + 12 + 8) as usize;
+        let len = u32_at(ifd1 + 2 + 24 + 8) as usize;
+
+        &tiff[offset..offset + len]
+    }
+
+    #[test]
+    fn new_exif_segment() {
+        let jpeg = insert(JPEG, THUMBNAIL).unwrap();
+
+        // Placed after APP0
+        assert_eq!(&jpeg[..8], &JPEG[..8]);
+        assert_eq!(&jpeg[8..10], &[0xFF, 0xE1]);
+        assert!(jpeg.ends_with(&JPEG[8..]));
+        assert_eq!(thumbnail(&jpeg), THUMBNAIL);
+    }
+
+    #[test]
+    fn existing_exif_segment() {
+        let jpeg = insert(JPEG, b"old").unwrap();
+        let jpeg = insert(&jpeg, THUMBNAIL).unwrap();
+
+        assert_eq!(
+            jpeg.windows(EXIF_HEADER.len())
+                .filter(|x| *x == EXIF_HEADER)
+                .count(),
+            1
+        );
+        assert_eq!(thumbnail(&jpeg), THUMBNAIL);
+    }
+
+    #[test]
+    fn too_large_thumbnail() {
+        assert!(insert(JPEG, &[0; u16::MAX as usize]).is_none());
+    }
+}
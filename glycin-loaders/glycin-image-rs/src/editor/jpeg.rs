@@ -1,6 +1,7 @@
 use std::io::{Cursor, Read};
 
 use editing::EditingFrame;
+use glycin_utils::safe_math::*;
 use glycin_utils::*;
 use gufo_common::field;
 use gufo_common::orientation::Orientation;
@@ -9,6 +10,8 @@ use gufo_jpeg::Jpeg;
 use zune_jpeg::zune_core::options::DecoderOptions;
 use zune_jpeg::zune_core::{self};
 
+use super::jpeg_huffman;
+
 pub struct EditJpeg {
     buf: Vec<u8>,
 }
@@ -86,6 +89,13 @@ pub fn apply_sparse<B: ByteData>(
     edit_jpeg: &EditJpeg,
     mut operations: Operations,
 ) -> Result<SparseEditorOutput<B>, glycin_utils::ProcessError> {
+    // Optimizing changes the image data
+    if operations.operations().contains(&Operation::Optimize) {
+        return Ok(SparseEditorOutput::from(apply_complete(
+            edit_jpeg, operations,
+        )?));
+    }
+
     let buf = edit_jpeg.buf.clone();
     let jpeg = gufo::jpeg::Jpeg::new(buf).expected_error()?;
 
@@ -105,6 +115,34 @@ pub fn apply_sparse<B: ByteData>(
     )?))
 }
 
+/// Orientation changes are sparse if the Exif data can store the orientation
+pub fn sparse_operations(
+    edit_jpeg: &EditJpeg,
+    operations: Operations,
+) -> Result<SparseOperations, glycin_utils::ProcessError> {
+    let jpeg = gufo::jpeg::Jpeg::new(edit_jpeg.buf.clone()).expected_error()?;
+    let rotate_sparse = rotate_sparse(Orientation::Id, &jpeg)
+        .ok()
+        .flatten()
+        .is_some();
+
+    let mut sparse = Vec::new();
+    for (index, operation) in operations.operations().iter().enumerate() {
+        let is_sparse = match operation {
+            Operation::Rotate(_) | Operation::MirrorHorizontally | Operation::MirrorVertically => {
+                rotate_sparse
+            }
+            _ => false,
+        };
+
+        if is_sparse {
+            sparse.push(index.try_u32()?);
+        }
+    }
+
+    Ok(SparseOperations::new(sparse))
+}
+
 pub fn apply_complete<B: ByteData>(
     edit_jpeg: &EditJpeg,
     mut operations: Operations,
@@ -118,12 +156,22 @@ pub fn apply_complete<B: ByteData>(
         operations.prepend(Operations::new_orientation(orientation));
     }
 
-    if let Some(orientation) = operations.orientation()
-        && let Some(byte_changes) = rotate_sparse(orientation, &jpeg)?
-    {
-        let mut data = jpeg.into_inner();
-        byte_changes.apply(&mut data).internal_error()?;
-        return CompleteEditorOutput::new_lossless(data);
+    if let Some(orientation) = operations.orientation() {
+        let byte_changes = rotate_sparse(orientation, &jpeg)?;
+
+        // Without rotation, for example if only optimizing
+        if byte_changes.is_some() || orientation == Orientation::Id {
+            let mut data = jpeg.into_inner();
+            if let Some(byte_changes) = byte_changes {
+                byte_changes.apply(&mut data).internal_error()?;
+            }
+            if operations.operations().contains(&Operation::Optimize)
+                && let Some(optimized) = jpeg_huffman::optimize(&data)?
+            {
+                data = optimized;
+            }
+            return CompleteEditorOutput::new_lossless(data);
+        }
     }
 
     apply_non_sparse(jpeg, operations)
@@ -134,15 +182,27 @@ fn apply_non_sparse<B: ByteData>(
     operations: Operations,
 ) -> Result<CompleteEditorOutput<B>, glycin_utils::ProcessError> {
     let mut out_buf = Vec::new();
-    let encoder = jpeg.encoder(&mut out_buf).expected_error()?;
+    let mut encoder = jpeg.encoder(&mut out_buf).expected_error()?;
+    if operations.operations().contains(&Operation::Optimize) {
+        encoder.set_optimized_huffman_tables(true);
+    }
     let mut buf = jpeg.into_inner();
 
     // Find out what the used color encoding/model is
     let mut decoder = zune_jpeg::JpegDecoder::new(Cursor::new(&mut buf));
     decoder.decode_headers().expected_error()?;
-    let colorspace = decoder.input_colorspace().expected_error()?;
+    let mut colorspace = decoder.input_colorspace().expected_error()?;
     drop(decoder);
 
+    // The LUT is defined for RGB values, so the image data have to be re-encoded as RGB
+    let apply_lut = operations
+        .operations()
+        .iter()
+        .any(|x| matches!(x, Operation::ApplyLut3D { .. }));
+    if apply_lut && colorspace == zune_core::colorspace::ColorSpace::YCbCr {
+        colorspace = zune_core::colorspace::ColorSpace::RGB;
+    }
+
     let decoder_options = DecoderOptions::new_fast()
         .jpeg_set_out_colorspace(colorspace)
         .set_max_height(u32::MAX as usize)
@@ -0,0 +1,709 @@
+//! Lossless re-encoding of JPEG entropy coded data with optimized Huffman
+//! tables
+//!
+//! The Huffman coded symbols and their extra bits are read and written again
+//! with tables that are generated for the actual symbol frequencies, as
+//! described in Annex K.2 of the JPEG specification. The DCT coefficients are
+//! never changed, such that the pixels stay exactly the same.
+//!
+//! Only sequential Huffman coded JPEGs (SOF0 and SOF1) are supported. All
+//! markers except for the Huffman tables are kept as they are.
+
+use glycin_utils::*;
+
+const SOF0: u8 = 0xC0;
+const SOF1: u8 = 0xC1;
+const DHT: u8 = 0xC4;
+const RST0: u8 = 0xD0;
+const SOI: u8 = 0xD8;
+const EOI: u8 = 0xD9;
+const SOS: u8 = 0xDA;
+const DRI: u8 = 0xDD;
+
+/// Re-encode the image data with optimized Huffman tables
+///
+/// Returns `None` for JPEGs that don't use sequential Huffman coding, like
+/// progressive or arithmetic coded JPEGs.
+pub fn optimize(data: &[u8]) -> Result<Option<Vec<u8>>, ProcessError> {
+    if !data.starts_with(&[0xFF, SOI]) {
+        return Err(ProcessError::expected(&"Missing JPEG SOI marker"));
+    }
+
+    let mut out = Vec::with_capacity(data.len());
+    out.extend_from_slice(&[0xFF, SOI]);
+
+    let mut tables: [[Option<HuffmanTable>; 4]; 2] = Default::default();
+    let mut frame = None;
+    let mut restart_interval = 0;
+    let mut pos = 2;
+
+    loop {
+        let marker = match data.get(pos..pos + 2) {
+            // Fill bytes
+            Some(&[0xFF, 0xFF]) => {
+                pos += 1;
+                continue;
+            }
+            Some(&[0xFF, marker]) => marker,
+            _ => return Err(ProcessError::expected(&"Invalid JPEG marker")),
+        };
+
+        if marker == EOI {
+            // Keep trailing data, like appended videos of motion photos
+            out.extend_from_slice(&data[pos..]);
+            return Ok(Some(out));
+        }
+
+        let segment_end = data
+            .get(pos + 2..pos + 4)
+            .map(|x| pos + 2 + usize::from(u16::from_be_bytes([x[0], x[1]])))
+            .filter(|end| *end <= data.len())
+            .ok_or_else(|| ProcessError::expected(&"Truncated JPEG segment"))?;
+        let segment = &data[pos + 4..segment_end];
+
+        match marker {
+            SOF0 | SOF1 => {
+                frame = Some(FrameHeader::parse(segment)?);
+                out.extend_from_slice(&data[pos..segment_end]);
+            }
+            // Other frame types, like progressive or arithmetic coding
+            0xC2 | 0xC3 | 0xC5..=0xC7 | 0xC9..=0xCB | 0xCD..=0xCF => return Ok(None),
+            DHT => {
+                // Replaced with the optimized tables before each scan
+                HuffmanTable::parse_segment(segment, &mut tables)?;
+            }
+            DRI => {
+                let &[hi, lo] = segment else {
+                    return Err(ProcessError::expected(&"Invalid JPEG DRI segment"));
+                };
+                restart_interval = u16::from_be_bytes([hi, lo]);
+                out.extend_from_slice(&data[pos..segment_end]);
+            }
+            SOS => {
+                let frame = frame
+                    .as_ref()
+                    .ok_or_else(|| ProcessError::expected(&"JPEG scan before frame header"))?;
+                let scan = ScanHeader::parse(segment, frame, &tables)?;
+                let scan_data = &data[segment_end..];
+
+                // First pass: Count how often each symbol occurs
+                let mut frequencies = [[[0; 256]; 4]; 2];
+                let scan_len = scan.read(scan_data, restart_interval, |event| {
+                    if let Event::Symbol {
+                        class, id, symbol, ..
+                    } = event
+                    {
+                        frequencies[class][id][usize::from(symbol)] += 1;
+                    }
+                })?;
+
+                let mut optimized: [[Option<HuffmanTable>; 4]; 2] = Default::default();
+                let mut dht = Vec::new();
+                for (class, id) in scan.tables() {
+                    if optimized[class][id].is_some() {
+                        continue;
+                    }
+                    let table = HuffmanTable::optimal(&frequencies[class][id])?;
+                    dht.push(((class as u8) << 4) | id as u8);
+                    dht.extend_from_slice(&table.counts);
+                    dht.extend_from_slice(&table.symbols);
+                    optimized[class][id] = Some(table);
+                }
+                out.extend_from_slice(&[0xFF, DHT]);
+                out.extend_from_slice(
+                    &u16::try_from(dht.len() + 2).internal_error()?.to_be_bytes(),
+                );
+                out.extend_from_slice(&dht);
+
+                out.extend_from_slice(&data[pos..segment_end]);
+
+                // Second pass: Write the symbols with the optimized tables
+                let mut writer = BitWriter::new(&mut out);
+                scan.read(scan_data, restart_interval, |event| match event {
+                    Event::Symbol {
+                        class,
+                        id,
+                        symbol,
+                        extra_bits,
+                        n_extra_bits,
+                    } => {
+                        let (code, len) = optimized[class][id]
+                            .as_ref()
+                            .map(|x| x.codes[usize::from(symbol)])
+                            .unwrap_or_default();
+                        writer.write(code, len);
+                        writer.write(extra_bits, n_extra_bits);
+                    }
+                    Event::Restart(n) => {
+                        writer.flush();
+                        writer.out.extend_from_slice(&[0xFF, RST0 + n]);
+                    }
+                })?;
+                writer.flush();
+
+                pos = segment_end + scan_len;
+                continue;
+            }
+            _ => out.extend_from_slice(&data[pos..segment_end]),
+        }
+
+        pos = segment_end;
+    }
+}
+
+struct FrameHeader {
+    width: u32,
+    height: u32,
+    /// ID and sampling factors of each component
+    components: Vec<(u8, u32, u32)>,
+    max_h: u32,
+    max_v: u32,
+}
+
+impl FrameHeader {
+    fn parse(segment: &[u8]) -> Result<Self, ProcessError> {
+        let invalid = || ProcessError::expected(&"Invalid JPEG frame header");
+
+        let [_precision, h_hi, h_lo, w_hi, w_lo, n, rest @ ..] = segment else {
+            return Err(invalid());
+        };
+        let height = u32::from(u16::from_be_bytes([*h_hi, *h_lo]));
+        let width = u32::from(u16::from_be_bytes([*w_hi, *w_lo]));
+
+        let components = rest
+            .chunks_exact(3)
+            .take(usize::from(*n))
+            .map(|x| (x[0], u32::from(x[1] >> 4), u32::from(x[1] & 0xF)))
+            .collect::<Vec<_>>();
+
+        // Scans with an unknown number of lines are not supported
+        if components.len() != usize::from(*n)
+            || height == 0
+            || width == 0
+            || components.iter().any(|(_, h, v)| *h == 0 || *v == 0)
+        {
+            return Err(invalid());
+        }
+
+        let max_h = components.iter().map(|x| x.1).max().ok_or_else(invalid)?;
+        let max_v = components.iter().map(|x| x.2).max().ok_or_else(invalid)?;
+
+        Ok(Self {
+            width,
+            height,
+            components,
+            max_h,
+            max_v,
+        })
+    }
+}
+
+struct ScanHeader<'a> {
+    /// Sampling factors, DC table, and AC table of each component
+    components: Vec<(u32, u32, usize, usize)>,
+    mcus_x: u32,
+    mcus_y: u32,
+    tables: &'a [[Option<HuffmanTable>; 4]; 2],
+}
+
+enum Event {
+    Symbol {
+        class: usize,
+        id: usize,
+        symbol: u8,
+        extra_bits: u16,
+        n_extra_bits: u8,
+    },
+    /// Restart marker with this number
+    Restart(u8),
+}
+
+impl<'a> ScanHeader<'a> {
+    fn parse(
+        segment: &[u8],
+        frame: &FrameHeader,
+        tables: &'a [[Option<HuffmanTable>; 4]; 2],
+    ) -> Result<Self, ProcessError> {
+        let invalid = || ProcessError::expected(&"Invalid JPEG scan header");
+
+        let [n, rest @ ..] = segment else {
+            return Err(invalid());
+        };
+
+        let mut components = Vec::new();
+        for x in rest.chunks_exact(2).take(usize::from(*n)) {
+            let (_, h, v) = frame
+                .components
+                .iter()
+                .find(|(id, _, _)| *id == x[0])
+                .ok_or_else(invalid)?;
+            let (dc, ac) = (usize::from(x[1] >> 4), usize::from(x[1] & 0xF));
+            if dc > 3 || ac > 3 || tables[0][dc].is_none() || tables[1][ac].is_none() {
+                return Err(invalid());
+            }
+            components.push((*h, *v, dc, ac));
+        }
+
+        if components.len() != usize::from(*n) || components.is_empty() {
+            return Err(invalid());
+        }
+
+        let (mcus_x, mcus_y, components) = if let [(h, v, dc, ac)] = components[..] {
+            // Non-interleaved scans consist of single blocks
+            let width = (frame.width * h).div_ceil(frame.max_h);
+            let height = (frame.height * v).div_ceil(frame.max_v);
+            (width.div_ceil(8), height.div_ceil(8), vec![(1, 1, dc, ac)])
+        } else {
+            (
+                frame.width.div_ceil(8 * frame.max_h),
+                frame.height.div_ceil(8 * frame.max_v),
+                components,
+            )
+        };
+
+        Ok(Self {
+            components,
+            mcus_x,
+            mcus_y,
+            tables,
+        })
+    }
+
+    /// Huffman tables used by the scan as `(class, id)`
+    fn tables(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.components
+            .iter()
+            .flat_map(|(_, _, dc, ac)| [(0, *dc), (1, *ac)])
+    }
+
+    /// Decode the symbols of the scan
+    ///
+    /// Returns the length of the entropy coded data.
+    fn read(
+        &self,
+        data: &[u8],
+        restart_interval: u16,
+        mut f: impl FnMut(Event),
+    ) -> Result<usize, ProcessError> {
+        let mut reader = BitReader::new(data);
+        let n_mcus = u64::from(self.mcus_x) * u64::from(self.mcus_y);
+        let restart_interval = u64::from(restart_interval);
+
+        for mcu in 0..n_mcus {
+            if restart_interval > 0 && mcu > 0 && mcu % restart_interval == 0 {
+                let n = reader.restart()?;
+                f(Event::Restart(n));
+            }
+
+            for (h, v, dc, ac) in &self.components {
+                for _ in 0..h * v {
+                    self.read_block(&mut reader, *dc, *ac, &mut f)?;
+                }
+            }
+        }
+
+        Ok(reader.end())
+    }
+
+    fn read_block(
+        &self,
+        reader: &mut BitReader,
+        dc: usize,
+        ac: usize,
+        f: &mut impl FnMut(Event),
+    ) -> Result<(), ProcessError> {
+        let (Some(dc_table), Some(ac_table)) = (&self.tables[0][dc], &self.tables[1][ac]) else {
+            return Err(ProcessError::expected(&"Missing JPEG Huffman table"));
+        };
+
+        let symbol = dc_table.decode(reader)?;
+        let n_extra_bits = symbol;
+        if n_extra_bits > 16 {
+            return Err(ProcessError::expected(&"Invalid JPEG DC coefficient"));
+        }
+        f(Event::Symbol {
+            class: 0,
+            id: dc,
+            symbol,
+            extra_bits: reader.read(n_extra_bits)?,
+            n_extra_bits,
+        });
+
+        let mut k = 1;
+        while k < 64 {
+            let symbol = ac_table.decode(reader)?;
+            let (run, n_extra_bits) = (symbol >> 4, symbol & 0xF);
+            f(Event::Symbol {
+                class: 1,
+                id: ac,
+                symbol,
+                extra_bits: reader.read(n_extra_bits)?,
+                n_extra_bits,
+            });
+
+            match (run, n_extra_bits) {
+                // End of block
+                (0, 0) => break,
+                // Run of 16 zeros
+                (15, 0) => k += 16,
+                (run, _) => k += usize::from(run) + 1,
+            }
+        }
+
+        if k > 64 {
+            return Err(ProcessError::expected(&"Invalid JPEG AC coefficients"));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+struct HuffmanTable {
+    /// Number of codes of each length from 1 to 16
+    counts: [u8; 16],
+    symbols: Vec<u8>,
+    /// Code and its length for each symbol
+    codes: Vec<(u16, u8)>,
+    /// Largest code of each length, or `-1`
+    max_code: [i32; 17],
+    /// Index into `symbols` for the first code of each length, minus that code
+    value_offset: [i32; 17],
+}
+
+impl HuffmanTable {
+    fn new(counts: [u8; 16], symbols: Vec<u8>) -> Result<Self, ProcessError> {
+        let n_codes = counts.iter().map(|x| usize::from(*x)).sum::<usize>();
+        if symbols.len() != n_codes {
+            return Err(ProcessError::expected(&"Invalid JPEG Huffman table"));
+        }
+
+        let mut codes = vec![(0, 0); 256];
+        let mut max_code = [-1; 17];
+        let mut value_offset = [0; 17];
+
+        let mut code = 0_i32;
+        let mut index = 0;
+        for (len, count) in (1..=16).zip(counts) {
+            value_offset[len] = index as i32 - code;
+            for _ in 0..count {
+                codes[usize::from(symbols[index])] = (code as u16, len as u8);
+                code += 1;
+                index += 1;
+            }
+            if count > 0 {
+                max_code[len] = code - 1;
+            }
+            if code > 1 << len {
+                return Err(ProcessError::expected(&"Invalid JPEG Huffman table"));
+            }
+            code <<= 1;
+        }
+
+        Ok(Self {
+            counts,
+            symbols,
+            codes,
+            max_code,
+            value_offset,
+        })
+    }
+
+    fn parse_segment(
+        mut segment: &[u8],
+        tables: &mut [[Option<HuffmanTable>; 4]; 2],
+    ) -> Result<(), ProcessError> {
+        let invalid = || ProcessError::expected(&"Invalid JPEG DHT segment");
+
+        while let [info, rest @ ..] = segment {
+            let (class, id) = (usize::from(info >> 4), usize::from(info & 0xF));
+            let counts: [u8; 16] = rest
+                .get(..16)
+                .and_then(|x| x.try_into().ok())
+                .ok_or_else(invalid)?;
+            let n_symbols = counts.iter().map(|x| usize::from(*x)).sum::<usize>();
+            let symbols = rest.get(16..16 + n_symbols).ok_or_else(invalid)?;
+
+            *tables
+                .get_mut(class)
+                .and_then(|x| x.get_mut(id))
+                .ok_or_else(invalid)? = Some(Self::new(counts, symbols.to_vec())?);
+
+            segment = &rest[16 + n_symbols..];
+        }
+
+        Ok(())
+    }
+
+    /// Optimal table for the symbol frequencies
+    ///
+    /// Generates code lengths of up to 16 bits as described in Annex K.2 of
+    /// the JPEG specification.
+    fn optimal(frequencies: &[u32; 256]) -> Result<Self, ProcessError> {
+        // Additional symbol ensures that no code consists of only 1-bits
+        let mut freq = [0_u64; 257];
+        for (x, y) in freq.iter_mut().zip(frequencies) {
+            *x = u64::from(*y);
+        }
+        freq[256] = 1;
+
+        let mut code_size = [0_usize; 257];
+        let mut others = [None::<usize>; 257];
+
+        loop {
+            // Least frequent symbol, preferring higher symbols on ties
+            let mut c1 = None;
+            for (i, x) in freq.iter().enumerate() {
+                if *x > 0 && c1.is_none_or(|c1: usize| *x <= freq[c1]) {
+                    c1 = Some(i);
+                }
+            }
+            let Some(c1) = c1 else {
+                break;
+            };
+
+            // Second least frequent symbol
+            let mut c2 = None;
+            for (i, x) in freq.iter().enumerate() {
+                if *x > 0 && i != c1 && c2.is_none_or(|c2: usize| *x <= freq[c2]) {
+                    c2 = Some(i);
+                }
+            }
+            let Some(mut c2) = c2 else {
+                break;
+            };
+
+            freq[c1] += freq[c2];
+            freq[c2] = 0;
+
+            let mut c1 = c1;
+            code_size[c1] += 1;
+            while let Some(next) = others[c1] {
+                c1 = next;
+                code_size[c1] += 1;
+            }
+            others[c1] = Some(c2);
+
+            code_size[c2] += 1;
+            while let Some(next) = others[c2] {
+                c2 = next;
+                code_size[c2] += 1;
+            }
+        }
+
+        let mut bits = [0_u32; 33];
+        for size in code_size.iter().filter(|x| **x > 0) {
+            if *size > 32 {
+                return Err(ProcessError::expected(&"Huffman code too long"));
+            }
+            bits[*size] += 1;
+        }
+
+        // Limit the code lengths to 16 bits
+        for i in (17..=32).rev() {
+            while bits[i] > 0 {
+                let mut j = i - 2;
+                while bits[j] == 0 {
+                    j -= 1;
+                }
+                bits[i] -= 2;
+                bits[i - 1] += 1;
+                bits[j + 1] += 2;
+                bits[j] -= 1;
+            }
+        }
+
+        // Remove the additional symbol from the longest codes
+        if let Some(i) = (1..=16).rev().find(|i| bits[*i] > 0) {
+            bits[i] -= 1;
+        }
+
+        let mut counts = [0; 16];
+        for (count, bits) in counts.iter_mut().zip(&bits[1..=16]) {
+            *count = *bits as u8;
+        }
+
+        let mut symbols = Vec::new();
+        for size in 1..=32 {
+            for symbol in 0..256 {
+                if code_size[symbol] == size {
+                    symbols.push(symbol as u8);
+                }
+            }
+        }
+
+        Self::new(counts, symbols)
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> Result<u8, ProcessError> {
+        let mut code = 0_i32;
+        for len in 1..=16 {
+            code = (code << 1) | i32::from(reader.read(1)?);
+            if code <= self.max_code[len] {
+                return self
+                    .symbols
+                    .get((code + self.value_offset[len]) as usize)
+                    .copied()
+                    .ok_or_else(|| ProcessError::expected(&"Invalid JPEG Huffman code"));
+            }
+        }
+
+        Err(ProcessError::expected(&"Invalid JPEG Huffman code"))
+    }
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bits: u32,
+    n_bits: u8,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            pos: 0,
+            bits: 0,
+            n_bits: 0,
+        }
+    }
+
+    fn read(&mut self, n: u8) -> Result<u16, ProcessError> {
+        while self.n_bits < n {
+            let byte = match self.data.get(self.pos..self.pos + 2) {
+                // Stuffed zero byte
+                Some(&[0xFF, 0x00]) => {
+                    self.pos += 2;
+                    0xFF
+                }
+                Some(&[0xFF, _]) | None => {
+                    return Err(ProcessError::expected(&"Truncated JPEG scan"));
+                }
+                Some(&[byte, _]) => {
+                    self.pos += 1;
+                    byte
+                }
+                Some(_) => unreachable!(),
+            };
+
+            self.bits = (self.bits << 8) | u32::from(byte);
+            self.n_bits += 8;
+        }
+
+        self.n_bits -= n;
+        Ok(((self.bits >> self.n_bits) & ((1 << n) - 1)) as u16)
+    }
+
+    /// Skip the remaining bits and the following restart marker
+    fn restart(&mut self) -> Result<u8, ProcessError> {
+        self.n_bits = 0;
+        match self.data.get(self.pos..self.pos + 2) {
+            Some(&[0xFF, marker @ RST0..=0xD7]) => {
+                self.pos += 2;
+                Ok(marker - RST0)
+            }
+            _ => Err(ProcessError::expected(&"Missing JPEG restart marker")),
+        }
+    }
+
+    /// Position of the marker following the entropy coded data
+    fn end(&self) -> usize {
+        let mut pos = self.pos;
+        while let Some(&byte) = self.data.get(pos) {
+            if byte == 0xFF && self.data.get(pos + 1) != Some(&0x00) {
+                break;
+            }
+            pos += if byte == 0xFF { 2 } else { 1 };
+        }
+        pos
+    }
+}
+
+struct BitWriter<'a> {
+    out: &'a mut Vec<u8>,
+    bits: u32,
+    n_bits: u8,
+}
+
+impl<'a> BitWriter<'a> {
+    fn new(out: &'a mut Vec<u8>) -> Self {
+        Self {
+            out,
+            bits: 0,
+            n_bits: 0,
+        }
+    }
+
+    fn write(&mut self, bits: u16, n: u8) {
+        self.bits = (self.bits << n) | (u32::from(bits) & ((1 << n) - 1));
+        self.n_bits += n;
+
+        while self.n_bits >= 8 {
+            self.n_bits -= 8;
+            let byte = (self.bits >> self.n_bits) as u8;
+            self.out.push(byte);
+            if byte == 0xFF {
+                self.out.push(0x00);
+            }
+        }
+    }
+
+    /// Pad the last byte with 1-bits
+    fn flush(&mut self) {
+        if self.n_bits > 0 {
+            let n = 8 - self.n_bits;
+            self.write((1 << n) - 1, n);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn decode(data: &[u8]) -> Vec<u8> {
+        zune_jpeg::JpegDecoder::new(Cursor::new(data))
+            .decode()
+            .unwrap()
+    }
+
+    #[test]
+    fn optimize_lossless() {
+        let (width, height) = (67_u16, 45_u16);
+        let pixels = (0..u32::from(width) * u32::from(height))
+            .flat_map(|i| {
+                let (x, y) = (i % u32::from(width), i / u32::from(width));
+                [(x * 3) as u8, (y * 5) as u8, ((x * y) % 256) as u8]
+            })
+            .collect::<Vec<_>>();
+
+        for restart_interval in [0, 3] {
+            let mut data = Vec::new();
+            let mut encoder = jpeg_encoder::Encoder::new(&mut data, 90);
+            encoder.set_restart_interval(restart_interval);
+            encoder
+                .encode(&pixels, width, height, jpeg_encoder::ColorType::Rgb)
+                .unwrap();
+
+            let optimized = optimize(&data).unwrap().unwrap();
+
+            assert!(optimized.len() < data.len());
+            assert_eq!(decode(&optimized), decode(&data));
+        }
+    }
+
+    #[test]
+    fn progressive_unsupported() {
+        let mut data = Vec::new();
+        let mut encoder = jpeg_encoder::Encoder::new(&mut data, 90);
+        encoder.set_progressive(true);
+        encoder
+            .encode(&[0; 16 * 16], 16, 16, jpeg_encoder::ColorType::Luma)
+            .unwrap();
+
+        assert!(optimize(&data).unwrap().is_none());
+    }
+}
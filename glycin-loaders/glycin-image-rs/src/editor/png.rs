@@ -14,6 +14,32 @@ pub struct EditorPng {
     editing_frame: glycin_utils::editing::EditingFrame<LocalMemory>,
 }
 
+/// Changes the bits per channel of a frame that is passed to [`create`]
+pub fn change_bit_depth(
+    mut frame: Frame<FungibleMemory>,
+    bit_depth: u8,
+) -> Result<Frame<FungibleMemory>, ProcessError> {
+    let editing_frame = editing::EditingFrame {
+        width: frame.width,
+        height: frame.height,
+        stride: frame.stride,
+        memory_format: frame.memory_format.into(),
+        texture: frame.texture,
+    };
+
+    let editing_frame = editing::change_bit_depth(editing_frame, bit_depth).expected_error()?;
+
+    let ExtendedMemoryFormat::Basic(memory_format) = editing_frame.memory_format else {
+        return Err(ProcessError::expected(&"Unexpected memory format"));
+    };
+
+    frame.stride = editing_frame.stride;
+    frame.memory_format = memory_format;
+    frame.texture = editing_frame.texture;
+
+    Ok(frame)
+}
+
 pub fn create<B: ByteData>(
     new_image: NewImage<B>,
     frame: Frame<FungibleMemory>,
@@ -75,21 +101,41 @@ pub fn load<S: Read>(mut stream: S) -> Result<EditorPng, glycin_utils::ProcessEr
 pub fn apply<B: ByteData>(
     img_editor: &EditorPng,
     mut operations: Operations,
+    bit_depth: Option<u8>,
 ) -> Result<CompleteEditorOutput<B>, glycin_utils::ProcessError> {
     if let Some(orientation) = img_editor.metadata.orientation() {
         operations.prepend(Operations::new_orientation(orientation));
     }
 
+    let optimize = operations.operations().contains(&Operation::Optimize);
+    // Only re-compressing the image data keeps all metadata unchanged
+    let all_optimize = operations
+        .operations()
+        .iter()
+        .all(|x| *x == Operation::Optimize);
+
     let editing_frame = img_editor.editing_frame.clone();
     let mut old_png = img_editor.png.clone();
 
-    let editing_frame =
+    let mut editing_frame =
         editing::apply_operations(editing_frame.into_funglible(), &operations).expected_error()?;
 
+    let original_bit_depth = editing::bit_depth(editing_frame.memory_format);
+    if let Some(bit_depth) = bit_depth {
+        editing_frame = editing::change_bit_depth(editing_frame, bit_depth).expected_error()?;
+    }
+    let bit_depth = editing::bit_depth(editing_frame.memory_format);
+
+    let compression = if optimize {
+        image::codecs::png::CompressionType::Best
+    } else {
+        image::codecs::png::CompressionType::Default
+    };
+
     let mut new_png_data = Cursor::new(Vec::new());
     let encoder = image::codecs::png::PngEncoder::new_with_quality(
         &mut new_png_data,
-        image::codecs::png::CompressionType::Default,
+        compression,
         image::codecs::png::FilterType::Adaptive,
     );
 
@@ -111,11 +157,20 @@ pub fn apply<B: ByteData>(
     // one
     old_png.replace_image_data(&new_png).expected_error()?;
 
+    // Reducing the bit depth loses image data
+    if all_optimize && bit_depth >= original_bit_depth {
+        let mut output = CompleteEditorOutput::new_lossless(old_png.into_inner())?;
+        output.info.bit_depth = bit_depth;
+        return Ok(output);
+    }
+
     let raw_data = reset_exif_orientation(old_png)?;
 
     let data = B::try_from_vec(raw_data).expected_error()?;
 
-    Ok(CompleteEditorOutput::new(data))
+    let mut output = CompleteEditorOutput::new(data);
+    output.info.bit_depth = bit_depth;
+    Ok(output)
 }
 
 fn reset_exif_orientation(mut png: gufo::png::Png) -> Result<Vec<u8>, glycin_utils::ProcessError> {
@@ -0,0 +1,133 @@
+//! Selection of embedded sizes in ICO and CUR files
+
+const HEADER_LEN: usize = 6;
+const ENTRY_LEN: usize = 16;
+
+struct Entry {
+    size: u32,
+    data: std::ops::Range<usize>,
+    raw: [u8; ENTRY_LEN],
+}
+
+/// Reduces an ICO file to the entry best matching the scale factor
+///
+/// The smallest entry is assumed to be the size for scale `1.0`. The entry
+/// with the requested size or the next larger one is selected. If there is
+/// no larger entry, the largest one is used. The data are returned unchanged
+/// if they can't be parsed.
+pub fn select_for_scale(data: Vec<u8>, scale_factor: f64) -> Vec<u8> {
+    let Some(entries) = entries(&data) else {
+        return data;
+    };
+
+    let Some(base_size) = entries.iter().map(|x| x.size).min() else {
+        return data;
+    };
+
+    let target_size = (base_size as f64 * scale_factor).round();
+
+    let selected = entries
+        .iter()
+        .filter(|x| x.size as f64 >= target_size)
+        .min_by_key(|x| x.size)
+        .or_else(|| entries.iter().max_by_key(|x| x.size));
+
+    let Some(selected) = selected else {
+        return data;
+    };
+
+    let offset = (HEADER_LEN + ENTRY_LEN) as u32;
+
+    let mut new_data = Vec::with_capacity(offset as usize + selected.data.len());
+    // Keep reserved field and type (ICO or CUR)
+    new_data.extend_from_slice(&data[..4]);
+    new_data.extend_from_slice(&1_u16.to_le_bytes());
+    new_data.extend_from_slice(&selected.raw[..12]);
+    new_data.extend_from_slice(&offset.to_le_bytes());
+    new_data.extend_from_slice(&data[selected.data.clone()]);
+
+    new_data
+}
+
+fn entries(data: &[u8]) -> Option<Vec<Entry>> {
+    let n_entries = u16::from_le_bytes(data.get(4..6)?.try_into().ok()?) as usize;
+
+    let mut entries = Vec::new();
+    for n in 0..n_entries {
+        let pos = HEADER_LEN + n * ENTRY_LEN;
+        let raw: [u8; ENTRY_LEN] = data.get(pos..pos + ENTRY_LEN)?.try_into().ok()?;
+
+        // A value of zero stands for 256 pixels
+        let size = match raw[0] {
+            0 => 256,
+            width => width as u32,
+        };
+
+        let len = u32::from_le_bytes(raw[8..12].try_into().ok()?) as usize;
+        let start = u32::from_le_bytes(raw[12..16].try_into().ok()?) as usize;
+        let end = start.checked_add(len)?;
+
+        if end > data.len() {
+            return None;
+        }
+
+        entries.push(Entry {
+            size,
+            data: start..end,
+            raw,
+        });
+    }
+
+    Some(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ico(sizes: &[u8]) -> Vec<u8> {
+        let mut data = vec![0, 0, 1, 0];
+        data.extend_from_slice(&(sizes.len() as u16).to_le_bytes());
+
+        let mut offset = HEADER_LEN + sizes.len() * ENTRY_LEN;
+        for size in sizes {
+            data.extend_from_slice(&[*size, *size, 0, 0, 1, 0, 32, 0]);
+            data.extend_from_slice(&4_u32.to_le_bytes());
+            data.extend_from_slice(&(offset as u32).to_le_bytes());
+            offset += 4;
+        }
+
+        for size in sizes {
+            data.extend_from_slice(&[*size; 4]);
+        }
+
+        data
+    }
+
+    fn selected_size(data: &[u8]) -> u8 {
+        assert_eq!(u16::from_le_bytes([data[4], data[5]]), 1);
+        let entry = &entries(data).unwrap()[0];
+        assert_eq!(&data[entry.data.clone()], &[entry.raw[0]; 4]);
+        entry.raw[0]
+    }
+
+    #[test]
+    fn select_by_scale() {
+        let data = ico(&[64, 32]);
+
+        assert_eq!(selected_size(&select_for_scale(data.clone(), 1.)), 32);
+        assert_eq!(selected_size(&select_for_scale(data.clone(), 2.)), 64);
+        // Next larger size
+        assert_eq!(selected_size(&select_for_scale(data.clone(), 1.5)), 64);
+        // Largest size as fallback
+        assert_eq!(selected_size(&select_for_scale(data, 3.)), 64);
+    }
+
+    #[test]
+    fn invalid_data_unchanged() {
+        let mut data = ico(&[32, 64]);
+        data.truncate(data.len() - 1);
+
+        assert_eq!(select_for_scale(data.clone(), 2.), data);
+    }
+}
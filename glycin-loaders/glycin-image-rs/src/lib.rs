@@ -1,12 +1,18 @@
 #![allow(clippy::large_enum_variant)]
 
 mod animated;
+mod cmyk;
 mod editor;
 mod exr;
+mod ico;
+mod progressive;
+mod raw_animation;
+mod rows;
 
 use std::io::{Cursor, Read};
-use std::sync::Mutex;
-use std::sync::mpsc::{Receiver, Sender, channel};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender, channel};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 pub use editor::ImgEditor;
 use glycin_utils::image_rs::Handler;
@@ -19,6 +25,9 @@ type Reader = Cursor<Vec<u8>>;
 type FrameReceiver = Receiver<Result<(Frame<LocalMemory>, bool), ProcessError>>;
 type FrameSender = Sender<Result<(Frame<LocalMemory>, bool), ProcessError>>;
 
+/// How often waiting for an animation frame checks for cancellation
+const CANCELLATION_CHECK_INTERVAL: Duration = Duration::from_millis(10);
+
 #[cfg(feature = "builtin")]
 #[derive(Debug, Clone)]
 pub struct BuiltinImageRs;
@@ -39,28 +48,48 @@ pub struct ImgLoader {
     pub decoder: Mutex<Option<Decoder>>,
     pub cicp: Mutex<Option<Cicp>>,
     pub pixel_density: Option<PixelDensity>,
+    rows: Mutex<Option<rows::RowDecoder>>,
+    frame_cancellation: FrameCancellation,
 }
 
 pub enum Decoder {
     ImageRsStatic(ImageRsFormat<Reader>),
+    /// Static PNG, decoded from the data shared with the row decoder
+    Png(Arc<[u8]>),
     ImageRsAnimated {
         join_handle: std::thread::JoinHandle<()>,
         frame_receiver: FrameReceiver,
     },
+    /// Animation frames that are not composited
+    RawAnimation(raw_animation::RawAnimation),
     Exr(Vec<u8>),
+    CmykJpeg {
+        data: Vec<u8>,
+        inverted: bool,
+        accepts_cmyk: bool,
+    },
+    ProgressiveJpeg(progressive::ProgressiveJpeg),
 }
 
 impl LoaderImplementation for ImgLoader {
     fn load<B: ByteData, R: Read>(
         mut stream: R,
         mime_type: String,
-        _details: InitializationDetails,
+        details: InitializationDetails,
     ) -> Result<(Self, ImageDetails<B>), ProcessError> {
         image_extras::register();
 
         let mut buf = Vec::new();
         stream.read_to_end(&mut buf).internal_error()?;
 
+        if matches!(
+            mime_type.as_str(),
+            "image/vnd.microsoft.icon" | "image/x-win-bitmap"
+        ) && let Some(scale_factor) = details.scale_factor
+        {
+            buf = ico::select_for_scale(buf, scale_factor);
+        }
+
         if mime_type == "image/x-exr" {
             let metadata = exr::metadata(&buf)?;
             return Ok((
@@ -129,6 +158,14 @@ impl LoaderImplementation for ImgLoader {
                 .expected_error()?;
         }
 
+        image_info.color_icc_profile = format
+            .icc_profile()
+            .ok()
+            .flatten()
+            .map(|x| B::try_from_vec(x))
+            .transpose()
+            .expected_error()?;
+
         let loader_impelementation = ImgLoader {
             pixel_density,
             ..Default::default()
@@ -153,14 +190,50 @@ impl LoaderImplementation for ImgLoader {
             });
         }
 
-        if format.decoder.is_animated() {
+        let raw_format = match format.decoder {
+            ImageRsDecoder::Gif(_) => Some(raw_animation::RawFormat::Gif),
+            ImageRsDecoder::Png(_) => Some(raw_animation::RawFormat::Apng),
+            _ => None,
+        };
+
+        if details.raw_animation_frames
+            && format.decoder.is_animated()
+            && let Some(raw_format) = raw_format
+        {
+            let animation = raw_animation::RawAnimation::new(data.into_inner(), raw_format);
+            *loader_impelementation.decoder.lock().unwrap() =
+                Some(Decoder::RawAnimation(animation));
+        } else if format.decoder.is_animated() {
             let (send, recv) = channel();
-            let thread =
-                std::thread::spawn(move || animated::worker(format, data, mime_type, send));
+            let allow_partial = details.allow_partial;
+            let thread = std::thread::spawn(move || {
+                animated::worker(format, data, mime_type, allow_partial, send)
+            });
             *loader_impelementation.decoder.lock().unwrap() = Some(Decoder::ImageRsAnimated {
                 join_handle: thread,
                 frame_receiver: recv,
             });
+        } else if mime_type == "image/jpeg" && cmyk::is_cmyk(data.get_ref()) {
+            let inverted = cmyk::is_inverted(data.get_ref(), details.assume_adobe_cmyk);
+            if !details.accepts_cmyk {
+                // The CMYK profile doesn't apply to the converted RGB data
+                image_info.color_icc_profile = None;
+            }
+            *loader_impelementation.decoder.lock().unwrap() = Some(Decoder::CmykJpeg {
+                data: data.into_inner(),
+                inverted,
+                accepts_cmyk: details.accepts_cmyk,
+            });
+        } else if mime_type == "image/jpeg"
+            && let Some(scan_ends) = progressive::scan_ends(data.get_ref())
+        {
+            let jpeg = progressive::ProgressiveJpeg::new(data.into_inner(), scan_ends);
+            *loader_impelementation.decoder.lock().unwrap() = Some(Decoder::ProgressiveJpeg(jpeg));
+        } else if mime_type == "image/png" {
+            // Only keep one copy of the data for frames and rows
+            let data = Arc::<[u8]>::from(data.into_inner());
+            *loader_impelementation.decoder.lock().unwrap() = Some(Decoder::Png(data.clone()));
+            *loader_impelementation.rows.lock().unwrap() = Some(rows::RowDecoder::new(data));
         } else {
             *loader_impelementation.decoder.lock().unwrap() = Some(Decoder::ImageRsStatic(format));
         }
@@ -175,18 +248,46 @@ impl LoaderImplementation for ImgLoader {
         // Ensure lock on data
         let cicp = self.cicp.lock().unwrap();
 
+        self.frame_cancellation.check()?;
+
         let Some(x) = std::mem::take(&mut *self.decoder.lock().unwrap()) else {
             return Err(ProcessError::NoMoreFrames);
         };
 
         let mut frame = match x {
             Decoder::ImageRsStatic(decoder) => decoder.frame().expected_error()?,
+            Decoder::Png(data) => {
+                let mut format = ImageRsFormat::create(Cursor::new(data.to_vec()), "image/png")?;
+                if let Err(err) = format.set_no_limits() {
+                    eprint!("Failed to unset decoder limits: {err}");
+                }
+                format.frame().expected_error()?
+            }
             Decoder::ImageRsAnimated {
                 join_handle,
                 frame_receiver,
             } => {
                 join_handle.thread().unpark();
-                let (frame, looped) = frame_receiver.recv().internal_error()??;
+
+                let received = loop {
+                    match frame_receiver.recv_timeout(CANCELLATION_CHECK_INTERVAL) {
+                        Ok(received) => break received,
+                        Err(RecvTimeoutError::Timeout) => {
+                            if self.frame_cancellation.is_cancelled() {
+                                // The frame is returned for the next request instead
+                                *self.decoder.lock().unwrap() = Some(Decoder::ImageRsAnimated {
+                                    join_handle,
+                                    frame_receiver,
+                                });
+                                return Err(ProcessError::Canceled);
+                            }
+                        }
+                        Err(err @ RecvTimeoutError::Disconnected) => {
+                            return Err(err).internal_error();
+                        }
+                    }
+                };
+                let (frame, looped) = received?;
 
                 // Write back decoder since we need it again in the future
                 *self.decoder.lock().unwrap() = Some(Decoder::ImageRsAnimated {
@@ -202,7 +303,30 @@ impl LoaderImplementation for ImgLoader {
                 }
                 frame
             }
+            Decoder::RawAnimation(mut animation) => {
+                let frame = animation.frame(frame_request.loop_animation);
+
+                // Keep the decoder for the following frames
+                *self.decoder.lock().unwrap() = Some(Decoder::RawAnimation(animation));
+
+                frame?
+            }
             Decoder::Exr(data) => exr::frame(&data)?,
+            Decoder::CmykJpeg {
+                data,
+                inverted,
+                accepts_cmyk,
+            } => cmyk::frame(&data, inverted, accepts_cmyk)?,
+            Decoder::ProgressiveJpeg(jpeg) => {
+                let frame = jpeg.frame(frame_request.progressive_scan)?;
+
+                // Keep the decoder for the following scans
+                if frame_request.progressive_scan.is_some() {
+                    *self.decoder.lock().unwrap() = Some(Decoder::ProgressiveJpeg(jpeg));
+                }
+
+                frame
+            }
         };
 
         frame.details.color_cicp = cicp.map(|x| {
@@ -218,6 +342,25 @@ impl LoaderImplementation for ImgLoader {
 
         frame.into_other().expected_error()
     }
+
+    fn next_rows<B: ByteData>(
+        &mut self,
+        rows_request: RowsRequest,
+    ) -> Result<Frame<B>, ProcessError> {
+        let mut rows = self.rows.lock().unwrap();
+        let Some(rows) = rows.as_mut() else {
+            return Err(ProcessError::expected(
+                &"The format can't be decoded incrementally",
+            ));
+        };
+
+        rows.next_rows(rows_request.max_rows)
+    }
+
+    fn frame_cancellation(&mut self, cancellation: FrameCancellation) -> bool {
+        self.frame_cancellation = cancellation;
+        true
+    }
 }
 
 pub enum ImageRsDecoder<T: std::io::BufRead + std::io::Seek> {
@@ -349,6 +492,7 @@ impl ImageRsFormat<Reader> {
                 codecs::webp::WebPDecoder::new(data).expected_error()?,
             ))
             .format_name("WebP")
+            .container_format("RIFF")
             .default_bit_depth(8)
             .supports_two_alpha_modes(true),
 
@@ -382,6 +526,11 @@ impl<T: std::io::BufRead + std::io::Seek> ImageRsFormat<T> {
         self
     }
 
+    pub fn container_format(mut self, container_format: impl ToString) -> Self {
+        self.handler = self.handler.container_format(container_format);
+        self
+    }
+
     pub fn supports_two_alpha_modes(mut self, supports_two_alpha_modes: bool) -> Self {
         self.handler = self
             .handler
@@ -437,6 +586,10 @@ impl<T: std::io::BufRead + std::io::Seek> ImageRsFormat<T> {
         self.visit(|x| image::ImageDecoder::xmp_metadata(*x))
     }
 
+    fn icc_profile(&mut self) -> Result<Option<Vec<u8>>, image::ImageError> {
+        self.visit(|x| image::ImageDecoder::icc_profile(*x))
+    }
+
     fn info<B: ByteData>(&mut self) -> ImageDetails<B> {
         match self.decoder {
             ImageRsDecoder::Bmp(ref mut d) => self.handler.info(d),
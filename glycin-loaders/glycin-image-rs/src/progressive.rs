@@ -0,0 +1,194 @@
+//! Scan by scan decoding of progressive JPEGs
+//!
+//! Progressive JPEGs store the image in several scans. The first scan
+//! contains a coarse version of the whole image and every following scan
+//! refines it. Decoding only the first scans yields an intermediate version
+//! of the full canvas, like browsers display while a file is loading. See
+//! [`FrameRequest::progressive_scan`].
+
+use std::borrow::Cow;
+use std::io::Cursor;
+
+use glycin_utils::*;
+use zune_jpeg::zune_core::colorspace::ColorSpace;
+use zune_jpeg::zune_core::options::DecoderOptions;
+
+const SOF2: u8 = 0xC2;
+const SOS: u8 = 0xDA;
+const EOI: u8 = 0xD9;
+
+pub struct ProgressiveJpeg {
+    data: Vec<u8>,
+    /// Offsets at which the entropy coded data of each scan ends
+    scan_ends: Vec<usize>,
+}
+
+/// Offsets at which the entropy coded data of each scan end
+///
+/// Returns `None` for baseline JPEGs.
+pub fn scan_ends(data: &[u8]) -> Option<Vec<usize>> {
+    let mut is_progressive = false;
+    let mut scan_ends = Vec::new();
+
+    // Skip SOI marker
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+    let mut pos = 2;
+
+    while let Some(&[0xFF, marker]) = data.get(pos..pos + 2) {
+        match marker {
+            EOI => break,
+            // Fill bytes
+            0xFF => {
+                pos += 1;
+                continue;
+            }
+            _ => {}
+        }
+
+        let &[len_hi, len_lo] = data.get(pos + 2..pos + 4)? else {
+            return None;
+        };
+        // The length includes the two length bytes
+        pos += 2 + usize::from(u16::from_be_bytes([len_hi, len_lo]));
+
+        match marker {
+            SOF2 => is_progressive = true,
+            SOS => {
+                pos = entropy_coded_data_end(data, pos);
+                scan_ends.push(pos);
+            }
+            _ => {}
+        }
+    }
+
+    (is_progressive && !scan_ends.is_empty()).then_some(scan_ends)
+}
+
+impl ProgressiveJpeg {
+    /// `scan_ends` as returned by [`scan_ends`]
+    pub fn new(data: Vec<u8>, scan_ends: Vec<usize>) -> Self {
+        Self { data, scan_ends }
+    }
+
+    pub fn n_scans(&self) -> u32 {
+        self.scan_ends.len() as u32
+    }
+
+    /// Frame decoded from the scans up to `scan`, counting from `1`
+    ///
+    /// Decodes all scans if `scan` is `None` or beyond the last scan.
+    pub fn frame<B: ByteData>(&self, scan: Option<u32>) -> Result<Frame<B>, ProcessError> {
+        let scan = scan.map_or(self.n_scans(), |x| x.clamp(1, self.n_scans()));
+
+        let data = if scan < self.n_scans() {
+            let end = self.scan_ends[scan as usize - 1];
+            let mut data = self.data[..end].to_vec();
+            data.extend_from_slice(&[0xFF, EOI]);
+            Cow::Owned(data)
+        } else {
+            Cow::Borrowed(self.data.as_slice())
+        };
+
+        let mut decoder = zune_jpeg::JpegDecoder::new(Cursor::new(&*data));
+        decoder.decode_headers().expected_error()?;
+        let grayscale = decoder.input_colorspace() == Some(ColorSpace::Luma);
+
+        let (colorspace, memory_format) = if grayscale {
+            (ColorSpace::Luma, MemoryFormat::G8)
+        } else {
+            (ColorSpace::RGB, MemoryFormat::R8g8b8)
+        };
+
+        let decoder_options = DecoderOptions::new_fast()
+            .jpeg_set_out_colorspace(colorspace)
+            .set_max_height(u32::MAX as usize)
+            .set_max_width(u32::MAX as usize);
+        let mut decoder =
+            zune_jpeg::JpegDecoder::new_with_options(Cursor::new(&*data), decoder_options);
+
+        let pixels = decoder.decode().expected_error()?;
+        let info = decoder.info().expected_error()?;
+
+        let texture = B::try_from_vec(pixels).expected_error()?;
+        let mut frame = Frame::new(
+            info.width as u32,
+            info.height as u32,
+            memory_format,
+            texture,
+        )?;
+
+        frame.details.info_alpha_channel = Some(false);
+        frame.details.info_grayscale = Some(grayscale);
+        frame.details.info_bit_depth = Some(8);
+        frame.details.n_progressive_scans = Some(self.n_scans());
+
+        Ok(frame)
+    }
+}
+
+/// Position of the first marker after the entropy coded data at `pos`
+///
+/// Stuffed `0xFF 0x00` bytes and restart markers are part of the data.
+fn entropy_coded_data_end(data: &[u8], mut pos: usize) -> usize {
+    while let Some(&byte) = data.get(pos) {
+        if byte == 0xFF {
+            match data.get(pos + 1) {
+                Some(0x00 | 0xD0..=0xD7) => pos += 1,
+                _ => return pos,
+            }
+        }
+        pos += 1;
+    }
+
+    data.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progressive_jpeg() -> Vec<u8> {
+        let pixels = (0..16 * 16).map(|x| x as u8).collect::<Vec<_>>();
+
+        let mut data = Vec::new();
+        let mut encoder = jpeg_encoder::Encoder::new(&mut data, 90);
+        encoder.set_progressive(true);
+        encoder
+            .encode(&pixels, 16, 16, jpeg_encoder::ColorType::Luma)
+            .unwrap();
+
+        data
+    }
+
+    #[test]
+    fn scans() {
+        let data = progressive_jpeg();
+        let scan_ends = scan_ends(&data).unwrap();
+        let jpeg = ProgressiveJpeg::new(data, scan_ends);
+        assert!(jpeg.n_scans() > 1);
+
+        let complete = jpeg.frame::<LocalMemory>(None).unwrap();
+        assert_eq!(complete.details.n_progressive_scans, Some(jpeg.n_scans()));
+
+        for scan in 1..=jpeg.n_scans() {
+            let frame = jpeg.frame::<LocalMemory>(Some(scan)).unwrap();
+            assert_eq!((frame.width, frame.height), (16, 16));
+            assert_eq!(frame.memory_format, MemoryFormat::G8);
+        }
+
+        let last = jpeg.frame::<LocalMemory>(Some(jpeg.n_scans())).unwrap();
+        assert_eq!(&*last.texture, &*complete.texture);
+    }
+
+    #[test]
+    fn baseline() {
+        let mut data = Vec::new();
+        jpeg_encoder::Encoder::new(&mut data, 90)
+            .encode(&[0; 16 * 16], 16, 16, jpeg_encoder::ColorType::Luma)
+            .unwrap();
+
+        assert!(scan_ends(&data).is_none());
+    }
+}
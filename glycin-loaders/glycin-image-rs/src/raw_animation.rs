@@ -0,0 +1,305 @@
+//! Animation frames as stored in the file
+//!
+//! Instead of compositing the frames onto the canvas like image-rs does,
+//! every frame only covers its own area of the canvas. The offset, disposal
+//! method, and blend operation from the file are reported with the frame.
+//! See [`InitializationDetails::raw_animation_frames`].
+
+use std::io::Cursor;
+use std::sync::Arc;
+
+use glycin_utils::safe_math::*;
+use glycin_utils::*;
+
+use crate::animated::frame_delay;
+use crate::rows::png_memory_format;
+
+type Source = Cursor<Arc<[u8]>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RawFormat {
+    Gif,
+    Apng,
+}
+
+pub struct RawAnimation {
+    data: Arc<[u8]>,
+    format: RawFormat,
+    /// Decoder of the current loop, created with the first frame request
+    decoder: Option<RawDecoder>,
+    /// Index of the next frame within the animation
+    n_frame: u64,
+}
+
+enum RawDecoder {
+    Gif(gif::Decoder<Source>),
+    Apng {
+        reader: png::Reader<Source>,
+        /// Frames that are part of the animation and not decoded yet
+        remaining: u32,
+    },
+}
+
+impl RawAnimation {
+    pub fn new(data: Vec<u8>, format: RawFormat) -> Self {
+        Self {
+            data: Arc::from(data),
+            format,
+            decoder: None,
+            n_frame: 0,
+        }
+    }
+
+    /// Next frame of the animation
+    ///
+    /// Starts over with the first frame after the last one, unless
+    /// `loop_animation` is `false`.
+    pub fn frame<B: ByteData>(&mut self, loop_animation: bool) -> Result<Frame<B>, ProcessError> {
+        loop {
+            let decoder = match &mut self.decoder {
+                Some(decoder) => decoder,
+                None => self
+                    .decoder
+                    .insert(RawDecoder::new(self.data.clone(), self.format)?),
+            };
+
+            if let Some(mut frame) = decoder.next_frame()? {
+                frame.details.n_frame = Some(self.n_frame);
+                self.n_frame += 1;
+                return Ok(frame);
+            }
+
+            // End of the animation
+            if !loop_animation || self.n_frame == 0 {
+                return Err(ProcessError::NoMoreFrames);
+            }
+
+            self.decoder = None;
+            self.n_frame = 0;
+        }
+    }
+}
+
+impl RawDecoder {
+    fn new(data: Arc<[u8]>, format: RawFormat) -> Result<Self, ProcessError> {
+        match format {
+            RawFormat::Gif => {
+                let mut options = gif::DecodeOptions::new();
+                options.set_color_output(gif::ColorOutput::RGBA);
+                // Frames have to be within the canvas
+                options.check_frame_consistency(true);
+                let decoder = options.read_info(Cursor::new(data)).expected_error()?;
+
+                Ok(Self::Gif(decoder))
+            }
+            RawFormat::Apng => {
+                let mut decoder = png::Decoder::new(Cursor::new(data));
+                decoder.set_transformations(png::Transformations::EXPAND);
+                let reader = decoder.read_info().expected_error()?;
+                let remaining = reader.info().animation_control.map_or(0, |x| x.num_frames);
+
+                Ok(Self::Apng { reader, remaining })
+            }
+        }
+    }
+
+    fn next_frame<B: ByteData>(&mut self) -> Result<Option<Frame<B>>, ProcessError> {
+        match self {
+            Self::Gif(decoder) => {
+                let Some(gif_frame) = decoder.read_next_frame().expected_error()? else {
+                    return Ok(None);
+                };
+
+                let texture = B::try_from_slice(&gif_frame.buffer).expected_error()?;
+                let mut frame = Frame::new(
+                    gif_frame.width.into(),
+                    gif_frame.height.into(),
+                    MemoryFormat::R8g8b8a8,
+                    texture,
+                )?;
+
+                // GIF delays are in hundredths of a second
+                frame.delay = Some(frame_delay(u32::from(gif_frame.delay) * 10, 1)).into();
+                frame.details.animation_offset =
+                    Some((gif_frame.left.into(), gif_frame.top.into()));
+                // Transparent pixels leave the canvas unchanged
+                frame.details.animation_blend_op = Some(BlendOp::Over);
+                frame.details.animation_disposal_method = Some(match gif_frame.dispose {
+                    gif::DisposalMethod::Any | gif::DisposalMethod::Keep => DisposalMethod::None,
+                    gif::DisposalMethod::Background => DisposalMethod::Background,
+                    gif::DisposalMethod::Previous => DisposalMethod::Previous,
+                });
+                frame.details.info_bit_depth = Some(8);
+
+                Ok(Some(frame))
+            }
+            Self::Apng { reader, remaining } => loop {
+                if *remaining == 0 {
+                    return Ok(None);
+                }
+
+                let canvas = (reader.info().width, reader.info().height);
+                let buffer_size = reader
+                    .output_line_size(canvas.0)
+                    .smul(canvas.1.try_usize()?)?;
+                let mut texture = vec![0; buffer_size];
+                let output_info = reader.next_frame(&mut texture).expected_error()?;
+
+                // The default image is not necessarily part of the animation
+                let Some(frame_control) = reader.info().frame_control else {
+                    continue;
+                };
+                *remaining -= 1;
+
+                texture.truncate(output_info.buffer_size());
+                let memory_format =
+                    png_memory_format(output_info.color_type, output_info.bit_depth)?;
+
+                // PNG stores 16 bit values in big endian
+                if cfg!(target_endian = "little") {
+                    MemoryFormat::swap_endianness_in_place(memory_format, &mut texture);
+                }
+
+                let texture = B::try_from_vec(texture).expected_error()?;
+                let mut frame = Frame::new(
+                    output_info.width,
+                    output_info.height,
+                    memory_format,
+                    texture,
+                )?;
+                frame.stride = output_info.line_size.try_u32()?;
+
+                // A denominator of zero stands for hundredths of a second
+                let delay_den = match frame_control.delay_den {
+                    0 => 100,
+                    delay_den => delay_den,
+                };
+                frame.delay = Some(frame_delay(
+                    frame_control.delay_num.into(),
+                    delay_den.into(),
+                ))
+                .into();
+                frame.details.animation_offset =
+                    Some((frame_control.x_offset, frame_control.y_offset));
+                frame.details.animation_blend_op = Some(match frame_control.blend_op {
+                    png::BlendOp::Source => BlendOp::Source,
+                    png::BlendOp::Over => BlendOp::Over,
+                });
+                frame.details.animation_disposal_method = Some(match frame_control.dispose_op {
+                    png::DisposeOp::None => DisposalMethod::None,
+                    png::DisposeOp::Background => DisposalMethod::Background,
+                    png::DisposeOp::Previous => DisposalMethod::Previous,
+                });
+                frame.details.info_bit_depth = Some(reader.info().bit_depth as u8);
+
+                return Ok(Some(frame));
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: [u8; 4] = [255, 0, 0, 255];
+
+    #[test]
+    fn gif() {
+        let mut data = Vec::new();
+        {
+            let mut encoder = gif::Encoder::new(&mut data, 4, 4, &[]).unwrap();
+
+            let mut frame = gif::Frame::from_rgba(4, 4, &mut [0; 4 * 4 * 4]);
+            frame.delay = 10;
+            encoder.write_frame(&frame).unwrap();
+
+            let mut frame = gif::Frame::from_rgba(2, 1, &mut RED.repeat(2));
+            frame.left = 1;
+            frame.top = 2;
+            frame.delay = 20;
+            frame.dispose = gif::DisposalMethod::Background;
+            encoder.write_frame(&frame).unwrap();
+        }
+
+        let mut animation = RawAnimation::new(data, RawFormat::Gif);
+
+        let frame = animation.frame::<LocalMemory>(true).unwrap();
+        assert_eq!((frame.width, frame.height), (4, 4));
+        assert_eq!(frame.details.animation_offset, Some((0, 0)));
+
+        let frame = animation.frame::<LocalMemory>(true).unwrap();
+        assert_eq!((frame.width, frame.height), (2, 1));
+        assert_eq!(&*frame.texture, RED.repeat(2));
+        assert_eq!(frame.details.n_frame, Some(1));
+        assert_eq!(frame.details.animation_offset, Some((1, 2)));
+        assert_eq!(frame.details.animation_blend_op, Some(BlendOp::Over));
+        assert_eq!(
+            frame.details.animation_disposal_method,
+            Some(DisposalMethod::Background)
+        );
+        assert_eq!(
+            Option::from(frame.delay),
+            Some(std::time::Duration::from_millis(200))
+        );
+
+        // Loops back to the first frame
+        let frame = animation.frame::<LocalMemory>(true).unwrap();
+        assert_eq!(frame.details.n_frame, Some(0));
+
+        animation.frame::<LocalMemory>(true).unwrap();
+        assert!(matches!(
+            animation.frame::<LocalMemory>(false),
+            Err(ProcessError::NoMoreFrames)
+        ));
+    }
+
+    #[test]
+    fn apng() {
+        let mut data = Vec::new();
+        let mut encoder = png::Encoder::new(&mut data, 4, 4);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_animated(2, 0).unwrap();
+        // The default image is not part of the animation
+        encoder.set_sep_def_img(true).unwrap();
+
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&[0; 4 * 4 * 4]).unwrap();
+        writer.write_image_data(&[0; 4 * 4 * 4]).unwrap();
+        writer.set_frame_dimension(2, 1).unwrap();
+        writer.set_frame_position(1, 2).unwrap();
+        writer.set_frame_delay(1, 4).unwrap();
+        writer.set_dispose_op(png::DisposeOp::Previous).unwrap();
+        writer.set_blend_op(png::BlendOp::Over).unwrap();
+        writer.write_image_data(&RED.repeat(2)).unwrap();
+        writer.finish().unwrap();
+
+        let mut animation = RawAnimation::new(data, RawFormat::Apng);
+
+        let frame = animation.frame::<LocalMemory>(true).unwrap();
+        assert_eq!((frame.width, frame.height), (4, 4));
+        assert_eq!(frame.details.n_frame, Some(0));
+        assert_eq!(frame.details.animation_blend_op, Some(BlendOp::Source));
+
+        let frame = animation.frame::<LocalMemory>(true).unwrap();
+        assert_eq!((frame.width, frame.height), (2, 1));
+        assert_eq!(frame.memory_format, MemoryFormat::R8g8b8a8);
+        assert_eq!(&*frame.texture, RED.repeat(2));
+        assert_eq!(frame.details.animation_offset, Some((1, 2)));
+        assert_eq!(frame.details.animation_blend_op, Some(BlendOp::Over));
+        assert_eq!(
+            frame.details.animation_disposal_method,
+            Some(DisposalMethod::Previous)
+        );
+        assert_eq!(
+            Option::from(frame.delay),
+            Some(std::time::Duration::from_millis(250))
+        );
+
+        assert!(matches!(
+            animation.frame::<LocalMemory>(false),
+            Err(ProcessError::NoMoreFrames)
+        ));
+    }
+}
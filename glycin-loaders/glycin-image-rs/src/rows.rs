@@ -0,0 +1,99 @@
+//! Incremental decoding for [`LoaderImplementation::next_rows`]
+
+use std::io::Cursor;
+use std::sync::Arc;
+
+use glycin_utils::*;
+
+/// Decodes PNG rows
+///
+/// The data are shared with [`crate::Decoder::Png`] and the decoder is only
+/// created with the first request.
+pub enum RowDecoder {
+    Pending(Arc<[u8]>),
+    Png(png::Reader<Cursor<Arc<[u8]>>>),
+}
+
+impl RowDecoder {
+    pub fn new(data: Arc<[u8]>) -> Self {
+        Self::Pending(data)
+    }
+
+    pub fn next_rows<B: ByteData>(&mut self, max_rows: u32) -> Result<Frame<B>, ProcessError> {
+        if let Self::Pending(data) = self {
+            let mut decoder = png::Decoder::new(Cursor::new(data.clone()));
+            decoder.set_transformations(png::Transformations::EXPAND);
+            let reader = decoder.read_info().expected_error()?;
+
+            // Rows of interlaced images are only complete after the last pass
+            if reader.info().interlaced {
+                return Err(ProcessError::expected(
+                    &"Interlaced PNGs can't be decoded incrementally",
+                ));
+            }
+
+            *self = Self::Png(reader);
+        }
+
+        let Self::Png(reader) = self else {
+            unreachable!()
+        };
+
+        let width = reader.info().width;
+        let (color_type, bit_depth) = reader.output_color_type();
+        let memory_format = png_memory_format(color_type, bit_depth)?;
+
+        let mut texture = Vec::new();
+        let mut n_rows = 0;
+        while n_rows < max_rows {
+            let Some(row) = reader.next_row().expected_error()? else {
+                break;
+            };
+            texture.extend_from_slice(row.data());
+            n_rows += 1;
+        }
+
+        if n_rows == 0 {
+            return Err(ProcessError::NoMoreFrames);
+        }
+
+        // PNG stores 16 bit values in big endian
+        if cfg!(target_endian = "little") {
+            MemoryFormat::swap_endianness_in_place(memory_format, &mut texture);
+        }
+
+        let texture = B::try_from_vec(texture).expected_error()?;
+        Ok(Frame::new(width, n_rows, memory_format, texture)?)
+    }
+}
+
+pub fn png_memory_format(
+    color_type: png::ColorType,
+    bit_depth: png::BitDepth,
+) -> Result<MemoryFormat, ProcessError> {
+    let sixteen_bit = match bit_depth {
+        png::BitDepth::Eight => false,
+        png::BitDepth::Sixteen => true,
+        bit_depth => {
+            return Err(ProcessError::expected(&format!(
+                "Unsupported bit depth after expansion: {bit_depth:?}"
+            )));
+        }
+    };
+
+    Ok(match (color_type, sixteen_bit) {
+        (png::ColorType::Grayscale, false) => MemoryFormat::G8,
+        (png::ColorType::Grayscale, true) => MemoryFormat::G16,
+        (png::ColorType::GrayscaleAlpha, false) => MemoryFormat::G8a8,
+        (png::ColorType::GrayscaleAlpha, true) => MemoryFormat::G16a16,
+        (png::ColorType::Rgb, false) => MemoryFormat::R8g8b8,
+        (png::ColorType::Rgb, true) => MemoryFormat::R16g16b16,
+        (png::ColorType::Rgba, false) => MemoryFormat::R8g8b8a8,
+        (png::ColorType::Rgba, true) => MemoryFormat::R16g16b16a16,
+        (color_type, _) => {
+            return Err(ProcessError::expected(&format!(
+                "Unsupported color type after expansion: {color_type:?}"
+            )));
+        }
+    })
+}
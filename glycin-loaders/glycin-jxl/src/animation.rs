@@ -0,0 +1,208 @@
+use std::mem::MaybeUninit;
+use std::time::Duration;
+
+use glycin_utils::*;
+use jpegxl_sys::common::types::{JxlBool, JxlDataType, JxlEndianness, JxlPixelFormat};
+use jpegxl_sys::decode::*;
+use jpegxl_sys::metadata::codestream_header::*;
+
+/// Decoder that returns the coalesced frames of an animation one by one
+pub struct Decoder {
+    decoder: *mut JxlDecoder,
+    data: Vec<u8>,
+    width: u32,
+    height: u32,
+    pixel_format: JxlPixelFormat,
+    memory_format: MemoryFormat,
+    ticks_per_second: f64,
+    n_frame: u64,
+    delay: Option<Duration>,
+}
+
+// The decoder is only accessed via `&mut self`
+unsafe impl Send for Decoder {}
+unsafe impl Sync for Decoder {}
+
+impl Decoder {
+    pub fn new(data: Vec<u8>, info: &JxlBasicInfo) -> Result<Self, ProcessError> {
+        let has_alpha = info.alpha_bits > 0;
+
+        let (data_type, memory_format) =
+            match (info.bits_per_sample > 8, info.num_color_channels, has_alpha) {
+                (false, 3, false) => (JxlDataType::Uint8, MemoryFormat::R8g8b8),
+                (false, 3, true) => (JxlDataType::Uint8, MemoryFormat::R8g8b8a8),
+                (false, 1, false) => (JxlDataType::Uint8, MemoryFormat::G8),
+                (false, 1, true) => (JxlDataType::Uint8, MemoryFormat::G8a8),
+                (true, 3, false) => (JxlDataType::Uint16, MemoryFormat::R16g16b16),
+                (true, 3, true) => (JxlDataType::Uint16, MemoryFormat::R16g16b16a16),
+                (true, 1, false) => (JxlDataType::Uint16, MemoryFormat::G16),
+                (true, 1, true) => (JxlDataType::Uint16, MemoryFormat::G16a16),
+                (_, n, _) => {
+                    return Err(ProcessError::expected(&format!(
+                        "Unsupported number of color channels: {n}"
+                    )));
+                }
+            };
+
+        let pixel_format = JxlPixelFormat {
+            num_channels: memory_format.n_channels().into(),
+            data_type,
+            endianness: JxlEndianness::Native,
+            align: 0,
+        };
+
+        let ticks_per_second = if info.animation.tps_denominator == 0 {
+            0.
+        } else {
+            info.animation.tps_numerator as f64 / info.animation.tps_denominator as f64
+        };
+
+        let decoder = unsafe { JxlDecoderCreate(std::ptr::null()) };
+        if decoder.is_null() {
+            return Err(ProcessError::expected(&"Failed to create JPEG XL decoder"));
+        }
+
+        let mut decoder = Self {
+            decoder,
+            data,
+            width: info.xsize,
+            height: info.ysize,
+            pixel_format,
+            memory_format,
+            ticks_per_second,
+            n_frame: 0,
+            delay: None,
+        };
+
+        unsafe {
+            decoder.check(JxlDecoderSubscribeEvents(
+                decoder.decoder,
+                JxlDecoderStatus::Frame as i32 | JxlDecoderStatus::FullImage as i32,
+            ))?;
+        }
+        decoder.set_input()?;
+
+        Ok(decoder)
+    }
+
+    /// Returns the next frame
+    ///
+    /// After the last frame, the decoder starts over with the first frame. If
+    /// `loop_animation` is not set, [`ProcessError::NoMoreFrames`] is returned
+    /// instead of the first frame.
+    pub fn frame<B: ByteData>(&mut self, loop_animation: bool) -> Result<Frame<B>, ProcessError> {
+        let mut buf = Vec::new();
+
+        loop {
+            let status = unsafe { JxlDecoderProcessInput(self.decoder) };
+
+            match status {
+                JxlDecoderStatus::Frame => {
+                    let mut header = MaybeUninit::uninit();
+                    unsafe {
+                        self.check(JxlDecoderGetFrameHeader(self.decoder, header.as_mut_ptr()))?;
+                    }
+                    let header: JxlFrameHeader = unsafe { header.assume_init() };
+                    self.delay = Some(self.duration(header.duration));
+                }
+                JxlDecoderStatus::NeedImageOutBuffer => {
+                    let mut size = 0;
+                    unsafe {
+                        self.check(JxlDecoderImageOutBufferSize(
+                            self.decoder,
+                            &self.pixel_format,
+                            &mut size,
+                        ))?;
+                    }
+                    buf.resize(size, 0);
+                    unsafe {
+                        self.check(JxlDecoderSetImageOutBuffer(
+                            self.decoder,
+                            &self.pixel_format,
+                            buf.as_mut_ptr().cast(),
+                            buf.len(),
+                        ))?;
+                    }
+                }
+                JxlDecoderStatus::FullImage => {
+                    let texture = B::try_from_slice(&buf).expected_error()?;
+                    let mut frame =
+                        Frame::new(self.width, self.height, self.memory_format, texture)
+                            .expected_error()?;
+                    frame.delay = self.delay.take().into();
+                    frame.details.n_frame = Some(self.n_frame);
+
+                    if self.pixel_format.data_type == JxlDataType::Uint16 {
+                        frame.details.info_bit_depth = Some(16);
+                    }
+                    if self.memory_format.has_alpha() {
+                        frame.details.info_alpha_channel = Some(true);
+                    }
+                    if self.pixel_format.num_channels <= 2 {
+                        frame.details.info_grayscale = Some(true);
+                    }
+
+                    self.n_frame += 1;
+
+                    return Ok(frame);
+                }
+                JxlDecoderStatus::Success => {
+                    unsafe { JxlDecoderRewind(self.decoder) };
+                    self.set_input()?;
+                    self.n_frame = 0;
+
+                    if !loop_animation {
+                        return Err(ProcessError::NoMoreFrames);
+                    }
+                }
+                status => {
+                    return Err(ProcessError::expected(&format!(
+                        "Unexpected JPEG XL decoder status: {status:?}"
+                    )));
+                }
+            }
+        }
+    }
+
+    fn duration(&self, ticks: u32) -> Duration {
+        if ticks == 0 || self.ticks_per_second <= 0. {
+            // Other decoders default to this value as well
+            Duration::from_millis(100)
+        } else {
+            Duration::from_secs_f64(ticks as f64 / self.ticks_per_second)
+        }
+    }
+
+    fn set_input(&mut self) -> Result<(), ProcessError> {
+        unsafe {
+            self.check(JxlDecoderSetInput(
+                self.decoder,
+                self.data.as_ptr(),
+                self.data.len(),
+            ))?;
+            JxlDecoderCloseInput(self.decoder);
+        }
+
+        Ok(())
+    }
+
+    fn check(&self, status: JxlDecoderStatus) -> Result<(), ProcessError> {
+        if status == JxlDecoderStatus::Success {
+            Ok(())
+        } else {
+            Err(ProcessError::expected(&format!(
+                "JPEG XL decoder failed: {status:?}"
+            )))
+        }
+    }
+}
+
+impl Drop for Decoder {
+    fn drop(&mut self) {
+        unsafe { JxlDecoderDestroy(self.decoder) };
+    }
+}
+
+pub fn is_animated(info: &JxlBasicInfo) -> bool {
+    info.have_animation == JxlBool::True
+}
@@ -1,5 +1,6 @@
 #![allow(clippy::large_enum_variant)]
 
+mod animation;
 mod editing;
 
 use std::io::Read;
@@ -22,6 +23,7 @@ init_main_loader_editor!(ImgDecoder, ImgEditor);
 #[derive(Default)]
 pub struct ImgDecoder {
     data: Vec<u8>,
+    animation: Option<animation::Decoder>,
     icc_profile: Option<Vec<u8>>,
     cicp: Option<Cicp>,
 }
@@ -42,9 +44,17 @@ impl LoaderImplementation for ImgDecoder {
         image_info.info_format_name = Some(String::from("JPEG XL"));
         image_info.metadata_exif = exif.map(B::try_from_vec).transpose().expected_error()?;
         image_info.transformation_ignore_exif = true;
+        image_info.has_transparency = (info.alpha_bits == 0).then_some(false);
+
+        let (data, animation) = if animation::is_animated(&info) {
+            (Vec::new(), Some(animation::Decoder::new(data, &info)?))
+        } else {
+            (data, None)
+        };
 
         let loader_implementation = ImgDecoder {
             data,
+            animation,
             icc_profile,
             cicp,
         };
@@ -54,8 +64,14 @@ impl LoaderImplementation for ImgDecoder {
 
     fn specific_frame<B: glycin_utils::ByteData>(
         &mut self,
-        _frame_request: FrameRequest,
+        frame_request: FrameRequest,
     ) -> Result<Frame<B>, ProcessError> {
+        if let Some(animation) = &mut self.animation {
+            let mut frame = animation.frame(frame_request.loop_animation)?;
+            self.set_color_details(&mut frame)?;
+            return Ok(frame);
+        }
+
         let runner = jpegxl_rs::parallel::resizable_runner::ResizableRunner::new(None).unwrap();
         let decoder = jpegxl_rs::decoder_builder()
             .parallel_runner(&runner)
@@ -131,14 +147,7 @@ impl LoaderImplementation for ImgDecoder {
 
         let mut frame = Frame::new(width, height, memory_format, texture).expected_error()?;
 
-        frame.details.color_icc_profile = self
-            .icc_profile
-            .as_deref()
-            .map(B::try_from_slice)
-            .transpose()
-            .expected_error()?;
-
-        frame.details.color_cicp = self.cicp.map(|x| x.to_bytes());
+        self.set_color_details(&mut frame)?;
 
         if bits != 8 {
             frame.details.info_bit_depth = Some(bits);
@@ -156,6 +165,21 @@ impl LoaderImplementation for ImgDecoder {
     }
 }
 
+impl ImgDecoder {
+    fn set_color_details<B: ByteData>(&self, frame: &mut Frame<B>) -> Result<(), ProcessError> {
+        frame.details.color_icc_profile = self
+            .icc_profile
+            .as_deref()
+            .map(B::try_from_slice)
+            .transpose()
+            .expected_error()?;
+
+        frame.details.color_cicp = self.cicp.map(|x| x.to_bytes());
+
+        Ok(())
+    }
+}
+
 fn basic_info(
     data: &[u8],
 ) -> (
@@ -65,6 +65,7 @@ pub fn thread<B: ByteData>(
 
     image_info.info_format_name = Some(String::from("SVG"));
     image_info.info_dimensions_text = dimensions_text(intrinsic_dimensions);
+    image_info.intrinsic_aspect_ratio = intrinsic_aspect_ratio(&handle);
     let physical_size = physical_size(intrinsic_dimensions);
 
     info_send.send(Ok(image_info)).unwrap();
@@ -246,6 +247,18 @@ pub fn svg_dimensions_float(renderer: &rsvg::Handle) -> (f64, f64) {
     }
 }
 
+/// Aspect ratio from the absolute size or the `viewBox`
+pub fn intrinsic_aspect_ratio(renderer: &rsvg::Handle) -> Option<f64> {
+    let (width, height) = if let Some(size) = renderer.intrinsic_size_in_pixels() {
+        size
+    } else {
+        let vbox = renderer.intrinsic_dimensions().2?;
+        (vbox.width(), vbox.height())
+    };
+
+    Some(width / height).filter(|x| x.is_finite() && *x > 0.)
+}
+
 pub fn svg_dimensions(renderer: &rsvg::Handle) -> (u32, u32) {
     let (width, height) = svg_dimensions_float(renderer);
     (width.round() as u32, height.round() as u32)
@@ -1,4 +1,5 @@
 use std::io::Cursor;
+use std::path::PathBuf;
 
 use glycin_utils::*;
 
@@ -19,6 +20,18 @@ impl Builtin for BuiltinTest {
 
 pub struct ImgDecoder {
     pub instructions: Vec<String>,
+    frame_cancellation: FrameCancellation,
+    interrupted: bool,
+    /// Removed when the state is dropped
+    tmp_file: Option<PathBuf>,
+}
+
+impl Drop for ImgDecoder {
+    fn drop(&mut self) {
+        if let Some(tmp_file) = &self.tmp_file {
+            let _ = std::fs::remove_file(tmp_file);
+        }
+    }
 }
 
 pub struct ImgEditor {
@@ -47,6 +60,18 @@ fn handle_instructions<B: ByteData>(
         "panic-next-step" => (),
         "infinte-loop-next-step" => (),
         "half-with-icc-profile" => (),
+        "image-icc-profile" => (),
+        "frame-composition" => (),
+        "zero-dimensions" => (),
+        "auxiliary-image" => (),
+        "image-items" => (),
+        "oversized-texture" => (),
+        "warnings" => (),
+        "reduced-precision" => (),
+        "interruptible-loop-next-step" => (),
+        "sleep-next-step" => (),
+        "tmp-file" => (),
+        "heartbeat" => (),
         other => panic!("unknwon instruction {other}"),
     }
 
@@ -61,12 +86,75 @@ impl LoaderImplementation for ImgDecoder {
     ) -> Result<(Self, ImageDetails<B>), ProcessError> {
         let instructions = handle_instructions::<B>(stream)?;
 
-        Ok((ImgDecoder { instructions }, ImageDetails::new(1, 1)))
+        let mut image_details = if instructions[0] == "zero-dimensions" {
+            ImageDetails::new(0, 0)
+        } else {
+            ImageDetails::new(1, 1)
+        };
+
+        if instructions[0] == "auxiliary-image" {
+            image_details.n_animation_frames = Some(1);
+            image_details.n_auxiliary_images = Some(1);
+        }
+
+        if instructions[0] == "image-items" {
+            image_details.image_items = Some(vec![
+                (1, String::from("thumbnail")),
+                (2, String::from("primary")),
+                (3, String::from("depth")),
+            ]);
+        }
+
+        if matches!(
+            instructions[0].as_str(),
+            "image-icc-profile" | "conflicting-icc-profiles"
+        ) {
+            image_details.color_icc_profile = Some(
+                B::try_from_vec(
+                    moxcms::ColorProfile::new_bt2020_hlg()
+                        .encode()
+                        .expected_error()?,
+                )
+                .expected_error()?,
+            );
+        }
+
+        if instructions[0] == "warnings" {
+            image_details.warnings = Some(vec![String::from("image warning")]);
+        }
+
+        // Never finishes loading and updates a file in `TMPDIR` until killed
+        if instructions[0] == "heartbeat" {
+            let path = std::env::temp_dir().join(&instructions[1]);
+            for n in 0_u64.. {
+                std::fs::write(&path, n.to_string()).expected_error()?;
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
+        }
+
+        // File in `TMPDIR` that exists as long as the image state
+        let tmp_file = if instructions[0] == "tmp-file" {
+            let path = std::env::temp_dir().join(&instructions[1]);
+            std::fs::write(&path, []).expected_error()?;
+            Some(path)
+        } else {
+            None
+        };
+
+        Ok((
+            ImgDecoder {
+                instructions,
+                frame_cancellation: Default::default(),
+                interrupted: false,
+                tmp_file,
+            },
+            image_details,
+        ))
     }
 
     fn specific_frame<B: ByteData>(
         &mut self,
-        _frame_request: FrameRequest,
+        frame_request: FrameRequest,
     ) -> Result<Frame<B>, ProcessError> {
         match self.instructions[0].as_str() {
             "panic-next-step" => panic!("Requested frame panic"),
@@ -74,6 +162,78 @@ impl LoaderImplementation for ImgDecoder {
                 eprintln!("Entering infinte loop as requested");
                 loop {}
             }
+            // Loops until the frame is cancelled, afterwards returns frames
+            "interruptible-loop-next-step" => {
+                while !self.interrupted {
+                    if self.frame_cancellation.is_cancelled() {
+                        self.interrupted = true;
+                        return Err(ProcessError::Canceled);
+                    }
+
+                    std::thread::sleep(std::time::Duration::from_millis(1));
+                }
+
+                Frame::new(
+                    1,
+                    1,
+                    MemoryFormat::G8,
+                    B::try_from_slice(&[0]).expected_error()?,
+                )
+                .expected_error()
+            }
+            // Sleeps for the given number of milliseconds before returning
+            "sleep-next-step" => {
+                let ms = self.instructions[1].parse().unwrap();
+                std::thread::sleep(std::time::Duration::from_millis(ms));
+
+                Frame::new(
+                    1,
+                    1,
+                    MemoryFormat::G8,
+                    B::try_from_slice(&[0]).expected_error()?,
+                )
+                .expected_error()
+            }
+            // Frame that only covers a part of the canvas
+            "frame-composition" => {
+                let mut frame = Frame::new(
+                    1,
+                    1,
+                    MemoryFormat::G8,
+                    B::try_from_slice(&[0]).expected_error()?,
+                )
+                .expected_error()?;
+                frame.details.animation_disposal_method = Some(DisposalMethod::Background);
+                frame.details.animation_blend_op = Some(BlendOp::Over);
+                frame.details.animation_offset = Some((0, 0));
+
+                Ok(frame)
+            }
+            // ICC profile is part of the image details
+            "image-icc-profile" => Frame::new(
+                1,
+                1,
+                MemoryFormat::R8g8b8,
+                B::try_from_slice(&[10, 20, 30]).expected_error()?,
+            )
+            .expected_error(),
+            // Frame profile differs from the image profile
+            "conflicting-icc-profiles" => {
+                let mut frame = Frame::new(
+                    1,
+                    1,
+                    MemoryFormat::R8g8b8,
+                    B::try_from_slice(&[10, 20, 30]).expected_error()?,
+                )
+                .expected_error()?;
+
+                frame.details.color_icc_profile = Some(
+                    B::try_from_vec(moxcms::ColorProfile::new_srgb().encode().expected_error()?)
+                        .expected_error()?,
+                );
+
+                Ok(frame)
+            }
             "half-with-icc-profile" => {
                 let mut frame = Frame::new(
                     1,
@@ -94,6 +254,107 @@ impl LoaderImplementation for ImgDecoder {
 
                 Ok(frame)
             }
+            "auxiliary-image" => {
+                let (value, auxiliary_type) = match frame_request.auxiliary_image {
+                    Some(index) => (100 + index as u8, Some(String::from("test:auxiliary"))),
+                    None => (0, None),
+                };
+
+                let mut frame = Frame::new(
+                    1,
+                    1,
+                    MemoryFormat::G8,
+                    B::try_from_slice(&[value]).expected_error()?,
+                )
+                .expected_error()?;
+                frame.details.auxiliary_type = auxiliary_type;
+
+                Ok(frame)
+            }
+            // Returns the item ID as value, the primary item by default
+            "image-items" => Frame::new(
+                1,
+                1,
+                MemoryFormat::G8,
+                B::try_from_slice(&[frame_request.image_item.unwrap_or(2) as u8])
+                    .expected_error()?,
+            )
+            .expected_error(),
+            // Texture has one byte per pixel more than announced
+            "oversized-texture" => {
+                let mut frame = Frame::new(
+                    1,
+                    1,
+                    MemoryFormat::G8,
+                    B::try_from_slice(&[0]).expected_error()?,
+                )
+                .expected_error()?;
+                let size: usize = self.instructions[1].parse().unwrap();
+                frame.texture = B::try_from_vec(vec![0; size]).expected_error()?;
+
+                Ok(frame)
+            }
+            "warnings" => {
+                let mut frame = Frame::new(
+                    1,
+                    1,
+                    MemoryFormat::G8,
+                    B::try_from_slice(&[0]).expected_error()?,
+                )
+                .expected_error()?;
+                frame.details.warnings = Some(vec![String::from("frame warning")]);
+
+                Ok(frame)
+            }
+            // 16-bit image that is decoded with 8 bits if requested
+            "reduced-precision" => {
+                let mut frame = if frame_request.reduced_precision {
+                    Frame::new(
+                        1,
+                        1,
+                        MemoryFormat::G8,
+                        B::try_from_slice(&[0x12]).expected_error()?,
+                    )
+                } else {
+                    Frame::new(
+                        1,
+                        1,
+                        MemoryFormat::G16,
+                        B::try_from_slice(&0x1234_u16.to_ne_bytes()).expected_error()?,
+                    )
+                }
+                .expected_error()?;
+                frame.details.info_bit_depth = Some(16);
+                frame.details.reduced_precision = Some(frame_request.reduced_precision);
+
+                Ok(frame)
+            }
+            other => panic!("unknwon instruction {other}"),
+        }
+    }
+
+    fn frame_cancellation(&mut self, cancellation: FrameCancellation) -> bool {
+        self.frame_cancellation = cancellation;
+        true
+    }
+
+    fn next_rows<B: ByteData>(
+        &mut self,
+        _rows_request: RowsRequest,
+    ) -> Result<Frame<B>, ProcessError> {
+        match self.instructions[0].as_str() {
+            "sleep-next-step" => {
+                let ms = self.instructions[1].parse().unwrap();
+                std::thread::sleep(std::time::Duration::from_millis(ms));
+
+                Frame::new(
+                    1,
+                    1,
+                    MemoryFormat::G8,
+                    B::try_from_slice(&[0]).expected_error()?,
+                )
+                .expected_error()
+            }
             other => panic!("unknwon instruction {other}"),
         }
     }
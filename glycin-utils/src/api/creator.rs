@@ -57,13 +57,27 @@ impl<B: ByteData> NewImage<B> {
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 #[cfg_attr(feature = "external", derive(DeserializeDict, SerializeDict, Type))]
 #[cfg_attr(feature = "external", zvariant(signature = "dict"))]
 #[non_exhaustive]
 pub struct EncodingOptions {
     pub quality: Option<u8>,
     pub compression: Option<u8>,
+    /// Embed a thumbnail with this maximum edge length into the output
+    ///
+    /// Formats that don't support embedded thumbnails ignore this option.
+    pub embed_thumbnail: Option<u32>,
+    /// Bits per channel of the output
+    ///
+    /// PNG supports 8 and 16 bits for grayscale and RGB images. When reducing
+    /// the depth, ordered dithering is applied. Other formats, memory
+    /// formats, and depths keep the depth of the image data.
+    pub bit_depth: Option<u8>,
+    /// Number of times an animation is played, `0` is infinite
+    ///
+    /// Only used if more than one frame is passed.
+    pub loop_count: Option<u32>,
 }
 
 #[derive(Debug)]
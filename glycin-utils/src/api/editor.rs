@@ -36,10 +36,45 @@ pub trait EditorImplementation: Send + Sync + Sized + RefUnwindSafe + 'static {
         Ok(SparseEditorOutput::from(complete))
     }
 
+    /// Like [`apply_sparse`](Self::apply_sparse) with encoding options
+    ///
+    /// The options only apply if the image has to be re-encoded. Editors that
+    /// implement [`apply_complete_with_options`](Self::apply_complete_with_options)
+    /// should implement this as well. By default, the options are ignored.
+    fn apply_sparse_with_options<B: ByteData>(
+        &self,
+        operations: Operations,
+        _encoding_options: EncodingOptions,
+    ) -> Result<SparseEditorOutput<B>, ProcessError> {
+        Self::apply_sparse(self, operations)
+    }
+
+    /// Report which operations can be applied sparsely
+    ///
+    /// Editors that implement sparse changes in
+    /// [`apply_sparse`](Self::apply_sparse) should implement this as well.
+    /// By default, no operation is reported as sparse.
+    fn sparse_operations(&self, _operations: Operations) -> Result<SparseOperations, ProcessError> {
+        Ok(SparseOperations::default())
+    }
+
     fn apply_complete<B: ByteData>(
         &self,
         operations: Operations,
     ) -> Result<CompleteEditorOutput<B>, ProcessError>;
+
+    /// Like [`apply_complete`](Self::apply_complete) with encoding options
+    ///
+    /// Editors that support options like
+    /// [`EncodingOptions::embed_thumbnail`] have to implement this. By
+    /// default, the options are ignored.
+    fn apply_complete_with_options<B: ByteData>(
+        &self,
+        operations: Operations,
+        _encoding_options: EncodingOptions,
+    ) -> Result<CompleteEditorOutput<B>, ProcessError> {
+        Self::apply_complete(self, operations)
+    }
 }
 
 #[cfg(feature = "external")]
@@ -104,7 +139,10 @@ impl<B: ByteData> SparseEditorOutput<B> {
         SparseEditorOutput {
             byte_changes: Some(byte_changes),
             data: None,
-            info: EditorOutputInfo { lossless: true },
+            info: EditorOutputInfo {
+                lossless: true,
+                ..Default::default()
+            },
         }
     }
 
@@ -143,6 +181,26 @@ impl<B: ByteData> From<CompleteEditorOutput<B>> for SparseEditorOutput<B> {
     }
 }
 
+/// Operations that an editor can apply as byte changes
+///
+/// Contains the indices of the operations that
+/// [`EditorImplementation::apply_sparse`] can apply without re-encoding the
+/// image, if they were passed on their own. All other operations require
+/// re-encoding.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "external", derive(DeserializeDict, SerializeDict, Type))]
+#[cfg_attr(feature = "external", zvariant(signature = "dict"))]
+#[non_exhaustive]
+pub struct SparseOperations {
+    pub sparse: Vec<u32>,
+}
+
+impl SparseOperations {
+    pub fn new(sparse: Vec<u32>) -> Self {
+        Self { sparse }
+    }
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "external", derive(DeserializeDict, SerializeDict, Type))]
 #[cfg_attr(feature = "external", zvariant(signature = "dict"))]
@@ -229,7 +287,10 @@ impl<B: ByteData> CompleteEditorOutput<B> {
 
     pub fn new_lossless(data: Vec<u8>) -> Result<Self, ProcessError> {
         let data = B::try_from_vec(data).expected_error()?;
-        let info = EditorOutputInfo { lossless: true };
+        let info = EditorOutputInfo {
+            lossless: true,
+            ..Default::default()
+        };
         Ok(Self { data, info })
     }
 
@@ -250,7 +311,10 @@ impl<B: ByteData> CompleteEditorOutput<B> {
 }
 
 #[derive(Debug, Default, Clone)]
-#[cfg_attr(feature = "external", derive(DeserializeDict, SerializeDict, Type))]
+#[cfg_attr(
+    feature = "external",
+    derive(Type, serde::Serialize, serde::Deserialize)
+)]
 #[cfg_attr(feature = "external", zvariant(signature = "dict"))]
 #[non_exhaustive]
 pub struct EditorOutputInfo {
@@ -258,5 +322,22 @@ pub struct EditorOutputInfo {
     ///
     /// Operations are considered lossless when all metadata are kept, no image
     /// data is lost, and no image quality is lost.
+    #[cfg_attr(feature = "external", serde(with = "as_value"))]
     pub lossless: bool,
+    /// A thumbnail was embedded as requested via
+    /// [`EncodingOptions::embed_thumbnail`]
+    #[cfg_attr(feature = "external", serde(with = "as_value", default))]
+    pub thumbnail_embedded: bool,
+    /// Bits per channel of the output, if known
+    ///
+    /// See [`EncodingOptions::bit_depth`](crate::EncodingOptions::bit_depth).
+    #[cfg_attr(
+        feature = "external",
+        serde(
+            with = "as_value::optional",
+            skip_serializing_if = "Option::is_none",
+            default
+        )
+    )]
+    pub bit_depth: Option<u8>,
 }
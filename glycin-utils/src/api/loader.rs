@@ -1,8 +1,12 @@
 use std::collections::BTreeMap;
 use std::io::Read;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 
-use glycin_common::{ColorProfilePreference, MemoryFormat, MemoryFormatInfo};
+use glycin_common::{
+    BlendOp, ColorProfilePreference, DisposalMethod, MemoryFormat, MemoryFormatInfo,
+};
 use gufo_common::orientation::Orientation;
 use gufo_common::physical_dimension;
 #[cfg(feature = "external")]
@@ -12,7 +16,7 @@ use zbus::zvariant::{self, Optional, Type};
 
 use crate::error::DimensionTooLargerError;
 use crate::safe_math::{SafeConversion, SafeMath};
-use crate::{ByteData, FungibleMemory, Limits, MemoryAllocationError, ProcessError};
+use crate::{ByteData, FungibleMemory, Limits, MemoryAllocationError, ProcessError, RemoteError};
 
 pub trait LoaderImplementation: Send + Sync + Sized + 'static {
     fn load<B: ByteData, R: Read + Send + 'static>(
@@ -25,6 +29,71 @@ pub trait LoaderImplementation: Send + Sync + Sized + 'static {
         &mut self,
         frame_request: FrameRequest,
     ) -> Result<Frame<T>, ProcessError>;
+
+    /// Enables interrupting [`specific_frame`](Self::specific_frame)
+    ///
+    /// Loaders that can interrupt decoding keep `cancellation`, check it
+    /// regularly while decoding a frame, and return
+    /// [`ProcessError::Canceled`] once it is cancelled. They have to return
+    /// `true` to signal that they support it.
+    ///
+    /// The default implementation returns `false`. Cancelling a frame request
+    /// then kills the loader process instead.
+    fn frame_cancellation(&mut self, cancellation: FrameCancellation) -> bool {
+        let _ = cancellation;
+        false
+    }
+
+    /// Decodes the next rows of the first frame
+    ///
+    /// Returns the rows following the rows of the previous call as a frame
+    /// with the width of the image and at most
+    /// [`max_rows`](RowsRequest::max_rows) rows. Returns
+    /// [`ProcessError::NoMoreFrames`] once all rows have been returned.
+    /// Orientation, scaling, and clipping are not applied to rows.
+    ///
+    /// The default implementation returns an error for loaders that can't
+    /// decode incrementally. Row requests are not combined with
+    /// [`specific_frame`](Self::specific_frame) for the same image.
+    fn next_rows<T: ByteData>(
+        &mut self,
+        rows_request: RowsRequest,
+    ) -> Result<Frame<T>, ProcessError> {
+        let _ = rows_request;
+        Err(ProcessError::expected(
+            &"The loader does not support decoding rows incrementally",
+        ))
+    }
+}
+
+/// Signals that the frame currently being decoded is no longer needed
+///
+/// See [`LoaderImplementation::frame_cancellation`].
+#[derive(Debug, Clone, Default)]
+pub struct FrameCancellation(Arc<AtomicBool>);
+
+impl FrameCancellation {
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Returns [`ProcessError::Canceled`] if the frame has been cancelled
+    pub fn check(&self) -> Result<(), ProcessError> {
+        if self.is_cancelled() {
+            Err(ProcessError::Canceled)
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Resets the cancellation before the next frame is decoded
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
 }
 
 #[cfg(feature = "external")]
@@ -52,6 +121,53 @@ pub struct InitializationDetails {
     pub base_dir: Option<std::path::PathBuf>,
     #[cfg_attr(feature = "external", serde(with = "as_value"))]
     pub limits: Limits,
+    /// Display scale factor
+    ///
+    /// Used to select between multiple embedded image sizes.
+    #[cfg_attr(
+        feature = "external",
+        serde(with = "optional", skip_serializing_if = "Option::is_none")
+    )]
+    pub scale_factor: Option<f64>,
+    /// Additional files that belong to the image
+    ///
+    /// These are files like XMP sidecars that are readable at the given paths.
+    /// Loaders that have no use for them ignore them.
+    #[cfg_attr(
+        feature = "external",
+        serde(with = "optional", skip_serializing_if = "Option::is_none")
+    )]
+    pub sidecars: Option<Vec<std::path::PathBuf>>,
+    /// Return the complete frames of truncated animations
+    ///
+    /// Loaders that support this end the animation before the first frame
+    /// that can't be decoded instead of returning an error for it. The last
+    /// complete frame carries a warning about the truncation.
+    #[cfg_attr(feature = "external", serde(with = "as_value", default))]
+    pub allow_partial: bool,
+    /// Return animation frames as stored in the file
+    ///
+    /// Loaders that support this don't composite the frames onto the canvas.
+    /// Each frame only covers its own area and carries its offset, disposal
+    /// method, and blend operation in [`FrameDetails`].
+    #[cfg_attr(feature = "external", serde(with = "as_value", default))]
+    pub raw_animation_frames: bool,
+    /// Whether CMYK JPEGs store inverted values like Adobe tools do
+    ///
+    /// If not set, the values are treated as inverted if the file contains
+    /// an Adobe APP14 marker.
+    #[cfg_attr(
+        feature = "external",
+        serde(with = "optional", skip_serializing_if = "Option::is_none")
+    )]
+    pub assume_adobe_cmyk: Option<bool>,
+    /// Client can handle frames with CMYK memory formats
+    ///
+    /// Only then loaders return [`MemoryFormat::C8m8y8k8`] frames, together
+    /// with the CMYK ICC profile. Otherwise, loaders convert CMYK data to RGB
+    /// themselves.
+    #[cfg_attr(feature = "external", serde(with = "as_value", default))]
+    pub accepts_cmyk: bool,
 }
 
 #[cfg(feature = "external")]
@@ -82,6 +198,62 @@ pub struct FrameRequest {
     /// Get first frame, if previously selected frame was the last one
     #[cfg_attr(feature = "external", serde(with = "as_value", default = "true_const"))]
     pub loop_animation: bool,
+    /// Return the auxiliary image with this index instead of the next frame
+    ///
+    /// Auxiliary images, like depth maps or alpha planes, are indexed
+    /// independently from animation frames. Requesting them does not advance
+    /// the animation.
+    #[cfg_attr(
+        feature = "external",
+        serde(with = "optional", skip_serializing_if = "Option::is_none", default)
+    )]
+    pub auxiliary_image: Option<u32>,
+    /// Return the item with this ID from the image container
+    ///
+    /// The IDs are listed in [`ImageDetails::image_items`]. Like auxiliary
+    /// images, requesting them does not advance the animation.
+    #[cfg_attr(
+        feature = "external",
+        serde(with = "optional", skip_serializing_if = "Option::is_none", default)
+    )]
+    pub image_item: Option<u32>,
+    /// Decode with a lower bit depth if that is faster
+    ///
+    /// Loaders that honor this set [`FrameDetails::reduced_precision`].
+    #[cfg_attr(feature = "external", serde(with = "as_value", default))]
+    pub reduced_precision: bool,
+    /// Only decode the scans up to this one, counting from `1`
+    ///
+    /// For progressive images, like progressive JPEGs. The total number of
+    /// scans is reported via [`FrameDetails::n_progressive_scans`]. Requesting
+    /// scans does not advance the animation.
+    #[cfg_attr(
+        feature = "external",
+        serde(with = "optional", skip_serializing_if = "Option::is_none", default)
+    )]
+    pub progressive_scan: Option<u32>,
+}
+
+/// Request for the next rows of an image
+///
+/// See [`LoaderImplementation::next_rows`].
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "external",
+    derive(serde::Deserialize, serde::Serialize, Type)
+)]
+#[cfg_attr(feature = "external", zvariant(signature = "dict"))]
+#[non_exhaustive]
+pub struct RowsRequest {
+    /// Maximum number of rows to return
+    #[cfg_attr(feature = "external", serde(with = "as_value"))]
+    pub max_rows: u32,
+}
+
+impl RowsRequest {
+    pub fn new(max_rows: u32) -> Self {
+        Self { max_rows }
+    }
 }
 
 impl Default for FrameRequest {
@@ -90,6 +262,10 @@ impl Default for FrameRequest {
             scale: None,
             clip: None,
             loop_animation: true,
+            auxiliary_image: None,
+            image_item: None,
+            reduced_precision: false,
+            progressive_scan: None,
         }
     }
 }
@@ -171,6 +347,32 @@ pub struct ImageDetails<B: ByteData> {
         )
     )]
     pub info_format_name: Option<String>,
+    /// Name of the container the image data are stored in
+    ///
+    /// Only set for formats that are based on a generic container format, like
+    /// `"ISOBMFF"` for AVIF and HEIC or `"RIFF"` for WebP.
+    #[cfg_attr(
+        feature = "external",
+        serde(
+            with = "as_value::optional",
+            skip_serializing_if = "Option::is_none",
+            default
+        )
+    )]
+    pub info_container_format: Option<String>,
+    /// Size of the image source in bytes
+    ///
+    /// Set by glycin if the size is known. Values set by loaders are
+    /// ignored.
+    #[cfg_attr(
+        feature = "external",
+        serde(
+            with = "as_value::optional",
+            skip_serializing_if = "Option::is_none",
+            default
+        )
+    )]
+    pub source_byte_size: Option<u64>,
     /// Textual description of the image dimensions
     #[cfg_attr(
         feature = "external",
@@ -220,6 +422,94 @@ pub struct ImageDetails<B: ByteData> {
         )
     )]
     pub transformation_orientation: Option<Orientation>,
+    /// ICC profile that applies to the whole image
+    ///
+    /// Loaders should set this if the profile is available without decoding
+    /// a frame. Frames can still provide their own profile.
+    #[cfg_attr(
+        feature = "external",
+        serde(
+            with = "as_value::optional",
+            skip_serializing_if = "Option::is_none",
+            default
+        )
+    )]
+    pub color_icc_profile: Option<B>,
+    /// Intrinsic width divided by height
+    ///
+    /// Only has to be set if it can't be derived from `width` and `height`,
+    /// like for vector images that only define a `viewBox`.
+    #[cfg_attr(
+        feature = "external",
+        serde(
+            with = "as_value::optional",
+            skip_serializing_if = "Option::is_none",
+            default
+        )
+    )]
+    pub intrinsic_aspect_ratio: Option<f64>,
+    /// Number of animation frames
+    #[cfg_attr(
+        feature = "external",
+        serde(
+            with = "as_value::optional",
+            skip_serializing_if = "Option::is_none",
+            default
+        )
+    )]
+    pub n_animation_frames: Option<u32>,
+    /// Number of auxiliary images
+    ///
+    /// See [`FrameRequest::auxiliary_image`].
+    #[cfg_attr(
+        feature = "external",
+        serde(
+            with = "as_value::optional",
+            skip_serializing_if = "Option::is_none",
+            default
+        )
+    )]
+    pub n_auxiliary_images: Option<u32>,
+    /// Items of image containers like HEIF, as ID and role
+    ///
+    /// Roles are `primary`, `image`, `thumbnail`, `depth`, `alpha`, and
+    /// `auxiliary`. Without a [`FrameRequest::image_item`], the primary item
+    /// is decoded. See [`FrameRequest::image_item`].
+    #[cfg_attr(
+        feature = "external",
+        serde(
+            with = "as_value::optional",
+            skip_serializing_if = "Option::is_none",
+            default
+        )
+    )]
+    pub image_items: Option<Vec<(u32, String)>>,
+    /// Whether any pixel is not fully opaque
+    ///
+    /// Only set if this is known without decoding all pixels. For example,
+    /// images without alpha channel are always opaque. Images with an alpha
+    /// channel can still be fully opaque.
+    #[cfg_attr(
+        feature = "external",
+        serde(
+            with = "as_value::optional",
+            skip_serializing_if = "Option::is_none",
+            default
+        )
+    )]
+    pub has_transparency: Option<bool>,
+    /// Caveats of the successful loading, like ignored metadata
+    ///
+    /// Warnings are no errors and don't affect whether loading succeeded.
+    #[cfg_attr(
+        feature = "external",
+        serde(
+            with = "as_value::optional",
+            skip_serializing_if = "Option::is_none",
+            default
+        )
+    )]
+    pub warnings: Option<Vec<String>>,
 }
 
 impl<B: ByteData> ImageDetails<B> {
@@ -231,11 +521,20 @@ impl<B: ByteData> ImageDetails<B> {
             dimensions_inch: None,
             info_dimensions_text: None,
             info_format_name: None,
+            info_container_format: None,
+            source_byte_size: None,
             metadata_exif: None,
             metadata_xmp: None,
             metadata_key_value: None,
             transformation_ignore_exif: false,
             transformation_orientation: None,
+            color_icc_profile: None,
+            intrinsic_aspect_ratio: None,
+            n_animation_frames: None,
+            n_auxiliary_images: None,
+            image_items: None,
+            has_transparency: None,
+            warnings: None,
         }
     }
 
@@ -246,12 +545,21 @@ impl<B: ByteData> ImageDetails<B> {
             #[allow(deprecated)]
             dimensions_inch: self.dimensions_inch,
             info_format_name: self.info_format_name,
+            info_container_format: self.info_container_format,
+            source_byte_size: self.source_byte_size,
             info_dimensions_text: self.info_dimensions_text,
             metadata_exif: self.metadata_exif.map(B::into_fungible),
             metadata_xmp: self.metadata_xmp.map(B::into_fungible),
             metadata_key_value: self.metadata_key_value,
             transformation_ignore_exif: self.transformation_ignore_exif,
             transformation_orientation: self.transformation_orientation,
+            color_icc_profile: self.color_icc_profile.map(B::into_fungible),
+            intrinsic_aspect_ratio: self.intrinsic_aspect_ratio,
+            n_animation_frames: self.n_animation_frames,
+            n_auxiliary_images: self.n_auxiliary_images,
+            image_items: self.image_items,
+            has_transparency: self.has_transparency,
+            warnings: self.warnings,
         }
     }
 
@@ -262,12 +570,21 @@ impl<B: ByteData> ImageDetails<B> {
             #[allow(deprecated)]
             dimensions_inch: self.dimensions_inch,
             info_format_name: self.info_format_name,
+            info_container_format: self.info_container_format,
+            source_byte_size: self.source_byte_size,
             info_dimensions_text: self.info_dimensions_text,
             metadata_exif: self.metadata_exif.map(|x| x.into_other()).transpose()?,
             metadata_xmp: self.metadata_xmp.map(|x| x.into_other()).transpose()?,
             metadata_key_value: self.metadata_key_value,
             transformation_ignore_exif: self.transformation_ignore_exif,
             transformation_orientation: self.transformation_orientation,
+            color_icc_profile: self.color_icc_profile.map(|x| x.into_other()).transpose()?,
+            intrinsic_aspect_ratio: self.intrinsic_aspect_ratio,
+            n_animation_frames: self.n_animation_frames,
+            n_auxiliary_images: self.n_auxiliary_images,
+            image_items: self.image_items,
+            has_transparency: self.has_transparency,
+            warnings: self.warnings,
         })
     }
 
@@ -280,6 +597,10 @@ impl<B: ByteData> ImageDetails<B> {
             metadata_xmp.initial_seal().await?;
         }
 
+        if let Some(color_icc_profile) = &mut self.color_icc_profile {
+            color_icc_profile.initial_seal().await?;
+        }
+
         Ok(())
     }
 
@@ -292,6 +613,10 @@ impl<B: ByteData> ImageDetails<B> {
             metadata_xmp.final_seal().await?;
         }
 
+        if let Some(color_icc_profile) = &mut self.color_icc_profile {
+            color_icc_profile.final_seal().await?;
+        }
+
         Ok(())
     }
 }
@@ -308,6 +633,13 @@ impl<B: ByteData> Default for FrameDetails<B> {
             n_frame: None,
             pixel_density: None,
             physical_size: None,
+            auxiliary_type: None,
+            warnings: None,
+            reduced_precision: None,
+            animation_disposal_method: None,
+            animation_blend_op: None,
+            animation_offset: None,
+            n_progressive_scans: None,
         }
     }
 }
@@ -329,6 +661,10 @@ pub struct Frame<B: ByteData> {
     pub height: u32,
     /// Line stride
     pub stride: u32,
+    #[cfg_attr(
+        feature = "external",
+        serde(deserialize_with = "deserialize_memory_format")
+    )]
     pub memory_format: MemoryFormat,
     pub texture: B,
     /// Duration to show frame for animations.
@@ -338,6 +674,57 @@ pub struct Frame<B: ByteData> {
     pub details: FrameDetails<B>,
 }
 
+#[cfg(feature = "external")]
+const UNKNOWN_MEMORY_FORMAT: &str = "Unknown memory format: ";
+
+/// Deserializes a memory format that might be unknown to this version
+///
+/// Newer loaders can send memory formats that did not exist when this
+/// version was built. The resulting error is turned into
+/// [`RemoteError::UnknownMemoryFormat`] via
+/// [`RemoteError::detect_unknown_memory_format`].
+#[cfg(feature = "external")]
+fn deserialize_memory_format<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<MemoryFormat, D::Error> {
+    let value = <u32 as serde::Deserialize>::deserialize(deserializer)?;
+
+    i32::try_from(value)
+        .ok()
+        .and_then(|x| MemoryFormat::try_from(x).ok())
+        .ok_or_else(|| serde::de::Error::custom(format!("{UNKNOWN_MEMORY_FORMAT}{}", value as i32)))
+}
+
+/// Raw value of a memory format that is unknown to this version
+///
+/// Returns `None` if the error was not caused by an unknown memory format.
+#[cfg(feature = "external")]
+fn unknown_memory_format(err: &zbus::Error) -> Option<i32> {
+    if let zbus::Error::Variant(zvariant::Error::Message(msg)) = err {
+        msg.strip_prefix(UNKNOWN_MEMORY_FORMAT)?.parse().ok()
+    } else {
+        None
+    }
+}
+
+#[cfg(feature = "external")]
+impl RemoteError {
+    /// Replaces failures to deserialize an unknown memory format
+    ///
+    /// zbus only reports the failure as a message. This has to be called on
+    /// errors of requests that return a [`Frame`] to obtain
+    /// [`RemoteError::UnknownMemoryFormat`] instead.
+    pub fn detect_unknown_memory_format(self) -> Self {
+        if let Self::ZBus(err) = &self
+            && let Some(value) = unknown_memory_format(err)
+        {
+            Self::UnknownMemoryFormat { value }
+        } else {
+            self
+        }
+    }
+}
+
 #[cfg(feature = "external")]
 impl<B: ByteData + zvariant::Type> zvariant::Type for Frame<B> {
     const SIGNATURE: &'static zvariant::Signature = <(
@@ -534,6 +921,82 @@ pub struct FrameDetails<B: ByteData> {
         )
     )]
     pub physical_size: Option<physical_dimension::PhysicalSize>,
+    /// Type of an auxiliary image, like an URN identifying depth maps
+    #[cfg_attr(
+        feature = "external",
+        serde(
+            with = "as_value::optional",
+            skip_serializing_if = "Option::is_none",
+            default
+        )
+    )]
+    pub auxiliary_type: Option<String>,
+    /// Caveats of the successful decoding, like a recovered truncated frame
+    ///
+    /// Warnings are no errors and don't affect whether decoding succeeded.
+    #[cfg_attr(
+        feature = "external",
+        serde(
+            with = "as_value::optional",
+            skip_serializing_if = "Option::is_none",
+            default
+        )
+    )]
+    pub warnings: Option<Vec<String>>,
+    /// Frame was decoded with a lower bit depth than stored in the file
+    ///
+    /// Only set by loaders that honored [`FrameRequest::reduced_precision`].
+    #[cfg_attr(
+        feature = "external",
+        serde(
+            with = "as_value::optional",
+            skip_serializing_if = "Option::is_none",
+            default
+        )
+    )]
+    pub reduced_precision: Option<bool>,
+    /// How the frame's area is treated before rendering the next frame
+    #[cfg_attr(
+        feature = "external",
+        serde(
+            with = "as_value::optional",
+            skip_serializing_if = "Option::is_none",
+            default
+        )
+    )]
+    pub animation_disposal_method: Option<DisposalMethod>,
+    /// How the frame is combined with the canvas
+    #[cfg_attr(
+        feature = "external",
+        serde(
+            with = "as_value::optional",
+            skip_serializing_if = "Option::is_none",
+            default
+        )
+    )]
+    pub animation_blend_op: Option<BlendOp>,
+    /// Position `(x, y)` of the frame's top left corner within the canvas
+    #[cfg_attr(
+        feature = "external",
+        serde(
+            with = "as_value::optional",
+            skip_serializing_if = "Option::is_none",
+            default
+        )
+    )]
+    pub animation_offset: Option<(u32, u32)>,
+    /// Number of scans of a progressive image
+    ///
+    /// See [`FrameRequest::progressive_scan`].
+    #[cfg_attr(
+        feature = "external",
+        serde(
+            with = "as_value::optional",
+            skip_serializing_if = "Option::is_none",
+            default
+        )
+    )]
+    pub n_progressive_scans: Option<u32>,
 }
 
 impl<B: ByteData> FrameDetails<B> {
@@ -548,6 +1011,13 @@ impl<B: ByteData> FrameDetails<B> {
             n_frame: self.n_frame,
             pixel_density: self.pixel_density,
             physical_size: self.physical_size,
+            auxiliary_type: self.auxiliary_type,
+            warnings: self.warnings,
+            reduced_precision: self.reduced_precision,
+            animation_disposal_method: self.animation_disposal_method,
+            animation_blend_op: self.animation_blend_op,
+            animation_offset: self.animation_offset,
+            n_progressive_scans: self.n_progressive_scans,
         }
     }
 
@@ -562,6 +1032,13 @@ impl<B: ByteData> FrameDetails<B> {
             n_frame: self.n_frame,
             pixel_density: self.pixel_density,
             physical_size: self.physical_size,
+            auxiliary_type: self.auxiliary_type,
+            warnings: self.warnings,
+            reduced_precision: self.reduced_precision,
+            animation_disposal_method: self.animation_disposal_method,
+            animation_blend_op: self.animation_blend_op,
+            animation_offset: self.animation_offset,
+            n_progressive_scans: self.n_progressive_scans,
         })
     }
 
@@ -581,3 +1058,75 @@ impl<B: ByteData> FrameDetails<B> {
         Ok(())
     }
 }
+
+#[cfg(all(test, feature = "external"))]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, serde::Serialize, serde::Deserialize, Type)]
+    struct RawFrame {
+        memory_format: u32,
+    }
+
+    #[derive(Debug, serde::Deserialize, Type)]
+    struct FrameMemoryFormat {
+        #[serde(deserialize_with = "deserialize_memory_format")]
+        memory_format: MemoryFormat,
+    }
+
+    fn deserialize(memory_format: u32) -> Result<MemoryFormat, zbus::Error> {
+        let ctxt = zvariant::serialized::Context::new_dbus(zvariant::LE, 0);
+        let data = zvariant::to_bytes(ctxt, &RawFrame { memory_format })?;
+        let (frame, _) = data.deserialize::<FrameMemoryFormat>()?;
+
+        Ok(frame.memory_format)
+    }
+
+    #[test]
+    fn initialization_details_missing_fields() {
+        // Older peers don't send the newer fields
+        let ctxt = zvariant::serialized::Context::new_dbus(zvariant::LE, 0);
+        let dict = std::collections::HashMap::<String, zvariant::Value>::new();
+        let data = zvariant::to_bytes(ctxt, &dict).unwrap();
+        let (details, _) = data.deserialize::<InitializationDetails>().unwrap();
+
+        assert!(!details.allow_partial);
+        assert!(!details.raw_animation_frames);
+        assert!(!details.accepts_cmyk);
+        assert_eq!(details.assume_adobe_cmyk, None);
+    }
+
+    #[test]
+    fn known_memory_format() {
+        assert_eq!(
+            deserialize(MemoryFormat::G8 as u32).unwrap(),
+            MemoryFormat::G8
+        );
+    }
+
+    #[test]
+    fn unknown_memory_format_value() {
+        let err = deserialize(999).unwrap_err();
+        assert_eq!(unknown_memory_format(&err), Some(999));
+
+        let err = deserialize(u32::MAX).unwrap_err();
+        assert_eq!(unknown_memory_format(&err), Some(-1));
+
+        assert_eq!(unknown_memory_format(&zbus::Error::InvalidReply), None);
+    }
+
+    #[test]
+    fn detect_unknown_memory_format() {
+        let err = RemoteError::ZBus(deserialize(999).unwrap_err());
+        assert!(matches!(
+            err.detect_unknown_memory_format(),
+            RemoteError::UnknownMemoryFormat { value: 999 }
+        ));
+
+        let err = RemoteError::ZBus(zbus::Error::InvalidReply);
+        assert!(matches!(
+            err.detect_unknown_memory_format(),
+            RemoteError::ZBus(zbus::Error::InvalidReply)
+        ));
+    }
+}
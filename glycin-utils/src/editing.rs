@@ -3,16 +3,20 @@ use std::sync::Arc;
 
 use crate::{DimensionTooLargerError, FungibleMemory, LocalMemory};
 
+mod change_bit_depth;
 mod change_memory_format;
 mod clip;
+mod lut;
 mod operations;
 mod orientation;
 
+pub use change_bit_depth::{bit_depth, change_bit_depth};
 pub use change_memory_format::change_memory_format;
 pub use clip::clip;
 use glycin_common::{ExtendedMemoryFormat, OperationId};
 use gufo_common::math::MathError;
 use gufo_common::read::ReadError;
+pub use lut::apply_lut_3d;
 pub use operations::apply_operations;
 pub use orientation::change_orientation;
 
@@ -55,6 +59,10 @@ pub enum Error {
     ZerocopyConvertError(String),
     #[error("Unknown operation: {0:?}")]
     UnknownOperation(OperationId),
+    #[error("Unsupported memory format: {0:?}")]
+    UnsupportedMemoryFormat(ExtendedMemoryFormat),
+    #[error("Invalid 3D LUT: {0}")]
+    InvalidLut3D(String),
     #[error("Failed to build rayon thread pool: {0}")]
     ThreadPoolBuildError(#[from] Arc<rayon::ThreadPoolBuildError>),
 }
@@ -0,0 +1,181 @@
+use glycin_common::{ExtendedMemoryFormat, MemoryFormatInfo};
+use gufo_common::math::Checked;
+
+use crate::editing::{EditingFrame, Error};
+use crate::{FungibleMemory, MemoryFormat};
+
+/// Thresholds for ordered dithering
+const BAYER_MATRIX: [[u32; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// Changes the bits per channel to `bit_depth`
+///
+/// Supports conversions between 8 and 16 bits for grayscale and RGB formats
+/// with and without straight alpha. When reducing the depth, ordered dithering
+/// is used to avoid banding. Other formats and depths are returned unchanged.
+/// Use [`bit_depth`] to check the depth of the result.
+pub fn change_bit_depth(
+    frame: EditingFrame<FungibleMemory>,
+    bit_depth: u8,
+) -> Result<EditingFrame<FungibleMemory>, Error> {
+    let ExtendedMemoryFormat::Basic(memory_format) = frame.memory_format else {
+        return Ok(frame);
+    };
+
+    let Some(target_format) = with_bit_depth(memory_format, bit_depth) else {
+        return Ok(frame);
+    };
+
+    if target_format == memory_format {
+        return Ok(frame);
+    }
+
+    let n_channels = memory_format.n_channels() as usize;
+    let src_pixel_n_bytes = memory_format.n_bytes().usize();
+    let src_row_length = (Checked::new(frame.width as usize) * src_pixel_n_bytes).check()?;
+    let target_stride = (Checked::new(frame.width) * target_format.n_bytes().u32()).check()?;
+    let total_size: usize =
+        (Checked::new(frame.height as usize) * target_stride as usize).check()?;
+
+    let mut texture = vec![0; total_size];
+
+    let src_rows = frame.texture.chunks(frame.stride as usize);
+    let target_rows = texture.chunks_exact_mut(target_stride as usize);
+
+    if src_rows.len() < frame.height as usize {
+        return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+    }
+
+    for (y, (src_row, target_row)) in src_rows.zip(target_rows).enumerate() {
+        let src_row = src_row
+            .get(..src_row_length)
+            .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?;
+
+        if bit_depth == 16 {
+            for (src, target) in src_row.iter().zip(target_row.chunks_exact_mut(2)) {
+                target.copy_from_slice(&(*src as u16 * 257).to_ne_bytes());
+            }
+        } else {
+            let src_pixels = src_row.chunks_exact(src_pixel_n_bytes);
+            let target_pixels = target_row.chunks_exact_mut(n_channels);
+
+            for (x, (src, target)) in src_pixels.zip(target_pixels).enumerate() {
+                let threshold = (BAYER_MATRIX[y % 4][x % 4] * 2 + 1) * u16::MAX as u32 / 32;
+
+                for (src, target) in src.chunks_exact(2).zip(target.iter_mut()) {
+                    let scaled = u16::from_ne_bytes([src[0], src[1]]) as u32 * 255;
+                    let value = scaled / u16::MAX as u32;
+                    let remainder = scaled % u16::MAX as u32;
+                    *target = (value + u32::from(remainder > threshold)) as u8;
+                }
+            }
+        }
+    }
+
+    Ok(EditingFrame {
+        width: frame.width,
+        height: frame.height,
+        stride: target_stride,
+        memory_format: ExtendedMemoryFormat::Basic(target_format),
+        texture: FungibleMemory::from_vec(texture),
+    })
+}
+
+/// Bits per channel of the memory format
+///
+/// Returns `None` for formats with channels of different sizes.
+pub fn bit_depth(memory_format: ExtendedMemoryFormat) -> Option<u8> {
+    let n_channels = memory_format.n_channels();
+    let n_bytes = memory_format.n_bytes().u8();
+
+    if n_bytes % n_channels == 0 {
+        Some(n_bytes / n_channels * 8)
+    } else {
+        None
+    }
+}
+
+fn with_bit_depth(memory_format: MemoryFormat, bit_depth: u8) -> Option<MemoryFormat> {
+    use MemoryFormat::*;
+
+    let formats = match memory_format {
+        G8 | G16 => (G8, G16),
+        G8a8 | G16a16 => (G8a8, G16a16),
+        R8g8b8 | R16g16b16 => (R8g8b8, R16g16b16),
+        R8g8b8a8 | R16g16b16a16 => (R8g8b8a8, R16g16b16a16),
+        _ => return None,
+    };
+
+    match bit_depth {
+        8 => Some(formats.0),
+        16 => Some(formats.1),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn frame(
+        width: u32,
+        memory_format: MemoryFormat,
+        texture: Vec<u8>,
+    ) -> EditingFrame<FungibleMemory> {
+        let stride = width * memory_format.n_bytes().u32();
+        EditingFrame {
+            width,
+            height: texture.len() as u32 / stride,
+            stride,
+            memory_format: ExtendedMemoryFormat::Basic(memory_format),
+            texture: FungibleMemory::from_vec(texture),
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let texture = vec![0, 1, 127, 128, 254, 255];
+        let frame = frame(2, MemoryFormat::R8g8b8, texture.clone());
+
+        let frame = change_bit_depth(frame, 16).unwrap();
+        assert!(matches!(
+            frame.memory_format,
+            ExtendedMemoryFormat::Basic(MemoryFormat::R16g16b16)
+        ));
+        assert_eq!(bit_depth(frame.memory_format), Some(16));
+
+        let frame = change_bit_depth(frame, 8).unwrap();
+        assert_eq!(&*frame.texture, texture.as_slice());
+        assert_eq!(bit_depth(frame.memory_format), Some(8));
+    }
+
+    #[test]
+    fn dithering() {
+        // Value between 8-bit 100 and 101
+        let value = (100 * 257 + 128u16).to_ne_bytes();
+        let texture = value.repeat(16);
+        let frame = change_bit_depth(frame(4, MemoryFormat::G16, texture), 8).unwrap();
+
+        assert!(frame.texture.iter().all(|x| *x == 100 || *x == 101));
+        assert!(frame.texture.contains(&100));
+        assert!(frame.texture.contains(&101));
+    }
+
+    #[test]
+    fn unsupported() {
+        let frame = frame(1, MemoryFormat::R8g8b8, vec![1, 2, 3]);
+        let frame = change_bit_depth(frame, 12).unwrap();
+        assert!(matches!(
+            frame.memory_format,
+            ExtendedMemoryFormat::Basic(MemoryFormat::R8g8b8)
+        ));
+
+        assert_eq!(
+            bit_depth(ExtendedMemoryFormat::Basic(MemoryFormat::R32g32b32Float)),
+            Some(32)
+        );
+        assert_eq!(
+            bit_depth(ExtendedMemoryFormat::Basic(MemoryFormat::R16g16b16Float)),
+            Some(16)
+        );
+    }
+}
@@ -49,6 +49,8 @@ pub fn change_memory_format(
                 && (!src_format.source_definition().contains(&Source::Opaque)
                     || !target_format.target_definition().contains(&Target::A))
                 && !target_format.target_definition().contains(&Target::RgbAvg)
+                && !src_format.is_cmyk()
+                && !target_format.is_cmyk()
             {
                 let mut source_target_index_map = [0; 4];
                 for (n, target) in target_format.target_definition().iter().enumerate() {
@@ -82,6 +84,8 @@ pub fn change_memory_format(
                 && (!src_format.source_definition().contains(&Source::Opaque)
                     || !target_format.target_definition().contains(&Target::A))
                 && !target_format.target_definition().contains(&Target::RgbAvg)
+                && !src_format.is_cmyk()
+                && !target_format.is_cmyk()
             {
                 let mut source_target_index_map = [0; 4];
                 for (n, target) in target_format.target_definition().iter().enumerate() {
@@ -114,24 +118,16 @@ pub fn change_memory_format(
                 });
             } else {
                 target_rows.into_par_iter().for_each(|(y, new_row)| {
-                    for x in 0..frame.width as usize {
-                        let x_ = x * src_pixel_n_bytes;
-
-                        // src bytes for pixel
-                        let i0 = x_ + y * frame.stride as usize;
-                        let i1 = i0 + src_pixel_n_bytes;
-
-                        // target bytes for pixel
-                        let k0 = x * target_pixel_n_bytes;
-                        let k1 = k0 + target_pixel_n_bytes;
-
-                        MemoryFormat::transform(
-                            src_format,
-                            &src_data[i0..i1],
-                            target_format,
-                            &mut new_row[k0..k1],
-                        );
-                    }
+                    // src bytes for row
+                    let i0 = y * frame.stride as usize;
+
+                    MemoryFormat::transform_row(
+                        src_format,
+                        &src_data[i0..],
+                        target_format,
+                        new_row,
+                        frame.width as usize,
+                    );
                 });
             }
         });
@@ -0,0 +1,194 @@
+use glycin_common::{BinaryData, ExtendedMemoryFormat, MemoryFormatInfo};
+use gufo_common::math::Checked;
+
+use crate::editing::{EditingFrame, Error};
+use crate::{FungibleMemory, MemoryFormat};
+
+/// Color grades the frame via a 3D LUT
+///
+/// See [`Operation::ApplyLut3D`](glycin_common::Operation::ApplyLut3D) for
+/// the layout of `lut`. The memory format of the frame is kept. Extended
+/// memory formats are not supported.
+pub fn apply_lut_3d(
+    frame: EditingFrame<FungibleMemory>,
+    lut: &BinaryData,
+    size: u32,
+) -> Result<EditingFrame<FungibleMemory>, Error> {
+    let ExtendedMemoryFormat::Basic(memory_format) = frame.memory_format else {
+        return Err(Error::UnsupportedMemoryFormat(frame.memory_format));
+    };
+
+    let n_entries = (Checked::new(size as usize) * size as usize * size as usize * 3).check()?;
+    let n_bytes: usize = (Checked::new(n_entries) * size_of::<f32>()).check()?;
+    if size < 2 || lut.as_slice().len() != n_bytes {
+        return Err(Error::InvalidLut3D(format!(
+            "Expected {n_bytes} bytes for size {size}, got {}",
+            lut.as_slice().len()
+        )));
+    }
+
+    let lut = lut
+        .as_slice()
+        .chunks_exact(size_of::<f32>())
+        .map(|x| f32::from_ne_bytes([x[0], x[1], x[2], x[3]]))
+        .collect::<Vec<_>>();
+
+    let pixel_n_bytes = memory_format.n_bytes().usize();
+    let row_length = (Checked::new(frame.width as usize) * pixel_n_bytes).check()?;
+    let stride = (Checked::new(frame.width) * memory_format.n_bytes().u32()).check()?;
+    let total_size: usize = (Checked::new(frame.height as usize) * stride as usize).check()?;
+
+    let mut texture = vec![0; total_size];
+
+    let src_rows = frame.texture.chunks(frame.stride as usize);
+    let target_rows = texture.chunks_exact_mut(stride as usize);
+
+    if src_rows.len() < frame.height as usize {
+        return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+    }
+
+    for (src_row, target_row) in src_rows.zip(target_rows) {
+        let src_row = src_row
+            .get(..row_length)
+            .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?;
+
+        let src_pixels = src_row.chunks_exact(pixel_n_bytes);
+        let target_pixels = target_row.chunks_exact_mut(pixel_n_bytes);
+
+        for (src, target) in src_pixels.zip(target_pixels) {
+            MemoryFormat::transform_rgb(memory_format, src, memory_format, target, |rgb| {
+                lookup(&lut, size as usize, rgb)
+            });
+        }
+    }
+
+    Ok(EditingFrame {
+        width: frame.width,
+        height: frame.height,
+        stride,
+        memory_format: frame.memory_format,
+        texture: FungibleMemory::from_vec(texture),
+    })
+}
+
+/// Trilinear interpolation between the grid points surrounding `rgb`
+fn lookup(lut: &[f32], size: usize, rgb: [f32; 3]) -> [f32; 3] {
+    let max_index = (size - 1) as f32;
+
+    let mut index = [0; 3];
+    let mut fraction = [0.; 3];
+    for (n, value) in rgb.into_iter().enumerate() {
+        let value = if value.is_nan() {
+            0.
+        } else {
+            value.clamp(0., 1.)
+        };
+        let position = value * max_index;
+        // Use the last cell for the upper boundary
+        let lower = (position.floor() as usize).min(size - 2);
+        index[n] = lower;
+        fraction[n] = position - lower as f32;
+    }
+
+    let entry = |r: usize, g: usize, b: usize| {
+        let i = 3 * (r + g * size + b * size * size);
+        [lut[i], lut[i + 1], lut[i + 2]]
+    };
+
+    let [r, g, b] = index;
+    let [fr, fg, fb] = fraction;
+
+    let mut result = [0.; 3];
+    for (n, value) in result.iter_mut().enumerate() {
+        let c00 = lerp(entry(r, g, b)[n], entry(r + 1, g, b)[n], fr);
+        let c10 = lerp(entry(r, g + 1, b)[n], entry(r + 1, g + 1, b)[n], fr);
+        let c01 = lerp(entry(r, g, b + 1)[n], entry(r + 1, g, b + 1)[n], fr);
+        let c11 = lerp(entry(r, g + 1, b + 1)[n], entry(r + 1, g + 1, b + 1)[n], fr);
+
+        let c0 = lerp(c00, c10, fg);
+        let c1 = lerp(c01, c11, fg);
+
+        *value = lerp(c0, c1, fb);
+    }
+
+    result
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn identity_lut(size: u32) -> BinaryData {
+        BinaryData::from_f32(&identity_values(size))
+    }
+
+    fn identity_values(size: u32) -> Vec<f32> {
+        let max = (size - 1) as f32;
+        let mut lut = Vec::new();
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    lut.extend([r as f32 / max, g as f32 / max, b as f32 / max]);
+                }
+            }
+        }
+        lut
+    }
+
+    fn frame(memory_format: MemoryFormat, texture: Vec<u8>) -> EditingFrame<FungibleMemory> {
+        let stride = memory_format.n_bytes().u32();
+        EditingFrame {
+            width: 1,
+            height: texture.len() as u32 / stride,
+            stride,
+            memory_format: ExtendedMemoryFormat::Basic(memory_format),
+            texture: FungibleMemory::from_vec(texture),
+        }
+    }
+
+    #[test]
+    fn identity() {
+        let texture: Vec<u8> = (0..=255).flat_map(|x| [x, 255 - x, x / 2, x]).collect();
+
+        for size in [17, 33, 65] {
+            let frame = frame(MemoryFormat::R8g8b8a8, texture.clone());
+            let frame = apply_lut_3d(frame, &identity_lut(size), size).unwrap();
+
+            assert_eq!(&*frame.texture, texture.as_slice());
+        }
+    }
+
+    #[test]
+    fn clamped() {
+        // Inverting LUT
+        let values: Vec<f32> = identity_values(2).into_iter().map(|x| 1. - x).collect();
+        let lut = BinaryData::from_f32(&values);
+
+        let texture = [2., -1., 0.25_f32]
+            .into_iter()
+            .flat_map(f32::to_ne_bytes)
+            .collect();
+        let frame = frame(MemoryFormat::R32g32b32Float, texture);
+        let frame = apply_lut_3d(frame, &lut, 2).unwrap();
+
+        let values: Vec<f32> = frame
+            .texture
+            .chunks_exact(4)
+            .map(|x| f32::from_ne_bytes(x.try_into().unwrap()))
+            .collect();
+        assert_eq!(values, [0., 1., 0.75]);
+    }
+
+    #[test]
+    fn invalid() {
+        let frame = frame(MemoryFormat::R8g8b8, vec![0, 0, 0]);
+        assert!(matches!(
+            apply_lut_3d(frame, &identity_lut(17), 16),
+            Err(Error::InvalidLut3D(_))
+        ));
+    }
+}
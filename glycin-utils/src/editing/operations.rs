@@ -22,6 +22,11 @@ pub fn apply_operations(
             Operation::Clip(clip) => {
                 frame = editing::clip(frame, *clip)?;
             }
+            Operation::ApplyLut3D { lut, size } => {
+                frame = editing::apply_lut_3d(frame, lut, *size)?;
+            }
+            // Only affects the encoding, not the pixel data
+            Operation::Optimize => {}
             op => return Err(Error::UnknownOperation(op.id())),
         }
     }
@@ -25,6 +25,13 @@ pub enum RemoteError {
     NoMoreFrames,
     MemoryAllocationError(String),
     Panic,
+    /// The frame uses a memory format that is unknown to this version
+    ///
+    /// Only created when receiving a frame, see
+    /// [`RemoteError::detect_unknown_memory_format`].
+    UnknownMemoryFormat {
+        value: i32,
+    },
 }
 
 #[cfg(not(feature = "external"))]
@@ -50,6 +57,7 @@ impl ProcessError {
             ProcessError::ConversionTooLargerError => RemoteError::ConversionTooLargerError,
             err @ ProcessError::OutOfMemory { .. } => RemoteError::OutOfMemory(err.to_string()),
             ProcessError::NoMoreFrames => RemoteError::NoMoreFrames,
+            ProcessError::Canceled => RemoteError::Aborted,
         }
     }
 
@@ -63,6 +71,7 @@ impl ProcessError {
             ProcessError::ConversionTooLargerError => RemoteError::ConversionTooLargerError,
             err @ ProcessError::OutOfMemory { .. } => RemoteError::OutOfMemory(err.to_string()),
             ProcessError::NoMoreFrames => RemoteError::NoMoreFrames,
+            ProcessError::Canceled => RemoteError::Aborted,
         }
     }
 }
@@ -82,6 +91,8 @@ pub enum ProcessError {
     OutOfMemory { location: Location },
     #[error("No more frames available")]
     NoMoreFrames,
+    #[error("Decoding was canceled")]
+    Canceled,
 }
 
 impl ProcessError {
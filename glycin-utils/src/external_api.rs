@@ -8,8 +8,35 @@ use std::panic::UnwindSafe;
 pub use editor::*;
 pub use loader::*;
 
-use crate::RemoteError;
+use zbus::zvariant::OwnedObjectPath;
+
+use crate::{GenericContexts, ProcessError, RemoteError};
 
 fn catch_unwind<R, F: FnOnce() -> R + UnwindSafe>(f: F) -> Result<R, RemoteError> {
     std::panic::catch_unwind(f).map_err(|_| RemoteError::Panic)
 }
+
+/// Drops the state of the first `n_images` images below `path_prefix`
+///
+/// Used by the `shutdown` methods of loaders and editors. Pending calls of an
+/// image are aborted via the cell returned by `dropped`.
+async fn drop_images<I: zbus::object_server::Interface>(
+    object_server: &zbus::ObjectServer,
+    path_prefix: &str,
+    n_images: u64,
+    dropped: impl Fn(&I) -> &async_lock::OnceCell<()>,
+    into_remote_error: fn(ProcessError) -> RemoteError,
+) -> Result<(), RemoteError> {
+    for image_id in 1..n_images {
+        let path = OwnedObjectPath::try_from(format!("{path_prefix}/{image_id}"))
+            .internal_error()
+            .map_err(into_remote_error)?;
+
+        if let Ok(image) = object_server.interface::<_, I>(&path).await {
+            let _ = dropped(&*image.get().await).set(()).await;
+            object_server.remove::<I, _>(&path).await?;
+        }
+    }
+
+    Ok(())
+}
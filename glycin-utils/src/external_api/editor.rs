@@ -18,6 +18,7 @@ use crate::{ByteData, MemoryAllocationError, SharedMemory, api};
 #[non_exhaustive]
 pub struct EditRequest {
     pub operations: SharedMemory,
+    pub encoding_options: Option<api::EncodingOptions>,
 }
 
 impl EditRequest {
@@ -27,7 +28,18 @@ impl EditRequest {
             .expected_error()
             .map_err(|x| x.into_editor_error())?;
         let operations = SharedMemory::try_from_vec(operations)?;
-        Ok(Self { operations })
+        Ok(Self {
+            operations,
+            encoding_options: None,
+        })
+    }
+
+    pub fn set_encoding_options(&mut self, encoding_options: api::EncodingOptions) {
+        self.encoding_options = Some(encoding_options);
+    }
+
+    pub fn encoding_options(&self) -> api::EncodingOptions {
+        self.encoding_options.clone().unwrap_or_default()
     }
 
     pub fn operations(&self) -> Result<Operations, RemoteError> {
@@ -119,6 +131,30 @@ impl<E: api::EditorImplementation> Editor<E> {
 
         Ok(dbus_image)
     }
+
+    /// Drops the state of all editable images before the process gets killed
+    ///
+    /// See the loader's `shutdown` method for details.
+    async fn shutdown(
+        &self,
+        #[zbus(object_server)] object_server: &zbus::ObjectServer,
+    ) -> Result<(), RemoteError> {
+        log::debug!("Shutting down");
+
+        let n_images = *self
+            .image_id
+            .lock()
+            .map_err(|err| RemoteError::InternalLoaderError(err.to_string()))?;
+
+        super::drop_images(
+            object_server,
+            "/org/gnome/glycin/editable_image",
+            n_images,
+            |image: &EditableImage<E>| &image.dropped,
+            ProcessError::into_editor_error,
+        )
+        .await
+    }
 }
 
 pub struct EditableImage<E: api::EditorImplementation> {
@@ -135,12 +171,13 @@ impl<E: api::EditorImplementation> EditableImage<E> {
     ) -> Result<api::SparseEditorOutput<SharedMemory>, RemoteError> {
         edit_request.initial_seal().await?;
         let operations = edit_request.operations()?;
+        let encoding_options = edit_request.encoding_options();
 
         let editor_implementation = self.editor_implementation.clone();
         let mut editor_output = blocking::unblock(move || {
-            super::catch_unwind(|| {
+            super::catch_unwind(move || {
                 editor_implementation
-                    .apply_sparse(operations)
+                    .apply_sparse_with_options(operations, encoding_options)
                     .map_err(|x| x.into_loader_error())
             })
             .flatten()
@@ -153,6 +190,26 @@ impl<E: api::EditorImplementation> EditableImage<E> {
         }
     }
 
+    /// Indices of the operations that can be applied sparsely
+    async fn sparse_operations(
+        &self,
+        mut edit_request: EditRequest,
+    ) -> Result<api::SparseOperations, RemoteError> {
+        edit_request.initial_seal().await?;
+        let operations = edit_request.operations()?;
+
+        let editor_implementation = self.editor_implementation.clone();
+        blocking::unblock(move || {
+            super::catch_unwind(|| {
+                editor_implementation
+                    .sparse_operations(operations)
+                    .map_err(|x| x.into_editor_error())
+            })
+            .flatten()
+        })
+        .await
+    }
+
     /// Same as [`Self::apply()`] but without potential to return sparse changes
     async fn apply_complete(
         &self,
@@ -160,12 +217,13 @@ impl<E: api::EditorImplementation> EditableImage<E> {
     ) -> Result<api::CompleteEditorOutput<SharedMemory>, RemoteError> {
         edit_request.initial_seal().await?;
         let operations = edit_request.operations()?;
+        let encoding_options = edit_request.encoding_options();
 
         let editor_implementation = self.editor_implementation.clone();
         let mut editor_output = blocking::unblock(move || {
             super::catch_unwind(move || {
                 editor_implementation
-                    .apply_complete(operations)
+                    .apply_complete_with_options(operations, encoding_options)
                     .map_err(|x| x.into_loader_error())
             })
             .flatten()
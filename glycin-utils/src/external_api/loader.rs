@@ -26,7 +26,7 @@ impl<T: api::LoaderImplementation> Loader<T> {
         let fd = OwnedFd::from(init_request.fd);
         let stream = UnixStream::from(fd);
 
-        let (loader_state, image_info) = blocking::unblock(|| {
+        let (mut loader_state, image_info) = blocking::unblock(|| {
             super::catch_unwind(|| {
                 T::load(stream, init_request.mime_type, init_request.details)
                     .map_err(|x| x.into_loader_error())
@@ -35,6 +35,11 @@ impl<T: api::LoaderImplementation> Loader<T> {
         .await
         .flatten()?;
 
+        let frame_cancellation = api::FrameCancellation::default();
+        let frame_cancellation = loader_state
+            .frame_cancellation(frame_cancellation.clone())
+            .then_some(frame_cancellation);
+
         let image_id = {
             let lock = self.image_id.lock();
             let mut image_id = match lock {
@@ -60,6 +65,7 @@ impl<T: api::LoaderImplementation> Loader<T> {
                     loader_implementation: Arc::new(Mutex::new(Box::new(loader_state))),
                     path: path.clone(),
                     dropped: Default::default(),
+                    frame_cancellation,
                 },
             )
             .await
@@ -68,12 +74,41 @@ impl<T: api::LoaderImplementation> Loader<T> {
 
         Ok(dbus_image)
     }
+
+    /// Drops the state of all images before the process gets killed
+    ///
+    /// This gives loaders the chance to remove temporary files and close
+    /// resources in the `Drop` implementation of their state. Images that are
+    /// still decoding a frame are only dropped once the decoding finishes,
+    /// which might be too late.
+    async fn shutdown(
+        &self,
+        #[zbus(object_server)] object_server: &zbus::ObjectServer,
+    ) -> Result<(), RemoteError> {
+        log::debug!("Shutting down");
+
+        let n_images = *self
+            .image_id
+            .lock()
+            .map_err(|err| RemoteError::InternalLoaderError(err.to_string()))?;
+
+        super::drop_images(
+            object_server,
+            "/org/gnome/glycin/image",
+            n_images,
+            |image: &Image<T>| &image.dropped,
+            ProcessError::into_loader_error,
+        )
+        .await
+    }
 }
 
 pub struct Image<T: api::LoaderImplementation> {
     pub loader_implementation: Arc<Mutex<Box<T>>>,
     pub path: OwnedObjectPath,
     dropped: async_lock::OnceCell<()>,
+    /// Only set if the loader supports interrupting the decoding
+    frame_cancellation: Option<api::FrameCancellation>,
 }
 
 impl<T: api::LoaderImplementation> Image<T> {
@@ -92,6 +127,13 @@ impl<T: api::LoaderImplementation> Image<T> {
         &self,
         frame_request: api::FrameRequest,
     ) -> Result<api::Frame<SharedMemory>, RemoteError> {
+        // Reset when the request arrives, such that a cancellation sent while this
+        // request waits for a previous frame to release the lock is not lost. The
+        // previous frame might then run to completion instead of being interrupted.
+        if let Some(frame_cancellation) = &self.frame_cancellation {
+            frame_cancellation.reset();
+        }
+
         let loader_implementation = self.loader_implementation.clone();
         let mut frame_request = blocking::unblock(move || {
             let mut loader_implementation = loader_implementation.lock().map_err(|err| {
@@ -115,6 +157,50 @@ impl<T: api::LoaderImplementation> Image<T> {
         }
     }
 
+    /// Returns the next rows of the first frame
+    ///
+    /// See [`api::LoaderImplementation::next_rows`].
+    async fn next_rows(
+        &self,
+        rows_request: api::RowsRequest,
+    ) -> Result<api::Frame<SharedMemory>, RemoteError> {
+        let loader_implementation = self.loader_implementation.clone();
+        let rows = blocking::unblock(move || {
+            let mut loader_implementation = loader_implementation.lock().map_err(|err| {
+                RemoteError::InternalLoaderError(format!(
+                    "Failed to lock loader state for operation: {err}"
+                ))
+            })?;
+
+            super::catch_unwind(move || {
+                loader_implementation
+                    .next_rows(rows_request)
+                    .map_err(|x| x.into_loader_error())
+            })
+            .flatten()
+        })
+        .fuse();
+
+        futures_util::select! {
+            result = rows => result,
+            _ = self.dropped.wait().fuse() => Err(RemoteError::Aborted),
+        }
+    }
+
+    /// Interrupts the frame that is currently being decoded
+    ///
+    /// Returns `false` if the loader does not support this. The process stays
+    /// usable for further frame requests.
+    async fn cancel_frame(&self) -> bool {
+        if let Some(frame_cancellation) = &self.frame_cancellation {
+            log::debug!("Canceling frame of {}", self.path);
+            frame_cancellation.cancel();
+            true
+        } else {
+            false
+        }
+    }
+
     async fn done(
         &self,
         #[zbus(object_server)] object_server: &zbus::ObjectServer,
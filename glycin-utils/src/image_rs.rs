@@ -9,6 +9,7 @@ use crate::{
 #[derive(Default, Clone, Debug)]
 pub struct Handler {
     pub format_name: Option<String>,
+    pub container_format: Option<String>,
     pub default_bit_depth: Option<u8>,
     pub supports_two_alpha_modes: bool,
     pub supports_two_grayscale_modes: bool,
@@ -21,6 +22,12 @@ impl Handler {
         self
     }
 
+    pub fn container_format(mut self, container_format: impl ToString) -> Self {
+        self.container_format = Some(container_format.to_string());
+
+        self
+    }
+
     pub fn default_bit_depth(mut self, default_bit_depth: u8) -> Self {
         self.default_bit_depth = Some(default_bit_depth);
 
@@ -43,6 +50,10 @@ impl Handler {
         let (width, height) = decoder.dimensions();
         let mut info = ImageDetails::new(width, height);
         info.info_format_name.clone_from(&self.format_name);
+        info.info_container_format
+            .clone_from(&self.container_format);
+        // Without alpha channel, the image is known to be opaque
+        info.has_transparency = (!decoder.color_type().has_alpha()).then_some(false);
 
         info
     }
@@ -1,6 +1,7 @@
 // Copyright (c) 2024 GNOME Foundation Inc.
 
 use std::ffi::{c_int, c_void};
+use std::io::{Read, Write};
 use std::os::fd::FromRawFd;
 use std::os::unix::net::UnixStream;
 use std::sync::Mutex;
@@ -38,6 +39,7 @@ impl DbusServer {
         log::info!("Loader {description} startup");
 
         let mut dbus_fd_str = None;
+        let mut dbus_token_stdin = false;
         let mut args = std::env::args().skip(1);
         while let Some(arg) = args.next() {
             match arg.as_str() {
@@ -45,6 +47,10 @@ impl DbusServer {
                     dbus_fd_str = args.next();
                 }
 
+                "--dbus-token-stdin" => {
+                    dbus_token_stdin = true;
+                }
+
                 _ => {
                     log::warn!("Stopping command line parsing at unknown argument: {arg:?}");
                     break;
@@ -64,7 +70,22 @@ impl DbusServer {
 
         log::debug!("Creating zbus connection to glycin");
 
-        let unix_stream: UnixStream = unsafe { UnixStream::from_raw_fd(dbus_fd) };
+        let mut unix_stream: UnixStream = unsafe { UnixStream::from_raw_fd(dbus_fd) };
+
+        if dbus_token_stdin {
+            log::debug!("Sending authentication token");
+
+            let mut token = Vec::new();
+            if let Err(err) = std::io::stdin().read_to_end(&mut token) {
+                log::error!("Failed to read authentication token from stdin: {err}");
+                std::process::exit(2);
+            }
+
+            if let Err(err) = unix_stream.write_all(&token) {
+                log::error!("Failed to send authentication token: {err}");
+                std::process::exit(2);
+            }
+        }
 
         #[cfg(feature = "tokio")]
         let unix_stream =
@@ -28,8 +28,8 @@ pub use error::*;
 #[cfg(feature = "external")]
 pub use external_api::*;
 pub use glycin_common::{
-    ExtendedMemoryFormat, MemoryFormat, MemoryFormatInfo, MemoryFormatSelection, Operation,
-    Operations,
+    BlendOp, DisposalMethod, ExtendedMemoryFormat, MemoryFormat, MemoryFormatInfo,
+    MemoryFormatSelection, Operation, Operations,
 };
 #[cfg(all(feature = "loader-utils", feature = "external"))]
 pub use instruction_handler::*;
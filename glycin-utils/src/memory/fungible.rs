@@ -13,6 +13,30 @@ impl FungibleMemory {
     pub fn from_vec(vec: Vec<u8>) -> Self {
         FungibleMemory::LocalMemory(vec)
     }
+
+    /// Memfd backing the memory, `None` for local memory
+    #[cfg(feature = "external")]
+    pub fn memfd(&self) -> Option<std::os::fd::BorrowedFd<'_>> {
+        match self {
+            FungibleMemory::SharedMemory(shared) => Some(shared.memfd()),
+            FungibleMemory::LocalMemory(_) => None,
+        }
+    }
+
+    /// Like [`ByteData::into_gbytes`], but also returns the memfd backing
+    /// shared memory
+    #[cfg(feature = "glib")]
+    pub fn into_gbytes_and_memfd(
+        self,
+    ) -> Result<(glib::Bytes, Option<std::os::fd::OwnedFd>), MemoryAllocationError> {
+        match self {
+            FungibleMemory::LocalMemory(local) => Ok((glib::Bytes::from_owned(local), None)),
+            #[cfg(feature = "external")]
+            FungibleMemory::SharedMemory(shared) => shared
+                .into_gbytes_and_memfd()
+                .map(|(bytes, memfd)| (bytes, Some(memfd))),
+        }
+    }
 }
 
 impl ByteData for FungibleMemory {
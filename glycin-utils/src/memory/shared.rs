@@ -1,5 +1,5 @@
 use std::ops::{Deref, DerefMut};
-use std::os::fd::{AsRawFd, OwnedFd};
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, OwnedFd};
 
 use log::warn;
 use nix::fcntl;
@@ -143,6 +143,21 @@ impl ByteData for SharedMemory {
 
     #[cfg(feature = "glib")]
     fn into_gbytes(self) -> Result<glib::Bytes, MemoryAllocationError> {
+        self.into_gbytes_and_memfd().map(|(bytes, _)| bytes)
+    }
+}
+
+impl SharedMemory {
+    /// Memfd that contains the data
+    pub fn memfd(&self) -> BorrowedFd<'_> {
+        self.memfd.as_fd()
+    }
+
+    #[cfg(feature = "glib")]
+    /// Like [`ByteData::into_gbytes`], but keeps the memfd open
+    ///
+    /// The memfd is moved out instead of being duplicated.
+    pub fn into_gbytes_and_memfd(self) -> Result<(glib::Bytes, OwnedFd), MemoryAllocationError> {
         if !matches!(self.mmap, Some(MMapOptions::ReadOnly(_))) {
             panic!("SharedMemory is lacking final seal.");
         }
@@ -173,11 +188,11 @@ impl ByteData for SharedMemory {
             }
         }
 
-        unsafe { gbytes_from_mmap(self.memfd.as_raw_fd()) }
+        let bytes = unsafe { gbytes_from_mmap(self.memfd.as_raw_fd()) }?;
+
+        Ok((bytes, self.memfd))
     }
-}
 
-impl SharedMemory {
     fn new_memfd(size: u64) -> std::io::Result<(OwnedFd, memmap::MmapMut)> {
         let memfd = nix::sys::memfd::memfd_create(
             c"glycin-frame",
@@ -8,6 +8,102 @@ use glib::translate::*;
 
 use crate::ffi;
 
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy)]
+#[non_exhaustive]
+#[doc(alias = "GlyColorMode")]
+pub enum ColorMode {
+    #[doc(alias = "GLY_COLOR_MODE_SRGB")]
+    Srgb,
+    #[doc(alias = "GLY_COLOR_MODE_CICP")]
+    Cicp,
+    #[doc(hidden)]
+    __Unknown(i32),
+}
+
+#[doc(hidden)]
+impl IntoGlib for ColorMode {
+    type GlibType = ffi::GlyColorMode;
+
+    #[inline]
+    fn into_glib(self) -> ffi::GlyColorMode {
+        match self {
+            Self::Srgb => ffi::GLY_COLOR_MODE_SRGB,
+            Self::Cicp => ffi::GLY_COLOR_MODE_CICP,
+            Self::__Unknown(value) => value,
+        }
+    }
+}
+
+#[doc(hidden)]
+impl FromGlib<ffi::GlyColorMode> for ColorMode {
+    #[inline]
+    unsafe fn from_glib(value: ffi::GlyColorMode) -> Self {
+        skip_assert_initialized!();
+
+        match value {
+            ffi::GLY_COLOR_MODE_SRGB => Self::Srgb,
+            ffi::GLY_COLOR_MODE_CICP => Self::Cicp,
+            value => Self::__Unknown(value),
+        }
+    }
+}
+
+impl StaticType for ColorMode {
+    #[inline]
+    #[doc(alias = "gly_color_mode_get_type")]
+    fn static_type() -> glib::Type {
+        unsafe { from_glib(ffi::gly_color_mode_get_type()) }
+    }
+}
+
+impl glib::HasParamSpec for ColorMode {
+    type ParamSpec = glib::ParamSpecEnum;
+    type SetValue = Self;
+    type BuilderFn = fn(&str, Self) -> glib::ParamSpecEnumBuilder<Self>;
+
+    fn param_spec_builder() -> Self::BuilderFn {
+        Self::ParamSpec::builder_with_default
+    }
+}
+
+impl glib::value::ValueType for ColorMode {
+    type Type = Self;
+}
+
+unsafe impl<'a> glib::value::FromValue<'a> for ColorMode {
+    type Checker = glib::value::GenericValueTypeChecker<Self>;
+
+    #[inline]
+    unsafe fn from_value(value: &'a glib::Value) -> Self {
+        skip_assert_initialized!();
+        unsafe { from_glib(glib::gobject_ffi::g_value_get_enum(value.to_glib_none().0)) }
+    }
+}
+
+impl ToValue for ColorMode {
+    #[inline]
+    fn to_value(&self) -> glib::Value {
+        let mut value = glib::Value::for_value_type::<Self>();
+        unsafe {
+            glib::gobject_ffi::g_value_set_enum(value.to_glib_none_mut().0, self.into_glib());
+        }
+        value
+    }
+
+    #[inline]
+    fn value_type(&self) -> glib::Type {
+        Self::static_type()
+    }
+}
+
+impl From<ColorMode> for glib::Value {
+    #[inline]
+    fn from(v: ColorMode) -> Self {
+        skip_assert_initialized!();
+        ToValue::to_value(&v)
+    }
+}
+
 #[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy)]
 #[non_exhaustive]
 #[doc(alias = "GlyLoaderError")]
@@ -182,6 +278,10 @@ pub enum MemoryFormat {
     G16a16,
     #[doc(alias = "GLY_MEMORY_G16")]
     G16,
+    #[doc(alias = "GLY_MEMORY_C8M8Y8K8")]
+    C8m8y8k8,
+    #[doc(alias = "GLY_MEMORY_C16M16Y16K16")]
+    C16m16y16k16,
     #[doc(hidden)]
     __Unknown(i32),
 }
@@ -231,6 +331,8 @@ impl IntoGlib for MemoryFormat {
             Self::G16a16Premultiplied => ffi::GLY_MEMORY_G16A16_PREMULTIPLIED,
             Self::G16a16 => ffi::GLY_MEMORY_G16A16,
             Self::G16 => ffi::GLY_MEMORY_G16,
+            Self::C8m8y8k8 => ffi::GLY_MEMORY_C8M8Y8K8,
+            Self::C16m16y16k16 => ffi::GLY_MEMORY_C16M16Y16K16,
             Self::__Unknown(value) => value,
         }
     }
@@ -267,6 +369,8 @@ impl FromGlib<ffi::GlyMemoryFormat> for MemoryFormat {
             ffi::GLY_MEMORY_G16A16_PREMULTIPLIED => Self::G16a16Premultiplied,
             ffi::GLY_MEMORY_G16A16 => Self::G16a16,
             ffi::GLY_MEMORY_G16 => Self::G16,
+            ffi::GLY_MEMORY_C8M8Y8K8 => Self::C8m8y8k8,
+            ffi::GLY_MEMORY_C16M16Y16K16 => Self::C16m16y16k16,
             value => Self::__Unknown(value),
         }
     }
@@ -3,9 +3,12 @@
 // from gir-files
 // DO NOT EDIT
 
+use std::boxed::Box as Box_;
+
+use glib::signal::{SignalHandlerId, connect_raw};
 use glib::translate::*;
 
-use crate::{Cicp, MemoryFormat, ffi};
+use crate::{Cicp, ColorMode, MemoryFormat, ffi};
 
 glib::wrapper! {
     #[doc(alias = "GlyFrame")]
@@ -29,6 +32,13 @@ impl Frame {
         unsafe { from_glib_full(ffi::gly_frame_get_color_cicp(self.to_glib_none().0)) }
     }
 
+    #[doc(alias = "gly_frame_get_color_state")]
+    #[doc(alias = "get_color_state")]
+    #[doc(alias = "color-state")]
+    pub fn color_state(&self) -> ColorMode {
+        unsafe { from_glib(ffi::gly_frame_get_color_state(self.to_glib_none().0)) }
+    }
+
     #[doc(alias = "gly_frame_get_delay")]
     #[doc(alias = "get_delay")]
     pub fn delay(&self) -> i64 {
@@ -58,6 +68,36 @@ impl Frame {
     pub fn width(&self) -> u32 {
         unsafe { ffi::gly_frame_get_width(self.to_glib_none().0) }
     }
+
+    #[doc(alias = "color-state")]
+    pub fn connect_color_state_notify<F: Fn(&Self) + Send + Sync + 'static>(
+        &self,
+        f: F,
+    ) -> SignalHandlerId {
+        unsafe extern "C" fn notify_color_state_trampoline<
+            F: Fn(&Frame) + Send + Sync + 'static,
+        >(
+            this: *mut ffi::GlyFrame,
+            _param_spec: glib::ffi::gpointer,
+            f: glib::ffi::gpointer,
+        ) {
+            unsafe {
+                let f: &F = &*(f as *const F);
+                f(&from_glib_borrow(this))
+            }
+        }
+        unsafe {
+            let f: Box_<F> = Box_::new(f);
+            connect_raw(
+                self.as_ptr() as *mut _,
+                c"notify::color-state".as_ptr() as *const _,
+                Some(std::mem::transmute::<*const (), unsafe extern "C" fn()>(
+                    notify_color_state_trampoline::<F> as *const (),
+                )),
+                Box_::into_raw(f),
+            )
+        }
+    }
 }
 
 unsafe impl Send for Frame {}
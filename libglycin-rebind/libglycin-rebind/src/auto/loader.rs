@@ -10,7 +10,7 @@ use glib::prelude::*;
 use glib::signal::{SignalHandlerId, connect_raw};
 use glib::translate::*;
 
-use crate::{Image, MemoryFormatSelection, SandboxSelector, ffi};
+use crate::{Image, MemoryFormat, MemoryFormatSelection, SandboxSelector, ffi};
 
 glib::wrapper! {
     #[doc(alias = "GlyLoader")]
@@ -140,6 +140,14 @@ impl Loader {
         }
     }
 
+    #[doc(alias = "gly_loader_set_memory_format")]
+    #[doc(alias = "memory-format")]
+    pub fn set_memory_format(&self, memory_format: MemoryFormat) {
+        unsafe {
+            ffi::gly_loader_set_memory_format(self.to_glib_none().0, memory_format.into_glib());
+        }
+    }
+
     #[doc(alias = "gly_loader_set_apply_transformations")]
     pub fn set_apply_transformations(&self, apply_transformations: bool) {
         unsafe {
@@ -29,6 +29,10 @@ use libc::{FILE, intptr_t, off_t, size_t, ssize_t, time_t, uintptr_t};
 use libc::{dev_t, gid_t, pid_t, socklen_t, uid_t};
 
 // Enums
+pub type GlyColorMode = c_int;
+pub const GLY_COLOR_MODE_SRGB: GlyColorMode = 0;
+pub const GLY_COLOR_MODE_CICP: GlyColorMode = 1;
+
 pub type GlyLoaderError = c_int;
 pub const GLY_LOADER_ERROR_FAILED: GlyLoaderError = 0;
 pub const GLY_LOADER_ERROR_UNKNOWN_IMAGE_FORMAT: GlyLoaderError = 1;
@@ -58,6 +62,8 @@ pub const GLY_MEMORY_G8: GlyMemoryFormat = 19;
 pub const GLY_MEMORY_G16A16_PREMULTIPLIED: GlyMemoryFormat = 20;
 pub const GLY_MEMORY_G16A16: GlyMemoryFormat = 21;
 pub const GLY_MEMORY_G16: GlyMemoryFormat = 22;
+pub const GLY_MEMORY_C8M8Y8K8: GlyMemoryFormat = 23;
+pub const GLY_MEMORY_C16M16Y16K16: GlyMemoryFormat = 24;
 
 pub type GlySandboxSelector = c_int;
 pub const GLY_SANDBOX_SELECTOR_AUTO: GlySandboxSelector = 0;
@@ -309,6 +315,11 @@ impl ::std::fmt::Debug for GlyNewFrame {
 
 unsafe extern "C" {
 
+    //=========================================================================
+    // GlyColorMode
+    //=========================================================================
+    pub fn gly_color_mode_get_type() -> GType;
+
     //=========================================================================
     // GlyLoaderError
     //=========================================================================
@@ -406,6 +417,7 @@ unsafe extern "C" {
     pub fn gly_frame_get_type() -> GType;
     pub fn gly_frame_get_buf_bytes(frame: *mut GlyFrame) -> *mut glib::GBytes;
     pub fn gly_frame_get_color_cicp(frame: *mut GlyFrame) -> *mut GlyCicp;
+    pub fn gly_frame_get_color_state(frame: *mut GlyFrame) -> GlyColorMode;
     pub fn gly_frame_get_delay(frame: *mut GlyFrame) -> i64;
     pub fn gly_frame_get_height(frame: *mut GlyFrame) -> u32;
     pub fn gly_frame_get_memory_format(frame: *mut GlyFrame) -> GlyMemoryFormat;
@@ -506,6 +518,7 @@ unsafe extern "C" {
         loader: *mut GlyLoader,
         memory_format_selection: GlyMemoryFormatSelection,
     );
+    pub fn gly_loader_set_memory_format(loader: *mut GlyLoader, memory_format: GlyMemoryFormat);
     pub fn gly_loader_set_apply_transformations(
         loader: *mut GlyLoader,
         apply_transformations: gboolean,
@@ -209,6 +209,13 @@ const RUST_LAYOUTS: &[(&str, Layout)] = &[
             alignment: align_of::<GlyCicp>(),
         },
     ),
+    (
+        "GlyColorMode",
+        Layout {
+            size: size_of::<GlyColorMode>(),
+            alignment: align_of::<GlyColorMode>(),
+        },
+    ),
     (
         "GlyCreatorClass",
         Layout {
@@ -289,6 +296,8 @@ const RUST_LAYOUTS: &[(&str, Layout)] = &[
 ];
 
 const RUST_CONSTANTS: &[(&str, &str)] = &[
+    ("(gint) GLY_COLOR_MODE_CICP", "1"),
+    ("(gint) GLY_COLOR_MODE_SRGB", "0"),
     ("(gint) GLY_LOADER_ERROR_FAILED", "0"),
     ("(gint) GLY_LOADER_ERROR_NO_MORE_FRAMES", "2"),
     ("(gint) GLY_LOADER_ERROR_UNKNOWN_IMAGE_FORMAT", "1"),
@@ -298,6 +307,8 @@ const RUST_CONSTANTS: &[(&str, &str)] = &[
     ("(gint) GLY_MEMORY_B8G8R8", "8"),
     ("(gint) GLY_MEMORY_B8G8R8A8", "3"),
     ("(gint) GLY_MEMORY_B8G8R8A8_PREMULTIPLIED", "0"),
+    ("(gint) GLY_MEMORY_C16M16Y16K16", "24"),
+    ("(gint) GLY_MEMORY_C8M8Y8K8", "23"),
     ("(gint) GLY_MEMORY_G16", "22"),
     ("(gint) GLY_MEMORY_G16A16", "21"),
     ("(gint) GLY_MEMORY_G16A16_PREMULTIPLIED", "20"),
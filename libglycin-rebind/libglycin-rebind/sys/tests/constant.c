@@ -29,6 +29,8 @@
     printf("\n");
 
 int main() {
+    PRINT_CONSTANT((gint) GLY_COLOR_MODE_CICP);
+    PRINT_CONSTANT((gint) GLY_COLOR_MODE_SRGB);
     PRINT_CONSTANT((gint) GLY_LOADER_ERROR_FAILED);
     PRINT_CONSTANT((gint) GLY_LOADER_ERROR_NO_MORE_FRAMES);
     PRINT_CONSTANT((gint) GLY_LOADER_ERROR_UNKNOWN_IMAGE_FORMAT);
@@ -38,6 +40,8 @@ int main() {
     PRINT_CONSTANT((gint) GLY_MEMORY_B8G8R8);
     PRINT_CONSTANT((gint) GLY_MEMORY_B8G8R8A8);
     PRINT_CONSTANT((gint) GLY_MEMORY_B8G8R8A8_PREMULTIPLIED);
+    PRINT_CONSTANT((gint) GLY_MEMORY_C16M16Y16K16);
+    PRINT_CONSTANT((gint) GLY_MEMORY_C8M8Y8K8);
     PRINT_CONSTANT((gint) GLY_MEMORY_G16);
     PRINT_CONSTANT((gint) GLY_MEMORY_G16A16);
     PRINT_CONSTANT((gint) GLY_MEMORY_G16A16_PREMULTIPLIED);
@@ -9,6 +9,7 @@
 
 int main() {
     printf("%s;%zu;%zu\n", "GlyCicp", sizeof(GlyCicp), alignof(GlyCicp));
+    printf("%s;%zu;%zu\n", "GlyColorMode", sizeof(GlyColorMode), alignof(GlyColorMode));
     printf("%s;%zu;%zu\n", "GlyCreatorClass", sizeof(GlyCreatorClass), alignof(GlyCreatorClass));
     printf("%s;%zu;%zu\n", "GlyEncodedImageClass", sizeof(GlyEncodedImageClass), alignof(GlyEncodedImageClass));
     printf("%s;%zu;%zu\n", "GlyFrameClass", sizeof(GlyFrameClass), alignof(GlyFrameClass));
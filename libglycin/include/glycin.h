@@ -329,6 +329,23 @@ void gly_loader_set_sandbox_selector(GlyLoader *loader,
 void gly_loader_set_accepted_memory_formats(GlyLoader *loader,
                                             GlyMemoryFormatSelection memory_format_selection);
 
+/**
+ * gly_loader_set_memory_format:
+ * @loader:
+ * @memory_format: Memory format of all frames
+ *
+ * Sets the memory format in which all frames are returned
+ *
+ * This is the same as [method@Loader.set_accepted_memory_formats] with
+ * only a single format selected. The conversion happens after the ICC
+ * profile has been applied. Converting into a format with a lower bit
+ * depth or without alpha channel loses information.
+ *
+ * Since: 2.2
+ */
+void gly_loader_set_memory_format(GlyLoader *loader,
+                                  GlyMemoryFormat memory_format);
+
 /**
  * gly_loader_set_apply_transformations:
  * @loader:
@@ -657,6 +674,8 @@ uint16_t gly_image_get_transformation_orientation(GlyImage *image);
  * @GLY_MEMORY_G16A16_PREMULTIPLIED: 16-bit gray with alpha premultiplied
  * @GLY_MEMORY_G16A16: 16-bit gray with alpha
  * @GLY_MEMORY_G16: 16-bit gray
+ * @GLY_MEMORY_C8M8Y8K8: 8-bit CMYK. Since: 2.2
+ * @GLY_MEMORY_C16M16Y16K16: 16-bit CMYK. Since: 2.2
  *
  * Memory format
  *
@@ -687,6 +706,8 @@ typedef enum
     GLY_MEMORY_G16A16_PREMULTIPLIED,
     GLY_MEMORY_G16A16,
     GLY_MEMORY_G16,
+    GLY_MEMORY_C8M8Y8K8,
+    GLY_MEMORY_C16M16Y16K16,
 } GlyMemoryFormat;
 
 GType gly_memory_format_get_type(void);
@@ -823,6 +844,38 @@ void gly_cicp_free(GlyCicp *cicp);
 
 G_DEFINE_AUTOPTR_CLEANUP_FUNC(GlyCicp, gly_cicp_free)
 
+/**
+ * GlyColorMode:
+ * @GLY_COLOR_MODE_SRGB: The texture is in sRGB
+ * @GLY_COLOR_MODE_CICP: The color state is given by the CICP from
+ *   [method@Gly.Frame.get_color_cicp]
+ *
+ * Color state of a frame's texture
+ *
+ * Since: 2.2
+ */
+typedef enum
+{
+    GLY_COLOR_MODE_SRGB,
+    GLY_COLOR_MODE_CICP,
+} GlyColorMode;
+
+GType gly_color_mode_get_type(void);
+
+/**
+ * gly_frame_get_color_state:
+ * @frame:
+ *
+ * Returns how the colors of the frame's texture have to be interpreted.
+ * ICC profiles are already applied by glycin. Frames with HDR content
+ * usually use [enum@Gly.ColorMode.CICP].
+ *
+ * Returns: Color state
+ *
+ * Since: 2.2
+ */
+GlyColorMode gly_frame_get_color_state(GlyFrame *frame);
+
 /**
  * gly_frame_get_color_cicp:
  * @frame:
@@ -2,7 +2,7 @@ use gio::prelude::*;
 use glib::ffi::GType;
 use glib::subclass::prelude::*;
 use glib::translate::*;
-use glycin::gobject::{self, GlyCicp};
+use glycin::gobject::{self, GlyCicp, GlyColorMode};
 
 use crate::GlyFrameDetails;
 
@@ -61,6 +61,14 @@ pub unsafe extern "C" fn gly_frame_get_memory_format(frame: *mut GlyFrame) -> i3
     }
 }
 
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn gly_frame_get_color_state(frame: *mut GlyFrame) -> i32 {
+    unsafe {
+        let frame = gobject::GlyFrame::from_glib_ptr_borrow(&frame);
+        frame.color_mode().into_glib()
+    }
+}
+
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn gly_frame_get_color_cicp(frame: *mut GlyFrame) -> *const GlyCicp {
     unsafe {
@@ -86,6 +94,11 @@ pub unsafe extern "C" fn gly_frame_get_details(frame: *mut GlyFrame) -> *const G
     }
 }
 
+#[unsafe(no_mangle)]
+pub extern "C" fn gly_color_mode_get_type() -> GType {
+    <GlyColorMode as StaticType>::static_type().into_glib()
+}
+
 #[unsafe(no_mangle)]
 pub extern "C" fn gly_cicp_get_type() -> GType {
     <GlyCicp as StaticType>::static_type().into_glib()
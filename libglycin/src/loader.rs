@@ -70,6 +70,16 @@ pub unsafe extern "C" fn gly_loader_set_accepted_memory_formats(
     }
 }
 
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn gly_loader_set_memory_format(loader: *mut GlyLoader, memory_format: i32) {
+    unsafe {
+        let memory_format = GlyMemoryFormat::from_glib(memory_format);
+        let obj = gobject::GlyLoader::from_glib_ptr_borrow(&loader);
+
+        obj.set_memory_format(memory_format);
+    }
+}
+
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn gly_loader_set_apply_transformations(
     loader: *mut GlyLoader,
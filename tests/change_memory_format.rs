@@ -30,3 +30,48 @@ async fn change_memory_format_internal() {
         }
     }
 }
+
+#[test]
+fn change_memory_format_half_float() {
+    block_on(change_memory_format_half_float_internal());
+}
+
+async fn change_memory_format_half_float_internal() {
+    for path in [
+        "test-images/images/color/color.png",
+        "test-images/images/gray-iccp/gray-iccp-GA16.png",
+    ] {
+        let file = gio::File::for_path(path);
+
+        let mut loader = glycin::Loader::new(file.clone());
+        loader.memory_format(glycin::MemoryFormat::R16g16b16a16Float);
+        let frame = loader.load().await.unwrap().next_frame().await.unwrap();
+
+        assert_eq!(
+            frame.memory_format(),
+            glycin::MemoryFormat::R16g16b16a16Float
+        );
+        assert_eq!(
+            glycin::gdk_memory_format(frame.memory_format()),
+            gdk::MemoryFormat::R16g16b16a16Float
+        );
+
+        let mut loader = glycin::Loader::new(file);
+        loader.memory_format(glycin::MemoryFormat::R32g32b32a32Float);
+        let reference = loader.load().await.unwrap().next_frame().await.unwrap();
+
+        let half = frame
+            .buf_slice()
+            .chunks_exact(2)
+            .map(|x| half::f16::from_ne_bytes([x[0], x[1]]).to_f32());
+        let float = reference
+            .buf_slice()
+            .chunks_exact(4)
+            .map(|x| f32::from_ne_bytes([x[0], x[1], x[2], x[3]]));
+
+        for (half, float) in half.zip(float) {
+            assert!((0. ..=1.).contains(&half), "{path}: {half}");
+            assert!((half - float).abs() < 1e-3, "{path}: {half} != {float}");
+        }
+    }
+}
@@ -2,6 +2,7 @@
 
 mod utils;
 
+use std::task::Poll;
 use std::time::Duration;
 
 use glycin_core::{Limits, MimeType, Operation, Operations};
@@ -43,6 +44,26 @@ fn glycin_test_panic_frame() {
     });
 }
 
+#[test]
+fn glycin_test_error_stage() {
+    init();
+
+    block_on(async {
+        let loader = glycin_core::Loader::new_vec(instruction(&[b"panic"]));
+        let err = loader.load().await.unwrap_err();
+        assert_eq!(err.stage(), glycin_core::Stage::Init, "Error: {err}");
+
+        let loader = glycin_core::Loader::new_vec(instruction(&[b"panic-next-step"]));
+        let mut image = loader.load().await.unwrap();
+        let err = image.next_frame().await.unwrap_err();
+        assert_eq!(err.stage(), glycin_core::Stage::Frame, "Error: {err}");
+
+        let editor = glycin_core::Editor::new_vec(instruction(&[b"panic"]));
+        let err = editor.edit().await.unwrap_err();
+        assert_eq!(err.stage(), glycin_core::Stage::Editor, "Error: {err}");
+    });
+}
+
 #[test]
 fn glycin_test_panic_create() {
     init();
@@ -126,6 +147,30 @@ fn glycin_test_timeout_load() {
     });
 }
 
+#[test]
+fn glycin_test_process_ready() {
+    init();
+
+    block_on(async {
+        // The loader never returns from init
+        let mut loader = glycin_core::Loader::new_vec(instruction(&[b"infinte-loop"]));
+        loader.limits(Limits::default().timeout(Duration::from_secs(5)));
+        let process_context = loader.process_context();
+        assert!(!process_context.is_ready());
+
+        // The process becomes ready although init never finishes
+        let (ready, result) = futures_util::join!(process_context.ready(), loader.load());
+        assert!(ready);
+        assert!(result.unwrap_err().is_timeout());
+
+        // Readiness fails if loading fails before the process is ready
+        let loader = glycin_core::Loader::new(gio::File::for_path("does-not-exist.png"));
+        let process_context = loader.process_context();
+        assert!(loader.load().await.is_err());
+        assert!(!process_context.ready().await);
+    });
+}
+
 #[test]
 fn glycin_test_timeout_next_frame() {
     init();
@@ -141,6 +186,239 @@ fn glycin_test_timeout_next_frame() {
     });
 }
 
+/// Children of the test process that have exited but were not reaped
+#[cfg(all(feature = "external-loaders", not(feature = "builtin-loaders")))]
+fn zombie_children() -> usize {
+    let pid = std::process::id().to_string();
+
+    std::fs::read_dir("/proc")
+        .unwrap()
+        .flatten()
+        .filter_map(|entry| std::fs::read_to_string(entry.path().join("stat")).ok())
+        .filter(|stat| {
+            // Fields after the command name are state and parent PID
+            let fields = stat
+                .rsplit_once(')')
+                .map(|(_, x)| x.split_whitespace().collect::<Vec<_>>())
+                .unwrap_or_default();
+            fields.first() == Some(&"Z") && fields.get(1) == Some(&pid.as_str())
+        })
+        .count()
+}
+
+#[cfg(all(feature = "external-loaders", not(feature = "builtin-loaders")))]
+#[test]
+fn glycin_test_loader_timeout() {
+    init();
+
+    block_on(async {
+        let mut loader = glycin_core::Loader::new_vec(instruction(&[b"sleep-next-step", b"10000"]));
+        loader.timeout(Duration::from_millis(500));
+
+        let start = std::time::Instant::now();
+        let mut image = loader.load().await.unwrap();
+
+        let err = image.next_frame().await.unwrap_err();
+        assert!(err.is_timeout(), "Error: {err}");
+        let glycin_core::ErrorKind::Timeout(elapsed) = err.kind() else {
+            unreachable!()
+        };
+        // The timeout covers loading and the frame request together
+        assert!(elapsed >= Duration::from_millis(500));
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        // The killed process is reaped
+        for _ in 0..100 {
+            if zombie_children() == 0 {
+                break;
+            }
+            async_io::Timer::after(Duration::from_millis(10)).await;
+        }
+        assert_eq!(zombie_children(), 0);
+    });
+}
+
+#[cfg(all(feature = "external-loaders", not(feature = "builtin-loaders")))]
+#[test]
+fn glycin_test_loader_timeout_scanlines() {
+    use futures_util::StreamExt;
+
+    init();
+
+    block_on(async {
+        let mut loader = glycin_core::Loader::new_vec(instruction(&[b"sleep-next-step", b"10000"]));
+        loader.timeout(Duration::from_millis(500));
+
+        let start = std::time::Instant::now();
+        let mut image = loader.load().await.unwrap();
+
+        let err = image.scanlines(1).next().await.unwrap().unwrap_err();
+        assert!(err.is_timeout(), "Error: {err}");
+        assert!(start.elapsed() < Duration::from_secs(5));
+    });
+}
+
+#[cfg(all(feature = "external-loaders", not(feature = "builtin-loaders")))]
+#[test]
+fn glycin_test_sandbox_tmpdir() {
+    init();
+
+    block_on(async {
+        let tmpdir = std::path::PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("glycin-tmpdir");
+        std::fs::create_dir_all(&tmpdir).unwrap();
+
+        // Without the option, files end up in the loader's private `TMPDIR`
+        let loader = glycin_core::Loader::new_vec(instruction(&[b"tmp-file", b"default"]));
+        let image = loader.load().await.unwrap();
+        assert!(!tmpdir.join("default").exists());
+        drop(image);
+
+        let mut loader = glycin_core::Loader::new_vec(instruction(&[b"tmp-file", b"state"]));
+        loader.sandbox_tmpdir(&tmpdir);
+        let image = loader.load().await.unwrap();
+        assert!(tmpdir.join("state").is_file());
+        drop(image);
+    });
+}
+
+#[cfg(all(feature = "external-loaders", not(feature = "builtin-loaders")))]
+#[test]
+fn glycin_test_load_many_kill() {
+    use futures_util::StreamExt;
+
+    init();
+
+    block_on(async {
+        let tmpdir = std::path::PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("load-many");
+        let _ = std::fs::remove_dir_all(&tmpdir);
+        std::fs::create_dir_all(&tmpdir).unwrap();
+
+        // One load finishes, two hang in flight, and the rest are queued
+        let loaders = (0..6)
+            .map(|i| {
+                let mut loader = if i == 0 {
+                    glycin_core::Loader::new_vec(instruction(&[b"sleep-next-step", b"0"]))
+                } else {
+                    let name = format!("heartbeat-{i}");
+                    glycin_core::Loader::new_vec(instruction(&[b"heartbeat", name.as_bytes()]))
+                };
+                loader.sandbox_tmpdir(&tmpdir);
+                loader
+            })
+            .collect::<Vec<_>>();
+
+        let cancellable = gio::Cancellable::new();
+        let mut stream = glycin_core::Loader::load_many(loaders, 3, cancellable.clone());
+
+        let (index, result) = stream.next().await.unwrap();
+        assert_eq!(index, 0);
+        result.unwrap();
+
+        let heartbeat = |i: usize| std::fs::read_to_string(tmpdir.join(format!("heartbeat-{i}")));
+        while heartbeat(1).is_err() || heartbeat(2).is_err() {
+            async_io::Timer::after(Duration::from_millis(10)).await;
+        }
+
+        cancellable.cancel();
+
+        let results = stream.collect::<Vec<_>>().await;
+        let mut indices = results.iter().map(|(index, _)| *index).collect::<Vec<_>>();
+        indices.sort();
+        assert_eq!(indices, [1, 2]);
+        assert!(
+            results
+                .iter()
+                .all(|(_, result)| result.as_ref().is_err_and(|err| err.is_cancelled()))
+        );
+
+        // The loaders in flight have been killed and stop updating their files
+        async_io::Timer::after(Duration::from_millis(100)).await;
+        let before = (heartbeat(1).unwrap(), heartbeat(2).unwrap());
+        async_io::Timer::after(Duration::from_millis(300)).await;
+        assert_eq!(before, (heartbeat(1).unwrap(), heartbeat(2).unwrap()));
+
+        // Queued loaders were never started
+        assert!((3..6).all(|i| heartbeat(i).is_err()));
+    });
+}
+
+#[cfg(all(feature = "external-loaders", not(feature = "builtin-loaders")))]
+#[test]
+fn glycin_test_shutdown() {
+    init();
+
+    block_on(async {
+        let tmpdir = std::path::PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("shutdown");
+        std::fs::create_dir_all(&tmpdir).unwrap();
+        let tmp_file = tmpdir.join("state");
+
+        // The process is dropped as soon as it's unused
+        let pool =
+            glycin_core::Pool::new(glycin_core::PoolConfig::new().retention_time(Duration::ZERO));
+
+        let mut loader = glycin_core::Loader::new_vec(instruction(&[b"tmp-file", b"state"]));
+        loader.sandbox_tmpdir(&tmpdir).pool(pool);
+        let image = loader.load().await.unwrap();
+        assert!(tmp_file.exists());
+
+        drop(image);
+
+        // The loader drops its state before the process is killed, and the
+        // killed process is reaped
+        for _ in 0..200 {
+            if !tmp_file.exists() && zombie_children() == 0 {
+                break;
+            }
+            async_io::Timer::after(Duration::from_millis(10)).await;
+        }
+        assert!(!tmp_file.exists());
+        assert_eq!(zombie_children(), 0);
+    });
+}
+
+#[cfg(all(feature = "external-loaders", not(feature = "builtin-loaders")))]
+#[test]
+fn glycin_test_token_authentication() {
+    init();
+
+    block_on(async {
+        let pool =
+            glycin_core::Pool::new(glycin_core::PoolConfig::new().token_authentication(true));
+
+        let mut loader = glycin_core::Loader::new_vec(instruction(&[b"sleep-next-step", b"0"]));
+        loader.pool(pool);
+        let mut image = loader.load().await.unwrap();
+        image.next_frame().await.unwrap();
+    });
+}
+
+#[test]
+fn glycin_test_cancel_frame() {
+    init();
+
+    block_on(async {
+        let loader = glycin_core::Loader::new_vec(instruction(&[b"interruptible-loop-next-step"]));
+        let mut image = loader.load().await.unwrap();
+
+        let cancellable = gio::Cancellable::new();
+        let frame_cancellable = cancellable.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(100));
+            frame_cancellable.cancel();
+        });
+
+        let err = image
+            .specific_frame(glycin_core::FrameRequest::new().cancellable(cancellable))
+            .await
+            .unwrap_err();
+        assert!(err.is_cancelled(), "Error: {err}");
+
+        // The loader process is still usable
+        let frame = image.next_frame().await.unwrap();
+        assert_eq!(frame.buf_slice(), &[0]);
+    });
+}
+
 #[test]
 fn glycin_test_f16_icc_profile() {
     init();
@@ -152,3 +430,357 @@ fn glycin_test_f16_icc_profile() {
         image.next_frame().await.unwrap();
     });
 }
+
+#[test]
+fn glycin_test_icc_profile_too_large() {
+    init();
+
+    block_on(async {
+        let mut loader = glycin_core::Loader::new_vec(instruction(&[b"half-with-icc-profile"]));
+        loader.limits(Limits::default().max_icc_profile_size(64));
+        let mut image = loader.load().await.unwrap();
+
+        let frame = image.next_frame().await.unwrap();
+        assert!(frame.details().color_icc_profile().is_none());
+        assert!(matches!(frame.color_state(), glycin_core::ColorState::Srgb));
+        assert_eq!(frame.details().warnings().len(), 1);
+    });
+}
+
+#[test]
+fn glycin_test_image_icc_profile_too_large() {
+    init();
+
+    block_on(async {
+        let mut loader = glycin_core::Loader::new_vec(instruction(&[b"image-icc-profile"]));
+        loader.limits(Limits::default().max_icc_profile_size(64));
+        let mut image = loader.load().await.unwrap();
+        assert!(image.details().color_icc_profile().is_none());
+        assert_eq!(image.details().warnings().len(), 1);
+
+        let frame = image.next_frame().await.unwrap();
+        assert!(matches!(frame.color_state(), glycin_core::ColorState::Srgb));
+    });
+}
+
+#[test]
+fn glycin_test_conflicting_icc_profiles() {
+    init();
+
+    block_on(async {
+        let loader = glycin_core::Loader::new_vec(instruction(&[b"conflicting-icc-profiles"]));
+        let mut image = loader.load().await.unwrap();
+
+        let image_icc_profile = image.details().color_icc_profile().unwrap().to_vec();
+        let frame_icc_profile = image.icc_profile().await.unwrap().unwrap();
+        assert_ne!(image_icc_profile, frame_icc_profile);
+
+        // Returns the prefetched frame
+        let frame = image.next_frame().await.unwrap();
+        assert_eq!(
+            frame.details().color_icc_profile(),
+            Some(frame_icc_profile.as_slice())
+        );
+        assert_eq!(
+            frame.details().conflicting_icc_profiles(),
+            Some((image_icc_profile.as_slice(), frame_icc_profile.as_slice()))
+        );
+
+        let loader = glycin_core::Loader::new_vec(instruction(&[b"conflicting-icc-profiles"]));
+        let (_, _, diagnostics) = loader.load_with_diagnostics().await.unwrap();
+        assert!(diagnostics.iter().any(|x| x.code() == "icc.conflict"));
+
+        // Without a frame profile, the image profile is returned
+        let loader = glycin_core::Loader::new_vec(instruction(&[b"image-icc-profile"]));
+        let mut image = loader.load().await.unwrap();
+        let image_icc_profile = image.details().color_icc_profile().map(<[u8]>::to_vec);
+        assert!(image_icc_profile.is_some());
+        assert_eq!(image.icc_profile().await.unwrap(), image_icc_profile);
+    });
+}
+
+#[test]
+fn glycin_test_frame_composition() {
+    init();
+
+    block_on(async {
+        let loader = glycin_core::Loader::new_vec(instruction(&[b"frame-composition"]));
+        let mut image = loader.load().await.unwrap();
+
+        let frame = image.next_frame().await.unwrap();
+        assert_eq!(
+            frame.disposal_method(),
+            Some(glycin_core::DisposalMethod::Background)
+        );
+        assert_eq!(frame.blend_op(), Some(glycin_core::BlendOp::Over));
+        assert_eq!(frame.offset(), Some((0, 0)));
+    });
+}
+
+#[test]
+fn glycin_test_try_next_frame() {
+    init();
+
+    block_on(async {
+        let loader = glycin_core::Loader::new_vec(instruction(&[b"half-with-icc-profile"]));
+        let mut image = loader.load().await.unwrap();
+
+        let frame = loop {
+            match image.try_next_frame() {
+                Poll::Ready(result) => break result.unwrap(),
+                Poll::Pending => std::thread::sleep(Duration::from_millis(1)),
+            }
+        };
+
+        assert_eq!(frame.width(), image.details().width());
+    });
+}
+
+#[test]
+fn glycin_test_try_next_frame_timeout() {
+    init();
+
+    block_on(async {
+        let mut loader = glycin_core::Loader::new_vec(instruction(&[b"infinte-loop-next-step"]));
+        loader.limits(Limits::default().timeout(Duration::from_millis(100)));
+
+        let mut image = loader.load().await.unwrap();
+
+        assert!(image.try_next_frame().is_pending());
+
+        let err = loop {
+            match image.try_next_frame() {
+                Poll::Ready(result) => break result.unwrap_err(),
+                Poll::Pending => std::thread::sleep(Duration::from_millis(1)),
+            }
+        };
+
+        assert!(err.is_timeout(), "Error: {err}");
+    });
+}
+
+#[test]
+fn glycin_test_auxiliary_image() {
+    init();
+
+    block_on(async {
+        let loader = glycin_core::Loader::new_vec(instruction(&[b"auxiliary-image"]));
+        let mut image = loader.load().await.unwrap();
+
+        assert_eq!(image.details().n_animation_frames(), Some(1));
+        assert_eq!(image.details().n_auxiliary_images(), Some(1));
+
+        let auxiliary = image
+            .specific_frame(glycin_core::FrameRequest::new().auxiliary_image(0))
+            .await
+            .unwrap();
+        assert_eq!(auxiliary.details().auxiliary_type(), Some("test:auxiliary"));
+        assert_eq!(auxiliary.buf_slice(), &[100]);
+
+        let err = image
+            .specific_frame(glycin_core::FrameRequest::new().auxiliary_image(1))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err.kind(), glycin_core::ErrorKind::NoAuxiliaryImage(1)),
+            "Unexpected error"
+        );
+
+        // The auxiliary image is not a depth map
+        let loader = glycin_core::Loader::new_vec(instruction(&[b"auxiliary-image"]));
+        assert!(loader.depth_map().await.unwrap().is_none());
+
+        let frame = image.next_frame().await.unwrap();
+        assert_eq!(frame.details().auxiliary_type(), None);
+        assert_eq!(frame.buf_slice(), &[0]);
+    });
+}
+
+#[test]
+fn glycin_test_image_items() {
+    init();
+
+    block_on(async {
+        let loader = glycin_core::Loader::new_vec(instruction(&[b"image-items"]));
+        let mut image = loader.load().await.unwrap();
+
+        let items = image.details().image_items();
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[1].id, 2);
+        assert_eq!(items[1].role, glycin_core::ImageItemRole::Primary);
+        assert_eq!(items[2].role, glycin_core::ImageItemRole::Depth);
+
+        let thumbnail = image
+            .specific_frame(glycin_core::FrameRequest::new().image_item(1))
+            .await
+            .unwrap();
+        assert_eq!(thumbnail.buf_slice(), &[1]);
+
+        let err = image
+            .specific_frame(glycin_core::FrameRequest::new().image_item(4))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err.kind(), glycin_core::ErrorKind::NoImageItem(4)),
+            "Unexpected error"
+        );
+
+        let loader = glycin_core::Loader::new_vec(instruction(&[b"image-items"]));
+        let depth_map = loader.depth_map().await.unwrap().unwrap();
+        assert_eq!(depth_map.source, glycin_core::DepthMapSource::ImageItem(3));
+        assert_eq!(depth_map.encoding, glycin_core::DepthEncoding::Unknown);
+        assert_eq!(depth_map.frame.buf_slice(), &[3]);
+
+        let frame = image.next_frame().await.unwrap();
+        assert_eq!(frame.buf_slice(), &[2]);
+    });
+}
+
+#[test]
+fn glycin_test_oversized_texture() {
+    init();
+
+    block_on(async {
+        let data = instruction(&[b"oversized-texture", b"100000"]);
+
+        // Extra data are ignored by default
+        let loader = glycin_core::Loader::new_vec(data.clone());
+        let mut image = loader.load().await.unwrap();
+        let frame = image.next_frame().await.unwrap();
+        assert_eq!((frame.width(), frame.height()), (1, 1));
+
+        let mut loader = glycin_core::Loader::new_vec(data);
+        loader.limits(Limits::default().strict_frame_size(true));
+        let mut image = loader.load().await.unwrap();
+        let err = image.next_frame().await.unwrap_err();
+        assert!(
+            matches!(
+                err.kind(),
+                glycin_core::ErrorKind::FrameSizeMismatch {
+                    texture_size: 100000,
+                    expected: 1,
+                    ..
+                }
+            ),
+            "Unexpected error: {err}"
+        );
+
+        // Less than a page of extra data is accepted in strict mode
+        let mut loader = glycin_core::Loader::new_vec(instruction(&[b"oversized-texture", b"100"]));
+        loader.limits(Limits::default().strict_frame_size(true));
+        let mut image = loader.load().await.unwrap();
+        image.next_frame().await.unwrap();
+    });
+}
+
+#[test]
+fn glycin_test_warnings() {
+    init();
+
+    block_on(async {
+        let loader = glycin_core::Loader::new_vec(instruction(&[b"warnings"]));
+        let mut image = loader.load().await.unwrap();
+        assert_eq!(image.details().warnings(), &[String::from("image warning")]);
+
+        let frame = image.next_frame().await.unwrap();
+        assert_eq!(frame.details().warnings(), &[String::from("frame warning")]);
+    });
+}
+
+#[test]
+fn glycin_test_reduced_precision() {
+    init();
+
+    block_on(async {
+        let loader = glycin_core::Loader::new_vec(instruction(&[b"reduced-precision"]));
+        let mut image = loader.load().await.unwrap();
+
+        let frame = image
+            .specific_frame(glycin_core::FrameRequest::new().reduced_precision(true))
+            .await
+            .unwrap();
+        assert!(frame.details().reduced_precision());
+        assert_eq!(frame.memory_format(), glycin_core::MemoryFormat::G8);
+        assert_eq!(frame.details().info_bit_depth(), Some(16));
+
+        let frame = image.next_frame().await.unwrap();
+        assert!(!frame.details().reduced_precision());
+        assert_eq!(frame.memory_format(), glycin_core::MemoryFormat::G16);
+        assert_eq!(frame.details().info_bit_depth(), Some(16));
+    });
+}
+
+#[test]
+fn glycin_test_zero_dimensions() {
+    init();
+
+    block_on(async {
+        let loader = glycin_core::Loader::new_vec(instruction(&[b"zero-dimensions"]));
+        let err = loader.load().await.unwrap_err();
+        let msg = err.to_string();
+        assert!(
+            matches!(
+                err.kind(),
+                glycin_core::ErrorKind::InvalidDimensions {
+                    width: 0,
+                    height: 0
+                }
+            ),
+            "Error: {msg}"
+        );
+    });
+}
+
+#[cfg(all(feature = "external-loaders", not(feature = "builtin-loaders")))]
+#[test]
+fn glycin_test_custom_format() {
+    init();
+
+    block_on(async {
+        let data = instruction(&[b"warnings", b"in-house"]);
+        let mime_type = MimeType::new_static("image/x-glycin-in-house");
+
+        let sniffer = glycin_core::register_sniffer(|head| {
+            head.starts_with(b"glycin-test\0warnings:in-house")
+                .then_some(MimeType::new_static("image/x-glycin-in-house"))
+        });
+
+        // Detected, but no loader configured
+        let loader = glycin_core::Loader::new_vec(data.clone());
+        assert!(loader.load().await.is_err());
+
+        let config = glycin_core::config::Config::cached().await;
+        let exec = config
+            .loader(&MimeType::new_static("image/x-glycin-test"))
+            .unwrap()
+            .exec()
+            .unwrap()
+            .to_path_buf();
+
+        let dir = std::path::PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("custom-format");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("in-house.conf"),
+            format!("[loader:{mime_type}]\nExec = {}\n", exec.display()),
+        )
+        .unwrap();
+
+        let config_dir = glycin_core::config::register_config_search_dir(&dir);
+
+        let loader = glycin_core::Loader::new_vec(data.clone());
+        let image = loader.load().await.unwrap();
+        assert_eq!(image.mime_type(), mime_type);
+
+        config_dir.unregister();
+        let loader = glycin_core::Loader::new_vec(data.clone());
+        assert!(loader.load().await.is_err());
+
+        sniffer.unregister();
+        let loader = glycin_core::Loader::new_vec(data);
+        let image = loader.load().await.unwrap();
+        assert_eq!(
+            image.mime_type(),
+            MimeType::new_static("image/x-glycin-test")
+        );
+    });
+}
@@ -41,6 +41,8 @@ def test_frame(frame):
     assert first_byte > 50 and first_byte < 70, f"Wrong first byte: {first_byte}"
     assert memory_format == Gly.MemoryFormat.R8G8B8, f"Wrong memory format: {memory_format}"
     assert frame.get_color_cicp() is None
+    assert frame.get_color_state() == Gly.ColorMode.SRGB
+    assert frame.props.color_state == Gly.ColorMode.SRGB
 
     assert not Gly.MemoryFormat.has_alpha(memory_format)
     assert not Gly.MemoryFormat.is_premultiplied(memory_format)
@@ -171,6 +173,8 @@ def main():
     frame = image.next_frame()
     cicp = frame.get_color_cicp()
 
+    assert frame.get_color_state() == Gly.ColorMode.CICP
+    assert frame.props.color_state == Gly.ColorMode.CICP
     assert cicp.color_primaries == 12
     assert cicp.transfer_characteristics == 13
     assert cicp.matrix_coefficients == 0
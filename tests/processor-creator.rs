@@ -176,6 +176,49 @@ fn processor_creator_jpeg_stride_invalid() {
     });
 }
 
+#[test]
+fn processor_creator_from_pixels() {
+    block_on(async {
+        init();
+
+        assert!(
+            Creator::supported_mime_types()
+                .await
+                .contains(&MimeType::PNG)
+        );
+
+        let texture = vec![255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255];
+        let creator = Creator::from_pixels(MimeType::PNG, 2, 2, MemoryFormat::R8g8b8, texture)
+            .await
+            .unwrap();
+        let encoded_image = creator.create().await.unwrap();
+
+        let loader = Loader::new_vec(encoded_image.data_full());
+        let mut image = loader.load().await.unwrap();
+        let frame = image.next_frame().await.unwrap();
+
+        assert_eq!(frame.width(), 2);
+        assert_eq!(frame.height(), 2);
+        assert_eq!(&frame.buf_slice()[..3], &[255, 0, 0]);
+
+        let err = Creator::from_pixels(MimeType::PNG, 2, 2, MemoryFormat::R8g8b8, vec![0; 11])
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            glycin::ErrorKind::TextureWrongSize { .. }
+        ));
+
+        let err = Creator::from_pixels(MimeType::PNG, 2, 0, MemoryFormat::R8g8b8, vec![])
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            glycin::ErrorKind::WidgthOrHeightZero(_)
+        ));
+    });
+}
+
 #[test]
 fn processor_creator_jpeg_quality() {
     block_on(async {
@@ -310,6 +353,42 @@ fn processor_creator_png() {
     });
 }
 
+#[test]
+fn processor_creator_png_bit_depth() {
+    block_on(async {
+        init();
+
+        let width = 4;
+        let height = 4;
+        let texture = (0..width * height * 3)
+            .flat_map(|x| (x as u16 * 1000).to_ne_bytes())
+            .collect::<Vec<_>>();
+
+        let mut creator = Creator::new(MimeType::PNG).await.unwrap();
+        creator.set_encoding_bit_depth(8);
+        creator
+            .add_frame(width, height, MemoryFormat::R16g16b16, texture.clone())
+            .unwrap();
+        let encoded_image = creator.create().await.unwrap();
+
+        let loader = glycin::Loader::new_vec(encoded_image.data_full());
+        let mut image = loader.load().await.unwrap();
+        let frame = image.next_frame().await.unwrap();
+        assert_eq!(frame.memory_format(), MemoryFormat::R8g8b8);
+
+        let mut creator = Creator::new(MimeType::PNG).await.unwrap();
+        creator
+            .add_frame(width, height, MemoryFormat::R16g16b16, texture)
+            .unwrap();
+        let encoded_image = creator.create().await.unwrap();
+
+        let loader = glycin::Loader::new_vec(encoded_image.data_full());
+        let mut image = loader.load().await.unwrap();
+        let frame = image.next_frame().await.unwrap();
+        assert_eq!(frame.memory_format(), MemoryFormat::R16g16b16);
+    });
+}
+
 #[test]
 fn processor_creator_avif() {
     if skip_file_ext(MimeType::AVIF.extension().unwrap()) {
@@ -1,4 +1,5 @@
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use glycin_core as glycin;
 
@@ -6,6 +7,7 @@ mod utils;
 
 use gio::prelude::FileExt;
 use glycin::SparseEdit;
+use glycin_utils::MemoryFormatInfo;
 use utils::*;
 
 #[test]
@@ -28,6 +30,492 @@ fn processor_editor_crop_too_large_value() {
     run_test("crop-too-large-value");
 }
 
+#[test]
+fn processor_editor_apply_lut_3d() {
+    init();
+
+    block_on(async {
+        let texture = vec![0, 64, 255, 10, 128, 200];
+        let mut creator = glycin::Creator::new(glycin::MimeType::PNG).await.unwrap();
+        creator
+            .add_frame(2, 1, glycin::MemoryFormat::R8g8b8, texture.clone())
+            .unwrap();
+        let png = creator.create().await.unwrap().data_full();
+        let path = write_tmp("apply-lut-3d.png", &png);
+
+        // Inverting LUT with two grid points per channel
+        let mut lut = Vec::new();
+        for b in [1., 0.] {
+            for g in [1., 0.] {
+                for r in [1., 0.] {
+                    lut.extend([r, g, b]);
+                }
+            }
+        }
+        let operations = glycin::Operations::new(vec![glycin::Operation::ApplyLut3D {
+            lut: glycin::BinaryData::from_f32(&lut),
+            size: 2,
+        }]);
+
+        let edit = glycin::Editor::new(gio::File::for_path(&path))
+            .edit()
+            .await
+            .unwrap()
+            .apply_complete(&operations)
+            .await
+            .unwrap();
+        assert!(!edit.is_lossless());
+
+        let mut image = glycin::Loader::new_vec(edit.data().to_vec())
+            .load()
+            .await
+            .unwrap();
+        let frame = image.next_frame().await.unwrap();
+        assert_eq!(frame.memory_format(), glycin::MemoryFormat::R8g8b8);
+        let inverted = texture.iter().map(|x| 255 - x).collect::<Vec<u8>>();
+        assert_eq!(frame.buf_slice(), inverted.as_slice());
+    });
+}
+
+#[test]
+fn processor_editor_optimize() {
+    init();
+
+    block_on(async {
+        // Gradient with the fastest compression
+        let (width, height) = (256, 256);
+        let texture = (0..height)
+            .flat_map(|y| (0..width).flat_map(move |x| [x as u8, y as u8, (x ^ y) as u8]))
+            .collect::<Vec<u8>>();
+        let mut creator = glycin::Creator::new(glycin::MimeType::PNG).await.unwrap();
+        creator
+            .add_frame(width, height, glycin::MemoryFormat::R8g8b8, texture)
+            .unwrap();
+        creator.set_encoding_compression(0).unwrap();
+        let fast = creator.create().await.unwrap().data_full();
+        let fast_path = write_tmp("optimize-fast.png", &fast);
+
+        for path in [
+            fast_path.as_path(),
+            Path::new("test-images/images/color/color.png"),
+        ] {
+            eprintln!("- {path:?}");
+
+            let original = std::fs::read(path).unwrap();
+
+            let operations = glycin::Operations::new(vec![glycin::Operation::Optimize]);
+            let editor = glycin::Editor::new(gio::File::for_path(path))
+                .edit()
+                .await
+                .unwrap();
+            let output = editor.apply_complete(&operations).await.unwrap();
+            assert!(output.is_lossless());
+            assert!(output.data() != original.as_slice());
+
+            let out_name = format!(
+                "{}-optimize-test-out",
+                path.file_name().unwrap().to_string_lossy()
+            );
+            let out_path = write_tmp(out_name, output.data());
+
+            let reference = load_texture(path).await;
+            let optimized = load_texture(out_path).await;
+
+            assert_eq!(reference.width(), optimized.width());
+            assert_eq!(reference.height(), optimized.height());
+            assert!(texture_to_bytes(&reference) == texture_to_bytes(&optimized));
+        }
+
+        let optimized = glycin::Editor::new(gio::File::for_path(&fast_path))
+            .edit()
+            .await
+            .unwrap()
+            .apply_complete(&glycin::Operations::new(vec![glycin::Operation::Optimize]))
+            .await
+            .unwrap();
+        assert!(
+            optimized.data().len() < fast.len(),
+            "{} >= {}",
+            optimized.data().len(),
+            fast.len()
+        );
+
+        // JPEG with the default Huffman tables
+        let (width, height) = (128, 96);
+        let texture = (0..height)
+            .flat_map(|y| (0..width).flat_map(move |x| [x as u8, y as u8, (x ^ y) as u8]))
+            .collect::<Vec<u8>>();
+        let mut creator = glycin::Creator::new(glycin::MimeType::JPEG).await.unwrap();
+        creator
+            .add_frame(width, height, glycin::MemoryFormat::R8g8b8, texture)
+            .unwrap();
+        let jpeg = creator.create().await.unwrap().data_full();
+        let jpeg_path = write_tmp("optimize.jpg", &jpeg);
+
+        let optimized = glycin::Editor::new(gio::File::for_path(&jpeg_path))
+            .edit()
+            .await
+            .unwrap()
+            .apply_complete(&glycin::Operations::new(vec![glycin::Operation::Optimize]))
+            .await
+            .unwrap();
+        assert!(optimized.is_lossless());
+        assert!(
+            optimized.data().len() < jpeg.len(),
+            "{} >= {}",
+            optimized.data().len(),
+            jpeg.len()
+        );
+
+        let optimized_path = write_tmp("optimize-out.jpg", optimized.data());
+        let reference = load_texture(&jpeg_path).await;
+        let optimized = load_texture(&optimized_path).await;
+        assert!(texture_to_bytes(&reference) == texture_to_bytes(&optimized));
+    })
+}
+
+#[test]
+fn processor_editor_split_sparse() {
+    init();
+
+    block_on(async {
+        let rotate = glycin::Operation::Rotate(gufo_common::orientation::Rotation::_90);
+        let clip = glycin::Operation::Clip((0, 0, 10, 10));
+        let operations = glycin::Operations::new(vec![rotate.clone(), clip.clone()]);
+
+        let path = "test-images/images/color-exif-orientation/color-rotated-90.jpg";
+        let editor = glycin::Editor::new(gio::File::for_path(path))
+            .edit()
+            .await
+            .unwrap();
+        let split = editor.split_sparse(&operations).await.unwrap();
+        assert_eq!(split.sparse, glycin::Operations::new(vec![rotate.clone()]));
+        assert_eq!(split.complete, glycin::Operations::new(vec![clip.clone()]));
+
+        let sparse = editor.apply_sparse(&split.sparse).await.unwrap();
+        assert!(matches!(sparse, SparseEdit::Sparse(_)));
+
+        let path = "test-images/images/color/color.png";
+        let editor = glycin::Editor::new(gio::File::for_path(path))
+            .edit()
+            .await
+            .unwrap();
+        let split = editor.split_sparse(&operations).await.unwrap();
+        assert!(split.sparse.operations().is_empty());
+        assert_eq!(split.complete, operations);
+    })
+}
+
+#[test]
+fn processor_editor_embed_thumbnail() {
+    init();
+
+    block_on(async {
+        let path = "test-images/images/color/color.jpg";
+
+        let operations = glycin::Operations::new(vec![glycin::Operation::Rotate(
+            gufo_common::orientation::Rotation::_90,
+        )]);
+        let mut editor = glycin::Editor::new(gio::File::for_path(path));
+        editor.embed_thumbnail(64);
+
+        let output = editor
+            .edit()
+            .await
+            .unwrap()
+            .apply_complete(&operations)
+            .await
+            .unwrap();
+        assert!(output.has_embedded_thumbnail());
+
+        // The thumbnail is a complete JPEG inside the Exif segment
+        let data = output.data();
+        let exif_start = data
+            .windows(6)
+            .position(|x| x == b"Exif\0\0")
+            .expect("No Exif segment");
+        let exif_len = usize::from(u16::from_be_bytes([
+            data[exif_start - 2],
+            data[exif_start - 1],
+        ]));
+        let exif = &data[exif_start..exif_start - 2 + exif_len];
+        let thumbnail_start = exif
+            .windows(3)
+            .position(|x| x == [0xFF, 0xD8, 0xFF])
+            .expect("No thumbnail in Exif segment");
+        assert!(
+            exif[thumbnail_start..]
+                .windows(2)
+                .any(|x| x == [0xFF, 0xD9])
+        );
+
+        let out_path = write_tmp("color.jpg-thumbnail-test-out", output.data());
+
+        let reference = load_texture(path).await;
+        let with_thumbnail = load_texture(out_path).await;
+
+        // Rotated by 90°
+        assert_eq!(reference.width(), with_thumbnail.height());
+        assert_eq!(reference.height(), with_thumbnail.width());
+    })
+}
+
+#[test]
+fn processor_editor_bit_depth() {
+    init();
+
+    block_on(async {
+        let width = 4;
+        let height = 2;
+        let texture = (0..width * height * 3)
+            .flat_map(|x| (x as u16 * 1000).to_ne_bytes())
+            .collect::<Vec<_>>();
+
+        let mut creator = glycin::Creator::new(glycin::MimeType::PNG).await.unwrap();
+        creator
+            .add_frame(width, height, glycin::MemoryFormat::R16g16b16, texture)
+            .unwrap();
+        let png = creator.create().await.unwrap().data_full();
+
+        let operations = glycin::Operations::new(vec![glycin::Operation::Rotate(
+            gufo_common::orientation::Rotation::_90,
+        )]);
+
+        let mut editor = glycin::Editor::new_vec(png.clone());
+        editor.bit_depth(8);
+        let output = editor
+            .edit()
+            .await
+            .unwrap()
+            .apply_complete(&operations)
+            .await
+            .unwrap();
+        assert_eq!(output.bit_depth(), Some(8));
+        let complete = output.data().to_vec();
+
+        let mut editor = glycin::Editor::new_vec(png);
+        editor.bit_depth(8);
+        let sparse = editor
+            .edit()
+            .await
+            .unwrap()
+            .apply_sparse(&operations)
+            .await
+            .unwrap();
+        let SparseEdit::Complete(sparse) = sparse else {
+            panic!("PNG rotation must be re-encoded");
+        };
+
+        for data in [complete, sparse.to_vec()] {
+            let mut image = glycin::Loader::new_vec(data).load().await.unwrap();
+            let frame = image.next_frame().await.unwrap();
+            assert_eq!((frame.width(), frame.height()), (height, width));
+            assert_eq!(frame.memory_format(), glycin::MemoryFormat::R8g8b8);
+        }
+    })
+}
+
+#[test]
+fn processor_editor_crop_animated_webp() {
+    init();
+
+    block_on(async {
+        let path = "test-images/images/animated-numbers/animated-numbers.webp";
+
+        let original = glycin::Loader::new(gio::File::for_path(path))
+            .load()
+            .await
+            .unwrap();
+        let width = original.details().width() / 2;
+        let height = original.details().height() / 2;
+
+        let operations =
+            glycin::Operations::new(vec![glycin::Operation::Clip((0, 0, width, height))]);
+        let output = glycin::Editor::new(gio::File::for_path(path))
+            .edit()
+            .await
+            .unwrap()
+            .apply_complete(&operations)
+            .await
+            .unwrap();
+
+        let out_path = write_tmp("animated-numbers.webp-crop-test-out", output.data());
+
+        let mut image = glycin::Loader::new(gio::File::for_path(out_path))
+            .load()
+            .await
+            .unwrap();
+        assert_eq!(image.mime_type().as_str(), "image/webp");
+
+        for n_frame in 0..4 {
+            let frame = image.next_frame().await.unwrap();
+            assert_eq!(frame.details().n_frame(), Some(n_frame));
+            assert_eq!((frame.width(), frame.height()), (width, height));
+            assert_eq!(frame.delay(), Some(Duration::from_millis(200)));
+        }
+    })
+}
+
+#[test]
+fn processor_editor_crop_still_webp() {
+    init();
+
+    block_on(async {
+        let path = "test-images/images/color/color.png";
+
+        let mut image = glycin::Loader::new(gio::File::for_path(path))
+            .load()
+            .await
+            .unwrap();
+        let frame = image.next_frame().await.unwrap();
+        let width = frame.width() / 2;
+        let height = frame.height() / 2;
+
+        let mut creator = glycin::Creator::new(glycin::MimeType::WEBP).await.unwrap();
+        creator
+            .add_frame(
+                frame.width(),
+                frame.height(),
+                frame.memory_format(),
+                frame.buf_slice().to_vec(),
+            )
+            .unwrap();
+        let webp = creator.create().await.unwrap();
+
+        let operations =
+            glycin::Operations::new(vec![glycin::Operation::Clip((0, 0, width, height))]);
+        let output = glycin::Editor::new_vec(webp.data_ref().to_vec())
+            .edit()
+            .await
+            .unwrap()
+            .apply_complete(&operations)
+            .await
+            .unwrap();
+
+        let mut image = glycin::Loader::new_vec(output.data().to_vec())
+            .load()
+            .await
+            .unwrap();
+        assert_eq!(image.mime_type().as_str(), "image/webp");
+
+        let frame = image.next_frame().await.unwrap();
+        assert_eq!((frame.width(), frame.height()), (width, height));
+        assert_eq!(frame.delay(), None);
+    })
+}
+
+#[test]
+fn processor_editor_encode_animation() {
+    init();
+
+    block_on(async {
+        let mut frames = Vec::new();
+        for color in [[255, 0, 0, 255], [0, 0, 255, 255]] {
+            let png = glycin::Creator::from_pixels(
+                glycin::MimeType::PNG,
+                2,
+                2,
+                glycin::MemoryFormat::R8g8b8a8,
+                color.repeat(4),
+            )
+            .await
+            .unwrap()
+            .create()
+            .await
+            .unwrap()
+            .data_full();
+
+            let frame = glycin::Loader::new_vec(png)
+                .load()
+                .await
+                .unwrap()
+                .next_frame()
+                .await
+                .unwrap();
+            frames.push((frame, Duration::from_millis(100)));
+        }
+
+        for mime_type in [
+            glycin::MimeType::GIF,
+            glycin::MimeType::PNG,
+            glycin::MimeType::WEBP,
+        ] {
+            let creator = glycin::Creator::from_animation(mime_type, frames.clone(), 0)
+                .await
+                .unwrap();
+            let data = creator.create().await.unwrap().data_full();
+
+            let mut loader = glycin::Loader::new_vec(data);
+            loader.memory_format(glycin::MemoryFormat::R8g8b8a8);
+            let mut image = loader.load().await.unwrap();
+
+            for color in [[255, 0, 0, 255], [0, 0, 255, 255]] {
+                let frame = image.next_frame().await.unwrap();
+                assert_eq!((frame.width(), frame.height()), (2, 2));
+                assert_eq!(frame.delay(), Some(Duration::from_millis(100)));
+                assert_eq!(&frame.buf_slice()[..4], color);
+            }
+        }
+
+        let mut creator = glycin::Creator::new(glycin::MimeType::GIF).await.unwrap();
+        creator
+            .add_frame(1, 1, glycin::MemoryFormat::R8g8b8, vec![0; 3])
+            .unwrap();
+        creator
+            .add_frame(2, 1, glycin::MemoryFormat::R8g8b8, vec![0; 6])
+            .unwrap();
+        assert!(creator.create().await.is_err());
+    });
+}
+
+#[test]
+fn processor_editor_from_frame() {
+    init();
+
+    block_on(async {
+        let path = "test-images/images/color/color.png";
+
+        let mut image = glycin::Loader::new(gio::File::for_path(path))
+            .load()
+            .await
+            .unwrap();
+        let frame = image.next_frame().await.unwrap();
+        let pixel_size = frame.memory_format().n_bytes().usize();
+
+        let operations = glycin::Operations::new(vec![glycin::Operation::Clip((0, 0, 10, 5))]);
+        let editable_image = glycin::Editor::from_frame(frame.clone(), glycin::MimeType::PNG)
+            .unwrap()
+            .edit()
+            .await
+            .unwrap();
+
+        let output = editable_image.apply_complete(&operations).await.unwrap();
+        assert!(!output.is_lossless());
+
+        let mut edited_image = glycin::Loader::new_vec(output.data().to_vec())
+            .load()
+            .await
+            .unwrap();
+        let edited_frame = edited_image.next_frame().await.unwrap();
+        assert_eq!(edited_frame.width(), 10);
+        assert_eq!(edited_frame.height(), 5);
+        assert_eq!(edited_frame.memory_format(), frame.memory_format());
+        assert_eq!(
+            &edited_frame.buf_slice()[..10 * pixel_size],
+            &frame.buf_slice()[..10 * pixel_size]
+        );
+
+        let sparse = editable_image.apply_sparse(&operations).await.unwrap();
+        assert!(matches!(sparse, SparseEdit::Complete(_)));
+    })
+}
+
+async fn load_texture(path: impl AsRef<Path>) -> gdk::Texture {
+    let loader = glycin::Loader::new(gio::File::for_path(path));
+    let mut image = loader.load().await.unwrap();
+    image.next_frame().await.unwrap().texture().unwrap()
+}
+
 fn run_test(test_name: &str) {
     init();
 
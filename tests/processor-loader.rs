@@ -1,8 +1,9 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
-use gio::prelude::FileExt;
+use gio::prelude::{CancellableExt, FileExt};
 use glycin_core as glycin;
+use glycin_utils::MemoryFormatInfo;
 use utils::*;
 
 mod utils;
@@ -92,6 +93,1174 @@ fn processor_loader_color_all_at_once() {
     );
 }
 
+#[test]
+fn processor_loader_info_many() {
+    use futures_util::StreamExt;
+
+    init();
+
+    let mut files = vec![gio::File::for_path("test-images/images/does-not-exist.png")];
+    for entry in std::fs::read_dir("test-images/images/color").unwrap() {
+        let path = entry.unwrap().path();
+        if !skip_file(&path) {
+            files.push(gio::File::for_path(path));
+        }
+    }
+
+    let n_files = files.len();
+    let results = block_on(glycin::Loader::info_many(files, 4).collect::<Vec<_>>());
+
+    assert_eq!(results.len(), n_files);
+    for (file, result) in results {
+        if file.path().unwrap().ends_with("does-not-exist.png") {
+            assert!(result.is_err());
+        } else {
+            assert!(result.is_ok(), "{:?}: {result:?}", file.path());
+        }
+    }
+}
+
+#[test]
+fn processor_loader_raw() {
+    init();
+
+    block_on(async {
+        let pixels = vec![255, 0, 0, 255, 0, 0, 255, 128];
+        let png = glycin::Creator::from_pixels(
+            glycin::MimeType::PNG,
+            2,
+            1,
+            glycin::MemoryFormat::R8g8b8a8,
+            pixels.clone(),
+        )
+        .await
+        .unwrap()
+        .create()
+        .await
+        .unwrap()
+        .data_full();
+
+        // Converted to an accepted memory format by default
+        let mut loader = glycin::Loader::new_vec(png.clone());
+        loader.memory_format(glycin::MemoryFormat::R8g8b8);
+        let frame = loader.load().await.unwrap().next_frame().await.unwrap();
+        assert_eq!(frame.memory_format(), glycin::MemoryFormat::R8g8b8);
+
+        // Returned as the loader provided it in raw mode
+        let mut loader = glycin::Loader::new_vec(png);
+        loader.memory_format(glycin::MemoryFormat::R8g8b8).raw(true);
+        let frame = loader.load().await.unwrap().next_frame().await.unwrap();
+        assert_eq!(frame.memory_format(), glycin::MemoryFormat::R8g8b8a8);
+        assert_eq!(frame.stride(), 8);
+        assert_eq!(frame.buf_slice(), pixels);
+    });
+}
+
+#[test]
+fn processor_loader_mime_type_fallbacks() {
+    init();
+
+    // Misdetect this file as GIF via its image ID
+    let sniffer = glycin::register_sniffer(|head| {
+        (head.get(18..26) == Some(b"fallback".as_slice())).then_some(glycin::MimeType::GIF)
+    });
+
+    // Uncompressed 2x1 TGA with an 8 byte image ID and a red and a blue pixel
+    let mut data = vec![8, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 1, 0, 24, 0x20];
+    data.extend_from_slice(b"fallback");
+    data.extend_from_slice(&[0, 0, 255, 255, 0, 0]);
+
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("fallback.tga");
+    std::fs::write(&path, data).unwrap();
+    let file = gio::File::for_path(&path);
+
+    block_on(async {
+        assert!(glycin::Loader::new(file.clone()).load().await.is_err());
+
+        let mut loader = glycin::Loader::new(file.clone());
+        loader.as_mime_type_fallbacks(vec![glycin::MimeType::PNG, glycin::MimeType::TGA]);
+        loader.memory_format(glycin::MemoryFormat::R8g8b8);
+        let mut image = loader.load().await.unwrap();
+        assert_eq!(image.mime_type(), glycin::MimeType::TGA);
+
+        let frame = image.next_frame().await.unwrap();
+        assert_eq!(frame.buf_slice(), [255, 0, 0, 0, 0, 255]);
+
+        let mut loader = glycin::Loader::new(file);
+        loader.as_mime_type_fallbacks(vec![glycin::MimeType::PNG]);
+        assert!(loader.load().await.is_err());
+    });
+
+    sniffer.unregister();
+}
+
+#[test]
+fn processor_loader_placeholder_scan_size() {
+    init();
+
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("placeholder.unknown");
+
+    block_on(async {
+        let mut creator = glycin::Creator::new(glycin::MimeType::JPEG).await.unwrap();
+        creator
+            .add_frame(64, 32, glycin::MemoryFormat::R8g8b8, vec![128; 64 * 32 * 3])
+            .unwrap();
+        let jpeg = creator.create().await.unwrap().data_full();
+
+        // Unknown format with an embedded preview after 1 KiB
+        let mut data = vec![0x42; 1024];
+        data.extend_from_slice(&jpeg);
+        data.extend_from_slice(&[0x42; 1024]);
+        std::fs::write(&path, data).unwrap();
+
+        let loader = glycin::Loader::new(gio::File::for_path(&path));
+        let (image, placeholder) = loader.load_or_placeholder().await.unwrap();
+        assert!(placeholder);
+        assert_eq!(
+            (image.details().width(), image.details().height()),
+            (64, 32)
+        );
+
+        // Preview is beyond the scanned range
+        let mut loader = glycin::Loader::new(gio::File::for_path(&path));
+        loader.limits(glycin::Limits::default().max_placeholder_scan_size(1024));
+        let err = loader.load_or_placeholder().await.unwrap_err();
+        assert!(err.unsupported_format().is_some());
+    });
+}
+
+#[test]
+fn processor_loader_thumbnail_directory() {
+    use futures_util::StreamExt;
+
+    init();
+
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("thumbnail-directory");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+
+    block_on(async {
+        let mut creator = glycin::Creator::new(glycin::MimeType::PNG).await.unwrap();
+        creator
+            .add_frame(64, 32, glycin::MemoryFormat::G8, vec![128; 64 * 32])
+            .unwrap();
+        let png = creator.create().await.unwrap().data_full();
+        std::fs::write(dir.join("a.png"), &png).unwrap();
+        std::fs::write(dir.join("b.png"), &png).unwrap();
+    });
+    std::fs::write(dir.join("notes.txt"), "not an image").unwrap();
+    std::fs::create_dir(dir.join("subdir.png")).unwrap();
+
+    let results = block_on(
+        glycin::Loader::thumbnail_directory(gio::File::for_path(&dir), 16, 2).collect::<Vec<_>>(),
+    );
+
+    let mut names = results
+        .iter()
+        .map(|(file, _)| file.basename().unwrap())
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, [PathBuf::from("a.png"), PathBuf::from("b.png")]);
+
+    for (_, frame) in results {
+        let frame = frame.unwrap();
+        assert_eq!((frame.width(), frame.height()), (16, 8));
+    }
+
+    let results = block_on(
+        glycin::Loader::thumbnail_directory(gio::File::for_path(dir.join("missing")), 16, 2)
+            .collect::<Vec<_>>(),
+    );
+    assert_eq!(results.len(), 1);
+    assert!(results[0].1.is_err());
+}
+
+#[test]
+fn processor_loader_index_library() {
+    use futures_util::StreamExt;
+
+    init();
+
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("index-library");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("subdir")).unwrap();
+
+    block_on(async {
+        let mut creator = glycin::Creator::new(glycin::MimeType::PNG).await.unwrap();
+        creator
+            .add_frame(64, 32, glycin::MemoryFormat::G8, vec![128; 64 * 32])
+            .unwrap();
+        let png = creator.create().await.unwrap().data_full();
+        std::fs::write(dir.join("a.png"), &png).unwrap();
+        std::fs::write(dir.join("subdir").join("b.png"), &png).unwrap();
+    });
+    std::fs::write(dir.join("notes.txt"), "not an image").unwrap();
+
+    let options = glycin::IndexOptions::new()
+        .concurrency(2)
+        .perceptual_hash(true);
+    let entries = block_on(
+        glycin::Loader::index_library(gio::File::for_path(&dir), options).collect::<Vec<_>>(),
+    );
+
+    let mut names = entries
+        .iter()
+        .map(|entry| entry.file().basename().unwrap())
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, [PathBuf::from("a.png"), PathBuf::from("b.png")]);
+
+    for entry in &entries {
+        assert!(entry.n_done() <= entry.n_total());
+        let image = entry.result().unwrap();
+        assert_eq!((image.width(), image.height()), (64, 32));
+        assert!(image.perceptual_hash().is_some());
+    }
+    assert_eq!(entries.last().unwrap().n_done(), 2);
+    assert_eq!(entries.last().unwrap().n_total(), 2);
+    assert_eq!(entries.last().unwrap().progress(), 1.);
+
+    let entries = block_on(
+        glycin::Loader::index_library(
+            gio::File::for_path(dir.join("missing")),
+            glycin::IndexOptions::new(),
+        )
+        .collect::<Vec<_>>(),
+    );
+    assert_eq!(entries.len(), 1);
+    assert!(entries[0].result().is_err());
+}
+
+#[test]
+fn processor_loader_index_library_cancel() {
+    use futures_util::StreamExt;
+
+    init();
+
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("index-library-cancel");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let n_files = 20;
+
+    block_on(async {
+        let mut creator = glycin::Creator::new(glycin::MimeType::PNG).await.unwrap();
+        creator
+            .add_frame(64, 32, glycin::MemoryFormat::G8, vec![128; 64 * 32])
+            .unwrap();
+        let png = creator.create().await.unwrap().data_full();
+        for i in 0..n_files {
+            std::fs::write(dir.join(format!("{i}.png")), &png).unwrap();
+        }
+    });
+
+    let cancellable = gio::Cancellable::new();
+    let options = glycin::IndexOptions::new()
+        .concurrency(1)
+        .cancellable(cancellable.clone());
+
+    block_on(async {
+        let mut stream = glycin::Loader::index_library(gio::File::for_path(&dir), options);
+
+        let first = stream.next().await.unwrap();
+        assert!(first.result().is_ok());
+
+        cancellable.cancel();
+
+        // Queued files are skipped and the stream ends
+        let rest = stream.collect::<Vec<_>>().await;
+        assert!(rest.len() < n_files - 1);
+        for entry in rest {
+            assert!(entry.result().is_err());
+        }
+    });
+}
+
+#[test]
+#[cfg(feature = "external-loaders")]
+fn processor_loader_intrinsic_aspect_ratio() {
+    init();
+
+    block_on(async {
+        let svg = br#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 200 100"/>"#;
+        let loader = glycin::Loader::new_vec(svg.to_vec());
+        let image = loader.load().await.unwrap();
+        assert_eq!(image.details().intrinsic_aspect_ratio(), Some(2.));
+
+        let loader = glycin::Loader::new(gio::File::for_path("test-images/images/color/color.jpg"));
+        let image = loader.load().await.unwrap();
+        let details = image.details();
+        assert_eq!(
+            details.intrinsic_aspect_ratio(),
+            Some(details.width() as f64 / details.height() as f64)
+        );
+    });
+}
+
+#[test]
+#[cfg(feature = "external-loaders")]
+fn processor_loader_sandbox_tmpdir() {
+    init();
+
+    block_on(async {
+        let tmpdir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("sandbox-tmpdir");
+        std::fs::create_dir_all(&tmpdir).unwrap();
+
+        let mut loader =
+            glycin::Loader::new(gio::File::for_path("test-images/images/color/color.jpg"));
+        loader.sandbox_tmpdir(&tmpdir);
+        let mut image = loader.load().await.unwrap();
+        image.next_frame().await.unwrap();
+
+        let mut loader =
+            glycin::Loader::new(gio::File::for_path("test-images/images/color/color.jpg"));
+        loader.sandbox_tmpdir(tmpdir.join("missing"));
+        let err = loader.load().await.unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            glycin::ErrorKind::InvalidTmpdir { .. }
+        ));
+    });
+}
+
+#[test]
+#[cfg(feature = "external-loaders")]
+fn processor_loader_warm_loader() {
+    init();
+
+    block_on(async {
+        let warm_loader = glycin::WarmLoader::new(glycin::MimeType::JPEG)
+            .await
+            .unwrap();
+
+        for path in [
+            "test-images/images/color/color.jpg",
+            "test-images/images/color/color.png",
+        ] {
+            let mut image = warm_loader.load(gio::File::for_path(path)).await.unwrap();
+            image.next_frame().await.unwrap();
+        }
+
+        let details = warm_loader
+            .info(gio::File::for_path("test-images/images/color/color.jpg"))
+            .await
+            .unwrap();
+        assert!(details.width() > 0);
+
+        let err = warm_loader
+            .load(gio::File::for_path("test-images/images/color/color.svg"))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            glycin::ErrorKind::IncompatibleWarmLoader(_)
+        ));
+
+        warm_loader.close();
+    });
+}
+
+#[test]
+fn processor_loader_applied_transformations() {
+    init();
+
+    block_on(async {
+        let rotated = "test-images/images/color-exif-orientation/color-rotated-90.jpg";
+
+        let loader = glycin::Loader::new(gio::File::for_path(rotated));
+        let frame = loader.load().await.unwrap().next_frame().await.unwrap();
+        let applied = frame.applied_transformations();
+        assert!(applied.orientation);
+        assert!(!applied.tone_mapped);
+
+        let mut loader = glycin::Loader::new(gio::File::for_path(rotated));
+        loader.apply_transformations(false);
+        let frame = loader.load().await.unwrap().next_frame().await.unwrap();
+        assert!(!frame.applied_transformations().orientation);
+
+        let mut loader = glycin::Loader::new(gio::File::for_path(rotated));
+        loader.raw(true);
+        let frame = loader.load().await.unwrap().next_frame().await.unwrap();
+        assert_eq!(
+            frame.applied_transformations(),
+            glycin::AppliedTransforms::default()
+        );
+
+        let mut loader =
+            glycin::Loader::new(gio::File::for_path("test-images/images/color/color.jpg"));
+        loader.memory_format(glycin::MemoryFormat::R8g8b8a8Premultiplied);
+        let frame = loader.load().await.unwrap().next_frame().await.unwrap();
+        assert!(frame.applied_transformations().memory_format_changed);
+        assert!(!frame.applied_transformations().orientation);
+
+        let loader = glycin::Loader::new(gio::File::for_path(
+            "test-images/images/gray-iccp/gray-iccp-GA16.png",
+        ));
+        let frame = loader.load().await.unwrap().next_frame().await.unwrap();
+        assert!(frame.applied_transformations().icc_to_srgb);
+        assert!(matches!(frame.color_state(), glycin::ColorState::Srgb));
+    });
+}
+
+#[test]
+fn processor_loader_images_equal() {
+    init();
+
+    block_on(async {
+        let jpeg = gio::File::for_path("test-images/images/color/color.jpg");
+        let png = gio::File::for_path("test-images/images/color/color.png");
+
+        assert!(
+            glycin::images_equal(jpeg.clone(), jpeg.clone(), gio::Cancellable::new())
+                .await
+                .unwrap()
+        );
+        assert!(
+            !glycin::images_equal(jpeg.clone(), png, gio::Cancellable::new())
+                .await
+                .unwrap()
+        );
+
+        let cancellable = gio::Cancellable::new();
+        cancellable.cancel();
+        let err = glycin::images_equal(jpeg.clone(), jpeg, cancellable)
+            .await
+            .unwrap_err();
+        assert!(err.is_cancelled());
+    });
+}
+
+#[test]
+fn processor_loader_has_transparency() {
+    init();
+
+    block_on(async {
+        let loader = glycin::Loader::new(gio::File::for_path("test-images/images/color/color.jpg"));
+        let image = loader.load().await.unwrap();
+        assert_eq!(image.details().has_transparency(), Some(false));
+    });
+}
+
+#[test]
+fn processor_loader_read_buffer_size() {
+    init();
+
+    block_on(async {
+        let mut loader =
+            glycin::Loader::new(gio::File::for_path("test-images/images/color/color.png"));
+        loader.read_buffer_size(1);
+        let mut image = loader.load().await.unwrap();
+        let frame = image.next_frame().await.unwrap();
+        assert_eq!(frame.width(), image.details().width());
+    });
+}
+
+#[test]
+fn processor_loader_stats() {
+    init();
+
+    block_on(async {
+        let path = "test-images/images/color/color.png";
+        let loader = glycin::Loader::new(gio::File::for_path(path));
+        let mut image = loader.load().await.unwrap();
+
+        let file_size = std::fs::metadata(path).unwrap().len();
+        let stats = image.stats();
+        assert!(stats.input_bytes <= file_size);
+        assert_eq!(stats.output_bytes, 0);
+
+        let frame = image.next_frame().await.unwrap();
+        let stats = image.stats();
+        // The complete file has been read by now
+        assert_eq!(stats.input_bytes, file_size);
+        assert_eq!(stats.output_bytes, frame.buf_bytes().len() as u64);
+        assert!(stats.decode_time > std::time::Duration::ZERO);
+    });
+}
+
+#[test]
+fn processor_loader_fifo() {
+    init();
+
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("fifo.png");
+    let _ = std::fs::remove_file(&path);
+    let status = std::process::Command::new("mkfifo")
+        .arg(&path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let data = std::fs::read("test-images/images/color/color.png").unwrap();
+    let writer = std::thread::spawn({
+        let path = path.clone();
+        move || std::fs::write(path, data).unwrap()
+    });
+
+    block_on(async {
+        // FIFOs report a size of zero, which must not be compared to the bytes read
+        let loader = glycin::Loader::new(gio::File::for_path(&path));
+        let mut image = loader.load().await.unwrap();
+        image.next_frame().await.unwrap();
+    });
+
+    writer.join().unwrap();
+}
+
+#[test]
+fn processor_loader_export_frames() {
+    init();
+
+    block_on(async {
+        let mut dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+        dir.push("export-frames");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let loader = glycin::Loader::new(gio::File::for_path(
+            "test-images/images/animated-numbers/animated-numbers.webp",
+        ));
+        let mut image = loader.load().await.unwrap();
+        let n_written = image
+            .export_frames(&dir, glycin::MimeType::PNG)
+            .await
+            .unwrap();
+        assert_eq!(n_written, 4);
+
+        for n in 0..n_written {
+            let path = dir.join(format!("frame_{n:04}.png"));
+            let mut image = glycin::Loader::new(gio::File::for_path(&path))
+                .load()
+                .await
+                .unwrap();
+            let frame = image.next_frame().await.unwrap();
+            assert_eq!(frame.width(), image.details().width());
+        }
+        assert!(!dir.join("frame_0004.png").exists());
+    });
+}
+
+#[test]
+fn processor_loader_perceptual_hash() {
+    init();
+
+    block_on(async {
+        let path = "test-images/images/color/color.jpg";
+        let loader = glycin::Loader::new(gio::File::for_path(path));
+        let mut image = loader.load().await.unwrap();
+        let hash = image.perceptual_hash().await.unwrap();
+
+        // The frame might have been decoded with a smaller scale
+        let loader = glycin::Loader::new(gio::File::for_path(path));
+        let frame = loader.load().await.unwrap().next_frame().await.unwrap();
+        let frame_hash = frame.perceptual_hash().unwrap();
+        assert!(
+            (hash ^ frame_hash).count_ones() <= 10,
+            "{hash:064b} {frame_hash:064b}"
+        );
+
+        // Re-save with lower quality
+        let mut creator = glycin::Creator::new(glycin::MimeType::JPEG).await.unwrap();
+        creator.set_encoding_quality(50).unwrap();
+        creator
+            .add_frame_with_stride(
+                frame.width(),
+                frame.height(),
+                frame.stride(),
+                frame.memory_format(),
+                frame.buf_slice().to_vec(),
+            )
+            .unwrap();
+        let data = creator.create().await.unwrap().data_full();
+
+        let mut resaved = glycin::Loader::new_vec(data).load().await.unwrap();
+        let resaved_hash = resaved.perceptual_hash().await.unwrap();
+        assert!(
+            (hash ^ resaved_hash).count_ones() <= 10,
+            "{hash:064b} {resaved_hash:064b}"
+        );
+
+        // The PNG version of the image
+        let loader = glycin::Loader::new(gio::File::for_path("test-images/images/color/color.png"));
+        let png_hash = loader
+            .load()
+            .await
+            .unwrap()
+            .perceptual_hash()
+            .await
+            .unwrap();
+        assert!((hash ^ png_hash).count_ones() <= 10);
+    });
+}
+
+#[test]
+fn processor_loader_blurhash() {
+    init();
+
+    block_on(async {
+        let path = "test-images/images/color/color.jpg";
+        let loader = glycin::Loader::new(gio::File::for_path(path));
+        let hash = loader.load().await.unwrap().blurhash(4, 3).await.unwrap();
+
+        // The frame might have been decoded with a smaller scale
+        let loader = glycin::Loader::new(gio::File::for_path(path));
+        let frame = loader.load().await.unwrap().next_frame().await.unwrap();
+        let frame_hash = frame.blurhash(4, 3).unwrap();
+        assert_eq!(hash.len(), frame_hash.len());
+        // Number of components
+        assert_eq!(hash[..1], frame_hash[..1]);
+    });
+}
+
+#[test]
+fn processor_loader_luma_preview() {
+    init();
+
+    block_on(async {
+        let path = "test-images/images/color/color.jpg";
+        let image = glycin::Loader::new(gio::File::for_path(path))
+            .load()
+            .await
+            .unwrap();
+        let (width, height) = (image.details().width(), image.details().height());
+
+        let frame = glycin::Loader::new(gio::File::for_path(path))
+            .luma_preview(32)
+            .await
+            .unwrap();
+
+        assert_eq!(frame.memory_format(), glycin::MemoryFormat::G8);
+        assert_eq!(frame.memory_format().n_channels(), 1);
+        assert_eq!(frame.width().max(frame.height()), 32);
+        assert_eq!(frame.stride(), frame.width());
+        assert_eq!(
+            frame.buf_slice().len(),
+            (frame.width() * frame.height()) as usize
+        );
+        assert_eq!(width > height, frame.width() > frame.height());
+        assert_eq!(frame.details().info_grayscale(), Some(true));
+        assert_eq!(frame.details().info_bit_depth(), Some(8));
+        assert!(frame.details().color_icc_profile().is_none());
+    });
+}
+
+#[test]
+fn processor_loader_symlinked_base_dir() {
+    init();
+
+    block_on(async {
+        let mut dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+        dir.push("symlinked-base-dir");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("real")).unwrap();
+        std::os::unix::fs::symlink("real", dir.join("link")).unwrap();
+
+        // Opaque red pixel referenced by the SVG
+        let mut creator = glycin::Creator::new(glycin::MimeType::PNG).await.unwrap();
+        creator
+            .add_frame(1, 1, glycin::MemoryFormat::R8g8b8a8, vec![255, 0, 0, 255])
+            .unwrap();
+        let png = creator.create().await.unwrap().data_full();
+        std::fs::write(dir.join("real/pixel.png"), png).unwrap();
+        std::fs::write(
+            dir.join("real/image.svg"),
+            br#"<svg xmlns="http://www.w3.org/2000/svg" width="4" height="4">
+                <image href="pixel.png" width="4" height="4"/>
+            </svg>"#,
+        )
+        .unwrap();
+
+        let mut loader = glycin::Loader::new(gio::File::for_path(dir.join("link/image.svg")));
+        loader
+            .use_expose_base_dir(true)
+            .memory_format(glycin::MemoryFormat::R8g8b8a8);
+        let mut image = loader.load().await.unwrap();
+        let frame = image.next_frame().await.unwrap();
+        assert_eq!(&frame.buf_slice()[..4], &[255, 0, 0, 255]);
+    });
+}
+
+#[test]
+fn processor_loader_xmp_sidecar() {
+    init();
+
+    block_on(async {
+        let mut dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+        dir.push("xmp-sidecar");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut creator = glycin::Creator::new(glycin::MimeType::PNG).await.unwrap();
+        creator
+            .add_frame(1, 1, glycin::MemoryFormat::G8, vec![0])
+            .unwrap();
+        let png = creator.create().await.unwrap().data_full();
+        std::fs::write(dir.join("image.png"), png).unwrap();
+
+        let xmp = br#"<x:xmpmeta xmlns:x="adobe:ns:meta/"/>"#;
+        std::fs::write(dir.join("image.xmp"), xmp).unwrap();
+
+        let loader = glycin::Loader::new(gio::File::for_path(dir.join("image.png")));
+        let image = loader.load().await.unwrap();
+        assert_eq!(image.details().metadata_xmp(), None);
+
+        let loader = glycin::Loader::new_multi(
+            gio::File::for_path(dir.join("image.png")),
+            vec![gio::File::for_path(dir.join("image.xmp"))],
+        );
+        let image = loader.load().await.unwrap();
+        assert_eq!(image.details().metadata_xmp(), Some(&xmp[..]));
+    });
+}
+
+#[test]
+fn processor_loader_use_sidecars() {
+    init();
+
+    block_on(async {
+        let mut dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+        dir.push("use-sidecars");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut creator = glycin::Creator::new(glycin::MimeType::PNG).await.unwrap();
+        creator
+            .add_frame(1, 1, glycin::MemoryFormat::G8, vec![0])
+            .unwrap();
+        let png = creator.create().await.unwrap().data_full();
+        let image_path = dir.join("photo.png");
+        std::fs::write(&image_path, png).unwrap();
+
+        // No sidecar present
+        let mut loader = glycin::Loader::new(gio::File::for_path(&image_path));
+        loader.use_sidecars(true);
+        let image = loader.load().await.unwrap();
+        assert_eq!(image.details().metadata_xmp(), None);
+
+        // Adobe style sidecar
+        let xmp = br#"<x:xmpmeta xmlns:x="adobe:ns:meta/"/>"#;
+        std::fs::write(dir.join("photo.xmp"), xmp).unwrap();
+
+        let loader = glycin::Loader::new(gio::File::for_path(&image_path));
+        let image = loader.load().await.unwrap();
+        assert_eq!(image.details().metadata_xmp(), None);
+
+        let mut loader = glycin::Loader::new(gio::File::for_path(&image_path));
+        loader.use_sidecars(true);
+        let image = loader.load().await.unwrap();
+        assert_eq!(image.details().metadata_xmp(), Some(&xmp[..]));
+        assert_eq!(
+            image
+                .details()
+                .metadata_key_value()
+                .and_then(|x| x.get("glycin:xmp-sidecar"))
+                .map(String::as_str),
+            Some("photo.xmp")
+        );
+
+        // Sidecars including the extension take precedence
+        let xmp_full_name = br#"<x:xmpmeta xmlns:x="adobe:ns:meta/" />"#;
+        std::fs::write(dir.join("photo.png.xmp"), xmp_full_name).unwrap();
+
+        let mut loader = glycin::Loader::new(gio::File::for_path(&image_path));
+        loader.use_sidecars(true);
+        let image = loader.load().await.unwrap();
+        assert_eq!(image.details().metadata_xmp(), Some(&xmp_full_name[..]));
+    });
+}
+
+#[test]
+fn processor_loader_xmp_sidecar_merge() {
+    init();
+
+    block_on(async {
+        let mut dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+        dir.push("xmp-sidecar-merge");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let xmp = |properties: &str| {
+            format!(
+                r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+                <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+                <rdf:Description
+                    xmlns:GFocus="http://ns.google.com/photos/1.0/focus/"
+                    xmlns:crs="http://ns.adobe.com/camera-raw-settings/1.0/"
+                    {properties}/>
+                </rdf:RDF>
+                </x:xmpmeta>"#
+            )
+        };
+
+        // JPEG with embedded XMP in an APP1 segment
+        let mut creator = glycin::Creator::new(glycin::MimeType::JPEG).await.unwrap();
+        creator
+            .add_frame(1, 1, glycin::MemoryFormat::G8, vec![0])
+            .unwrap();
+        let jpeg = creator.create().await.unwrap().data_full();
+        let embedded = xmp(
+            r#"GFocus:FocalPointX="0.25" GFocus:FocalPointY="0.25" crs:HasCrop="True"
+            crs:CropLeft="0" crs:CropTop="0" crs:CropRight="0.5" crs:CropBottom="0.5""#,
+        );
+        let mut segment = b"http://ns.adobe.com/xap/1.0/\0".to_vec();
+        segment.extend_from_slice(embedded.as_bytes());
+        let mut data = jpeg[..2].to_vec();
+        data.extend_from_slice(&[0xFF, 0xE1]);
+        data.extend_from_slice(&(segment.len() as u16 + 2).to_be_bytes());
+        data.extend_from_slice(&segment);
+        data.extend_from_slice(&jpeg[2..]);
+        std::fs::write(dir.join("photo.jpg"), data).unwrap();
+
+        // Uppercase extension as written by some cameras and tools
+        let sidecar = xmp(r#"GFocus:FocalPointX="0.75" GFocus:FocalPointY="0.75""#);
+        std::fs::write(dir.join("photo.jpg.XMP"), &sidecar).unwrap();
+
+        let mut loader = glycin::Loader::new(gio::File::for_path(dir.join("photo.jpg")));
+        loader.use_sidecars(true);
+        let image = loader.load().await.unwrap();
+        let details = image.details();
+        // Sidecar properties take precedence over embedded ones
+        assert_eq!(details.focal_point(), Some((0.75, 0.75)));
+        assert_eq!(details.suggested_crop(), Some((0., 0., 0.5, 0.5)));
+        assert_eq!(
+            details
+                .metadata_key_value()
+                .and_then(|x| x.get("glycin:xmp-sidecar"))
+                .map(String::as_str),
+            Some("photo.jpg.XMP")
+        );
+
+        // Sidecars don't depend on the loader
+        std::fs::write(
+            dir.join("drawing.svg"),
+            br#"<svg xmlns="http://www.w3.org/2000/svg" width="4" height="4"/>"#,
+        )
+        .unwrap();
+        std::fs::write(dir.join("drawing.xmp"), &sidecar).unwrap();
+
+        let mut loader = glycin::Loader::new(gio::File::for_path(dir.join("drawing.svg")));
+        loader.use_sidecars(true);
+        let image = loader.load().await.unwrap();
+        assert_eq!(image.details().metadata_xmp(), Some(sidecar.as_bytes()));
+        assert_eq!(image.details().focal_point(), Some((0.75, 0.75)));
+
+        // Invalid sidecars are ignored
+        std::fs::write(dir.join("drawing.xmp"), b"<x:xmpmeta").unwrap();
+        let mut loader = glycin::Loader::new(gio::File::for_path(dir.join("drawing.svg")));
+        loader.use_sidecars(true);
+        let image = loader.load().await.unwrap();
+        assert_eq!(image.details().metadata_xmp(), None);
+        assert!(!image.details().warnings().is_empty());
+    });
+}
+
+#[test]
+fn processor_loader_max_compression_ratio() {
+    init();
+
+    block_on(async {
+        // Uniform image that compresses extremely well
+        let mut creator = glycin::Creator::new(glycin::MimeType::PNG).await.unwrap();
+        creator
+            .add_frame(1000, 1000, glycin::MemoryFormat::G8, vec![0; 1000 * 1000])
+            .unwrap();
+        let png = creator.create().await.unwrap().data_full();
+
+        let mut loader = glycin::Loader::new_vec(png.clone());
+        loader.max_compression_ratio(100.);
+        let err = loader.load().await.unwrap_err();
+        assert!(
+            matches!(
+                err.kind(),
+                glycin::ErrorKind::SuspectedDecompressionBomb { .. }
+            ),
+            "Unexpected error: {err}"
+        );
+
+        let mut loader = glycin::Loader::new_vec(png.clone());
+        loader.max_compression_ratio(1_000_000.);
+        loader.load().await.unwrap();
+
+        glycin::Loader::new_vec(png).load().await.unwrap();
+
+        let mut loader =
+            glycin::Loader::new(gio::File::for_path("test-images/images/color/color.jpg"));
+        loader.max_compression_ratio(1000.);
+        loader.load().await.unwrap();
+    });
+}
+
+#[test]
+fn processor_loader_presentation_time() {
+    init();
+
+    block_on(async {
+        let file = gio::File::for_path("test-images/images/animated-numbers/animated-numbers.webp");
+        let mut image = glycin::Loader::new(file).load().await.unwrap();
+
+        // Continues counting after looping back to the first frame
+        for n in 0..6 {
+            let frame = image.next_frame().await.unwrap();
+            assert_eq!(frame.frame_index(), n);
+            assert_eq!(
+                frame.presentation_time(),
+                Duration::from_millis(200) * n as u32
+            );
+        }
+
+        let file = gio::File::for_path("test-images/images/color/color.png");
+        let mut image = glycin::Loader::new(file).load().await.unwrap();
+        let frame = image.next_frame().await.unwrap();
+        assert_eq!(frame.frame_index(), 0);
+        assert_eq!(frame.presentation_time(), Duration::ZERO);
+    });
+}
+
+#[test]
+fn processor_loader_cancel_frame() {
+    init();
+
+    block_on(async {
+        let file = gio::File::for_path("test-images/images/animated-numbers/animated-numbers.gif");
+        let mut image = glycin::Loader::new(file).load().await.unwrap();
+
+        let cancellable = gio::Cancellable::new();
+        let frame_cancellable = cancellable.clone();
+        std::thread::spawn(move || frame_cancellable.cancel());
+
+        let result = image
+            .specific_frame(glycin::FrameRequest::new().cancellable(cancellable))
+            .await;
+        if let Err(err) = &result {
+            assert!(err.is_cancelled(), "Error: {err}");
+        }
+
+        // The loader process is still usable
+        let frame = image.next_frame().await.unwrap();
+        let expected_index = if result.is_ok() { 1 } else { 0 };
+        assert_eq!(frame.frame_index(), expected_index);
+    });
+}
+
+#[test]
+fn processor_loader_allow_partial() {
+    init();
+
+    block_on(async {
+        let mut data =
+            std::fs::read("test-images/images/animated-numbers/animated-numbers.gif").unwrap();
+        // Cut off the trailer and the end of the last frame
+        data.truncate(data.len() - 20);
+
+        let request = glycin::FrameRequest::default().loop_animation(false);
+
+        // Fails at the incomplete frame
+        let mut image = glycin::Loader::new_vec(data.clone()).load().await.unwrap();
+        let err = loop {
+            match image.specific_frame(request.clone()).await {
+                Ok(_) => continue,
+                Err(err) => break err,
+            }
+        };
+        assert!(!err.has_no_more_frames());
+
+        // Ends before the incomplete frame
+        let mut loader = glycin::Loader::new_vec(data);
+        loader.allow_partial(true);
+        let mut image = loader.load().await.unwrap();
+
+        let mut frames = Vec::new();
+        let err = loop {
+            match image.specific_frame(request.clone()).await {
+                Ok(frame) => frames.push(frame),
+                Err(err) => break err,
+            }
+        };
+        assert!(err.has_no_more_frames());
+
+        let (last, complete) = frames.split_last().unwrap();
+        assert!(!last.details().warnings().is_empty());
+        assert!(complete.iter().all(|x| x.details().warnings().is_empty()));
+    });
+}
+
+#[test]
+fn processor_loader_source_byte_size() {
+    init();
+
+    block_on(async {
+        let path = "test-images/images/color/color.png";
+        let size = std::fs::metadata(path).unwrap().len();
+
+        let image = glycin::Loader::new(gio::File::for_path(path))
+            .load()
+            .await
+            .unwrap();
+        assert_eq!(image.details().source_byte_size(), Some(size));
+        assert_eq!(image.details().info_format_name(), Some("PNG"));
+        assert_eq!(image.details().info_container_format(), None);
+
+        let image = glycin::Loader::new_vec(std::fs::read(path).unwrap())
+            .load()
+            .await
+            .unwrap();
+        assert_eq!(image.details().source_byte_size(), Some(size));
+
+        let file = gio::File::for_path("test-images/images/animated-numbers/animated-numbers.webp");
+        let image = glycin::Loader::new(file).load().await.unwrap();
+        assert_eq!(image.details().info_format_name(), Some("WebP"));
+        assert_eq!(image.details().info_container_format(), Some("RIFF"));
+    });
+}
+
+#[test]
+fn processor_loader_frame_stride() {
+    init();
+
+    block_on(async {
+        let file = gio::File::for_path("test-images/images/animated-numbers/animated-numbers.webp");
+
+        let mut image = glycin::Loader::new(file.clone()).load().await.unwrap();
+        let mut all_frames = Vec::new();
+        for _ in 0..6 {
+            all_frames.push(image.next_frame().await.unwrap());
+        }
+
+        let mut loader = glycin::Loader::new(file);
+        loader.frame_stride(2);
+        let mut image = loader.load().await.unwrap();
+
+        for n in [0, 2, 4] {
+            let frame = image.next_frame().await.unwrap();
+            let expected = &all_frames[n];
+
+            assert_eq!(frame.frame_index(), n as u64);
+            assert_eq!(frame.presentation_time(), expected.presentation_time());
+            assert_eq!(frame.buf_slice(), expected.buf_slice());
+            assert_eq!(
+                frame.delay(),
+                Some(expected.delay().unwrap() + all_frames[n + 1].delay().unwrap())
+            );
+        }
+    });
+}
+
+#[test]
+fn processor_loader_planar() {
+    init();
+
+    block_on(async {
+        let mut creator = glycin::Creator::new(glycin::MimeType::PNG).await.unwrap();
+        creator
+            .add_frame(
+                2,
+                2,
+                glycin::MemoryFormat::R8g8b8,
+                vec![10, 20, 30, 11, 21, 31, 12, 22, 32, 13, 23, 33],
+            )
+            .unwrap();
+        let png = creator.create().await.unwrap().data_full();
+
+        let mut loader = glycin::Loader::new_vec(png);
+        loader
+            .memory_format(glycin::MemoryFormat::R8g8b8)
+            .planar(true);
+        let mut image = loader.load().await.unwrap();
+        let frame = image.next_frame().await.unwrap();
+
+        assert_eq!(frame.memory_format(), glycin::MemoryFormat::R8g8b8);
+        assert_eq!(frame.stride(), 2);
+        assert_eq!(
+            frame.buf_slice(),
+            &[10, 11, 12, 13, 20, 21, 22, 23, 30, 31, 32, 33]
+        );
+
+        let planes = frame.planes().unwrap();
+        assert_eq!(planes.len(), 3);
+        for (n, plane) in planes.iter().enumerate() {
+            assert_eq!(plane.offset, n * 4);
+            assert_eq!(plane.stride, 2);
+        }
+
+        // Operations that need interleaved channels
+        let is_planar_error =
+            |err: glycin::Error| matches!(err.kind(), glycin::ErrorKind::PlanarFrame);
+        assert!(is_planar_error(frame.texture().unwrap_err()));
+        assert!(is_planar_error(frame.perceptual_hash().unwrap_err()));
+        assert!(is_planar_error(frame.blurhash(4, 3).unwrap_err()));
+        assert!(is_planar_error(frame.realign_stride(256).unwrap_err()));
+        assert!(is_planar_error(
+            glycin::Editor::from_frame(frame.clone(), glycin::MimeType::PNG).unwrap_err()
+        ));
+    });
+}
+
+#[test]
+fn processor_loader_straight_alpha() {
+    init();
+
+    block_on(async {
+        let texture = vec![
+            200, 100, 50, 128, 10, 20, 30, 255, 0, 0, 0, 0, 40, 80, 120, 64,
+        ];
+
+        let mut creator = glycin::Creator::new(glycin::MimeType::PNG).await.unwrap();
+        creator
+            .add_frame(2, 2, glycin::MemoryFormat::R8g8b8a8, texture.clone())
+            .unwrap();
+        let png = creator.create().await.unwrap().data_full();
+
+        let mut loader = glycin::Loader::new_vec(png.clone());
+        loader.memory_format(glycin::MemoryFormat::R8g8b8a8Premultiplied);
+        let frame = loader.load().await.unwrap().next_frame().await.unwrap();
+        assert_eq!(
+            frame.memory_format(),
+            glycin::MemoryFormat::R8g8b8a8Premultiplied
+        );
+
+        let mut loader = glycin::Loader::new_vec(png);
+        loader
+            .memory_format(glycin::MemoryFormat::R8g8b8a8Premultiplied)
+            .straight_alpha(true);
+        let frame = loader.load().await.unwrap().next_frame().await.unwrap();
+        assert_eq!(frame.memory_format(), glycin::MemoryFormat::R8g8b8a8);
+        assert_eq!(frame.buf_slice(), texture);
+    });
+}
+
+#[test]
+#[cfg(feature = "external-loaders")]
+fn processor_loader_straight_alpha_unpremultiply() {
+    init();
+
+    block_on(async {
+        // The SVG loader always returns premultiplied data
+        let svg = br#"<svg xmlns="http://www.w3.org/2000/svg" width="2" height="2">
+            <rect width="2" height="2" fill="rgb(200,100,50)" fill-opacity="0.5"/>
+            </svg>"#;
+
+        let loader = glycin::Loader::new_vec(svg.to_vec());
+        let frame = loader.load().await.unwrap().next_frame().await.unwrap();
+        assert!(frame.memory_format().is_premultiplied());
+
+        let mut loader = glycin::Loader::new_vec(svg.to_vec());
+        loader
+            .memory_format(glycin::MemoryFormat::R8g8b8a8)
+            .straight_alpha(true);
+        let frame = loader.load().await.unwrap().next_frame().await.unwrap();
+        assert_eq!(frame.memory_format(), glycin::MemoryFormat::R8g8b8a8);
+
+        // Color values are divided by alpha again
+        for pixel in frame.buf_slice().chunks_exact(4) {
+            for (value, expected) in pixel.iter().zip([200, 100, 50, 128]) {
+                assert!(
+                    value.abs_diff(expected) <= 2,
+                    "{pixel:?} is not straight alpha"
+                );
+            }
+        }
+    });
+}
+
 fn test_dir(dir: impl AsRef<Path>) {
     block_on(test_dir_options(dir, true));
 }
@@ -130,7 +1299,7 @@ async fn test_dir_animated(dir: impl AsRef<Path>) {
                 }
             };
 
-            let data = texture_to_bytes(&frame.texture());
+            let data = texture_to_bytes(&frame.texture().unwrap());
             let result = compare_images(reference_path, &path, &data, false).await;
 
             if result.is_failed() {
@@ -191,3 +1360,851 @@ async fn test_input_stream() {
 
     assert_eq!(image.details().width(), 600);
 }
+
+#[test]
+fn processor_loader_load_rgba8() {
+    init();
+
+    block_on(async {
+        let loader = glycin::Loader::new(gio::File::for_path("test-images/images/color/color.png"));
+        let (reference, width, height) = loader.load_rgba8().await.unwrap();
+        assert_eq!(reference.len(), width as usize * height as usize * 4);
+
+        // Same pixels with 16 bit per channel
+        let reference16 = reference
+            .iter()
+            .flat_map(|x| (*x as u16 * 257).to_ne_bytes())
+            .collect::<Vec<u8>>();
+
+        for (mime_type, memory_format, data) in [
+            (
+                glycin::MimeType::PNG,
+                glycin::MemoryFormat::R8g8b8a8,
+                &reference,
+            ),
+            (
+                glycin::MimeType::PNG,
+                glycin::MemoryFormat::R16g16b16a16,
+                &reference16,
+            ),
+            (
+                glycin::MimeType::QOI,
+                glycin::MemoryFormat::R8g8b8a8,
+                &reference,
+            ),
+            (
+                glycin::MimeType::TIFF,
+                glycin::MemoryFormat::R8g8b8a8,
+                &reference,
+            ),
+        ] {
+            if skip_file_ext(mime_type.extension().unwrap()) {
+                continue;
+            }
+
+            let mut creator = glycin::Creator::new(mime_type.clone()).await.unwrap();
+            creator
+                .add_frame(width, height, memory_format, data.clone())
+                .unwrap();
+            let encoded_image = creator.create().await.unwrap();
+
+            let loader = glycin::Loader::new_vec(encoded_image.data_full());
+            let (buffer, buffer_width, buffer_height) = loader.load_rgba8().await.unwrap();
+
+            assert_eq!((buffer_width, buffer_height), (width, height));
+            assert!(
+                buffer == reference,
+                "{} from {memory_format:?} differs",
+                mime_type.as_str()
+            );
+        }
+    });
+}
+
+#[test]
+fn processor_loader_process_priority() {
+    init();
+
+    assert!(glycin::ProcessPriority::new(-1).is_err());
+    assert!(glycin::ProcessPriority::new(20).is_err());
+    assert_eq!(glycin::ProcessPriority::new(10).unwrap().nice(), 10);
+
+    block_on(async {
+        let mut loader =
+            glycin::Loader::new(gio::File::for_path("test-images/images/color/color.png"));
+        loader.process_priority(glycin::ProcessPriority::background());
+        let mut image = loader.load().await.unwrap();
+        image.next_frame().await.unwrap();
+
+        // The loader process is still running while the image exists
+        #[cfg(all(feature = "external-loaders", not(feature = "builtin-loaders")))]
+        {
+            const IOPRIO_CLASS_IDLE: i32 = 3;
+            assert!(
+                child_priorities()
+                    .contains(&(glycin::ProcessPriority::MAX_NICE, IOPRIO_CLASS_IDLE)),
+                "No child with background priority: {:?}",
+                child_priorities()
+            );
+        }
+    });
+}
+
+/// Nice value and IO scheduling class of the children of the test process
+#[cfg(all(feature = "external-loaders", not(feature = "builtin-loaders")))]
+fn child_priorities() -> Vec<(i32, i32)> {
+    const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+    const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+
+    let pid = std::process::id().to_string();
+
+    std::fs::read_dir("/proc")
+        .unwrap()
+        .flatten()
+        .filter_map(|entry| {
+            let stat = std::fs::read_to_string(entry.path().join("stat")).ok()?;
+            // Fields after the command name, starting with state and parent PID
+            let (_, fields) = stat.rsplit_once(')')?;
+            let fields = fields.split_whitespace().collect::<Vec<_>>();
+            if fields.get(1) != Some(&pid.as_str()) {
+                return None;
+            }
+
+            // Field 19 in proc_pid_stat(5)
+            let nice = fields.get(16)?.parse().ok()?;
+
+            let child_pid: libc::c_int = entry.file_name().to_str()?.parse().ok()?;
+            let ioprio =
+                unsafe { libc::syscall(libc::SYS_ioprio_get, IOPRIO_WHO_PROCESS, child_pid) };
+
+            Some((nice, ioprio as i32 >> IOPRIO_CLASS_SHIFT))
+        })
+        .collect()
+}
+
+#[test]
+fn processor_loader_animation_composition() {
+    init();
+
+    block_on(async {
+        let file = gio::File::for_path("test-images/images/animated-numbers/animated-numbers.gif");
+        let mut image = glycin::Loader::new(file).load().await.unwrap();
+
+        // Frames are composited by the loader and cover the whole canvas
+        let frame = image.next_frame().await.unwrap();
+        assert_eq!(frame.offset(), None);
+        assert_eq!(frame.blend_op(), None);
+        assert_eq!(frame.disposal_method(), None);
+        assert_eq!(frame.width(), image.details().width());
+
+        // Frames as stored in the file
+        let file = gio::File::for_path("test-images/images/animated-numbers/animated-numbers.gif");
+        let mut loader = glycin::Loader::new(file);
+        loader.raw_animation_frames(true);
+        let mut image = loader.load().await.unwrap();
+
+        for n_frame in 0..4 {
+            let frame = image.next_frame().await.unwrap();
+            assert_eq!(frame.details().n_frame(), Some(n_frame));
+            assert_eq!(frame.blend_op(), Some(glycin::BlendOp::Over));
+            assert!(frame.disposal_method().is_some());
+
+            let (x, y) = frame.offset().unwrap();
+            assert!(x + frame.width() <= image.details().width());
+            assert!(y + frame.height() <= image.details().height());
+        }
+
+        let file = gio::File::for_path("test-images/images/color/color.png");
+        let mut image = glycin::Loader::new(file).load().await.unwrap();
+        let frame = image.next_frame().await.unwrap();
+        assert_eq!(frame.offset(), None);
+    });
+}
+
+#[test]
+fn processor_loader_new_vec() {
+    init();
+
+    block_on(async {
+        let path = "test-images/images/color/color.png";
+
+        let loader = glycin::Loader::new(gio::File::for_path(path));
+        let (reference, width, height) = loader.load_rgba8().await.unwrap();
+
+        let loader = glycin::Loader::new_vec(std::fs::read(path).unwrap());
+        let (data, vec_width, vec_height) = loader.load_rgba8().await.unwrap();
+
+        assert_eq!((vec_width, vec_height), (width, height));
+        assert!(data == reference);
+    });
+}
+
+#[test]
+#[cfg(feature = "external-loaders")]
+fn processor_loader_sandbox_wrapper() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    init();
+
+    let n_calls = Arc::new(AtomicUsize::new(0));
+
+    // Passthrough wrapper that only sets up the environment
+    let sandbox_wrapper = glycin::SandboxWrapper::new({
+        let n_calls = n_calls.clone();
+        move |args| {
+            n_calls.fetch_add(1, Ordering::Relaxed);
+
+            let mut command = std::process::Command::new("env");
+            command.arg("-i");
+            for (key, value) in args.environment() {
+                let mut var = key.clone();
+                var.push("=");
+                var.push(value);
+                command.arg(var);
+            }
+            command.arg(args.exec());
+            command
+        }
+    });
+
+    block_on(async {
+        let mut loader =
+            glycin::Loader::new(gio::File::for_path("test-images/images/color/color.png"));
+        loader.sandbox_wrapper(sandbox_wrapper.clone());
+        let mut image = loader.load().await.unwrap();
+        image.next_frame().await.unwrap();
+
+        assert_eq!(
+            image.active_sandbox_mechanism(),
+            glycin::SandboxMechanism::Custom
+        );
+        assert_eq!(n_calls.load(Ordering::Relaxed), 1);
+
+        // Warm loaders start their process via the wrapper as well
+        let warm_loader = glycin::WarmLoader::new_with_sandbox_wrapper(
+            glycin::MimeType::JPEG,
+            sandbox_wrapper.clone(),
+        )
+        .await
+        .unwrap();
+        let file = gio::File::for_path("test-images/images/color/color.jpg");
+        let mut image = warm_loader.load(file).await.unwrap();
+        image.next_frame().await.unwrap();
+        assert_eq!(
+            image.active_sandbox_mechanism(),
+            glycin::SandboxMechanism::Custom
+        );
+        assert_eq!(n_calls.load(Ordering::Relaxed), 2);
+
+        // Editors as well
+        let file = gio::File::for_path("test-images/images/color/color.jpg");
+        let mut editor = glycin::Editor::new(file);
+        editor.sandbox_wrapper(sandbox_wrapper.clone());
+        let operations = glycin::Operations::new(vec![glycin::Operation::MirrorHorizontally]);
+        editor
+            .edit()
+            .await
+            .unwrap()
+            .apply_complete(&operations)
+            .await
+            .unwrap();
+        assert_eq!(n_calls.load(Ordering::Relaxed), 3);
+    });
+}
+
+#[test]
+fn processor_loader_load_many_cancel() {
+    use futures_util::StreamExt;
+
+    init();
+
+    let n_loaders = 20;
+    let concurrency = 2;
+
+    let loaders = (0..n_loaders)
+        .map(|_| glycin::Loader::new(gio::File::for_path("test-images/images/color/color.png")))
+        .collect::<Vec<_>>();
+    let cancellable = gio::Cancellable::new();
+
+    block_on(async {
+        let mut stream = glycin::Loader::load_many(loaders, concurrency, cancellable.clone());
+
+        let (_, result) = stream.next().await.unwrap();
+        result.unwrap();
+
+        cancellable.cancel();
+
+        // Queued loaders are dropped and only loads in flight return
+        let results = stream.collect::<Vec<_>>().await;
+        assert!(results.len() <= concurrency, "{} results", results.len());
+        assert!(results.iter().all(|(_, result)| {
+            result
+                .as_ref()
+                .map_or_else(|err| err.is_cancelled(), |_| true)
+        }));
+    });
+}
+
+#[test]
+fn processor_loader_scanlines() {
+    use futures_util::StreamExt;
+
+    init();
+
+    block_on(async {
+        let (width, height) = (37, 53);
+        let texture: Vec<u8> = (0..width * height * 3)
+            .map(|x| (x * 7 % 251) as u8)
+            .collect();
+
+        let mut creator = glycin::Creator::new(glycin::MimeType::PNG).await.unwrap();
+        creator
+            .add_frame(width, height, glycin::MemoryFormat::R8g8b8, texture)
+            .unwrap();
+        let png = creator.create().await.unwrap().data_full();
+
+        // Full decode
+        let mut loader = glycin::Loader::new_vec(png.clone());
+        loader.memory_format(glycin::MemoryFormat::R8g8b8);
+        let frame = loader.load().await.unwrap().next_frame().await.unwrap();
+        let row_len = (width * 3) as usize;
+        let expected: u64 = frame
+            .buf_slice()
+            .chunks(frame.stride() as usize)
+            .flat_map(|row| &row[..row_len])
+            .map(|x| u64::from(*x))
+            .sum();
+
+        // Streaming decode
+        let mut loader = glycin::Loader::new_vec(png);
+        loader.memory_format(glycin::MemoryFormat::R8g8b8);
+        let mut image = loader.load().await.unwrap();
+        let mut scanlines = image.scanlines(8);
+
+        let mut sum = 0;
+        let mut next_row = 0;
+        while let Some(rows) = scanlines.next().await {
+            let rows = rows.unwrap();
+            assert_eq!(rows.first_row(), next_row);
+            assert!(rows.n_rows() <= 8);
+            assert_eq!(rows.memory_format(), glycin::MemoryFormat::R8g8b8);
+            sum += rows.rows().flatten().map(|x| u64::from(*x)).sum::<u64>();
+            next_row += rows.n_rows();
+        }
+
+        assert_eq!(next_row, height);
+        assert_eq!(sum, expected);
+
+        // Rows and frames share the data
+        drop(scanlines);
+        let frame = image.next_frame().await.unwrap();
+        assert_eq!((frame.width(), frame.height()), (width, height));
+    });
+}
+
+#[test]
+fn processor_loader_progressive_jpeg() {
+    use futures_util::StreamExt;
+
+    init();
+
+    block_on(async {
+        // DC scan and two AC scans
+        let file = gio::File::for_path("fixtures/gray-progressive.jpg");
+        let mut image = glycin::Loader::new(file).load().await.unwrap();
+        let frames = image
+            .progressive_frames()
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(frames.len(), 3);
+        for frame in &frames {
+            assert_eq!((frame.width(), frame.height()), (16, 16));
+            assert_eq!(frame.details().n_progressive_scans(), Some(3));
+        }
+
+        // Baseline JPEG with the same pixels
+        let file = gio::File::for_path("fixtures/gray.jpg");
+        let mut image = glycin::Loader::new(file).load().await.unwrap();
+        let baseline = image.progressive_frames().collect::<Vec<_>>().await;
+
+        assert_eq!(baseline.len(), 1);
+        let baseline = baseline.into_iter().next().unwrap().unwrap();
+        assert_eq!(baseline.details().n_progressive_scans(), None);
+        assert_eq!(frames.last().unwrap().buf_slice(), baseline.buf_slice());
+    });
+}
+
+#[test]
+fn processor_loader_accepted_formats() {
+    init();
+
+    block_on(async {
+        let mut creator = glycin::Creator::new(glycin::MimeType::PNG).await.unwrap();
+        creator
+            .add_frame(
+                2,
+                1,
+                glycin::MemoryFormat::R8g8b8,
+                vec![255, 0, 0, 0, 0, 255],
+            )
+            .unwrap();
+        let png = creator.create().await.unwrap().data_full();
+
+        // Native format is accepted
+        let mut loader = glycin::Loader::new_vec(png.clone());
+        loader.accepted_formats(vec![
+            glycin::MemoryFormat::B8g8r8a8,
+            glycin::MemoryFormat::R8g8b8,
+        ]);
+        let frame = loader.load().await.unwrap().next_frame().await.unwrap();
+        assert_eq!(frame.memory_format(), glycin::MemoryFormat::R8g8b8);
+        assert!(!frame.applied_transformations().memory_format_changed);
+
+        // Conversion into the preferred format
+        let mut loader = glycin::Loader::new_vec(png);
+        loader.accepted_formats(vec![
+            glycin::MemoryFormat::B8g8r8a8,
+            glycin::MemoryFormat::R8g8b8a8,
+        ]);
+        let frame = loader.load().await.unwrap().next_frame().await.unwrap();
+        assert_eq!(frame.memory_format(), glycin::MemoryFormat::B8g8r8a8);
+        assert!(frame.applied_transformations().memory_format_changed);
+        assert_eq!(&frame.buf_slice()[..8], [0, 0, 255, 255, 255, 0, 0, 255]);
+    });
+}
+
+#[test]
+fn processor_loader_as_memfd() {
+    use std::os::unix::fs::FileExt;
+
+    init();
+
+    block_on(async {
+        let texture: Vec<u8> = (0..4 * 3 * 3).collect();
+
+        let mut creator = glycin::Creator::new(glycin::MimeType::PNG).await.unwrap();
+        creator
+            .add_frame(4, 3, glycin::MemoryFormat::R8g8b8, texture)
+            .unwrap();
+        let png = creator.create().await.unwrap().data_full();
+
+        let loader = glycin::Loader::new_vec(png);
+        let frame = loader.load().await.unwrap().next_frame().await.unwrap();
+
+        let memfd = frame.as_memfd().expect("Frame is not backed by a memfd");
+        let file = std::fs::File::from(memfd.try_clone_to_owned().unwrap());
+
+        let mut data = vec![0; frame.buf_slice().len()];
+        file.read_exact_at(&mut data, 0).unwrap();
+        assert_eq!(data, frame.buf_slice());
+
+        // Sealed against writing
+        assert!(file.write_at(&[0], 0).is_err());
+    });
+}
+
+/// First pixel of a CMYK JPEG fixture after conversion to RGB
+async fn cmyk_first_pixel(name: &str, assume_adobe_cmyk: Option<bool>) -> [u8; 3] {
+    let mut loader = glycin::Loader::new(gio::File::for_path(Path::new("fixtures").join(name)));
+    loader.assume_adobe_cmyk(assume_adobe_cmyk);
+    let frame = loader.load().await.unwrap().next_frame().await.unwrap();
+
+    assert_eq!(frame.memory_format(), glycin::MemoryFormat::R8g8b8);
+    assert!(frame.applied_transformations().memory_format_changed);
+    assert_eq!((frame.width(), frame.height()), (16, 16));
+
+    frame.buf_slice()[..3].try_into().unwrap()
+}
+
+fn assert_pixel_eq(pixel: [u8; 3], expected: [u8; 3]) {
+    for (value, expected) in pixel.into_iter().zip(expected) {
+        assert!(value.abs_diff(expected) <= 2, "{pixel:?} != {expected:?}");
+    }
+}
+
+#[test]
+fn processor_loader_cmyk() {
+    init();
+
+    // Both files store CMYK (0, 102, 204, 51), `cmyk-adobe.jpg` inverted and
+    // with an Adobe marker
+    let rgb = [204, 122, 41];
+    // Result if the values are interpreted with the wrong convention
+    let rgb_inverted = [0, 20, 41];
+
+    block_on(async {
+        assert_pixel_eq(cmyk_first_pixel("cmyk.jpg", None).await, rgb);
+        assert_pixel_eq(cmyk_first_pixel("cmyk-adobe.jpg", None).await, rgb);
+
+        assert_pixel_eq(cmyk_first_pixel("cmyk.jpg", Some(false)).await, rgb);
+        assert_pixel_eq(cmyk_first_pixel("cmyk-adobe.jpg", Some(true)).await, rgb);
+
+        assert_pixel_eq(cmyk_first_pixel("cmyk.jpg", Some(true)).await, rgb_inverted);
+        assert_pixel_eq(
+            cmyk_first_pixel("cmyk-adobe.jpg", Some(false)).await,
+            rgb_inverted,
+        );
+    });
+}
+
+#[test]
+fn processor_loader_cmyk_raw() {
+    init();
+
+    block_on(async {
+        let file = gio::File::for_path("fixtures/cmyk.jpg");
+        let mut loader = glycin::Loader::new(file.clone());
+        loader.raw(true);
+        let raw = loader.load().await.unwrap().next_frame().await.unwrap();
+
+        // Raw frames are converted to RGB as well
+        assert_eq!(raw.memory_format(), glycin::MemoryFormat::R8g8b8);
+        assert_pixel_eq(raw.buf_slice()[..3].try_into().unwrap(), [204, 122, 41]);
+
+        // The file has no ICC profile, so the textures are the same
+        let frame = glycin::Loader::new(file)
+            .load()
+            .await
+            .unwrap()
+            .next_frame()
+            .await
+            .unwrap();
+        assert!(
+            texture_to_bytes(&raw.texture().unwrap())
+                == texture_to_bytes(&frame.texture().unwrap())
+        );
+    });
+}
+
+#[test]
+fn processor_loader_memory_format_16_bit() {
+    init();
+
+    block_on(async {
+        let texture: Vec<u8> = [0xffff_u16, 0x8080, 0]
+            .into_iter()
+            .flat_map(u16::to_ne_bytes)
+            .collect();
+
+        let mut creator = glycin::Creator::new(glycin::MimeType::PNG).await.unwrap();
+        creator
+            .add_frame(1, 1, glycin::MemoryFormat::R16g16b16, texture)
+            .unwrap();
+        let png = creator.create().await.unwrap().data_full();
+
+        let loader = glycin::Loader::new_vec(png.clone());
+        let frame = loader.load().await.unwrap().next_frame().await.unwrap();
+        assert_eq!(frame.memory_format(), glycin::MemoryFormat::R16g16b16);
+
+        let mut loader = glycin::Loader::new_vec(png);
+        loader.memory_format(glycin::MemoryFormat::R8g8b8a8);
+        let frame = loader.load().await.unwrap().next_frame().await.unwrap();
+        assert_eq!(frame.memory_format(), glycin::MemoryFormat::R8g8b8a8);
+        assert_eq!(&frame.buf_slice()[..4], [255, 128, 0, 255]);
+    });
+}
+
+#[test]
+fn processor_loader_reduced_precision_ignored() {
+    init();
+
+    block_on(async {
+        let texture: Vec<u8> = [0xffff_u16, 0x8080, 0]
+            .into_iter()
+            .flat_map(u16::to_ne_bytes)
+            .collect();
+
+        let mut creator = glycin::Creator::new(glycin::MimeType::PNG).await.unwrap();
+        creator
+            .add_frame(1, 1, glycin::MemoryFormat::R16g16b16, texture.clone())
+            .unwrap();
+        let png = creator.create().await.unwrap().data_full();
+
+        let loader = glycin::Loader::new_vec(png);
+        let mut image = loader.load().await.unwrap();
+        let frame = image
+            .specific_frame(glycin::FrameRequest::new().reduced_precision(true))
+            .await
+            .unwrap();
+
+        // The loader can't decode faster with fewer bits and ignores the hint
+        assert!(!frame.details().reduced_precision());
+        assert_eq!(frame.memory_format(), glycin::MemoryFormat::R16g16b16);
+        assert_eq!(frame.buf_slice(), texture);
+    });
+}
+
+#[test]
+fn processor_loader_new_bytes() {
+    init();
+
+    // Red 3x2 PNG
+    const PNG: &[u8] = &[
+        0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44,
+        0x52, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x02, 0x08, 0x02, 0x00, 0x00, 0x00, 0x12,
+        0x16, 0xf1, 0x4d, 0x00, 0x00, 0x00, 0x10, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9c, 0x63, 0xf8,
+        0xcf, 0xc0, 0x00, 0x41, 0x0c, 0x70, 0x16, 0x00, 0x41, 0xd2, 0x05, 0xfb, 0x87, 0xf0, 0xb9,
+        0x48, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e, 0x44, 0xae, 0x42, 0x60, 0x82,
+    ];
+
+    block_on(async {
+        let loader = glycin::Loader::new_bytes(gio::glib::Bytes::from_static(PNG));
+        let image = loader.load().await.unwrap();
+        assert_eq!(image.details().width(), 3);
+        assert_eq!(image.details().height(), 2);
+
+        let loader = glycin::Loader::new_vec(PNG.to_vec());
+        let mut image = loader.load().await.unwrap();
+        assert_eq!(image.details().width(), 3);
+
+        let frame = image.next_frame().await.unwrap();
+        assert_eq!(&frame.buf_slice()[..3], [255, 0, 0]);
+    });
+}
+
+#[test]
+fn processor_loader_format_support() {
+    init();
+
+    block_on(async {
+        let formats = glycin::format_support().await;
+        let format = |mime_type: glycin::MimeType| {
+            formats
+                .iter()
+                .find(|x| *x.mime_type() == mime_type)
+                .unwrap()
+        };
+
+        let png = format(glycin::MimeType::PNG);
+        assert!(png.has_loader());
+        assert!(png.has_editor());
+        assert!(png.has_creator());
+        assert!(!png.animation());
+        assert!(png.metadata());
+
+        assert!(format(glycin::MimeType::GIF).animation());
+        assert!(!format(glycin::MimeType::BMP).animation());
+
+        if !skip_file_ext("jxl") {
+            let jxl = format(glycin::MimeType::JXL);
+            assert!(jxl.has_loader());
+            assert!(jxl.animation());
+            assert!(jxl.metadata());
+        }
+    });
+}
+
+#[test]
+fn processor_loader_gdepth() {
+    init();
+
+    block_on(async {
+        let depth_png = glycin::Creator::from_pixels(
+            glycin::MimeType::PNG,
+            2,
+            1,
+            glycin::MemoryFormat::R8g8b8,
+            vec![0, 0, 0, 255, 255, 255],
+        )
+        .await
+        .unwrap()
+        .create()
+        .await
+        .unwrap()
+        .data_full();
+
+        let xmp = format!(
+            r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+            <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+            <rdf:Description
+                xmlns:GDepth="http://ns.google.com/photos/1.0/depthmap/"
+                GDepth:Format="RangeInverse"
+                GDepth:Near="0.5"
+                GDepth:Far="12.25"
+                GDepth:Mime="image/png"
+                GDepth:Data="{}"/>
+            </rdf:RDF>
+            </x:xmpmeta>"#,
+            gio::glib::base64_encode(&depth_png)
+        );
+
+        // JPEG with the XMP in an APP1 segment
+        let jpeg = glycin::Creator::from_pixels(
+            glycin::MimeType::JPEG,
+            1,
+            1,
+            glycin::MemoryFormat::G8,
+            vec![0],
+        )
+        .await
+        .unwrap()
+        .create()
+        .await
+        .unwrap()
+        .data_full();
+        let mut segment = b"http://ns.adobe.com/xap/1.0/\0".to_vec();
+        segment.extend_from_slice(xmp.as_bytes());
+        let mut data = jpeg[..2].to_vec();
+        data.extend_from_slice(&[0xFF, 0xE1]);
+        data.extend_from_slice(&(segment.len() as u16 + 2).to_be_bytes());
+        data.extend_from_slice(&segment);
+        data.extend_from_slice(&jpeg[2..]);
+
+        let depth_map = glycin::Loader::new_vec(data.clone())
+            .depth_map()
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(depth_map.source, glycin::DepthMapSource::Xmp);
+        assert_eq!(
+            depth_map.encoding,
+            glycin::DepthEncoding::RangeInverse {
+                near: 0.5,
+                far: 12.25
+            }
+        );
+        assert_eq!(depth_map.frame.memory_format(), glycin::MemoryFormat::G8);
+        assert_eq!(depth_map.frame.buf_slice(), [0, 255]);
+
+        // The depth map is decoded with the limits of the loader
+        let mut loader = glycin::Loader::new_vec(data);
+        loader.limits(glycin::Limits::default().max_dimensions((1, 1)));
+        assert!(loader.depth_map().await.is_err());
+
+        // Images without depth data
+        let loader = glycin::Loader::new_vec(jpeg);
+        assert!(loader.depth_map().await.unwrap().is_none());
+    });
+}
+
+#[test]
+fn processor_loader_icc_profile() {
+    init();
+
+    block_on(async {
+        let mut creator = glycin::Creator::new(glycin::MimeType::JPEG).await.unwrap();
+        creator
+            .add_frame(1, 1, glycin::MemoryFormat::R8g8b8, vec![255, 0, 0])
+            .unwrap()
+            .set_color_icc_profile(Some(vec![1, 2, 3]))
+            .unwrap();
+        let jpeg = creator.create().await.unwrap().data_full();
+
+        let mut image = glycin::Loader::new_vec(jpeg).load().await.unwrap();
+        assert_eq!(image.icc_profile().await.unwrap(), Some(vec![1, 2, 3]));
+
+        // The frame loaded for the profile is returned next
+        let frame = image.next_frame().await.unwrap();
+        assert_eq!(frame.frame_index(), 0);
+        assert_eq!((frame.width(), frame.height()), (1, 1));
+
+        let png = glycin::Creator::from_pixels(
+            glycin::MimeType::PNG,
+            1,
+            1,
+            glycin::MemoryFormat::R8g8b8,
+            vec![255, 0, 0],
+        )
+        .await
+        .unwrap()
+        .create()
+        .await
+        .unwrap()
+        .data_full();
+
+        let mut image = glycin::Loader::new_vec(png).load().await.unwrap();
+        assert_eq!(image.icc_profile().await.unwrap(), None);
+    });
+}
+
+#[test]
+fn processor_loader_jxl_detection() {
+    init();
+
+    if skip_file_ext("jxl") {
+        return;
+    }
+
+    block_on(async {
+        let creator = glycin::Creator::from_pixels(
+            glycin::MimeType::JXL,
+            2,
+            1,
+            glycin::MemoryFormat::R8g8b8,
+            vec![255, 0, 0, 0, 0, 255],
+        )
+        .await
+        .unwrap();
+        let codestream = creator.create().await.unwrap().data_full();
+        assert!(codestream.starts_with(b"\xFF\x0A"));
+
+        // Signature and file type box followed by the codestream box
+        let mut container = b"\x00\x00\x00\x0CJXL\x20\x0D\x0A\x87\x0A".to_vec();
+        container.extend_from_slice(b"\x00\x00\x00\x14ftypjxl\x20\x00\x00\x00\x00jxl\x20");
+        container.extend_from_slice(&(codestream.len() as u32 + 8).to_be_bytes());
+        container.extend_from_slice(b"jxlc");
+        container.extend_from_slice(&codestream);
+
+        let mut textures = Vec::new();
+        for data in [codestream, container] {
+            // Without a file name, only the signature identifies the format
+            let mut loader = glycin::Loader::new_vec(data);
+            loader.memory_format(glycin::MemoryFormat::R8g8b8);
+            let mut image = loader.load().await.unwrap();
+            assert_eq!(image.mime_type(), glycin::MimeType::JXL);
+
+            let frame = image.next_frame().await.unwrap();
+            assert_eq!((frame.width(), frame.height()), (2, 1));
+            // Lossy encoding only approximately keeps the red pixel
+            let [r, g, b] = [0, 1, 2].map(|i| frame.buf_slice()[i]);
+            assert!(r > 200 && g < 50 && b < 50, "{r} {g} {b}");
+            textures.push(frame.buf_slice().to_vec());
+        }
+
+        // Both variants contain the same codestream
+        assert_eq!(textures[0], textures[1]);
+    });
+}
+
+#[cfg(not(feature = "tokio"))]
+#[test]
+fn processor_loader_load_blocking() {
+    init();
+
+    let file = gio::File::for_path("test-images/images/color/color.png");
+    let image = glycin::Loader::new(file.clone()).load_blocking().unwrap();
+    assert!(image.details().width() > 0);
+
+    // Doesn't deadlock while the thread owns the default main context
+    let main_context = gio::glib::MainContext::default();
+    let _guard = main_context.acquire().unwrap();
+    let image = glycin::Loader::new(file.clone()).load_blocking().unwrap();
+    assert!(image.details().height() > 0);
+
+    // An explicitly selected context is kept
+    let specific_context = gio::glib::MainContext::new();
+    let main_loop = gio::glib::MainLoop::new(Some(&specific_context), false);
+    let thread = std::thread::spawn({
+        let specific_context = specific_context.clone();
+        let main_loop = main_loop.clone();
+        move || {
+            let _guard = specific_context.acquire().unwrap();
+            main_loop.run();
+        }
+    });
+
+    let mut loader = glycin::Loader::new(file);
+    loader.main_context_selector(glycin::MainContextSelector::Specific(
+        specific_context.clone(),
+    ));
+    let image = loader.load_blocking().unwrap();
+    assert!(image.details().width() > 0);
+
+    main_loop.quit();
+    thread.join().unwrap();
+}
@@ -91,7 +91,7 @@ async fn get_texture(path: impl AsRef<Path>) -> gdk::Texture {
     loader.use_expose_base_dir(true);
     let mut image = loader.load().await.unwrap();
     let frame = image.next_frame().await.unwrap();
-    frame.texture()
+    frame.texture().unwrap()
 }
 
 async fn get_info(path: impl AsRef<Path>) -> glycin::ImageDetails {